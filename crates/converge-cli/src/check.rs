@@ -98,6 +98,96 @@ fn serving_check(
     }
 }
 
+/// Can the next scan trust the stat index (doc 15 §5c)? A bad one is
+/// not dangerous by default — a scan that cannot read it reads every
+/// file instead — but one that reads and is wrong would snap stale
+/// content, which only `deep` can see: it re-hashes every file the
+/// index would let a scan skip.
+fn stat_index_check(
+    ws: &converge_client::workspace::Workspace,
+    deep: bool,
+    rebuild: bool,
+) -> Check {
+    const FIX: &str = "converge doctor --rebuild-index";
+    if rebuild && let Err(err) = ws.rebuild_stat_index() {
+        return Check::bad("stat index", format!("rebuild failed: {err:#}"), FIX);
+    }
+    match ws.check_stat_index(deep) {
+        Ok(report) if !report.present => Check::ok("stat index", "not built yet"),
+        Ok(report) => match report.problem {
+            Some(problem) => Check::bad("stat index", problem, FIX),
+            None if deep => Check::ok(
+                "stat index",
+                format!(
+                    "{} files, {} checked against disk",
+                    report.entries, report.verified
+                ),
+            ),
+            None => Check::ok("stat index", format!("{} files", report.entries)),
+        },
+        Err(err) => Check::bad("stat index", format!("{err:#}"), FIX),
+    }
+}
+
+/// Does every link between this workspace and the worktrees sharing its
+/// objects hold (doc 15 §5f)? A broken one is not cosmetic: gc marks
+/// from every listed worktree, so a listed one that is gone stops gc,
+/// and a linked one its primary does not list has its objects swept.
+/// `None` when nothing is linked.
+fn worktrees_check(ws: &converge_client::workspace::Workspace) -> Option<Check> {
+    match ws.worktree_link_problem() {
+        Ok(Some(problem)) => {
+            return Some(Check::bad("worktrees", problem, "converge worktree repair"));
+        }
+        Ok(None) => {}
+        Err(err) => {
+            return Some(Check::bad(
+                "worktrees",
+                format!("{err:#}"),
+                "converge worktree repair",
+            ));
+        }
+    }
+    let worktrees = match ws.worktrees() {
+        Ok(worktrees) => worktrees,
+        Err(err) => {
+            return Some(Check::bad(
+                "worktrees",
+                format!("{err:#}"),
+                "converge worktree list",
+            ));
+        }
+    };
+    let broken: Vec<String> = worktrees
+        .iter()
+        .filter_map(|w| {
+            w.problem
+                .as_ref()
+                .map(|problem| format!("{} at {} {problem}", w.record.name, w.record.path))
+        })
+        .collect();
+    if !broken.is_empty() {
+        return Some(Check::bad(
+            "worktrees",
+            broken.join("; "),
+            "converge worktree prune   (or `converge worktree repair` in one that moved)",
+        ));
+    }
+    if worktrees.is_empty() && ws.store.primary_dir().is_none() {
+        return None;
+    }
+    Some(Check::ok(
+        "worktrees",
+        match ws.store.primary_dir() {
+            Some(primary) => format!(
+                "linked to {}",
+                primary.parent().unwrap_or(primary).display()
+            ),
+            None => format!("{} linked", worktrees.len()),
+        },
+    ))
+}
+
 /// One check `doctor` ran, and what to do when it failed.
 #[derive(Serialize)]
 struct Check {
@@ -149,11 +239,14 @@ const CLOCK_SKEW_WARN_SECONDS: i64 = 60;
 /// to solve.
 ///
 /// It reports and recommends. It never changes state — a diagnostic you
-/// cannot safely run when you are unsure is not one.
+/// cannot safely run when you are unsure is not one — except that
+/// `rebuild_index` rebuilds the stat index before the checks run, when
+/// asked in so many words.
 pub(crate) fn run_doctor(
     mode: OutputMode,
     session: &Session,
     deep: bool,
+    rebuild_index: bool,
 ) -> Result<serde_json::Value> {
     let mut checks: Vec<Check> = Vec::new();
 
@@ -170,6 +263,8 @@ pub(crate) fn run_doctor(
             )
             .unwrap_or(0);
             checks.push(Check::ok("store format", format!("version {version}")));
+            checks.push(stat_index_check(ws, deep, rebuild_index));
+            checks.extend(worktrees_check(ws));
         }
         // A format mismatch surfaces here, and it must **not** be
         // answered with `converge init`: `init --force` on a store this
@@ -178,8 +273,18 @@ pub(crate) fn run_doctor(
         Err(err) if format!("{err:#}").contains("format") => checks.push(Check::bad(
             "workspace",
             format!("{err:#}"),
-            "use a Convergence build that reads this format — do NOT run `init --force` here",
+            "older: converge migrate   newer: upgrade Convergence — do NOT run `init --force` here",
         )),
+        // A linked worktree whose primary is gone has lost every object
+        // it names; there is nothing here `init` would bring back.
+        Err(err) if format!("{err:#}").contains("this worktree is linked") => {
+            checks.push(Check::bad(
+                "workspace",
+                format!("{err:#}"),
+                "restore the primary workspace from a backup, or check the work out again \
+                 somewhere else",
+            ))
+        }
         Err(err) => checks.push(Check::bad(
             "workspace",
             format!("{err:#}"),
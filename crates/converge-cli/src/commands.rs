@@ -18,6 +18,13 @@ pub(crate) enum Command {
         /// Optional snap message.
         #[arg(short, long)]
         message: Option<String>,
+        /// Capture only the changes under this path or glob, relative to
+        /// the current directory; the rest stay uncaptured. Repeatable.
+        #[arg(long)]
+        path: Vec<String>,
+        /// Choose which changes to capture, one at a time.
+        #[arg(short, long, conflicts_with = "path")]
+        interactive: bool,
     },
     /// List snaps: your current line of work first, then the rest.
     History,
@@ -35,6 +42,11 @@ pub(crate) enum Command {
         /// nothing.
         #[arg(long)]
         preflight: bool,
+        /// Write text superpositions as conflict-marker files instead of
+        /// refusing; the next snap turns a de-markered file into a
+        /// resolution.
+        #[arg(long)]
+        markers: bool,
     },
     /// Diff two snaps.
     Diff { from: String, to: String },
@@ -60,6 +72,10 @@ pub(crate) enum Command {
         scope: String,
         #[arg(long)]
         gate: String,
+        /// Name shown on the snaps you capture here. Defaults to the name
+        /// given at the last login, then to your subject.
+        #[arg(long)]
+        name: Option<String>,
     },
     /// Publish a snap (default: latest) to the configured remote gate.
     Publish {
@@ -75,6 +91,9 @@ pub(crate) enum Command {
         /// Message recorded on the publication.
         #[arg(short, long, alias = "notes")]
         message: Option<String>,
+        /// Sign the snap and the publish with your newest personal key.
+        #[arg(long)]
+        sign: bool,
     },
     /// Fetch a candidate's tree into the local store.
     Fetch {
@@ -101,6 +120,14 @@ pub(crate) enum Command {
         /// Report what checking out would cost, and change nothing.
         #[arg(long)]
         preflight: bool,
+        /// With --checkout: write text superpositions as conflict-marker
+        /// files, with the candidate's base as the common ancestor.
+        #[arg(long, requires = "checkout")]
+        markers: bool,
+        /// Fetch manifests and recipes now and each blob when something
+        /// first reads it: the tree can be browsed before it is downloaded.
+        #[arg(long)]
+        lazy: bool,
     },
     /// Show a candidate's record.
     #[command(alias = "bundle")]
@@ -128,6 +155,48 @@ pub(crate) enum Command {
         #[arg(long)]
         force: bool,
     },
+    /// Set uncaptured edits aside and put the working tree back to head.
+    Shelve {
+        /// Shelve only edits under this path or glob, relative to the
+        /// current directory (repeatable; default: all).
+        #[arg(long)]
+        path: Vec<String>,
+        /// Name the shelf (default: the first free `shelf-N`).
+        #[arg(long)]
+        name: Option<String>,
+        /// List shelves instead of shelving.
+        #[arg(long, conflicts_with_all = ["path", "name"])]
+        list: bool,
+    },
+    /// Re-apply a shelf to the working tree and drop it.
+    Unshelve {
+        /// Shelf name (default: the newest).
+        name: Option<String>,
+    },
+    /// Replay the change one snap made onto head, as a new snap.
+    Pick {
+        snap_id: String,
+        /// How a path both sides changed is merged (doc 17 §4).
+        #[arg(long, default_value = "whole-file")]
+        strategy: String,
+        /// A per-path exception to --strategy, as PATTERN=STRATEGY;
+        /// repeatable, first match wins.
+        #[arg(long = "rule", value_name = "PATTERN=STRATEGY")]
+        rules: Vec<String>,
+    },
+    /// Replay head's own snaps onto another snap's lineage.
+    Rebase {
+        /// The snap to build on.
+        #[arg(long)]
+        onto: String,
+        /// How a path both sides changed is merged (doc 17 §4).
+        #[arg(long, default_value = "whole-file")]
+        strategy: String,
+        /// A per-path exception to --strategy, as PATTERN=STRATEGY;
+        /// repeatable, first match wins.
+        #[arg(long = "rule", value_name = "PATTERN=STRATEGY")]
+        rules: Vec<String>,
+    },
     /// Show workspace status: changes, head, snaps, remote.
     Status,
     /// Set or replace a snap's message (identity is unaffected).
@@ -206,12 +275,21 @@ pub(crate) enum Command {
     Gc {
         #[arg(long)]
         execute: bool,
+        /// Collect this workspace's store instead: sweep objects no snap,
+        /// resolution or marker file reaches, and pack small ones.
+        #[arg(long)]
+        local: bool,
     },
     /// Show or set the repo's server-side retention policy.
     Retention {
         #[command(subcommand)]
         command: RetentionCommand,
     },
+    /// Show or set whether the repo refuses unsigned publishes.
+    Signing {
+        #[command(subcommand)]
+        command: SigningCommand,
+    },
     /// Share unpublished lineage through lanes.
     Sync {
         #[command(subcommand)]
@@ -275,6 +353,22 @@ pub(crate) enum Command {
         /// object store is gone (g02.022 batch 22.3).
         #[arg(long)]
         deep: bool,
+        /// Throw the stat index away and build it again from the working
+        /// tree first: the fix when the index check fails. The one thing
+        /// `doctor` changes, and only when asked.
+        #[arg(long)]
+        rebuild_index: bool,
+    },
+    /// Bring this workspace's store up to the format this build reads.
+    Migrate {
+        /// List the steps that would run and change nothing.
+        #[arg(long)]
+        dry_run: bool,
+        /// Skip the copy taken into `.converge/backups` before the
+        /// first step. An interrupted migration can still be resumed;
+        /// it just cannot be undone.
+        #[arg(long)]
+        no_backup: bool,
     },
     /// Show or set the workflow profile (shapes guidance, not behavior).
     Profile {
@@ -284,15 +378,83 @@ pub(crate) enum Command {
     },
     /// Watch the workspace and capture automatic snaps on quiet periods.
     Watch {
-        /// Poll interval in milliseconds.
+        /// Quiet period in milliseconds: how long the tree must go
+        /// unchanged before it is captured. With `--poll`, the interval
+        /// between scans.
         #[arg(long, default_value_t = 2000)]
         interval_ms: u64,
         /// Run a single check-capture-thin cycle and exit (for tests).
         #[arg(long)]
         once: bool,
+        /// Rescan the whole tree every interval instead of following
+        /// filesystem events.
+        #[arg(long)]
+        poll: bool,
+    },
+    /// Say whether paths are captured, and which `.convergeignore` rule
+    /// decides it.
+    CheckIgnore {
+        /// Paths, relative to the current directory.
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+    },
+    /// Choose which parts of the tree are on disk; the rest stays in
+    /// every snap as head has it.
+    Sparse {
+        #[command(subcommand)]
+        command: SparseCommand,
+    },
+    /// More checkouts of this repo sharing one object store.
+    Worktree {
+        #[command(subcommand)]
+        command: WorktreeCommand,
     },
 }
 
+#[derive(Subcommand)]
+pub(crate) enum SparseCommand {
+    /// Show the sparse patterns; none means the whole tree is on disk.
+    List,
+    /// Put more of the tree on disk.
+    Add {
+        /// Tree paths or globs from the workspace root, e.g.
+        /// `assets/characters`.
+        #[arg(required = true)]
+        patterns: Vec<String>,
+        /// Proceed even if the working tree has uncaptured changes.
+        #[arg(long)]
+        force: bool,
+    },
+    /// Take patterns away; removing the last one restores the whole tree.
+    Remove {
+        #[arg(required = true)]
+        patterns: Vec<String>,
+        /// Proceed even if the working tree has uncaptured changes.
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub(crate) enum WorktreeCommand {
+    /// Check a snap out into a new directory linked to this workspace's
+    /// objects.
+    Add {
+        /// Directory for the worktree; must be empty or not exist yet.
+        dir: PathBuf,
+        /// Snap to check out (default: this workspace's head).
+        #[arg(long)]
+        snap: Option<String>,
+    },
+    /// List the linked worktrees and whether each link holds.
+    List,
+    /// Forget worktrees whose directory is gone or no longer links back.
+    Prune,
+    /// From a linked worktree: list it with its primary again, where it
+    /// now is.
+    Repair,
+}
+
 #[derive(Subcommand)]
 pub(crate) enum GitCommand {
     /// Mirror the workspace head's lineage to a git branch.
@@ -328,6 +490,16 @@ pub(crate) enum RetentionCommand {
     },
 }
 
+#[derive(Subcommand)]
+pub(crate) enum SigningCommand {
+    Show,
+    Set {
+        /// `true` refuses every publish without a valid signature.
+        #[arg(long, action = clap::ArgAction::Set)]
+        require_signed: bool,
+    },
+}
+
 #[derive(Subcommand)]
 pub(crate) enum SyncCommand {
     /// Push the current head's lineage to a lane.
@@ -410,6 +582,14 @@ pub(crate) enum GateCommand {
         approvals: u32,
         #[arg(long, default_value = "whole-file")]
         strategy: String,
+        /// A per-path exception to --strategy, as PATTERN=STRATEGY;
+        /// repeatable, first match wins (e.g. `--rule Cargo.lock=whole-file`).
+        #[arg(long = "rule", value_name = "PATTERN=STRATEGY")]
+        rules: Vec<String>,
+        /// A lane for the lane-priority strategy; repeatable, highest
+        /// priority first.
+        #[arg(long = "lane-priority", value_name = "LANE")]
+        lane_priority: Vec<String>,
         /// Candidates from this gate may be released to a channel.
         #[arg(long)]
         releasable: bool,
@@ -428,6 +608,28 @@ pub(crate) enum GateCommand {
         approvals: Option<u32>,
         #[arg(long)]
         strategy: Option<String>,
+        /// Replaces the whole per-path rule list; repeatable, as
+        /// PATTERN=STRATEGY.
+        #[arg(
+            long = "rule",
+            value_name = "PATTERN=STRATEGY",
+            conflicts_with = "clear_rules"
+        )]
+        rules: Option<Vec<String>>,
+        /// Drop every per-path rule, leaving --strategy for all paths.
+        #[arg(long)]
+        clear_rules: bool,
+        /// Replaces the whole lane ranking; repeatable, highest priority
+        /// first.
+        #[arg(
+            long = "lane-priority",
+            value_name = "LANE",
+            conflicts_with = "clear_lane_priority"
+        )]
+        lane_priority: Option<Vec<String>>,
+        /// Drop the lane ranking.
+        #[arg(long)]
+        clear_lane_priority: bool,
         #[arg(long)]
         releasable: Option<bool>,
         #[arg(long)]
@@ -619,17 +821,41 @@ pub(crate) enum ResolveCommand {
         #[arg(long)]
         preview: bool,
     },
-    /// Validate a decisions file against a snap or candidate.
+    /// Validate a decisions file against a snap or candidate. Paths it
+    /// leaves open are filled from decisions recorded for the same
+    /// variants, when there are any.
     Validate {
         target: String,
-        /// JSON file: { "<path>": <decision>, ... }
+        /// JSON file: { "<path>": <decision>, ... }. Optional when every
+        /// path has a recorded decision.
+        decisions: Option<PathBuf>,
+        /// Decide only from the file; ignore recorded decisions.
+        #[arg(long)]
+        no_reuse: bool,
+    },
+    /// Resolve one path to a file you wrote instead of any variant: store
+    /// the file and record the decision, naming the variants it replaces,
+    /// in a decisions file (created if missing).
+    Capture {
+        target: String,
+        /// The superposed path, as `resolve list` prints it.
+        path: String,
+        /// The hand-edited file to take the content from.
+        file: PathBuf,
+        /// Decisions file to add the decision to.
+        #[arg(long)]
         decisions: PathBuf,
     },
     /// Apply a decisions file: capture the resolved tree as a snap and
-    /// materialize it into the workspace.
+    /// materialize it into the workspace. Paths the file leaves open
+    /// reuse decisions recorded for the same variants, and every
+    /// decision applied is recorded for next time.
     Apply {
         target: String,
-        decisions: PathBuf,
+        decisions: Option<PathBuf>,
+        /// Decide only from the file; ignore recorded decisions.
+        #[arg(long)]
+        no_reuse: bool,
         /// Message for the resolution snap.
         #[arg(short, long)]
         message: Option<String>,
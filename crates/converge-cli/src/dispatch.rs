@@ -6,9 +6,13 @@ use anyhow::{Context, Result};
 use serde::Serialize;
 
 use converge_client::diff::{DiffLine, diff_trees, tree_from_store};
-use converge_client::model::{ObjectId, ResolutionDecision};
-use converge_client::resolve::{apply_resolution, superposition_variants, validate_resolution};
-use converge_client::workspace::Workspace;
+use converge_client::model::gates::{STRATEGIES, StrategyPolicy};
+use converge_client::model::{ObjectId, Resolution, ResolutionDecision, ResolvedContent};
+use converge_client::resolve::{
+    apply_resolution, record_decisions, reuse_recorded_decisions, superposition_variants,
+    validate_resolution,
+};
+use converge_client::workspace::{Replayed, SuperpositionMode, Workspace, WorktreeStatus};
 
 use crate::check::run_doctor;
 use crate::commands::*;
@@ -36,6 +40,9 @@ struct SnapSummary {
     created_at: String,
     message: Option<String>,
     trigger: String,
+    /// Absent on snaps captured before `converge login` and on imports.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    author: Option<converge_client::model::SnapAuthor>,
     /// Reachable from the current head by walking parents.
     on_current_line: bool,
     files: u64,
@@ -127,6 +134,7 @@ fn snap_summary(s: &converge_client::model::SnapRecord) -> SnapSummary {
         created_at: s.created_at.clone(),
         message: s.message.clone(),
         trigger: s.trigger.clone(),
+        author: s.author.clone(),
         // Callers that care set this; the default suits the summary
         // views that only ever show head's own lineage.
         on_current_line: true,
@@ -138,14 +146,21 @@ fn snap_summary(s: &converge_client::model::SnapRecord) -> SnapSummary {
 pub(crate) fn run(cli: &Cli, mode: OutputMode, session: &Session) -> Result<serde_json::Value> {
     match &cli.command {
         Command::Init { force } => cmd_init(mode, force),
-        Command::Snap { message } => cmd_snap(mode, session, message),
+        Command::Snap {
+            message,
+            path,
+            interactive,
+        } => cmd_snap(mode, session, message, path, *interactive),
         Command::History => cmd_history(mode, session),
         Command::Restore {
             snap_id,
             force,
             snap_first,
             preflight,
-        } => cmd_restore(mode, session, snap_id, force, snap_first, preflight),
+            markers,
+        } => cmd_restore(
+            mode, session, snap_id, force, snap_first, preflight, markers,
+        ),
         Command::Diff { from, to } => cmd_diff(mode, session, from, to),
         Command::Changes => cmd_changes(mode, session),
         Command::Resolve { command } => run_resolve(mode, command, session),
@@ -155,14 +170,16 @@ pub(crate) fn run(cli: &Cli, mode: OutputMode, session: &Session) -> Result<serd
             repo,
             scope,
             gate,
+            name,
             ..
-        } => cmd_login(mode, session, url, token, repo, scope, gate),
+        } => cmd_login(mode, session, url, token, repo, scope, gate, name),
         Command::Publish {
             snap,
             gate,
             lane,
             message,
-        } => cmd_publish(mode, session, snap, gate, lane, message),
+            sign,
+        } => cmd_publish(mode, session, snap, gate, lane, message, sign),
         Command::Release {
             candidate_id,
             version,
@@ -177,8 +194,9 @@ pub(crate) fn run(cli: &Cli, mode: OutputMode, session: &Session) -> Result<serd
             candidate_id,
             release,
         } => cmd_verify(mode, session, candidate_id, release),
-        Command::Gc { execute } => cmd_gc(mode, session, execute),
+        Command::Gc { execute, local } => cmd_gc(mode, session, execute, local),
         Command::Retention { command } => cmd_retention(mode, session, command),
+        Command::Signing { command } => cmd_signing(mode, session, command),
         Command::Fetch {
             candidate_id,
             release,
@@ -187,6 +205,8 @@ pub(crate) fn run(cli: &Cli, mode: OutputMode, session: &Session) -> Result<serd
             force,
             snap_first,
             preflight,
+            markers,
+            lazy,
         } => cmd_fetch(
             mode,
             session,
@@ -197,13 +217,38 @@ pub(crate) fn run(cli: &Cli, mode: OutputMode, session: &Session) -> Result<serd
             force,
             snap_first,
             preflight,
+            markers,
+            lazy,
         ),
-        Command::Watch { interval_ms, once } => cmd_watch(mode, session, interval_ms, once),
+        Command::Watch {
+            interval_ms,
+            once,
+            poll,
+        } => cmd_watch(mode, session, interval_ms, once, poll),
+        Command::CheckIgnore { paths } => cmd_check_ignore(mode, session, paths),
+        Command::Sparse { command } => cmd_sparse(mode, session, command),
+        Command::Worktree { command } => cmd_worktree(mode, session, command),
+        Command::Migrate { dry_run, no_backup } => cmd_migrate(mode, *dry_run, *no_backup),
         Command::Profile { set } => cmd_profile(mode, session, set),
-        Command::Doctor { deep } => run_doctor(mode, session, *deep),
+        Command::Doctor {
+            deep,
+            rebuild_index,
+        } => run_doctor(mode, session, *deep, *rebuild_index),
         Command::Remote { command } => cmd_remote(mode, session, command),
         Command::Show { target, path } => cmd_show(mode, session, target, path),
         Command::Unsnap { keep, force } => cmd_unsnap(mode, session, keep, force),
+        Command::Shelve { path, name, list } => cmd_shelve(mode, session, path, name, *list),
+        Command::Unshelve { name } => cmd_unshelve(mode, session, name),
+        Command::Pick {
+            snap_id,
+            strategy,
+            rules,
+        } => cmd_pick(mode, session, snap_id, strategy, rules),
+        Command::Rebase {
+            onto,
+            strategy,
+            rules,
+        } => cmd_rebase(mode, session, onto, strategy, rules),
         Command::Candidate {
             candidate_id,
             release,
@@ -234,18 +279,112 @@ fn cmd_init(mode: OutputMode, force: &bool) -> Result<serde_json::Value> {
     })
 }
 
+/// Deliberately not through the session: it caches an opened
+/// workspace, and opening is the thing an old store refuses.
+fn cmd_migrate(mode: OutputMode, dry_run: bool, no_backup: bool) -> Result<serde_json::Value> {
+    let cwd = std::env::current_dir().context("read current directory")?;
+    let root = Workspace::find_root(&cwd)?;
+    let report = converge_client::store::LocalStore::migrate(
+        &root,
+        converge_client::model::migrate::Options {
+            dry_run,
+            backup: !no_backup,
+        },
+    )?;
+    emit(mode, report, |report| {
+        if report.steps.is_empty() && !report.resumed {
+            println!("workspace is already format {}", report.to);
+            return;
+        }
+        let verb = if report.dry_run {
+            "would migrate"
+        } else {
+            "migrated"
+        };
+        let resumed = if report.resumed { " (resumed)" } else { "" };
+        println!(
+            "{verb} workspace from format {} to {}{resumed}",
+            report.from, report.to
+        );
+        for step in &report.steps {
+            println!("  {} -> {}: {}", step.from, step.to, step.summary);
+        }
+        match (&report.backup, report.dry_run) {
+            (Some(backup), true) => println!("backup would go to {}", backup.display()),
+            (Some(backup), false) => println!("backup: {}", backup.display()),
+            (None, _) => println!("no backup"),
+        }
+    })
+}
+
 fn cmd_snap(
     mode: OutputMode,
     session: &Session,
     message: &Option<String>,
+    paths: &[String],
+    interactive: bool,
 ) -> Result<serde_json::Value> {
     let ws = session.workspace()?;
-    let snap = ws.create_snap(message.clone())?;
+    let snap = if interactive {
+        let picked = pick_changes(&ws.pending_changes()?)?;
+        ws.create_snap_selected(message.clone(), |line| picked.contains(line.path()))?
+    } else if paths.is_empty() {
+        ws.create_snap(message.clone())?
+    } else {
+        let cwd = std::env::current_dir().context("read current directory")?;
+        let patterns = paths
+            .iter()
+            .map(|path| workspace_path(&ws, &cwd.join(path)))
+            .collect::<Result<Vec<_>>>()?;
+        ws.create_snap_paths(message.clone(), &patterns)?
+    };
     emit(mode, snap_summary(&snap), |s| {
         println!("snap {} ({} files, {} bytes)", s.id, s.files, s.bytes);
     })
 }
 
+/// Ask about each pending change on stderr, reading one answer a line
+/// from stdin: `y` takes it, `n` leaves it, `a` takes it and every one
+/// after, `q` leaves the rest. Running out of input leaves the rest.
+fn pick_changes(changes: &[DiffLine]) -> Result<std::collections::HashSet<String>> {
+    use std::io::{BufRead, Write};
+    if changes.is_empty() {
+        anyhow::bail!("nothing to snap: the working tree matches head");
+    }
+    let mut picked = std::collections::HashSet::new();
+    let mut lines = std::io::stdin().lock().lines();
+    let mut all = false;
+    for line in changes {
+        let status = match line {
+            DiffLine::Added { .. } => 'A',
+            DiffLine::Deleted { .. } => 'D',
+            DiffLine::Modified { .. } => 'M',
+        };
+        if !all {
+            let answer = loop {
+                eprint!("{status} {}  snap this? [y,n,a,q] ", line.path());
+                std::io::stderr().flush().context("write prompt")?;
+                let Some(answer) = lines.next() else {
+                    break "q".to_string();
+                };
+                let answer = answer.context("read answer")?.trim().to_lowercase();
+                if matches!(answer.as_str(), "y" | "n" | "a" | "q") {
+                    break answer;
+                }
+                eprintln!("y: snap it, n: leave it, a: snap it and the rest, q: leave the rest");
+            };
+            match answer.as_str() {
+                "n" => continue,
+                "q" => break,
+                "a" => all = true,
+                _ => {}
+            }
+        }
+        picked.insert(line.path().to_string());
+    }
+    Ok(picked)
+}
+
 fn cmd_history(mode: OutputMode, session: &Session) -> Result<serde_json::Value> {
     let ws = session.workspace()?;
     let snaps = ws.list_snaps()?;
@@ -289,7 +428,12 @@ fn cmd_history(mode: OutputMode, session: &Session) -> Result<serde_json::Value>
             } else {
                 "  [off your current line]"
             };
-            println!("{}  {}  {note}{line}", s.id, s.created_at);
+            let who = s
+                .author
+                .as_ref()
+                .map(|a| format!("{}  ", a.display_name))
+                .unwrap_or_default();
+            println!("{}  {}  {who}{note}{line}", s.id, s.created_at);
         }
     })
 }
@@ -301,6 +445,7 @@ fn cmd_restore(
     force: &bool,
     snap_first: &bool,
     preflight: &bool,
+    markers: &bool,
 ) -> Result<serde_json::Value> {
     let ws = session.workspace()?;
     if *preflight {
@@ -314,23 +459,35 @@ fn cmd_restore(
         *snap_first,
         &format!("converge restore {snap_id}"),
     )?;
-    ws.restore_snap(snap_id, *force)?;
+    let superpositions = if *markers {
+        let snap = ws.store.get_snap(snap_id)?;
+        SuperpositionMode::Markers {
+            base: marker_base(session, &ws, snap.derived_from_candidate.as_deref()),
+        }
+    } else {
+        SuperpositionMode::Refuse
+    };
+    let marked = ws.restore_snap_with(snap_id, *force, &superpositions)?;
     #[derive(Serialize)]
     struct Restored {
         snap: String,
         kept: Option<String>,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        marked: Vec<String>,
     }
     emit(
         mode,
         Restored {
             snap: snap_id.clone(),
             kept,
+            marked,
         },
         |r| {
             if let Some(kept) = &r.kept {
                 println!("kept your work as snap {}", short(kept));
             }
             println!("restored {}", r.snap);
+            print_marked(&r.marked);
         },
     )
 }
@@ -379,6 +536,7 @@ fn cmd_changes(mode: OutputMode, session: &Session) -> Result<serde_json::Value>
     })
 }
 
+#[allow(clippy::too_many_arguments)] // one per login option
 fn cmd_login(
     mode: OutputMode,
     session: &Session,
@@ -387,6 +545,7 @@ fn cmd_login(
     repo: &String,
     scope: &String,
     gate: &String,
+    name: &Option<String>,
 ) -> Result<serde_json::Value> {
     let ws = session.workspace()?;
     let mut cfg = ws.store.read_config()?;
@@ -403,6 +562,33 @@ fn cmd_login(
     };
     ws.store.set_remote_token(&remote, &token)?;
     session.forget_token();
+    // Snaps are authored as whoever the server says this token is
+    // (doc 17 §1) — asked, not typed, so upload's subject check agrees
+    // with it. A server that cannot answer leaves the author as it was:
+    // the remote is still worth configuring.
+    match converge_client::remote::RemoteClient::new(url, &token).whoami() {
+        Ok(who) => {
+            let display_name = name
+                .clone()
+                .or_else(|| {
+                    cfg.author
+                        .as_ref()
+                        .filter(|a| a.subject == who.subject)
+                        .map(|a| a.display_name.clone())
+                })
+                .unwrap_or_else(|| who.subject.clone());
+            cfg.author = Some(converge_client::model::AuthorConfig {
+                subject: who.subject,
+                display_name,
+            });
+        }
+        Err(err) => {
+            if mode == OutputMode::Human {
+                eprintln!("warning: could not learn who this token belongs to: {err:#}");
+                eprintln!("  snaps keep their previous author until the next login");
+            }
+        }
+    }
     cfg.remote = Some(remote);
     ws.store.write_config(&cfg)?;
     emit(mode, format!("{repo}/{scope}/{gate} @ {url}"), |target| {
@@ -417,9 +603,18 @@ fn cmd_publish(
     gate: &Option<String>,
     lane: &Option<String>,
     message: &Option<String>,
+    sign: &bool,
 ) -> Result<serde_json::Value> {
     let ws = session.workspace()?;
     let (client, remote) = remote_client(session, &ws, mode)?;
+    // Opened before anything uploads, so a wrong passphrase costs
+    // nothing but the prompt (doc 19 §12).
+    let client = if *sign {
+        let passphrase = read_passphrase(false)?;
+        client.with_signer(converge_client::identity::Signer::load(None, &passphrase)?)
+    } else {
+        client
+    };
     let snap = match snap {
         Some(id) => ws.store.get_snap(id)?,
         None => latest_snap(&ws)?,
@@ -479,16 +674,27 @@ fn cmd_publish(
     struct PublishSummary {
         candidate: converge_client::model::CandidateRecord,
         uploaded_objects: usize,
+        uploaded_bytes: u64,
+        sent_bytes: u64,
     }
     emit(
         mode,
         PublishSummary {
             candidate,
             uploaded_objects: stats.uploaded,
+            uploaded_bytes: stats.logical_bytes,
+            sent_bytes: stats.sent_bytes,
         },
         |s| {
+            // The wire size only when it differs: an older server takes
+            // everything plain (doc 16 §1f).
+            let sent = if s.sent_bytes < s.uploaded_bytes {
+                format!(", {} of {} bytes sent", s.sent_bytes, s.uploaded_bytes)
+            } else {
+                String::new()
+            };
             println!(
-                "published to {gate}: candidate {} ({}, {} objects uploaded)",
+                "published to {gate}: candidate {} ({}, {} objects uploaded{sent})",
                 s.candidate.candidate_id,
                 describe_status(&s.candidate.status),
                 s.uploaded_objects
@@ -561,6 +767,12 @@ fn cmd_gates(
                 gate.strategy,
                 if gate.may_release { "  releasable" } else { "" }
             );
+            for rule in &gate.strategy_rules {
+                println!("  {}  {}", rule.pattern, rule.strategy);
+            }
+            if !gate.lane_priority.is_empty() {
+                println!("  lanes  {}", gate.lane_priority.join(" > "));
+            }
         }
     })
 }
@@ -651,6 +863,14 @@ fn cmd_verify(
         } else {
             println!("FAILED: {}", r.detail);
         }
+        for check in &r.signatures {
+            println!(
+                "  input {}  by {}  {}",
+                check.publication_id,
+                check.publisher,
+                describe_signature(&check.status)
+            );
+        }
     })?;
     if verified {
         Ok(serde_json::Value::Null)
@@ -661,14 +881,41 @@ fn cmd_verify(
     }
 }
 
-fn cmd_gc(mode: OutputMode, session: &Session, execute: &bool) -> Result<serde_json::Value> {
+/// Loose objects younger than this survive a local gc unreached: a snap
+/// being captured has written its objects but not yet its record.
+const LOCAL_GC_GRACE: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+fn cmd_gc(
+    mode: OutputMode,
+    session: &Session,
+    execute: &bool,
+    local: &bool,
+) -> Result<serde_json::Value> {
     let ws = session.workspace()?;
+    if *local {
+        let report = ws.collect_garbage(!execute, LOCAL_GC_GRACE)?;
+        return emit(mode, report, |r| {
+            println!(
+                "{}: {} reachable, swept {} objects ({} bytes stored, {} bytes of content), \
+                 dropped {} promised blobs; packed {} objects, {} packs and {} loose objects left",
+                if r.dry_run { "dry-run" } else { "executed" },
+                r.reachable_objects,
+                r.swept_objects,
+                r.swept_bytes,
+                r.swept_logical_bytes,
+                r.dropped_promises,
+                r.packed_objects,
+                r.packs,
+                r.loose_objects
+            );
+        });
+    }
     let (client, remote) = remote_client(session, &ws, mode)?;
     let report = client.gc(&remote.repo_id, !execute)?;
     emit(mode, report, |r| {
         println!(
             "{}: dropped {} releases, {} candidates, {} publications; \
-                     {} reachable, swept {} objects ({} bytes)",
+                     {} reachable, swept {} objects ({} bytes stored, {} bytes of content)",
             if r["dry_run"].as_bool().unwrap_or(true) {
                 "dry-run"
             } else {
@@ -679,7 +926,9 @@ fn cmd_gc(mode: OutputMode, session: &Session, execute: &bool) -> Result<serde_j
             r["dropped_publications"],
             r["reachable_objects"],
             r["swept_objects"],
-            r["swept_bytes"]
+            r["swept_bytes"],
+            // Absent from an older server, which stores nothing encoded.
+            r.get("swept_logical_bytes").unwrap_or(&r["swept_bytes"])
         );
     })
 }
@@ -724,7 +973,36 @@ fn cmd_retention(
     }
 }
 
-#[allow(clippy::too_many_arguments)] // seven fetch options are the verb's shape
+fn cmd_signing(
+    mode: OutputMode,
+    session: &Session,
+    command: &SigningCommand,
+) -> Result<serde_json::Value> {
+    let ws = session.workspace()?;
+    let (client, remote) = remote_client(session, &ws, mode)?;
+    match command {
+        SigningCommand::Show => {
+            let policy = client.get_signing_policy(&remote.repo_id)?;
+            emit(mode, policy, |p| {
+                println!("unsigned publishes: {}", describe_signing(p));
+            })
+        }
+        SigningCommand::Set { require_signed } => {
+            let policy = converge_client::model::SigningPolicy {
+                require_signed: *require_signed,
+            };
+            client.set_signing_policy(&remote.repo_id, &policy)?;
+            emit(mode, policy, |p| {
+                println!(
+                    "signing updated: unsigned publishes {}",
+                    describe_signing(p)
+                );
+            })
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)] // nine fetch options are the verb's shape
 fn cmd_fetch(
     mode: OutputMode,
     session: &Session,
@@ -735,6 +1013,8 @@ fn cmd_fetch(
     force: &bool,
     snap_first: &bool,
     preflight: &bool,
+    markers: &bool,
+    lazy: &bool,
 ) -> Result<serde_json::Value> {
     let ws = session.workspace()?;
     let (client, remote) = remote_client(session, &ws, mode)?;
@@ -749,7 +1029,7 @@ fn cmd_fetch(
     }
     // A fetched candidate for the configured target becomes the new
     // publish base (doc 17 §2) — see `fetch_candidate_tree`.
-    let root = fetch_candidate_tree(session, &ws, &candidate_id)?;
+    let root = fetch_candidate_tree(session, &ws, &candidate_id, *lazy)?;
     if *preflight {
         return emit_overwrite_plan(&ws, None, false, mode);
     }
@@ -774,15 +1054,28 @@ fn cmd_fetch(
             *snap_first,
             &format!("converge fetch {candidate_id} --checkout"),
         )?;
-        Some(ws.adopt_tree(
+        let superpositions = if *markers {
+            SuperpositionMode::Markers {
+                base: marker_base(session, &ws, Some(&candidate_id)),
+            }
+        } else {
+            SuperpositionMode::Refuse
+        };
+        Some(ws.adopt_tree_with(
             &root,
             Some(format!("checkout of candidate {}", short(&candidate_id))),
             Some(&candidate_id),
             *force,
+            &superpositions,
         )?)
     } else {
         None
     };
+    let marked = if snap.is_some() {
+        ws.marked_paths()?
+    } else {
+        Vec::new()
+    };
 
     #[derive(Serialize)]
     struct Fetched {
@@ -792,6 +1085,9 @@ fn cmd_fetch(
         /// The snap `--snap-first` captured, if it did.
         kept: Option<String>,
         materialized_to: Option<String>,
+        /// Paths `--markers` wrote as conflict-marker files.
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        marked: Vec<String>,
         next: Option<String>,
     }
     emit(
@@ -802,6 +1098,7 @@ fn cmd_fetch(
             snap: snap.map(|s| s.id),
             kept,
             materialized_to: into.as_ref().map(|d| d.display().to_string()),
+            marked,
             // A bare fetch is invisible without this (audit P1.4).
             next: (!*checkout && into.is_none()).then(|| format!("show {candidate_id}")),
         },
@@ -810,14 +1107,20 @@ fn cmd_fetch(
                 println!(
                     "checked out candidate {} as snap {snap}",
                     short(&f.candidate_id)
-                )
+                );
+                print_marked(&f.marked);
             }
             (None, Some(dir)) => {
                 println!("fetched candidate {} into {dir}", short(&f.candidate_id))
             }
             (None, None) => {
+                let held = if *lazy {
+                    "blobs on first read"
+                } else {
+                    "nothing materialized"
+                };
                 println!(
-                    "fetched candidate {} into the local store (nothing materialized)",
+                    "fetched candidate {} into the local store ({held})",
                     short(&f.candidate_id)
                 );
                 println!(
@@ -834,9 +1137,52 @@ fn cmd_watch(
     session: &Session,
     interval_ms: &u64,
     once: &bool,
+    poll: &bool,
 ) -> Result<serde_json::Value> {
     let ws = session.workspace()?;
     let mut captured: Vec<serde_json::Value> = Vec::new();
+    let mut record = |snap: converge_client::model::SnapRecord| -> Result<()> {
+        let thinned = ws
+            .thin_automatic_snaps(time::OffsetDateTime::now_utc())?
+            .len();
+        // Capture mode drives the TUI, which owns the
+        // terminal: progress chatter there corrupts the
+        // screen and breaks the envelope contract (audit P3).
+        if mode == OutputMode::Human {
+            println!("auto-snap {} ({} thinned)", snap.id, thinned);
+        }
+        captured.push(serde_json::json!({
+            "id": snap.id,
+            "thinned": thinned,
+        }));
+        Ok(())
+    };
+    let interval = std::time::Duration::from_millis(*interval_ms);
+
+    // Event-driven (doc 15 §5a): wait for the tree to go quiet, then
+    // rescan only the directories that changed. Any trouble with the
+    // watches themselves — no inotify, the watch limit — drops to the
+    // polling loop below rather than stopping the capture.
+    if !*once && !*poll {
+        let fallback = match ws.watch_tree() {
+            Ok(mut watcher) => loop {
+                let changes = match watcher.next_quiet(interval) {
+                    Ok(changes) => changes,
+                    Err(err) => break err,
+                };
+                let head = ws.store.get_head()?;
+                let snap = ws.create_snap_changed(&changes, "automatic")?;
+                if head.as_ref() != Some(&snap.id) {
+                    record(snap)?;
+                }
+            },
+            Err(err) => err,
+        };
+        if mode != OutputMode::Capture {
+            eprintln!("warning: {fallback:#}; polling every {interval_ms} ms instead");
+        }
+    }
+
     // Debounce: capture only when the tree is stable across two
     // consecutive ticks and differs from head (doc 17 makes
     // no-change captures free, so the guard is about quiet, not
@@ -850,32 +1196,145 @@ fn cmd_watch(
         };
         let stable = previous_root.as_ref() == Some(&root) || *once;
         if stable && head_root.as_ref() != Some(&root) {
-            let snap = ws.create_snap_with(None, "automatic")?;
-            let thinned = ws
-                .thin_automatic_snaps(time::OffsetDateTime::now_utc())?
-                .len();
-            // Capture mode drives the TUI, which owns the
-            // terminal: progress chatter there corrupts the
-            // screen and breaks the envelope contract (audit P3).
-            if mode == OutputMode::Human {
-                println!("auto-snap {} ({} thinned)", snap.id, thinned);
-            }
-            captured.push(serde_json::json!({
-                "id": snap.id,
-                "thinned": thinned,
-            }));
+            record(ws.create_snap_with(None, "automatic")?)?;
         }
         previous_root = Some(root);
         if *once {
             break;
         }
-        std::thread::sleep(std::time::Duration::from_millis(*interval_ms));
+        std::thread::sleep(interval);
     }
     emit(mode, captured, |c| {
         println!("watch cycle complete ({} captures)", c.len());
     })
 }
 
+fn cmd_sparse(
+    mode: OutputMode,
+    session: &Session,
+    command: &SparseCommand,
+) -> Result<serde_json::Value> {
+    let ws = session.workspace()?;
+    let patterns = match command {
+        SparseCommand::List => ws.sparse_patterns()?,
+        SparseCommand::Add { patterns, force } => ws.sparse_add(patterns, *force)?,
+        SparseCommand::Remove { patterns, force } => ws.sparse_remove(patterns, *force)?,
+    };
+    emit(mode, patterns, |p| {
+        if p.is_empty() {
+            println!("not sparse: the whole tree is on disk");
+        }
+        for pattern in p {
+            println!("{pattern}");
+        }
+    })
+}
+
+fn cmd_worktree(
+    mode: OutputMode,
+    session: &Session,
+    command: &WorktreeCommand,
+) -> Result<serde_json::Value> {
+    let ws = session.workspace()?;
+    let list = |worktrees: &Vec<WorktreeStatus>| {
+        for w in worktrees {
+            match &w.problem {
+                None => println!("{}\t{}", w.record.name, w.record.path),
+                Some(problem) => {
+                    println!("{}\t{}\t({problem})", w.record.name, w.record.path)
+                }
+            }
+        }
+    };
+    match command {
+        WorktreeCommand::Add { dir, snap } => {
+            let cwd = std::env::current_dir().context("read current directory")?;
+            let added = ws.add_worktree(&cwd.join(dir), snap.as_deref())?;
+            emit(mode, added, |w| {
+                println!("linked worktree {} at {}", w.name, w.path);
+            })
+        }
+        WorktreeCommand::List => emit(mode, ws.worktrees()?, |worktrees| {
+            if worktrees.is_empty() {
+                println!("no linked worktrees");
+            }
+            list(worktrees);
+        }),
+        WorktreeCommand::Prune => emit(mode, ws.prune_worktrees()?, |pruned| {
+            if pruned.is_empty() {
+                println!("every worktree link holds; nothing pruned");
+            } else {
+                println!("forgot {} worktree(s):", pruned.len());
+                list(pruned);
+            }
+        }),
+        WorktreeCommand::Repair => emit(mode, ws.repair_worktree()?, |w| {
+            println!("listed with its primary as {}", w.name);
+        }),
+    }
+}
+
+fn cmd_check_ignore(
+    mode: OutputMode,
+    session: &Session,
+    paths: &[PathBuf],
+) -> Result<serde_json::Value> {
+    let ws = session.workspace()?;
+    let cwd = std::env::current_dir().context("read current directory")?;
+    #[derive(Serialize)]
+    struct Checked {
+        path: String,
+        ignored: bool,
+        #[serde(rename = "match")]
+        decided_by: Option<converge_client::workspace::IgnoreMatch>,
+    }
+    let mut checked = Vec::new();
+    for path in paths {
+        let tree_path = workspace_path(&ws, &cwd.join(path))?;
+        let decided_by = ws.check_ignore(&tree_path)?;
+        checked.push(Checked {
+            ignored: decided_by.as_ref().is_some_and(|m| m.ignores()),
+            path: tree_path,
+            decided_by,
+        });
+    }
+    emit(mode, checked, |checked| {
+        for c in checked {
+            // `git check-ignore -v`'s shape: source:line:pattern, then
+            // the path.
+            match &c.decided_by {
+                Some(m) if m.line == 0 => println!("{}:{}\t{}", m.source, m.pattern, c.path),
+                Some(m) => println!("{}:{}:{}\t{}", m.source, m.line, m.pattern, c.path),
+                None => println!("::\t{}", c.path),
+            }
+        }
+    })
+}
+
+/// `path` as a tree path of `ws`, without touching the filesystem: the
+/// path need not exist.
+fn workspace_path(ws: &Workspace, path: &std::path::Path) -> Result<String> {
+    use std::path::Component;
+    let mut parts: Vec<String> = Vec::new();
+    let relative = path.strip_prefix(&ws.root).with_context(|| {
+        format!(
+            "{} is outside the workspace at {}",
+            path.display(),
+            ws.root.display()
+        )
+    })?;
+    for component in relative.components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy().into_owned()),
+            Component::ParentDir if parts.pop().is_none() => {
+                anyhow::bail!("{} is outside the workspace", path.display());
+            }
+            _ => {}
+        }
+    }
+    Ok(parts.join("/"))
+}
+
 fn cmd_profile(
     mode: OutputMode,
     session: &Session,
@@ -1020,6 +1479,8 @@ fn cmd_show(
         kind: &'static str,
         root_manifest: String,
         derived_from_candidate: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        author: Option<converge_client::model::SnapAuthor>,
         message: Option<String>,
         created_at: Option<String>,
         path: String,
@@ -1035,6 +1496,7 @@ fn cmd_show(
                 .as_ref()
                 .and_then(|s| s.derived_from_candidate.clone())
                 .or(candidate_id),
+            author: snap.as_ref().and_then(|s| s.author.clone()),
             message: snap.as_ref().and_then(|s| s.message.clone()),
             created_at: snap.as_ref().map(|s| s.created_at.clone()),
             path: path.to_owned(),
@@ -1045,6 +1507,15 @@ fn cmd_show(
             if let Some(created) = &s.created_at {
                 println!("  captured {created}");
             }
+            if let Some(author) = &s.author {
+                match &author.key_id {
+                    Some(key) => println!(
+                        "  author: {} <{}>  key {key}",
+                        author.display_name, author.subject
+                    ),
+                    None => println!("  author: {} <{}>", author.display_name, author.subject),
+                }
+            }
             if let Some(message) = &s.message {
                 println!("  message: {message}");
             }
@@ -1111,6 +1582,118 @@ fn cmd_unsnap(
     )
 }
 
+fn cmd_shelve(
+    mode: OutputMode,
+    session: &Session,
+    paths: &[String],
+    name: &Option<String>,
+    list: bool,
+) -> Result<serde_json::Value> {
+    let ws = session.workspace()?;
+    if list {
+        return emit(mode, ws.list_shelves()?, |shelves| {
+            if shelves.is_empty() {
+                println!("nothing is shelved");
+            }
+            for shelf in shelves {
+                println!(
+                    "{}\t{}\t{} paths on {}",
+                    shelf.name,
+                    shelf.created_at,
+                    shelf.paths.len(),
+                    short(&shelf.base)
+                );
+            }
+        });
+    }
+    let cwd = std::env::current_dir().context("read current directory")?;
+    let paths = paths
+        .iter()
+        .map(|path| workspace_path(&ws, &cwd.join(path)))
+        .collect::<Result<Vec<_>>>()?;
+    let shelf = ws.shelve(name.as_deref(), &paths)?;
+    emit(mode, shelf, |shelf| {
+        println!("shelved {} paths as {}", shelf.paths.len(), shelf.name);
+        for path in &shelf.paths {
+            println!("  {path}");
+        }
+        println!("`converge unshelve {}` puts them back", shelf.name);
+    })
+}
+
+fn cmd_unshelve(
+    mode: OutputMode,
+    session: &Session,
+    name: &Option<String>,
+) -> Result<serde_json::Value> {
+    let ws = session.workspace()?;
+    let unshelved = ws.unshelve(name.as_deref())?;
+    emit(mode, unshelved, |u| {
+        println!("unshelved {} ({} paths)", u.name, u.paths.len());
+        if !u.conflicts.is_empty() {
+            println!("changed on both sides, written as conflict markers:");
+            for path in &u.conflicts {
+                println!("  {path}");
+            }
+        }
+    })
+}
+
+fn cmd_pick(
+    mode: OutputMode,
+    session: &Session,
+    snap_id: &str,
+    strategy: &str,
+    rules: &[String],
+) -> Result<serde_json::Value> {
+    let ws = session.workspace()?;
+    let policy = replay_policy(strategy, rules)?;
+    emit_replayed(mode, ws.pick(snap_id, &policy)?)
+}
+
+fn cmd_rebase(
+    mode: OutputMode,
+    session: &Session,
+    onto: &str,
+    strategy: &str,
+    rules: &[String],
+) -> Result<serde_json::Value> {
+    let ws = session.workspace()?;
+    let policy = replay_policy(strategy, rules)?;
+    emit_replayed(mode, ws.rebase_onto(onto, &policy)?)
+}
+
+/// The strategy a replay merges with. No server sees it first, so an
+/// unknown strategy is refused here rather than left to superpose.
+fn replay_policy(strategy: &str, rules: &[String]) -> Result<StrategyPolicy> {
+    let rules = parse_strategy_rules(rules)?;
+    for name in std::iter::once(strategy).chain(rules.iter().map(|r| r.strategy.as_str())) {
+        if !STRATEGIES.contains(&name) {
+            anyhow::bail!("strategy {name} is not one of: {}", STRATEGIES.join(", "));
+        }
+    }
+    StrategyPolicy::new(strategy, &rules)
+}
+
+fn emit_replayed(mode: OutputMode, replayed: Replayed) -> Result<serde_json::Value> {
+    emit(mode, replayed, |r| {
+        match r.snaps.as_slice() {
+            [] => println!("nothing new to replay; head is {}", short(&r.head)),
+            snaps => {
+                for snap in snaps {
+                    println!("snap {snap}");
+                }
+            }
+        }
+        if !r.conflicts.is_empty() {
+            println!("changed on both sides, superposed and written as conflict markers:");
+            for path in &r.conflicts {
+                println!("  {path}");
+            }
+        }
+    })
+}
+
 fn cmd_candidate(
     mode: OutputMode,
     session: &Session,
@@ -1139,14 +1722,33 @@ fn cmd_candidate(
             describe_window(&p.candidate.window),
             p.candidate.base_candidate_id.as_deref().unwrap_or("none")
         );
+        for rule in &p.candidate.strategy_rules {
+            println!("    {}  {}", rule.pattern, rule.strategy);
+        }
+        if !p.candidate.lane_priority.is_empty() {
+            println!("    lanes  {}", p.candidate.lane_priority.join(" > "));
+        }
+        for rename in &p.candidate.renames {
+            println!(
+                "  moved {} -> {}  ({})",
+                rename.from, rename.to, rename.lane
+            );
+        }
         for input in &p.inputs {
+            let signature = p
+                .signatures
+                .iter()
+                .find(|check| check.publication_id == input.publication_id)
+                .map(|check| describe_signature(&check.status))
+                .unwrap_or_else(|| "not checked".into());
             println!(
-                "  input {}  lane {}  by {}  base {}  parents {}",
+                "  input {}  lane {}  by {}  base {}  parents {}  {}",
                 input.publication_id,
                 input.lane_id,
                 input.publisher,
                 input.base_candidate_id.as_deref().unwrap_or("none"),
-                input.snap_parents.len()
+                input.snap_parents.len(),
+                signature
             );
         }
     })
@@ -2231,6 +2833,23 @@ fn run_tui() -> Result<serde_json::Value> {
     }
 }
 
+/// `PATTERN=STRATEGY` flags, in the order given. Whether the strategy
+/// exists is the server's validation to report, with every other fault.
+fn parse_strategy_rules(rules: &[String]) -> Result<Vec<converge_client::model::StrategyRule>> {
+    rules
+        .iter()
+        .map(|rule| {
+            let (pattern, strategy) = rule
+                .rsplit_once('=')
+                .with_context(|| format!("rule {rule:?} is not PATTERN=STRATEGY"))?;
+            Ok(converge_client::model::StrategyRule {
+                pattern: pattern.to_string(),
+                strategy: strategy.to_string(),
+            })
+        })
+        .collect()
+}
+
 fn run_gate_change(
     mode: OutputMode,
     client: &converge_client::remote::RemoteClient,
@@ -2256,6 +2875,8 @@ fn run_gate_change(
             name,
             approvals,
             strategy,
+            rules,
+            lane_priority,
             releasable,
             ..
         } => {
@@ -2269,6 +2890,8 @@ fn run_gate_change(
                 required_approvals: *approvals,
                 strategy: strategy.clone(),
                 may_release: *releasable,
+                strategy_rules: parse_strategy_rules(rules)?,
+                lane_priority: lane_priority.clone(),
             });
         }
         GateCommand::Edit {
@@ -2277,6 +2900,10 @@ fn run_gate_change(
             name,
             approvals,
             strategy,
+            rules,
+            clear_rules,
+            lane_priority,
+            clear_lane_priority,
             releasable,
             ..
         } => {
@@ -2299,6 +2926,18 @@ fn run_gate_change(
             if let Some(strategy) = strategy {
                 gate.strategy = strategy.clone();
             }
+            if let Some(rules) = rules {
+                gate.strategy_rules = parse_strategy_rules(rules)?;
+            }
+            if *clear_rules {
+                gate.strategy_rules.clear();
+            }
+            if let Some(lanes) = lane_priority {
+                gate.lane_priority = lanes.clone();
+            }
+            if *clear_lane_priority {
+                gate.lane_priority.clear();
+            }
             if let Some(releasable) = releasable {
                 gate.may_release = *releasable;
             }
@@ -2469,6 +3108,15 @@ fn run_resolve(
             // Path -> stable variant keys (order matches display order).
             let (root, _) = resolve_target(session, &ws, target)?;
             let variants = superposition_variants(&ws.store, &root)?;
+            // Offered, not applied: `resolve apply` reuses them for the
+            // paths its decisions file leaves open (doc 17 §2c).
+            let mut recorded = BTreeMap::new();
+            for (path, vs) in &variants {
+                let keys: Vec<_> = vs.iter().map(|v| v.key()).collect();
+                if let Some(decision) = ws.store.recorded_decision(&keys)? {
+                    recorded.insert(path.clone(), decision);
+                }
+            }
             if !preview {
                 let keyed: std::collections::BTreeMap<
                     String,
@@ -2479,7 +3127,14 @@ fn run_resolve(
                     .collect();
                 return emit(mode, keyed, |keyed| {
                     for (path, keys) in keyed {
-                        println!("{path}  {} variants", keys.len());
+                        match recorded.get(path) {
+                            Some(decision) => println!(
+                                "{path}  {} variants  (reused: {})",
+                                keys.len(),
+                                describe_recorded(decision)
+                            ),
+                            None => println!("{path}  {} variants", keys.len()),
+                        }
                     }
                 });
             }
@@ -2512,6 +3167,10 @@ fn run_resolve(
                             "elided": preview.elided,
                             "skipped_common_lines": skipped,
                             "why": preview.why,
+                            "reused": matches!(
+                                recorded.get(&path),
+                                Some(ResolutionDecision::Key(k)) if k == key
+                            ),
                         })
                     })
                     .collect();
@@ -2519,7 +3178,12 @@ fn run_resolve(
             }
             emit(mode, serde_json::Value::Object(previewed), |previewed| {
                 for (path, variants) in previewed.as_object().into_iter().flatten() {
-                    println!("{path}");
+                    match recorded.get(path) {
+                        Some(decision) => {
+                            println!("{path}  (reused: {})", describe_recorded(decision))
+                        }
+                        None => println!("{path}"),
+                    }
                     for variant in variants.as_array().into_iter().flatten() {
                         println!("  [{}]", variant["source"].as_str().unwrap_or("?"));
                         if let Some(n) = variant["skipped_common_lines"].as_u64()
@@ -2545,22 +3209,50 @@ fn run_resolve(
                 }
             })
         }
-        ResolveCommand::Validate { target, decisions } => {
-            let decisions = read_decisions(decisions)?;
+        ResolveCommand::Validate {
+            target,
+            decisions,
+            no_reuse,
+        } => {
+            let mut decisions = match decisions {
+                Some(path) => read_decisions(path)?,
+                None => BTreeMap::new(),
+            };
             let (root, _) = resolve_target(session, &ws, target)?;
+            let reused = if *no_reuse {
+                Vec::new()
+            } else {
+                reuse_recorded_decisions(&ws.store, &root, &mut decisions)?
+            };
             let report = validate_resolution(&ws.store, &root, &decisions)?;
             let ok = report.ok;
-            let value = emit(mode, report, |r| {
+
+            #[derive(Serialize)]
+            struct Validated {
+                #[serde(flatten)]
+                report: converge_client::resolve::ResolutionValidation,
+                /// Paths decided by a recorded decision, not the file.
+                reused: Vec<String>,
+            }
+            let value = emit(mode, Validated { report, reused }, |v| {
+                for path in &v.reused {
+                    println!("reused recorded decision for {path}");
+                }
+                let r = &v.report;
                 if r.ok {
                     println!("valid");
                 } else {
                     println!(
-                        "invalid: {} missing, {} extraneous, {} out-of-range, {} invalid keys",
+                        "invalid: {} missing, {} extraneous, {} out-of-range, {} invalid keys, {} invalid content",
                         r.missing.len(),
                         r.extraneous.len(),
                         r.out_of_range.len(),
-                        r.invalid_keys.len()
+                        r.invalid_keys.len(),
+                        r.invalid_content.len()
                     );
+                    for bad in &r.invalid_content {
+                        println!("  {}: {}", bad.path, bad.reason);
+                    }
                 }
             });
             if ok {
@@ -2569,16 +3261,92 @@ fn run_resolve(
                 Err(ReportedFailure("resolution invalid".into()).into())
             }
         }
+        ResolveCommand::Capture {
+            target,
+            path,
+            file,
+            decisions: decisions_path,
+        } => {
+            let (root, _) = resolve_target(session, &ws, target)?;
+            let variants = superposition_variants(&ws.store, &root)?;
+            let Some(vs) = variants.get(path) else {
+                anyhow::bail!("{path} is not a superposition in {}", short(target));
+            };
+            let decision = ResolutionDecision::Content(ResolvedContent {
+                content: ws.capture_file(path, file)?,
+                supersedes: vs.iter().map(|v| v.key()).collect(),
+            });
+
+            // Add to what is already decided: a resolver works through
+            // the paths one at a time.
+            let mut decisions = if decisions_path.exists() {
+                read_decisions(decisions_path)?
+            } else {
+                BTreeMap::new()
+            };
+            decisions.insert(path.clone(), decision.clone());
+            let bytes = serde_json::to_vec_pretty(&decisions).context("serialize decisions")?;
+            std::fs::write(decisions_path, bytes)
+                .with_context(|| format!("write {}", decisions_path.display()))?;
+
+            #[derive(Serialize)]
+            struct ContentCaptured {
+                path: String,
+                decision: ResolutionDecision,
+                supersedes: usize,
+                decisions: String,
+            }
+            emit(
+                mode,
+                ContentCaptured {
+                    path: path.clone(),
+                    decision,
+                    supersedes: vs.len(),
+                    decisions: decisions_path.display().to_string(),
+                },
+                |r| {
+                    println!(
+                        "{}: hand-edited content replaces {} variant(s) -> {}",
+                        r.path, r.supersedes, r.decisions
+                    );
+                },
+            )
+        }
         ResolveCommand::Apply {
             target,
             decisions,
+            no_reuse,
             message,
             force,
             no_checkout,
         } => {
-            let decisions = read_decisions(decisions)?;
+            let mut decisions = match decisions {
+                Some(path) => read_decisions(path)?,
+                None => BTreeMap::new(),
+            };
             let (root, candidate_id) = resolve_target(session, &ws, target)?;
+            let reused = if *no_reuse {
+                Vec::new()
+            } else {
+                reuse_recorded_decisions(&ws.store, &root, &mut decisions)?
+            };
             let resolved = apply_resolution(&ws.store, &root, &decisions)?;
+            record_decisions(&ws.store, &root, &decisions)?;
+            // The record is what ties a candidate's conflicts to how they
+            // were settled, hand-edited content and what it superseded
+            // included.
+            if let Some(candidate_id) = &candidate_id {
+                let handwritten = decisions
+                    .values()
+                    .any(|d| matches!(d, ResolutionDecision::Content(_)));
+                ws.store.put_resolution(&Resolution {
+                    version: if handwritten { 3 } else { 2 },
+                    candidate_id: candidate_id.clone(),
+                    root_manifest: root.clone(),
+                    created_at: now_rfc3339()?,
+                    decisions: decisions.clone(),
+                })?;
+            }
 
             // A resolved tree used to stop here as a manifest id no verb
             // accepted (audit P1.1). It lands as a snap, so `publish`,
@@ -2598,6 +3366,8 @@ fn run_resolve(
                 root_manifest: String,
                 derived_from_candidate: Option<String>,
                 paths_resolved: usize,
+                /// Paths decided by a recorded decision, not the file.
+                reused: Vec<String>,
                 checked_out: bool,
                 /// The verb that continues the flow — the inbox and the
                 /// TUI both surface this rather than inventing their own.
@@ -2610,10 +3380,14 @@ fn run_resolve(
                     root_manifest: resolved.as_str().to_string(),
                     derived_from_candidate: candidate_id,
                     paths_resolved: decisions.len(),
+                    reused,
                     checked_out: !*no_checkout,
                     next: format!("publish --snap {}", snap.id),
                 },
                 |r| {
+                    for path in &r.reused {
+                        println!("reused recorded decision for {path}");
+                    }
                     println!(
                         "resolved {} path(s) -> snap {}{}",
                         r.paths_resolved,
@@ -2631,6 +3405,16 @@ fn run_resolve(
     }
 }
 
+/// What a recorded decision would do, in the words `resolve list` offers
+/// it with.
+fn describe_recorded(decision: &ResolutionDecision) -> String {
+    match decision {
+        ResolutionDecision::Key(key) => format!("take {}", key.source),
+        ResolutionDecision::Index(i) => format!("take variant {i}"),
+        ResolutionDecision::Content(_) => "hand-edited content".into(),
+    }
+}
+
 /// One progress line per transferred batch, on stderr.
 ///
 /// Batch granularity is the honest unit: the client negotiates, then
@@ -2682,6 +3466,24 @@ fn describe_status(status: &converge_client::model::CandidateStatus) -> String {
     }
 }
 
+fn describe_signature(status: &converge_client::model::SignatureStatus) -> String {
+    use converge_client::model::SignatureStatus as S;
+    match status {
+        S::Unsigned => "unsigned".into(),
+        S::Verified { key_id } => format!("signed by key {key_id}"),
+        S::KeyRetired { key_id } => format!("signed by key {key_id}, no longer registered"),
+        S::Invalid { reason } => format!("BAD SIGNATURE: {reason}"),
+    }
+}
+
+fn describe_signing(policy: &converge_client::model::SigningPolicy) -> &'static str {
+    if policy.require_signed {
+        "refused"
+    } else {
+        "accepted"
+    }
+}
+
 /// `(first_seq, last_seq)` as a range a person reads.
 fn describe_window(window: &(u64, u64)) -> String {
     if window.0 == window.1 {
@@ -2707,6 +3509,9 @@ fn describe_limit(limit: Option<u32>) -> String {
 /// only resolvable thing was a local snap. A candidate whose objects are not
 /// local yet is fetched first; that is the same work the user would have
 /// done by hand, and it is idempotent.
+///
+/// A partial store fetches it lazily (doc 16 §1g): listing and diffing
+/// read manifests, and should not wait on blobs nothing has asked for.
 fn resolve_target(
     session: &Session,
     ws: &Workspace,
@@ -2715,11 +3520,41 @@ fn resolve_target(
     if let Ok(snap) = ws.store.get_snap(target) {
         return Ok((snap.root_manifest, snap.derived_from_candidate));
     }
-    let root = fetch_candidate_tree(session, ws, target)
+    let root = fetch_candidate_tree(session, ws, target, ws.store.is_partial())
         .with_context(|| format!("{target} is neither a local snap nor a reachable candidate"))?;
     Ok((root, Some(target.to_string())))
 }
 
+/// The tree of `candidate_id`'s W, as the common ancestor for marker
+/// files (doc 17 §2d). Best effort: without a remote, or for a candidate
+/// with no W, markers carry the variants alone.
+fn marker_base(session: &Session, ws: &Workspace, candidate_id: Option<&str>) -> Option<ObjectId> {
+    let (client, remote) = remote_client(session, ws, OutputMode::Capture).ok()?;
+    let base_id = client
+        .get_candidate(candidate_id?)
+        .ok()?
+        .base_candidate_id?;
+    // Not `fetch_candidate_tree`: W is only read here, and must not
+    // become the publish base.
+    let fetched = if ws.store.is_partial() {
+        client.fetch_candidate_lazy(&ws.store, &remote.repo_id, &base_id)
+    } else {
+        client.fetch_candidate(&ws.store, &remote.repo_id, &base_id)
+    };
+    fetched.ok()
+}
+
+fn print_marked(marked: &[String]) {
+    if marked.is_empty() {
+        return;
+    }
+    println!("wrote conflict markers in {} file(s):", marked.len());
+    for path in marked {
+        println!("  {path}");
+    }
+    println!("edit them to the content you want, then `converge snap`");
+}
+
 /// Fetch a candidate's tree into the local store and, when the candidate
 /// belongs to the configured target, record it as the publish base.
 ///
@@ -2727,10 +3562,21 @@ fn resolve_target(
 /// it declares no knowledge of the candidate it resolved, so the fold
 /// re-superposes the very paths the user just decided (batch 16.1). Both
 /// `fetch` and `resolve` go through here so neither can forget.
-fn fetch_candidate_tree(session: &Session, ws: &Workspace, candidate_id: &str) -> Result<ObjectId> {
+///
+/// `lazy` fetches the tree's shape and promises its blobs (doc 16 §1g).
+fn fetch_candidate_tree(
+    session: &Session,
+    ws: &Workspace,
+    candidate_id: &str,
+    lazy: bool,
+) -> Result<ObjectId> {
     let (client, remote) = remote_client(session, ws, OutputMode::Capture)?;
     let candidate = client.get_candidate(candidate_id)?;
-    let root = client.fetch_candidate(&ws.store, &remote.repo_id, candidate_id)?;
+    let root = if lazy {
+        client.fetch_candidate_lazy(&ws.store, &remote.repo_id, candidate_id)?
+    } else {
+        client.fetch_candidate(&ws.store, &remote.repo_id, candidate_id)?
+    };
     if candidate.scope_id == remote.scope {
         ws.store.set_last_seen_candidate(
             &remote,
@@ -186,7 +186,12 @@ impl Session {
         {
             return Ok(ws.clone());
         }
-        let ws = Workspace::discover(&cwd)?;
+        let mut ws = Workspace::discover(&cwd)?;
+        // Promised blobs are read from the remote (doc 16 §1g). Attached
+        // whether or not the store is partial yet — a `fetch --lazy` can
+        // make it so mid-session — and it connects only if one is read.
+        let source = converge_client::remote::RemoteBlobs::new(ws.store.clone());
+        ws.store = ws.store.with_blob_source(std::sync::Arc::new(source));
         cache.workspace = Some((cwd, ws.clone()));
         Ok(ws)
     }
@@ -23,7 +23,7 @@ pub(crate) fn list_tree(ws: &Workspace, root: &ObjectId, path: &str) -> Result<V
 
     let mut current = root.clone();
     for segment in path.split('/').filter(|s| !s.is_empty()) {
-        let manifest = ws.store.get_manifest(&current)?;
+        let manifest = ws.store.get_dir(&current)?;
         let entry = manifest
             .entries
             .into_iter()
@@ -37,7 +37,7 @@ pub(crate) fn list_tree(ws: &Workspace, root: &ObjectId, path: &str) -> Result<V
 
     Ok(ws
         .store
-        .get_manifest(&current)?
+        .get_dir(&current)?
         .entries
         .into_iter()
         .map(|entry| match entry.kind {
@@ -65,6 +65,7 @@ pub(crate) fn list_tree(ws: &Workspace, root: &ObjectId, path: &str) -> Result<V
                 size: None,
                 variants: Some(variants.len()),
             },
+            Kind::Page { .. } => unreachable!("get_dir reads pages through"),
         })
         .collect())
 }
@@ -182,6 +183,7 @@ pub(crate) fn variant_preview(
         // Not an absence of content: a deliberate deletion, and the
         // chooser needs to see it as a real option.
         K::Tombstone => return empty("deleted in this variant"),
+        K::MovedTo { path } => return empty(&format!("moved to {path} in this variant")),
     };
 
     let looked_at = bytes.len().min(PREVIEW_BYTES);
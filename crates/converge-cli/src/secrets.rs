@@ -46,14 +46,13 @@ pub(crate) fn restrict_file(path: &std::path::Path) -> Result<()> {
 /// behind itself rather than trusting anyone to remember.
 pub(crate) fn ensure_ignored(ws: &Workspace, path: &std::path::Path) -> Result<bool> {
     let entry = path.display().to_string();
-    let ignore_path = ws.root.join(".convergeignore");
-    let existing = std::fs::read_to_string(&ignore_path).unwrap_or_default();
-    if existing
-        .lines()
-        .any(|line| line.trim() == entry || line.trim() == entry.trim_end_matches('/'))
-    {
+    // Covered by any rule, not just one spelled like the path: the
+    // same question the scan will ask.
+    if ws.check_ignore(&entry)?.is_some_and(|m| m.ignores()) {
         return Ok(false);
     }
+    let ignore_path = ws.root.join(".convergeignore");
+    let existing = std::fs::read_to_string(&ignore_path).unwrap_or_default();
     let mut updated = existing;
     if !updated.is_empty() && !updated.ends_with('\n') {
         updated.push('\n');
@@ -481,6 +480,11 @@ pub(crate) fn register_key_if_possible(
     let Ok((client, remote)) = remote_client(session, &ws, OutputMode::Capture) else {
         return Ok(false);
     };
-    client.register_key(&remote.repo_id, &public.public_key, &public.label)?;
+    client.register_key(
+        &remote.repo_id,
+        &public.public_key,
+        public.signing_key.as_deref(),
+        &public.label,
+    )?;
     Ok(true)
 }
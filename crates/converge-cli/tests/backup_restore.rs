@@ -73,6 +73,8 @@ fn provision(data_dir: &Path) -> Result<()> {
                 required_approvals: 0,
                 strategy: "whole-file".into(),
                 may_release: true,
+                strategy_rules: vec![],
+                lane_priority: vec![],
             }],
         },
     )?;
@@ -93,6 +93,357 @@ fn errors_exit_one_with_json_envelope() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Neither variant is right: the resolver writes the file, captures it
+/// as the decision, and the record keeps what it replaced (doc 17 §2b).
+#[test]
+fn resolve_capture_applies_hand_edited_content() -> anyhow::Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let root = tmp.path();
+    assert!(converge(root, &["init"]).status.success());
+
+    let ws = Workspace::discover(root)?;
+    let variant = |source: &str, bytes: &[u8]| -> anyhow::Result<SuperpositionVariant> {
+        Ok(SuperpositionVariant {
+            source: source.into(),
+            kind: SuperpositionVariantKind::File {
+                blob: ws.store.put_blob(bytes)?,
+                mode: 0o100644,
+                size: bytes.len() as u64,
+            },
+        })
+    };
+    let root_manifest = ws.store.put_manifest(&Manifest {
+        version: 1,
+        entries: vec![ManifestEntry {
+            name: "conflicted.txt".into(),
+            kind: ManifestEntryKind::Superposition {
+                variants: vec![
+                    variant("lane-a", b"variant a")?,
+                    variant("lane-b", b"variant b")?,
+                ],
+            },
+        }],
+    })?;
+    let snap = converge_client::model::SnapRecord {
+        version: 2,
+        id: compute_snap_id(&root_manifest, &[], Some("cand-1"), None, None),
+        created_at: "2026-07-23T00:00:00Z".into(),
+        root_manifest,
+        parents: Vec::new(),
+        derived_from_candidate: Some("cand-1".into()),
+        author: None,
+        replayed_from: None,
+        message: Some("superposed".into()),
+        trigger: "explicit".into(),
+        stats: SnapStats::default(),
+    };
+    ws.store.put_snap(&snap)?;
+    ws.store.set_head(Some(&snap.id))?;
+
+    // The edit can live anywhere; only its bytes are taken.
+    let scratch = tempfile::tempdir()?;
+    let edited = scratch.path().join("merged.txt");
+    std::fs::write(&edited, "variant a and b")?;
+    let decisions = scratch.path().join("decisions.json");
+    let captured = json_data(&converge(
+        root,
+        &[
+            "--json",
+            "resolve",
+            "capture",
+            &snap.id,
+            "conflicted.txt",
+            edited.to_str().unwrap(),
+            "--decisions",
+            decisions.to_str().unwrap(),
+        ],
+    ));
+    assert_eq!(captured["supersedes"], 2);
+
+    let out = converge(
+        root,
+        &[
+            "--json",
+            "resolve",
+            "validate",
+            &snap.id,
+            decisions.to_str().unwrap(),
+        ],
+    );
+    assert!(out.status.success(), "{}", stdout(&out));
+
+    let resolved = json_data(&converge(
+        root,
+        &[
+            "--json",
+            "resolve",
+            "apply",
+            &snap.id,
+            decisions.to_str().unwrap(),
+            // A superposition is never on disk, so the empty working
+            // tree differs from the snap it came from.
+            "--force",
+        ],
+    ));
+    assert_eq!(resolved["checked_out"], true);
+    assert_eq!(
+        std::fs::read_to_string(root.join("conflicted.txt"))?,
+        "variant a and b"
+    );
+
+    let record = ws.store.get_resolution("cand-1")?;
+    assert_eq!(record.version, 3);
+    let Some(converge_client::model::ResolutionDecision::Content(content)) =
+        record.decisions.get("conflicted.txt")
+    else {
+        panic!("expected a hand-edited decision: {:?}", record.decisions);
+    };
+    let sources: Vec<_> = content
+        .supersedes
+        .iter()
+        .map(|k| k.source.as_str())
+        .collect();
+    assert_eq!(sources, ["lane-a", "lane-b"]);
+    Ok(())
+}
+
+/// The same two variants meeting again are decided the way they were
+/// last time, and say so (doc 17 §2c).
+#[test]
+fn resolve_apply_reuses_recorded_decisions() -> anyhow::Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let root = tmp.path();
+    assert!(converge(root, &["init"]).status.success());
+
+    let ws = Workspace::discover(root)?;
+    let variant = |source: &str, bytes: &[u8]| -> anyhow::Result<SuperpositionVariant> {
+        Ok(SuperpositionVariant {
+            source: source.into(),
+            kind: SuperpositionVariantKind::File {
+                blob: ws.store.put_blob(bytes)?,
+                mode: 0o100644,
+                size: bytes.len() as u64,
+            },
+        })
+    };
+    let a = variant("lane-a", b"variant a")?;
+    let b = variant("lane-b", b"variant b")?;
+    let snap_of = |variants: Vec<SuperpositionVariant>, message: &str| -> anyhow::Result<String> {
+        let root_manifest = ws.store.put_manifest(&Manifest {
+            version: 1,
+            entries: vec![ManifestEntry {
+                name: "conflicted.txt".into(),
+                kind: ManifestEntryKind::Superposition { variants },
+            }],
+        })?;
+        let snap = converge_client::model::SnapRecord {
+            version: 2,
+            id: compute_snap_id(&root_manifest, &[], None, None, None),
+            created_at: "2026-07-23T00:00:00Z".into(),
+            root_manifest,
+            parents: Vec::new(),
+            derived_from_candidate: None,
+            author: None,
+            replayed_from: None,
+            message: Some(message.into()),
+            trigger: "explicit".into(),
+            stats: SnapStats::default(),
+        };
+        ws.store.put_snap(&snap)?;
+        Ok(snap.id)
+    };
+    let first = snap_of(vec![a.clone(), b.clone()], "first window")?;
+    let second = snap_of(vec![b, a], "next window")?;
+
+    let scratch = tempfile::tempdir()?;
+    let decisions = scratch.path().join("decisions.json");
+    std::fs::write(&decisions, r#"{"conflicted.txt": 1}"#)?;
+    let applied = json_data(&converge(
+        root,
+        &[
+            "--json",
+            "resolve",
+            "apply",
+            &first,
+            decisions.to_str().unwrap(),
+            "--no-checkout",
+        ],
+    ));
+    assert_eq!(applied["reused"], serde_json::json!([]));
+
+    // Offered by `list`...
+    let out = converge(root, &["resolve", "list", &second]);
+    assert!(
+        stdout(&out).contains("conflicted.txt  2 variants  (reused: take lane-b)"),
+        "{}",
+        stdout(&out)
+    );
+    let previewed = json_data(&converge(
+        root,
+        &["--json", "resolve", "list", &second, "--preview"],
+    ));
+    assert_eq!(previewed["conflicted.txt"][0]["reused"], true);
+    assert_eq!(previewed["conflicted.txt"][1]["reused"], false);
+
+    // ...and applied without a decisions file, marked as reused.
+    let applied = json_data(&converge(
+        root,
+        &["--json", "resolve", "apply", &second, "--no-checkout"],
+    ));
+    assert_eq!(applied["reused"], serde_json::json!(["conflicted.txt"]));
+    let resolved = ws.store.get_manifest(&converge_client::model::ObjectId(
+        applied["root_manifest"].as_str().unwrap().to_string(),
+    ))?;
+    match &resolved.entries[0].kind {
+        ManifestEntryKind::File { blob, .. } => {
+            assert_eq!(ws.store.get_blob(blob)?, b"variant b");
+        }
+        other => panic!("expected resolved file, got {other:?}"),
+    }
+
+    // Opting out leaves the path undecided.
+    let out = converge(
+        root,
+        &["--json", "resolve", "validate", &second, "--no-reuse"],
+    );
+    assert_eq!(out.status.code(), Some(1));
+    Ok(())
+}
+
+/// `restore --markers` checks a superposed snap out as marker files, and
+/// the snap after de-markering is the resolution (doc 17 §2d).
+#[test]
+fn restore_markers_then_snap_resolves() -> anyhow::Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let root = tmp.path();
+    assert!(converge(root, &["init"]).status.success());
+
+    let ws = Workspace::discover(root)?;
+    let variant = |source: &str, bytes: &[u8]| -> anyhow::Result<SuperpositionVariant> {
+        Ok(SuperpositionVariant {
+            source: source.into(),
+            kind: SuperpositionVariantKind::File {
+                blob: ws.store.put_blob(bytes)?,
+                mode: 0o100644,
+                size: bytes.len() as u64,
+            },
+        })
+    };
+    let root_manifest = ws.store.put_manifest(&Manifest {
+        version: 1,
+        entries: vec![ManifestEntry {
+            name: "conflicted.txt".into(),
+            kind: ManifestEntryKind::Superposition {
+                variants: vec![
+                    variant("lane-a", b"variant a\n")?,
+                    variant("lane-b", b"variant b\n")?,
+                ],
+            },
+        }],
+    })?;
+    let snap = converge_client::model::SnapRecord {
+        version: 2,
+        id: compute_snap_id(&root_manifest, &[], Some("cand-1"), None, None),
+        created_at: "2026-07-23T00:00:00Z".into(),
+        root_manifest,
+        parents: Vec::new(),
+        derived_from_candidate: Some("cand-1".into()),
+        author: None,
+        replayed_from: None,
+        message: Some("superposed".into()),
+        trigger: "explicit".into(),
+        stats: SnapStats::default(),
+    };
+    ws.store.put_snap(&snap)?;
+
+    let out = converge(root, &["restore", &snap.id, "--force"]);
+    assert!(!out.status.success(), "a superposition refuses by default");
+
+    let restored = json_data(&converge(
+        root,
+        &["--json", "restore", &snap.id, "--force", "--markers"],
+    ));
+    assert_eq!(restored["marked"], serde_json::json!(["conflicted.txt"]));
+    // No remote, so no W to diff against: the variants alone.
+    assert_eq!(
+        std::fs::read_to_string(root.join("conflicted.txt"))?,
+        "<<<<<<< lane-a\nvariant a\n=======\nvariant b\n>>>>>>> lane-b\n"
+    );
+    let unchanged = json_data(&converge(root, &["--json", "snap"]));
+    assert_eq!(unchanged["id"], snap.id.as_str());
+
+    std::fs::write(root.join("conflicted.txt"), "variant a and b\n")?;
+    let resolved = json_data(&converge(root, &["--json", "snap"]));
+    assert_ne!(resolved["id"], snap.id.as_str());
+    let record = ws.store.get_resolution("cand-1")?;
+    assert!(matches!(
+        record.decisions.get("conflicted.txt"),
+        Some(converge_client::model::ResolutionDecision::Content(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn sparse_verbs_narrow_and_widen_the_working_tree() -> anyhow::Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let root = tmp.path();
+    assert!(converge(root, &["init"]).status.success());
+    std::fs::create_dir_all(root.join("assets/characters"))?;
+    std::fs::create_dir_all(root.join("src"))?;
+    std::fs::write(root.join("assets/characters/hero.png"), "hero")?;
+    std::fs::write(root.join("src/main.rs"), "fn main() {}")?;
+    assert!(converge(root, &["snap", "-m", "base"]).status.success());
+
+    assert_eq!(
+        json_data(&converge(root, &["--json", "sparse", "list"])),
+        serde_json::json!([])
+    );
+    let added = json_data(&converge(
+        root,
+        &["--json", "sparse", "add", "assets/characters"],
+    ));
+    assert_eq!(added, serde_json::json!(["assets/characters"]));
+    assert!(!root.join("src").exists());
+    // Status is clean: src is carried, not deleted.
+    let status = json_data(&converge(root, &["--json", "status"]));
+    assert_eq!(status["pending"]["count"], 0, "{status}");
+
+    let out = converge(root, &["sparse", "remove", "assets"]);
+    assert!(!out.status.success());
+    assert!(
+        stdout(&converge(root, &["sparse", "remove", "assets/characters"])).contains("whole tree")
+    );
+    assert!(root.join("src/main.rs").exists());
+    Ok(())
+}
+
+#[test]
+fn gc_local_is_a_dry_run_unless_executed() -> anyhow::Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let root = tmp.path();
+    assert!(converge(root, &["init"]).status.success());
+    std::fs::write(root.join("a.txt"), "a")?;
+    assert!(converge(root, &["snap", "-m", "base"]).status.success());
+
+    let dry = json_data(&converge(root, &["--json", "gc", "--local"]));
+    assert_eq!(dry["dry_run"], true, "{dry}");
+    assert_eq!(dry["swept_objects"], 0, "{dry}");
+    assert!(
+        root.join(".converge/objects/blobs")
+            .read_dir()?
+            .next()
+            .is_some()
+    );
+    let run = json_data(&converge(root, &["--json", "gc", "--local", "--execute"]));
+    assert_eq!(run["dry_run"], false, "{run}");
+    assert_eq!(run["packs"], 1, "{run}");
+    assert_eq!(run["loose_objects"], 0, "{run}");
+
+    assert!(stdout(&converge(root, &["gc", "--local"])).starts_with("dry-run"));
+    assert!(converge(root, &["status"]).status.success());
+    Ok(())
+}
+
 #[test]
 fn resolve_list_validate_apply_over_superposition() -> anyhow::Result<()> {
     let tmp = tempfile::tempdir()?;
@@ -135,11 +486,13 @@ fn resolve_list_validate_apply_over_superposition() -> anyhow::Result<()> {
     let created_at = "2026-07-23T00:00:00Z".to_string();
     let snap = converge_client::model::SnapRecord {
         version: 2,
-        id: compute_snap_id(&root_manifest, &[], None),
+        id: compute_snap_id(&root_manifest, &[], None, None, None),
         created_at,
         root_manifest,
         parents: Vec::new(),
         derived_from_candidate: None,
+        author: None,
+        replayed_from: None,
         message: Some("superposed".into()),
         trigger: "explicit".into(),
         stats: SnapStats::default(),
@@ -414,6 +767,136 @@ fn unsnap_undoes_the_capture_and_leaves_the_tree() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Doc 15 §5e: shelve sets edits aside relative to the cwd, unshelve
+/// brings them back.
+#[test]
+fn shelve_and_unshelve_round_trip_edits() -> anyhow::Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let root = tmp.path();
+    assert!(converge(root, &["init"]).status.success());
+    std::fs::create_dir(root.join("src"))?;
+    std::fs::write(root.join("src/lib.rs"), "old")?;
+    std::fs::write(root.join("top.txt"), "old")?;
+    assert!(converge(root, &["snap", "-m", "base"]).status.success());
+
+    std::fs::write(root.join("src/lib.rs"), "new")?;
+    std::fs::write(root.join("top.txt"), "new")?;
+    let shelf = json_data(&converge(
+        &root.join("src"),
+        &["--json", "shelve", "--path", ".", "--name", "lib"],
+    ));
+    assert_eq!(shelf["name"], "lib");
+    assert_eq!(shelf["paths"], serde_json::json!(["src/lib.rs"]));
+    assert_eq!(std::fs::read_to_string(root.join("src/lib.rs"))?, "old");
+    assert_eq!(std::fs::read_to_string(root.join("top.txt"))?, "new");
+
+    let listed = json_data(&converge(root, &["--json", "shelve", "--list"]));
+    assert_eq!(listed[0]["name"], "lib");
+    let status = json_data(&converge(root, &["--json", "status"]));
+    assert_eq!(status["pending"]["count"], 1, "only top.txt is pending");
+
+    let back = json_data(&converge(root, &["--json", "unshelve"]));
+    assert_eq!(back["name"], "lib");
+    assert_eq!(std::fs::read_to_string(root.join("src/lib.rs"))?, "new");
+    assert_eq!(
+        converge(root, &["--json", "unshelve"]).status.code(),
+        Some(1)
+    );
+    Ok(())
+}
+
+/// Doc 15 §5g: `snap --path` and `snap -i` capture part of the tree and
+/// leave the rest pending.
+#[test]
+fn snap_captures_only_the_selected_changes() -> anyhow::Result<()> {
+    use std::io::Write;
+    let tmp = tempfile::tempdir()?;
+    let root = tmp.path();
+    assert!(converge(root, &["init"]).status.success());
+    std::fs::create_dir(root.join("src"))?;
+    std::fs::write(root.join("src/lib.rs"), "old")?;
+    std::fs::write(root.join("notes.txt"), "old")?;
+    std::fs::write(root.join("todo.txt"), "old")?;
+    assert!(converge(root, &["snap", "-m", "base"]).status.success());
+
+    std::fs::write(root.join("src/lib.rs"), "new")?;
+    std::fs::write(root.join("notes.txt"), "new")?;
+    std::fs::write(root.join("todo.txt"), "new")?;
+    json_data(&converge(
+        &root.join("src"),
+        &["--json", "snap", "--path", "*.rs"],
+    ));
+    let status = json_data(&converge(root, &["--json", "status"]));
+    assert_eq!(status["pending"]["count"], 2, "the .txt files are pending");
+
+    // Asked in path order: notes.txt taken, todo.txt left.
+    let mut child = Command::new(env!("CARGO_BIN_EXE_converge"))
+        .current_dir(root)
+        .args(["--json", "snap", "-i", "-m", "notes"])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()?;
+    child.stdin.take().expect("stdin").write_all(b"y\nn\n")?;
+    let out = child.wait_with_output()?;
+    assert_eq!(json_data(&out)["message"], "notes");
+    assert!(String::from_utf8_lossy(&out.stderr).contains("M notes.txt"));
+    let status = json_data(&converge(root, &["--json", "status"]));
+    assert_eq!(status["pending"]["count"], 1, "todo.txt is pending");
+    assert_eq!(
+        converge(root, &["snap", "--path", "src"]).status.code(),
+        Some(1),
+        "nothing under src is left to snap"
+    );
+    Ok(())
+}
+
+/// Doc 15 §5h: `pick` carries one snap's change over from another
+/// lineage; `rebase --onto` moves head's own snaps there.
+#[test]
+fn pick_and_rebase_replay_changes_across_lineages() -> anyhow::Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let root = tmp.path();
+    assert!(converge(root, &["init"]).status.success());
+    std::fs::write(root.join("fix.txt"), "bug")?;
+    std::fs::write(root.join("mine.txt"), "old")?;
+    let base = json_data(&converge(root, &["--json", "snap", "-m", "base"]));
+    let base = base["id"].as_str().unwrap().to_string();
+    std::fs::write(root.join("fix.txt"), "fixed")?;
+    let fix = json_data(&converge(root, &["--json", "snap", "-m", "fix"]));
+    let fix = fix["id"].as_str().unwrap().to_string();
+
+    assert!(converge(root, &["restore", &base]).status.success());
+    std::fs::write(root.join("mine.txt"), "new")?;
+    assert!(converge(root, &["snap", "-m", "mine"]).status.success());
+    let picked = json_data(&converge(root, &["--json", "pick", &fix]));
+    assert_eq!(picked["snaps"].as_array().map(Vec::len), Some(1));
+    assert_eq!(std::fs::read_to_string(root.join("fix.txt"))?, "fixed");
+    assert_eq!(std::fs::read_to_string(root.join("mine.txt"))?, "new");
+
+    // Undo the pick, and move the whole line over instead.
+    let undone = json_data(&converge(root, &["--json", "unsnap"]));
+    let mine = undone["head"].as_str().unwrap().to_string();
+    assert!(
+        converge(root, &["restore", "--force", &mine])
+            .status
+            .success()
+    );
+    let rebased = json_data(&converge(root, &["--json", "rebase", "--onto", &fix]));
+    assert_eq!(rebased["snaps"].as_array().map(Vec::len), Some(1));
+    let history = json_data(&converge(root, &["--json", "history"]));
+    let on_line: Vec<&str> = history
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|s| s["on_current_line"] == true)
+        .map(|s| s["id"].as_str().unwrap())
+        .collect();
+    assert!(on_line.contains(&fix.as_str()) && !on_line.contains(&mine.as_str()));
+    assert_eq!(std::fs::read_to_string(root.join("fix.txt"))?, "fixed");
+    Ok(())
+}
+
 /// Batch 16.2 (audit P4.18): read-only browsing of a captured tree.
 #[test]
 fn show_lists_a_snap_tree_without_touching_the_workspace() -> anyhow::Result<()> {
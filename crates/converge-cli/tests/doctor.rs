@@ -218,6 +218,100 @@ fn an_unusable_credential_is_told_apart_from_a_missing_grant() -> Result<()> {
     Ok(())
 }
 
+/// A stat index that fails its checksum is reported with its fix, and
+/// the fix is the one flag that lets `doctor` write (doc 15 §5c).
+#[test]
+fn a_corrupt_stat_index_is_reported_and_rebuilt_on_request() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let home = tempfile::tempdir()?;
+    converge(dir.path(), home.path(), &["init"]);
+    std::fs::write(dir.path().join("a.txt"), "a")?;
+    converge(dir.path(), home.path(), &["status"]);
+    let index = dir.path().join(".converge/index");
+    let healthy = report(&converge(dir.path(), home.path(), &["--json", "doctor"]));
+    assert_eq!(check(&healthy, "stat index")["detail"], "1 files");
+
+    std::fs::write(&index, b"not an index")?;
+    let broken = report(&converge(dir.path(), home.path(), &["--json", "doctor"]));
+    let stat_index = check(&broken, "stat index");
+    assert_eq!(stat_index["ok"], false);
+    assert_eq!(stat_index["fix"], "converge doctor --rebuild-index");
+    assert_eq!(std::fs::read(&index)?, b"not an index", "doctor wrote");
+
+    let rebuilt = report(&converge(
+        dir.path(),
+        home.path(),
+        &["--json", "doctor", "--rebuild-index", "--deep"],
+    ));
+    let stat_index = check(&rebuilt, "stat index");
+    assert_eq!(stat_index["ok"], true, "{stat_index}");
+    assert_eq!(stat_index["detail"], "1 files, 1 checked against disk");
+    Ok(())
+}
+
+/// Doc 15 §5f: a link between a primary and a worktree that no longer
+/// holds is reported from both ends, each with the verb that fixes it.
+#[test]
+fn a_dangling_worktree_link_is_reported_with_its_fix() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let home = tempfile::tempdir()?;
+    let main = tmp.path().join("main");
+    std::fs::create_dir(&main)?;
+    converge(&main, home.path(), &["init"]);
+    std::fs::write(main.join("a.txt"), "a")?;
+    converge(&main, home.path(), &["snap"]);
+    let out = converge(&main, home.path(), &["worktree", "add", "../wt"]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let wt = tmp.path().join("wt");
+    assert_eq!(std::fs::read_to_string(wt.join("a.txt"))?, "a");
+
+    let healthy = report(&converge(&main, home.path(), &["--json", "doctor"]));
+    assert_eq!(check(&healthy, "worktrees")["detail"], "1 linked");
+
+    // Forgotten by its primary: the worktree's own doctor says so.
+    std::fs::remove_file(main.join(".converge/worktrees/wt.json"))?;
+    let unlisted = report(&converge(&wt, home.path(), &["--json", "doctor"]));
+    let worktrees = check(&unlisted, "worktrees");
+    assert_eq!(worktrees["ok"], false);
+    assert_eq!(worktrees["fix"], "converge worktree repair");
+    assert!(
+        converge(&wt, home.path(), &["worktree", "repair"])
+            .status
+            .success()
+    );
+
+    // Gone from disk: the primary's doctor says so.
+    std::fs::remove_dir_all(&wt)?;
+    let dangling = report(&converge(&main, home.path(), &["--json", "doctor"]));
+    let worktrees = check(&dangling, "worktrees");
+    assert_eq!(worktrees["ok"], false, "{worktrees}");
+    assert!(
+        worktrees["fix"]
+            .as_str()
+            .unwrap_or("")
+            .starts_with("converge worktree prune")
+    );
+    assert!(
+        converge(&main, home.path(), &["worktree", "prune"])
+            .status
+            .success()
+    );
+    let pruned = report(&converge(&main, home.path(), &["--json", "doctor"]));
+    assert!(
+        pruned["checks"]
+            .as_array()
+            .expect("checks")
+            .iter()
+            .all(|c| c["name"] != "worktrees"),
+        "nothing is linked any more"
+    );
+    Ok(())
+}
+
 /// A diagnostic you cannot safely run when you are unsure is not one.
 #[test]
 fn doctor_changes_nothing() -> Result<()> {
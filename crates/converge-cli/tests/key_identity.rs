@@ -120,12 +120,32 @@ fn key_init_registers_a_public_half_and_keeps_the_private_one_sealed() -> Result
         assert_eq!(mode & 0o077, 0, "private key is group- or world-readable");
     }
 
+    // The signing half (doc 19 §12) gets the same treatment, in its own
+    // file so an older binary can still open the X25519 one.
+    let signing = home.path().join("keys").join(format!("{key_id}.sign.age"));
+    assert!(
+        std::fs::read(&signing)?.starts_with(b"age-encryption.org/"),
+        "the signing seed is not stored as an age file"
+    );
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&signing)?.permissions().mode();
+        assert_eq!(mode & 0o077, 0, "signing key is group- or world-readable");
+    }
+
     // The server stores the public half against the *token's* subject.
     let listed = json_data(&converge(ws, home.path(), &["--json", "key", "list"]));
     let repo_keys = listed["repo"].as_array().expect("repo keys");
     assert_eq!(repo_keys.len(), 1);
     assert_eq!(repo_keys[0]["subject"], "alice");
     assert_eq!(repo_keys[0]["key_id"], key_id.as_str());
+    assert!(
+        repo_keys[0]["signing_key"]
+            .as_str()
+            .is_some_and(|k| k.len() == 64),
+        "the verifying half is registered with the public one: {listed}"
+    );
     assert_eq!(
         listed["local"].as_array().map(Vec::len),
         Some(1),
@@ -173,6 +193,14 @@ fn a_wrong_passphrase_does_not_open_the_key() -> Result<()> {
     let right = age::secrecy::SecretString::from("correct horse battery staple".to_string());
     let pair = converge_client::identity::KeyPair::load_in(home.path(), Some(&key_id), &right)?;
     assert_eq!(pair.public.key_id, key_id);
+
+    // The signing half is sealed under the same passphrase.
+    assert!(
+        converge_client::identity::Signer::load_in(home.path(), Some(&key_id), &wrong).is_err(),
+        "a wrong passphrase must not open the signing key either"
+    );
+    let signer = converge_client::identity::Signer::load_in(home.path(), Some(&key_id), &right)?;
+    assert_eq!(signer.key_id(), key_id);
     Ok(())
 }
 
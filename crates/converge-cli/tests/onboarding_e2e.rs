@@ -690,6 +690,87 @@ fn a_repo_can_be_given_a_staged_gate_graph_from_the_cli() -> Result<()> {
         "an untouched field was reset"
     );
 
+    // Per-path rules, in the order given; a typo'd strategy in one is
+    // refused by the server's validation like the fallback's would be.
+    let edit_rules = |rules: &[&str]| {
+        let mut args = vec!["gates", "edit", "review", "--execute"];
+        for rule in rules {
+            args.extend(["--rule", rule]);
+        }
+        converge(ws.path(), &args)
+    };
+    assert!(
+        edit_rules(&["Cargo.lock=whole-file", "src/**=text-line-merge"])
+            .status
+            .success()
+    );
+    let graph = json_data(&converge(ws.path(), &["--json", "gates"]));
+    let rules = graph["gates"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|g| g["gate_id"] == "review")
+        .unwrap()["strategy_rules"]
+        .clone();
+    assert_eq!(rules[0]["pattern"], "Cargo.lock");
+    assert_eq!(rules[1]["strategy"], "text-line-merge");
+    let refused = edit_rules(&["*.lock=line-soup"]);
+    assert!(!refused.status.success());
+    assert!(
+        String::from_utf8_lossy(&refused.stderr).contains("line-soup"),
+        "{}",
+        String::from_utf8_lossy(&refused.stderr)
+    );
+
+    // lane-priority needs lanes to rank; given them, the order is kept.
+    let unranked = converge(
+        ws.path(),
+        &[
+            "gates",
+            "edit",
+            "review",
+            "--strategy",
+            "lane-priority",
+            "--execute",
+        ],
+    );
+    assert!(!unranked.status.success());
+    assert!(
+        String::from_utf8_lossy(&unranked.stderr).contains("ranks no lanes"),
+        "{}",
+        String::from_utf8_lossy(&unranked.stderr)
+    );
+    assert!(
+        converge(
+            ws.path(),
+            &[
+                "gates",
+                "edit",
+                "review",
+                "--strategy",
+                "lane-priority",
+                "--lane-priority",
+                "director",
+                "--lane-priority",
+                "contractor",
+                "--execute",
+            ],
+        )
+        .status
+        .success()
+    );
+    let graph = json_data(&converge(ws.path(), &["--json", "gates"]));
+    let review = graph["gates"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|g| g["gate_id"] == "review")
+        .unwrap();
+    assert_eq!(
+        review["lane_priority"],
+        serde_json::json!(["director", "contractor"])
+    );
+
     // Removing a gate also drops it from everyone's upstreams, since
     // otherwise the graph is refused for naming a gate that is gone --
     // true, but not the answer anybody wants.
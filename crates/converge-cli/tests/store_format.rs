@@ -30,24 +30,29 @@ fn a_fresh_workspace_is_stamped_with_the_current_format() -> Result<()> {
     assert!(converge(dir.path(), &["init"]).status.success());
     assert_eq!(
         std::fs::read_to_string(stamp(dir.path()))?.trim(),
-        "converge-workspace-1"
+        "converge-workspace-4"
     );
     Ok(())
 }
 
-/// A store written before the stamp existed reads as version 1, and
-/// opening it does not write one — `doctor` opens a workspace and is
-/// tested to change nothing (batch 22.1).
+/// A store written before the stamp existed reads as version 1, which
+/// this build no longer reads (snap records gained authors) —
+/// and refusing it does not write a stamp either: `doctor` opens a
+/// workspace and is tested to change nothing (batch 22.1).
 #[test]
-fn an_unstamped_workspace_still_works_and_stays_unstamped() -> Result<()> {
+fn an_unstamped_workspace_is_refused_and_stays_unstamped() -> Result<()> {
     let dir = tempfile::tempdir()?;
     assert!(converge(dir.path(), &["init"]).status.success());
     std::fs::remove_file(stamp(dir.path()))?;
 
     let out = converge(dir.path(), &["status"]);
     assert!(
-        out.status.success(),
-        "an unstamped store predates the stamp; it is version 1, not broken: {}",
+        !out.status.success(),
+        "an unstamped store is version 1, and this build reads 4"
+    );
+    assert!(
+        String::from_utf8_lossy(&out.stderr).contains("format 1"),
+        "{}",
         String::from_utf8_lossy(&out.stderr)
     );
     assert!(
@@ -152,6 +157,96 @@ fn doctor_reports_the_store_format() -> Result<()> {
         .find(|c| c["name"] == "store format")
         .expect("store format check");
     assert_eq!(format["ok"], true);
-    assert_eq!(format["detail"], "version 1");
+    assert_eq!(format["detail"], "version 4");
+    Ok(())
+}
+
+fn snap_id(dir: &Path, message: &str) -> Result<String> {
+    let out = converge(dir, &["--json", "snap", "-m", message]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let report: serde_json::Value = serde_json::from_slice(&out.stdout)?;
+    Ok(report["data"]["id"].as_str().expect("snap id").to_string())
+}
+
+/// Doc 16 §3: the way forward from the refusal. The refusal names the
+/// command, a dry run changes nothing, and the real run moves the stamp
+/// and keeps a backup of what it started from. A version-1 workspace's
+/// snaps are anonymous, which are still identified as they were then
+/// (doc 17 §1), so every id and HEAD come through unchanged.
+#[test]
+fn migrate_brings_a_version_one_workspace_forward() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    assert!(converge(dir.path(), &["init"]).status.success());
+    std::fs::write(dir.path().join("a.txt"), "one")?;
+    let first = snap_id(dir.path(), "first")?;
+    std::fs::write(dir.path().join("a.txt"), "two")?;
+    let second = snap_id(dir.path(), "second")?;
+    // What a version-1 build wrote: the same records, and no stamp.
+    std::fs::remove_file(stamp(dir.path()))?;
+
+    let out = converge(dir.path(), &["status"]);
+    assert!(!out.status.success());
+    let message = String::from_utf8_lossy(&out.stderr);
+    assert!(message.contains("converge migrate"), "{message}");
+
+    let out = converge(dir.path(), &["migrate", "--dry-run"]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let plan = String::from_utf8_lossy(&out.stdout);
+    assert!(
+        (1..4).all(|n| plan.contains(&format!("{n} -> {}", n + 1))),
+        "{plan}"
+    );
+    assert!(!stamp(dir.path()).exists(), "a dry run wrote the stamp");
+    assert!(!dir.path().join(".converge/backups").exists());
+
+    let out = converge(dir.path(), &["migrate"]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert_eq!(
+        std::fs::read_to_string(stamp(dir.path()))?.trim(),
+        "converge-workspace-4"
+    );
+    let converge_dir = dir.path().join(".converge");
+    assert_eq!(std::fs::read_to_string(converge_dir.join("HEAD"))?, second);
+    for id in [&first, &second] {
+        assert!(converge_dir.join(format!("snaps/{id}.json")).exists());
+        assert!(
+            converge_dir
+                .join(format!("backups/format-1/snaps/{id}.json"))
+                .exists(),
+            "the backup holds the store as it was"
+        );
+    }
+    assert!(!converge_dir.join("migrating").exists());
+
+    // The workspace opens again, and its lineage is intact.
+    let out = converge(dir.path(), &["--json", "history"]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let history: serde_json::Value = serde_json::from_slice(&out.stdout)?;
+    let listed: Vec<&str> = history["data"]
+        .as_array()
+        .expect("history")
+        .iter()
+        .map(|s| s["id"].as_str().expect("id"))
+        .collect();
+    assert_eq!(listed, vec![second.as_str(), first.as_str()]);
+
+    let out = converge(dir.path(), &["migrate"]);
+    assert!(String::from_utf8_lossy(&out.stdout).contains("already format 4"));
     Ok(())
 }
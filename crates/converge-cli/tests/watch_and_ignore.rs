@@ -56,88 +56,107 @@ fn watch_captures_only_a_settled_tree_and_only_once() -> Result<()> {
         "an unchanged tree is not recaptured"
     );
 
-    // Two ticks over a tree that keeps moving: with a real interval the
-    // loop must not capture a tree it has only seen once. `--once`
-    // bypasses the debounce deliberately (it is the test hook), so this
-    // drives the real loop with a short interval instead.
+    // A tree that keeps moving: the loop must not capture a state it
+    // has not seen settle. `--once` bypasses the debounce deliberately
+    // (it is the test hook), so this drives the real loop with a short
+    // interval instead — both the event-driven one and `--poll`.
+    for (round, flags) in [&[][..], &["--poll"][..]].into_iter().enumerate() {
+        let automatic = settled_captures(root, flags, round)?;
+        // However many ticks passed, history holds about one automatic
+        // snap for the settled state, not one per edit.
+        assert!(
+            (1..=3).contains(&automatic),
+            "expected a small number of automatic snaps for {flags:?}, got {automatic}"
+        );
+        assert_eq!(
+            std::fs::read_to_string(root.join("moving.txt"))?,
+            format!("edit {round}.5"),
+            "the file itself is untouched by capture"
+        );
+    }
+    Ok(())
+}
+
+/// Run `converge watch` over six quick edits of `moving.txt`, let it
+/// settle, and count the automatic snaps it added.
+fn settled_captures(root: &Path, flags: &[&str], round: usize) -> Result<usize> {
+    let automatic = || -> usize {
+        json_data(&converge(root, &["--json", "history"]))
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|s| s["trigger"] == "automatic")
+            .count()
+    };
+    let before = automatic();
     let mut child = Command::new(env!("CARGO_BIN_EXE_converge"))
         .current_dir(root)
         .args(["--json", "watch", "--interval-ms", "60"])
+        .args(flags)
         .stdout(std::process::Stdio::piped())
         .spawn()?;
+    // Give the watches time to land before the first edit.
+    std::thread::sleep(std::time::Duration::from_millis(200));
     for i in 0..6 {
-        std::fs::write(root.join("moving.txt"), format!("edit {i}"))?;
+        std::fs::write(root.join("moving.txt"), format!("edit {round}.{i}"))?;
         std::thread::sleep(std::time::Duration::from_millis(40));
     }
     // Let it settle, then stop.
     std::thread::sleep(std::time::Duration::from_millis(400));
     let _ = child.kill();
     let _ = child.wait();
-
-    // However many ticks passed, history holds one automatic snap for
-    // the settled state, not one per edit.
-    let history = json_data(&converge(root, &["--json", "history"]));
-    let automatic = history
-        .as_array()
-        .unwrap()
-        .iter()
-        .filter(|s| s["trigger"] == "automatic")
-        .count();
-    assert!(
-        (1..=3).contains(&automatic),
-        "expected a small number of automatic snaps, got {automatic}"
-    );
-    assert_eq!(
-        std::fs::read_to_string(root.join("moving.txt"))?,
-        "edit 5",
-        "the file itself is untouched by capture"
-    );
-    Ok(())
+    Ok(automatic() - before)
 }
 
-/// `.convergeignore` decides what a snap contains. Doc 18 §3 fixes its
-/// grammar deliberately narrow — exact names and `dir/` forms, no
-/// negation, no nesting — so the tests pin the non-features too.
+/// `.convergeignore` decides what a snap contains, with `.gitignore`'s
+/// grammar (doc 18 §3): globs, `**`, negation, `dir/` forms, anchoring.
 #[test]
-fn convergeignore_governs_capture_and_admits_its_limits() -> Result<()> {
+fn convergeignore_governs_capture_with_gitignore_grammar() -> Result<()> {
     let tmp = tempfile::tempdir()?;
     let root = tmp.path();
     let ws = Workspace::init(root, false)?;
 
     std::fs::create_dir_all(root.join("build/nested"))?;
-    std::fs::create_dir_all(root.join("src"))?;
+    std::fs::create_dir_all(root.join("src/build"))?;
+    std::fs::create_dir_all(root.join("docs"))?;
     std::fs::write(root.join("build/out.bin"), "artifact")?;
     std::fs::write(root.join("build/nested/deep.bin"), "artifact")?;
     std::fs::write(root.join("src/main.rs"), "fn main() {}")?;
+    std::fs::write(root.join("src/main.o"), "object")?;
+    std::fs::write(root.join("src/build/gen.rs"), "generated")?;
+    std::fs::write(root.join("docs/keep.log"), "wanted")?;
     std::fs::write(root.join("notes.txt"), "keep me")?;
     std::fs::write(root.join("scratch.tmp"), "drop me")?;
-    std::fs::write(root.join(".convergeignore"), "build/\nscratch.tmp\n")?;
+    std::fs::write(root.join("debug.log"), "drop me")?;
+    std::fs::write(
+        root.join(".convergeignore"),
+        "/build/\n*.tmp\n*.o\n**/*.log\n!docs/keep.log\n",
+    )?;
 
     let snap = ws.create_snap(Some("ignored".into()))?;
-    let names: Vec<String> = ws
-        .store
-        .get_manifest(&snap.root_manifest)?
-        .entries
-        .iter()
-        .map(|e| e.name.clone())
-        .collect();
-    assert!(
-        !names.contains(&"build".to_string()),
-        "a `dir/` rule excludes the whole directory: {names:?}"
-    );
-    assert!(
-        !names.contains(&"scratch.tmp".to_string()),
-        "an exact name is excluded: {names:?}"
-    );
-    assert!(
-        names.contains(&"src".to_string()) && names.contains(&"notes.txt".to_string()),
-        "everything else is captured: {names:?}"
-    );
-    assert!(
-        names.contains(&".convergeignore".to_string()),
-        "the ignore file itself is part of the tree — it is project \
-         configuration, and a teammate restoring the snap needs it"
-    );
+    let paths = tree_paths(&ws, &snap.root_manifest)?;
+    for gone in ["build", "scratch.tmp", "debug.log", "src/main.o"] {
+        assert!(
+            !paths.contains(&gone.to_string()),
+            "{gone} captured: {paths:?}"
+        );
+    }
+    for kept in [
+        "src/main.rs",
+        "notes.txt",
+        // `/build/` is anchored: only the root one goes.
+        "src/build/gen.rs",
+        // The later `!` rule wins.
+        "docs/keep.log",
+        // The ignore file itself is part of the tree — it is project
+        // configuration, and a teammate restoring the snap needs it.
+        ".convergeignore",
+    ] {
+        assert!(
+            paths.contains(&kept.to_string()),
+            "{kept} missing: {paths:?}"
+        );
+    }
 
     // Ignored paths are not deleted by a restore: they were never in the
     // snap, and restore materializes the snap.
@@ -146,72 +165,109 @@ fn convergeignore_governs_capture_and_admits_its_limits() -> Result<()> {
         root.join("build/out.bin").exists(),
         "restore removed an ignored path it never captured"
     );
+    assert!(root.join("scratch.tmp").exists());
 
-    // The documented non-features. These are not wildcards; they match
-    // literally, which is the contract doc 18 §3 states.
-    std::fs::write(root.join(".convergeignore"), "*.tmp\n!notes.txt\nsrc\n")?;
-    std::fs::write(root.join("other.tmp"), "still here")?;
-    let second = ws.create_snap(Some("literal".into()))?;
-    let names: Vec<String> = ws
-        .store
-        .get_manifest(&second.root_manifest)?
-        .entries
-        .iter()
-        .map(|e| e.name.clone())
-        .collect();
+    // `dir/` matches directories only; a file of that name is captured.
+    std::fs::write(root.join(".convergeignore"), "notes.txt/\nsrc\n")?;
+    let second = ws.create_snap(Some("dir-only".into()))?;
+    let paths = tree_paths(&ws, &second.root_manifest)?;
+    assert!(paths.contains(&"notes.txt".to_string()), "{paths:?}");
     assert!(
-        names.contains(&"other.tmp".to_string()),
-        "`*.tmp` is a literal name, not a glob: {names:?}"
-    );
-    assert!(
-        names.contains(&"notes.txt".to_string()),
-        "a `!` line is ignored entirely, so notes.txt stays captured"
-    );
-    assert!(
-        !names.contains(&"src".to_string()),
-        "a bare directory name excludes it, same as `src/`: {names:?}"
+        !paths.iter().any(|p| p.starts_with("src")),
+        "a bare directory name excludes it, same as `src/`: {paths:?}"
     );
     Ok(())
 }
 
-/// Ignore rules apply at the root only (doc 18 §3): a `.convergeignore`
-/// in a subdirectory is data, not configuration. Pinned because the
-/// opposite is what most tools do, and a silent difference is worse than
-/// a documented one.
+/// A `.convergeignore` in a subdirectory governs that subdirectory, its
+/// rules relative to it and ahead of its ancestors' — what `.gitignore`
+/// does, so an imported repo keeps its nested rules.
 #[test]
-fn nested_convergeignore_is_captured_not_obeyed() -> Result<()> {
+fn nested_convergeignore_governs_its_directory() -> Result<()> {
     let tmp = tempfile::tempdir()?;
     let root = tmp.path();
     let ws = Workspace::init(root, false)?;
 
-    std::fs::create_dir_all(root.join("sub"))?;
-    std::fs::write(root.join("sub/.convergeignore"), "secret.txt\n")?;
-    std::fs::write(root.join("sub/secret.txt"), "captured anyway")?;
+    std::fs::create_dir_all(root.join("sub/deeper"))?;
+    std::fs::write(root.join(".convergeignore"), "*.env\n")?;
+    std::fs::write(
+        root.join("sub/.convergeignore"),
+        "/secret.txt\n!shared.env\n",
+    )?;
+    std::fs::write(root.join("sub/secret.txt"), "ignored")?;
+    std::fs::write(root.join("sub/deeper/secret.txt"), "anchored rule: kept")?;
+    std::fs::write(root.join("sub/shared.env"), "re-included")?;
+    std::fs::write(root.join("sub/local.env"), "ignored by the root file")?;
+    std::fs::write(root.join("secret.txt"), "outside sub: kept")?;
 
     let snap = ws.create_snap(Some("nested".into()))?;
-    let root_manifest = ws.store.get_manifest(&snap.root_manifest)?;
-    let sub = root_manifest
-        .entries
-        .iter()
-        .find(|e| e.name == "sub")
-        .expect("sub captured");
-    let converge_client::model::ManifestEntryKind::Dir { manifest } = &sub.kind else {
-        panic!("sub is not a directory");
-    };
-    let names: Vec<String> = ws
-        .store
-        .get_manifest(manifest)?
-        .entries
-        .iter()
-        .map(|e| e.name.clone())
-        .collect();
-    assert!(
-        names.contains(&"secret.txt".to_string()),
-        "a nested ignore file must not filter: {names:?}"
-    );
+    let paths = tree_paths(&ws, &snap.root_manifest)?;
+    for gone in ["sub/secret.txt", "sub/local.env"] {
+        assert!(
+            !paths.contains(&gone.to_string()),
+            "{gone} captured: {paths:?}"
+        );
+    }
+    for kept in [
+        "sub/.convergeignore",
+        "sub/deeper/secret.txt",
+        "sub/shared.env",
+        "secret.txt",
+    ] {
+        assert!(
+            paths.contains(&kept.to_string()),
+            "{kept} missing: {paths:?}"
+        );
+    }
+
+    // The same walk answers `check-ignore`, naming the deciding rule.
+    let checked = json_data(&converge(
+        root,
+        &[
+            "--json",
+            "check-ignore",
+            "sub/secret.txt",
+            "sub/shared.env",
+            "secret.txt",
+            ".converge/config.json",
+        ],
+    ));
+    assert_eq!(checked[0]["ignored"], true);
+    assert_eq!(checked[0]["match"]["source"], "sub/.convergeignore");
+    assert_eq!(checked[0]["match"]["line"], 1);
+    assert_eq!(checked[1]["ignored"], false);
+    assert_eq!(checked[1]["match"]["pattern"], "!shared.env");
+    assert_eq!(checked[2]["ignored"], false);
+    assert!(checked[2]["match"].is_null());
+    assert_eq!(checked[3]["ignored"], true);
+    assert_eq!(checked[3]["match"]["source"], "built-in");
+
+    // Relative to the current directory, like git's.
+    let checked = json_data(&converge(
+        &root.join("sub"),
+        &["--json", "check-ignore", "local.env"],
+    ));
+    assert_eq!(checked[0]["path"], "sub/local.env");
+    assert_eq!(checked[0]["match"]["source"], ".convergeignore");
     Ok(())
 }
 
+/// Every path in a stored tree, `/`-separated, directories included.
+fn tree_paths(ws: &Workspace, root: &converge_client::model::ObjectId) -> Result<Vec<String>> {
+    let mut out = Vec::new();
+    let mut stack = vec![(String::new(), root.clone())];
+    while let Some((prefix, id)) = stack.pop() {
+        for entry in ws.store.get_manifest(&id)?.entries {
+            let path = format!("{prefix}{}", entry.name);
+            if let converge_client::model::ManifestEntryKind::Dir { manifest } = &entry.kind {
+                stack.push((format!("{path}/"), manifest.clone()));
+            }
+            out.push(path);
+        }
+    }
+    Ok(out)
+}
+
 /// Batch 22.4, from the first real project: ignore rules were matched
 /// only against the top level, so `target` excluded a root build
 /// directory and silently captured `crates/todo-core/target` — 18 MB and
@@ -41,6 +41,8 @@ pub(super) fn sig_for_kind(kind: &ManifestEntryKind) -> Result<EntrySig> {
         ManifestEntryKind::Superposition { variants } => Ok(EntrySig::Superposition {
             variants: variants.len(),
         }),
-        ManifestEntryKind::Dir { .. } => anyhow::bail!("dir entry should be handled by traversal"),
+        ManifestEntryKind::Dir { .. } | ManifestEntryKind::Page { .. } => {
+            anyhow::bail!("dir entry should be handled by traversal")
+        }
     }
 }
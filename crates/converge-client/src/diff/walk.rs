@@ -24,6 +24,11 @@ pub(super) fn walk_manifest(
         let path = join_path(prefix, &e.name);
         match &e.kind {
             ManifestEntryKind::Dir { manifest } => push_dir((manifest.clone(), path)),
+            // A page of this same directory (doc 16 §1b): its entries
+            // live under the directory's own prefix.
+            ManifestEntryKind::Page { manifest, .. } => {
+                push_dir((manifest.clone(), prefix.to_string()))
+            }
             other => {
                 out.insert(path, sig_for_kind(other)?);
             }
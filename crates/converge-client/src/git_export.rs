@@ -26,7 +26,8 @@ const MAP_FILE: &str = "git-map.json";
 /// git repo at `git_workdir`. Incremental: snaps already in the mapping
 /// table are reused as parents, not re-exported. Mirror branches are
 /// force-moved (doc 18: snapshot semantics, read-only for git users).
-/// Who the mirrored commits are attributed to.
+/// Who runs the export: the committer of every mirrored commit, and the
+/// author of any snap that names none.
 ///
 /// A mirror is a git artifact, so git's own identity is the right source:
 /// it makes `git log --author`, `git blame` and forge attribution work on
@@ -34,12 +35,9 @@ const MAP_FILE: &str = "git-map.json";
 /// Batch 22.4 found every commit authored `Converge <converge@local>` in
 /// a repo with two identities.
 ///
-/// The honest limit: this is whoever runs the export, applied to the
-/// whole lineage. A local snap record carries no author -- identity is
-/// attached at publish, server-side -- so per-snap attribution is not
-/// available to ask for. That is right for the ordinary case of one
-/// person's workspace and wrong for a history that mixes authors; fixing
-/// it properly means putting an author on the snap record.
+/// Snaps now carry their author (doc 17 §1), so a history that mixes
+/// people exports as one; see [`snap_author`]. This identity fills in
+/// for snaps captured before login or imported from git.
 fn git_identity(git_workdir: &Path) -> (String, String) {
     let get = |key: &str| -> Option<String> {
         let out = Command::new("git")
@@ -97,7 +95,7 @@ pub fn export_lineage(
         }
     }
 
-    let author = git_identity(git_workdir);
+    let committer = git_identity(git_workdir);
     let mut map = load_map(store)?;
 
     // Lineage oldest-first (parents before children), tolerating thinned
@@ -142,7 +140,7 @@ pub fn export_lineage(
             export_ref,
             mark_of: &mark_of,
             map: &map,
-            author: author.clone(),
+            committer: committer.clone(),
         };
         write_commit(&ctx, &mut stream, snap, mark)?;
     }
@@ -211,7 +209,24 @@ struct CommitContext<'a> {
     mark_of: &'a BTreeMap<String, usize>,
     /// Snaps mirrored by an earlier export, by git sha.
     map: &'a BTreeMap<String, String>,
-    author: (String, String),
+    committer: (String, String),
+}
+
+/// The git author for one snap: its display name, with its subject as
+/// the email. Subjects are often not addresses, and that is fine — git
+/// treats the email as an opaque identifier, and the subject is the one
+/// string that stays the same person across machines.
+///
+/// Anything fast-import cannot carry falls back to `committer` whole,
+/// rather than half of one identity paired with half of another.
+fn snap_author(snap: &SnapRecord, committer: &(String, String)) -> (String, String) {
+    let usable = |value: &str| !value.is_empty() && !value.contains(['\n', '<', '>']);
+    match &snap.author {
+        Some(author) if usable(&author.display_name) && usable(&author.subject) => {
+            (author.display_name.clone(), author.subject.clone())
+        }
+        _ => committer.clone(),
+    }
 }
 
 fn write_commit(
@@ -225,8 +240,10 @@ fn write_commit(
         export_ref,
         mark_of,
         map,
-        author: (author_name, author_email),
+        committer,
     } = ctx;
+    let (author_name, author_email) = snap_author(snap, committer);
+    let (committer_name, committer_email) = committer;
     let epoch = time::OffsetDateTime::parse(
         &snap.created_at,
         &time::format_description::well_known::Rfc3339,
@@ -242,6 +259,9 @@ fn write_commit(
     if let Some(candidate) = &snap.derived_from_candidate {
         message.push_str(&format!("Converge-Derived-From-Candidate: {candidate}\n"));
     }
+    if let Some(replayed) = &snap.replayed_from {
+        message.push_str(&format!("Converge-Replayed-From: {replayed}\n"));
+    }
     let thinned = snap
         .parents
         .iter()
@@ -257,7 +277,7 @@ fn write_commit(
         format!("author {author_name} <{author_email}> {epoch} +0000\n").as_bytes(),
     );
     stream.extend_from_slice(
-        format!("committer {author_name} <{author_email}> {epoch} +0000\n").as_bytes(),
+        format!("committer {committer_name} <{committer_email}> {epoch} +0000\n").as_bytes(),
     );
     stream.extend_from_slice(format!("data {}\n{message}\n", message.len() + 1).as_bytes());
 
@@ -286,7 +306,7 @@ fn emit_tree(
     manifest_id: &ObjectId,
     prefix: &str,
 ) -> Result<()> {
-    let manifest = store.get_manifest(manifest_id)?;
+    let manifest = store.get_dir(manifest_id)?;
     for entry in manifest.entries {
         let path = if prefix.is_empty() {
             entry.name.clone()
@@ -315,6 +335,7 @@ fn emit_tree(
             ManifestEntryKind::Superposition { .. } => {
                 bail!("superposition reached export stream (ensure_exportable missed it)")
             }
+            ManifestEntryKind::Page { .. } => unreachable!("get_dir reads pages through"),
         }
     }
     Ok(())
@@ -373,7 +394,7 @@ fn emit_file(stream: &mut Vec<u8>, path: &str, mode: u32, content: &[u8]) {
 
 /// Doc 18 §2: superposed trees refuse to export.
 fn ensure_exportable(store: &LocalStore, manifest_id: &ObjectId) -> Result<()> {
-    let manifest: Manifest = store.get_manifest(manifest_id)?;
+    let manifest: Manifest = store.get_dir(manifest_id)?;
     for entry in &manifest.entries {
         match &entry.kind {
             ManifestEntryKind::Superposition { .. } => {
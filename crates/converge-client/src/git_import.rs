@@ -132,7 +132,7 @@ fn snap_of_tree(
     let mut stats = SnapStats::default();
     let root_manifest = workspace.build_manifest_of(tree, &mut stats)?;
     let parents: Vec<String> = parent.into_iter().collect();
-    let id = compute_snap_id(&root_manifest, &parents, None);
+    let id = compute_snap_id(&root_manifest, &parents, None, None, None);
     if workspace.store.has_snap(&id) {
         return workspace.store.get_snap(&id);
     }
@@ -145,6 +145,8 @@ fn snap_of_tree(
         root_manifest,
         parents,
         derived_from_candidate: None,
+        author: None,
+        replayed_from: None,
         message: Some(message.to_string()),
         trigger: "explicit".to_string(),
         stats,
@@ -153,33 +155,35 @@ fn snap_of_tree(
     Ok(snap)
 }
 
-/// Root `.gitignore` -> `.convergeignore` (doc 18 §3): simple patterns
-/// only; negations and comments dropped. Never overwrites an existing
-/// `.convergeignore`.
+/// Every tracked `.gitignore` -> a `.convergeignore` beside it (doc 18
+/// §3). The grammars are the same, so rules are copied whole, negations
+/// and nesting included. Never overwrites an existing `.convergeignore`.
 fn translate_gitignore(root: &Path) -> Result<bool> {
-    let target = root.join(".convergeignore");
-    if target.exists() {
-        return Ok(false);
-    }
-    let Ok(source) = std::fs::read_to_string(root.join(".gitignore")) else {
-        return Ok(false);
-    };
-    let lines: Vec<&str> = source
-        .lines()
-        .map(str::trim)
-        .filter(|l| !l.is_empty() && !l.starts_with('#') && !l.starts_with('!'))
-        .collect();
-    if lines.is_empty() {
-        return Ok(false);
+    let listed = git_out(root, &["ls-files", "-z", "--", ":(glob)**/.gitignore"])?;
+    let mut translated = false;
+    for source in listed.split('\0').filter(|p| !p.is_empty()) {
+        let source = root.join(source);
+        let Some(dir) = source.parent() else {
+            continue;
+        };
+        let target = dir.join(".convergeignore");
+        if target.exists() {
+            continue;
+        }
+        let Ok(rules) = std::fs::read_to_string(&source) else {
+            continue;
+        };
+        if rules.trim().is_empty() {
+            continue;
+        }
+        std::fs::write(
+            &target,
+            format!("# generated from .gitignore by `converge git import`\n{rules}"),
+        )
+        .with_context(|| format!("write {}", target.display()))?;
+        translated = true;
     }
-    std::fs::write(
-        &target,
-        format!(
-            "# generated from .gitignore by `converge git import`\n{}\n",
-            lines.join("\n")
-        ),
-    )?;
-    Ok(true)
+    Ok(translated)
 }
 
 fn git_out(workdir: &Path, args: &[&str]) -> Result<String> {
@@ -7,6 +7,12 @@
 //! Keys live under the *user's* home, not the workspace: an identity is
 //! a person, not a checkout, and a second workspace must not mean a
 //! second identity that existing secrets were never encrypted to.
+//!
+//! Each key made since doc 19 §12 also has an ed25519 signing half,
+//! sealed in its own file under the same passphrase. Own file, because
+//! an older binary must still be able to open the X25519 half, and
+//! because opening a key for secrets should not pay for decrypting the
+//! signing seed too.
 
 use std::path::{Path, PathBuf};
 
@@ -14,6 +20,9 @@ use age::secrecy::{ExposeSecret, SecretString};
 use anyhow::{Context, Result, anyhow};
 use serde::{Deserialize, Serialize};
 
+use crate::model::PublishSignature;
+use crate::model::signing::{self, from_hex, to_hex};
+
 /// Where personal keys live. `CONVERGE_HOME` overrides, which is what
 /// makes this testable without touching a developer's real keys.
 pub fn converge_home() -> Result<PathBuf> {
@@ -41,6 +50,10 @@ pub struct PublicKey {
     /// Free-text hint, usually the machine the key was made on.
     pub label: String,
     pub created_at: String,
+    /// Hex ed25519 verifying key. Absent for a key made before signing
+    /// existed; such a key can still seal and open secrets.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signing_key: Option<String>,
 }
 
 /// Local index of this machine's keys, so `key list` and key selection
@@ -89,24 +102,26 @@ impl KeyPair {
     ) -> Result<Self> {
         let identity = age::x25519::Identity::generate();
         let public_key = identity.to_public().to_string();
+        let mut seed = [0u8; 32];
+        getrandom::fill(&mut seed).map_err(|err| anyhow!("read system randomness: {err}"))?;
+        let signing = ed25519_dalek::SigningKey::from_bytes(&seed);
         let public = PublicKey {
             key_id: key_id_for(&public_key),
             public_key,
             label: label.to_string(),
             created_at: now.to_string(),
+            signing_key: Some(to_hex(signing.verifying_key().as_bytes())),
         };
 
         let secret = identity.to_string();
-        let sealed = age::encrypt(
-            &age::scrypt::Recipient::new(passphrase.clone()),
-            secret.expose_secret().as_bytes(),
-        )
-        .map_err(|err| anyhow!("encrypt private key: {err}"))?;
+        let sealed = seal_private(passphrase, secret.expose_secret().as_bytes())?;
+        let sealed_seed = seal_private(passphrase, to_hex(&seed).as_bytes())?;
+        seed.fill(0);
 
         let dir = keys_dir_in(home);
         std::fs::create_dir_all(&dir).with_context(|| format!("create {}", dir.display()))?;
-        let path = dir.join(format!("{}.age", public.key_id));
-        write_private(&path, &sealed)?;
+        write_private(&dir.join(format!("{}.age", public.key_id)), &sealed)?;
+        write_private(&signing_path(home, &public.key_id), &sealed_seed)?;
 
         let mut index = load_index(home)?;
         index.version = 1;
@@ -122,25 +137,9 @@ impl KeyPair {
     }
 
     pub fn load_in(home: &Path, key_id: Option<&str>, passphrase: &SecretString) -> Result<Self> {
-        let index = load_index(home)?;
-        let public = match key_id {
-            Some(id) => index
-                .keys
-                .iter()
-                .find(|k| k.key_id == id)
-                .ok_or_else(|| anyhow!("no local key {id}; run `converge key init`"))?,
-            None => index.keys.last().ok_or_else(|| {
-                anyhow!("no personal key on this machine; run `converge key init`")
-            })?,
-        }
-        .clone();
-
+        let public = find_key(home, key_id)?;
         let path = keys_dir_in(home).join(format!("{}.age", public.key_id));
-        let sealed =
-            std::fs::read(&path).with_context(|| format!("read private key {}", path.display()))?;
-        let plaintext = age::decrypt(&age::scrypt::Identity::new(passphrase.clone()), &sealed)
-            .map_err(|_| anyhow!("wrong passphrase for key {}", public.key_id))?;
-        let secret = String::from_utf8(plaintext).context("private key is not utf-8")?;
+        let secret = open_private(&path, &public.key_id, passphrase)?;
         let identity: age::x25519::Identity = secret
             .trim()
             .parse()
@@ -167,6 +166,101 @@ impl KeyPair {
     }
 }
 
+/// The signing half of a personal key, opened (doc 19 §12).
+pub struct Signer {
+    key_id: String,
+    key: ed25519_dalek::SigningKey,
+}
+
+impl Signer {
+    /// Open the signing half of a key by id, or of the newest one.
+    pub fn load(key_id: Option<&str>, passphrase: &SecretString) -> Result<Self> {
+        Self::load_in(&converge_home()?, key_id, passphrase)
+    }
+
+    pub fn load_in(home: &Path, key_id: Option<&str>, passphrase: &SecretString) -> Result<Self> {
+        let public = find_key(home, key_id)?;
+        let Some(verifying) = &public.signing_key else {
+            anyhow::bail!(
+                "key {} was made before signing existed and cannot sign; \
+                 run `converge key rotate` for one that can",
+                public.key_id
+            );
+        };
+        let path = signing_path(home, &public.key_id);
+        let seed = open_private(&path, &public.key_id, passphrase)?;
+        let seed: [u8; 32] = from_hex(seed.trim())
+            .and_then(|bytes| bytes.try_into().ok())
+            .with_context(|| format!("signing key {} is malformed", public.key_id))?;
+        let key = ed25519_dalek::SigningKey::from_bytes(&seed);
+        // The same swap check as the X25519 half.
+        if &to_hex(key.verifying_key().as_bytes()) != verifying {
+            anyhow::bail!(
+                "signing key {} does not match its recorded public half",
+                public.key_id
+            );
+        }
+        Ok(Self {
+            key_id: public.key_id,
+            key,
+        })
+    }
+
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    /// Hex ed25519 signature over `payload`.
+    pub fn sign(&self, payload: &[u8]) -> String {
+        use ed25519_dalek::Signer as _;
+        to_hex(&self.key.sign(payload).to_bytes())
+    }
+
+    /// Both signatures a signed publish carries: over the snap id, and
+    /// over the request itself.
+    pub fn sign_publish(&self, request: &crate::model::PublishRequest) -> PublishSignature {
+        PublishSignature {
+            key_id: self.key_id.clone(),
+            snap: self.sign(&signing::snap_payload(&request.snap.id)),
+            publish: self.sign(&signing::publish_payload(&request.into())),
+        }
+    }
+}
+
+/// A key from the local index, by id or the newest.
+fn find_key(home: &Path, key_id: Option<&str>) -> Result<PublicKey> {
+    let index = load_index(home)?;
+    let found = match key_id {
+        Some(id) => index
+            .keys
+            .iter()
+            .find(|k| k.key_id == id)
+            .ok_or_else(|| anyhow!("no local key {id}; run `converge key init`"))?,
+        None => index
+            .keys
+            .last()
+            .ok_or_else(|| anyhow!("no personal key on this machine; run `converge key init`"))?,
+    };
+    Ok(found.clone())
+}
+
+fn signing_path(home: &Path, key_id: &str) -> PathBuf {
+    keys_dir_in(home).join(format!("{key_id}.sign.age"))
+}
+
+fn seal_private(passphrase: &SecretString, plaintext: &[u8]) -> Result<Vec<u8>> {
+    age::encrypt(&age::scrypt::Recipient::new(passphrase.clone()), plaintext)
+        .map_err(|err| anyhow!("encrypt private key: {err}"))
+}
+
+fn open_private(path: &Path, key_id: &str, passphrase: &SecretString) -> Result<String> {
+    let sealed =
+        std::fs::read(path).with_context(|| format!("read private key {}", path.display()))?;
+    let plaintext = age::decrypt(&age::scrypt::Identity::new(passphrase.clone()), &sealed)
+        .map_err(|_| anyhow!("wrong passphrase for key {key_id}"))?;
+    String::from_utf8(plaintext).context("private key is not utf-8")
+}
+
 /// Every key this machine holds, newest last. Public data only.
 pub fn local_keys() -> Result<Vec<PublicKey>> {
     local_keys_in(&converge_home()?)
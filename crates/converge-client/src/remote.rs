@@ -16,11 +16,17 @@ pub struct RemoteClient {
     batch_cap: usize,
     /// Optional transfer reporter (batch 16.4, audit P4.20).
     progress: Option<std::sync::Arc<dyn Fn(Progress) + Send + Sync>>,
+    /// Signs publishes when set (doc 19 §12).
+    signer: Option<std::sync::Arc<crate::identity::Signer>>,
 }
 
 /// Transfer progress, reported once per batch — the granularity the
 /// wire actually moves in, and the one that matters for the beachhead's
 /// large binaries (audit P4.20).
+///
+/// The total is an object count. Bytes are only ever counted as done:
+/// an upload reads each object as its batch fills and a download learns
+/// sizes as they arrive, so neither knows the byte total up front.
 #[derive(Clone, Copy, Debug)]
 pub struct Progress {
     /// "upload" or "download".
@@ -28,7 +34,6 @@ pub struct Progress {
     pub objects_done: usize,
     pub objects_total: usize,
     pub bytes_done: u64,
-    pub bytes_total: u64,
 }
 
 /// What one probe of the server found (g02.022 batch 22.1).
@@ -93,14 +98,22 @@ impl RemoteClient {
 mod candidates;
 mod identity;
 mod lanes;
+mod lazy;
 mod members;
 mod secrets;
 mod transport;
 
+pub use lazy::RemoteBlobs;
+
 #[derive(Debug)]
 pub struct UploadStats {
     pub negotiated_manifests: usize,
     pub uploaded: usize,
+    /// Plain size of the objects uploaded.
+    pub logical_bytes: u64,
+    /// What they took on the wire; less than `logical_bytes` when the
+    /// server accepted encoded frames (doc 16 §1f).
+    pub sent_bytes: u64,
 }
 
 /// All manifest ids reachable from `root`, root first.
@@ -195,7 +208,11 @@ fn collect_kinds(
             ManifestEntryKind::FileChunks { recipe, .. } => {
                 recipes.insert(recipe.clone());
             }
-            ManifestEntryKind::Dir { manifest } => dirs.push(manifest.clone()),
+            // Pages travel like subdirectories (doc 16 §1b): child
+            // manifests, negotiated and fetched in the same waves.
+            ManifestEntryKind::Dir { manifest } | ManifestEntryKind::Page { manifest, .. } => {
+                dirs.push(manifest.clone())
+            }
             ManifestEntryKind::Symlink { .. } => {}
             ManifestEntryKind::Superposition { variants } => {
                 for variant in variants {
@@ -208,7 +225,8 @@ fn collect_kinds(
                         }
                         SuperpositionVariantKind::Dir { manifest } => dirs.push(manifest.clone()),
                         SuperpositionVariantKind::Symlink { .. }
-                        | SuperpositionVariantKind::Tombstone => {}
+                        | SuperpositionVariantKind::Tombstone
+                        | SuperpositionVariantKind::MovedTo { .. } => {}
                     }
                 }
             }
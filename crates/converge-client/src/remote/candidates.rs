@@ -1,11 +1,11 @@
-//! Candidates, releases, inbox, events, retention, GC.
+//! Candidates, releases, inbox, events, retention, signing policy, GC.
 
 use anyhow::{Context, Result};
 
 use converge_model::{
     ApproveRequest, CandidateRecord, EventRecord, InboxReport, ObjectId, PromoteRequest,
-    PublishRequest, ReleaseRecord, ReleaseRequest, RetentionPolicy, SnapRecord, VerifyReport,
-    WIRE_VERSION,
+    PublishRequest, ReleaseRecord, ReleaseRequest, RetentionPolicy, SigningPolicy, SnapRecord,
+    VerifyReport, WIRE_VERSION,
 };
 
 use crate::store::LocalStore;
@@ -28,20 +28,25 @@ impl RemoteClient {
         notes: Option<String>,
     ) -> Result<(CandidateRecord, UploadStats)> {
         let stats = self.upload_tree(store, repo_id, &snap.root_manifest)?;
+        let mut request = PublishRequest {
+            wire_version: WIRE_VERSION,
+            repo_id: repo_id.into(),
+            scope_id: scope_id.into(),
+            gate_id: gate_id.into(),
+            snap: snap.clone(),
+            base_candidate_id,
+            lane_id,
+            notes,
+            signature: None,
+        };
+        if let Some(signer) = &self.signer {
+            request.signature = Some(signer.sign_publish(&request));
+        }
         let response = Self::check(
             self.http
                 .post(self.url("/api/publish"))
                 .bearer_auth(&self.token)
-                .json(&PublishRequest {
-                    wire_version: WIRE_VERSION,
-                    repo_id: repo_id.into(),
-                    scope_id: scope_id.into(),
-                    gate_id: gate_id.into(),
-                    snap: snap.clone(),
-                    base_candidate_id,
-                    lane_id,
-                    notes,
-                })
+                .json(&request)
                 .send()
                 .context("publish")?,
         )?;
@@ -75,6 +80,23 @@ impl RemoteClient {
         Ok(root)
     }
 
+    /// [`Self::fetch_candidate`], but only the tree's shape: manifests
+    /// and recipes arrive now, and every missing blob is promised and
+    /// fetched when first read (doc 16 §1g).
+    pub fn fetch_candidate_lazy(
+        &self,
+        store: &LocalStore,
+        repo_id: &str,
+        candidate_id: &str,
+    ) -> Result<ObjectId> {
+        let candidate = self.get_candidate(candidate_id)?;
+        let root = candidate
+            .root_manifest
+            .context("candidate has no root manifest")?;
+        self.fetch_manifest_tree_with(store, repo_id, &root, true)?;
+        Ok(root)
+    }
+
     /// Poll the event feed after `since` (doc 14 §5b: hints, not truth).
     /// One page of the event feed. `EventPage::gap` is true when pruning
     /// removed events this cursor never saw — reconcile via inbox/status
@@ -263,6 +285,29 @@ impl RemoteClient {
         Ok(())
     }
 
+    pub fn get_signing_policy(&self, repo_id: &str) -> Result<SigningPolicy> {
+        let response = Self::check(
+            self.http
+                .get(self.url(&format!("/api/repos/{repo_id}/signing")))
+                .bearer_auth(&self.token)
+                .send()
+                .context("get signing policy")?,
+        )?;
+        response.json().context("parse signing policy")
+    }
+
+    pub fn set_signing_policy(&self, repo_id: &str, policy: &SigningPolicy) -> Result<()> {
+        Self::check(
+            self.http
+                .put(self.url(&format!("/api/repos/{repo_id}/signing")))
+                .bearer_auth(&self.token)
+                .json(policy)
+                .send()
+                .context("set signing policy")?,
+        )?;
+        Ok(())
+    }
+
     pub fn promote(
         &self,
         candidate_id: &str,
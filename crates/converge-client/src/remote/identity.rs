@@ -7,11 +7,13 @@ use super::Probe;
 use super::RemoteClient;
 
 impl RemoteClient {
-    /// Register a public key for the calling subject (batch 19.1).
+    /// Register a public key for the calling subject (batch 19.1), with
+    /// its verifying half when it has one (doc 19 §12).
     pub fn register_key(
         &self,
         repo_id: &str,
         public_key: &str,
+        signing_key: Option<&str>,
         label: &str,
     ) -> Result<crate::model::PublicKeyRecord> {
         let response = Self::check(
@@ -21,6 +23,7 @@ impl RemoteClient {
                 .json(&crate::model::RegisterKeyRequest {
                     public_key: public_key.into(),
                     label: label.into(),
+                    signing_key: signing_key.map(str::to_string),
                 })
                 .send()
                 .context("register key")?,
@@ -103,6 +106,19 @@ impl RemoteClient {
         response.json().context("parse auth config")
     }
 
+    /// The subject this client's token resolves to — what a snap
+    /// captured here is authored as, and what upload checks it against.
+    pub fn whoami(&self) -> Result<crate::model::WhoAmI> {
+        let response = Self::check(
+            self.http
+                .get(self.url("/api/auth/whoami"))
+                .bearer_auth(&self.token)
+                .send()
+                .context("ask who the token belongs to")?,
+        )?;
+        response.json().context("parse whoami")
+    }
+
     /// Trade a provider-issued identity token for a Convergence one.
     pub fn exchange_identity(base_url: &str, id_token: &str) -> Result<crate::model::TokenIssued> {
         let url = format!("{}/api/auth/exchange", base_url.trim_end_matches('/'));
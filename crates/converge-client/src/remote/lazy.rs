@@ -0,0 +1,66 @@
+//! The remote as a [`BlobSource`]: promised blobs read on first use
+//! (doc 16 §1g).
+
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+
+use converge_model::ObjectId;
+
+use crate::store::{BlobSource, LocalStore};
+
+use super::RemoteClient;
+
+/// Fetches promised blobs from the workspace's configured remote.
+///
+/// Connecting waits for the first fetch: the token is decrypted with
+/// scrypt, and most verbs read no promised blob at all.
+pub struct RemoteBlobs {
+    store: LocalStore,
+    client: Mutex<Option<(RemoteClient, String)>>,
+}
+
+impl RemoteBlobs {
+    /// A source for `store`'s remote. `store` should carry no source of
+    /// its own; only its config and tokens are read.
+    pub fn new(store: LocalStore) -> Self {
+        Self {
+            store,
+            client: Mutex::new(None),
+        }
+    }
+
+    /// A source over a client already open on `repo_id`.
+    pub fn connected(store: LocalStore, client: RemoteClient, repo_id: &str) -> Self {
+        Self {
+            store,
+            client: Mutex::new(Some((client, repo_id.to_string()))),
+        }
+    }
+
+    fn connect(&self) -> Result<(RemoteClient, String)> {
+        let remote = self
+            .store
+            .read_config()?
+            .remote
+            .context("no remote configured; run `converge login` first")?;
+        let token = self
+            .store
+            .get_remote_token(&remote)?
+            .context("no token stored for this remote; run `converge login` again")?;
+        Ok((RemoteClient::new(&remote.base_url, &token), remote.repo_id))
+    }
+}
+
+impl BlobSource for RemoteBlobs {
+    fn fetch_blobs(&self, ids: &[ObjectId]) -> Result<Vec<Vec<u8>>> {
+        let (client, repo_id) = {
+            let mut cached = self.client.lock().expect("remote blobs lock");
+            if cached.is_none() {
+                *cached = Some(self.connect()?);
+            }
+            cached.clone().expect("connected above")
+        };
+        client.get_blobs(&repo_id, ids.to_vec())
+    }
+}
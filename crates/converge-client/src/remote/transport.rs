@@ -5,7 +5,8 @@ use anyhow::{Context, Result, bail};
 use std::collections::BTreeSet;
 
 use converge_model::{
-    NegotiateRequest, NegotiateResponse, ObjectFrame, ObjectId, ObjectSet, WIRE_VERSION,
+    NegotiateRequest, NegotiateResponse, ObjectEncoding, ObjectFrame, ObjectId, ObjectSet,
+    WIRE_VERSION,
 };
 
 use crate::store::LocalStore;
@@ -26,6 +27,7 @@ impl RemoteClient {
             http: reqwest::blocking::Client::new(),
             batch_cap: 8 * 1024 * 1024,
             progress: None,
+            signer: None,
         }
     }
 
@@ -36,6 +38,14 @@ impl RemoteClient {
         self
     }
 
+    /// Sign every publish with `signer` (doc 19 §12). Off by default:
+    /// opening the key costs a passphrase, which only the caller can ask
+    /// for.
+    pub fn with_signer(mut self, signer: crate::identity::Signer) -> Self {
+        self.signer = Some(std::sync::Arc::new(signer));
+        self
+    }
+
     pub(crate) fn report(&self, progress: Progress) {
         if let Some(sink) = &self.progress {
             sink(progress);
@@ -48,12 +58,21 @@ impl RemoteClient {
         self
     }
 
-    /// Upload frames in cap-split batches (doc 16 §1c).
-    fn put_frames(&self, repo_id: &str, frames: Vec<ObjectFrame>) -> Result<()> {
-        let objects_total = frames.len();
-        let bytes_total: u64 = frames.iter().map(|f| f.bytes.len() as u64).sum();
+    /// Upload frames in cap-split batches (doc 16 §1c), reading each one
+    /// only when its batch is being filled: what is held at once is one
+    /// batch and one frame, not everything missing (doc 16 §2). Returns
+    /// the logical and the sent bytes.
+    fn put_frames(
+        &self,
+        repo_id: &str,
+        objects_total: usize,
+        frames: impl Iterator<Item = Result<(ObjectFrame, u64)>>,
+    ) -> Result<(u64, u64)> {
+        // As on the way down, the total is object count: the sizes are
+        // only known once each object is read.
         let mut objects_done = 0usize;
         let mut bytes_done = 0u64;
+        let mut logical_bytes = 0u64;
 
         let mut batch: Vec<ObjectFrame> = Vec::new();
         let mut batch_bytes = 0usize;
@@ -81,12 +100,13 @@ impl RemoteClient {
                 objects_done: *objects_done,
                 objects_total,
                 bytes_done: *bytes_done,
-                bytes_total,
             });
             batch.clear();
             Ok(())
         };
         for frame in frames {
+            let (frame, len) = frame?;
+            logical_bytes += len;
             if (batch_bytes + frame.bytes.len() > self.batch_cap || batch.len() >= MAX_BATCH_FRAMES)
                 && !batch.is_empty()
             {
@@ -96,11 +116,14 @@ impl RemoteClient {
             batch_bytes += frame.bytes.len();
             batch.push(frame);
         }
-        flush(&mut batch, &mut objects_done, &mut bytes_done)
+        flush(&mut batch, &mut objects_done, &mut bytes_done)?;
+        Ok((logical_bytes, bytes_done))
     }
 
     /// Download a set of objects as CBOR frames, splitting requests above
-    /// the server's id cap (doc 16 §1c).
+    /// the server's id cap (doc 16 §1c). Frames come back decoded: the
+    /// server may encode any it likes once asked (doc 16 §1f), and an
+    /// older one ignores the asking.
     fn get_frames(&self, repo_id: &str, request: &ObjectSet) -> Result<Vec<ObjectFrame>> {
         // Total is object count, not bytes: on the way down the sizes are
         // exactly what has not arrived yet.
@@ -110,23 +133,31 @@ impl RemoteClient {
         for chunk in split_object_set(request, MAX_BATCH_FRAMES) {
             let response = Self::check(
                 self.http
-                    .post(self.url(&format!("/api/repos/{repo_id}/objects/batch-get")))
+                    .post(self.url(&format!(
+                        "/api/repos/{repo_id}/objects/batch-get?accept={}",
+                        ObjectEncoding::Zstd.as_str()
+                    )))
                     .bearer_auth(&self.token)
                     .json(&chunk)
                     .send()
                     .context("download batch")?,
             )?;
             let bytes = response.bytes().context("read batch body")?;
-            let mut decoded: Vec<ObjectFrame> =
+            let decoded: Vec<ObjectFrame> =
                 ciborium::from_reader(bytes.as_ref()).context("decode batch")?;
             bytes_done += decoded.iter().map(|f| f.bytes.len() as u64).sum::<u64>();
-            frames.append(&mut decoded);
+            for frame in decoded {
+                let (kind, id) = (frame.kind.clone(), frame.id.clone());
+                let object = frame
+                    .into_object()
+                    .with_context(|| format!("decode {kind} {}", id.as_str()))?;
+                frames.push(ObjectFrame::plain(&kind, id, object));
+            }
             self.report(Progress {
                 phase: "download",
                 objects_done: frames.len(),
                 objects_total,
                 bytes_done,
-                bytes_total: bytes_done,
             });
         }
         Ok(frames)
@@ -166,6 +197,10 @@ impl RemoteClient {
     }
 
     pub fn negotiate(&self, repo_id: &str, objects: ObjectSet) -> Result<ObjectSet> {
+        Ok(self.negotiate_response(repo_id, objects)?.missing)
+    }
+
+    fn negotiate_response(&self, repo_id: &str, objects: ObjectSet) -> Result<NegotiateResponse> {
         let response = Self::check(
             self.http
                 .post(self.url(&format!("/api/repos/{repo_id}/negotiate")))
@@ -177,8 +212,7 @@ impl RemoteClient {
                 .send()
                 .context("negotiate")?,
         )?;
-        let parsed: NegotiateResponse = response.json().context("parse negotiate response")?;
-        Ok(parsed.missing)
+        response.json().context("parse negotiate response")
     }
 
     /// Upload everything reachable from `root_manifest` that the server does
@@ -191,17 +225,19 @@ impl RemoteClient {
         root_manifest: &ObjectId,
     ) -> Result<UploadStats> {
         let manifests = collect_manifests(store, root_manifest)?;
-        let missing_set: BTreeSet<ObjectId> = self
-            .negotiate(
-                repo_id,
-                ObjectSet {
-                    manifests: manifests.to_vec(),
-                    ..Default::default()
-                },
-            )?
-            .manifests
-            .into_iter()
-            .collect();
+        let negotiated = self.negotiate_response(
+            repo_id,
+            ObjectSet {
+                manifests: manifests.to_vec(),
+                ..Default::default()
+            },
+        )?;
+        // Encode only for a server that said it decodes (doc 16 §1f).
+        let accept = negotiated
+            .encodings
+            .iter()
+            .find_map(|name| ObjectEncoding::parse(name));
+        let missing_set: BTreeSet<ObjectId> = negotiated.missing.manifests.into_iter().collect();
         // Child-first: `collect_manifests` walks parent-first, so the
         // reverse never streams a parent before its children — a torn
         // batch stream cannot leave a parent without its subtree.
@@ -230,34 +266,27 @@ impl RemoteClient {
             },
         )?;
 
-        let mut frames: Vec<ObjectFrame> = Vec::new();
-        for id in &missing.recipes {
-            frames.push(ObjectFrame {
-                kind: "recipes".into(),
-                id: id.clone(),
-                bytes: store.get_recipe_bytes(id)?,
-            });
-        }
-        for id in &missing.blobs {
-            frames.push(ObjectFrame {
-                kind: "blobs".into(),
-                id: id.clone(),
-                bytes: store.get_blob(id)?,
-            });
-        }
-        // Manifests last so a present root implies a complete subtree.
-        for id in &missing_manifests {
-            frames.push(ObjectFrame {
-                kind: "manifests".into(),
-                id: id.clone(),
-                bytes: store.get_manifest_bytes(id)?,
-            });
-        }
-        let uploaded = frames.len();
-        self.put_frames(repo_id, frames)?;
+        let wanted: Vec<(&str, &ObjectId)> = missing
+            .recipes
+            .iter()
+            .map(|id| ("recipes", id))
+            .chain(missing.blobs.iter().map(|id| ("blobs", id)))
+            // Manifests last so a present root implies a complete subtree.
+            .chain(missing_manifests.iter().map(|id| ("manifests", id)))
+            .collect();
+        let uploaded = wanted.len();
+        let (logical_bytes, sent_bytes) = self.put_frames(
+            repo_id,
+            uploaded,
+            wanted
+                .into_iter()
+                .map(|(kind, id)| store.transfer_frame(kind, id, accept)),
+        )?;
         Ok(UploadStats {
             negotiated_manifests: manifests.len(),
             uploaded,
+            logical_bytes,
+            sent_bytes,
         })
     }
 
@@ -268,6 +297,18 @@ impl RemoteClient {
         store: &LocalStore,
         repo_id: &str,
         manifest_id: &ObjectId,
+    ) -> Result<()> {
+        self.fetch_manifest_tree_with(store, repo_id, manifest_id, false)
+    }
+
+    /// The wave walk, with `lazy` promising the blobs instead of
+    /// fetching them (doc 16 §1g).
+    pub(crate) fn fetch_manifest_tree_with(
+        &self,
+        store: &LocalStore,
+        repo_id: &str,
+        manifest_id: &ObjectId,
+        lazy: bool,
     ) -> Result<()> {
         let mut manifest_wave: Vec<ObjectId> = vec![manifest_id.clone()];
         let mut blobs = BTreeSet::new();
@@ -322,15 +363,25 @@ impl RemoteClient {
             .filter(|id| !store.has_blob(id))
             .cloned()
             .collect();
-        for frame in self.get_frames(
+        if lazy {
+            store.promise_blobs(&need_blobs)?;
+            return Ok(());
+        }
+        for bytes in self.get_blobs(repo_id, need_blobs)? {
+            store.put_blob(&bytes)?;
+        }
+        Ok(())
+    }
+
+    /// Blob bytes by id, batched like every other download.
+    pub(crate) fn get_blobs(&self, repo_id: &str, ids: Vec<ObjectId>) -> Result<Vec<Vec<u8>>> {
+        let frames = self.get_frames(
             repo_id,
             &ObjectSet {
-                blobs: need_blobs,
+                blobs: ids,
                 ..Default::default()
             },
-        )? {
-            store.put_blob(&frame.bytes)?;
-        }
-        Ok(())
+        )?;
+        Ok(frames.into_iter().map(|frame| frame.bytes).collect())
     }
 }
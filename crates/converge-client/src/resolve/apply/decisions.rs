@@ -37,6 +37,9 @@ pub(super) fn decision_to_index(
                 );
             }
         },
+        ResolutionDecision::Content(_) => {
+            anyhow::bail!("hand-edited content for {} selects no variant", path)
+        }
     }
 }
 
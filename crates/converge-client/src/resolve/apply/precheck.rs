@@ -26,6 +26,9 @@ pub(super) fn ensure_resolution_valid(
         if !report.invalid_keys.is_empty() {
             parts.push(format!("invalid_keys={}", report.invalid_keys.len()));
         }
+        if !report.invalid_content.is_empty() {
+            parts.push(format!("invalid_content={}", report.invalid_content.len()));
+        }
         anyhow::bail!("resolution invalid: {}", parts.join(" "));
     }
 
@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use anyhow::{Context, Result};
 
 use crate::model::{
-    Manifest, ManifestEntry, ManifestEntryKind, ObjectId, ResolutionDecision,
+    ManifestEntry, ManifestEntryKind, ObjectId, ResolutionDecision, ResolvedContentKind,
     SuperpositionVariantKind,
 };
 use crate::store::LocalStore;
@@ -24,7 +24,7 @@ pub(super) fn rewrite_manifest(
         return Ok(out.clone());
     }
 
-    let manifest = store.get_manifest(id)?;
+    let manifest = store.get_dir(id)?;
     let mut out_entries = Vec::with_capacity(manifest.entries.len());
 
     for e in manifest.entries {
@@ -45,7 +45,16 @@ pub(super) fn rewrite_manifest(
                 let decision = decisions
                     .get(&path)
                     .with_context(|| format!("no resolution decision for {}", path))?;
-                let idx = decision_to_index(&path, decision, &variants)?;
+                let idx = match decision {
+                    ResolutionDecision::Content(resolved) => {
+                        out_entries.push(ManifestEntry {
+                            name: e.name,
+                            kind: content_entry(&resolved.content),
+                        });
+                        continue;
+                    }
+                    _ => decision_to_index(&path, decision, &variants)?,
+                };
 
                 let v = &variants[idx];
                 match &v.kind {
@@ -72,8 +81,11 @@ pub(super) fn rewrite_manifest(
                     SuperpositionVariantKind::Symlink { target } => ManifestEntryKind::Symlink {
                         target: target.clone(),
                     },
-                    SuperpositionVariantKind::Tombstone => {
-                        // Drop entry entirely.
+                    // Drop entry entirely. A move elsewhere drops it here
+                    // too; whether the file lands at its destination is
+                    // decided at that path.
+                    SuperpositionVariantKind::Tombstone
+                    | SuperpositionVariantKind::MovedTo { .. } => {
                         continue;
                     }
                 }
@@ -85,19 +97,29 @@ pub(super) fn rewrite_manifest(
                 ManifestEntryKind::FileChunks { recipe, mode, size }
             }
             ManifestEntryKind::Symlink { target } => ManifestEntryKind::Symlink { target },
+            ManifestEntryKind::Page { .. } => unreachable!("get_dir reads pages through"),
         };
 
         out_entries.push(ManifestEntry { name: e.name, kind });
     }
 
-    // Deterministic order.
-    out_entries.sort_by(|a, b| a.name.cmp(&b.name));
-
-    let out_manifest = Manifest {
-        version: 1,
-        entries: out_entries,
-    };
-    let out_id = store.put_manifest(&out_manifest)?;
+    // Deterministic order, and paged past the threshold.
+    let out_id = store.put_dir(out_entries)?;
     memo.insert(memo_key, out_id.clone());
     Ok(out_id)
 }
+
+fn content_entry(content: &ResolvedContentKind) -> ManifestEntryKind {
+    match content {
+        ResolvedContentKind::File { blob, mode, size } => ManifestEntryKind::File {
+            blob: blob.clone(),
+            mode: *mode,
+            size: *size,
+        },
+        ResolvedContentKind::ChunkedFile { recipe, mode, size } => ManifestEntryKind::FileChunks {
+            recipe: recipe.clone(),
+            mode: *mode,
+            size: *size,
+        },
+    }
+}
@@ -1,9 +1,13 @@
 mod apply;
+mod recorded;
 mod types;
 mod validate;
 mod variants;
 
 pub use self::apply::apply_resolution;
-pub use self::types::{InvalidKeyDecision, OutOfRangeDecision, ResolutionValidation};
+pub use self::recorded::{record_decisions, reuse_recorded_decisions};
+pub use self::types::{
+    InvalidContentDecision, InvalidKeyDecision, OutOfRangeDecision, ResolutionValidation,
+};
 pub use self::validate::validate_resolution;
 pub use self::variants::{superposition_variant_counts, superposition_variants};
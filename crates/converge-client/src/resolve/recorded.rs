@@ -0,0 +1,77 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+
+use crate::model::{ObjectId, ResolutionDecision};
+use crate::store::LocalStore;
+
+use super::variants::required_superpositions;
+
+/// Remember each decision against the variants it decided among (doc 17
+/// §2c), so the same conflict in a later candidate is not decided twice.
+///
+/// A pick is stored as the chosen variant's key whatever form it was
+/// given in: an index means nothing once the variants arrive in another
+/// order. Call after a successful apply — an invalid decision is not
+/// worth repeating.
+pub fn record_decisions(
+    store: &LocalStore,
+    root: &ObjectId,
+    decisions: &BTreeMap<String, ResolutionDecision>,
+) -> Result<usize> {
+    let mut recorded = 0;
+    for (path, variants) in required_superpositions(store, root, decisions)? {
+        let Some(decision) = decisions.get(&path) else {
+            continue;
+        };
+        let chosen = match decision {
+            ResolutionDecision::Index(i) => variants.get(*i as usize),
+            ResolutionDecision::Key(key) => variants.iter().find(|v| &v.key() == key),
+            ResolutionDecision::Content(_) => None,
+        };
+        let stable = match chosen {
+            Some(variant) => ResolutionDecision::Key(variant.key()),
+            None if matches!(decision, ResolutionDecision::Content(_)) => decision.clone(),
+            None => continue,
+        };
+        let keys: Vec<_> = variants.iter().map(|v| v.key()).collect();
+        store.put_recorded_decision(&keys, &stable)?;
+        recorded += 1;
+    }
+    Ok(recorded)
+}
+
+/// Fill the paths `decisions` leaves open with recorded decisions for the
+/// same variants; returns the paths filled, sorted.
+///
+/// Decisions already given always stand. A reused pick of a `Dir`
+/// variant can expose nested superpositions, so this repeats until a
+/// pass finds nothing new.
+pub fn reuse_recorded_decisions(
+    store: &LocalStore,
+    root: &ObjectId,
+    decisions: &mut BTreeMap<String, ResolutionDecision>,
+) -> Result<Vec<String>> {
+    let mut reused = Vec::new();
+    loop {
+        let mut found = Vec::new();
+        for (path, variants) in required_superpositions(store, root, decisions)? {
+            if decisions.contains_key(&path) {
+                continue;
+            }
+            let keys: Vec<_> = variants.iter().map(|v| v.key()).collect();
+            if let Some(decision) = store.recorded_decision(&keys)? {
+                found.push((path, decision));
+            }
+        }
+        if found.is_empty() {
+            break;
+        }
+        for (path, decision) in found {
+            reused.push(path.clone());
+            decisions.insert(path, decision);
+        }
+    }
+    reused.sort();
+    Ok(reused)
+}
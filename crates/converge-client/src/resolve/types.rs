@@ -7,6 +7,10 @@ pub struct ResolutionValidation {
     pub extraneous: Vec<String>,
     pub out_of_range: Vec<OutOfRangeDecision>,
     pub invalid_keys: Vec<InvalidKeyDecision>,
+    /// Hand-edited decisions whose content is not in the store, or whose
+    /// `supersedes` no longer matches the variants at the path.
+    #[serde(default)]
+    pub invalid_content: Vec<InvalidContentDecision>,
 }
 
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
@@ -22,3 +26,9 @@ pub struct InvalidKeyDecision {
     pub wanted: VariantKey,
     pub available: Vec<VariantKey>,
 }
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct InvalidContentDecision {
+    pub path: String,
+    pub reason: String,
+}
@@ -1,9 +1,13 @@
 use anyhow::Result;
 
-use crate::model::{ObjectId, ResolutionDecision};
+use crate::model::{
+    ObjectId, ResolutionDecision, ResolvedContent, ResolvedContentKind, SuperpositionVariant,
+};
 use crate::store::LocalStore;
 
-use super::types::{InvalidKeyDecision, OutOfRangeDecision, ResolutionValidation};
+use super::types::{
+    InvalidContentDecision, InvalidKeyDecision, OutOfRangeDecision, ResolutionValidation,
+};
 use super::variants::required_superpositions;
 
 pub fn validate_resolution(
@@ -32,6 +36,7 @@ pub fn validate_resolution(
 
     let mut out_of_range = Vec::new();
     let mut invalid_keys = Vec::new();
+    let mut invalid_content = Vec::new();
     for (path, decision) in decisions {
         let Some(vs) = variants.get(path) else {
             continue;
@@ -57,6 +62,14 @@ pub fn validate_resolution(
                     });
                 }
             }
+            ResolutionDecision::Content(content) => {
+                if let Some(reason) = content_problem(store, content, vs) {
+                    invalid_content.push(InvalidContentDecision {
+                        path: path.clone(),
+                        reason,
+                    });
+                }
+            }
         }
     }
 
@@ -64,13 +77,58 @@ pub fn validate_resolution(
     extraneous.sort();
     out_of_range.sort_by(|a, b| a.path.cmp(&b.path));
     invalid_keys.sort_by(|a, b| a.path.cmp(&b.path));
+    invalid_content.sort_by(|a, b| a.path.cmp(&b.path));
 
-    let ok = missing.is_empty() && out_of_range.is_empty() && invalid_keys.is_empty();
+    let ok = missing.is_empty()
+        && out_of_range.is_empty()
+        && invalid_keys.is_empty()
+        && invalid_content.is_empty();
     Ok(ResolutionValidation {
         ok,
         missing,
         extraneous,
         out_of_range,
         invalid_keys,
+        invalid_content,
     })
 }
+
+/// Why a hand-edited decision cannot be applied, if it cannot.
+///
+/// The content must be local or promised (doc 16 §1g) — apply writes
+/// it into the tree and publish uploads it from there — and
+/// `supersedes` must name exactly the variants at the path. A variant
+/// it does not name arrived after the content was written, so the
+/// resolver never weighed it.
+fn content_problem(
+    store: &LocalStore,
+    decision: &ResolvedContent,
+    variants: &[SuperpositionVariant],
+) -> Option<String> {
+    let present = match &decision.content {
+        ResolvedContentKind::File { blob, .. } => store.knows_blob(blob),
+        ResolvedContentKind::ChunkedFile { recipe, .. } => {
+            store.has_recipe(recipe)
+                && store
+                    .get_recipe(recipe)
+                    .is_ok_and(|r| r.chunks.iter().all(|c| store.knows_blob(&c.blob)))
+        }
+    };
+    if !present {
+        return Some("content is not in the local store".into());
+    }
+    let keys: Vec<_> = variants.iter().map(|v| v.key()).collect();
+    let unseen = keys
+        .iter()
+        .filter(|k| !decision.supersedes.contains(k))
+        .count();
+    if unseen > 0 {
+        return Some(format!(
+            "{unseen} variant(s) arrived after the content was written"
+        ));
+    }
+    if decision.supersedes.iter().any(|k| !keys.contains(k)) {
+        return Some("supersedes a variant that is not at this path".into());
+    }
+    None
+}
@@ -13,7 +13,7 @@ pub fn superposition_variants(
     let mut stack = vec![(String::new(), root.clone())];
 
     while let Some((prefix, mid)) = stack.pop() {
-        let manifest = store.get_manifest(&mid)?;
+        let manifest = store.get_dir(&mid)?;
         for e in manifest.entries {
             let path = if prefix.is_empty() {
                 e.name.clone()
@@ -31,6 +31,7 @@ pub fn superposition_variants(
                 ManifestEntryKind::File { .. }
                 | ManifestEntryKind::FileChunks { .. }
                 | ManifestEntryKind::Symlink { .. } => {}
+                ManifestEntryKind::Page { .. } => unreachable!("get_dir reads pages through"),
             }
         }
     }
@@ -55,7 +56,7 @@ pub fn required_superpositions(
     let mut stack = vec![(String::new(), root.clone())];
 
     while let Some((prefix, mid)) = stack.pop() {
-        let manifest = store.get_manifest(&mid)?;
+        let manifest = store.get_dir(&mid)?;
         for e in manifest.entries {
             let path = if prefix.is_empty() {
                 e.name.clone()
@@ -79,6 +80,7 @@ pub fn required_superpositions(
                 ManifestEntryKind::File { .. }
                 | ManifestEntryKind::FileChunks { .. }
                 | ManifestEntryKind::Symlink { .. } => {}
+                ManifestEntryKind::Page { .. } => unreachable!("get_dir reads pages through"),
             }
         }
     }
@@ -87,7 +89,8 @@ pub fn required_superpositions(
 }
 
 /// The variant a decision selects, or `None` when it resolves to nothing
-/// (out of range / unknown key — reported by validation itself).
+/// (out of range / unknown key — reported by validation itself) or to
+/// hand-edited content, which is always a file.
 pub fn variant_for<'v>(
     decision: &ResolutionDecision,
     variants: &'v [SuperpositionVariant],
@@ -95,6 +98,7 @@ pub fn variant_for<'v>(
     let index = match decision {
         ResolutionDecision::Index(i) => *i as usize,
         ResolutionDecision::Key(key) => variants.iter().position(|v| &v.key() == key)?,
+        ResolutionDecision::Content(_) => return None,
     };
     variants.get(index).map(|v| &v.kind)
 }
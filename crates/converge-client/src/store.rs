@@ -7,14 +7,35 @@ use crate::model::ObjectId;
 
 const STORE_DIR: &str = ".converge";
 mod core_setup;
+mod linked;
+mod local_gc;
+mod migrate;
 mod object_crud;
+mod packs;
+mod promised;
 mod snap_resolution;
 mod state_meta;
+pub use local_gc::{GcRoots, LocalGcReport};
+pub use migrate::WORKSPACE_STEPS;
+pub use promised::BlobSource;
 pub use state_meta::{StaleToken, TokenStoreSurvey, survey_token_store};
 
 #[derive(Clone)]
 pub struct LocalStore {
     root: PathBuf,
+    /// `objects/` under `root`, or under the primary's `.converge` when
+    /// this is a linked worktree (doc 15 §5f).
+    objects: PathBuf,
+    /// The primary's `.converge` directory, for a linked worktree.
+    primary: Option<PathBuf>,
+    /// Write blobs and manifests zstd-encoded where that saves space
+    /// (doc 16 §1f). Read from config when the store is opened.
+    compress_objects: bool,
+    /// Where promised blobs are read from (doc 16 §1g). Attached by the
+    /// caller, which knows the remote; a bare store has none.
+    blob_source: Option<std::sync::Arc<dyn BlobSource>>,
+    /// The pack indexes as last read (doc 16 §1h), shared by clones.
+    packs: std::sync::Arc<std::sync::Mutex<Option<std::sync::Arc<packs::PackSet>>>>,
 }
 
 pub(crate) fn hash_bytes(bytes: &[u8]) -> ObjectId {
@@ -36,7 +57,19 @@ impl LocalStore {
         } else {
             ("_", "_")
         };
-        self.root.join("objects").join(kind).join(a).join(b).join(h)
+        self.objects.join(kind).join(a).join(b).join(h)
+    }
+
+    /// Where the same object lives when stored encoded: `<hash>.zst`
+    /// beside the plain name (doc 16 §1f).
+    fn encoded_object_path(
+        &self,
+        kind: &str,
+        id: &ObjectId,
+        encoding: crate::model::ObjectEncoding,
+    ) -> PathBuf {
+        self.object_path(kind, id)
+            .with_extension(encoding.extension())
     }
 }
 
@@ -56,7 +89,11 @@ fn write_if_absent(path: &Path, bytes: &[u8]) -> Result<()> {
 pub(crate) fn write_atomic(path: &Path, bytes: &[u8]) -> Result<()> {
     let parent = path.parent().context("path has no parent")?;
     fs::create_dir_all(parent).context("create parent directories")?;
-    let tmp = path.with_extension(format!("tmp.{}", std::process::id()));
+    // Unique per write, not just per process: scan workers (doc 15 §5d)
+    // may store the same object at once.
+    static WRITES: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+    let n = WRITES.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    let tmp = path.with_extension(format!("tmp.{}.{n}", std::process::id()));
     {
         use std::io::Write;
         let mut file = fs::File::create(&tmp)
@@ -25,7 +25,22 @@ impl LocalStore {
         // time a read looks wrong, something has already been written
         // against an assumption that did not hold.
         crate::model::format::check_compatible(&root, crate::model::format::StoreKind::Workspace)?;
-        Ok(Self { root })
+        let primary = Self::read_link(&root)?;
+        let mut store = Self {
+            objects: primary.as_ref().unwrap_or(&root).join("objects"),
+            primary,
+            root,
+            compress_objects: false,
+            blob_source: None,
+            packs: Default::default(),
+        };
+        // Leniently: a config that does not parse fails the verb that
+        // needs it, with that verb's context, not every open.
+        store.compress_objects = store
+            .read_config()
+            .map(|cfg| cfg.compress_objects)
+            .unwrap_or(false);
+        Ok(store)
     }
 
     pub fn init(workspace_root: &Path, force: bool) -> Result<Self> {
@@ -63,8 +78,6 @@ impl LocalStore {
         fs::create_dir_all(root.join("objects/blobs")).context("create blobs dir")?;
         fs::create_dir_all(root.join("objects/manifests")).context("create manifests dir")?;
         fs::create_dir_all(root.join("objects/recipes")).context("create recipes dir")?;
-        fs::create_dir_all(root.join("snaps")).context("create snaps dir")?;
-        fs::create_dir_all(root.join("resolutions")).context("create resolutions dir")?;
 
         let cfg = WorkspaceConfig {
             version: 1,
@@ -72,23 +85,21 @@ impl LocalStore {
             chunking: None,
             retention: None,
             workflow_profile: WorkflowProfile::default(),
+            author: None,
+            compress_objects: false,
+            sparse: Vec::new(),
+            scan_threads: None,
         };
-        let cfg_bytes = serde_json::to_vec_pretty(&cfg).context("serialize workspace config")?;
-        write_atomic(&root.join("config.json"), &cfg_bytes).context("write config.json")?;
-
-        let state = WorkspaceState {
-            version: 1,
-            lane_sync: std::collections::HashMap::new(),
-            remote_tokens: std::collections::HashMap::new(),
-            last_published: std::collections::HashMap::new(),
-            last_seen_candidate: std::collections::HashMap::new(),
-        };
-        let state_bytes = serde_json::to_vec_pretty(&state).context("serialize workspace state")?;
-        write_atomic(&root.join("state.json"), &state_bytes).context("write state.json")?;
+        Self::write_fresh(&root, &cfg)?;
 
-        crate::model::format::write_version(&root, crate::model::format::StoreKind::Workspace)?;
-
-        Ok(Self { root })
+        Ok(Self {
+            objects: root.join("objects"),
+            primary: None,
+            root,
+            compress_objects: false,
+            blob_source: None,
+            packs: Default::default(),
+        })
     }
 
     /// Pure read (audit R2): no writes on this hot path. Tokens live in
@@ -104,4 +115,27 @@ impl LocalStore {
         write_atomic(&self.root.join("config.json"), &bytes).context("write config.json")?;
         Ok(())
     }
+
+    /// Everything but the objects a new workspace starts with: its
+    /// record directories, `cfg`, an empty state and the format stamp,
+    /// written last.
+    pub(super) fn write_fresh(root: &Path, cfg: &WorkspaceConfig) -> Result<()> {
+        fs::create_dir_all(root.join("snaps")).context("create snaps dir")?;
+        fs::create_dir_all(root.join("resolutions")).context("create resolutions dir")?;
+
+        let cfg_bytes = serde_json::to_vec_pretty(cfg).context("serialize workspace config")?;
+        write_atomic(&root.join("config.json"), &cfg_bytes).context("write config.json")?;
+
+        let state = WorkspaceState {
+            version: 1,
+            lane_sync: std::collections::HashMap::new(),
+            remote_tokens: std::collections::HashMap::new(),
+            last_published: std::collections::HashMap::new(),
+            last_seen_candidate: std::collections::HashMap::new(),
+        };
+        let state_bytes = serde_json::to_vec_pretty(&state).context("serialize workspace state")?;
+        write_atomic(&root.join("state.json"), &state_bytes).context("write state.json")?;
+
+        crate::model::format::write_version(root, crate::model::format::StoreKind::Workspace)
+    }
 }
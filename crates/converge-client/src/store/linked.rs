@@ -0,0 +1,158 @@
+//! Linked worktrees (doc 15 §5f): a workspace whose `.converge` holds
+//! its own head, snaps, state and config, and reads and writes objects
+//! in its primary's store.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow};
+
+use crate::model::{WorkspaceConfig, WorktreeRecord};
+
+use super::{LocalStore, write_atomic};
+
+/// In a linked worktree's `.converge`: the primary's `.converge`
+/// directory, absolute, on one line.
+const LINK_FILE: &str = "primary";
+
+/// In a primary's `.converge`: one record per linked worktree.
+const WORKTREES_DIR: &str = "worktrees";
+
+impl LocalStore {
+    /// The primary a linked worktree at `root` reads objects from, or
+    /// `None` for a primary. A link to a primary that is gone is an
+    /// error: every object this worktree names went with it.
+    pub(super) fn read_link(root: &Path) -> Result<Option<PathBuf>> {
+        let link = root.join(LINK_FILE);
+        if !link.exists() {
+            return Ok(None);
+        }
+        let text = fs::read_to_string(&link).context("read worktree link")?;
+        let primary = PathBuf::from(text.trim());
+        if !primary.join("config.json").is_file() {
+            anyhow::bail!(
+                "this worktree is linked to the workspace at {}, which is gone; \
+                 its objects went with it",
+                primary.parent().unwrap_or(&primary).display()
+            );
+        }
+        crate::model::format::check_compatible(
+            &primary,
+            crate::model::format::StoreKind::Workspace,
+        )
+        .context("the primary workspace this worktree is linked to")?;
+        Ok(Some(primary))
+    }
+
+    /// What the worktree at `workspace_root` links to, unchecked: `None`
+    /// when there is no link to read.
+    pub fn link_target(workspace_root: &Path) -> Option<PathBuf> {
+        fs::read_to_string(Self::converge_dir(workspace_root).join(LINK_FILE))
+            .ok()
+            .map(|text| PathBuf::from(text.trim()))
+    }
+
+    /// The primary's `.converge` directory, when this is a linked
+    /// worktree.
+    pub fn primary_dir(&self) -> Option<&Path> {
+        self.primary.as_deref()
+    }
+
+    /// The store that holds the objects and the worktree list: this
+    /// one, or the primary it links to.
+    pub fn primary_store(&self) -> Result<LocalStore> {
+        match &self.primary {
+            None => Ok(self.clone()),
+            Some(primary) => {
+                let root = primary.parent().unwrap_or(primary);
+                Ok(LocalStore::open(root)?.with_blob_source_of(self))
+            }
+        }
+    }
+
+    /// A new linked worktree at `workspace_root`, sharing `from`'s
+    /// objects — `from`'s primary's, when `from` is itself linked — and
+    /// starting from `cfg`. Nothing is registered with the primary here.
+    pub fn init_linked(
+        workspace_root: &Path,
+        from: &LocalStore,
+        cfg: &WorkspaceConfig,
+    ) -> Result<Self> {
+        let root = Self::converge_dir(workspace_root);
+        if root.exists() {
+            return Err(anyhow!("{} already exists", root.display()));
+        }
+        let primary = match &from.primary {
+            Some(primary) => primary.clone(),
+            None => from
+                .root
+                .canonicalize()
+                .with_context(|| format!("canonicalize {}", from.root.display()))?,
+        };
+        fs::create_dir_all(&root).context("create .converge")?;
+        // First, so nothing ever opens this store as a primary with no
+        // objects of its own.
+        write_atomic(
+            &root.join(LINK_FILE),
+            format!("{}\n", primary.display()).as_bytes(),
+        )
+        .context("write worktree link")?;
+        Self::write_fresh(&root, cfg)?;
+        Ok(Self {
+            objects: from.objects.clone(),
+            primary: Some(primary),
+            root,
+            compress_objects: cfg.compress_objects,
+            blob_source: from.blob_source.clone(),
+            packs: Default::default(),
+        })
+    }
+
+    fn with_blob_source_of(mut self, other: &LocalStore) -> Self {
+        self.blob_source = other.blob_source.clone();
+        self
+    }
+
+    pub fn put_worktree(&self, worktree: &WorktreeRecord) -> Result<()> {
+        let bytes = serde_json::to_vec_pretty(worktree).context("serialize worktree")?;
+        write_atomic(&self.worktree_path(&worktree.name), &bytes).context("write worktree")
+    }
+
+    pub fn has_worktree(&self, name: &str) -> bool {
+        self.worktree_path(name).exists()
+    }
+
+    /// Every worktree linked to this primary, by name.
+    pub fn list_worktrees(&self) -> Result<Vec<WorktreeRecord>> {
+        let dir = self.root.join(WORKTREES_DIR);
+        let mut out: Vec<WorktreeRecord> = Vec::new();
+        if !dir.is_dir() {
+            return Ok(out);
+        }
+        for entry in fs::read_dir(&dir).context("read worktrees dir")? {
+            let path = entry.context("read worktrees dir entry")?.path();
+            if path.extension().and_then(|s| s.to_str()) != Some("json") {
+                continue;
+            }
+            let bytes = fs::read(&path).with_context(|| format!("read {}", path.display()))?;
+            out.push(
+                serde_json::from_slice(&bytes)
+                    .with_context(|| format!("parse worktree file {}", path.display()))?,
+            );
+        }
+        out.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(out)
+    }
+
+    pub fn delete_worktree(&self, name: &str) -> Result<()> {
+        let path = self.worktree_path(name);
+        if path.exists() {
+            fs::remove_file(&path).with_context(|| format!("remove {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    fn worktree_path(&self, name: &str) -> PathBuf {
+        self.root.join(WORKTREES_DIR).join(format!("{name}.json"))
+    }
+}
@@ -0,0 +1,329 @@
+//! Workspace garbage collection (doc 16 §1h): mark from the roots the
+//! workspace names, sweep what nothing reaches, and pack the rest.
+
+use std::collections::HashSet;
+use std::fs;
+use std::io::Read;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::model::compression;
+use crate::model::{ManifestEntryKind, ObjectEncoding, ObjectId, SuperpositionVariantKind};
+
+use super::LocalStore;
+use super::object_crud::{KIND_BLOBS, KIND_MANIFESTS, KIND_RECIPES};
+use super::packs::{MAX_PACKS, PACK_OBJECT_MAX, Packing, remove_if_present as remove};
+
+/// What the workspace keeps: trees, and content named outside any tree.
+#[derive(Clone, Debug, Default)]
+pub struct GcRoots {
+    pub manifests: Vec<ObjectId>,
+    pub recipes: Vec<ObjectId>,
+    pub blobs: Vec<ObjectId>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct LocalGcReport {
+    pub dry_run: bool,
+    pub reachable_objects: u64,
+    pub swept_objects: u64,
+    /// Bytes freed as stored, which is less than `swept_logical_bytes`
+    /// where swept objects were encoded (doc 16 §1f).
+    pub swept_bytes: u64,
+    pub swept_logical_bytes: u64,
+    /// Promises dropped for blobs nothing reaches any more (doc 16 §1g).
+    pub dropped_promises: u64,
+    /// Objects moved from loose files into a pack.
+    pub packed_objects: u64,
+    /// Packs, and loose objects, once the run is done.
+    pub packs: u64,
+    pub loose_objects: u64,
+}
+
+/// One object file under `objects/<kind>/`.
+struct Loose {
+    kind: &'static str,
+    id: ObjectId,
+    path: PathBuf,
+    encoding: Option<ObjectEncoding>,
+    len: u64,
+    modified: SystemTime,
+}
+
+#[derive(Default)]
+struct Reachable {
+    manifests: HashSet<ObjectId>,
+    recipes: HashSet<ObjectId>,
+    blobs: HashSet<ObjectId>,
+}
+
+impl Reachable {
+    fn contains(&self, kind: &str, id: &ObjectId) -> bool {
+        match kind {
+            KIND_MANIFESTS => self.manifests.contains(id),
+            KIND_RECIPES => self.recipes.contains(id),
+            _ => self.blobs.contains(id),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.manifests.len() + self.recipes.len() + self.blobs.len()
+    }
+}
+
+impl LocalStore {
+    /// Sweep every object `roots` do not reach, then pack what is left.
+    ///
+    /// Loose objects younger than `grace` are kept whether reached or
+    /// not: a capture writes its objects before its snap record. So are
+    /// packed objects in a pack freshened inside `grace`, which is what
+    /// a capture that found its object already packed does instead of
+    /// writing it. Snap records themselves are never deleted here.
+    pub fn collect_garbage(
+        &self,
+        roots: &GcRoots,
+        dry_run: bool,
+        grace: Duration,
+    ) -> Result<LocalGcReport> {
+        let reachable = self.mark(roots)?;
+        let mut report = LocalGcReport {
+            dry_run,
+            reachable_objects: reachable.len() as u64,
+            ..Default::default()
+        };
+        let cutoff = SystemTime::now()
+            .checked_sub(grace)
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+
+        // Loose: swept if unreached and old enough, packed if reached
+        // and small.
+        let packs = self.reload_packs()?;
+        let mut to_pack = Vec::new();
+        let mut loose_left = 0u64;
+        for object in self.loose_objects()? {
+            if !reachable.contains(object.kind, &object.id) {
+                if object.modified > cutoff {
+                    loose_left += 1;
+                    continue;
+                }
+                report.swept_objects += 1;
+                report.swept_bytes += object.len;
+                report.swept_logical_bytes +=
+                    logical_len(object.encoding, object.len, || read_header(&object.path));
+                if !dry_run {
+                    remove(&object.path)?;
+                }
+            } else if object.len <= PACK_OBJECT_MAX {
+                to_pack.push(object);
+            } else {
+                loose_left += 1;
+            }
+        }
+
+        // Packed: whatever is unreached goes when its pack is rewritten,
+        // unless a capture freshened the pack inside the grace window.
+        let packed = packs.objects();
+        let (kept, dropped): (Vec<_>, Vec<_>) = packed
+            .into_iter()
+            .partition(|p| reachable.contains(p.kind, &p.id) || p.modified > cutoff);
+        for object in &dropped {
+            report.swept_objects += 1;
+            report.swept_bytes += object.len;
+            report.swept_logical_bytes +=
+                logical_len(object.encoding, object.len, || object.read());
+        }
+        // A loose copy of an object already packed is just removed.
+        let kept_ids: HashSet<(&str, &ObjectId)> = kept.iter().map(|p| (p.kind, &p.id)).collect();
+        let (duplicates, to_pack): (Vec<_>, Vec<_>) = to_pack
+            .into_iter()
+            .partition(|o| kept_ids.contains(&(o.kind, &o.id)));
+        report.packed_objects = to_pack.len() as u64;
+
+        let rewrite = !dropped.is_empty() || packs.len() >= MAX_PACKS;
+        let pack_count = packs.len() as u64;
+        report.packs = match (rewrite, to_pack.is_empty()) {
+            (true, _) if kept.is_empty() && to_pack.is_empty() => 0,
+            (true, _) => 1,
+            (false, true) => pack_count,
+            (false, false) => pack_count + 1,
+        };
+        report.loose_objects = loose_left;
+
+        let dropped_promises = self
+            .promised_blobs()?
+            .into_iter()
+            .filter(|id| !reachable.blobs.contains(id))
+            .collect::<Vec<_>>();
+        report.dropped_promises = dropped_promises.len() as u64;
+
+        if dry_run {
+            return Ok(report);
+        }
+        for id in &dropped_promises {
+            remove(&self.object_path("promised", id))?;
+        }
+
+        let loose_in = to_pack.iter().map(|o| {
+            Ok(Packing {
+                kind: o.kind,
+                id: o.id.clone(),
+                encoding: o.encoding,
+                bytes: fs::read(&o.path).with_context(|| format!("read {}", o.path.display()))?,
+            })
+        });
+        if rewrite {
+            let old: Vec<PathBuf> = packs.paths().map(PathBuf::from).collect();
+            if kept.is_empty() && to_pack.is_empty() {
+                self.retire_packs(&old)?;
+            } else {
+                let carried = kept.iter().map(|p| {
+                    Ok(Packing {
+                        kind: p.kind,
+                        id: p.id.clone(),
+                        encoding: p.encoding,
+                        bytes: p.read()?,
+                    })
+                });
+                self.write_pack(carried.chain(loose_in), &old)?;
+            }
+        } else if !to_pack.is_empty() {
+            self.write_pack(loose_in, &[])?;
+        }
+        // Only once the pack holding them is in place.
+        for object in to_pack.iter().chain(&duplicates) {
+            remove(&object.path)?;
+        }
+        Ok(report)
+    }
+
+    /// Everything `roots` reach. A tree that cannot be read fails the
+    /// mark, and with it the run: sweeping under a partial mark would
+    /// delete what the unread part holds.
+    fn mark(&self, roots: &GcRoots) -> Result<Reachable> {
+        let mut reachable = Reachable::default();
+        reachable.blobs.extend(roots.blobs.iter().cloned());
+        let mut recipes: Vec<ObjectId> = roots.recipes.clone();
+        let mut manifests: Vec<ObjectId> = roots.manifests.clone();
+        while let Some(id) = manifests.pop() {
+            if !reachable.manifests.insert(id.clone()) {
+                continue;
+            }
+            let manifest = self
+                .get_manifest(&id)
+                .with_context(|| format!("mark from manifest {}; nothing swept", id.as_str()))?;
+            for entry in manifest.entries {
+                match entry.kind {
+                    ManifestEntryKind::File { blob, .. } => {
+                        reachable.blobs.insert(blob);
+                    }
+                    ManifestEntryKind::FileChunks { recipe, .. } => recipes.push(recipe),
+                    ManifestEntryKind::Dir { manifest }
+                    | ManifestEntryKind::Page { manifest, .. } => manifests.push(manifest),
+                    ManifestEntryKind::Symlink { .. } => {}
+                    ManifestEntryKind::Superposition { variants } => {
+                        for variant in variants {
+                            match variant.kind {
+                                SuperpositionVariantKind::File { blob, .. } => {
+                                    reachable.blobs.insert(blob);
+                                }
+                                SuperpositionVariantKind::FileChunks { recipe, .. } => {
+                                    recipes.push(recipe)
+                                }
+                                SuperpositionVariantKind::Dir { manifest } => {
+                                    manifests.push(manifest)
+                                }
+                                SuperpositionVariantKind::Symlink { .. }
+                                | SuperpositionVariantKind::Tombstone
+                                | SuperpositionVariantKind::MovedTo { .. } => {}
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        for id in recipes {
+            if !reachable.recipes.insert(id.clone()) {
+                continue;
+            }
+            let recipe = self
+                .get_recipe(&id)
+                .with_context(|| format!("mark from recipe {}; nothing swept", id.as_str()))?;
+            reachable
+                .blobs
+                .extend(recipe.chunks.into_iter().map(|c| c.blob));
+        }
+        Ok(reachable)
+    }
+
+    /// Every loose object file. Temp files from writes in flight are
+    /// not objects yet, and are left alone.
+    fn loose_objects(&self) -> Result<Vec<Loose>> {
+        let mut out = Vec::new();
+        for kind in [KIND_BLOBS, KIND_MANIFESTS, KIND_RECIPES] {
+            let mut stack = vec![self.objects.join(kind)];
+            while let Some(dir) = stack.pop() {
+                let listing = match fs::read_dir(&dir) {
+                    Ok(listing) => listing,
+                    Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+                    Err(err) => {
+                        return Err(err).with_context(|| format!("read {}", dir.display()));
+                    }
+                };
+                for entry in listing {
+                    let entry = entry?;
+                    let meta = entry.metadata()?;
+                    if meta.is_dir() {
+                        stack.push(entry.path());
+                        continue;
+                    }
+                    let name = entry.file_name().to_string_lossy().into_owned();
+                    let (hash, encoding) = match name.split_once('.') {
+                        None => (name.as_str(), None),
+                        Some((hash, "zst")) => (hash, Some(ObjectEncoding::Zstd)),
+                        Some(_) => continue,
+                    };
+                    out.push(Loose {
+                        kind,
+                        id: ObjectId(hash.to_string()),
+                        path: entry.path(),
+                        encoding,
+                        len: meta.len(),
+                        modified: meta.modified()?,
+                    });
+                }
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// The plain size of an object `len` bytes as stored, from the header
+/// `read` returns. An object whose header does not say counts as
+/// stored, as the server's report counts it.
+fn logical_len(
+    encoding: Option<ObjectEncoding>,
+    len: u64,
+    read: impl FnOnce() -> Result<Vec<u8>>,
+) -> u64 {
+    let Some(encoding) = encoding else {
+        return len;
+    };
+    read()
+        .ok()
+        .and_then(|bytes| compression::logical_len(encoding, &bytes))
+        .unwrap_or(len)
+}
+
+/// The leading bytes of a loose object: enough for a zstd frame header.
+fn read_header(path: &std::path::Path) -> Result<Vec<u8>> {
+    const FRAME_HEADER_MAX: u64 = 18;
+    let mut header = Vec::new();
+    fs::File::open(path)
+        .with_context(|| format!("open {}", path.display()))?
+        .take(FRAME_HEADER_MAX)
+        .read_to_end(&mut header)?;
+    Ok(header)
+}
@@ -0,0 +1,76 @@
+//! Workspace format migrations (doc 16 §3).
+//!
+//! The framework — ordering, journal, backup, resumption — is
+//! [`crate::model::migrate`]; this is the workspace's registry and the
+//! steps in it. Steps work on the `.converge` directory without going
+//! through [`LocalStore::open`], which is exactly what refuses them.
+
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::model::format::StoreKind;
+use crate::model::migrate::{Options, Report, Step};
+
+use super::LocalStore;
+
+/// Every workspace migration this build knows, oldest first.
+pub const WORKSPACE_STEPS: &[Step] = &[
+    Step {
+        from: 1,
+        summary: "adopt converge-snap-v5 for authored records; stored records keep their ids",
+        apply: adopt_snap_v5,
+    },
+    Step {
+        from: 2,
+        summary: "allow zstd-encoded objects (doc 16 §1f); stored objects stay as they are",
+        apply: allow_encoded_objects,
+    },
+    Step {
+        from: 3,
+        summary: "allow packed objects (doc 16 §1h); loose objects stay as they are",
+        apply: allow_packed_objects,
+    },
+];
+
+impl LocalStore {
+    /// Bring the workspace at `workspace_root` to the current format.
+    pub fn migrate(workspace_root: &Path, options: Options) -> Result<Report> {
+        crate::model::migrate::run(
+            &Self::converge_dir(workspace_root),
+            StoreKind::Workspace,
+            WORKSPACE_STEPS,
+            options,
+        )
+    }
+}
+
+/// 1 -> 2: nothing to rewrite.
+///
+/// Every version-1 record is anonymous, and anonymous records are still
+/// identified under `converge-snap-v4` (doc 17 §1), so every id, parent
+/// and pointer — HEAD, last-published, lane-sync — stays true, here and
+/// on any server that holds the same records. The step exists for the
+/// stamp: once records may carry an author, a version-1 binary must
+/// refuse the store rather than derive their ids wrongly.
+fn adopt_snap_v5(_root: &Path) -> Result<()> {
+    Ok(())
+}
+
+/// 2 -> 3: nothing to rewrite.
+///
+/// Every object a version-2 store holds is plain, and plain objects are
+/// read as they always were. The step exists for the stamp: once a
+/// store may hold `<id>.zst`, a version-2 binary must refuse it rather
+/// than report those objects missing.
+fn allow_encoded_objects(_root: &Path) -> Result<()> {
+    Ok(())
+}
+
+/// 3 -> 4: nothing to rewrite.
+///
+/// Packs are written only by `gc --local`, which a version-3 store has
+/// never run. The step exists for the stamp, as the two before it do.
+fn allow_packed_objects(_root: &Path) -> Result<()> {
+    Ok(())
+}
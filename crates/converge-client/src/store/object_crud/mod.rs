@@ -2,18 +2,26 @@ use std::fs;
 
 use anyhow::{Context, Result, anyhow};
 
-use crate::model::{FileRecipe, Manifest, ObjectId};
+use crate::model::compression;
+use crate::model::merge::MergeObjects;
+use crate::model::paging;
+use crate::model::{FileRecipe, Manifest, ManifestEntry, ObjectEncoding, ObjectFrame, ObjectId};
 
 use super::{LocalStore, hash_bytes, write_if_absent};
 
-const KIND_BLOBS: &str = "blobs";
-const KIND_MANIFESTS: &str = "manifests";
-const KIND_RECIPES: &str = "recipes";
+pub(super) const KIND_BLOBS: &str = "blobs";
+pub(super) const KIND_MANIFESTS: &str = "manifests";
+pub(super) const KIND_RECIPES: &str = "recipes";
+
+/// Kinds that may be stored or sent encoded (doc 16 §1f). A recipe is a
+/// list of hashes, which no compressor does anything with.
+fn encodable(kind: &str) -> bool {
+    kind != KIND_RECIPES
+}
 
 fn put_object(store: &LocalStore, kind: &str, bytes: &[u8]) -> Result<ObjectId> {
     let id = hash_bytes(bytes);
-    write_if_absent(&store.object_path(kind, &id), bytes)
-        .with_context(|| format!("store {kind} object"))?;
+    write_object(store, kind, &id, bytes).with_context(|| format!("store {kind} object"))?;
     Ok(id)
 }
 
@@ -26,27 +34,97 @@ fn put_object_bytes(store: &LocalStore, kind: &str, id: &ObjectId, bytes: &[u8])
             actual.as_str()
         ));
     }
+    write_object(store, kind, id, bytes).with_context(|| format!("store {kind} object bytes"))
+}
+
+/// Write-if-absent in whichever form the store keeps: an object already
+/// present in either form is not written again.
+fn write_object(store: &LocalStore, kind: &str, id: &ObjectId, bytes: &[u8]) -> Result<()> {
+    if has_loose(store, kind, id) || store.freshen_packed(kind, id)? {
+        return Ok(());
+    }
+    if store.compress_objects
+        && encodable(kind)
+        && let Some(encoded) = compression::encode(bytes)
+    {
+        let path = store.encoded_object_path(kind, id, ObjectEncoding::Zstd);
+        return write_if_absent(&path, &encoded);
+    }
     write_if_absent(&store.object_path(kind, id), bytes)
-        .with_context(|| format!("store {kind} object bytes"))
 }
 
-fn get_object(store: &LocalStore, kind: &str, id: &ObjectId) -> Result<Vec<u8>> {
-    let path = store.object_path(kind, id);
-    let bytes = fs::read(&path).with_context(|| format!("read {kind} object {}", id.as_str()))?;
-    let actual = hash_bytes(&bytes);
+/// The object as stored: its bytes and their encoding, if any. Loose
+/// first, then packed (doc 16 §1h).
+fn read_stored(
+    store: &LocalStore,
+    kind: &str,
+    id: &ObjectId,
+) -> Result<(Vec<u8>, Option<ObjectEncoding>)> {
+    let context = || format!("read {kind} object {}", id.as_str());
+    let missing = match fs::read(store.object_path(kind, id)) {
+        Ok(bytes) => return Ok((bytes, None)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => err,
+        Err(err) => return Err(err).with_context(context),
+    };
+    if encodable(kind) {
+        let encoded = store.encoded_object_path(kind, id, ObjectEncoding::Zstd);
+        if let Ok(bytes) = fs::read(encoded) {
+            return Ok((bytes, Some(ObjectEncoding::Zstd)));
+        }
+    }
+    match store.read_packed(kind, id).with_context(context)? {
+        Some(stored) => Ok(stored),
+        // Nowhere: report the plain name, which is what an older store
+        // would have had.
+        None => Err(missing).with_context(context),
+    }
+}
+
+/// Decode a stored object and check it against its id.
+fn verified(
+    store: &LocalStore,
+    kind: &str,
+    id: &ObjectId,
+    bytes: &[u8],
+    encoding: Option<ObjectEncoding>,
+) -> Result<Option<Vec<u8>>> {
+    let decoded = match encoding {
+        None => None,
+        Some(encoding) => Some(
+            compression::decode(encoding, bytes, u64::MAX)
+                .with_context(|| format!("decode {kind} object {}", id.as_str()))?,
+        ),
+    };
+    let actual = hash_bytes(decoded.as_deref().unwrap_or(bytes));
     if actual != *id {
         return Err(anyhow!(
             "{kind} integrity check failed for {} (expected {}, got {})",
-            path.display(),
+            store.object_path(kind, id).display(),
             id.as_str(),
             actual.as_str()
         ));
     }
-    Ok(bytes)
+    Ok(decoded)
 }
 
-fn has_object(store: &LocalStore, kind: &str, id: &ObjectId) -> bool {
+fn get_object(store: &LocalStore, kind: &str, id: &ObjectId) -> Result<Vec<u8>> {
+    let (bytes, encoding) = read_stored(store, kind, id)?;
+    Ok(verified(store, kind, id, &bytes, encoding)?.unwrap_or(bytes))
+}
+
+fn has_loose(store: &LocalStore, kind: &str, id: &ObjectId) -> bool {
     store.object_path(kind, id).exists()
+        || (encodable(kind)
+            && store
+                .encoded_object_path(kind, id, ObjectEncoding::Zstd)
+                .exists())
+}
+
+fn has_object(store: &LocalStore, kind: &str, id: &ObjectId) -> bool {
+    has_loose(store, kind, id)
+        || store
+            .pack_set()
+            .is_ok_and(|packs| packs.find(kind, id).is_some())
 }
 
 impl LocalStore {
@@ -58,8 +136,38 @@ impl LocalStore {
         has_object(self, KIND_BLOBS, id)
     }
 
+    /// Whether a capture can name this blob without storing it again. A
+    /// packed one is freshened, as a write would have been (doc 16 §1h).
+    pub(crate) fn reuse_blob(&self, id: &ObjectId) -> Result<bool> {
+        Ok(has_loose(self, KIND_BLOBS, id) || self.freshen_packed(KIND_BLOBS, id)?)
+    }
+
+    /// `reuse_blob` for a recipe. A packed recipe's chunks are freshened
+    /// with it: nothing else in the capture names them.
+    pub(crate) fn reuse_recipe(&self, id: &ObjectId) -> Result<bool> {
+        if has_loose(self, KIND_RECIPES, id) {
+            return Ok(true);
+        }
+        if !self.freshen_packed(KIND_RECIPES, id)? {
+            return Ok(false);
+        }
+        for chunk in self.get_recipe(id)?.chunks {
+            if !self.reuse_blob(&chunk.blob)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// A promised blob (doc 16 §1g) is fetched here on first read.
     pub fn get_blob(&self, id: &ObjectId) -> Result<Vec<u8>> {
-        get_object(self, KIND_BLOBS, id)
+        match get_object(self, KIND_BLOBS, id) {
+            Err(_) if self.is_promised(id) => {
+                self.ensure_blobs(std::slice::from_ref(id))?;
+                get_object(self, KIND_BLOBS, id)
+            }
+            read => read,
+        }
     }
 
     pub fn put_manifest(&self, manifest: &Manifest) -> Result<ObjectId> {
@@ -85,6 +193,23 @@ impl LocalStore {
             .with_context(|| format!("parse manifest {}", id.as_str()))
     }
 
+    /// A directory's entries, read through its pages if it is paged
+    /// (arch doc 16 §1b). Walkers that want a directory's contents use
+    /// this; walkers that want its objects read the raw manifest.
+    pub fn get_dir(&self, id: &ObjectId) -> Result<Manifest> {
+        let entries = paging::flatten(self.get_manifest(id)?, |page| self.get_manifest(page))?;
+        Ok(Manifest {
+            version: 1,
+            entries,
+        })
+    }
+
+    /// Store a directory holding `entries`, paging it past the threshold.
+    pub fn put_dir(&self, entries: Vec<ManifestEntry>) -> Result<ObjectId> {
+        let manifest = paging::build(entries, |page| self.put_manifest(page))?;
+        self.put_manifest(&manifest)
+    }
+
     pub fn put_recipe(&self, recipe: &FileRecipe) -> Result<ObjectId> {
         let bytes = crate::model::encoding::encode_recipe(recipe);
         put_object(self, KIND_RECIPES, &bytes)
@@ -107,4 +232,59 @@ impl LocalStore {
         crate::model::encoding::decode_recipe(&bytes)
             .with_context(|| format!("parse recipe {}", id.as_str()))
     }
+
+    /// A verified object ready to send, and its plain size (doc 16 §1f).
+    ///
+    /// Kept in its stored encoding when the peer accepts that, encoded
+    /// now when it is stored plain and encoding is worth it, and plain
+    /// for a peer that accepts nothing — which is every older server.
+    pub(crate) fn transfer_frame(
+        &self,
+        kind: &str,
+        id: &ObjectId,
+        accept: Option<ObjectEncoding>,
+    ) -> Result<(ObjectFrame, u64)> {
+        let (bytes, encoding) = read_stored(self, kind, id)?;
+        let decoded = verified(self, kind, id, &bytes, encoding)?;
+        let accept = accept.filter(|_| encodable(kind));
+        let plain = match (decoded, encoding) {
+            (Some(plain), Some(stored)) if accept == Some(stored) => {
+                let frame = ObjectFrame::encoded(kind, id.clone(), bytes, stored);
+                return Ok((frame, plain.len() as u64));
+            }
+            (Some(plain), _) => plain,
+            (None, _) => bytes,
+        };
+        let len = plain.len() as u64;
+        let encoded = accept.and_then(|encoding| Some((encoding, compression::encode(&plain)?)));
+        let frame = match encoded {
+            Some((encoding, encoded)) => ObjectFrame::encoded(kind, id.clone(), encoded, encoding),
+            None => ObjectFrame::plain(kind, id.clone(), plain),
+        };
+        Ok((frame, len))
+    }
+}
+
+/// The fold reads and writes through the store (doc 15 §5h); a promised
+/// blob it needs is fetched like any other read.
+impl MergeObjects for LocalStore {
+    fn get_manifest(&self, id: &ObjectId) -> Result<Manifest> {
+        LocalStore::get_manifest(self, id)
+    }
+
+    fn put_manifest(&self, manifest: &Manifest) -> Result<ObjectId> {
+        LocalStore::put_manifest(self, manifest)
+    }
+
+    fn get_blob(&self, id: &ObjectId) -> Result<Vec<u8>> {
+        LocalStore::get_blob(self, id)
+    }
+
+    fn put_blob(&self, bytes: &[u8]) -> Result<ObjectId> {
+        LocalStore::put_blob(self, bytes)
+    }
+
+    fn get_recipe(&self, id: &ObjectId) -> Result<FileRecipe> {
+        LocalStore::get_recipe(self, id)
+    }
 }
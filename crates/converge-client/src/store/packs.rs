@@ -0,0 +1,416 @@
+//! Pack files (doc 16 §1h): small objects gathered into one file, found
+//! through a sorted index beside it.
+
+use std::fs;
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::{Context, Result, bail};
+
+use crate::model::{ObjectEncoding, ObjectId};
+
+use super::LocalStore;
+
+const PACK_MAGIC: &[u8; 8] = b"CVPACK\x00\x01";
+const INDEX_MAGIC: &[u8; 8] = b"CVINDX\x00\x01";
+/// kind, id, encoding, offset, length.
+const ENTRY_LEN: usize = 1 + 32 + 1 + 8 + 8;
+
+/// Objects stored at up to this size are packed. Larger ones stay
+/// loose: one open per read is nothing beside the read itself.
+pub(super) const PACK_OBJECT_MAX: u64 = 1024 * 1024;
+
+/// Past this many packs, the next gc folds them into one.
+pub(super) const MAX_PACKS: usize = 16;
+
+/// How often a process touches a pack it keeps finding objects in. Well
+/// inside gc's grace window, so a long-running capture stays covered.
+const FRESHEN_EVERY: Duration = Duration::from_secs(60);
+
+fn kind_code(kind: &str) -> Option<u8> {
+    match kind {
+        "blobs" => Some(0),
+        "manifests" => Some(1),
+        "recipes" => Some(2),
+        _ => None,
+    }
+}
+
+fn kind_name(code: u8) -> Option<&'static str> {
+    match code {
+        0 => Some("blobs"),
+        1 => Some("manifests"),
+        2 => Some("recipes"),
+        _ => None,
+    }
+}
+
+/// An id as the index keys it: the raw hash. An id that is not a
+/// blake3 hash cannot have been packed.
+fn raw_id(id: &ObjectId) -> Option<[u8; 32]> {
+    blake3::Hash::from_hex(id.as_str())
+        .ok()
+        .map(|hash| *hash.as_bytes())
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Entry {
+    kind: u8,
+    id: [u8; 32],
+    encoding: Option<ObjectEncoding>,
+    offset: u64,
+    len: u64,
+}
+
+struct Pack {
+    path: PathBuf,
+    /// Sorted by `(kind, id)`.
+    entries: Vec<Entry>,
+    modified: SystemTime,
+    /// When this process last touched the pack, if it has.
+    freshened: Mutex<Option<Instant>>,
+}
+
+impl Pack {
+    fn find(&self, code: u8, raw: [u8; 32]) -> Option<&Entry> {
+        self.entries
+            .binary_search_by(|e| (e.kind, e.id).cmp(&(code, raw)))
+            .ok()
+            .map(|at| &self.entries[at])
+    }
+
+    /// Move the pack's mtime to now, unless this process did lately.
+    fn freshen(&self) -> Result<()> {
+        let mut freshened = self.freshened.lock().expect("pack freshen lock");
+        if freshened.is_some_and(|at| at.elapsed() < FRESHEN_EVERY) {
+            return Ok(());
+        }
+        fs::OpenOptions::new()
+            .write(true)
+            .open(&self.path)
+            .and_then(|file| file.set_modified(SystemTime::now()))
+            .with_context(|| format!("freshen pack {}", self.path.display()))?;
+        *freshened = Some(Instant::now());
+        Ok(())
+    }
+}
+
+/// Where one packed object's stored bytes are.
+#[derive(Clone, Debug)]
+pub(super) struct Packed {
+    pub(super) kind: &'static str,
+    pub(super) id: ObjectId,
+    pub(super) encoding: Option<ObjectEncoding>,
+    pub(super) len: u64,
+    /// The pack's mtime when it was read: when it was written, or last
+    /// freshened by a capture that found an object in it.
+    pub(super) modified: SystemTime,
+    path: PathBuf,
+    offset: u64,
+}
+
+impl Packed {
+    pub(super) fn read(&self) -> Result<Vec<u8>> {
+        let mut file = fs::File::open(&self.path)
+            .with_context(|| format!("open pack {}", self.path.display()))?;
+        file.seek(SeekFrom::Start(self.offset))
+            .with_context(|| format!("seek pack {}", self.path.display()))?;
+        let mut bytes = vec![0; self.len as usize];
+        file.read_exact(&mut bytes)
+            .with_context(|| format!("read pack {}", self.path.display()))?;
+        Ok(bytes)
+    }
+}
+
+/// Every pack in a store, as read from their indexes.
+#[derive(Default)]
+pub(super) struct PackSet {
+    packs: Vec<Pack>,
+}
+
+impl PackSet {
+    fn load(dir: &Path) -> Result<Self> {
+        let mut packs = Vec::new();
+        let listing = match fs::read_dir(dir) {
+            Ok(listing) => listing,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(err).with_context(|| format!("read {}", dir.display())),
+        };
+        let mut indexes: Vec<PathBuf> = Vec::new();
+        for entry in listing {
+            let path = entry?.path();
+            if path.extension().is_some_and(|e| e == "idx") {
+                indexes.push(path);
+            }
+        }
+        indexes.sort();
+        for index in indexes {
+            // The pack is renamed into place before its index, so an
+            // index without a pack is one being deleted.
+            let path = index.with_extension("pack");
+            if !path.exists() {
+                continue;
+            }
+            let entries = read_index(&index)?;
+            let modified = fs::metadata(&path)
+                .and_then(|meta| meta.modified())
+                .with_context(|| format!("stat {}", path.display()))?;
+            packs.push(Pack {
+                path,
+                entries,
+                modified,
+                freshened: Mutex::new(None),
+            });
+        }
+        Ok(Self { packs })
+    }
+
+    pub(super) fn len(&self) -> usize {
+        self.packs.len()
+    }
+
+    pub(super) fn find(&self, kind: &str, id: &ObjectId) -> Option<Packed> {
+        let (code, raw) = (kind_code(kind)?, raw_id(id)?);
+        self.packs
+            .iter()
+            .find_map(|pack| Some(packed(pack, pack.find(code, raw)?)))
+    }
+
+    /// Whether a pack holds the object; if one does, it is freshened.
+    fn freshen(&self, kind: &str, id: &ObjectId) -> Result<bool> {
+        let (Some(code), Some(raw)) = (kind_code(kind), raw_id(id)) else {
+            return Ok(false);
+        };
+        match self
+            .packs
+            .iter()
+            .find(|pack| pack.find(code, raw).is_some())
+        {
+            Some(pack) => pack.freshen().map(|()| true),
+            None => Ok(false),
+        }
+    }
+
+    /// Every packed object, once each.
+    pub(super) fn objects(&self) -> Vec<Packed> {
+        let mut seen = std::collections::HashSet::new();
+        let mut out = Vec::new();
+        for pack in &self.packs {
+            for entry in &pack.entries {
+                if seen.insert((entry.kind, entry.id)) {
+                    out.push(packed(pack, entry));
+                }
+            }
+        }
+        out
+    }
+
+    pub(super) fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.packs.iter().map(|p| p.path.as_path())
+    }
+}
+
+fn packed(pack: &Pack, entry: &Entry) -> Packed {
+    Packed {
+        kind: kind_name(entry.kind).expect("index entries are checked on load"),
+        id: ObjectId(blake3::Hash::from(entry.id).to_hex().to_string()),
+        encoding: entry.encoding,
+        len: entry.len,
+        modified: pack.modified,
+        path: pack.path.clone(),
+        offset: entry.offset,
+    }
+}
+
+fn read_index(path: &Path) -> Result<Vec<Entry>> {
+    let bytes = fs::read(path).with_context(|| format!("read pack index {}", path.display()))?;
+    let Some(body) = bytes.strip_prefix(INDEX_MAGIC.as_slice()) else {
+        bail!("{} is not a pack index", path.display());
+    };
+    if body.len() % ENTRY_LEN != 0 {
+        bail!("pack index {} is truncated", path.display());
+    }
+    let mut entries = Vec::with_capacity(body.len() / ENTRY_LEN);
+    for raw in body.chunks_exact(ENTRY_LEN) {
+        let kind = raw[0];
+        let encoding = match raw[33] {
+            0 => None,
+            1 => Some(ObjectEncoding::Zstd),
+            other => bail!("pack index {} names encoding {other}", path.display()),
+        };
+        if kind_name(kind).is_none() {
+            bail!("pack index {} names object kind {kind}", path.display());
+        }
+        entries.push(Entry {
+            kind,
+            id: raw[1..33].try_into().expect("32 bytes"),
+            encoding,
+            offset: u64::from_le_bytes(raw[34..42].try_into().expect("8 bytes")),
+            len: u64::from_le_bytes(raw[42..50].try_into().expect("8 bytes")),
+        });
+    }
+    if !entries
+        .windows(2)
+        .all(|w| (w[0].kind, w[0].id) < (w[1].kind, w[1].id))
+    {
+        bail!("pack index {} is not sorted", path.display());
+    }
+    Ok(entries)
+}
+
+/// One object on its way into a pack, as stored.
+pub(super) struct Packing {
+    pub(super) kind: &'static str,
+    pub(super) id: ObjectId,
+    pub(super) encoding: Option<ObjectEncoding>,
+    pub(super) bytes: Vec<u8>,
+}
+
+/// Write `objects` as a new pack and its index; returns the pack's
+/// path. The pack is named for its content, so writing the same objects
+/// twice lands on the same name.
+fn write_pack(dir: &Path, objects: impl Iterator<Item = Result<Packing>>) -> Result<PathBuf> {
+    fs::create_dir_all(dir).with_context(|| format!("create {}", dir.display()))?;
+    let tmp = dir.join(format!("pack.tmp.{}", std::process::id()));
+    let mut entries = Vec::new();
+    let mut hasher = blake3::Hasher::new();
+    {
+        let file = fs::File::create(&tmp).with_context(|| format!("create {}", tmp.display()))?;
+        let mut out = BufWriter::new(file);
+        out.write_all(PACK_MAGIC)?;
+        let mut offset = PACK_MAGIC.len() as u64;
+        for object in objects {
+            let object = object?;
+            let (Some(kind), Some(id)) = (kind_code(object.kind), raw_id(&object.id)) else {
+                bail!("{} {} cannot be packed", object.kind, object.id.as_str());
+            };
+            out.write_all(&object.bytes)
+                .with_context(|| format!("write {}", tmp.display()))?;
+            hasher.update(&[kind, u8::from(object.encoding.is_some())]);
+            hasher.update(&id);
+            entries.push(Entry {
+                kind,
+                id,
+                encoding: object.encoding,
+                offset,
+                len: object.bytes.len() as u64,
+            });
+            offset += object.bytes.len() as u64;
+        }
+        let file = out
+            .into_inner()
+            .map_err(|err| err.into_error())
+            .with_context(|| format!("flush {}", tmp.display()))?;
+        file.sync_all()
+            .with_context(|| format!("fsync {}", tmp.display()))?;
+    }
+    entries.sort_by_key(|e| (e.kind, e.id));
+    entries.dedup_by_key(|e| (e.kind, e.id));
+
+    let name = format!("pack-{}", hasher.finalize().to_hex());
+    let pack = dir.join(format!("{name}.pack"));
+    fs::rename(&tmp, &pack)
+        .with_context(|| format!("rename {} -> {}", tmp.display(), pack.display()))?;
+    let mut index = Vec::with_capacity(INDEX_MAGIC.len() + entries.len() * ENTRY_LEN);
+    index.extend_from_slice(INDEX_MAGIC);
+    for entry in &entries {
+        index.push(entry.kind);
+        index.extend_from_slice(&entry.id);
+        index.push(match entry.encoding {
+            None => 0,
+            Some(ObjectEncoding::Zstd) => 1,
+        });
+        index.extend_from_slice(&entry.offset.to_le_bytes());
+        index.extend_from_slice(&entry.len.to_le_bytes());
+    }
+    // Last: the index is what makes the pack visible.
+    super::write_atomic(&pack.with_extension("idx"), &index)?;
+    Ok(pack)
+}
+
+impl LocalStore {
+    fn packs_dir(&self) -> PathBuf {
+        self.objects.join("packs")
+    }
+
+    /// The packs as last read. Cheap after the first call.
+    pub(super) fn pack_set(&self) -> Result<Arc<PackSet>> {
+        let mut cached = self.packs.lock().expect("pack cache lock");
+        if let Some(set) = &*cached {
+            return Ok(set.clone());
+        }
+        let set = Arc::new(PackSet::load(&self.packs_dir())?);
+        *cached = Some(set.clone());
+        Ok(set)
+    }
+
+    /// Read the packs again: another process may have written some.
+    pub(super) fn reload_packs(&self) -> Result<Arc<PackSet>> {
+        let set = Arc::new(PackSet::load(&self.packs_dir())?);
+        *self.packs.lock().expect("pack cache lock") = Some(set.clone());
+        Ok(set)
+    }
+
+    /// The object as stored in a pack, if it is in one. A miss reads the
+    /// indexes again before giving up.
+    pub(super) fn read_packed(
+        &self,
+        kind: &str,
+        id: &ObjectId,
+    ) -> Result<Option<(Vec<u8>, Option<ObjectEncoding>)>> {
+        if let Some(packed) = self.pack_set()?.find(kind, id)
+            && let Ok(bytes) = packed.read()
+        {
+            return Ok(Some((bytes, packed.encoding)));
+        }
+        match self.reload_packs()?.find(kind, id) {
+            Some(packed) => Ok(Some((packed.read()?, packed.encoding))),
+            None => Ok(None),
+        }
+    }
+
+    /// Whether a pack already holds the object, for a write that would
+    /// store it again. A capture that finds it there writes nothing, so
+    /// the pack is freshened instead: gc's grace window then covers the
+    /// object until the capture's snap record names it.
+    pub(super) fn freshen_packed(&self, kind: &str, id: &ObjectId) -> Result<bool> {
+        self.pack_set()?.freshen(kind, id)
+    }
+
+    /// Write a pack holding `objects`, then retire `replaced` packs.
+    pub(super) fn write_pack(
+        &self,
+        objects: impl Iterator<Item = Result<Packing>>,
+        replaced: &[PathBuf],
+    ) -> Result<()> {
+        let written = write_pack(&self.packs_dir(), objects)?;
+        let replaced: Vec<PathBuf> = replaced
+            .iter()
+            .filter(|p| **p != written)
+            .cloned()
+            .collect();
+        self.retire_packs(&replaced)
+    }
+
+    /// Delete packs whose objects are held elsewhere now, or by nothing.
+    pub(super) fn retire_packs(&self, packs: &[PathBuf]) -> Result<()> {
+        for pack in packs {
+            // Index first: a pack without one is invisible.
+            remove_if_present(&pack.with_extension("idx"))?;
+            remove_if_present(pack)?;
+        }
+        self.reload_packs()?;
+        Ok(())
+    }
+}
+
+pub(super) fn remove_if_present(path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+            Err(err).with_context(|| format!("remove {}", path.display()))
+        }
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,120 @@
+//! Promised blobs (doc 16 §1g): known to be on the remote, not yet local.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::sync::Arc;
+
+use anyhow::{Context, Result, bail};
+
+use crate::model::ObjectId;
+
+use super::{LocalStore, hash_bytes};
+
+const KIND_PROMISED: &str = "promised";
+
+/// Where a promised blob comes from when something first reads it.
+pub trait BlobSource: Send + Sync {
+    /// The bytes of every blob in `ids`, in any order. The store checks
+    /// each against its hash, so a source need not.
+    fn fetch_blobs(&self, ids: &[ObjectId]) -> Result<Vec<Vec<u8>>>;
+}
+
+impl LocalStore {
+    /// Read promised blobs through `source`. Without one, reading a
+    /// promised blob is an error naming what to do instead.
+    pub fn with_blob_source(mut self, source: Arc<dyn BlobSource>) -> Self {
+        self.blob_source = Some(source);
+        self
+    }
+
+    /// Record blobs as on the remote and not here. Blobs already local
+    /// are skipped; returns how many promises were written.
+    pub fn promise_blobs<'a>(&self, ids: impl IntoIterator<Item = &'a ObjectId>) -> Result<usize> {
+        let mut promised = 0;
+        for id in ids {
+            if self.has_blob(id) || self.is_promised(id) {
+                continue;
+            }
+            let path = self.object_path(KIND_PROMISED, id);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).context("create promised dir")?;
+            }
+            fs::write(&path, b"").with_context(|| format!("promise blob {}", id.as_str()))?;
+            promised += 1;
+        }
+        Ok(promised)
+    }
+
+    /// The blob is promised and has not arrived.
+    pub fn is_promised(&self, id: &ObjectId) -> bool {
+        self.object_path(KIND_PROMISED, id).exists() && !self.has_blob(id)
+    }
+
+    /// Local, or promised: a blob reading it will find.
+    pub fn knows_blob(&self, id: &ObjectId) -> bool {
+        self.has_blob(id) || self.is_promised(id)
+    }
+
+    /// Every blob still promised, sorted.
+    pub fn promised_blobs(&self) -> Result<Vec<ObjectId>> {
+        let dir = self.objects.join(KIND_PROMISED);
+        let mut out = Vec::new();
+        if !dir.is_dir() {
+            return Ok(out);
+        }
+        let mut stack = vec![dir];
+        while let Some(dir) = stack.pop() {
+            for entry in fs::read_dir(&dir).with_context(|| format!("read {}", dir.display()))? {
+                let entry = entry?;
+                if entry.file_type()?.is_dir() {
+                    stack.push(entry.path());
+                } else if let Some(name) = entry.file_name().to_str() {
+                    let id = ObjectId(name.to_string());
+                    if !self.has_blob(&id) {
+                        out.push(id);
+                    }
+                }
+            }
+        }
+        out.sort();
+        Ok(out)
+    }
+
+    /// Whether any blob was ever promised here: the store is partial,
+    /// and walkers may find blobs that are not local yet.
+    pub fn is_partial(&self) -> bool {
+        self.objects.join(KIND_PROMISED).is_dir()
+    }
+
+    /// Fetch whichever of `ids` are promised, in one request. Returns
+    /// how many arrived.
+    pub fn ensure_blobs(&self, ids: &[ObjectId]) -> Result<usize> {
+        let need: BTreeSet<&ObjectId> = ids.iter().filter(|id| self.is_promised(id)).collect();
+        if need.is_empty() {
+            return Ok(0);
+        }
+        let Some(source) = &self.blob_source else {
+            bail!(
+                "{} blob(s) were fetched lazily and are still on the remote; \
+                 configure the remote (`converge login`) or fetch without --lazy",
+                need.len()
+            );
+        };
+        let wanted: Vec<ObjectId> = need.iter().map(|id| (*id).clone()).collect();
+        for bytes in source
+            .fetch_blobs(&wanted)
+            .context("fetch promised blobs")?
+        {
+            let id = hash_bytes(&bytes);
+            if !need.contains(&id) {
+                bail!("remote sent blob {} which was not asked for", id.as_str());
+            }
+            self.put_blob(&bytes)?;
+            let _ = fs::remove_file(self.object_path(KIND_PROMISED, &id));
+        }
+        if let Some(missing) = wanted.iter().find(|id| !self.has_blob(id)) {
+            bail!("remote did not send promised blob {}", missing.as_str());
+        }
+        Ok(wanted.len())
+    }
+}
@@ -8,5 +8,7 @@ use crate::model::{Resolution, SnapRecord};
 use super::{LocalStore, write_atomic};
 
 mod head;
+mod recorded;
 mod resolutions;
+mod shelves;
 mod snaps;
@@ -0,0 +1,60 @@
+use super::*;
+
+use crate::model::{ResolutionDecision, VariantKey};
+
+impl LocalStore {
+    /// Remember how the superposition holding exactly `keys` was decided
+    /// (doc 17 §2c). The same lanes contesting the same content in a
+    /// later window produce the same keys, whatever the path or order.
+    pub fn put_recorded_decision(
+        &self,
+        keys: &[VariantKey],
+        decision: &ResolutionDecision,
+    ) -> Result<()> {
+        let bytes = serde_json::to_vec_pretty(decision).context("serialize recorded decision")?;
+        write_atomic(&self.recorded_decision_path(keys)?, &bytes).context("write recorded decision")
+    }
+
+    pub fn recorded_decision(&self, keys: &[VariantKey]) -> Result<Option<ResolutionDecision>> {
+        let path = self.recorded_decision_path(keys)?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        let bytes = fs::read(&path).with_context(|| format!("read {}", path.display()))?;
+        let decision = serde_json::from_slice(&bytes).context("parse recorded decision")?;
+        Ok(Some(decision))
+    }
+
+    /// Every recorded decision, in no particular order.
+    pub fn recorded_decisions(&self) -> Result<Vec<ResolutionDecision>> {
+        let dir = self.root.join("recorded");
+        let mut out = Vec::new();
+        if !dir.is_dir() {
+            return Ok(out);
+        }
+        for entry in fs::read_dir(&dir).context("read recorded dir")? {
+            let path = entry.context("read recorded dir entry")?.path();
+            if path.extension().and_then(|s| s.to_str()) != Some("json") {
+                continue;
+            }
+            let bytes = fs::read(&path).with_context(|| format!("read {}", path.display()))?;
+            out.push(serde_json::from_slice(&bytes).context("parse recorded decision")?);
+        }
+        Ok(out)
+    }
+
+    fn recorded_decision_path(&self, keys: &[VariantKey]) -> Result<PathBuf> {
+        let mut encoded = keys
+            .iter()
+            .map(serde_json::to_string)
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .context("serialize variant keys")?;
+        encoded.sort();
+        encoded.dedup();
+        let id = super::super::hash_bytes(encoded.join("\n").as_bytes());
+        Ok(self
+            .root
+            .join("recorded")
+            .join(format!("{}.json", id.as_str())))
+    }
+}
@@ -2,7 +2,7 @@ use super::*;
 
 impl LocalStore {
     pub fn put_resolution(&self, resolution: &Resolution) -> Result<()> {
-        if resolution.version != 1 && resolution.version != 2 {
+        if !(1..=3).contains(&resolution.version) {
             return Err(anyhow!("unsupported resolution version"));
         }
         let bytes = serde_json::to_vec_pretty(resolution).context("serialize resolution")?;
@@ -21,7 +21,7 @@ impl LocalStore {
             .join(format!("{}.json", candidate_id));
         let bytes = fs::read(&path).with_context(|| format!("read {}", path.display()))?;
         let r: Resolution = serde_json::from_slice(&bytes).context("parse resolution")?;
-        if r.version != 1 && r.version != 2 {
+        if !(1..=3).contains(&r.version) {
             return Err(anyhow!("unsupported resolution version"));
         }
         if r.candidate_id != candidate_id {
@@ -36,4 +36,24 @@ impl LocalStore {
             .join(format!("{}.json", candidate_id))
             .exists()
     }
+
+    /// Every resolution record, in no particular order.
+    pub fn list_resolutions(&self) -> Result<Vec<Resolution>> {
+        let dir = self.root.join("resolutions");
+        let mut out = Vec::new();
+        if !dir.is_dir() {
+            return Ok(out);
+        }
+        for entry in fs::read_dir(&dir).context("read resolutions dir")? {
+            let path = entry.context("read resolutions dir entry")?.path();
+            if let Some(candidate_id) = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .and_then(|n| n.strip_suffix(".json"))
+            {
+                out.push(self.get_resolution(candidate_id)?);
+            }
+        }
+        Ok(out)
+    }
 }
@@ -0,0 +1,57 @@
+use super::*;
+
+use crate::model::ShelfRecord;
+
+impl LocalStore {
+    pub fn put_shelf(&self, shelf: &ShelfRecord) -> Result<()> {
+        let bytes = serde_json::to_vec_pretty(shelf).context("serialize shelf")?;
+        write_atomic(&self.shelf_path(&shelf.name), &bytes).context("write shelf")
+    }
+
+    pub fn has_shelf(&self, name: &str) -> bool {
+        self.shelf_path(name).exists()
+    }
+
+    pub fn get_shelf(&self, name: &str) -> Result<ShelfRecord> {
+        let path = self.shelf_path(name);
+        if !path.exists() {
+            return Err(anyhow!("no shelf named {name}"));
+        }
+        let bytes = fs::read(&path).with_context(|| format!("read shelf {name}"))?;
+        serde_json::from_slice(&bytes).with_context(|| format!("parse shelf {name}"))
+    }
+
+    /// Every shelf, oldest first.
+    pub fn list_shelves(&self) -> Result<Vec<ShelfRecord>> {
+        let dir = self.root.join("shelves");
+        let mut out: Vec<ShelfRecord> = Vec::new();
+        if !dir.is_dir() {
+            return Ok(out);
+        }
+        for entry in fs::read_dir(&dir).context("read shelves dir")? {
+            let path = entry.context("read shelves dir entry")?.path();
+            if path.extension().and_then(|s| s.to_str()) != Some("json") {
+                continue;
+            }
+            let bytes = fs::read(&path).with_context(|| format!("read {}", path.display()))?;
+            out.push(
+                serde_json::from_slice(&bytes)
+                    .with_context(|| format!("parse shelf file {}", path.display()))?,
+            );
+        }
+        out.sort_by(|a, b| (&a.created_at, &a.name).cmp(&(&b.created_at, &b.name)));
+        Ok(out)
+    }
+
+    pub fn delete_shelf(&self, name: &str) -> Result<()> {
+        let path = self.shelf_path(name);
+        if path.exists() {
+            fs::remove_file(&path).with_context(|| format!("remove {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    fn shelf_path(&self, name: &str) -> PathBuf {
+        self.root.join("shelves").join(format!("{name}.json"))
+    }
+}
@@ -10,17 +10,35 @@ use crate::store::LocalStore;
 mod chunk_io;
 mod chunking;
 mod dirstamp;
+mod gc;
+mod ignore;
 mod manifest_query;
 mod manifest_scan;
+mod markers;
 mod materialize_fs;
+mod partial_snap;
 mod path_ops;
+mod pick;
 mod restore_materialize;
 mod root_lifecycle;
+mod shelf;
 mod snap_ops;
+mod sparse;
+mod stat_index;
 mod thinning;
+mod tree_edit;
+mod tree_watch;
 mod undo;
+mod worktree;
 
+pub use ignore::IgnoreMatch;
+pub use markers::SuperpositionMode;
+pub use pick::Replayed;
+pub use shelf::Unshelved;
+pub use stat_index::StatIndexReport;
+pub use tree_watch::{TreeChanges, TreeWatcher};
 pub use undo::Unsnapped;
+pub use worktree::WorktreeStatus;
 
 #[derive(Clone)]
 pub struct Workspace {
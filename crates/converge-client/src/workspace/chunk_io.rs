@@ -1,25 +1,27 @@
+use std::io::Read;
+
 use anyhow::Result;
 
-use crate::model::{ObjectId, chunk_data};
+use crate::model::{ChunkParams, FileRecipe, ObjectId, chunk_stream};
 use crate::store::LocalStore;
 use crate::store::hash_bytes;
 
-use super::chunking::ChunkingPolicy;
-
-pub(super) fn chunk_bytes_to_recipe_store(
+/// Chunk `source` into the store a chunk at a time (doc 16 §2). The
+/// recipe is returned unstored: a caller checking the file held still
+/// stores it only then.
+pub(super) fn chunk_into_store(
     store: &LocalStore,
-    data: &[u8],
-    policy: ChunkingPolicy,
-) -> Result<ObjectId> {
-    let (recipe, blobs) = chunk_data(data, policy.params);
-    for (_, slice) in &blobs {
-        store.put_blob(slice)?;
-    }
-    store.put_recipe(&recipe)
+    source: impl Read,
+    params: ChunkParams,
+) -> Result<FileRecipe> {
+    chunk_stream(source, params, |_, chunk| store.put_blob(chunk).map(drop))
+}
+
+/// The recipe `source` chunks to, storing nothing.
+pub(super) fn chunk_to_recipe(source: impl Read, params: ChunkParams) -> Result<FileRecipe> {
+    chunk_stream(source, params, |_, _| Ok(()))
 }
 
-pub(super) fn chunk_bytes_to_recipe_id(data: &[u8], policy: ChunkingPolicy) -> Result<ObjectId> {
-    let (recipe, _) = chunk_data(data, policy.params);
-    let bytes = crate::model::encoding::encode_recipe(&recipe);
-    Ok(hash_bytes(&bytes))
+pub(super) fn recipe_id(recipe: &FileRecipe) -> ObjectId {
+    hash_bytes(&crate::model::encoding::encode_recipe(recipe))
 }
@@ -1,18 +1,65 @@
-use anyhow::{Result, anyhow};
+use std::path::Path;
 
-use crate::model::{ChunkParams, ChunkingConfig};
+use anyhow::{Context, Result, anyhow, bail};
+use globset::{GlobBuilder, GlobMatcher};
+
+use crate::model::{ChunkParams, ChunkingAction, ChunkingConfig, ChunkingRule, WorkspaceConfig};
 
 const DEFAULT_CHUNK_THRESHOLD: u64 = 8 * 1024 * 1024;
 
-#[derive(Clone, Copy, Debug)]
+/// How a capture stores each file (doc 16 §2): the workspace's rules,
+/// then the profile's, then one size for everything else.
+#[derive(Clone, Debug)]
 pub(super) struct ChunkingPolicy {
-    pub(super) params: ChunkParams,
-    pub(super) threshold: u64,
+    params: ChunkParams,
+    threshold: u64,
+    rules: Vec<CompiledRule>,
+}
+
+#[derive(Clone, Debug)]
+struct CompiledRule {
+    matcher: GlobMatcher,
+    /// No `/` in the pattern: match the file name at any depth.
+    by_name: bool,
+    action: ChunkingAction,
+}
+
+impl ChunkingPolicy {
+    /// The parameters to chunk this file with, or `None` to store it
+    /// whole. `relative` is from the scan root.
+    pub(super) fn for_file(&self, relative: &Path, size: u64) -> Option<ChunkParams> {
+        let rule = self.rules.iter().find(|rule| {
+            if rule.by_name {
+                relative
+                    .file_name()
+                    .is_some_and(|name| rule.matcher.is_match(name))
+            } else {
+                rule.matcher.is_match(relative)
+            }
+        });
+        let (params, threshold) = match rule.map(|rule| &rule.action) {
+            Some(ChunkingAction::Never) => return None,
+            Some(ChunkingAction::Chunk { params, threshold }) => {
+                (*params, threshold.unwrap_or(self.threshold))
+            }
+            None => (self.params, self.threshold),
+        };
+        (size >= threshold).then_some(params)
+    }
+}
+
+pub(super) fn chunking_policy(cfg: &WorkspaceConfig) -> Result<ChunkingPolicy> {
+    let mut policy = chunking_policy_from_config(cfg.chunking.as_ref())?;
+    let own = cfg.chunking.iter().flat_map(|c| c.rules.iter().cloned());
+    for rule in own.chain(cfg.workflow_profile.chunking_rules()) {
+        policy.rules.push(compile(rule)?);
+    }
+    Ok(policy)
 }
 
 // Config carries a single `chunk_size`; it maps to the FastCDC average with
 // min = avg/4 and max = avg*4 (arch 16).
-pub(super) fn chunking_policy_from_config(cfg: Option<&ChunkingConfig>) -> Result<ChunkingPolicy> {
+fn chunking_policy_from_config(cfg: Option<&ChunkingConfig>) -> Result<ChunkingPolicy> {
     let threshold = cfg.map(|c| c.threshold).unwrap_or(DEFAULT_CHUNK_THRESHOLD);
     let params = match cfg.map(|c| c.chunk_size) {
         None => ChunkParams::default(),
@@ -26,5 +73,64 @@ pub(super) fn chunking_policy_from_config(cfg: Option<&ChunkingConfig>) -> Resul
             }
         }
     };
-    Ok(ChunkingPolicy { params, threshold })
+    check_params(&params).context("chunking.chunk_size")?;
+    Ok(ChunkingPolicy {
+        params,
+        threshold,
+        rules: Vec::new(),
+    })
+}
+
+fn compile(rule: ChunkingRule) -> Result<CompiledRule> {
+    let context = || format!("chunking rule {:?}", rule.pattern);
+    let matcher = GlobBuilder::new(&rule.pattern)
+        .literal_separator(true)
+        .build()
+        .with_context(context)?
+        .compile_matcher();
+    if let ChunkingAction::Chunk { params, .. } = &rule.action {
+        check_params(params).with_context(context)?;
+    }
+    Ok(CompiledRule {
+        matcher,
+        by_name: !rule.pattern.contains('/'),
+        action: rule.action,
+    })
+}
+
+/// FastCDC asserts on its bounds rather than returning an error, so a
+/// typo in config.json would otherwise be a panic mid-capture.
+fn check_params(params: &ChunkParams) -> Result<()> {
+    use fastcdc_bounds::*;
+    let ChunkParams {
+        min_size,
+        avg_size,
+        max_size,
+    } = *params;
+    if !(MIN.0..=MIN.1).contains(&min_size)
+        || !(AVG.0..=AVG.1).contains(&avg_size)
+        || !(MAX.0..=MAX.1).contains(&max_size)
+    {
+        bail!(
+            "chunk sizes {min_size}/{avg_size}/{max_size} are out of range: \
+             min {}..={}, avg {}..={}, max {}..={}",
+            MIN.0,
+            MIN.1,
+            AVG.0,
+            AVG.1,
+            MAX.0,
+            MAX.1
+        );
+    }
+    if !(min_size <= avg_size && avg_size <= max_size) {
+        bail!("chunk sizes {min_size}/{avg_size}/{max_size} must satisfy min <= avg <= max");
+    }
+    Ok(())
+}
+
+/// FastCDC v2020's accepted ranges, as (lowest, highest).
+mod fastcdc_bounds {
+    pub(super) const MIN: (u32, u32) = (64, 1024 * 1024);
+    pub(super) const AVG: (u32, u32) = (256, 4 * 1024 * 1024);
+    pub(super) const MAX: (u32, u32) = (1024, 16 * 1024 * 1024);
 }
@@ -3,9 +3,8 @@ use std::path::Path;
 use anyhow::{Context, Result, anyhow};
 
 use super::Workspace;
-use super::manifest_scan::common::{
-    is_ignored, load_root_ignores, read_dir_sorted, should_ignore_name,
-};
+use super::ignore::IgnoreRules;
+use super::manifest_scan::common::{read_dir_sorted, should_ignore_name, tree_path};
 
 /// Cheap change detector for the working tree (batch 15.3).
 ///
@@ -20,7 +19,7 @@ use super::manifest_scan::common::{
 /// whose miss path is the real scan — snap and publish always rescan.
 impl Workspace {
     pub fn dirstamp(&self) -> Result<String> {
-        let ignores = load_root_ignores(&self.root);
+        let ignores = IgnoreRules::root(&self.root);
         let mut hasher = blake3::Hasher::new();
         stamp_dir(&self.root, &self.root, &ignores, &mut hasher)?;
         Ok(hasher.finalize().to_hex().to_string())
@@ -30,7 +29,7 @@ impl Workspace {
 fn stamp_dir(
     scan_root: &Path,
     dir: &Path,
-    root_ignores: &std::collections::HashSet<String>,
+    ignores: &IgnoreRules,
     hasher: &mut blake3::Hasher,
 ) -> Result<()> {
     for child in read_dir_sorted(dir)? {
@@ -42,23 +41,25 @@ fn stamp_dir(
             continue;
         }
         let path = child.path();
-        // The same rule the scan uses (batch 22.4). A dirstamp that
+        let file_type = child.file_type().context("read file type")?;
+        let tree_path = tree_path(path.strip_prefix(scan_root).unwrap_or(&path));
+        // The same rules the scan uses (batch 22.4). A dirstamp that
         // disagreed with the scan would either miss changes or force a
         // rescan on every tick — the cache would be worse than none.
-        if is_ignored(
-            root_ignores,
-            path.strip_prefix(scan_root).unwrap_or(&path),
-            &name,
-        ) {
+        if ignores.is_ignored(&tree_path, file_type.is_dir()) {
             continue;
         }
 
-        let file_type = child.file_type().context("read file type")?;
         hasher.update(name.as_bytes());
 
         if file_type.is_dir() {
             hasher.update(b"d");
-            stamp_dir(scan_root, &path, root_ignores, hasher)?;
+            stamp_dir(
+                scan_root,
+                &path,
+                &ignores.descend(&path, &tree_path),
+                hasher,
+            )?;
             hasher.update(b"/");
             continue;
         }
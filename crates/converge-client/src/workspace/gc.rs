@@ -0,0 +1,67 @@
+use std::time::Duration;
+
+use crate::model::{ResolutionDecision, ResolvedContentKind};
+use crate::store::{GcRoots, LocalGcReport};
+
+use super::markers::MarkerState;
+use super::*;
+
+impl Workspace {
+    /// Local garbage collection (doc 16 §1h): sweep the objects nothing
+    /// here reaches, and pack the small ones that are left. "Here" is
+    /// every workspace sharing the objects: the primary and its linked
+    /// worktrees (doc 15 §5f).
+    pub fn collect_garbage(&self, dry_run: bool, grace: Duration) -> Result<LocalGcReport> {
+        let mut roots = GcRoots::default();
+        for ws in self.sharing_objects()? {
+            ws.add_gc_roots(&mut roots)
+                .with_context(|| format!("roots of the workspace at {}", ws.root.display()))?;
+        }
+        self.store.collect_garbage(&roots, dry_run, grace)
+    }
+
+    /// Add what this workspace still refers to: every snap record — head and
+    /// pinned snaps among them — every resolution and the content its
+    /// decisions name, decisions recorded for reuse, the tree
+    /// conflict-marker files were written from, and every shelf with the
+    /// base it unshelves against.
+    ///
+    /// A candidate fetched but never checked out or resolved is not a
+    /// root; it can be fetched again.
+    fn add_gc_roots(&self, roots: &mut GcRoots) -> Result<()> {
+        let snaps = self.store.list_snaps()?;
+        // Pinned snaps are kept by keeping their records, which nothing
+        // here deletes; a head without one is a store to repair first.
+        if let Some(head) = self.store.get_head()?
+            && !snaps.iter().any(|s| s.id == head)
+        {
+            anyhow::bail!("head {head} has no snap record; nothing swept");
+        }
+        roots
+            .manifests
+            .extend(snaps.into_iter().map(|s| s.root_manifest));
+
+        let mut decisions = self.store.recorded_decisions()?;
+        for resolution in self.store.list_resolutions()? {
+            roots.manifests.push(resolution.root_manifest);
+            decisions.extend(resolution.decisions.into_values());
+        }
+        for decision in decisions {
+            if let ResolutionDecision::Content(content) = decision {
+                match content.content {
+                    ResolvedContentKind::File { blob, .. } => roots.blobs.push(blob),
+                    ResolvedContentKind::ChunkedFile { recipe, .. } => roots.recipes.push(recipe),
+                }
+            }
+        }
+        if let Some(root) = MarkerState::load(&self.store)?.root() {
+            roots.manifests.push(root.clone());
+        }
+        // A shelf is the only copy of what it holds (doc 15 §5e).
+        for shelf in self.store.list_shelves()? {
+            roots.manifests.push(shelf.root_manifest);
+            roots.manifests.push(shelf.base_root);
+        }
+        Ok(())
+    }
+}
@@ -2,22 +2,40 @@ use super::*;
 
 use crate::model::Manifest;
 
+use super::stat_index::StatIndex;
+
 impl Workspace {
     /// Compute a manifest tree for the current working directory without writing a snap.
     ///
-    /// Note: this still reads file contents to compute stable blob ids.
+    /// Note: this still reads the content of every file the stat index
+    /// (doc 15 §5c) cannot vouch for, to compute stable blob ids.
     pub fn current_manifest_tree(
         &self,
+    ) -> Result<(ObjectId, HashMap<ObjectId, Manifest>, SnapStats)> {
+        let index = StatIndex::open(&self.store);
+        let scanned = self.scan_in_memory(&index)?;
+        // A read-only workspace still answers; it just hashes again.
+        let _ = index.save(true);
+        Ok(scanned)
+    }
+
+    pub(super) fn scan_in_memory(
+        &self,
+        index: &StatIndex,
     ) -> Result<(ObjectId, HashMap<ObjectId, Manifest>, SnapStats)> {
         let cfg = self.store.read_config()?;
-        let policy = chunking::chunking_policy_from_config(cfg.chunking.as_ref())?;
+        let policy = chunking::chunking_policy(&cfg)?;
         let mut stats = SnapStats::default();
         let mut manifests: HashMap<ObjectId, Manifest> = HashMap::new();
         let root_manifest = manifest_scan::build_manifest_in_memory(
-            &self.root,
+            self,
             &mut stats,
             &mut manifests,
-            policy,
+            &policy,
+            &super::markers::MarkerState::load(&self.store)?,
+            &super::sparse::Sparse::new(&cfg.sparse)?,
+            index,
+            manifest_scan::common::scan_threads(&cfg),
         )?;
         Ok((root_manifest, manifests, stats))
     }
@@ -1,59 +1,39 @@
 use std::fs;
-use std::path::Path;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
 use anyhow::{Context, Result, anyhow};
 
-/// Root-level ignore patterns from `.convergeignore` (arch doc 18 §3):
-/// exact names, `dir/` forms. No negations or nesting — documented.
-pub(in crate::workspace) fn load_root_ignores(
-    root: &std::path::Path,
-) -> std::collections::HashSet<String> {
-    let mut out = std::collections::HashSet::new();
-    if let Ok(text) = std::fs::read_to_string(root.join(".convergeignore")) {
-        for line in text.lines() {
-            let line = line.trim();
-            if line.is_empty() || line.starts_with('#') || line.starts_with('!') {
-                continue;
-            }
-            out.insert(line.trim_end_matches('/').to_string());
-        }
-    }
-    out
-}
+use crate::model::{
+    ChunkParams, FileRecipe, ManifestEntry, ManifestEntryKind, ObjectId, SnapStats, WorkspaceConfig,
+};
+
+use super::super::chunking::ChunkingPolicy;
+use super::super::markers::MarkerState;
+use super::super::stat_index::{FileStat, Indexed, StatIndex};
 
 pub(in crate::workspace) fn should_ignore_name(name: &str) -> bool {
     matches!(name, ".converge" | ".git")
 }
 
-/// Does `.convergeignore` exclude this entry?
-///
-/// A bare name matches **at any depth**, the way `.gitignore` does. A rule
-/// containing a slash is anchored to the workspace root.
-///
-/// Batch 22.4 found why this matters, on the first real project: rules
-/// were matched only against the top level, so `target` excluded a root
-/// build directory and silently captured `crates/todo-core/target` — 18 MB
-/// and some seventeen hundred files, in a project with about forty real
-/// ones. Every Rust workspace with nested crates and every JS monorepo
-/// hits that immediately.
-pub(in crate::workspace) fn is_ignored(
-    ignores: &std::collections::HashSet<String>,
-    relative: &Path,
-    name: &str,
-) -> bool {
-    if ignores.contains(name) {
-        return true;
-    }
-    // Anchored rules: compare against the path from the workspace root,
-    // with `/` separators so a rule reads the same on every platform.
-    let rel = relative
+/// A path relative to the scan root as a tree path: `/`-separated, so a
+/// rule or a recorded path reads the same on every platform.
+pub(in crate::workspace) fn tree_path(relative: &Path) -> String {
+    relative
         .components()
         .map(|c| c.as_os_str().to_string_lossy())
         .collect::<Vec<_>>()
-        .join("/");
-    ignores
-        .iter()
-        .any(|rule| rule.contains('/') && rule == &rel)
+        .join("/")
+}
+
+/// `name` inside the directory at `prefix`.
+pub(in crate::workspace) fn join(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_string()
+    } else {
+        format!("{prefix}/{name}")
+    }
 }
 
 pub(in crate::workspace) fn read_dir_sorted(dir: &Path) -> Result<Vec<fs::DirEntry>> {
@@ -79,18 +59,21 @@ pub(super) fn symlink_target(path: &Path) -> Result<String> {
 }
 
 pub(super) fn file_mode(path: &Path) -> Result<u32> {
+    let meta = fs::symlink_metadata(path).with_context(|| format!("stat {}", path.display()))?;
+    Ok(mode_of(&meta))
+}
+
+pub(in crate::workspace) fn mode_of(meta: &fs::Metadata) -> u32 {
     #[cfg(unix)]
     {
         use std::os::unix::fs::PermissionsExt;
-        let meta =
-            fs::symlink_metadata(path).with_context(|| format!("stat {}", path.display()))?;
-        Ok(meta.permissions().mode())
+        meta.permissions().mode()
     }
 
     #[cfg(not(unix))]
     {
-        let _ = path;
-        Ok(0)
+        let _ = meta;
+        0
     }
 }
 
@@ -109,19 +92,36 @@ fn os_str_bytes(s: &std::ffi::OsStr) -> Vec<u8> {
 /// would otherwise snap torn bytes (silent small-file truncation) or
 /// record a stale size. Bounded retries, then a loud failure instead
 /// of a torn snapshot.
-pub(super) fn read_file_stable(path: &Path) -> Result<(Vec<u8>, u64)> {
+pub(in crate::workspace) fn read_file_stable(path: &Path) -> Result<(Vec<u8>, u64)> {
+    stream_file_stable(path, |mut file| {
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)
+            .with_context(|| format!("read file {}", path.display()))?;
+        let len = bytes.len() as u64;
+        Ok((bytes, len))
+    })
+}
+
+/// `read_file_stable` for a file too big to hold: `consume` reads the
+/// open file however it likes and says how many bytes it read, and the
+/// same re-stat decides whether its result stands.
+pub(in crate::workspace) fn stream_file_stable<T>(
+    path: &Path,
+    mut consume: impl FnMut(fs::File) -> Result<(T, u64)>,
+) -> Result<(T, u64)> {
     const ATTEMPTS: u32 = 3;
     for _ in 0..ATTEMPTS {
         let before =
             fs::symlink_metadata(path).with_context(|| format!("stat {}", path.display()))?;
-        let bytes = fs::read(path).with_context(|| format!("read file {}", path.display()))?;
+        let file = fs::File::open(path).with_context(|| format!("read file {}", path.display()))?;
+        let (out, len) = consume(file)?;
         let after =
             fs::symlink_metadata(path).with_context(|| format!("stat {}", path.display()))?;
         if before.len() == after.len()
-            && bytes.len() as u64 == after.len()
+            && len == after.len()
             && before.modified().ok() == after.modified().ok()
         {
-            return Ok((bytes, after.len()));
+            return Ok((out, len));
         }
     }
     anyhow::bail!(
@@ -129,3 +129,212 @@ pub(super) fn read_file_stable(path: &Path) -> Result<(Vec<u8>, u64)> {
         path.display()
     )
 }
+
+/// A file the stat index could not vouch for, waiting to be read.
+pub(super) struct Unread {
+    pub(super) name: String,
+    pub(super) path: PathBuf,
+    pub(super) tree_path: String,
+    /// As `stat` described it before anything read it.
+    pub(super) stat: FileStat,
+}
+
+impl Unread {
+    /// Remember what the file held, but only when the bytes read are the
+    /// ones `stat` described.
+    pub(super) fn record(&self, index: Option<&StatIndex>, size: u64, content: Indexed) {
+        if let Some(index) = index.filter(|_| size == self.stat.size()) {
+            index.record(&self.tree_path, self.stat, content);
+        }
+    }
+}
+
+/// A file the rules chunk, streamed through `chunk` rather than read
+/// whole, so memory stays bounded by the chunk size (doc 16 §2).
+/// `None` when it has to be read whole after all: a marker file, whose
+/// every byte decides whether it is still a superposition, or one that
+/// no longer chunks at the size it was read at.
+pub(super) fn stream_chunked(
+    policy: &ChunkingPolicy,
+    markers: &MarkerState,
+    relative: &Path,
+    file: &Unread,
+    chunk: impl Fn(fs::File, ChunkParams) -> Result<FileRecipe>,
+) -> Result<Option<(FileRecipe, ChunkParams)>> {
+    let Some(params) = policy.for_file(relative, file.stat.size()) else {
+        return Ok(None);
+    };
+    if markers.marks(&file.tree_path) {
+        return Ok(None);
+    }
+    let (recipe, size) = stream_file_stable(&file.path, |source| {
+        let recipe = chunk(source, params)?;
+        let size = recipe.size;
+        Ok((recipe, size))
+    })?;
+    Ok((policy.for_file(relative, size) == Some(params)).then_some((recipe, params)))
+}
+
+/// The workers a scan reads files on (doc 15 §5d).
+pub(in crate::workspace) fn scan_threads(cfg: &WorkspaceConfig) -> usize {
+    cfg.scan_threads
+        .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get()))
+        .max(1)
+}
+
+/// One directory's entries as the walk finds them.
+///
+/// Files the stat index cannot vouch for are not read where they are
+/// found: they go on the scan's one queue, and the whole queue is read on
+/// one pool of workers after the walk (doc 15 §5d). A tree of many small
+/// directories then keeps every worker busy, where reading each
+/// directory's few files on their own would leave most of them idle.
+#[derive(Default)]
+pub(super) struct Listing {
+    slots: Vec<(String, Slot)>,
+    waiting: bool,
+}
+
+/// An entry of a listed directory.
+pub(super) enum Slot {
+    Known(ManifestEntryKind),
+    /// The file at this index of the scan's queue.
+    Queued(usize),
+    /// A directory that cannot be written until queued files below it
+    /// are read.
+    Waiting(Listing),
+}
+
+impl Listing {
+    pub(super) fn push(&mut self, name: String, kind: ManifestEntryKind) {
+        self.slots.push((name, Slot::Known(kind)));
+    }
+
+    pub(super) fn queue(&mut self, queue: &mut Vec<Unread>, file: Unread) {
+        self.slots
+            .push((file.name.clone(), Slot::Queued(queue.len())));
+        self.waiting = true;
+        queue.push(file);
+    }
+
+    pub(super) fn push_dir(&mut self, name: String, dir: Slot) {
+        self.waiting |= matches!(dir, Slot::Waiting(_));
+        self.slots.push((name, dir));
+    }
+
+    /// This directory as its parent holds it: written now through `put`
+    /// when nothing below it waits on the queue, so a scan the index
+    /// mostly vouches for holds no more than it did directory by
+    /// directory.
+    pub(super) fn finish(
+        self,
+        put: impl FnOnce(Vec<ManifestEntry>) -> Result<ObjectId>,
+    ) -> Result<Slot> {
+        if self.waiting {
+            return Ok(Slot::Waiting(self));
+        }
+        let entries = self
+            .slots
+            .into_iter()
+            .map(|(name, slot)| match slot {
+                Slot::Known(kind) => ManifestEntry { name, kind },
+                Slot::Queued(_) | Slot::Waiting(_) => unreachable!("nothing waits here"),
+            })
+            .collect();
+        Ok(Slot::Known(ManifestEntryKind::Dir {
+            manifest: put(entries)?,
+        }))
+    }
+}
+
+/// Read the queue with `read` on up to `threads` workers, then write the
+/// directories that waited on it. `root` is the scan root as its walk
+/// finished it. Each file read counts into `stats` here.
+pub(super) fn settle(
+    root: Slot,
+    queue: &[Unread],
+    threads: usize,
+    stats: &mut SnapStats,
+    read: impl Fn(&Unread) -> Result<(ManifestEntryKind, u64)> + Sync,
+    mut put: impl FnMut(Vec<ManifestEntry>) -> Result<ObjectId>,
+) -> Result<ObjectId> {
+    let mut read: Vec<Option<(ManifestEntryKind, u64)>> = map_ordered(threads, queue, read)?
+        .into_iter()
+        .map(Some)
+        .collect();
+    match root {
+        Slot::Known(ManifestEntryKind::Dir { manifest }) => Ok(manifest),
+        Slot::Waiting(listing) => write_waiting(listing, &mut read, stats, &mut put),
+        Slot::Known(_) | Slot::Queued(_) => unreachable!("a scan root is a directory"),
+    }
+}
+
+fn write_waiting(
+    listing: Listing,
+    read: &mut [Option<(ManifestEntryKind, u64)>],
+    stats: &mut SnapStats,
+    put: &mut impl FnMut(Vec<ManifestEntry>) -> Result<ObjectId>,
+) -> Result<ObjectId> {
+    let mut entries = Vec::with_capacity(listing.slots.len());
+    for (name, slot) in listing.slots {
+        let kind = match slot {
+            Slot::Known(kind) => kind,
+            Slot::Queued(at) => {
+                let (kind, bytes) = read[at].take().expect("each queued file is read once");
+                stats.files += 1;
+                stats.bytes += bytes;
+                kind
+            }
+            Slot::Waiting(dir) => ManifestEntryKind::Dir {
+                manifest: write_waiting(dir, read, stats, put)?,
+            },
+        };
+        entries.push(ManifestEntry { name, kind });
+    }
+    put(entries)
+}
+
+/// `f` over `items` on up to `threads` scoped workers, with results in
+/// the order of `items` whichever finished first — so the manifest is
+/// the one a single thread would build. After an error no new item is
+/// started, and the earliest failing item's error is returned.
+fn map_ordered<T, R, F>(threads: usize, items: &[T], f: F) -> Result<Vec<R>>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> Result<R> + Sync,
+{
+    let workers = threads.min(items.len());
+    if workers <= 1 {
+        return items.iter().map(f).collect();
+    }
+    let next = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
+    let mut done: Vec<(usize, Result<R>)> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut out = Vec::new();
+                    while !failed.load(Ordering::Relaxed) {
+                        let at = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(item) = items.get(at) else {
+                            break;
+                        };
+                        let result = f(item);
+                        if result.is_err() {
+                            failed.store(true, Ordering::Relaxed);
+                        }
+                        out.push((at, result));
+                    }
+                    out
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap_or_else(|p| std::panic::resume_unwind(p)))
+            .collect()
+    });
+    done.sort_by_key(|(at, _)| *at);
+    done.into_iter().map(|(_, result)| result).collect()
+}
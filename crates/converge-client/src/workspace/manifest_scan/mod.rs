@@ -1,46 +1,295 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::Path;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 
-use crate::model::{Manifest, ObjectId, SnapStats};
+use crate::model::{
+    Manifest, ManifestEntry, ManifestEntryKind, ObjectId, ResolvedContentKind, SnapStats,
+};
+use crate::store::LocalStore;
 
 use super::Workspace;
 use super::chunking::ChunkingPolicy;
+use super::ignore::IgnoreRules;
+use super::markers::MarkerState;
+use super::sparse::{Cover, Sparse};
+use super::stat_index::StatIndex;
+use super::tree_watch::TreeChanges;
 
 pub(in crate::workspace) mod common;
 mod scan_memory;
 mod scan_store;
 
 use self::scan_memory::build_manifest_in_memory_impl;
-use self::scan_store::build_manifest_store_impl;
+use self::scan_store::{build_manifest_store_impl, rescan_store_impl};
 
 impl Workspace {
     /// Build a manifest of an arbitrary directory into this workspace's
     /// store (git import extracts historical trees this way).
     pub fn build_manifest_of(&self, dir: &Path, stats: &mut SnapStats) -> Result<ObjectId> {
         let cfg = self.store.read_config()?;
-        let policy = super::chunking::chunking_policy_from_config(cfg.chunking.as_ref())?;
-        self.build_manifest(dir, stats, policy)
+        let policy = super::chunking::chunking_policy(&cfg)?;
+        let ignores = IgnoreRules::root(dir);
+        build_manifest_store_impl(
+            self,
+            dir,
+            dir,
+            &ignores,
+            stats,
+            &policy,
+            &MarkerState::default(),
+            None,
+            common::scan_threads(&cfg),
+            None,
+        )
     }
 
+    /// Capture one file into the store the way a snap would hold it at
+    /// `tree_path` — whole or chunked by the same rules — as content for
+    /// a hand-edited resolution (doc 17 §2b). The file itself may live
+    /// anywhere; only its bytes and mode are taken.
+    pub fn capture_file(&self, tree_path: &str, file: &Path) -> Result<ResolvedContentKind> {
+        let cfg = self.store.read_config()?;
+        let policy = super::chunking::chunking_policy(&cfg)?;
+        let meta =
+            std::fs::symlink_metadata(file).with_context(|| format!("stat {}", file.display()))?;
+        if !meta.is_file() {
+            anyhow::bail!("{} is not a regular file", file.display());
+        }
+        let mode = common::mode_of(&meta);
+        let tree_path = Path::new(tree_path);
+        // Streamed when the rules chunk it, as the scans do (doc 16 §2).
+        let recipe = match policy.for_file(tree_path, meta.len()) {
+            Some(params) => {
+                common::stream_file_stable(file, |source| {
+                    let recipe = super::chunk_io::chunk_into_store(&self.store, source, params)?;
+                    let size = recipe.size;
+                    Ok((recipe, size))
+                })?
+                .0
+            }
+            None => {
+                let (bytes, size) = common::read_file_stable(file)?;
+                let Some(params) = policy.for_file(tree_path, size) else {
+                    return Ok(ResolvedContentKind::File {
+                        blob: self.store.put_blob(&bytes)?,
+                        mode,
+                        size,
+                    });
+                };
+                super::chunk_io::chunk_into_store(&self.store, bytes.as_slice(), params)?
+            }
+        };
+        Ok(ResolvedContentKind::ChunkedFile {
+            size: recipe.size,
+            recipe: self.store.put_recipe(&recipe)?,
+            mode,
+        })
+    }
+
+    /// Scan the working tree into the store. Under a sparse set, what
+    /// the set leaves off disk is head's (doc 15 §5b).
     pub(super) fn build_manifest(
         &self,
-        dir: &Path,
         stats: &mut SnapStats,
-        policy: ChunkingPolicy,
+        policy: &ChunkingPolicy,
+        markers: &MarkerState,
+        sparse: &Sparse,
+        threads: usize,
+    ) -> Result<ObjectId> {
+        let ignores = IgnoreRules::root(&self.root);
+        let carry = self.sparse_carry(sparse)?;
+        let index = StatIndex::open(&self.store);
+        let root = build_manifest_store_impl(
+            self,
+            &self.root,
+            &self.root,
+            &ignores,
+            stats,
+            policy,
+            markers,
+            Some(&index),
+            threads,
+            carry.as_ref(),
+        )?;
+        // The snap stands without it; the next scan is only slower.
+        let _ = index.save(true);
+        Ok(root)
+    }
+
+    /// The workspace tree as `previous` — the tree it held at the last
+    /// capture — plus `changes`, reading only the directories they reach
+    /// (doc 15 §5a). `stats` counts just what was read.
+    #[allow(clippy::too_many_arguments)]
+    pub(super) fn rescan_manifest(
+        &self,
+        previous: &ObjectId,
+        changes: &TreeChanges,
+        stats: &mut SnapStats,
+        policy: &ChunkingPolicy,
+        markers: &MarkerState,
+        sparse: &Sparse,
+        threads: usize,
     ) -> Result<ObjectId> {
-        let ignores = common::load_root_ignores(dir);
-        build_manifest_store_impl(self, dir, dir, &ignores, stats, policy)
+        let ignores = IgnoreRules::root(&self.root);
+        let sparse = (!sparse.is_everything()).then_some(sparse);
+        let carry = Carry::load(&self.store, Some(previous), sparse, Some(changes))?;
+        let index = StatIndex::open(&self.store);
+        let root = rescan_store_impl(
+            self,
+            &self.root,
+            &self.root,
+            "",
+            &ignores,
+            stats,
+            policy,
+            markers,
+            Some(&index),
+            threads,
+            &carry,
+        )?;
+        let _ = index.save(false);
+        Ok(root)
+    }
+
+    /// What the root of a scan takes from head under `sparse`; nothing
+    /// for a full workspace, or one with no head yet.
+    fn sparse_carry<'a>(&self, sparse: &'a Sparse) -> Result<Option<Carry<'a>>> {
+        if sparse.is_everything() {
+            return Ok(None);
+        }
+        let head = match self.store.get_head()? {
+            Some(head) => Some(self.store.get_snap(&head)?.root_manifest),
+            None => None,
+        };
+        Carry::load(&self.store, head.as_ref(), Some(sparse), None).map(Some)
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub(super) fn build_manifest_in_memory(
-    dir: &Path,
+    workspace: &Workspace,
     stats: &mut SnapStats,
     manifests: &mut HashMap<ObjectId, Manifest>,
-    policy: ChunkingPolicy,
+    policy: &ChunkingPolicy,
+    markers: &MarkerState,
+    sparse: &Sparse,
+    index: &StatIndex,
+    threads: usize,
 ) -> Result<ObjectId> {
-    let ignores = common::load_root_ignores(dir);
-    build_manifest_in_memory_impl(dir, dir, &ignores, stats, manifests, policy)
+    let dir = &workspace.root;
+    let ignores = IgnoreRules::root(dir);
+    let carry = workspace.sparse_carry(sparse)?;
+    build_manifest_in_memory_impl(
+        &workspace.store,
+        dir,
+        dir,
+        &ignores,
+        stats,
+        manifests,
+        policy,
+        markers,
+        Some(index),
+        threads,
+        carry.as_ref(),
+    )
+}
+
+/// What a scan of one directory takes from head instead of disk.
+///
+/// Two callers need it. Under a sparse set (doc 15 §5b) a directory only
+/// partly on disk keeps head's entries for whatever the set leaves out:
+/// those paths are not deleted, just not here. And a watch's rescan
+/// (§5a) keeps head's manifest for every subdirectory its changes do not
+/// reach.
+pub(super) struct Carry<'a> {
+    /// Head's entries for this directory.
+    previous: BTreeMap<String, ManifestEntryKind>,
+    /// Set while this directory is only partly selected.
+    sparse: Option<&'a Sparse>,
+    changes: Option<&'a TreeChanges>,
+}
+
+impl<'a> Carry<'a> {
+    fn load(
+        store: &LocalStore,
+        previous: Option<&ObjectId>,
+        sparse: Option<&'a Sparse>,
+        changes: Option<&'a TreeChanges>,
+    ) -> Result<Self> {
+        let previous = match previous {
+            Some(id) => store
+                .get_dir(id)?
+                .entries
+                .into_iter()
+                .map(|e| (e.name, e.kind))
+                .collect(),
+            None => BTreeMap::new(),
+        };
+        Ok(Self {
+            previous,
+            sparse,
+            changes,
+        })
+    }
+
+    /// How much of the child at `tree_path` the sparse set puts on disk.
+    fn cover(&self, tree_path: &str, is_dir: bool) -> Cover {
+        self.sparse
+            .map_or(Cover::All, |s| s.cover(tree_path, is_dir))
+    }
+
+    /// Head's manifest for subdirectory `name`, if nothing below it has
+    /// changed since.
+    fn unchanged(&self, name: &str, tree_path: &str) -> Option<ObjectId> {
+        match (self.changes, self.previous.get(name)) {
+            (Some(changes), Some(ManifestEntryKind::Dir { manifest }))
+                if !changes.touches(tree_path) =>
+            {
+                Some(manifest.clone())
+            }
+            _ => None,
+        }
+    }
+
+    /// The carry for subdirectory `name`, which the sparse set covers as
+    /// `cover`; `None` when it has nothing to take from head.
+    fn child(&self, store: &LocalStore, name: &str, cover: Cover) -> Result<Option<Carry<'a>>> {
+        let sparse = self.sparse.filter(|_| cover == Cover::Partial);
+        if sparse.is_none() && self.changes.is_none() {
+            return Ok(None);
+        }
+        match self.previous.get(name) {
+            Some(ManifestEntryKind::Dir { manifest }) => {
+                Carry::load(store, Some(manifest), sparse, self.changes).map(Some)
+            }
+            // New since head: there is nothing to rescan against.
+            _ => Ok(sparse.map(|sparse| Carry {
+                previous: BTreeMap::new(),
+                sparse: Some(sparse),
+                changes: None,
+            })),
+        }
+    }
+
+    /// Head's entries that stay because the sparse set leaves them off
+    /// disk. `prefix` is this directory's tree path; `on_disk` the names
+    /// the scan found there.
+    fn kept(&self, prefix: &str, on_disk: &HashSet<String>) -> Vec<ManifestEntry> {
+        if self.sparse.is_none() {
+            return Vec::new();
+        }
+        self.previous
+            .iter()
+            .filter(|(name, kind)| {
+                let tree_path = common::join(prefix, name);
+                let is_dir = matches!(kind, ManifestEntryKind::Dir { .. });
+                !on_disk.contains(*name) && self.cover(&tree_path, is_dir) == Cover::None
+            })
+            .map(|(name, kind)| ManifestEntry {
+                name: name.clone(),
+                kind: kind.clone(),
+            })
+            .collect()
+    }
 }
@@ -1,28 +1,71 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fs;
 use std::path::Path;
 
 use anyhow::{Context, Result, anyhow};
 
+use crate::model::paging;
 use crate::model::{Manifest, ManifestEntry, ManifestEntryKind, ObjectId, SnapStats};
-use crate::store::hash_bytes;
+use crate::store::{LocalStore, hash_bytes};
 
-use super::common;
-
-use super::super::chunk_io::chunk_bytes_to_recipe_id;
+use super::super::chunk_io::{chunk_to_recipe, recipe_id};
 use super::super::chunking::ChunkingPolicy;
+use super::super::ignore::IgnoreRules;
+use super::super::markers::MarkerState;
+use super::super::sparse::Cover;
+use super::super::stat_index::{FileStat, Indexed, StatIndex};
+use super::Carry;
 use super::common::{
-    file_mode, read_dir_sorted, read_file_stable, should_ignore_name, symlink_target,
+    Listing, Slot, Unread, file_mode, read_dir_sorted, read_file_stable, settle,
+    should_ignore_name, stream_chunked, symlink_target, tree_path,
 };
 
+#[allow(clippy::too_many_arguments)]
 pub(super) fn build_manifest_in_memory_impl(
+    store: &LocalStore,
     scan_root: &Path,
     dir: &Path,
-    root_ignores: &std::collections::HashSet<String>,
+    ignores: &IgnoreRules,
     stats: &mut SnapStats,
     manifests: &mut HashMap<ObjectId, Manifest>,
-    policy: ChunkingPolicy,
+    policy: &ChunkingPolicy,
+    markers: &MarkerState,
+    index: Option<&StatIndex>,
+    threads: usize,
+    carry: Option<&Carry<'_>>,
 ) -> Result<ObjectId> {
-    let mut entries = Vec::new();
+    let mut queue = Vec::new();
+    let root = walk(
+        store, scan_root, dir, ignores, stats, manifests, policy, markers, index, &mut queue, carry,
+    )?;
+    settle(
+        root,
+        &queue,
+        threads,
+        stats,
+        |file| read_in_memory(scan_root, policy, markers, index, file),
+        |entries| put_in_memory(manifests, entries),
+    )
+}
+
+/// List `dir` and everything below it, queueing the files the index
+/// cannot vouch for.
+#[allow(clippy::too_many_arguments)]
+fn walk(
+    store: &LocalStore,
+    scan_root: &Path,
+    dir: &Path,
+    ignores: &IgnoreRules,
+    stats: &mut SnapStats,
+    manifests: &mut HashMap<ObjectId, Manifest>,
+    policy: &ChunkingPolicy,
+    markers: &MarkerState,
+    index: Option<&StatIndex>,
+    queue: &mut Vec<Unread>,
+    carry: Option<&Carry<'_>>,
+) -> Result<Slot> {
+    let mut listing = Listing::default();
+    let mut on_disk = HashSet::new();
     let children = read_dir_sorted(dir)?;
 
     for child in children {
@@ -35,42 +78,65 @@ pub(super) fn build_manifest_in_memory_impl(
             continue;
         }
         let path = child.path();
-        if common::is_ignored(
-            root_ignores,
-            path.strip_prefix(scan_root).unwrap_or(&path),
-            &file_name,
-        ) {
+        let file_type = child.file_type().context("read file type")?;
+        let relative = path.strip_prefix(scan_root).unwrap_or(&path);
+        let tree_path = tree_path(relative);
+        if ignores.is_ignored(&tree_path, file_type.is_dir()) {
             continue;
         }
-
-        let file_type = child.file_type().context("read file type")?;
+        let cover = carry.map_or(Cover::All, |c| c.cover(&tree_path, file_type.is_dir()));
+        if cover == Cover::None {
+            continue;
+        }
+        on_disk.insert(file_name.clone());
 
         let kind = if file_type.is_dir() {
             stats.dirs += 1;
-            let manifest = build_manifest_in_memory_impl(
+            let child = match carry {
+                Some(c) => c.child(store, &file_name, cover)?,
+                None => None,
+            };
+            let dir = walk(
+                store,
                 scan_root,
                 &path,
-                root_ignores,
+                &ignores.descend(&path, &tree_path),
                 stats,
                 manifests,
                 policy,
+                markers,
+                index,
+                queue,
+                child.as_ref(),
             )?;
-            ManifestEntryKind::Dir { manifest }
+            listing.push_dir(file_name, dir);
+            continue;
         } else if file_type.is_file() {
-            let mode = file_mode(&path)?;
-            let (bytes, size) = read_file_stable(&path)?;
-
-            let kind = if size >= policy.threshold {
-                let recipe = chunk_bytes_to_recipe_id(&bytes, policy)?;
-                ManifestEntryKind::FileChunks { recipe, mode, size }
-            } else {
-                let blob = hash_bytes(&bytes);
-                ManifestEntryKind::File { blob, mode, size }
-            };
-
-            stats.files += 1;
-            stats.bytes += size;
-            kind
+            let meta =
+                fs::symlink_metadata(&path).with_context(|| format!("stat {}", path.display()))?;
+            let stat = FileStat::of(&meta);
+            // Unchanged since a scan read it (doc 15 §5c).
+            let cached = index
+                .filter(|_| !markers.marks(&tree_path))
+                .and_then(|index| {
+                    index.lookup(&tree_path, &stat, policy.for_file(relative, stat.size()))
+                });
+            if let Some(kind) = cached {
+                stats.files += 1;
+                stats.bytes += stat.size();
+                listing.push(file_name, kind);
+                continue;
+            }
+            listing.queue(
+                queue,
+                Unread {
+                    name: file_name,
+                    path,
+                    tree_path,
+                    stat,
+                },
+            );
+            continue;
         } else if file_type.is_symlink() {
             let target = symlink_target(&path)?;
             stats.symlinks += 1;
@@ -79,19 +145,121 @@ pub(super) fn build_manifest_in_memory_impl(
             continue;
         };
 
-        entries.push(ManifestEntry {
-            name: file_name,
-            kind,
-        });
+        listing.push(file_name, kind);
     }
 
-    entries.sort_by(|a, b| a.name.cmp(&b.name));
-    let manifest = Manifest {
-        version: 1,
-        entries,
+    // Head's entries outside the sparse set, with the manifests below
+    // them, so the result reads as a whole tree (doc 15 §5b).
+    if let Some(carry) = carry {
+        let prefix = tree_path(dir.strip_prefix(scan_root).unwrap_or(dir));
+        for entry in carry.kept(&prefix, &on_disk) {
+            remember_stored(store, &entry.kind, stats, manifests)?;
+            listing.push(entry.name, entry.kind);
+        }
+    }
+
+    listing.finish(|entries| put_in_memory(manifests, entries))
+}
+
+/// Page exactly as `put_dir` would, so the ids match a stored scan
+/// (doc 16 §1b).
+fn put_in_memory(
+    manifests: &mut HashMap<ObjectId, Manifest>,
+    entries: Vec<ManifestEntry>,
+) -> Result<ObjectId> {
+    let manifest = paging::build(entries, |page| Ok(remember(manifests, page.clone())))?;
+    Ok(remember(manifests, manifest))
+}
+
+/// Hash one file as a snap would hold it, storing nothing. Returns its
+/// entry and the bytes it counts for: none for a marker file, which is
+/// not content yet.
+fn read_in_memory(
+    scan_root: &Path,
+    policy: &ChunkingPolicy,
+    markers: &MarkerState,
+    index: Option<&StatIndex>,
+    file: &Unread,
+) -> Result<(ManifestEntryKind, u64)> {
+    let relative = file.path.strip_prefix(scan_root).unwrap_or(&file.path);
+    let mode = file_mode(&file.path)?;
+    let streamed = stream_chunked(policy, markers, relative, file, chunk_to_recipe)?;
+    let (recipe, params) = match streamed {
+        Some(streamed) => streamed,
+        None => {
+            let (bytes, size) = read_file_stable(&file.path)?;
+            // A marker file still being edited is the superposition it
+            // was written from, not content (doc 17 §2d).
+            if let Some(kind) = markers.still_superposed(relative, &bytes) {
+                return Ok((kind, 0));
+            }
+            let Some(params) = policy.for_file(relative, size) else {
+                let blob = hash_bytes(&bytes);
+                file.record(index, size, Indexed::Blob(blob.clone()));
+                return Ok((ManifestEntryKind::File { blob, mode, size }, size));
+            };
+            (chunk_to_recipe(bytes.as_slice(), params)?, params)
+        }
     };
+    let size = recipe.size;
+    let recipe = recipe_id(&recipe);
+    file.record(index, size, Indexed::Recipe(recipe.clone(), params));
+    Ok((ManifestEntryKind::FileChunks { recipe, mode, size }, size))
+}
+
+fn remember(manifests: &mut HashMap<ObjectId, Manifest>, manifest: Manifest) -> ObjectId {
     let bytes = crate::model::encoding::encode_manifest(&manifest);
     let id = hash_bytes(&bytes);
     manifests.insert(id.clone(), manifest);
-    Ok(id)
+    id
+}
+
+/// Count a carried entry into `stats` and copy the stored manifests
+/// below it into `manifests`.
+fn remember_stored(
+    store: &LocalStore,
+    kind: &ManifestEntryKind,
+    stats: &mut SnapStats,
+    manifests: &mut HashMap<ObjectId, Manifest>,
+) -> Result<()> {
+    let mut stack = match kind {
+        ManifestEntryKind::Dir { manifest } => {
+            stats.dirs += 1;
+            vec![manifest.clone()]
+        }
+        kind => {
+            count(kind, stats);
+            return Ok(());
+        }
+    };
+    while let Some(id) = stack.pop() {
+        if manifests.contains_key(&id) {
+            continue;
+        }
+        let manifest = store.get_manifest(&id)?;
+        for entry in &manifest.entries {
+            match &entry.kind {
+                ManifestEntryKind::Dir { manifest } => {
+                    stats.dirs += 1;
+                    stack.push(manifest.clone());
+                }
+                ManifestEntryKind::Page { manifest, .. } => stack.push(manifest.clone()),
+                kind => count(kind, stats),
+            }
+        }
+        manifests.insert(id, manifest);
+    }
+    Ok(())
+}
+
+fn count(kind: &ManifestEntryKind, stats: &mut SnapStats) {
+    match kind {
+        ManifestEntryKind::File { size, .. } | ManifestEntryKind::FileChunks { size, .. } => {
+            stats.files += 1;
+            stats.bytes += size;
+        }
+        ManifestEntryKind::Symlink { .. } => stats.symlinks += 1,
+        ManifestEntryKind::Superposition { .. } => stats.files += 1,
+        ManifestEntryKind::Dir { .. } | ManifestEntryKind::Page { .. } => {}
+    }
 }
@@ -1,25 +1,68 @@
+use std::collections::HashSet;
+use std::fs;
 use std::path::Path;
 
 use anyhow::{Context, Result, anyhow};
 
-use crate::model::{Manifest, ManifestEntry, ManifestEntryKind, ObjectId, SnapStats};
+use crate::model::{ManifestEntryKind, ObjectId, SnapStats};
 
 use super::super::Workspace;
-use super::super::chunk_io::chunk_bytes_to_recipe_store;
+use super::super::chunk_io::chunk_into_store;
 use super::super::chunking::ChunkingPolicy;
+use super::super::ignore::IgnoreRules;
+use super::super::markers::MarkerState;
+use super::super::sparse::Cover;
+use super::super::stat_index::{FileStat, Indexed, StatIndex};
+use super::Carry;
 use super::common::{
-    file_mode, read_dir_sorted, read_file_stable, should_ignore_name, symlink_target,
+    Listing, Slot, Unread, file_mode, join, read_dir_sorted, read_file_stable, settle,
+    should_ignore_name, stream_chunked, symlink_target, tree_path,
 };
 
+#[allow(clippy::too_many_arguments)]
 pub(super) fn build_manifest_store_impl(
     workspace: &Workspace,
     scan_root: &Path,
     dir: &Path,
-    root_ignores: &std::collections::HashSet<String>,
+    ignores: &IgnoreRules,
     stats: &mut SnapStats,
-    policy: ChunkingPolicy,
+    policy: &ChunkingPolicy,
+    markers: &MarkerState,
+    index: Option<&StatIndex>,
+    threads: usize,
+    carry: Option<&Carry<'_>>,
 ) -> Result<ObjectId> {
-    let mut entries = Vec::new();
+    let mut queue = Vec::new();
+    let root = walk(
+        workspace, scan_root, dir, ignores, stats, policy, markers, index, &mut queue, carry,
+    )?;
+    settle(
+        root,
+        &queue,
+        threads,
+        stats,
+        |file| read_into_store(workspace, scan_root, policy, markers, index, file),
+        |entries| workspace.store.put_dir(entries),
+    )
+}
+
+/// List `dir` and everything below it, queueing the files the index
+/// cannot vouch for.
+#[allow(clippy::too_many_arguments)]
+fn walk(
+    workspace: &Workspace,
+    scan_root: &Path,
+    dir: &Path,
+    ignores: &IgnoreRules,
+    stats: &mut SnapStats,
+    policy: &ChunkingPolicy,
+    markers: &MarkerState,
+    index: Option<&StatIndex>,
+    queue: &mut Vec<Unread>,
+    carry: Option<&Carry<'_>>,
+) -> Result<Slot> {
+    let mut listing = Listing::default();
+    let mut on_disk = HashSet::new();
     let children = read_dir_sorted(dir)?;
 
     for child in children {
@@ -32,46 +75,82 @@ pub(super) fn build_manifest_store_impl(
             continue;
         }
         let path = child.path();
-        // The same rule the in-memory scan and the dirstamp use
+        let file_type = child.file_type().context("read file type")?;
+        let relative = path.strip_prefix(scan_root).unwrap_or(&path);
+        let tree_path = tree_path(relative);
+        // The same rules the in-memory scan and the dirstamp walk with
         // (batch 22.4). Three copies of this check existed and only one
         // was fixed at first — which is exactly how the root-only
         // behaviour survived being noticed.
-        if super::common::is_ignored(
-            root_ignores,
-            path.strip_prefix(scan_root).unwrap_or(&path),
-            &file_name,
-        ) {
+        if ignores.is_ignored(&tree_path, file_type.is_dir()) {
             continue;
         }
-
-        let file_type = child.file_type().context("read file type")?;
+        // Outside the sparse set: head's entry stands (doc 15 §5b).
+        let cover = carry.map_or(Cover::All, |c| c.cover(&tree_path, file_type.is_dir()));
+        if cover == Cover::None {
+            continue;
+        }
+        on_disk.insert(file_name.clone());
 
         let kind = if file_type.is_dir() {
             stats.dirs += 1;
-            let manifest = build_manifest_store_impl(
-                workspace,
-                scan_root,
-                &path,
-                root_ignores,
-                stats,
-                policy,
-            )?;
-            ManifestEntryKind::Dir { manifest }
+            let ignores = ignores.descend(&path, &tree_path);
+            let dir = match carry.and_then(|c| c.unchanged(&file_name, &tree_path)) {
+                // Nothing below moved since the last capture.
+                Some(manifest) => Slot::Known(ManifestEntryKind::Dir { manifest }),
+                None => {
+                    let child = match carry {
+                        Some(c) => c.child(&workspace.store, &file_name, cover)?,
+                        None => None,
+                    };
+                    match child.as_ref() {
+                        Some(child) if child.changes.is_some() => rewalk(
+                            workspace, scan_root, &path, &tree_path, &ignores, stats, policy,
+                            markers, index, queue, child,
+                        )?,
+                        child => walk(
+                            workspace, scan_root, &path, &ignores, stats, policy, markers, index,
+                            queue, child,
+                        )?,
+                    }
+                }
+            };
+            listing.push_dir(file_name, dir);
+            continue;
         } else if file_type.is_file() {
-            let mode = file_mode(&path)?;
-            let (bytes, size) = read_file_stable(&path)?;
-
-            let kind = if size >= policy.threshold {
-                let recipe = chunk_bytes_to_recipe_store(&workspace.store, &bytes, policy)?;
-                ManifestEntryKind::FileChunks { recipe, mode, size }
-            } else {
-                let blob = workspace.store.put_blob(&bytes)?;
-                ManifestEntryKind::File { blob, mode, size }
+            let meta =
+                fs::symlink_metadata(&path).with_context(|| format!("stat {}", path.display()))?;
+            let stat = FileStat::of(&meta);
+            // Unchanged since a scan read it, and still in the store: a
+            // status scan hashes without storing (doc 15 §5c).
+            let cached = index
+                .filter(|_| !markers.marks(&tree_path))
+                .and_then(|index| {
+                    index.lookup(&tree_path, &stat, policy.for_file(relative, stat.size()))
+                });
+            let reused = match &cached {
+                Some(ManifestEntryKind::File { blob, .. }) => workspace.store.reuse_blob(blob)?,
+                Some(ManifestEntryKind::FileChunks { recipe, .. }) => {
+                    workspace.store.reuse_recipe(recipe)?
+                }
+                _ => false,
             };
-
-            stats.files += 1;
-            stats.bytes += size;
-            kind
+            if let Some(kind) = cached.filter(|_| reused) {
+                stats.files += 1;
+                stats.bytes += stat.size();
+                listing.push(file_name, kind);
+                continue;
+            }
+            listing.queue(
+                queue,
+                Unread {
+                    name: file_name,
+                    path,
+                    tree_path,
+                    stat,
+                },
+            );
+            continue;
         } else if file_type.is_symlink() {
             let target = symlink_target(&path)?;
             stats.symlinks += 1;
@@ -80,16 +159,154 @@ pub(super) fn build_manifest_store_impl(
             continue;
         };
 
-        entries.push(ManifestEntry {
-            name: file_name,
-            kind,
-        });
+        listing.push(file_name, kind);
     }
 
-    entries.sort_by(|a, b| a.name.cmp(&b.name));
-    let manifest = Manifest {
-        version: 1,
-        entries,
+    if let Some(carry) = carry {
+        let prefix = tree_path(dir.strip_prefix(scan_root).unwrap_or(dir));
+        for entry in carry.kept(&prefix, &on_disk) {
+            listing.push(entry.name, entry.kind);
+        }
+    }
+
+    // Sorts, and pages a directory past the threshold (doc 16 §1b).
+    listing.finish(|entries| workspace.store.put_dir(entries))
+}
+
+/// Rebuild head's manifest of `dir` — `carry` holds its entries —
+/// reading from disk only what the carried changes reach (doc 15 §5a).
+/// A directory whose own listing changed is listed again; one that only
+/// has changes below it keeps its entries and rebuilds just the
+/// subdirectories they are in.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn rescan_store_impl(
+    workspace: &Workspace,
+    scan_root: &Path,
+    dir: &Path,
+    tree_path: &str,
+    ignores: &IgnoreRules,
+    stats: &mut SnapStats,
+    policy: &ChunkingPolicy,
+    markers: &MarkerState,
+    index: Option<&StatIndex>,
+    threads: usize,
+    carry: &Carry<'_>,
+) -> Result<ObjectId> {
+    let mut queue = Vec::new();
+    let root = rewalk(
+        workspace, scan_root, dir, tree_path, ignores, stats, policy, markers, index, &mut queue,
+        carry,
+    )?;
+    settle(
+        root,
+        &queue,
+        threads,
+        stats,
+        |file| read_into_store(workspace, scan_root, policy, markers, index, file),
+        |entries| workspace.store.put_dir(entries),
+    )
+}
+
+/// `walk` for a rescan: only where the carried changes reach.
+#[allow(clippy::too_many_arguments)]
+fn rewalk(
+    workspace: &Workspace,
+    scan_root: &Path,
+    dir: &Path,
+    tree_path: &str,
+    ignores: &IgnoreRules,
+    stats: &mut SnapStats,
+    policy: &ChunkingPolicy,
+    markers: &MarkerState,
+    index: Option<&StatIndex>,
+    queue: &mut Vec<Unread>,
+    carry: &Carry<'_>,
+) -> Result<Slot> {
+    let changes = carry.changes.expect("a rescan carries its changes");
+    if changes.contains(tree_path) {
+        return walk(
+            workspace,
+            scan_root,
+            dir,
+            ignores,
+            stats,
+            policy,
+            markers,
+            index,
+            queue,
+            Some(carry),
+        );
+    }
+    let mut listing = Listing::default();
+    for (name, kind) in &carry.previous {
+        let child = join(tree_path, name);
+        match kind {
+            ManifestEntryKind::Dir { manifest } if changes.touches(&child) => {
+                let cover = carry.cover(&child, true);
+                let dir = match carry.child(&workspace.store, name, cover)? {
+                    Some(next) if cover != Cover::None => {
+                        let path = dir.join(name);
+                        rewalk(
+                            workspace,
+                            scan_root,
+                            &path,
+                            &child,
+                            &ignores.descend(&path, &child),
+                            stats,
+                            policy,
+                            markers,
+                            index,
+                            queue,
+                            &next,
+                        )?
+                    }
+                    _ => Slot::Known(ManifestEntryKind::Dir {
+                        manifest: manifest.clone(),
+                    }),
+                };
+                listing.push_dir(name.clone(), dir);
+            }
+            kind => listing.push(name.clone(), kind.clone()),
+        }
+    }
+    listing.finish(|entries| workspace.store.put_dir(entries))
+}
+
+/// Store one file as a snap holds it. Returns its entry and the bytes
+/// it counts for: none for a marker file, which is not content yet.
+fn read_into_store(
+    workspace: &Workspace,
+    scan_root: &Path,
+    policy: &ChunkingPolicy,
+    markers: &MarkerState,
+    index: Option<&StatIndex>,
+    file: &Unread,
+) -> Result<(ManifestEntryKind, u64)> {
+    let relative = file.path.strip_prefix(scan_root).unwrap_or(&file.path);
+    let mode = file_mode(&file.path)?;
+    let streamed = stream_chunked(policy, markers, relative, file, |source, params| {
+        chunk_into_store(&workspace.store, source, params)
+    })?;
+    let (recipe, params) = match streamed {
+        Some(streamed) => streamed,
+        None => {
+            let (bytes, size) = read_file_stable(&file.path)?;
+            // A marker file still being edited is the superposition it
+            // was written from, not content (doc 17 §2d).
+            if let Some(kind) = markers.still_superposed(relative, &bytes) {
+                return Ok((kind, 0));
+            }
+            let Some(params) = policy.for_file(relative, size) else {
+                let blob = workspace.store.put_blob(&bytes)?;
+                file.record(index, size, Indexed::Blob(blob.clone()));
+                return Ok((ManifestEntryKind::File { blob, mode, size }, size));
+            };
+            let recipe = chunk_into_store(&workspace.store, bytes.as_slice(), params)?;
+            (recipe, params)
+        }
     };
-    workspace.store.put_manifest(&manifest)
+    let size = recipe.size;
+    let recipe = workspace.store.put_recipe(&recipe)?;
+    file.record(index, size, Indexed::Recipe(recipe.clone(), params));
+    Ok((ManifestEntryKind::FileChunks { recipe, mode, size }, size))
 }
@@ -0,0 +1,317 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::tree_edit::lookup;
+use super::*;
+
+use crate::model::{
+    ManifestEntryKind, Resolution, ResolutionDecision, ResolvedContent, ResolvedContentKind,
+    SuperpositionVariant, SuperpositionVariantKind,
+};
+
+const STATE_FILE: &str = "markers.json";
+
+/// How a materialize into the workspace writes a superposition.
+#[derive(Clone, Debug, Default)]
+pub enum SuperpositionMode {
+    /// Refuse: a superposed tree cannot be checked out.
+    #[default]
+    Refuse,
+    /// Write text superpositions as diff3-style conflict-marker files
+    /// (doc 17 §2d), with `base` — the tree of the candidate's W — as
+    /// the common ancestor when it is known.
+    Markers { base: Option<ObjectId> },
+}
+
+/// The paths a checkout wrote as marker files, and the superposition
+/// each stands for. Lives in `.converge`, so the scan can tell a file
+/// still being resolved from one that is finished.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(super) struct MarkerState {
+    /// The superposed tree the markers were written from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    root: Option<ObjectId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    candidate_id: Option<String>,
+    #[serde(default)]
+    paths: BTreeMap<String, Vec<SuperpositionVariant>>,
+}
+
+impl MarkerState {
+    pub(super) fn new(
+        root: &ObjectId,
+        candidate_id: Option<&str>,
+        paths: BTreeMap<String, Vec<SuperpositionVariant>>,
+    ) -> Self {
+        Self {
+            root: Some(root.clone()),
+            candidate_id: candidate_id.map(str::to_string),
+            paths,
+        }
+    }
+
+    pub(super) fn load(store: &LocalStore) -> Result<Self> {
+        let path = store.root_dir().join(STATE_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let bytes = fs::read(&path).with_context(|| format!("read {}", path.display()))?;
+        serde_json::from_slice(&bytes).context("parse marker state")
+    }
+
+    pub(super) fn save(&self, store: &LocalStore) -> Result<()> {
+        let path = store.root_dir().join(STATE_FILE);
+        if self.paths.is_empty() {
+            if path.exists() {
+                fs::remove_file(&path).with_context(|| format!("remove {}", path.display()))?;
+            }
+            return Ok(());
+        }
+        let bytes = serde_json::to_vec_pretty(self).context("serialize marker state")?;
+        crate::store::write_atomic(&path, &bytes).context("write marker state")
+    }
+
+    /// The superposed tree the markers were written from.
+    pub(super) fn root(&self) -> Option<&ObjectId> {
+        self.root.as_ref()
+    }
+
+    pub(super) fn paths(&self) -> impl Iterator<Item = &String> {
+        self.paths.keys()
+    }
+
+    /// Whether the file at `tree_path` was written as a marker file; the
+    /// scan reads those whatever their stat says.
+    pub(super) fn marks(&self, tree_path: &str) -> bool {
+        self.paths.contains_key(tree_path)
+    }
+
+    /// What the scan records for the file at `relative`: the
+    /// superposition it was written from while it still carries markers,
+    /// `None` once it is plain content (or was never marked).
+    pub(super) fn still_superposed(
+        &self,
+        relative: &Path,
+        bytes: &[u8],
+    ) -> Option<ManifestEntryKind> {
+        if self.paths.is_empty() {
+            return None;
+        }
+        let variants = self
+            .paths
+            .get(&super::manifest_scan::common::tree_path(relative))?;
+        has_markers(bytes).then(|| ManifestEntryKind::Superposition {
+            variants: variants.clone(),
+        })
+    }
+}
+
+/// A line opening or closing a conflict region. `=======` alone is not
+/// counted: it underlines headings in plain text.
+fn has_markers(bytes: &[u8]) -> bool {
+    bytes
+        .split(|b| *b == b'\n')
+        .any(|line| line.starts_with(b"<<<<<<<") || line.starts_with(b">>>>>>>"))
+}
+
+/// The marker file for a superposition at `path`, or an error naming
+/// why it has no text form.
+pub(super) fn render_superposition(
+    store: &LocalStore,
+    base_root: Option<&ObjectId>,
+    path: &str,
+    variants: &[SuperpositionVariant],
+) -> Result<String> {
+    // Nothing upstream promises two sides, and a server's manifest may
+    // carry fewer: there is no region to draw.
+    if variants.len() < 2 {
+        anyhow::bail!(
+            "cannot write markers for {path}: the superposition has {} variant(s)",
+            variants.len()
+        );
+    }
+    let mut texts = Vec::with_capacity(variants.len());
+    for v in variants {
+        let text = match &v.kind {
+            SuperpositionVariantKind::File { blob, .. } => text_of(store, Content::Blob(blob))?,
+            SuperpositionVariantKind::FileChunks { recipe, .. } => {
+                text_of(store, Content::Recipe(recipe))?
+            }
+            _ => None,
+        };
+        let Some(text) = text else {
+            anyhow::bail!(
+                "cannot write markers for {path}: the {} variant is not a text file",
+                v.source
+            );
+        };
+        texts.push((v.source.as_str(), text));
+    }
+    let base = match base_root {
+        Some(root) => Some(match lookup(store, root, path)? {
+            Some(ManifestEntryKind::File { blob, .. }) => {
+                text_of(store, Content::Blob(&blob))?.unwrap_or_default()
+            }
+            Some(ManifestEntryKind::FileChunks { recipe, .. }) => {
+                text_of(store, Content::Recipe(&recipe))?.unwrap_or_default()
+            }
+            // Added on every side since W: the common ancestor is empty.
+            _ => String::new(),
+        }),
+        None => None,
+    };
+
+    if let [(a_source, a), (b_source, b)] = texts.as_slice() {
+        let mut options = diffy::MergeOptions::new();
+        let (ancestor, style) = match &base {
+            Some(base) => (base.as_str(), diffy::ConflictStyle::Diff3),
+            None => ("", diffy::ConflictStyle::Merge),
+        };
+        options.set_conflict_style(style);
+        // A clean merge would be a resolution nobody looked at: the next
+        // snap would take it. Those fall through to whole-file regions.
+        if let Err(marked) = options.merge(ancestor, a, b) {
+            // Only diffy's own marker lines change: every other line keeps
+            // its terminator, so a CRLF file stays CRLF and a missing final
+            // newline stays missing.
+            return Ok(marked
+                .split_inclusive('\n')
+                .map(|line| {
+                    let body = line.trim_end_matches(['\r', '\n']);
+                    let end = &line[body.len()..];
+                    match body {
+                        "<<<<<<< ours" => format!("<<<<<<< {a_source}{end}"),
+                        "||||||| original" => format!("||||||| base{end}"),
+                        ">>>>>>> theirs" => format!(">>>>>>> {b_source}{end}"),
+                        _ => line.to_string(),
+                    }
+                })
+                .collect());
+        }
+    }
+
+    // Whole-file regions: more than two variants, or two that merge.
+    let block = |text: &str| {
+        if text.is_empty() || text.ends_with('\n') {
+            text.to_string()
+        } else {
+            format!("{text}\n")
+        }
+    };
+    let mut out = format!("<<<<<<< {}\n{}", texts[0].0, block(&texts[0].1));
+    if let Some(base) = &base {
+        out.push_str(&format!("||||||| base\n{}", block(base)));
+    }
+    for (_, text) in &texts[1..texts.len() - 1] {
+        out.push_str(&format!("=======\n{}", block(text)));
+    }
+    let (last_source, last) = &texts[texts.len() - 1];
+    out.push_str(&format!("=======\n{}>>>>>>> {last_source}\n", block(last)));
+    Ok(out)
+}
+
+enum Content<'a> {
+    Blob(&'a ObjectId),
+    Recipe(&'a ObjectId),
+}
+
+/// File content as text; `None` for binary content.
+fn text_of(store: &LocalStore, content: Content<'_>) -> Result<Option<String>> {
+    let bytes = match content {
+        Content::Blob(blob) => store.get_blob(blob)?,
+        Content::Recipe(recipe) => {
+            let mut bytes = Vec::new();
+            for chunk in store.get_recipe(recipe)?.chunks {
+                bytes.extend(store.get_blob(&chunk.blob)?);
+            }
+            bytes
+        }
+    };
+    if bytes.contains(&0) {
+        return Ok(None);
+    }
+    Ok(String::from_utf8(bytes).ok())
+}
+
+impl Workspace {
+    /// A tree holding marker files is mid-resolution: rewriting it would
+    /// lose the record of what each file stands for.
+    pub(super) fn refuse_over_markers(&self, verb: &str) -> Result<()> {
+        if MarkerState::load(&self.store)?.paths().next().is_some() {
+            anyhow::bail!("finish resolving the conflict-marker files before {verb}");
+        }
+        Ok(())
+    }
+
+    /// Paths the last checkout wrote as conflict markers that are still
+    /// unresolved.
+    pub fn marked_paths(&self) -> Result<Vec<String>> {
+        Ok(MarkerState::load(&self.store)?.paths().cloned().collect())
+    }
+
+    /// Turn marker files the user has finished with into resolution
+    /// decisions (doc 17 §2d), after a capture of `root`.
+    ///
+    /// A marked path that `root` holds as a plain file was de-markered:
+    /// its content is the resolution, recorded as a hand-edited decision
+    /// superseding the variants it was written from — reusable for the
+    /// same conflict later (§2c), and in the checked-out candidate's
+    /// resolution record. A path still superposed stays marked; one the
+    /// user deleted or replaced with something else is simply forgotten.
+    pub(super) fn settle_markers(&self, root: &ObjectId) -> Result<Vec<String>> {
+        let mut state = MarkerState::load(&self.store)?;
+        if state.paths.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut settled = BTreeMap::new();
+        let mut open = BTreeMap::new();
+        for (path, variants) in std::mem::take(&mut state.paths) {
+            let content = match lookup(&self.store, root, &path)? {
+                Some(ManifestEntryKind::Superposition { .. }) => {
+                    open.insert(path, variants);
+                    continue;
+                }
+                Some(ManifestEntryKind::File { blob, mode, size }) => {
+                    ResolvedContentKind::File { blob, mode, size }
+                }
+                Some(ManifestEntryKind::FileChunks { recipe, mode, size }) => {
+                    ResolvedContentKind::ChunkedFile { recipe, mode, size }
+                }
+                _ => continue,
+            };
+            let keys: Vec<_> = variants.iter().map(|v| v.key()).collect();
+            let decision = ResolutionDecision::Content(ResolvedContent {
+                content,
+                supersedes: keys.clone(),
+            });
+            self.store.put_recorded_decision(&keys, &decision)?;
+            settled.insert(path, decision);
+        }
+        state.paths = open;
+
+        if let (Some(candidate_id), Some(superposed)) = (&state.candidate_id, &state.root)
+            && !settled.is_empty()
+        {
+            let mut record = if self.store.has_resolution(candidate_id) {
+                self.store.get_resolution(candidate_id)?
+            } else {
+                Resolution {
+                    version: 3,
+                    candidate_id: candidate_id.clone(),
+                    root_manifest: superposed.clone(),
+                    created_at: String::new(),
+                    decisions: BTreeMap::new(),
+                }
+            };
+            record.version = 3;
+            record.created_at = time::OffsetDateTime::now_utc()
+                .format(&time::format_description::well_known::Rfc3339)
+                .context("format created_at")?;
+            record.decisions.extend(settled.clone());
+            self.store.put_resolution(&record)?;
+        }
+        state.save(&self.store)?;
+        Ok(settled.into_keys().collect())
+    }
+}
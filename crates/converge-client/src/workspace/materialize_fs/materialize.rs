@@ -1,14 +1,22 @@
+use std::collections::BTreeMap;
 use std::fs;
 use std::io::{BufWriter, Write};
 use std::path::Path;
 
 use anyhow::{Context, Result, anyhow};
 
-use crate::model::{ManifestEntryKind, ObjectId, SuperpositionVariantKind};
+use crate::model::{ManifestEntryKind, ObjectId, SuperpositionVariant, SuperpositionVariantKind};
 use crate::store::LocalStore;
 
+use super::super::SuperpositionMode;
+use super::super::markers::render_superposition;
+use super::super::sparse::{Cover, Sparse};
 use super::platform::{create_symlink, set_file_mode};
 
+/// Superposition paths written as marker files, with the variants each
+/// stands for.
+pub(in crate::workspace) type Marked = BTreeMap<String, Vec<SuperpositionVariant>>;
+
 /// Manifest entry names become filesystem paths, and manifests can come
 /// from a remote — treat every name as untrusted (batch 12.1, audit D2).
 fn validate_entry_name(name: &str) -> Result<()> {
@@ -65,17 +73,100 @@ pub(super) fn materialize_manifest(
     store: &LocalStore,
     manifest_id: &ObjectId,
     out_dir: &Path,
+    mode: &SuperpositionMode,
+    sparse: &Sparse,
+) -> Result<Marked> {
+    let mut marked = Marked::new();
+    let sparse = (!sparse.is_everything()).then_some(sparse);
+    if store.is_partial() {
+        // One request for every promised blob about to be written, not
+        // one per file (doc 16 §1g).
+        let mut promised = Vec::new();
+        promised_under(store, manifest_id, "", mode, sparse, &mut promised)?;
+        store.ensure_blobs(&promised)?;
+    }
+    materialize_manifest_at_depth(store, manifest_id, out_dir, "", mode, sparse, &mut marked)?;
+    Ok(marked)
+}
+
+/// The promised blobs materializing `manifest_id` will read, walked the
+/// way [`materialize_manifest_at_depth`] walks it.
+fn promised_under(
+    store: &LocalStore,
+    manifest_id: &ObjectId,
+    prefix: &str,
+    mode: &SuperpositionMode,
+    sparse: Option<&Sparse>,
+    out: &mut Vec<ObjectId>,
 ) -> Result<()> {
-    materialize_manifest_at_depth(store, manifest_id, out_dir, 0)
+    let mut want = |blob: ObjectId| {
+        if store.is_promised(&blob) {
+            out.push(blob);
+        }
+    };
+    let mut subdirs = Vec::new();
+    for entry in store.get_dir(manifest_id)?.entries {
+        let tree_path = if prefix.is_empty() {
+            entry.name.clone()
+        } else {
+            format!("{prefix}/{}", entry.name)
+        };
+        let is_dir = matches!(entry.kind, ManifestEntryKind::Dir { .. });
+        let cover = sparse.map_or(Cover::All, |s| s.cover(&tree_path, is_dir));
+        if cover == Cover::None {
+            continue;
+        }
+        match entry.kind {
+            ManifestEntryKind::Dir { manifest } => {
+                subdirs.push((
+                    manifest,
+                    tree_path,
+                    sparse.filter(|_| cover == Cover::Partial),
+                ));
+            }
+            ManifestEntryKind::File { blob, .. } => want(blob),
+            ManifestEntryKind::FileChunks { recipe, .. } => {
+                for chunk in store.get_recipe(&recipe)?.chunks {
+                    want(chunk.blob);
+                }
+            }
+            ManifestEntryKind::Superposition { variants }
+                if matches!(mode, SuperpositionMode::Markers { .. }) =>
+            {
+                for variant in variants {
+                    match variant.kind {
+                        SuperpositionVariantKind::File { blob, .. } => want(blob),
+                        SuperpositionVariantKind::FileChunks { recipe, .. } => {
+                            for chunk in store.get_recipe(&recipe)?.chunks {
+                                want(chunk.blob);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    for (manifest, tree_path, sparse) in subdirs {
+        promised_under(store, &manifest, &tree_path, mode, sparse, out)?;
+    }
+    Ok(())
 }
 
+/// `sparse` is set while `out_dir` is only partly selected; below a
+/// fully selected directory everything is written.
 fn materialize_manifest_at_depth(
     store: &LocalStore,
     manifest_id: &ObjectId,
     out_dir: &Path,
-    depth: usize,
+    prefix: &str,
+    mode: &SuperpositionMode,
+    sparse: Option<&Sparse>,
+    marked: &mut Marked,
 ) -> Result<()> {
-    let manifest = store.get_manifest(manifest_id)?;
+    let depth = prefix.split('/').filter(|s| !s.is_empty()).count();
+    let manifest = store.get_dir(manifest_id)?;
     let mut seen = std::collections::HashSet::new();
     for entry in manifest.entries {
         validate_entry_name(&entry.name)?;
@@ -87,11 +178,24 @@ fn materialize_manifest_at_depth(
             ));
         }
         let path = out_dir.join(&entry.name);
+        let tree_path = if prefix.is_empty() {
+            entry.name.clone()
+        } else {
+            format!("{prefix}/{}", entry.name)
+        };
+        let is_dir = matches!(entry.kind, ManifestEntryKind::Dir { .. });
+        let cover = sparse.map_or(Cover::All, |s| s.cover(&tree_path, is_dir));
+        if cover == Cover::None {
+            continue;
+        }
         match entry.kind {
             ManifestEntryKind::Dir { manifest } => {
                 fs::create_dir_all(&path)
                     .with_context(|| format!("create dir {}", path.display()))?;
-                materialize_manifest_at_depth(store, &manifest, &path, depth + 1)?;
+                let sparse = sparse.filter(|_| cover == Cover::Partial);
+                materialize_manifest_at_depth(
+                    store, &manifest, &path, &tree_path, mode, sparse, marked,
+                )?;
             }
             ManifestEntryKind::File { blob, mode, .. } => {
                 let bytes = store.get_blob(&blob)?;
@@ -107,10 +211,20 @@ fn materialize_manifest_at_depth(
                 create_symlink(&target, &path)?
             }
             ManifestEntryKind::Superposition { variants } => {
+                if let SuperpositionMode::Markers { base } = mode {
+                    let text = render_superposition(store, base.as_ref(), &tree_path, &variants)?;
+                    fs::write(&path, text)
+                        .with_context(|| format!("write markers {}", path.display()))?;
+                    marked.insert(tree_path, variants);
+                    continue;
+                }
                 let mut sources = Vec::new();
                 for v in variants {
                     sources.push(match v.kind {
                         SuperpositionVariantKind::Tombstone => format!("{}: tombstone", v.source),
+                        SuperpositionVariantKind::MovedTo { path } => {
+                            format!("{}: moved to {path}", v.source)
+                        }
                         SuperpositionVariantKind::File { .. } => format!("{}: file", v.source),
                         SuperpositionVariantKind::FileChunks { .. } => {
                             format!("{}: chunked_file", v.source)
@@ -127,6 +241,7 @@ fn materialize_manifest_at_depth(
                     sources.join(", ")
                 ));
             }
+            ManifestEntryKind::Page { .. } => unreachable!("get_dir reads pages through"),
         }
     }
     Ok(())
@@ -9,6 +9,10 @@ use anyhow::Result;
 use crate::model::ObjectId;
 use crate::store::LocalStore;
 
+use super::SuperpositionMode;
+use super::sparse::Sparse;
+pub(super) use materialize::Marked;
+
 pub(super) fn is_empty_except_converge_and_git(root: &Path) -> Result<bool> {
     clear::is_empty_except_converge_and_git(root)
 }
@@ -22,12 +26,17 @@ pub(super) fn is_empty_dir(root: &Path) -> Result<bool> {
 /// same filesystem, so the swap is renames — and only then is the
 /// destination cleared (preserving `preserve` entries) and the new tree
 /// moved in. A failed materialize leaves `dest` untouched.
+///
+/// Only what `sparse` selects is written (doc 15 §5b). Returns the
+/// paths `mode` wrote as conflict markers.
 pub(super) fn materialize_via_temp(
     store: &LocalStore,
     manifest_id: &ObjectId,
     dest: &Path,
     preserve: &[&str],
-) -> Result<()> {
+    mode: &SuperpositionMode,
+    sparse: &Sparse,
+) -> Result<Marked> {
     use anyhow::Context;
 
     std::fs::create_dir_all(dest).with_context(|| format!("create dir {}", dest.display()))?;
@@ -49,10 +58,13 @@ pub(super) fn materialize_via_temp(
     }
     std::fs::create_dir(&temp).with_context(|| format!("create temp {}", temp.display()))?;
 
-    if let Err(err) = materialize::materialize_manifest(store, manifest_id, &temp) {
-        let _ = std::fs::remove_dir_all(&temp);
-        return Err(err);
-    }
+    let marked = match materialize::materialize_manifest(store, manifest_id, &temp, mode, sparse) {
+        Ok(marked) => marked,
+        Err(err) => {
+            let _ = std::fs::remove_dir_all(&temp);
+            return Err(err);
+        }
+    };
 
     // Success: destroy-and-swap. Preserve internal dirs and the temp
     // tree itself.
@@ -80,5 +92,5 @@ pub(super) fn materialize_via_temp(
             .with_context(|| format!("move {} into place", target.display()))?;
     }
     std::fs::remove_dir_all(&temp).with_context(|| format!("remove temp {}", temp.display()))?;
-    Ok(())
+    Ok(marked)
 }
@@ -0,0 +1,139 @@
+use globset::GlobMatcher;
+
+use super::sparse::compile_tree_pattern;
+use super::tree_edit::set_path;
+use super::*;
+
+use crate::diff::{DiffLine, EntrySig};
+use crate::model::{ManifestEntryKind, ResolvedContentKind, SnapRecord};
+
+impl Workspace {
+    /// What a snap would capture now, against head: every uncaptured
+    /// change, by path. Nothing is written to the store.
+    pub fn pending_changes(&self) -> Result<Vec<DiffLine>> {
+        let (root, manifests, _) = self.current_manifest_tree()?;
+        let base = match self.store.get_head()? {
+            Some(head) => {
+                let head = self.store.get_snap(&head)?;
+                if head.root_manifest == root {
+                    return Ok(Vec::new());
+                }
+                crate::diff::tree_from_store(&self.store, &head.root_manifest)?
+            }
+            None => Default::default(),
+        };
+        let working = crate::diff::tree_from_memory(&manifests, &root)?;
+        Ok(crate::diff::diff_trees(&base, &working))
+    }
+
+    /// Snap head plus only the changes under `patterns` (doc 15 §5g),
+    /// written as sparse patterns are (§5b). The other changes stay on
+    /// disk, uncaptured.
+    pub fn create_snap_paths(
+        &self,
+        message: Option<String>,
+        patterns: &[String],
+    ) -> Result<SnapRecord> {
+        let selection = Selection::new(patterns)?;
+        self.create_snap_selected(message, |line| selection.selects(line.path()))
+    }
+
+    /// Snap head plus the pending changes `select` picks. The tree is
+    /// head's with only the picked paths rewritten, so every directory
+    /// they do not reach keeps its id.
+    pub fn create_snap_selected(
+        &self,
+        message: Option<String>,
+        select: impl Fn(&DiffLine) -> bool,
+    ) -> Result<SnapRecord> {
+        // A snap settles every marker file it holds (doc 17 §2d); one
+        // left out of it would be read as given up.
+        self.refuse_over_markers("snapping part of the tree")?;
+        let picked: Vec<DiffLine> = self
+            .pending_changes()?
+            .into_iter()
+            .filter(|line| select(line))
+            .collect();
+        if picked.is_empty() {
+            anyhow::bail!("nothing to snap: no uncaptured change is selected");
+        }
+        let mut root = match self.store.get_head()? {
+            Some(head) => self.store.get_snap(&head)?.root_manifest,
+            None => self.store.put_dir(Vec::new())?,
+        };
+        for line in &picked {
+            let value = match line {
+                DiffLine::Deleted { .. } => None,
+                DiffLine::Added { path, to } | DiffLine::Modified { path, to, .. } => {
+                    Some(self.capture_entry(path, to)?)
+                }
+            };
+            root = set_path(&self.store, &root, line.path(), value)?;
+        }
+        let cfg = self.store.read_config()?;
+        let stats = self.stats_for_root(&root)?;
+        self.snap_scanned(&cfg, root, stats, message, "explicit")
+    }
+
+    /// The working tree's entry at `path`, stored. The scan only hashed
+    /// it; a file is read again, as a snap would read it.
+    fn capture_entry(&self, path: &str, scanned: &EntrySig) -> Result<ManifestEntryKind> {
+        Ok(match scanned {
+            EntrySig::Symlink { target } => ManifestEntryKind::Symlink {
+                target: target.clone(),
+            },
+            EntrySig::File { .. } | EntrySig::FileChunks { .. } => {
+                match self.capture_file(path, &self.root.join(path))? {
+                    ResolvedContentKind::File { blob, mode, size } => {
+                        ManifestEntryKind::File { blob, mode, size }
+                    }
+                    ResolvedContentKind::ChunkedFile { recipe, mode, size } => {
+                        ManifestEntryKind::FileChunks { recipe, mode, size }
+                    }
+                }
+            }
+            EntrySig::Superposition { .. } => {
+                anyhow::bail!("{path} is unresolved; resolve it before snapping it")
+            }
+        })
+    }
+}
+
+/// The paths `converge snap --path` and `converge shelve --path` pick.
+/// A pattern selects a path and everything below it; `.` selects the
+/// whole tree.
+pub(in crate::workspace) struct Selection {
+    everything: bool,
+    matchers: Vec<GlobMatcher>,
+}
+
+impl Selection {
+    pub(in crate::workspace) fn new(patterns: &[String]) -> Result<Self> {
+        let mut selection = Selection {
+            everything: false,
+            matchers: Vec::with_capacity(patterns.len()),
+        };
+        for pattern in patterns {
+            // `.` names the whole tree here, as an empty pattern does.
+            if pattern.trim().trim_end_matches('/') == "." {
+                selection.everything = true;
+                continue;
+            }
+            match compile_tree_pattern(pattern, "path")? {
+                Some((_, matcher)) => selection.matchers.push(matcher),
+                None => selection.everything = true,
+            }
+        }
+        Ok(selection)
+    }
+
+    /// Whether a pattern matches `tree_path` or a directory above it.
+    pub(in crate::workspace) fn selects(&self, tree_path: &str) -> bool {
+        self.everything
+            || tree_path
+                .match_indices('/')
+                .map(|(at, _)| &tree_path[..at])
+                .chain([tree_path])
+                .any(|prefix| self.matchers.iter().any(|m| m.is_match(prefix)))
+    }
+}
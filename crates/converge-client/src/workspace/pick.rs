@@ -0,0 +1,169 @@
+use serde::Serialize;
+
+use super::markers::SuperpositionMode;
+use super::*;
+
+use crate::model::SnapRecord;
+use crate::model::gates::StrategyPolicy;
+use crate::model::merge::{self, FOLD_VERSION, MergeInput};
+
+/// What `pick` or `rebase_onto` did.
+#[derive(Clone, Debug, Serialize)]
+pub struct Replayed {
+    /// Head afterwards.
+    pub head: String,
+    /// The snaps made, oldest first. A change head already held makes
+    /// none.
+    pub snaps: Vec<String>,
+    /// Paths both sides changed, differently: superposed in the new
+    /// snaps and written as conflict markers (doc 17 §2d).
+    pub conflicts: Vec<String>,
+}
+
+impl Workspace {
+    /// Replay the change `snap_id` made to its first parent onto head,
+    /// as a new snap (doc 15 §5h). Only that change moves: nothing else
+    /// from the snap's lineage is read or checked out. Paths both sides
+    /// changed go to the strategy `policy` picks for them.
+    pub fn pick(&self, snap_id: &str, policy: &StrategyPolicy) -> Result<Replayed> {
+        let head = self.head_to_replay_onto("picking")?;
+        let snap = self.store.get_snap(snap_id)?;
+        if self.lineage_ids(&head)?.contains(&snap.id) {
+            anyhow::bail!("{} is already in head's history", short(&snap.id));
+        }
+        self.replay(&head, &[snap], "head", policy)
+    }
+
+    /// Replay head's own snaps — those `onto` does not already have,
+    /// following first parents — onto `onto`, one by one, and move head
+    /// to the last (doc 15 §5h). The snaps replayed stay in the store,
+    /// off the new lineage.
+    pub fn rebase_onto(&self, onto: &str, policy: &StrategyPolicy) -> Result<Replayed> {
+        let head = self.head_to_replay_onto("rebasing")?;
+        let onto = self.store.get_snap(onto)?.id;
+        if self.lineage_ids(&head)?.contains(&onto) {
+            anyhow::bail!("head already builds on {}", short(&onto));
+        }
+        let theirs = self.lineage_ids(&onto)?;
+        let mut mine = Vec::new();
+        let mut next = Some(head);
+        while let Some(id) = next.filter(|id| !theirs.contains(id)) {
+            let snap = self.store.get_snap(&id)?;
+            next = snap.parents.first().cloned();
+            mine.push(snap);
+        }
+        mine.reverse();
+        self.replay(&onto, &mine, &format!("onto {}", short(&onto)), policy)
+    }
+
+    /// Head, when the working tree matches it: a replay checks a new tree
+    /// out, and uncaptured edits would go with the old one.
+    fn head_to_replay_onto(&self, verb: &str) -> Result<String> {
+        self.refuse_over_markers(verb)?;
+        let Some(head) = self.store.get_head()? else {
+            anyhow::bail!("nothing to replay onto: this workspace has no head snap yet");
+        };
+        if !self.pending_changes()?.is_empty() {
+            anyhow::bail!(
+                "the working tree has uncaptured changes; snap or shelve them before {verb}"
+            );
+        }
+        Ok(head)
+    }
+
+    /// Fold each of `snaps`' changes onto `onto`'s tree in turn, record
+    /// one snap per change, then check the last out and move head to it.
+    fn replay(
+        &self,
+        onto: &str,
+        snaps: &[SnapRecord],
+        ours: &str,
+        policy: &StrategyPolicy,
+    ) -> Result<Replayed> {
+        let mut root = self.store.get_snap(onto)?.root_manifest;
+        let mut last = onto.to_string();
+        let mut made = Vec::new();
+        for snap in snaps {
+            let base = match snap.parents.first() {
+                Some(parent) => Some(self.store.get_snap(parent)?.root_manifest),
+                None => None,
+            };
+            root = fold_change(
+                &self.store,
+                &root,
+                base.as_ref(),
+                &snap.root_manifest,
+                policy,
+                ours,
+                &format!("snap {}", short(&snap.id)),
+            )?;
+            // A change `onto` already held makes no snap. One that lands
+            // names the snap it came from, whose author made it.
+            let next = self.capture_tree_on(
+                vec![last.clone()],
+                &root,
+                snap.message.clone(),
+                None,
+                Some(&snap.id),
+            )?;
+            if next.id != last {
+                last = next.id;
+                made.push(last.clone());
+            }
+        }
+
+        // Recorded first, so a checkout that fails loses nothing. The
+        // common ancestor markers show is where the replayed line left
+        // `onto`'s.
+        let fork = match snaps.first().and_then(|snap| snap.parents.first()) {
+            Some(parent) => Some(self.store.get_snap(parent)?.root_manifest),
+            None => None,
+        };
+        let mode = SuperpositionMode::Markers { base: fork };
+        let conflicts = self
+            .materialize_workspace(&root, None, &mode)
+            .with_context(|| {
+                format!(
+                    "{} holds a conflict markers cannot show; head and the working tree \
+                     are unchanged, and `converge resolve list {last}` shows what to settle",
+                    short(&last)
+                )
+            })?;
+        self.store.set_head(Some(&last))?;
+        Ok(Replayed {
+            head: last,
+            snaps: made,
+            conflicts,
+        })
+    }
+}
+
+/// `onto` with the change from `base` to `theirs` folded in, by the fold
+/// a gate builds candidates with (doc 17 §2-4). `onto`'s own change since
+/// `base` and the snap's are its two inputs, folded onto `base`: a path
+/// one side changed takes that change, a file one side moved takes the
+/// other's edit with it, and a path both changed, differently, goes to
+/// the strategy `policy` picks — superposing when that cannot settle it.
+/// Only changed paths are read, and directories nothing touched keep
+/// their ids.
+fn fold_change(
+    store: &LocalStore,
+    onto: &ObjectId,
+    base: Option<&ObjectId>,
+    theirs: &ObjectId,
+    policy: &StrategyPolicy,
+    ours_source: &str,
+    theirs_source: &str,
+) -> Result<ObjectId> {
+    let input = |lane: &str, tree: &ObjectId| MergeInput {
+        lane: lane.to_string(),
+        base: base.cloned(),
+        tree: tree.clone(),
+    };
+    let inputs = [input(ours_source, onto), input(theirs_source, theirs)];
+    Ok(merge::merge_window_outcome(store, base, &inputs, policy, FOLD_VERSION)?.root)
+}
+
+fn short(id: &str) -> String {
+    id.chars().take(8).collect()
+}
@@ -1,23 +1,34 @@
+use super::markers::MarkerState;
+use super::sparse::Sparse;
 use super::*;
 
 impl Workspace {
     pub fn restore_snap(&self, snap_id: &str, force: bool) -> Result<()> {
+        self.restore_snap_with(snap_id, force, &SuperpositionMode::Refuse)
+            .map(|_| ())
+    }
+
+    /// `restore_snap`, writing superpositions as `mode` says. Returns the
+    /// paths written as conflict markers.
+    pub fn restore_snap_with(
+        &self,
+        snap_id: &str,
+        force: bool,
+        mode: &SuperpositionMode,
+    ) -> Result<Vec<String>> {
         let snap = self.store.get_snap(snap_id)?;
         self.ensure_safe_to_overwrite(force)?;
 
         // Destruction deferred until the target fully materializes
         // (batch 12.1): a superposed or unfetchable target must not
         // cost the current tree.
-        let preserve = self.preserved_entries();
-        let preserve: Vec<&str> = preserve.iter().map(String::as_str).collect();
-        materialize_fs::materialize_via_temp(
-            &self.store,
+        let marked = self.materialize_workspace(
             &snap.root_manifest,
-            &self.root,
-            &preserve,
+            snap.derived_from_candidate.as_deref(),
+            mode,
         )?;
         self.store.set_head(Some(&snap.id))?;
-        Ok(())
+        Ok(marked)
     }
 
     /// Materialize a stored tree into the workspace and capture it as a
@@ -33,16 +44,59 @@ impl Workspace {
         message: Option<String>,
         derived_from_candidate: Option<&str>,
         force: bool,
+    ) -> Result<crate::model::SnapRecord> {
+        self.adopt_tree_with(
+            root_manifest,
+            message,
+            derived_from_candidate,
+            force,
+            &SuperpositionMode::Refuse,
+        )
+    }
+
+    /// `adopt_tree`, writing superpositions as `mode` says; the snap
+    /// still holds the superposed tree, so status stays clean until a
+    /// marker file is edited.
+    pub fn adopt_tree_with(
+        &self,
+        root_manifest: &ObjectId,
+        message: Option<String>,
+        derived_from_candidate: Option<&str>,
+        force: bool,
+        mode: &SuperpositionMode,
     ) -> Result<crate::model::SnapRecord> {
         self.ensure_safe_to_overwrite(force)?;
-        let preserve = self.preserved_entries();
-        let preserve: Vec<&str> = preserve.iter().map(String::as_str).collect();
-        materialize_fs::materialize_via_temp(&self.store, root_manifest, &self.root, &preserve)?;
+        self.materialize_workspace(root_manifest, derived_from_candidate, mode)?;
         let snap = self.capture_tree(root_manifest, message, derived_from_candidate)?;
         self.store.set_head(Some(&snap.id))?;
         Ok(snap)
     }
 
+    /// Replace the working tree with `root_manifest` — as much of it as
+    /// the sparse set selects (doc 15 §5b) — and remember which paths
+    /// became marker files (doc 17 §2d). Any earlier marker state
+    /// belonged to the tree just replaced, so it is overwritten.
+    pub(super) fn materialize_workspace(
+        &self,
+        root_manifest: &ObjectId,
+        candidate_id: Option<&str>,
+        mode: &SuperpositionMode,
+    ) -> Result<Vec<String>> {
+        let preserve = self.preserved_entries()?;
+        let preserve: Vec<&str> = preserve.iter().map(String::as_str).collect();
+        let marked = materialize_fs::materialize_via_temp(
+            &self.store,
+            root_manifest,
+            &self.root,
+            &preserve,
+            mode,
+            &Sparse::load(&self.store)?,
+        )?;
+        let paths = marked.keys().cloned().collect();
+        MarkerState::new(root_manifest, candidate_id, marked).save(&self.store)?;
+        Ok(paths)
+    }
+
     /// Entries a workspace materialize must leave alone: the internals,
     /// plus everything `.convergeignore` claims (batch 18.4).
     ///
@@ -51,10 +105,24 @@ impl Workspace {
     /// them and a restore has nothing to put back. Deleting them anyway
     /// destroys expensive local state to no purpose, and it is not what
     /// checking out a revision means anywhere else.
-    fn preserved_entries(&self) -> Vec<String> {
+    ///
+    /// Only top-level entries can be kept whole: an ignored path deeper
+    /// down sits inside a directory the snap replaces.
+    fn preserved_entries(&self) -> Result<Vec<String>> {
         let mut preserve = vec![".converge".to_string(), ".git".to_string()];
-        preserve.extend(super::manifest_scan::common::load_root_ignores(&self.root));
-        preserve
+        let ignores = super::ignore::IgnoreRules::root(&self.root);
+        for entry in
+            fs::read_dir(&self.root).with_context(|| format!("read dir {}", self.root.display()))?
+        {
+            let entry = entry?;
+            let Ok(name) = entry.file_name().into_string() else {
+                continue;
+            };
+            if ignores.is_ignored(&name, entry.file_type()?.is_dir()) {
+                preserve.push(name);
+            }
+        }
+        Ok(preserve)
     }
 
     /// What replacing this workspace's tree with `target` would cost.
@@ -118,7 +186,7 @@ impl Workspace {
     }
 
     /// Refuse to overwrite a workspace carrying uncaptured work.
-    fn ensure_safe_to_overwrite(&self, force: bool) -> Result<()> {
+    pub(super) fn ensure_safe_to_overwrite(&self, force: bool) -> Result<()> {
         if !force {
             let (cur_root, _cur_manifests, _stats) = self.current_manifest_tree()?;
 
@@ -155,7 +223,14 @@ impl Workspace {
     pub fn materialize_snap_to(&self, snap_id: &str, out_dir: &Path, force: bool) -> Result<()> {
         let snap = self.store.get_snap(snap_id)?;
         ensure_output_dir_ready(out_dir, force)?;
-        materialize_fs::materialize_via_temp(&self.store, &snap.root_manifest, out_dir, &[])?;
+        materialize_fs::materialize_via_temp(
+            &self.store,
+            &snap.root_manifest,
+            out_dir,
+            &[],
+            &SuperpositionMode::Refuse,
+            &Sparse::default(),
+        )?;
         Ok(())
     }
 
@@ -167,7 +242,14 @@ impl Workspace {
         force: bool,
     ) -> Result<()> {
         ensure_output_dir_ready(out_dir, force)?;
-        materialize_fs::materialize_via_temp(&self.store, root_manifest, out_dir, &[])?;
+        materialize_fs::materialize_via_temp(
+            &self.store,
+            root_manifest,
+            out_dir,
+            &[],
+            &SuperpositionMode::Refuse,
+            &Sparse::default(),
+        )?;
         Ok(())
     }
 }
@@ -10,6 +10,15 @@ impl Workspace {
     }
 
     pub fn discover(start: &Path) -> Result<Self> {
+        let root = Self::find_root(start)?;
+        let store = LocalStore::open(&root)?;
+        Ok(Self { root, store })
+    }
+
+    /// The workspace root `discover` would open, without opening it —
+    /// for `converge migrate`, which has to reach a store that `open`
+    /// refuses (doc 16 §3).
+    pub fn find_root(start: &Path) -> Result<std::path::PathBuf> {
         let start = start
             .canonicalize()
             .with_context(|| format!("canonicalize {}", start.display()))?;
@@ -39,11 +48,7 @@ impl Workspace {
             if !converge_dir.join("config.json").is_file() {
                 continue;
             }
-            let store = LocalStore::open(dir)?;
-            return Ok(Self {
-                root: dir.to_path_buf(),
-                store,
-            });
+            return Ok(dir.to_path_buf());
         }
         Err(anyhow!(
             "No .converge workspace found here or in any parent directory \
@@ -0,0 +1,232 @@
+use serde::Serialize;
+use time::format_description::well_known::Rfc3339;
+
+use super::markers::MarkerState;
+use super::partial_snap::Selection;
+use super::sparse::{Cover, Sparse};
+use super::tree_edit::{lookup, set_path, variants};
+use super::*;
+
+use crate::model::{ManifestEntryKind, ShelfRecord};
+
+/// What `unshelve` put back.
+#[derive(Clone, Debug, Serialize)]
+pub struct Unshelved {
+    pub name: String,
+    /// Every path the shelf touched, as it now is on disk.
+    pub paths: Vec<String>,
+    /// Paths changed on both sides since the shelf was taken, written as
+    /// conflict markers (doc 17 §2d).
+    pub conflicts: Vec<String>,
+}
+
+/// The variant source the working tree's side of a conflict is shown as.
+const CURRENT: &str = "current";
+
+impl Workspace {
+    /// Set uncaptured edits aside as a shelf (doc 15 §5e) and put the
+    /// working tree back to head. With `paths`, only edits under those
+    /// patterns are shelved, as `snap --path` selects them (§5g), and the
+    /// rest stay on disk.
+    pub fn shelve(&self, name: Option<&str>, paths: &[String]) -> Result<ShelfRecord> {
+        let name = match name {
+            Some(name) => {
+                check_shelf_name(name)?;
+                if self.store.has_shelf(name) {
+                    anyhow::bail!("a shelf named {name} already exists");
+                }
+                name.to_string()
+            }
+            None => (1..)
+                .map(|n| format!("shelf-{n}"))
+                .find(|name| !self.store.has_shelf(name))
+                .expect("a free shelf name"),
+        };
+        self.refuse_over_markers("shelving")?;
+        let Some(base) = self.store.get_head()? else {
+            anyhow::bail!("nothing to shelve onto: this workspace has no head snap yet");
+        };
+        let base_root = self.store.get_snap(&base)?.root_manifest;
+        let current = self.scan_into_store()?;
+
+        let selection = Selection::new(paths)?;
+        let changed: Vec<String> = changed_paths(&self.store, &base_root, &current)?
+            .into_iter()
+            .filter(|path| paths.is_empty() || selection.selects(path))
+            .collect();
+        if changed.is_empty() {
+            anyhow::bail!(
+                "nothing to shelve: {} matches head",
+                if paths.is_empty() {
+                    "the working tree"
+                } else {
+                    "every path given"
+                }
+            );
+        }
+        // The shelf is head plus the edits; what stays on disk is the
+        // working tree without them. Shelving everything is both trees
+        // whole.
+        let (shelved, kept) = if paths.is_empty() {
+            (current, base_root.clone())
+        } else {
+            let mut shelved = base_root.clone();
+            let mut kept = current.clone();
+            for path in &changed {
+                shelved = set_path(
+                    &self.store,
+                    &shelved,
+                    path,
+                    lookup(&self.store, &current, path)?,
+                )?;
+                kept = set_path(
+                    &self.store,
+                    &kept,
+                    path,
+                    lookup(&self.store, &base_root, path)?,
+                )?;
+            }
+            (shelved, kept)
+        };
+
+        let shelf = ShelfRecord {
+            name,
+            created_at: time::OffsetDateTime::now_utc()
+                .format(&Rfc3339)
+                .context("format created_at")?,
+            base,
+            base_root,
+            root_manifest: shelved,
+            paths: changed,
+        };
+        // Recorded before the tree is touched, so the edits are never
+        // only in a tree being rewritten.
+        self.store.put_shelf(&shelf)?;
+        if let Err(err) = self.materialize_workspace(&kept, None, &SuperpositionMode::Refuse) {
+            self.store.delete_shelf(&shelf.name)?;
+            return Err(err);
+        }
+        Ok(shelf)
+    }
+
+    /// Re-apply a shelf — the newest when `name` is `None` — to the
+    /// working tree, and drop it. A path changed on disk or by a new head
+    /// since it was shelved, differently, becomes a conflict-marker file
+    /// against the shelf's base. A conflict markers cannot show refuses
+    /// the whole unshelve and keeps the shelf.
+    pub fn unshelve(&self, name: Option<&str>) -> Result<Unshelved> {
+        let shelf = match name {
+            Some(name) => self.store.get_shelf(name)?,
+            None => match self.store.list_shelves()?.pop() {
+                Some(shelf) => shelf,
+                None => anyhow::bail!("nothing is shelved"),
+            },
+        };
+        self.refuse_over_markers("unshelving")?;
+        // Off disk, an edit would be carried as head's by the next scan
+        // (doc 15 §5b): put back, and silently lost.
+        let sparse = Sparse::load(&self.store)?;
+        if let Some(path) = shelf
+            .paths
+            .iter()
+            .find(|path| sparse.cover(path, false) == Cover::None)
+        {
+            anyhow::bail!(
+                "{path} is outside the sparse set; `converge sparse add` it before unshelving {}",
+                shelf.name
+            );
+        }
+        let current = self.scan_into_store()?;
+
+        let mut target = current.clone();
+        let mut conflicts = Vec::new();
+        for path in &shelf.paths {
+            let base = lookup(&self.store, &shelf.base_root, path)?;
+            let theirs = lookup(&self.store, &shelf.root_manifest, path)?;
+            let ours = lookup(&self.store, &current, path)?;
+            let value = if ours == base {
+                theirs
+            } else if ours == theirs {
+                continue;
+            } else {
+                conflicts.push(path.clone());
+                Some(ManifestEntryKind::Superposition {
+                    variants: [
+                        variants(CURRENT, ours)?,
+                        variants(&format!("shelf {}", shelf.name), theirs)?,
+                    ]
+                    .concat(),
+                })
+            };
+            target = set_path(&self.store, &target, path, value)?;
+        }
+
+        let mode = if conflicts.is_empty() {
+            SuperpositionMode::Refuse
+        } else {
+            SuperpositionMode::Markers {
+                base: Some(shelf.base_root.clone()),
+            }
+        };
+        self.materialize_workspace(&target, None, &mode)
+            .with_context(|| {
+                format!(
+                    "{} conflicts with the working tree where markers cannot show it; \
+                     the shelf is kept and nothing was changed",
+                    shelf.name
+                )
+            })?;
+        self.store.delete_shelf(&shelf.name)?;
+        Ok(Unshelved {
+            name: shelf.name,
+            paths: shelf.paths,
+            conflicts,
+        })
+    }
+
+    pub fn list_shelves(&self) -> Result<Vec<ShelfRecord>> {
+        self.store.list_shelves()
+    }
+
+    /// The working tree, stored: what a snap would capture, with no
+    /// snap made.
+    fn scan_into_store(&self) -> Result<ObjectId> {
+        let cfg = self.store.read_config()?;
+        self.build_manifest(
+            &mut SnapStats::default(),
+            &chunking::chunking_policy(&cfg)?,
+            &MarkerState::default(),
+            &Sparse::new(&cfg.sparse)?,
+            manifest_scan::common::scan_threads(&cfg),
+        )
+    }
+}
+
+/// A shelf name is a file name in `.converge/shelves`.
+fn check_shelf_name(name: &str) -> Result<()> {
+    let ok = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !ok {
+        anyhow::bail!(
+            "shelf name {name:?} must be letters, digits, `-`, `_` or `.`, not starting with `.`"
+        );
+    }
+    Ok(())
+}
+
+/// The file, symlink and superposition paths that differ between two
+/// stored trees.
+fn changed_paths(store: &LocalStore, from: &ObjectId, to: &ObjectId) -> Result<Vec<String>> {
+    if from == to {
+        return Ok(Vec::new());
+    }
+    let from = crate::diff::tree_from_store(store, from)?;
+    let to = crate::diff::tree_from_store(store, to)?;
+    Ok(crate::diff::diff_trees(&from, &to)
+        .iter()
+        .map(|line| line.path().to_string())
+        .collect())
+}
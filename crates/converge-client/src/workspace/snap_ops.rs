@@ -4,7 +4,9 @@ use super::*;
 
 use time::format_description::well_known::Rfc3339;
 
-use crate::model::{SnapRecord, compute_snap_id};
+use super::sparse::Sparse;
+use super::tree_watch::TreeChanges;
+use crate::model::{SnapAuthor, SnapRecord, compute_snap_id};
 
 impl Workspace {
     pub fn create_snap(&self, message: Option<String>) -> Result<SnapRecord> {
@@ -30,6 +32,25 @@ impl Workspace {
         derived_from_candidate: Option<&str>,
     ) -> Result<SnapRecord> {
         let parents: Vec<String> = self.store.get_head()?.into_iter().collect();
+        self.capture_tree_on(
+            parents,
+            root_manifest,
+            message,
+            derived_from_candidate,
+            None,
+        )
+    }
+
+    /// `capture_tree` with the parents given rather than head, and for a
+    /// replay, the snap whose change the tree carries (doc 15 §5h).
+    pub(super) fn capture_tree_on(
+        &self,
+        parents: Vec<String>,
+        root_manifest: &ObjectId,
+        message: Option<String>,
+        derived_from_candidate: Option<&str>,
+        replayed_from: Option<&str>,
+    ) -> Result<SnapRecord> {
         let message = message
             .map(|m| m.trim().to_string())
             .filter(|m| !m.is_empty());
@@ -43,7 +64,14 @@ impl Workspace {
             }
         }
 
-        let id = compute_snap_id(root_manifest, &parents, derived_from_candidate);
+        let author = self.snap_author(&self.store.read_config()?);
+        let id = compute_snap_id(
+            root_manifest,
+            &parents,
+            derived_from_candidate,
+            author.as_ref(),
+            replayed_from,
+        );
         let snap = SnapRecord {
             version: 2,
             id,
@@ -53,6 +81,8 @@ impl Workspace {
             root_manifest: root_manifest.clone(),
             parents,
             derived_from_candidate: derived_from_candidate.map(str::to_string),
+            author,
+            replayed_from: replayed_from.map(str::to_string),
             message,
             trigger: "explicit".to_string(),
             stats: self.stats_for_root(root_manifest)?,
@@ -65,12 +95,12 @@ impl Workspace {
     /// that arrived from the wire has to be walked once to say the same
     /// thing. Superpositions count as one entry — an unresolved path is
     /// one thing in the tree, whatever it holds.
-    fn stats_for_root(&self, root: &ObjectId) -> Result<SnapStats> {
+    pub(super) fn stats_for_root(&self, root: &ObjectId) -> Result<SnapStats> {
         let mut stats = SnapStats::default();
         let mut stack = vec![root.clone()];
         while let Some(id) = stack.pop() {
             stats.dirs += 1;
-            for entry in self.store.get_manifest(&id)?.entries {
+            for entry in self.store.get_dir(&id)?.entries {
                 match entry.kind {
                     crate::model::ManifestEntryKind::Dir { manifest } => stack.push(manifest),
                     crate::model::ManifestEntryKind::File { size, .. }
@@ -80,6 +110,9 @@ impl Workspace {
                     }
                     crate::model::ManifestEntryKind::Symlink { .. } => stats.symlinks += 1,
                     crate::model::ManifestEntryKind::Superposition { .. } => stats.files += 1,
+                    crate::model::ManifestEntryKind::Page { .. } => {
+                        unreachable!("get_dir reads pages through")
+                    }
                 }
             }
         }
@@ -91,11 +124,60 @@ impl Workspace {
     pub fn create_snap_with(&self, message: Option<String>, trigger: &str) -> Result<SnapRecord> {
         // Validate store format early.
         let cfg = self.store.read_config()?;
-        let policy = chunking::chunking_policy_from_config(cfg.chunking.as_ref())?;
+        let policy = chunking::chunking_policy(&cfg)?;
 
         let mut stats = SnapStats::default();
-        let root_manifest = self.build_manifest(&self.root, &mut stats, policy)?;
+        let markers = super::markers::MarkerState::load(&self.store)?;
+        let sparse = Sparse::new(&cfg.sparse)?;
+        let root_manifest = self.build_manifest(
+            &mut stats,
+            &policy,
+            &markers,
+            &sparse,
+            manifest_scan::common::scan_threads(&cfg),
+        )?;
+        if !sparse.is_everything() {
+            // The scan counted what is on disk; the snap holds more.
+            stats = self.stats_for_root(&root_manifest)?;
+        }
+        self.snap_scanned(&cfg, root_manifest, stats, message, trigger)
+    }
+
+    /// `create_snap_with` for a watcher that knows what changed (doc 15
+    /// §5a): only the directories `changes` reaches are read again, and
+    /// the rest is the head's tree as stored. Without a head, or when the
+    /// changes cannot say what moved, this is the full scan.
+    pub fn create_snap_changed(&self, changes: &TreeChanges, trigger: &str) -> Result<SnapRecord> {
+        let head = match self.store.get_head()? {
+            Some(head) if !changes.is_full() => self.store.get_snap(&head)?,
+            _ => return self.create_snap_with(None, trigger),
+        };
+        let cfg = self.store.read_config()?;
+        let policy = chunking::chunking_policy(&cfg)?;
+        let markers = super::markers::MarkerState::load(&self.store)?;
+        let mut scratch = SnapStats::default();
+        let root_manifest = self.rescan_manifest(
+            &head.root_manifest,
+            changes,
+            &mut scratch,
+            &policy,
+            &markers,
+            &Sparse::new(&cfg.sparse)?,
+            manifest_scan::common::scan_threads(&cfg),
+        )?;
+        let stats = self.stats_for_root(&root_manifest)?;
+        self.snap_scanned(&cfg, root_manifest, stats, None, trigger)
+    }
 
+    /// Record a scanned tree as a snap and move head to it.
+    pub(super) fn snap_scanned(
+        &self,
+        cfg: &crate::model::WorkspaceConfig,
+        root_manifest: ObjectId,
+        stats: SnapStats,
+        message: Option<String>,
+        trigger: &str,
+    ) -> Result<SnapRecord> {
         let parents: Vec<String> = self.store.get_head()?.into_iter().collect();
 
         // Idempotent recapture (arch 17 §1): a tree identical to the head
@@ -134,7 +216,8 @@ impl Workspace {
             }
         }
 
-        let id = compute_snap_id(&root_manifest, &parents, None);
+        let author = self.snap_author(cfg);
+        let id = compute_snap_id(&root_manifest, &parents, None, author.as_ref(), None);
 
         let created_at = time::OffsetDateTime::now_utc()
             .format(&Rfc3339)
@@ -146,15 +229,37 @@ impl Workspace {
             root_manifest,
             parents,
             derived_from_candidate: None,
+            author,
+            replayed_from: None,
             message,
             trigger: trigger.to_string(),
             stats,
         };
         self.store.put_snap(&snap)?;
         self.store.set_head(Some(&snap.id))?;
+        self.settle_markers(&snap.root_manifest)?;
         Ok(snap)
     }
 
+    /// Who a snap captured now is authored as (doc 17 §1): the subject
+    /// and name `converge login` recorded, plus the newest personal key
+    /// this machine holds. No login, no author — a workspace that has
+    /// never talked to a server has nobody to vouch for the name.
+    ///
+    /// The key is best-effort. An unreadable key index is not a reason
+    /// to refuse a snap, and the key id is a hint, not a signature.
+    fn snap_author(&self, cfg: &crate::model::WorkspaceConfig) -> Option<SnapAuthor> {
+        let author = cfg.author.as_ref()?;
+        let key_id = crate::identity::local_keys()
+            .ok()
+            .and_then(|keys| keys.last().map(|k| k.key_id.clone()));
+        Some(SnapAuthor {
+            subject: author.subject.clone(),
+            display_name: author.display_name.clone(),
+            key_id,
+        })
+    }
+
     /// Lineage order: head-first parent walk, then any snaps unreachable
     /// from head (parallel branches) newest-first by `created_at`.
     pub fn list_snaps(&self) -> Result<Vec<SnapRecord>> {
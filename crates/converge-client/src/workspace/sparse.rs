@@ -0,0 +1,204 @@
+use anyhow::{Context, Result, bail};
+use globset::{GlobBuilder, GlobMatcher};
+
+use super::*;
+
+/// How much of one path a sparse set selects.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(in crate::workspace) enum Cover {
+    /// The path and everything under it.
+    All,
+    /// A directory some of whose contents are selected: it exists on
+    /// disk, holding only those.
+    Partial,
+    /// Nothing: the path stays in the tree as head has it, and off disk.
+    None,
+}
+
+/// The sparse patterns in force for a workspace (doc 15 §5b).
+///
+/// A pattern selects a path and everything below it: `assets/characters`
+/// is that directory, `assets/*/textures` every such directory, and
+/// `**/*.psd` every Photoshop file. Patterns are tree paths from the
+/// workspace root, with `.convergeignore`'s glob grammar.
+#[derive(Clone, Debug, Default)]
+pub(in crate::workspace) struct Sparse {
+    patterns: Vec<Pattern>,
+}
+
+#[derive(Clone, Debug)]
+struct Pattern {
+    /// The leading segments with no glob in them: only directories on
+    /// the way to or below this can hold a match.
+    literal: String,
+    globbed: bool,
+    /// How many segments a match has, unless a `**` lets it have any
+    /// number: a directory that deep or deeper cannot hold one.
+    depth: Option<usize>,
+    matcher: GlobMatcher,
+}
+
+impl Sparse {
+    pub(in crate::workspace) fn new(patterns: &[String]) -> Result<Self> {
+        let mut compiled = Vec::with_capacity(patterns.len());
+        for pattern in patterns {
+            let Some((pattern, matcher)) = compile_tree_pattern(pattern, "sparse pattern")? else {
+                return Err(whole_tree(pattern));
+            };
+            let literal: Vec<&str> = pattern
+                .split('/')
+                .take_while(|s| !s.contains(['*', '?', '[', '{', '\\']))
+                .collect();
+            let segments = pattern.split('/').count();
+            compiled.push(Pattern {
+                globbed: literal.len() != segments,
+                literal: literal.join("/"),
+                depth: (!pattern.split('/').any(|s| s.contains("**"))).then_some(segments),
+                matcher,
+            });
+        }
+        Ok(Self { patterns: compiled })
+    }
+
+    /// The sparse set the workspace's config names.
+    pub(in crate::workspace) fn load(store: &LocalStore) -> Result<Self> {
+        Self::new(&store.read_config()?.sparse)
+    }
+
+    /// No patterns: a full workspace.
+    pub(in crate::workspace) fn is_everything(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// How much of `tree_path` is selected, given that its parent is
+    /// only partly selected.
+    pub(in crate::workspace) fn cover(&self, tree_path: &str, is_dir: bool) -> Cover {
+        if self.is_everything() || self.patterns.iter().any(|p| p.matcher.is_match(tree_path)) {
+            return Cover::All;
+        }
+        let depth = tree_path.split('/').count();
+        let below = |p: &Pattern| {
+            (p.literal.is_empty()
+                || p.literal == tree_path
+                || tree_path.starts_with(&format!("{}/", p.literal)))
+                && p.depth.is_none_or(|d| depth < d)
+        };
+        let reaches = self
+            .patterns
+            .iter()
+            .any(|p| p.literal.starts_with(&format!("{tree_path}/")) || (p.globbed && below(p)));
+        if is_dir && reaches {
+            Cover::Partial
+        } else {
+            Cover::None
+        }
+    }
+}
+
+/// A tree-path pattern as stored, without a leading `/` or `./` or a
+/// trailing `/`, and compiled with `.convergeignore`'s glob grammar.
+/// `None` for one that trims to nothing: the whole tree. `noun` names
+/// the pattern in errors.
+pub(in crate::workspace) fn compile_tree_pattern(
+    pattern: &str,
+    noun: &str,
+) -> Result<Option<(String, GlobMatcher)>> {
+    let Some(path) = normalize(pattern, noun)? else {
+        return Ok(None);
+    };
+    let matcher = GlobBuilder::new(&path)
+        .literal_separator(true)
+        .backslash_escape(true)
+        .build()
+        .with_context(|| format!("{noun} {pattern:?}"))?
+        .compile_matcher();
+    Ok(Some((path, matcher)))
+}
+
+fn normalize(pattern: &str, noun: &str) -> Result<Option<String>> {
+    let trimmed = pattern.trim();
+    let trimmed = trimmed.strip_prefix("./").unwrap_or(trimmed);
+    let trimmed = trimmed.trim_matches('/');
+    if trimmed.is_empty() {
+        return Ok(None);
+    }
+    if trimmed
+        .split('/')
+        .any(|s| s.is_empty() || s == "." || s == "..")
+    {
+        bail!("{noun} {pattern:?} is not a tree path");
+    }
+    Ok(Some(trimmed.to_string()))
+}
+
+/// A sparse pattern as stored. The whole tree is not one.
+fn sparse_pattern(pattern: &str) -> Result<String> {
+    normalize(pattern, "sparse pattern")?.ok_or_else(|| whole_tree(pattern))
+}
+
+fn whole_tree(pattern: &str) -> anyhow::Error {
+    anyhow::anyhow!(
+        "sparse pattern {pattern:?} selects the whole tree; use `converge sparse remove` instead"
+    )
+}
+
+impl Workspace {
+    /// The sparse patterns in force; empty for a full workspace.
+    pub fn sparse_patterns(&self) -> Result<Vec<String>> {
+        Ok(self.store.read_config()?.sparse)
+    }
+
+    /// Add sparse patterns and write what they select to disk. Returns
+    /// the patterns now in force.
+    pub fn sparse_add(&self, patterns: &[String], force: bool) -> Result<Vec<String>> {
+        let mut next = self.sparse_patterns()?;
+        for pattern in patterns {
+            let pattern = sparse_pattern(pattern)?;
+            if !next.contains(&pattern) {
+                next.push(pattern);
+            }
+        }
+        self.set_sparse(next, force)
+    }
+
+    /// Drop sparse patterns and take what only they selected off disk.
+    /// Removing the last one makes the workspace full again.
+    pub fn sparse_remove(&self, patterns: &[String], force: bool) -> Result<Vec<String>> {
+        let mut next = self.sparse_patterns()?;
+        for pattern in patterns {
+            let pattern = sparse_pattern(pattern)?;
+            let Some(at) = next.iter().position(|p| *p == pattern) else {
+                bail!("{pattern} is not a sparse pattern here");
+            };
+            next.remove(at);
+        }
+        self.set_sparse(next, force)
+    }
+
+    /// Replace the sparse set and rewrite the working tree to match
+    /// head under it. Nothing is captured: the tree head holds does not
+    /// change, only how much of it is on disk.
+    fn set_sparse(&self, patterns: Vec<String>, force: bool) -> Result<Vec<String>> {
+        Sparse::new(&patterns)?;
+        self.refuse_over_markers("changing the sparse set")?;
+        // Judged under the old set: that is what the disk reflects.
+        self.ensure_safe_to_overwrite(force)?;
+        let head = self.store.get_head()?;
+        let mut cfg = self.store.read_config()?;
+        let previous = std::mem::replace(&mut cfg.sparse, patterns.clone());
+        self.store.write_config(&cfg)?;
+        if let Some(head) = head {
+            let snap = self.store.get_snap(&head)?;
+            if let Err(err) = self.materialize_workspace(
+                &snap.root_manifest,
+                snap.derived_from_candidate.as_deref(),
+                &SuperpositionMode::Refuse,
+            ) {
+                cfg.sparse = previous;
+                self.store.write_config(&cfg)?;
+                return Err(err);
+            }
+        }
+        Ok(patterns)
+    }
+}
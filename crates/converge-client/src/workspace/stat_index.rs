@@ -0,0 +1,436 @@
+//! The stat index (doc 15 §5c): what each file held when a scan last
+//! read it, keyed by what `stat` said about it then, so the next scan —
+//! in the next process — reads only the files something has touched.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result, bail};
+
+use crate::model::{ChunkParams, ManifestEntryKind, ObjectId};
+use crate::store::{LocalStore, hash_bytes};
+
+use super::chunk_io::{chunk_to_recipe, recipe_id};
+use super::manifest_scan::common::{mode_of, read_file_stable, stream_file_stable};
+use super::*;
+
+const INDEX_FILE: &str = "index";
+const MAGIC: &[u8; 8] = b"CVSTAT\x00\x01";
+
+/// A point in time as the filesystem records it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+struct Stamp {
+    secs: u64,
+    nanos: u32,
+}
+
+impl Stamp {
+    fn of(time: std::io::Result<SystemTime>) -> Self {
+        // Before the epoch, or unknown: the oldest stamp, which the
+        // racy check still compares honestly against.
+        let since = time
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .unwrap_or_default();
+        Self {
+            secs: since.as_secs(),
+            nanos: since.subsec_nanos(),
+        }
+    }
+}
+
+/// What `stat` says about a file: anything that changes when its bytes
+/// do, short of reading them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(in crate::workspace) struct FileStat {
+    size: u64,
+    mtime: Stamp,
+    ctime: Stamp,
+    ino: u64,
+    mode: u32,
+}
+
+impl FileStat {
+    pub(in crate::workspace) fn of(meta: &fs::Metadata) -> Self {
+        #[cfg(unix)]
+        let (ctime, ino) = {
+            use std::os::unix::fs::MetadataExt;
+            (
+                Stamp {
+                    secs: meta.ctime().max(0) as u64,
+                    nanos: meta.ctime_nsec().clamp(0, 999_999_999) as u32,
+                },
+                meta.ino(),
+            )
+        };
+        #[cfg(not(unix))]
+        let (ctime, ino) = (Stamp::default(), 0);
+        Self {
+            size: meta.len(),
+            mtime: Stamp::of(meta.modified()),
+            ctime,
+            ino,
+            mode: mode_of(meta),
+        }
+    }
+
+    pub(in crate::workspace) fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+/// The content a file held, as the scan stored it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(in crate::workspace) enum Indexed {
+    Blob(ObjectId),
+    /// Chunked with `ChunkParams`; other params give another recipe.
+    Recipe(ObjectId, ChunkParams),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Entry {
+    stat: FileStat,
+    content: Indexed,
+}
+
+/// The index as one scan reads and extends it.
+///
+/// Racy entries are git's problem too: a file written in the same
+/// timestamp tick as the index can change again without its stat
+/// changing. So an entry is trusted only when its mtime is strictly
+/// older than the index file's own; anything newer is read again, and
+/// the save that follows makes it old enough.
+pub(in crate::workspace) struct StatIndex {
+    path: PathBuf,
+    /// When the index was written; `None` before the first save.
+    written: Option<Stamp>,
+    entries: HashMap<String, Entry>,
+    seen: Mutex<HashMap<String, Entry>>,
+}
+
+impl StatIndex {
+    /// The workspace's index. One that cannot be read is treated as
+    /// empty: the scan reads everything, and its save replaces the file.
+    pub(in crate::workspace) fn open(store: &LocalStore) -> Self {
+        let path = store.root_dir().join(INDEX_FILE);
+        let (entries, written) = read(&path).unwrap_or_default();
+        Self {
+            path,
+            written,
+            entries,
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn trusted(&self, entry: &Entry) -> bool {
+        self.written
+            .is_some_and(|written| entry.stat.mtime < written)
+    }
+
+    /// What the file at `tree_path` holds, when its stat is unchanged
+    /// since the scan that recorded it and `params` would store it the
+    /// same way.
+    pub(in crate::workspace) fn lookup(
+        &self,
+        tree_path: &str,
+        stat: &FileStat,
+        params: Option<ChunkParams>,
+    ) -> Option<ManifestEntryKind> {
+        let entry = self.entries.get(tree_path)?;
+        if entry.stat != *stat || !self.trusted(entry) {
+            return None;
+        }
+        let (mode, size) = (stat.mode, stat.size);
+        let kind = match (&entry.content, params) {
+            (Indexed::Blob(blob), None) => ManifestEntryKind::File {
+                blob: blob.clone(),
+                mode,
+                size,
+            },
+            (Indexed::Recipe(recipe, was), Some(now)) if *was == now => {
+                ManifestEntryKind::FileChunks {
+                    recipe: recipe.clone(),
+                    mode,
+                    size,
+                }
+            }
+            _ => return None,
+        };
+        self.record(tree_path, *stat, entry.content.clone());
+        Some(kind)
+    }
+
+    /// Note what the scan found at `tree_path`, as `stat` described the
+    /// file before it was read.
+    pub(in crate::workspace) fn record(&self, tree_path: &str, stat: FileStat, content: Indexed) {
+        self.seen
+            .lock()
+            .expect("stat index lock")
+            .insert(tree_path.to_string(), Entry { stat, content });
+    }
+
+    /// Write what this scan saw. A `full` scan saw every file on disk,
+    /// so paths it did not record are gone; a partial one (a watch's
+    /// rescan) keeps the trusted entries it did not reach.
+    ///
+    /// Nothing is written when nothing changed. A failed write loses
+    /// only speed, so callers may ignore it.
+    pub(in crate::workspace) fn save(&self, full: bool) -> Result<()> {
+        let seen = std::mem::take(&mut *self.seen.lock().expect("stat index lock"));
+        let mut entries: HashMap<String, Entry> = if full {
+            HashMap::new()
+        } else {
+            self.entries
+                .iter()
+                .filter(|(_, e)| self.trusted(e))
+                .map(|(p, e)| (p.clone(), e.clone()))
+                .collect()
+        };
+        entries.extend(seen);
+        if entries == self.entries && entries.values().all(|e| self.trusted(e)) {
+            return Ok(());
+        }
+        crate::store::write_atomic(&self.path, &encode(&entries)).context("write stat index")
+    }
+}
+
+fn read(path: &Path) -> Result<(HashMap<String, Entry>, Option<Stamp>)> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Default::default()),
+        Err(err) => return Err(err).with_context(|| format!("read {}", path.display())),
+    };
+    let written = Stamp::of(fs::metadata(path).and_then(|m| m.modified()));
+    Ok((decode(&bytes)?, Some(written)))
+}
+
+fn raw_id(id: &ObjectId) -> Option<[u8; 32]> {
+    blake3::Hash::from_hex(id.as_str())
+        .ok()
+        .map(|hash| *hash.as_bytes())
+}
+
+fn put_stamp(out: &mut Vec<u8>, stamp: Stamp) {
+    out.extend_from_slice(&stamp.secs.to_le_bytes());
+    out.extend_from_slice(&stamp.nanos.to_le_bytes());
+}
+
+/// Magic, then per entry: path length and path, size, mtime, ctime,
+/// inode, mode, then the content — a tag, the raw id, and for a recipe
+/// the chunk params. A blake3 hash of everything before it closes the
+/// file, so a torn or scribbled index reads as corrupt, not as wrong.
+fn encode(entries: &HashMap<String, Entry>) -> Vec<u8> {
+    let mut paths: Vec<&String> = entries.keys().collect();
+    paths.sort();
+    let mut out = MAGIC.to_vec();
+    for path in paths {
+        let entry = &entries[path];
+        let (Indexed::Blob(id) | Indexed::Recipe(id, _)) = &entry.content;
+        // Not a hash, so not worth a slot: the file is just read again.
+        let Some(raw) = raw_id(id) else {
+            continue;
+        };
+        out.extend_from_slice(&(path.len() as u32).to_le_bytes());
+        out.extend_from_slice(path.as_bytes());
+        out.extend_from_slice(&entry.stat.size.to_le_bytes());
+        put_stamp(&mut out, entry.stat.mtime);
+        put_stamp(&mut out, entry.stat.ctime);
+        out.extend_from_slice(&entry.stat.ino.to_le_bytes());
+        out.extend_from_slice(&entry.stat.mode.to_le_bytes());
+        match &entry.content {
+            Indexed::Blob(_) => {
+                out.push(0);
+                out.extend_from_slice(&raw);
+            }
+            Indexed::Recipe(_, params) => {
+                out.push(1);
+                out.extend_from_slice(&raw);
+                for n in [params.min_size, params.avg_size, params.max_size] {
+                    out.extend_from_slice(&n.to_le_bytes());
+                }
+            }
+        }
+    }
+    let sum = blake3::hash(&out);
+    out.extend_from_slice(sum.as_bytes());
+    out
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        if self.bytes.len() < n {
+            bail!("stat index is truncated");
+        }
+        let (head, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Ok(head)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into()?))
+    }
+
+    fn stamp(&mut self) -> Result<Stamp> {
+        Ok(Stamp {
+            secs: self.u64()?,
+            nanos: self.u32()?,
+        })
+    }
+
+    fn id(&mut self) -> Result<ObjectId> {
+        let raw: [u8; 32] = self.take(32)?.try_into()?;
+        Ok(ObjectId(blake3::Hash::from(raw).to_hex().to_string()))
+    }
+}
+
+fn decode(bytes: &[u8]) -> Result<HashMap<String, Entry>> {
+    let Some(body) = bytes.strip_prefix(MAGIC.as_slice()) else {
+        bail!("not a stat index");
+    };
+    if body.len() < 32 {
+        bail!("stat index is truncated");
+    }
+    let (body, sum) = body.split_at(body.len() - 32);
+    if blake3::hash(&bytes[..bytes.len() - 32]).as_bytes() != sum {
+        bail!("stat index fails its checksum");
+    }
+    let mut reader = Reader { bytes: body };
+    let mut entries = HashMap::new();
+    while !reader.bytes.is_empty() {
+        let len = reader.u32()? as usize;
+        let path = String::from_utf8(reader.take(len)?.to_vec()).context("stat index path")?;
+        let stat = FileStat {
+            size: reader.u64()?,
+            mtime: reader.stamp()?,
+            ctime: reader.stamp()?,
+            ino: reader.u64()?,
+            mode: reader.u32()?,
+        };
+        let content = match reader.take(1)?[0] {
+            0 => Indexed::Blob(reader.id()?),
+            1 => Indexed::Recipe(
+                reader.id()?,
+                ChunkParams {
+                    min_size: reader.u32()?,
+                    avg_size: reader.u32()?,
+                    max_size: reader.u32()?,
+                },
+            ),
+            other => bail!("stat index names content kind {other}"),
+        };
+        entries.insert(path, Entry { stat, content });
+    }
+    Ok(entries)
+}
+
+/// What `doctor` found in the stat index.
+#[derive(Clone, Debug, Default, serde::Serialize)]
+pub struct StatIndexReport {
+    /// False when there is no index yet.
+    pub present: bool,
+    pub entries: usize,
+    /// Entries checked against the file they describe (`deep` only).
+    pub verified: usize,
+    /// Why the index cannot be trusted, when it cannot.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub problem: Option<String>,
+}
+
+impl Workspace {
+    /// Check the stat index (doc 15 §5c) without changing it. `deep`
+    /// also reads every file the index would let a scan skip, and
+    /// compares its content with what the index says it holds.
+    pub fn check_stat_index(&self, deep: bool) -> Result<StatIndexReport> {
+        let path = self.store.root_dir().join(INDEX_FILE);
+        if !path.exists() {
+            return Ok(StatIndexReport::default());
+        }
+        let index = match read(&path) {
+            Ok((entries, written)) => StatIndex {
+                path,
+                written,
+                entries,
+                seen: Mutex::new(HashMap::new()),
+            },
+            Err(err) => {
+                return Ok(StatIndexReport {
+                    present: true,
+                    problem: Some(format!("{err:#}")),
+                    ..Default::default()
+                });
+            }
+        };
+        let mut report = StatIndexReport {
+            present: true,
+            entries: index.entries.len(),
+            ..Default::default()
+        };
+        if !deep {
+            return Ok(report);
+        }
+        let mut wrong = Vec::new();
+        let mut tree_paths: Vec<&String> = index.entries.keys().collect();
+        tree_paths.sort();
+        for tree_path in tree_paths {
+            let entry = &index.entries[tree_path];
+            let file = self.root.join(tree_path);
+            let Ok(meta) = fs::symlink_metadata(&file) else {
+                continue;
+            };
+            if FileStat::of(&meta) != entry.stat || !index.trusted(entry) {
+                continue;
+            }
+            let held = match &entry.content {
+                Indexed::Blob(_) => read_file_stable(&file).map(|(bytes, _)| hash_bytes(&bytes)),
+                Indexed::Recipe(_, params) => stream_file_stable(&file, |source| {
+                    let recipe = chunk_to_recipe(source, *params)?;
+                    let size = recipe.size;
+                    Ok((recipe_id(&recipe), size))
+                })
+                .map(|(id, _)| id),
+            };
+            let Ok(held) = held else {
+                continue;
+            };
+            let holds = match &entry.content {
+                Indexed::Blob(id) | Indexed::Recipe(id, _) => held == *id,
+            };
+            report.verified += 1;
+            if !holds {
+                wrong.push(tree_path.as_str());
+            }
+        }
+        if let Some(first) = wrong.first() {
+            report.problem = Some(format!(
+                "{} entr{} name content the file does not hold (first: {first})",
+                wrong.len(),
+                if wrong.len() == 1 { "y" } else { "ies" }
+            ));
+        }
+        Ok(report)
+    }
+
+    /// Throw the stat index away and build it again from a full scan of
+    /// the working tree. Returns how many files it holds.
+    pub fn rebuild_stat_index(&self) -> Result<usize> {
+        let path = self.store.root_dir().join(INDEX_FILE);
+        if path.exists() {
+            fs::remove_file(&path).with_context(|| format!("remove {}", path.display()))?;
+        }
+        let index = StatIndex::open(&self.store);
+        self.scan_in_memory(&index)?;
+        index.save(true)?;
+        Ok(read(&path)?.0.len())
+    }
+}
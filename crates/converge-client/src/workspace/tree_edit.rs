@@ -0,0 +1,119 @@
+use anyhow::Result;
+
+use crate::model::{
+    ManifestEntry, ManifestEntryKind, ObjectId, SuperpositionVariant, SuperpositionVariantKind,
+    paging,
+};
+use crate::store::LocalStore;
+
+/// The entry at `path` in the tree at `root`, if there is one. A paged
+/// directory on the way costs its index and the one page holding the
+/// name, not every page.
+pub(super) fn lookup(
+    store: &LocalStore,
+    root: &ObjectId,
+    path: &str,
+) -> Result<Option<ManifestEntryKind>> {
+    let mut dir = root.clone();
+    let mut segments = path.split('/').peekable();
+    while let Some(segment) = segments.next() {
+        let manifest = store.get_manifest(&dir)?;
+        let Some(kind) = paging::lookup(&manifest, segment, |page| store.get_manifest(page))?
+        else {
+            return Ok(None);
+        };
+        match (kind, segments.peek()) {
+            (kind, None) => return Ok(Some(kind)),
+            (ManifestEntryKind::Dir { manifest }, Some(_)) => dir = manifest,
+            _ => return Ok(None),
+        }
+    }
+    Ok(None)
+}
+
+/// The tree at `root` with the entry at `path` replaced by `value`, or
+/// removed when it is `None`. Directories on the way are made as needed,
+/// and one left empty by a removal goes too. Only those directories are
+/// rewritten: every other subtree keeps its id.
+pub(super) fn set_path(
+    store: &LocalStore,
+    root: &ObjectId,
+    path: &str,
+    value: Option<ManifestEntryKind>,
+) -> Result<ObjectId> {
+    match edit_dir(store, Some(root), path, value)? {
+        Some(root) => Ok(root),
+        None => store.put_dir(Vec::new()),
+    }
+}
+
+fn edit_dir(
+    store: &LocalStore,
+    dir: Option<&ObjectId>,
+    path: &str,
+    value: Option<ManifestEntryKind>,
+) -> Result<Option<ObjectId>> {
+    let mut entries = match dir {
+        Some(dir) => store.get_dir(dir)?.entries,
+        None => Vec::new(),
+    };
+    let (name, rest) = match path.split_once('/') {
+        Some((name, rest)) => (name, Some(rest)),
+        None => (path, None),
+    };
+    let at = entries.iter().position(|e| e.name == name);
+    let replacement = match rest {
+        None => value,
+        Some(rest) => {
+            let child = match at.map(|at| &entries[at].kind) {
+                Some(ManifestEntryKind::Dir { manifest }) => Some(manifest.clone()),
+                // Nothing to remove below a file; a value replaces it.
+                Some(_) if value.is_none() => return Ok(dir.cloned()),
+                _ => None,
+            };
+            edit_dir(store, child.as_ref(), rest, value)?
+                .map(|manifest| ManifestEntryKind::Dir { manifest })
+        }
+    };
+    if let Some(at) = at {
+        entries.remove(at);
+    }
+    if let Some(kind) = replacement {
+        entries.push(ManifestEntry {
+            name: name.to_string(),
+            kind,
+        });
+    }
+    if entries.is_empty() {
+        return Ok(None);
+    }
+    store.put_dir(entries).map(Some)
+}
+
+/// `kind` as the variants of a superposition, attributed to `source`:
+/// a tombstone for `None`, and another superposition's own variants,
+/// which keep their provenance.
+pub(super) fn variants(
+    source: &str,
+    kind: Option<ManifestEntryKind>,
+) -> Result<Vec<SuperpositionVariant>> {
+    let kind = match kind {
+        None => SuperpositionVariantKind::Tombstone,
+        Some(ManifestEntryKind::File { blob, mode, size }) => {
+            SuperpositionVariantKind::File { blob, mode, size }
+        }
+        Some(ManifestEntryKind::FileChunks { recipe, mode, size }) => {
+            SuperpositionVariantKind::FileChunks { recipe, mode, size }
+        }
+        Some(ManifestEntryKind::Dir { manifest }) => SuperpositionVariantKind::Dir { manifest },
+        Some(ManifestEntryKind::Symlink { target }) => SuperpositionVariantKind::Symlink { target },
+        Some(ManifestEntryKind::Superposition { variants }) => return Ok(variants),
+        Some(ManifestEntryKind::Page { .. }) => {
+            anyhow::bail!("a page entry outside a page index")
+        }
+    };
+    Ok(vec![SuperpositionVariant {
+        source: source.to_string(),
+        kind,
+    }])
+}
@@ -0,0 +1,351 @@
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+
+use super::Workspace;
+
+/// What changed in the working tree since a watcher last reported: the
+/// directories whose own entries moved, as tree paths (`""` is the
+/// root), or `full` when only a whole rescan can say.
+#[derive(Clone, Debug, Default)]
+pub struct TreeChanges {
+    pub(in crate::workspace) dirs: BTreeSet<String>,
+    pub(in crate::workspace) full: bool,
+}
+
+impl TreeChanges {
+    /// Everything: the first report of a watch, an event queue overflow,
+    /// or an edited ignore file, which changes what every directory
+    /// below it holds.
+    pub fn everything() -> Self {
+        Self {
+            dirs: BTreeSet::new(),
+            full: true,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.full && self.dirs.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.full
+    }
+
+    pub fn dirs(&self) -> impl Iterator<Item = &str> {
+        self.dirs.iter().map(String::as_str)
+    }
+
+    /// Did the listing of `tree_path` itself change?
+    pub(in crate::workspace) fn contains(&self, tree_path: &str) -> bool {
+        self.full || self.dirs.contains(tree_path)
+    }
+
+    /// Did anything at or below `tree_path` change?
+    pub(in crate::workspace) fn touches(&self, tree_path: &str) -> bool {
+        if self.contains(tree_path) || (tree_path.is_empty() && !self.dirs.is_empty()) {
+            return true;
+        }
+        let prefix = format!("{tree_path}/");
+        self.dirs
+            .range(prefix.clone()..)
+            .next()
+            .is_some_and(|d| d.starts_with(&prefix))
+    }
+}
+
+impl Workspace {
+    /// Start watching the working tree for changes (doc 15 §5a): one
+    /// inotify watch per directory the scan would read, so ignored build
+    /// output costs no watches and raises no events.
+    ///
+    /// Fails when the platform has no inotify, or the kernel's watch
+    /// limit runs out; `converge watch` then polls instead.
+    pub fn watch_tree(&self) -> Result<TreeWatcher> {
+        TreeWatcher::new(&self.root)
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub use self::linux::TreeWatcher;
+
+#[cfg(not(target_os = "linux"))]
+pub struct TreeWatcher(());
+
+#[cfg(not(target_os = "linux"))]
+impl TreeWatcher {
+    fn new(_root: &std::path::Path) -> Result<Self> {
+        anyhow::bail!("event-driven watch needs inotify, which this platform lacks")
+    }
+
+    pub fn watched_dirs(&self) -> usize {
+        0
+    }
+
+    pub fn next_quiet(&mut self, _quiet: std::time::Duration) -> Result<TreeChanges> {
+        unreachable!("a TreeWatcher cannot be constructed here")
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::collections::HashMap;
+    use std::io::ErrorKind;
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, Instant};
+
+    use anyhow::{Context, Result, anyhow};
+    use inotify::{Event, EventMask, Inotify, WatchDescriptor, WatchMask};
+
+    use super::TreeChanges;
+    use crate::workspace::ignore::IgnoreRules;
+    use crate::workspace::manifest_scan::common::{read_dir_sorted, should_ignore_name};
+
+    /// How often a watcher waiting out a quiet period looks for more
+    /// events. While nothing is pending it blocks in the kernel instead.
+    const STEP: Duration = Duration::from_millis(25);
+
+    struct Watched {
+        tree_path: String,
+        /// The ignore rules in force in its parent, which its own
+        /// `.convergeignore` extends into `rules`.
+        inherited: IgnoreRules,
+        /// The ignore rules in force inside this directory.
+        rules: IgnoreRules,
+    }
+
+    /// Inotify watches over a working tree, gathering changed directories
+    /// until the tree goes quiet.
+    pub struct TreeWatcher {
+        root: PathBuf,
+        inotify: Inotify,
+        dirs: HashMap<WatchDescriptor, Watched>,
+        pending: TreeChanges,
+        buffer: Vec<u8>,
+    }
+
+    fn mask() -> WatchMask {
+        WatchMask::CREATE
+            | WatchMask::DELETE
+            | WatchMask::MODIFY
+            | WatchMask::CLOSE_WRITE
+            | WatchMask::ATTRIB
+            | WatchMask::MOVED_FROM
+            | WatchMask::MOVED_TO
+            | WatchMask::ONLYDIR
+            | WatchMask::DONT_FOLLOW
+            | WatchMask::EXCL_UNLINK
+    }
+
+    impl TreeWatcher {
+        pub(super) fn new(root: &Path) -> Result<Self> {
+            let inotify = Inotify::init().context("start inotify")?;
+            let mut watcher = Self {
+                root: root.to_path_buf(),
+                inotify,
+                dirs: HashMap::new(),
+                // The tree may have moved before the first watch landed.
+                pending: TreeChanges::everything(),
+                buffer: vec![0; 64 * 1024],
+            };
+            watcher.watch_dir(root.to_path_buf(), String::new(), IgnoreRules::default())?;
+            Ok(watcher)
+        }
+
+        pub fn watched_dirs(&self) -> usize {
+            self.dirs.len()
+        }
+
+        /// Block until something changes and then nothing has for
+        /// `quiet`, and report what changed — the debounce: a build still
+        /// writing keeps pushing the capture back.
+        pub fn next_quiet(&mut self, quiet: Duration) -> Result<TreeChanges> {
+            let mut last = Instant::now();
+            loop {
+                let arrived = self.read(self.pending.is_empty())?;
+                if arrived {
+                    last = Instant::now();
+                }
+                if !self.pending.is_empty() && last.elapsed() >= quiet {
+                    return Ok(std::mem::take(&mut self.pending));
+                }
+                if !arrived {
+                    std::thread::sleep(STEP.min(quiet));
+                }
+            }
+        }
+
+        /// Take one buffer of events; `true` if any of them mattered.
+        fn read(&mut self, block: bool) -> Result<bool> {
+            let mut buffer = std::mem::take(&mut self.buffer);
+            let read = if block {
+                self.inotify.read_events_blocking(&mut buffer)
+            } else {
+                self.inotify.read_events(&mut buffer)
+            };
+            let events: Vec<Event<String>> = match read {
+                Ok(events) => events
+                    .map(|e| Event {
+                        wd: e.wd,
+                        mask: e.mask,
+                        cookie: e.cookie,
+                        name: e.name.map(|n| n.to_string_lossy().into_owned()),
+                    })
+                    .collect(),
+                Err(err) if err.kind() == ErrorKind::WouldBlock => Vec::new(),
+                Err(err) => return Err(err).context("read inotify events"),
+            };
+            self.buffer = buffer;
+            let mut mattered = false;
+            for event in events {
+                mattered |= self.absorb(event)?;
+            }
+            Ok(mattered)
+        }
+
+        fn absorb(&mut self, event: Event<String>) -> Result<bool> {
+            if event.mask.contains(EventMask::Q_OVERFLOW) {
+                // Events were dropped: nothing short of a rescan knows
+                // what they said.
+                self.pending.full = true;
+                return Ok(true);
+            }
+            if event.mask.contains(EventMask::IGNORED) {
+                // The directory is gone; its parent's event covers it.
+                self.dirs.remove(&event.wd);
+                return Ok(false);
+            }
+            let Some(watched) = self.dirs.get(&event.wd) else {
+                return Ok(false);
+            };
+            // A nameless event is about the directory itself, whose
+            // metadata the scan does not record.
+            let Some(name) = event.name else {
+                return Ok(false);
+            };
+            if should_ignore_name(&name) {
+                return Ok(false);
+            }
+            let child = if watched.tree_path.is_empty() {
+                name.clone()
+            } else {
+                format!("{}/{name}", watched.tree_path)
+            };
+            let is_dir = event.mask.contains(EventMask::ISDIR);
+            if watched.rules.is_ignored(&child, is_dir) {
+                return Ok(false);
+            }
+            let parent = watched.tree_path.clone();
+            let rules = watched.rules.clone();
+            if name == ".convergeignore" {
+                self.pending.full = true;
+                self.rewatch(&parent)?;
+                return Ok(true);
+            }
+            self.pending.dirs.insert(parent);
+            if is_dir
+                && event
+                    .mask
+                    .intersects(EventMask::CREATE | EventMask::MOVED_TO)
+            {
+                self.watch_dir(self.root.join(&child), child, rules)?;
+            }
+            Ok(true)
+        }
+
+        /// Drop the watches at and under `tree_path` and watch it again
+        /// under fresh rules: its ignore file changed, so directories it
+        /// now ignores must stop raising events and ones it no longer
+        /// ignores must start.
+        fn rewatch(&mut self, tree_path: &str) -> Result<()> {
+            let prefix = format!("{tree_path}/");
+            let stale: Vec<WatchDescriptor> = self
+                .dirs
+                .iter()
+                .filter(|(_, w)| {
+                    tree_path.is_empty()
+                        || w.tree_path == tree_path
+                        || w.tree_path.starts_with(&prefix)
+                })
+                .map(|(wd, _)| wd.clone())
+                .collect();
+            let mut inherited = None;
+            for wd in stale {
+                let watched = self.dirs.remove(&wd).expect("collected from dirs");
+                if watched.tree_path == tree_path {
+                    inherited = Some(watched.inherited);
+                }
+                // A directory already gone took its watch with it.
+                let _ = self.inotify.watches().remove(wd);
+            }
+            let Some(inherited) = inherited else {
+                return Ok(());
+            };
+            self.watch_dir(self.root.join(tree_path), tree_path.to_string(), inherited)
+        }
+
+        /// Watch `dir` and every directory under it the scan would read.
+        /// `rules` are those in force in its parent.
+        fn watch_dir(&mut self, dir: PathBuf, tree_path: String, rules: IgnoreRules) -> Result<()> {
+            let mut stack = vec![(dir, tree_path, rules)];
+            while let Some((dir, tree_path, inherited)) = stack.pop() {
+                let wd = match self.inotify.watches().add(&dir, mask()) {
+                    Ok(wd) => wd,
+                    // Gone before the watch landed: its parent saw it go.
+                    Err(err) if err.kind() == ErrorKind::NotFound => continue,
+                    Err(err) if err.kind() == ErrorKind::StorageFull => {
+                        return Err(limit_reached(self.dirs.len()));
+                    }
+                    Err(err) => {
+                        return Err(err).with_context(|| format!("watch {}", dir.display()));
+                    }
+                };
+                let rules = inherited.descend(&dir, &tree_path);
+                let children = match read_dir_sorted(&dir) {
+                    Ok(children) => children,
+                    Err(_) if !dir.is_dir() => continue,
+                    Err(err) => return Err(err),
+                };
+                for child in children {
+                    let Ok(name) = child.file_name().into_string() else {
+                        continue;
+                    };
+                    if should_ignore_name(&name) || !child.file_type()?.is_dir() {
+                        continue;
+                    }
+                    let child_path = if tree_path.is_empty() {
+                        name.clone()
+                    } else {
+                        format!("{tree_path}/{name}")
+                    };
+                    if rules.is_ignored(&child_path, true) {
+                        continue;
+                    }
+                    stack.push((child.path(), child_path, rules.clone()));
+                }
+                self.dirs.insert(
+                    wd,
+                    Watched {
+                        tree_path,
+                        inherited,
+                        rules,
+                    },
+                );
+            }
+            Ok(())
+        }
+    }
+
+    /// ENOSPC from `inotify_add_watch`: the per-user watch budget is
+    /// spent, by this tree or by every other watcher on the machine.
+    fn limit_reached(watched: usize) -> anyhow::Error {
+        let limit = std::fs::read_to_string("/proc/sys/fs/inotify/max_user_watches")
+            .map(|s| s.trim().to_string())
+            .unwrap_or_else(|_| "unknown".to_string());
+        anyhow!(
+            "inotify watch limit reached after {watched} directories \
+             (fs.inotify.max_user_watches = {limit}); raise it with \
+             `sysctl fs.inotify.max_user_watches=<n>`, or run `converge watch --poll`"
+        )
+    }
+}
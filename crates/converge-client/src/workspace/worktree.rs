@@ -0,0 +1,244 @@
+use serde::Serialize;
+use time::format_description::well_known::Rfc3339;
+
+use super::*;
+
+use crate::model::WorktreeRecord;
+
+/// A linked worktree, and what is wrong with its link if anything is.
+#[derive(Clone, Debug, Serialize)]
+pub struct WorktreeStatus {
+    #[serde(flatten)]
+    pub record: WorktreeRecord,
+    /// Why the primary cannot reach this worktree's roots: its directory
+    /// is gone, or no longer links back.
+    pub problem: Option<String>,
+}
+
+impl Workspace {
+    /// Check out `snap`, or this workspace's head, into a new linked
+    /// worktree at `dir` (doc 15 §5f). It shares this workspace's
+    /// objects, continues its lineage, and keeps its own head, snaps,
+    /// state and config from then on.
+    pub fn add_worktree(&self, dir: &Path, snap: Option<&str>) -> Result<WorktreeRecord> {
+        let snap = match snap {
+            Some(id) => Some(self.store.get_snap(id)?.id),
+            None => self.store.get_head()?,
+        };
+        let primary = self.store.primary_store()?;
+        let created = !dir.exists();
+        if !created
+            && fs::read_dir(dir)
+                .with_context(|| format!("read {}", dir.display()))?
+                .next()
+                .is_some()
+        {
+            anyhow::bail!("{} is not empty", dir.display());
+        }
+        fs::create_dir_all(dir).with_context(|| format!("create {}", dir.display()))?;
+        let dir = dir
+            .canonicalize()
+            .with_context(|| format!("canonicalize {}", dir.display()))?;
+        // Its files would be captured as the enclosing workspace's.
+        if let Ok(enclosing) = Workspace::find_root(&dir) {
+            if created {
+                let _ = fs::remove_dir(&dir);
+            }
+            anyhow::bail!(
+                "{} is inside the workspace at {}; a worktree goes beside it",
+                dir.display(),
+                enclosing.display()
+            );
+        }
+        let record = new_record(&primary, &dir)?;
+        // Registered first: from here on, gc anywhere marks from this
+        // worktree's roots, before it can hold anything only it reaches.
+        primary.put_worktree(&record)?;
+        if let Err(err) = self.link_worktree(&dir, snap.as_deref()) {
+            let _ = fs::remove_dir_all(LocalStore::converge_dir(&dir));
+            primary.delete_worktree(&record.name)?;
+            return Err(err);
+        }
+        Ok(record)
+    }
+
+    fn link_worktree(&self, dir: &Path, snap: Option<&str>) -> Result<()> {
+        let cfg = self.store.read_config()?;
+        let ws = Workspace {
+            root: dir.to_path_buf(),
+            store: LocalStore::init_linked(dir, &self.store, &cfg)?,
+        };
+        // Tokens are keyed by workspace (batch 21.1); the new one
+        // starts logged in as this one is.
+        if let Some(remote) = &cfg.remote
+            && let Some(token) = self.store.get_remote_token(remote)?
+        {
+            ws.store.set_remote_token(remote, &token)?;
+        }
+        let state = self.store.read_state()?;
+        ws.store.mutate_state(|st| {
+            st.last_published = state.last_published;
+            st.last_seen_candidate = state.last_seen_candidate;
+            Ok(())
+        })?;
+        let Some(snap) = snap else {
+            return Ok(());
+        };
+        // The lineage it continues: history, thinning and unsnap read
+        // it as they do here.
+        let mut stack = vec![snap.to_string()];
+        while let Some(id) = stack.pop() {
+            if ws.store.has_snap(&id) || !self.store.has_snap(&id) {
+                continue;
+            }
+            let record = self.store.get_snap(&id)?;
+            stack.extend(record.parents.iter().cloned());
+            ws.store.put_snap(&record)?;
+        }
+        ws.restore_snap(snap, true)
+    }
+
+    /// The worktrees linked to this workspace's primary, checked.
+    pub fn worktrees(&self) -> Result<Vec<WorktreeStatus>> {
+        let primary = self.store.primary_store()?;
+        Ok(primary
+            .list_worktrees()?
+            .into_iter()
+            .map(|record| WorktreeStatus {
+                problem: link_problem(&record, &primary),
+                record,
+            })
+            .collect())
+    }
+
+    /// Forget every worktree whose link is broken. Its roots stop
+    /// counting: the next gc sweeps what only it reached.
+    pub fn prune_worktrees(&self) -> Result<Vec<WorktreeStatus>> {
+        let primary = self.store.primary_store()?;
+        let broken: Vec<WorktreeStatus> = self
+            .worktrees()?
+            .into_iter()
+            .filter(|w| w.problem.is_some())
+            .collect();
+        for worktree in &broken {
+            primary.delete_worktree(&worktree.record.name)?;
+        }
+        Ok(broken)
+    }
+
+    /// Make sure the primary lists this linked worktree where it now
+    /// is — after the worktree moved, or a prune dropped it.
+    pub fn repair_worktree(&self) -> Result<WorktreeRecord> {
+        if self.store.primary_dir().is_none() {
+            anyhow::bail!("{} is not a linked worktree", self.root.display());
+        }
+        let primary = self.store.primary_store()?;
+        let listed = primary.list_worktrees()?;
+        if let Some(record) = listed.iter().find(|r| same_dir(&r.path, &self.root)) {
+            return Ok(record.clone());
+        }
+        let record = new_record(&primary, &self.root)?;
+        primary.put_worktree(&record)?;
+        Ok(record)
+    }
+
+    /// For a linked worktree: why its primary's gc would not mark from
+    /// it, if it would not. `None` for a primary.
+    pub fn worktree_link_problem(&self) -> Result<Option<String>> {
+        let Some(primary_dir) = self.store.primary_dir() else {
+            return Ok(None);
+        };
+        let listed = self.store.primary_store()?.list_worktrees()?;
+        if listed.iter().any(|r| same_dir(&r.path, &self.root)) {
+            return Ok(None);
+        }
+        Ok(Some(format!(
+            "the workspace at {} does not list this worktree; its gc would sweep \
+             what only this worktree holds",
+            primary_dir.parent().unwrap_or(primary_dir).display()
+        )))
+    }
+
+    /// Every workspace sharing this one's objects: the primary and each
+    /// linked worktree. A worktree that cannot be opened fails the
+    /// whole call, since its roots cannot be counted.
+    pub(super) fn sharing_objects(&self) -> Result<Vec<Workspace>> {
+        let primary = self.store.primary_store()?;
+        let mut out = vec![Workspace {
+            root: primary
+                .root_dir()
+                .parent()
+                .unwrap_or(primary.root_dir())
+                .to_path_buf(),
+            store: primary.clone(),
+        }];
+        for worktree in self.worktrees()? {
+            if let Some(problem) = worktree.problem {
+                anyhow::bail!(
+                    "worktree {} at {} {problem}; `converge worktree prune` forgets it",
+                    worktree.record.name,
+                    worktree.record.path
+                );
+            }
+            let root = PathBuf::from(&worktree.record.path);
+            let store = LocalStore::open(&root)
+                .with_context(|| format!("open worktree {}", worktree.record.name))?;
+            out.push(Workspace { root, store });
+        }
+        Ok(out)
+    }
+}
+
+/// A record for a worktree at `root`, named after its directory: the
+/// name is a file name in the primary's `.converge/worktrees`.
+fn new_record(primary: &LocalStore, root: &Path) -> Result<WorktreeRecord> {
+    let base: String = root
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let base = match base.trim_start_matches('.') {
+        "" => "worktree",
+        base => base,
+    };
+    let name = std::iter::once(base.to_string())
+        .chain((2..).map(|n| format!("{base}-{n}")))
+        .find(|name| !primary.has_worktree(name))
+        .expect("a free worktree name");
+    Ok(WorktreeRecord {
+        name,
+        path: root.display().to_string(),
+        created_at: time::OffsetDateTime::now_utc()
+            .format(&Rfc3339)
+            .context("format created_at")?,
+    })
+}
+
+fn link_problem(record: &WorktreeRecord, primary: &LocalStore) -> Option<String> {
+    let root = Path::new(&record.path);
+    if !root.is_dir() {
+        return Some("is gone".to_string());
+    }
+    match LocalStore::link_target(root) {
+        None => Some("is no longer a linked worktree".to_string()),
+        Some(target) if !same_dir(&target, primary.root_dir()) => {
+            Some(format!("links to {} instead", target.display()))
+        }
+        Some(_) => None,
+    }
+}
+
+fn same_dir(a: impl AsRef<Path>, b: impl AsRef<Path>) -> bool {
+    match (a.as_ref().canonicalize(), b.as_ref().canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a.as_ref() == b.as_ref(),
+    }
+}
@@ -27,11 +27,13 @@ fn synthetic_snap(
     let root = ws.store.put_manifest(&manifest)?;
     let snap = SnapRecord {
         version: 2,
-        id: compute_snap_id(&root, &[], None),
+        id: compute_snap_id(&root, &[], None, None, None),
         created_at: created.format(&Rfc3339)?,
         root_manifest: root,
         parents: Vec::new(),
         derived_from_candidate: None,
+        author: None,
+        replayed_from: None,
         message: None,
         trigger: trigger.into(),
         stats: SnapStats::default(),
@@ -92,3 +92,36 @@ fn identical_content_dedupes_across_files() -> anyhow::Result<()> {
     );
     Ok(())
 }
+
+/// The scans stream chunked files through the chunker instead of
+/// reading them whole (doc 16 §2). The recipe must be the one
+/// `chunk_data` builds over the same bytes, or a streamed snap would
+/// dedupe against nothing it captured before.
+#[test]
+fn streamed_capture_matches_whole_file_chunking() -> anyhow::Result<()> {
+    use converge_client::model::{ChunkParams, chunk_data, encoding::encode_recipe};
+
+    let dir = tempfile::tempdir()?;
+    let root = dir.path();
+    let ws = Workspace::init(root, false)?;
+    let big = pseudo_random_bytes(20 * 1024 * 1024 + 5, 11);
+    fs::write(root.join("take.wav"), &big)?;
+
+    let (expected, _) = chunk_data(&big, ChunkParams::default());
+    let expected = blake3::hash(&encode_recipe(&expected)).to_hex().to_string();
+
+    // `status` chunks without storing; a snap stores as it goes.
+    let (scanned, manifests, _) = ws.current_manifest_tree()?;
+    let snap = ws.create_snap(None)?;
+    assert_eq!(snap.root_manifest, scanned);
+    let ManifestEntryKind::FileChunks { recipe, size, .. } = &manifests[&scanned].entries[0].kind
+    else {
+        panic!("a chunked file");
+    };
+    assert_eq!(recipe.as_str(), expected);
+    assert_eq!(*size, big.len() as u64);
+    for chunk in ws.store.get_recipe(recipe)?.chunks {
+        assert!(ws.store.has_blob(&chunk.blob));
+    }
+    Ok(())
+}
@@ -0,0 +1,202 @@
+//! Doc 16 §2: per-path chunking rules. One size does not fit a workspace
+//! that mixes gigabyte texture packs, audio takes that share most of
+//! their bytes, and JSON the engine rewrites wholesale.
+
+use std::fs;
+
+use anyhow::Result;
+
+use converge_client::model::{
+    ChunkParams, ChunkingConfig, ChunkingRule, Manifest, ManifestEntryKind, WorkflowProfile,
+};
+use converge_client::workspace::Workspace;
+
+const MIB: usize = 1024 * 1024;
+
+/// Deterministic, incompressible-looking bytes, so CDC has boundaries
+/// to find.
+fn noise(len: usize, mut seed: u64) -> Vec<u8> {
+    let mut out = Vec::with_capacity(len);
+    while out.len() < len {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        out.extend_from_slice(&seed.to_le_bytes());
+    }
+    out.truncate(len);
+    out
+}
+
+fn configure(
+    ws: &Workspace,
+    rules: Vec<ChunkingRule>,
+    profile: Option<WorkflowProfile>,
+) -> Result<()> {
+    let mut cfg = ws.store.read_config()?;
+    cfg.chunking = Some(ChunkingConfig {
+        chunk_size: 1024 * 1024,
+        threshold: 8 * 1024 * 1024,
+        rules,
+    });
+    if let Some(profile) = profile {
+        cfg.workflow_profile = profile;
+    }
+    ws.store.write_config(&cfg)
+}
+
+/// The entry at `path` in the snap's tree.
+fn entry(ws: &Workspace, manifest: &Manifest, path: &str) -> Result<ManifestEntryKind> {
+    let (first, rest) = path.split_once('/').unwrap_or((path, ""));
+    let found = manifest
+        .entries
+        .iter()
+        .find(|e| e.name == first)
+        .unwrap_or_else(|| panic!("{path} is not in the tree"));
+    match (&found.kind, rest) {
+        (ManifestEntryKind::Dir { manifest }, rest) if !rest.is_empty() => {
+            entry(ws, &ws.store.get_dir(manifest)?, rest)
+        }
+        (kind, _) => Ok(kind.clone()),
+    }
+}
+
+fn chunk_params(ws: &Workspace, kind: &ManifestEntryKind) -> Result<Option<ChunkParams>> {
+    match kind {
+        ManifestEntryKind::FileChunks { recipe, .. } => Ok(ws.store.get_recipe(recipe)?.params),
+        _ => Ok(None),
+    }
+}
+
+#[test]
+fn rules_choose_per_path_and_the_first_match_wins() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let root = tmp.path();
+    let ws = Workspace::init(root, false)?;
+    let small = ChunkParams {
+        min_size: 16 * 1024,
+        avg_size: 64 * 1024,
+        max_size: 256 * 1024,
+    };
+    configure(
+        &ws,
+        vec![
+            ChunkingRule::never("*.json"),
+            ChunkingRule::chunk("stems/**", small, Some(MIB as u64)),
+            ChunkingRule::chunk("*.wav", ChunkParams::default(), None),
+        ],
+        None,
+    )?;
+
+    fs::create_dir_all(root.join("stems/take1"))?;
+    fs::create_dir_all(root.join("data"))?;
+    fs::write(root.join("data/level.json"), noise(9 * MIB, 1))?;
+    fs::write(root.join("stems/take1/vox.wav"), noise(2 * MIB, 2))?;
+    fs::write(root.join("vox.wav"), noise(2 * MIB, 3))?;
+    fs::write(root.join("big.bin"), noise(9 * MIB, 4))?;
+
+    let snap = ws.create_snap(None)?;
+    let tree = ws.store.get_dir(&snap.root_manifest)?;
+
+    // A bare pattern matches the file name at any depth; `never` holds
+    // past the size threshold.
+    assert!(matches!(
+        entry(&ws, &tree, "data/level.json")?,
+        ManifestEntryKind::File { .. }
+    ));
+    // The anchored rule wins over the later `*.wav`, and its own
+    // threshold lets a 2 MiB file chunk.
+    let stem = entry(&ws, &tree, "stems/take1/vox.wav")?;
+    assert_eq!(chunk_params(&ws, &stem)?, Some(small));
+    // Outside `stems/` the same name falls to the next rule, whose
+    // threshold is the workspace's: 2 MiB stays whole.
+    assert!(matches!(
+        entry(&ws, &tree, "vox.wav")?,
+        ManifestEntryKind::File { .. }
+    ));
+    // Unmatched: the workspace-wide parameters.
+    let big = entry(&ws, &tree, "big.bin")?;
+    assert_eq!(chunk_params(&ws, &big)?, Some(ChunkParams::default()));
+
+    // The in-memory scan `status` uses agrees with what capture stored.
+    let (current, _, _) = ws.current_manifest_tree()?;
+    assert_eq!(current, snap.root_manifest);
+    Ok(())
+}
+
+/// A game-assets workspace gets its profile's rules without writing any,
+/// and a rule of its own still comes first.
+#[test]
+fn profile_defaults_apply_after_the_workspace_rules() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let root = tmp.path();
+    let ws = Workspace::init(root, false)?;
+    configure(
+        &ws,
+        vec![ChunkingRule::chunk(
+            "keep/*.json",
+            ChunkParams::default(),
+            None,
+        )],
+        Some(WorkflowProfile::GameAssets),
+    )?;
+
+    fs::create_dir_all(root.join("keep"))?;
+    fs::write(root.join("scene.json"), noise(9 * MIB, 5))?;
+    fs::write(root.join("keep/atlas.json"), noise(9 * MIB, 6))?;
+    fs::write(root.join("textures.pak"), noise(9 * MIB, 7))?;
+
+    let snap = ws.create_snap(None)?;
+    let tree = ws.store.get_dir(&snap.root_manifest)?;
+    assert!(matches!(
+        entry(&ws, &tree, "scene.json")?,
+        ManifestEntryKind::File { .. }
+    ));
+    let kept = entry(&ws, &tree, "keep/atlas.json")?;
+    assert_eq!(chunk_params(&ws, &kept)?, Some(ChunkParams::default()));
+    let pak = entry(&ws, &tree, "textures.pak")?;
+    assert_eq!(
+        chunk_params(&ws, &pak)?.map(|p| p.avg_size),
+        Some(4 * MIB as u32),
+        "packed assets take the large profile chunks"
+    );
+    Ok(())
+}
+
+/// FastCDC asserts on its bounds; a bad rule has to be an error that
+/// names the rule, before anything is captured.
+#[test]
+fn a_bad_rule_is_refused_by_name() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let root = tmp.path();
+    let ws = Workspace::init(root, false)?;
+    fs::write(root.join("a.bin"), b"a")?;
+
+    let huge = ChunkParams {
+        min_size: 4 * MIB as u32,
+        avg_size: 16 * MIB as u32,
+        max_size: 64 * MIB as u32,
+    };
+    configure(&ws, vec![ChunkingRule::chunk("*.pak", huge, None)], None)?;
+    let err = ws.create_snap(None).unwrap_err();
+    assert!(format!("{err:#}").contains("\"*.pak\""), "{err:#}");
+    assert!(format!("{err:#}").contains("out of range"), "{err:#}");
+
+    configure(&ws, vec![ChunkingRule::never("assets/[.json")], None)?;
+    let err = ws.create_snap(None).unwrap_err();
+    assert!(format!("{err:#}").contains("assets/[.json"), "{err:#}");
+    assert!(ws.store.get_head()?.is_none(), "nothing was captured");
+    Ok(())
+}
+
+/// A workspace config written before rules existed reads as it did.
+#[test]
+fn a_config_without_rules_still_parses() -> Result<()> {
+    let cfg: ChunkingConfig =
+        serde_json::from_str(r#"{"chunk_size": 2097152, "threshold": 4194304}"#)?;
+    assert!(cfg.rules.is_empty());
+    let cfg: ChunkingConfig =
+        serde_json::from_str(r#"{"rules": [{"pattern": "*.json", "action": "never"}]}"#)?;
+    assert_eq!(cfg.rules, vec![ChunkingRule::never("*.json")]);
+    assert_eq!(cfg.threshold, 8 * 1024 * 1024);
+    Ok(())
+}
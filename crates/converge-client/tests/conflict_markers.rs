@@ -0,0 +1,273 @@
+//! Doc 17 §2d: text superpositions checked out as conflict markers, and a
+//! de-markered file captured as the resolution.
+
+use std::collections::BTreeMap;
+use std::fs;
+
+use anyhow::Result;
+
+use converge_client::model::{
+    Manifest, ManifestEntry, ManifestEntryKind, ObjectId, ResolutionDecision, ResolvedContentKind,
+    SuperpositionVariant, SuperpositionVariantKind,
+};
+use converge_client::resolve::reuse_recorded_decisions;
+use converge_client::store::LocalStore;
+use converge_client::workspace::{SuperpositionMode, Workspace};
+
+fn file(store: &LocalStore, bytes: &[u8]) -> Result<ManifestEntryKind> {
+    Ok(ManifestEntryKind::File {
+        blob: store.put_blob(bytes)?,
+        mode: 0o100644,
+        size: bytes.len() as u64,
+    })
+}
+
+fn variant(store: &LocalStore, source: &str, bytes: &[u8]) -> Result<SuperpositionVariant> {
+    let ManifestEntryKind::File { blob, mode, size } = file(store, bytes)? else {
+        unreachable!()
+    };
+    Ok(SuperpositionVariant {
+        source: source.to_string(),
+        kind: SuperpositionVariantKind::File { blob, mode, size },
+    })
+}
+
+/// `src/<name>` holding `kind`, beside an untouched `README`.
+fn tree(store: &LocalStore, name: &str, kind: ManifestEntryKind) -> Result<ObjectId> {
+    let src = store.put_manifest(&Manifest {
+        version: 1,
+        entries: vec![ManifestEntry {
+            name: name.to_string(),
+            kind,
+        }],
+    })?;
+    store.put_manifest(&Manifest {
+        version: 1,
+        entries: vec![
+            ManifestEntry {
+                name: "README".to_string(),
+                kind: file(store, b"readme\n")?,
+            },
+            ManifestEntry {
+                name: "src".to_string(),
+                kind: ManifestEntryKind::Dir { manifest: src },
+            },
+        ],
+    })
+}
+
+#[test]
+fn marker_file_round_trips_into_a_resolution() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let ws = Workspace::init(tmp.path(), false)?;
+    let base = tree(&ws.store, "x.txt", file(&ws.store, b"one\ntwo\nthree\n")?)?;
+    let a = variant(&ws.store, "lane-a", b"one\nTWO-A\nthree\n")?;
+    let b = variant(&ws.store, "lane-b", b"one\nTWO-B\nthree\n")?;
+    let superposed = tree(
+        &ws.store,
+        "x.txt",
+        ManifestEntryKind::Superposition {
+            variants: vec![a.clone(), b.clone()],
+        },
+    )?;
+
+    // The default still refuses, and costs nothing.
+    assert!(
+        ws.adopt_tree(&superposed, None, Some("cand-1"), true)
+            .is_err()
+    );
+    assert!(!tmp.path().join("README").exists());
+
+    let snap = ws.adopt_tree_with(
+        &superposed,
+        None,
+        Some("cand-1"),
+        true,
+        &SuperpositionMode::Markers { base: Some(base) },
+    )?;
+    let marked = fs::read_to_string(tmp.path().join("src/x.txt"))?;
+    assert_eq!(
+        marked,
+        "one\n<<<<<<< lane-a\nTWO-A\n||||||| base\ntwo\n=======\nTWO-B\n>>>>>>> lane-b\nthree\n"
+    );
+    assert_eq!(ws.marked_paths()?, vec!["src/x.txt".to_string()]);
+
+    // Checked out and half-edited, the tree is still the superposed one:
+    // status is clean and a snap captures nothing new.
+    assert_eq!(ws.current_manifest_tree()?.0, snap.root_manifest);
+    fs::write(
+        tmp.path().join("src/x.txt"),
+        marked.replace("TWO-A", "TWO-A!"),
+    )?;
+    assert_eq!(ws.current_manifest_tree()?.0, snap.root_manifest);
+    assert_eq!(ws.create_snap(None)?.id, snap.id);
+
+    // De-markered, the file is the resolution.
+    fs::write(tmp.path().join("src/x.txt"), b"one\nTWO-AB\nthree\n")?;
+    let resolved = ws.create_snap(None)?;
+    assert_ne!(resolved.root_manifest, snap.root_manifest);
+    assert!(ws.marked_paths()?.is_empty());
+
+    let record = ws.store.get_resolution("cand-1")?;
+    assert_eq!(record.root_manifest, superposed);
+    let ResolutionDecision::Content(decision) = &record.decisions["src/x.txt"] else {
+        panic!("expected a content decision");
+    };
+    let ResolvedContentKind::File { blob, .. } = &decision.content else {
+        panic!("expected a whole file");
+    };
+    assert_eq!(ws.store.get_blob(blob)?, b"one\nTWO-AB\nthree\n");
+    assert_eq!(decision.supersedes, vec![a.key(), b.key()]);
+
+    // And it is offered again wherever the same conflict turns up.
+    let mut decisions = BTreeMap::new();
+    let reused = reuse_recorded_decisions(&ws.store, &superposed, &mut decisions)?;
+    assert_eq!(reused, vec!["src/x.txt".to_string()]);
+    assert_eq!(decisions["src/x.txt"], record.decisions["src/x.txt"]);
+    Ok(())
+}
+
+#[test]
+fn markers_without_a_base_or_for_binary_variants() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let ws = Workspace::init(tmp.path(), false)?;
+    let text = tree(
+        &ws.store,
+        "x.txt",
+        ManifestEntryKind::Superposition {
+            variants: vec![
+                variant(&ws.store, "lane-a", b"a\n")?,
+                variant(&ws.store, "lane-b", b"b\n")?,
+                variant(&ws.store, "lane-c", b"c\n")?,
+            ],
+        },
+    )?;
+    let mode = SuperpositionMode::Markers { base: None };
+    ws.adopt_tree_with(&text, None, None, true, &mode)?;
+    assert_eq!(
+        fs::read_to_string(tmp.path().join("src/x.txt"))?,
+        "<<<<<<< lane-a\na\n=======\nb\n=======\nc\n>>>>>>> lane-c\n"
+    );
+
+    // A file deleted instead of resolved is just forgotten.
+    fs::remove_file(tmp.path().join("src/x.txt"))?;
+    ws.create_snap(None)?;
+    assert!(ws.marked_paths()?.is_empty());
+
+    let binary = tree(
+        &ws.store,
+        "x.bin",
+        ManifestEntryKind::Superposition {
+            variants: vec![
+                variant(&ws.store, "lane-a", b"\0a")?,
+                variant(&ws.store, "lane-b", b"\0b")?,
+            ],
+        },
+    )?;
+    let err = ws
+        .adopt_tree_with(&binary, None, None, true, &mode)
+        .unwrap_err();
+    assert!(format!("{err:#}").contains("not a text file"), "{err:#}");
+    assert!(tmp.path().join("README").exists());
+    Ok(())
+}
+
+#[test]
+fn a_one_variant_superposition_refuses_markers() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let ws = Workspace::init(tmp.path(), false)?;
+    let lone = tree(
+        &ws.store,
+        "x.txt",
+        ManifestEntryKind::Superposition {
+            variants: vec![variant(&ws.store, "lane-a", b"a\n")?],
+        },
+    )?;
+    let err = ws
+        .adopt_tree_with(
+            &lone,
+            None,
+            None,
+            true,
+            &SuperpositionMode::Markers { base: None },
+        )
+        .unwrap_err();
+    assert!(format!("{err:#}").contains("src/x.txt"), "{err:#}");
+    assert!(!tmp.path().join("README").exists());
+    Ok(())
+}
+
+/// Markers for `a` against `b` over `base`, then the file resolved to
+/// `a`'s side: the resolution is `a` byte for byte.
+fn resolve_to_first_side(base: &[u8], a: &[u8], b: &[u8]) -> Result<String> {
+    let tmp = tempfile::tempdir()?;
+    let ws = Workspace::init(tmp.path(), false)?;
+    let base = tree(&ws.store, "x.txt", file(&ws.store, base)?)?;
+    let superposed = tree(
+        &ws.store,
+        "x.txt",
+        ManifestEntryKind::Superposition {
+            variants: vec![
+                variant(&ws.store, "lane-a", a)?,
+                variant(&ws.store, "lane-b", b)?,
+            ],
+        },
+    )?;
+    ws.adopt_tree_with(
+        &superposed,
+        None,
+        Some("cand-1"),
+        true,
+        &SuperpositionMode::Markers { base: Some(base) },
+    )?;
+    let marked = fs::read_to_string(tmp.path().join("src/x.txt"))?;
+
+    // Keep the first side: drop the markers and everything after ours.
+    let mut kept = String::new();
+    let mut side = None;
+    for line in marked.split_inclusive('\n') {
+        match line.trim_end() {
+            "<<<<<<< lane-a" => side = Some(true),
+            "||||||| base" | "=======" => side = Some(false),
+            ">>>>>>> lane-b" => side = None,
+            _ if side != Some(false) => kept.push_str(line),
+            _ => {}
+        }
+    }
+    fs::write(tmp.path().join("src/x.txt"), &kept)?;
+    ws.create_snap(None)?;
+    let ResolutionDecision::Content(decision) =
+        &ws.store.get_resolution("cand-1")?.decisions["src/x.txt"]
+    else {
+        panic!("expected a content decision");
+    };
+    let ResolvedContentKind::File { blob, .. } = &decision.content else {
+        panic!("expected a whole file");
+    };
+    assert_eq!(ws.store.get_blob(blob)?, a);
+    Ok(marked)
+}
+
+#[test]
+fn crlf_lines_keep_their_terminators() -> Result<()> {
+    let marked = resolve_to_first_side(
+        b"one\r\ntwo\r\nthree\r\n",
+        b"one\r\nTWO-A\r\nthree\r\n",
+        b"one\r\nTWO-B\r\nthree\r\n",
+    )?;
+    assert!(marked.starts_with("one\r\n"), "{marked:?}");
+    assert!(marked.contains("TWO-A\r\n"), "{marked:?}");
+    assert!(marked.ends_with("three\r\n"), "{marked:?}");
+    Ok(())
+}
+
+#[test]
+fn a_missing_final_newline_stays_missing() -> Result<()> {
+    let marked = resolve_to_first_side(
+        b"one\ntwo\nthree",
+        b"one\nTWO-A\nthree",
+        b"one\nTWO-B\nthree",
+    )?;
+    assert!(marked.ends_with("\nthree"), "{marked:?}");
+    Ok(())
+}
@@ -139,11 +139,13 @@ fn superposed_tree_refuses_to_export() -> Result<()> {
     let root_manifest = ws.store.put_manifest(&manifest)?;
     let snap = SnapRecord {
         version: 2,
-        id: compute_snap_id(&root_manifest, &[], None),
+        id: compute_snap_id(&root_manifest, &[], None, None, None),
         created_at: "2026-07-25T00:00:00Z".into(),
         root_manifest,
         parents: Vec::new(),
         derived_from_candidate: None,
+        author: None,
+        replayed_from: None,
         message: None,
         trigger: "explicit".into(),
         stats: SnapStats::default(),
@@ -195,3 +197,58 @@ fn lost_map_reexport_produces_no_duplicate_commits() -> Result<()> {
     );
     Ok(())
 }
+
+/// Doc 18 §2: each commit is authored by its snap's author, and the one
+/// running the export is only the committer. A snap from before login
+/// has no author and falls back to the exporter.
+#[test]
+fn each_commit_is_authored_by_its_snap() -> Result<()> {
+    if !git_available() {
+        eprintln!("git not available; skipping");
+        return Ok(());
+    }
+    let tmp = tempfile::tempdir()?;
+    let root = tmp.path();
+    git_out(root, &["init", "--quiet"])?;
+    let ws = Workspace::init(root, false)?;
+    let set_author = |subject: &str, name: &str| -> Result<()> {
+        let mut cfg = ws.store.read_config()?;
+        cfg.author = Some(converge_client::model::AuthorConfig {
+            subject: subject.into(),
+            display_name: name.into(),
+        });
+        ws.store.write_config(&cfg)
+    };
+
+    std::fs::write(root.join("a.txt"), "one")?;
+    ws.create_snap(Some("anonymous".into()))?;
+    set_author("ada@example.com", "Ada")?;
+    std::fs::write(root.join("a.txt"), "two")?;
+    ws.create_snap(Some("by ada".into()))?;
+    set_author("grace", "Grace Hopper")?;
+    std::fs::write(root.join("a.txt"), "three")?;
+    let head = ws.create_snap(Some("by grace".into()))?;
+    assert_eq!(
+        head.author.as_ref().map(|a| a.subject.as_str()),
+        Some("grace")
+    );
+
+    export_lineage(&ws.store, root, "converge/lane/local", &head.id)?;
+    let log = git_out(
+        root,
+        &["log", "--format=%s|%an|%ae|%cn", "converge/lane/local"],
+    )?;
+    let rows: Vec<&str> = log.lines().collect();
+    assert_eq!(rows.len(), 3);
+    assert!(rows[0].starts_with("by grace|Grace Hopper|grace|"), "{log}");
+    assert!(rows[1].starts_with("by ada|Ada|ada@example.com|"), "{log}");
+    let committers: std::collections::BTreeSet<&str> =
+        rows.iter().filter_map(|r| r.rsplit('|').next()).collect();
+    assert_eq!(committers.len(), 1, "one exporter commits them all: {log}");
+    let committer = *committers.iter().next().expect("one committer");
+    assert!(
+        rows[2].starts_with(&format!("anonymous|{committer}|")),
+        "an unauthored snap falls back to the exporter: {log}"
+    );
+    Ok(())
+}
@@ -122,17 +122,24 @@ fn gitignore_translates_and_capture_honors_it() -> Result<()> {
         root.join(".gitignore"),
         "# comment\nbuild/\n!keep.txt\ntarget\n",
     )?;
+    std::fs::create_dir(root.join("sub"))?;
+    std::fs::write(root.join("sub/.gitignore"), "*.gen\n")?;
     git(root, &["add", "."])?;
     git(root, &["commit", "--quiet", "-m", "ignore file"])?;
     std::fs::create_dir(root.join("build"))?;
     std::fs::write(root.join("build/artifact.bin"), "junk")?;
+    std::fs::write(root.join("sub/code.gen"), "junk")?;
 
     let ws = Workspace::init(root, false)?;
     let report = import(&ws, ImportDepth::Seed)?;
     assert!(report.translated_ignores);
     let generated = std::fs::read_to_string(root.join(".convergeignore"))?;
     assert!(generated.contains("build/"));
-    assert!(!generated.contains("!keep.txt"), "negations dropped");
+    assert!(generated.contains("!keep.txt"), "negations kept");
+    assert!(
+        std::fs::read_to_string(root.join("sub/.convergeignore"))?.contains("*.gen"),
+        "nested .gitignore translated beside itself"
+    );
 
     // Capture after translation excludes the ignored dir.
     std::fs::write(root.join("new.txt"), "x")?;
@@ -142,6 +149,22 @@ fn gitignore_translates_and_capture_honors_it() -> Result<()> {
         !manifest.entries.iter().any(|e| e.name == "build"),
         "root ignore honored"
     );
+    let sub = manifest
+        .entries
+        .iter()
+        .find(|e| e.name == "sub")
+        .expect("sub captured");
+    let converge_client::model::ManifestEntryKind::Dir { manifest: sub } = &sub.kind else {
+        panic!("sub is not a directory");
+    };
+    assert!(
+        !ws.store
+            .get_manifest(sub)?
+            .entries
+            .iter()
+            .any(|e| e.name == "code.gen"),
+        "nested ignore honored"
+    );
     assert!(manifest.entries.iter().any(|e| e.name == "new.txt"));
     Ok(())
 }
@@ -241,13 +241,19 @@ fn concurrent_state_mutations_all_land() -> Result<()> {
 #[test]
 fn snap_id_parents_are_length_prefixed() -> Result<()> {
     let root = ObjectId("aa".repeat(32));
-    let split = compute_snap_id(&root, &["ab".to_string(), "cd".to_string()], None);
-    let joined = compute_snap_id(&root, &["ab,cd".to_string()], None);
+    let split = compute_snap_id(
+        &root,
+        &["ab".to_string(), "cd".to_string()],
+        None,
+        None,
+        None,
+    );
+    let joined = compute_snap_id(&root, &["ab,cd".to_string()], None, None, None);
     assert_ne!(split, joined, "parent boundaries must be canonical");
 
     // Empty-parent shapes stay distinct too.
-    let none = compute_snap_id(&root, &[], None);
-    let one_empty = compute_snap_id(&root, &[String::new()], None);
+    let none = compute_snap_id(&root, &[], None, None, None);
+    let one_empty = compute_snap_id(&root, &[String::new()], None, None, None);
     assert_ne!(none, one_empty);
     Ok(())
 }
@@ -0,0 +1,181 @@
+//! Doc 16 §1h: local gc sweeps what nothing in the workspace reaches and
+//! packs what is left, and every object still reads the same.
+
+use std::fs;
+use std::time::Duration;
+
+use anyhow::Result;
+
+use converge_client::model::{
+    ResolutionDecision, ResolvedContent, ResolvedContentKind, VariantKey, VariantKeyKind,
+};
+use converge_client::workspace::Workspace;
+
+fn loose_files(root: &std::path::Path) -> Result<usize> {
+    let mut count = 0;
+    let mut stack = vec![root.join(".converge/objects/blobs")];
+    while let Some(dir) = stack.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                stack.push(path);
+            } else {
+                count += 1;
+            }
+        }
+    }
+    Ok(count)
+}
+
+#[test]
+fn gc_sweeps_what_no_snap_reaches_and_packs_the_rest() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let root = tmp.path();
+    let ws = Workspace::init(root, false)?;
+    fs::create_dir(root.join("src"))?;
+    fs::write(root.join("src/a.txt"), "a")?;
+    fs::write(root.join("b.txt"), "b")?;
+    let kept = ws.create_snap(None)?;
+    fs::write(root.join("b.txt"), "b, undone")?;
+    ws.create_snap(None)?;
+    ws.unsnap(false, false)?;
+    let undone = ws.store.put_blob(b"b, undone")?;
+
+    // A dry run counts and touches nothing; the grace window keeps
+    // what was just written.
+    let young = ws.collect_garbage(true, Duration::from_secs(3600))?;
+    assert_eq!(young.swept_objects, 0);
+    let dry = ws.collect_garbage(true, Duration::ZERO)?;
+    assert!(dry.swept_objects >= 2, "{dry:?}");
+    assert!(ws.store.has_blob(&undone));
+
+    let run = ws.collect_garbage(false, Duration::ZERO)?;
+    assert_eq!(run.swept_objects, dry.swept_objects);
+    assert_eq!(run.packed_objects, run.reachable_objects);
+    assert_eq!((run.packs, run.loose_objects), (1, 0));
+    assert!(!ws.store.has_blob(&undone));
+    assert_eq!(loose_files(root)?, 0);
+
+    // Everything kept reads back out of the pack.
+    ws.restore_snap(&kept.id, true)?;
+    assert_eq!(fs::read_to_string(root.join("b.txt"))?, "b");
+    assert_eq!(fs::read_to_string(root.join("src/a.txt"))?, "a");
+
+    // New captures pack beside the first; dropping one rewrites.
+    fs::write(root.join("b.txt"), "b2")?;
+    let second = ws.create_snap(None)?;
+    let run = ws.collect_garbage(false, Duration::ZERO)?;
+    assert_eq!((run.swept_objects, run.packs), (0, 2));
+    ws.unsnap(false, false)?;
+    let run = ws.collect_garbage(false, Duration::ZERO)?;
+    assert!(run.swept_objects >= 2, "{run:?}");
+    assert_eq!(run.packs, 1);
+    assert!(ws.store.get_snap(&second.id).is_err());
+    ws.restore_snap(&kept.id, true)?;
+    assert_eq!(fs::read_to_string(root.join("b.txt"))?, "b");
+    Ok(())
+}
+
+#[test]
+fn gc_keeps_content_a_recorded_decision_names() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let ws = Workspace::init(tmp.path(), false)?;
+    let hand = ws.store.put_blob(b"written by hand")?;
+    let stray = ws.store.put_blob(b"named by nothing")?;
+    let keys = vec![VariantKey {
+        source: "lane-a".into(),
+        kind: VariantKeyKind::Tombstone,
+    }];
+    ws.store.put_recorded_decision(
+        &keys,
+        &ResolutionDecision::Content(ResolvedContent {
+            content: ResolvedContentKind::File {
+                blob: hand.clone(),
+                mode: 0o100644,
+                size: 15,
+            },
+            supersedes: keys.clone(),
+        }),
+    )?;
+
+    ws.collect_garbage(false, Duration::ZERO)?;
+    assert_eq!(ws.store.get_blob(&hand)?, b"written by hand");
+    assert!(!ws.store.has_blob(&stray));
+    Ok(())
+}
+
+#[test]
+fn gc_reports_encoded_objects_at_their_content_size() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let root = tmp.path();
+    let ws = Workspace::init(root, false)?;
+    let mut cfg = ws.store.read_config()?;
+    cfg.compress_objects = true;
+    ws.store.write_config(&cfg)?;
+    let ws = Workspace::discover(root)?;
+    let text = "fn main() { println!(\"hello\"); }\n".repeat(200);
+
+    // Loose: an unreached blob, stored encoded.
+    ws.store.put_blob(text.as_bytes())?;
+    let loose = ws.collect_garbage(true, Duration::ZERO)?;
+    assert_eq!(loose.swept_objects, 1);
+    assert_eq!(loose.swept_logical_bytes, text.len() as u64);
+    assert!(
+        loose.swept_bytes * 4 < loose.swept_logical_bytes,
+        "{loose:?}"
+    );
+
+    // Packed: dropped from a pack once its snap is undone.
+    fs::write(root.join("a.txt"), "a")?;
+    ws.create_snap(None)?;
+    fs::write(root.join("a.txt"), &text)?;
+    ws.create_snap(None)?;
+    ws.collect_garbage(false, Duration::ZERO)?;
+    ws.unsnap(false, false)?;
+    let packed = ws.collect_garbage(false, Duration::ZERO)?;
+    assert!(
+        packed.swept_logical_bytes >= text.len() as u64,
+        "{packed:?}"
+    );
+    assert!(
+        packed.swept_bytes * 4 < packed.swept_logical_bytes,
+        "{packed:?}"
+    );
+    Ok(())
+}
+
+#[test]
+fn gc_keeps_a_packed_object_a_capture_just_reused() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let root = tmp.path();
+    let ws = Workspace::init(root, false)?;
+    fs::write(root.join("a.txt"), "a")?;
+    ws.create_snap(None)?;
+    fs::write(root.join("a.txt"), "a, undone")?;
+    ws.create_snap(None)?;
+    ws.collect_garbage(false, Duration::ZERO)?;
+    ws.unsnap(false, false)?;
+
+    // The pack is old and the blob in it unreached: gc would drop it.
+    let packs = root.join(".converge/objects/packs");
+    let old = std::time::SystemTime::now() - Duration::from_secs(7200);
+    for entry in fs::read_dir(&packs)? {
+        fs::File::options()
+            .write(true)
+            .open(entry?.path())?
+            .set_modified(old)?;
+    }
+    let grace = Duration::from_secs(3600);
+    let dry = ws.collect_garbage(true, grace)?;
+    assert!(dry.swept_objects >= 1, "{dry:?}");
+
+    // A capture of the same content finds it packed and writes nothing;
+    // gc runs before its snap record lands.
+    let ws = Workspace::discover(root)?;
+    let undone = ws.store.put_blob(b"a, undone")?;
+    assert_eq!(loose_files(root)?, 0);
+    let run = ws.collect_garbage(false, grace)?;
+    assert_eq!(run.swept_objects, 0, "{run:?}");
+    assert_eq!(ws.store.get_blob(&undone)?, b"a, undone");
+    Ok(())
+}
@@ -0,0 +1,121 @@
+//! Paged directory manifests (doc 16 §1b) from the client side: a
+//! directory past the threshold is stored as a page index, the in-memory
+//! scan agrees with the stored one to the id, and every content walker —
+//! diff, stats, restore — sees the directory, not its pages.
+
+use std::fs;
+
+use anyhow::{Context, Result};
+
+use converge_client::diff::{diff_trees, tree_from_memory, tree_from_store};
+use converge_client::model::ManifestEntryKind;
+use converge_client::model::paging::{self, PAGE_THRESHOLD};
+use converge_client::workspace::Workspace;
+
+const FILES: usize = PAGE_THRESHOLD + 500;
+
+fn wide_dir(root: &std::path::Path) -> Result<()> {
+    fs::create_dir_all(root.join("wide")).context("create wide dir")?;
+    for i in 0..FILES {
+        fs::write(root.join(format!("wide/f{i:05}.txt")), format!("{i}\n"))
+            .context("write file")?;
+    }
+    fs::write(root.join("top.txt"), b"top\n").context("write top.txt")?;
+    Ok(())
+}
+
+fn wide_manifest(
+    ws: &Workspace,
+    root: &converge_client::model::ObjectId,
+) -> Result<converge_client::model::Manifest> {
+    let top = ws.store.get_manifest(root)?;
+    let wide = top
+        .entries
+        .iter()
+        .find(|e| e.name == "wide")
+        .context("wide present")?;
+    match &wide.kind {
+        ManifestEntryKind::Dir { manifest } => ws.store.get_manifest(manifest),
+        other => anyhow::bail!("expected dir, got {other:?}"),
+    }
+}
+
+#[test]
+fn a_wide_directory_is_stored_as_pages_and_reads_back_whole() -> Result<()> {
+    let tmp = tempfile::tempdir().context("create tempdir")?;
+    let root = tmp.path();
+    wide_dir(root)?;
+
+    let ws = Workspace::init(root, false)?;
+    let snap = ws.create_snap(Some("wide".to_string()))?;
+
+    let index = wide_manifest(&ws, &snap.root_manifest)?;
+    assert!(paging::is_index(&index), "over the threshold pages");
+    assert!(index.entries.len() > 1);
+    assert_eq!(snap.stats.files as usize, FILES + 1);
+    assert_eq!(snap.stats.dirs, 1, "pages are not directories");
+
+    let stored = tree_from_store(&ws.store, &snap.root_manifest)?;
+    assert_eq!(stored.len(), FILES + 1);
+    assert!(stored.contains_key("wide/f00042.txt"));
+
+    // The in-memory scan pages identically, so a clean tree diffs empty.
+    let (mem_root, manifests, _) = ws.current_manifest_tree()?;
+    assert_eq!(mem_root, snap.root_manifest);
+    assert!(diff_trees(&stored, &tree_from_memory(&manifests, &mem_root)?).is_empty());
+
+    // Restore materializes every page's entries into the one directory.
+    fs::remove_dir_all(root.join("wide")).context("remove wide")?;
+    ws.restore_snap(&snap.id, true)?;
+    assert_eq!(fs::read_dir(root.join("wide"))?.count(), FILES);
+    assert_eq!(fs::read_to_string(root.join("wide/f04000.txt"))?, "4000\n");
+    Ok(())
+}
+
+#[test]
+fn one_edit_rewrites_one_page() -> Result<()> {
+    let tmp = tempfile::tempdir().context("create tempdir")?;
+    let root = tmp.path();
+    wide_dir(root)?;
+
+    let ws = Workspace::init(root, false)?;
+    let before = ws.create_snap(None)?;
+    fs::write(root.join("wide/f01234.txt"), b"edited\n").context("edit file")?;
+    let after = ws.create_snap(None)?;
+
+    let a = wide_manifest(&ws, &before.root_manifest)?;
+    let b = wide_manifest(&ws, &after.root_manifest)?;
+    assert_eq!(a.entries.len(), b.entries.len());
+    let changed = a
+        .entries
+        .iter()
+        .zip(&b.entries)
+        .filter(|(x, y)| x != y)
+        .count();
+    assert_eq!(changed, 1, "only the page holding the edit changes");
+
+    let diff = diff_trees(
+        &tree_from_store(&ws.store, &before.root_manifest)?,
+        &tree_from_store(&ws.store, &after.root_manifest)?,
+    );
+    assert_eq!(diff.len(), 1);
+    assert_eq!(diff[0].path(), "wide/f01234.txt");
+    Ok(())
+}
+
+#[test]
+fn a_directory_at_the_threshold_is_not_paged() -> Result<()> {
+    let tmp = tempfile::tempdir().context("create tempdir")?;
+    let root = tmp.path();
+    fs::create_dir_all(root.join("wide")).context("create wide dir")?;
+    for i in 0..PAGE_THRESHOLD {
+        fs::write(root.join(format!("wide/f{i:05}")), b"x").context("write file")?;
+    }
+
+    let ws = Workspace::init(root, false)?;
+    let snap = ws.create_snap(None)?;
+    let wide = wide_manifest(&ws, &snap.root_manifest)?;
+    assert!(!paging::is_index(&wide));
+    assert_eq!(wide.entries.len(), PAGE_THRESHOLD);
+    Ok(())
+}
@@ -0,0 +1,125 @@
+//! Doc 16 §1f: a workspace that stores objects encoded captures the same
+//! ids as one that does not, and reads back the same bytes.
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+
+use converge_client::workspace::Workspace;
+
+fn source_text() -> String {
+    (0..4000)
+        .map(|i| format!("let value_{i} = compute({i});\n"))
+        .collect()
+}
+
+/// Every file under the store's objects directory, by name.
+fn object_files(root: &Path) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut stack = vec![root.join(".converge/objects")];
+    while let Some(dir) = stack.pop() {
+        for entry in fs::read_dir(&dir).into_iter().flatten().flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                stack.push(path);
+            } else {
+                out.push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+    }
+    out
+}
+
+fn fill(root: &Path) -> Result<()> {
+    fs::create_dir_all(root.join("src"))?;
+    fs::write(root.join("src/main.rs"), source_text())?;
+    fs::write(root.join("tiny.txt"), "tiny")?;
+    Ok(())
+}
+
+#[test]
+fn encoded_objects_keep_their_ids_and_read_back() -> Result<()> {
+    let plain_dir = tempfile::tempdir()?;
+    let plain = Workspace::init(plain_dir.path(), false)?;
+    fill(plain_dir.path())?;
+    let plain_snap = plain.create_snap(None)?;
+
+    let dir = tempfile::tempdir()?;
+    let ws = Workspace::init(dir.path(), false)?;
+    let mut cfg = ws.store.read_config()?;
+    cfg.compress_objects = true;
+    ws.store.write_config(&cfg)?;
+    // Read when the store is opened.
+    let ws = Workspace::discover(dir.path())?;
+    fill(dir.path())?;
+    let snap = ws.create_snap(None)?;
+
+    assert_eq!(snap.root_manifest, plain_snap.root_manifest);
+    let files = object_files(dir.path());
+    let encoded = files.iter().filter(|name| name.ends_with(".zst")).count();
+    assert!(encoded >= 1, "the source file is stored encoded: {files:?}");
+    assert!(
+        files.iter().any(|name| !name.contains('.')),
+        "a four-byte file is not worth encoding: {files:?}"
+    );
+
+    // Restoring reads through the encoding.
+    fs::remove_dir_all(dir.path().join("src"))?;
+    ws.restore_snap(&snap.id, true)?;
+    assert_eq!(
+        fs::read_to_string(dir.path().join("src/main.rs"))?,
+        source_text()
+    );
+
+    // Switching it off changes what is written, not what can be read.
+    let mut cfg = ws.store.read_config()?;
+    cfg.compress_objects = false;
+    ws.store.write_config(&cfg)?;
+    let ws = Workspace::discover(dir.path())?;
+    fs::write(dir.path().join("src/lib.rs"), source_text().repeat(2))?;
+    let next = ws.create_snap(None)?;
+    assert_eq!(
+        object_files(dir.path())
+            .iter()
+            .filter(|name| name.ends_with(".zst"))
+            .count(),
+        encoded
+    );
+    fs::remove_dir_all(dir.path().join("src"))?;
+    ws.restore_snap(&next.id, true)?;
+    assert_eq!(
+        fs::read_to_string(dir.path().join("src/main.rs"))?,
+        source_text()
+    );
+    Ok(())
+}
+
+/// A damaged encoded object fails its integrity check like a plain one.
+#[test]
+fn a_corrupt_encoded_object_is_refused() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let ws = Workspace::init(dir.path(), false)?;
+    let mut cfg = ws.store.read_config()?;
+    cfg.compress_objects = true;
+    ws.store.write_config(&cfg)?;
+    let ws = Workspace::discover(dir.path())?;
+    let id = ws.store.put_blob(source_text().as_bytes())?;
+
+    let h = id.as_str();
+    let path = dir
+        .path()
+        .join(".converge/objects/blobs")
+        .join(&h[..2])
+        .join(&h[2..4])
+        .join(format!("{h}.zst"));
+    let mut bytes = fs::read(&path)?;
+    let last = bytes.len() - 1;
+    bytes[last] ^= 0xff;
+    fs::write(&path, bytes)?;
+
+    let err = ws.store.get_blob(&id).unwrap_err();
+    assert!(format!("{err:#}").contains(h), "{err:#}");
+    assert!(ws.store.has_blob(&id));
+    Ok(())
+}
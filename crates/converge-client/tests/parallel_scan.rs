@@ -0,0 +1,185 @@
+//! Doc 15 §5d: the scan reads, hashes and chunks files on a pool of
+//! workers, and the tree it builds does not depend on how many.
+
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+use anyhow::Result;
+
+use converge_client::model::{ChunkingConfig, ObjectId};
+use converge_client::workspace::Workspace;
+
+fn pseudo_random_bytes(len: usize, mut seed: u64) -> Vec<u8> {
+    let mut out = Vec::with_capacity(len);
+    while out.len() < len {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        out.extend_from_slice(&seed.to_le_bytes());
+    }
+    out.truncate(len);
+    out
+}
+
+/// A workspace scanning on `threads` workers, chunking files from
+/// `threshold` bytes up.
+fn workspace(root: &Path, threads: usize, threshold: u64) -> Result<Workspace> {
+    let ws = Workspace::init(root, false)?;
+    let mut cfg = ws.store.read_config()?;
+    cfg.scan_threads = Some(threads);
+    cfg.chunking = Some(ChunkingConfig {
+        chunk_size: 64 * 1024,
+        threshold,
+        rules: Vec::new(),
+    });
+    ws.store.write_config(&cfg)?;
+    Ok(ws)
+}
+
+/// The stat index would answer a second scan without reading anything.
+fn scan_afresh(ws: &Workspace) -> Result<ObjectId> {
+    let _ = fs::remove_file(ws.root.join(".converge/index"));
+    Ok(ws.current_manifest_tree()?.0)
+}
+
+#[test]
+fn any_worker_count_builds_the_same_tree() -> Result<()> {
+    let fill = |root: &Path| -> Result<()> {
+        for dir in 0..4u64 {
+            let sub = root.join(format!("dir-{dir}"));
+            fs::create_dir_all(sub.join("nested"))?;
+            for file in 0..12u64 {
+                let seed = dir * 100 + file + 1;
+                let len = (seed as usize * 9_973) % (300 * 1024);
+                fs::write(
+                    sub.join(format!("f-{file:02}.bin")),
+                    pseudo_random_bytes(len, seed),
+                )?;
+            }
+            // Same bytes under two names: two workers may store one
+            // object at once.
+            fs::write(sub.join("nested/same-a.txt"), "twice")?;
+            fs::write(sub.join("nested/same-b.txt"), "twice")?;
+        }
+        Ok(())
+    };
+
+    let one_dir = tempfile::tempdir()?;
+    let one = workspace(one_dir.path(), 1, 128 * 1024)?;
+    fill(one_dir.path())?;
+    let many_dir = tempfile::tempdir()?;
+    let many = workspace(many_dir.path(), 8, 128 * 1024)?;
+    fill(many_dir.path())?;
+
+    let sequential = scan_afresh(&one)?;
+    assert_eq!(scan_afresh(&many)?, sequential);
+    let (one_snap, many_snap) = (one.create_snap(None)?, many.create_snap(None)?);
+    assert_eq!(one_snap.root_manifest, sequential);
+    assert_eq!(many_snap.root_manifest, sequential);
+    assert_eq!(one_snap.stats.files, many_snap.stats.files);
+    assert_eq!(one_snap.stats.bytes, many_snap.stats.bytes);
+
+    // Everything the workers stored reads back.
+    let file = many_dir.path().join("dir-2/f-07.bin");
+    let bytes = fs::read(&file)?;
+    fs::remove_dir_all(many_dir.path().join("dir-2"))?;
+    many.restore_snap(&many_snap.id, true)?;
+    assert_eq!(fs::read(&file)?, bytes);
+    Ok(())
+}
+
+/// Dev-only (run with `effigy bench`, or `cargo test -- --ignored`): a
+/// DAW session's worth of large files, scanned on one worker and on one
+/// per core. Both build the same tree; the timings are the point.
+#[test]
+#[ignore]
+fn scan_benchmark_large_files() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    for take in 0..48u64 {
+        fs::write(
+            dir.path().join(format!("take-{take:02}.wav")),
+            pseudo_random_bytes(16 * 1024 * 1024, take + 1),
+        )?;
+    }
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+
+    let mut roots = Vec::new();
+    let mut times = Vec::new();
+    for threads in [1, cores] {
+        // A fresh store and no stat index: every file is read, hashed,
+        // chunked and stored.
+        let _ = fs::remove_dir_all(dir.path().join(".converge"));
+        let ws = Workspace::init(dir.path(), false)?;
+        let mut cfg = ws.store.read_config()?;
+        cfg.scan_threads = Some(threads);
+        ws.store.write_config(&cfg)?;
+        let started = Instant::now();
+        roots.push(ws.create_snap(None)?.root_manifest);
+        times.push(started.elapsed());
+    }
+    let speedup = times[0].as_secs_f64() / times[1].as_secs_f64();
+    eprintln!(
+        "768 MiB in 48 chunked files: 1 worker {:?}, {cores} workers {:?} ({speedup:.1}x)",
+        times[0], times[1]
+    );
+    assert_eq!(roots[0], roots[1]);
+    if cores >= 4 {
+        assert!(
+            speedup > 1.5,
+            "{cores} workers were only {speedup:.1}x faster"
+        );
+    }
+    Ok(())
+}
+
+/// Dev-only, like the one above: a source tree's shape — thousands of
+/// directories holding a few small files each. The scan reads the files
+/// of every directory on one pool, so the workers stay busy even though
+/// no single directory has enough files to share out.
+#[test]
+#[ignore]
+fn scan_benchmark_many_small_directories() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    for module in 0..2_000u64 {
+        let sub = dir
+            .path()
+            .join(format!("src/group-{:02}/module-{module:04}", module % 40));
+        fs::create_dir_all(&sub)?;
+        for file in 0..3u64 {
+            let seed = module * 10 + file + 1;
+            fs::write(
+                sub.join(format!("part-{file}.rs")),
+                pseudo_random_bytes(4 * 1024 + (seed as usize % 2048), seed),
+            )?;
+        }
+    }
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+
+    let mut roots = Vec::new();
+    let mut times = Vec::new();
+    for threads in [1, cores] {
+        let _ = fs::remove_dir_all(dir.path().join(".converge"));
+        let ws = Workspace::init(dir.path(), false)?;
+        let mut cfg = ws.store.read_config()?;
+        cfg.scan_threads = Some(threads);
+        ws.store.write_config(&cfg)?;
+        let started = Instant::now();
+        roots.push(ws.create_snap(None)?.root_manifest);
+        times.push(started.elapsed());
+    }
+    let speedup = times[0].as_secs_f64() / times[1].as_secs_f64();
+    eprintln!(
+        "6000 small files in 2000 directories: 1 worker {:?}, {cores} workers {:?} \
+         ({speedup:.1}x)",
+        times[0], times[1]
+    );
+    assert_eq!(roots[0], roots[1]);
+    if cores >= 4 {
+        assert!(
+            speedup > 1.5,
+            "{cores} workers were only {speedup:.1}x faster"
+        );
+    }
+    Ok(())
+}
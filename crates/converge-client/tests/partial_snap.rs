@@ -0,0 +1,118 @@
+//! Doc 15 §5g: a snap of selected paths is head with only those paths
+//! changed; the rest stays pending.
+
+use std::fs;
+
+use anyhow::Result;
+
+use converge_client::diff::DiffLine;
+use converge_client::model::{ManifestEntryKind, ObjectId};
+use converge_client::workspace::Workspace;
+
+/// A workspace with `a.txt`, `src/b.txt` and `docs/c.txt` captured as head.
+fn captured() -> Result<(tempfile::TempDir, Workspace)> {
+    let tmp = tempfile::tempdir()?;
+    let ws = Workspace::init(tmp.path(), false)?;
+    fs::create_dir(tmp.path().join("src"))?;
+    fs::create_dir(tmp.path().join("docs"))?;
+    fs::write(tmp.path().join("a.txt"), "a\n")?;
+    fs::write(tmp.path().join("src/b.txt"), "b\n")?;
+    fs::write(tmp.path().join("docs/c.txt"), "c\n")?;
+    ws.create_snap(None)?;
+    Ok((tmp, ws))
+}
+
+fn pending(ws: &Workspace) -> Result<Vec<String>> {
+    Ok(ws
+        .pending_changes()?
+        .iter()
+        .map(|line| line.path().to_string())
+        .collect())
+}
+
+/// The id of the directory `name` in the root of `snap`.
+fn subtree(ws: &Workspace, snap: &str, name: &str) -> Result<ObjectId> {
+    let root = ws.store.get_snap(snap)?.root_manifest;
+    let entry = ws
+        .store
+        .get_dir(&root)?
+        .entries
+        .into_iter()
+        .find(|e| e.name == name)
+        .expect("a directory entry");
+    match entry.kind {
+        ManifestEntryKind::Dir { manifest } => Ok(manifest),
+        other => panic!("{name} is {other:?}"),
+    }
+}
+
+#[test]
+fn a_path_snap_takes_only_the_changes_under_it() -> Result<()> {
+    let (tmp, ws) = captured()?;
+    let root = tmp.path();
+    let head = ws.store.get_head()?.expect("a head");
+    fs::write(root.join("a.txt"), "a, edited\n")?;
+    fs::write(root.join("src/b.txt"), "b, edited\n")?;
+    fs::write(root.join("src/new.txt"), "new\n")?;
+    fs::remove_file(root.join("docs/c.txt"))?;
+
+    let snap = ws.create_snap_paths(Some("src only".to_string()), &["src".to_string()])?;
+    assert_eq!(snap.parents, [head.as_str()]);
+    assert_eq!(ws.store.get_head()?.as_deref(), Some(snap.id.as_str()));
+    assert_eq!(snap.stats.files, 4, "a.txt, docs/c.txt and both under src");
+    // Untouched subtrees are head's, by id.
+    assert_eq!(
+        subtree(&ws, &snap.id, "docs")?,
+        subtree(&ws, &head, "docs")?
+    );
+    assert_ne!(subtree(&ws, &snap.id, "src")?, subtree(&ws, &head, "src")?);
+    // And the rest is still pending, on disk as it was.
+    assert_eq!(pending(&ws)?, ["a.txt", "docs/c.txt"]);
+    assert_eq!(fs::read_to_string(root.join("a.txt"))?, "a, edited\n");
+
+    // Globs match as `.convergeignore`'s do; a deletion is a change too.
+    ws.create_snap_paths(None, &["docs/*.txt".to_string()])?;
+    assert_eq!(pending(&ws)?, ["a.txt"]);
+    assert!(
+        ws.create_snap_paths(None, &["src".to_string()]).is_err(),
+        "nothing under src is left"
+    );
+    ws.create_snap_paths(None, &[".".to_string()])?;
+    assert!(pending(&ws)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn a_selected_snap_can_pick_change_by_change() -> Result<()> {
+    let (tmp, ws) = captured()?;
+    let root = tmp.path();
+    fs::write(root.join("a.txt"), "a, edited\n")?;
+    fs::write(root.join("src/b.txt"), "b, edited\n")?;
+
+    let snap = ws.create_snap_selected(
+        None,
+        |line| matches!(line, DiffLine::Modified { path, .. } if path == "src/b.txt"),
+    )?;
+    assert_eq!(pending(&ws)?, ["a.txt"]);
+    // What was captured reads back as it was on disk.
+    fs::write(root.join("src/b.txt"), "b\n")?;
+    ws.restore_snap(&snap.id, true)?;
+    assert_eq!(fs::read_to_string(root.join("src/b.txt"))?, "b, edited\n");
+    assert_eq!(fs::read_to_string(root.join("a.txt"))?, "a\n");
+    Ok(())
+}
+
+#[test]
+fn the_first_snap_can_be_partial_too() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let ws = Workspace::init(tmp.path(), false)?;
+    fs::create_dir(tmp.path().join("src"))?;
+    fs::write(tmp.path().join("src/b.txt"), "b\n")?;
+    fs::write(tmp.path().join("scratch.txt"), "not yet\n")?;
+
+    let snap = ws.create_snap_paths(None, &["src".to_string()])?;
+    assert!(snap.parents.is_empty());
+    assert_eq!(snap.stats.files, 1);
+    assert_eq!(pending(&ws)?, ["scratch.txt"]);
+    Ok(())
+}
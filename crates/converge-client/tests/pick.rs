@@ -0,0 +1,247 @@
+//! Doc 15 §5h: pick and rebase replay a snap's change against its first
+//! parent onto another lineage, superposing what both sides changed.
+
+use std::fs;
+
+use anyhow::Result;
+
+use converge_client::model::gates::StrategyPolicy;
+use converge_client::model::{ManifestEntryKind, ObjectId};
+use converge_client::workspace::Workspace;
+
+/// A workspace with `a.txt`, `b.txt` and `lib/c.txt` captured as the
+/// fork point, and two lineages off it: `theirs`, then head.
+struct Fork {
+    _tmp: tempfile::TempDir,
+    ws: Workspace,
+    fork: String,
+    theirs: String,
+}
+
+fn fork(theirs: &[(&str, Option<&str>)], ours: &[(&str, Option<&str>)]) -> Result<Fork> {
+    let tmp = tempfile::tempdir()?;
+    let ws = Workspace::init(tmp.path(), false)?;
+    fs::create_dir(tmp.path().join("lib"))?;
+    fs::write(tmp.path().join("a.txt"), "a\n")?;
+    fs::write(tmp.path().join("b.txt"), "b\n")?;
+    fs::write(tmp.path().join("lib/c.txt"), "c\n")?;
+    let fork = ws.create_snap(None)?.id;
+    let edit = |edits: &[(&str, Option<&str>)], message: &str| -> Result<String> {
+        ws.restore_snap(&fork, true)?;
+        for (path, content) in edits {
+            match content {
+                Some(text) => fs::write(tmp.path().join(path), text)?,
+                None => fs::remove_file(tmp.path().join(path))?,
+            }
+        }
+        Ok(ws.create_snap(Some(message.to_string()))?.id)
+    };
+    let theirs = edit(theirs, "theirs")?;
+    edit(ours, "ours")?;
+    Ok(Fork {
+        _tmp: tmp,
+        ws,
+        fork,
+        theirs,
+    })
+}
+
+fn whole_file() -> StrategyPolicy {
+    StrategyPolicy::uniform("whole-file")
+}
+
+fn entry(ws: &Workspace, snap: &str, name: &str) -> Result<Option<ManifestEntryKind>> {
+    let root = ws.store.get_snap(snap)?.root_manifest;
+    Ok(ws
+        .store
+        .get_dir(&root)?
+        .entries
+        .into_iter()
+        .find(|e| e.name == name)
+        .map(|e| e.kind))
+}
+
+fn dir_id(ws: &Workspace, snap: &str, name: &str) -> Result<ObjectId> {
+    match entry(ws, snap, name)? {
+        Some(ManifestEntryKind::Dir { manifest }) => Ok(manifest),
+        other => panic!("{name} is {other:?}"),
+    }
+}
+
+#[test]
+fn a_pick_replays_only_that_snaps_change() -> Result<()> {
+    let f = fork(
+        &[("a.txt", Some("a, fixed\n")), ("new.txt", Some("new\n"))],
+        &[("b.txt", Some("b, ours\n"))],
+    )?;
+    let root = &f.ws.root;
+    let head = f.ws.store.get_head()?.expect("a head");
+
+    let picked = f.ws.pick(&f.theirs, &whole_file())?;
+    assert!(picked.conflicts.is_empty());
+    assert_eq!(picked.snaps, [picked.head.as_str()]);
+    let snap = f.ws.store.get_snap(&picked.head)?;
+    assert_eq!(snap.parents, [head.as_str()]);
+    assert_eq!(snap.message.as_deref(), Some("theirs"));
+    assert_eq!(snap.replayed_from.as_deref(), Some(f.theirs.as_str()));
+    assert_eq!(fs::read_to_string(root.join("a.txt"))?, "a, fixed\n");
+    assert_eq!(fs::read_to_string(root.join("b.txt"))?, "b, ours\n");
+    assert_eq!(fs::read_to_string(root.join("new.txt"))?, "new\n");
+    // What the change did not touch keeps its id.
+    assert_eq!(
+        dir_id(&f.ws, &snap.id, "lib")?,
+        dir_id(&f.ws, &f.fork, "lib")?
+    );
+    assert!(f.ws.pending_changes()?.is_empty());
+
+    // Picked again, it is already there.
+    assert!(
+        f.ws.pick(&snap.id, &whole_file()).is_err(),
+        "in head's history"
+    );
+    let again = f.ws.pick(&f.theirs, &whole_file())?;
+    assert!(again.snaps.is_empty());
+    assert_eq!(again.head, snap.id);
+    Ok(())
+}
+
+#[test]
+fn a_change_made_on_both_sides_is_superposed_not_refused() -> Result<()> {
+    let f = fork(
+        &[("a.txt", Some("a, theirs\n"))],
+        &[("a.txt", Some("a, ours\n"))],
+    )?;
+    let picked = f.ws.pick(&f.theirs, &whole_file())?;
+    assert_eq!(picked.conflicts, ["a.txt"]);
+    match entry(&f.ws, &picked.head, "a.txt")? {
+        Some(ManifestEntryKind::Superposition { variants }) => {
+            let sources: Vec<&str> = variants.iter().map(|v| v.source.as_str()).collect();
+            assert_eq!(
+                sources,
+                ["head", format!("snap {}", &f.theirs[..8]).as_str()]
+            );
+        }
+        other => panic!("a.txt is {other:?}"),
+    }
+    let marked = fs::read_to_string(f.ws.root.join("a.txt"))?;
+    assert!(
+        marked.contains("a, ours") && marked.contains("a, theirs"),
+        "{marked}"
+    );
+    assert_eq!(f.ws.marked_paths()?, ["a.txt"]);
+    Ok(())
+}
+
+#[test]
+fn a_strategy_settles_what_both_sides_changed() -> Result<()> {
+    let f = fork(
+        &[("lib/c.txt", Some("c\nfrom theirs\n"))],
+        &[("lib/c.txt", Some("from ours\nc\n"))],
+    )?;
+    let policy = StrategyPolicy::uniform("text-line-merge");
+    let picked = f.ws.pick(&f.theirs, &policy)?;
+    assert!(picked.conflicts.is_empty());
+    assert_eq!(
+        fs::read_to_string(f.ws.root.join("lib/c.txt"))?,
+        "from ours\nc\nfrom theirs\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn a_picked_edit_follows_a_file_head_moved() -> Result<()> {
+    let f = fork(&[("a.txt", Some("a, fixed\n"))], &[])?;
+    fs::rename(f.ws.root.join("a.txt"), f.ws.root.join("lib/a.txt"))?;
+    f.ws.create_snap(Some("move a".to_string()))?;
+
+    let picked = f.ws.pick(&f.theirs, &whole_file())?;
+    assert!(picked.conflicts.is_empty());
+    assert!(!f.ws.root.join("a.txt").exists());
+    assert_eq!(
+        fs::read_to_string(f.ws.root.join("lib/a.txt"))?,
+        "a, fixed\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn a_conflict_markers_cannot_show_keeps_head_and_the_tree() -> Result<()> {
+    let f = fork(&[("b.txt", None)], &[("b.txt", Some("b, ours\n"))])?;
+    let head = f.ws.store.get_head()?;
+    let err =
+        f.ws.pick(&f.theirs, &whole_file())
+            .expect_err("a delete against an edit");
+    assert!(format!("{err:#}").contains("resolve list"), "{err:#}");
+    assert_eq!(f.ws.store.get_head()?, head);
+    assert_eq!(fs::read_to_string(f.ws.root.join("b.txt"))?, "b, ours\n");
+    Ok(())
+}
+
+#[test]
+fn a_rebase_replays_heads_own_snaps_onto_another_lineage() -> Result<()> {
+    let f = fork(
+        &[("lib/c.txt", Some("c, upstream\n"))],
+        &[("a.txt", Some("a, mine\n"))],
+    )?;
+    let root = f.ws.root.clone();
+    fs::write(root.join("b.txt"), "b, mine\n")?;
+    let old_head = f.ws.create_snap(Some("second".to_string()))?.id;
+
+    let rebased = f.ws.rebase_onto(&f.theirs, &whole_file())?;
+    assert_eq!(rebased.snaps.len(), 2);
+    let first = f.ws.store.get_snap(&rebased.snaps[0])?;
+    let second = f.ws.store.get_snap(&rebased.snaps[1])?;
+    assert_eq!(first.parents, [f.theirs.as_str()]);
+    assert_eq!(first.message.as_deref(), Some("ours"));
+    assert_eq!(second.parents, [first.id.as_str()]);
+    // Each names the snap it replays, which keeps the change's author.
+    let old_first = f.ws.store.get_snap(&old_head)?.parents[0].clone();
+    assert_eq!(first.replayed_from.as_deref(), Some(old_first.as_str()));
+    assert_eq!(second.replayed_from.as_deref(), Some(old_head.as_str()));
+    assert_eq!(rebased.head, second.id);
+    assert_eq!(fs::read_to_string(root.join("a.txt"))?, "a, mine\n");
+    assert_eq!(fs::read_to_string(root.join("b.txt"))?, "b, mine\n");
+    assert_eq!(fs::read_to_string(root.join("lib/c.txt"))?, "c, upstream\n");
+    // The old line is still there, off head's.
+    assert!(f.ws.store.has_snap(&old_head));
+    assert!(!f.ws.lineage_ids(&rebased.head)?.contains(&old_head));
+    assert!(
+        f.ws.rebase_onto(&f.theirs, &whole_file()).is_err(),
+        "already on it"
+    );
+    Ok(())
+}
+
+/// A directory past the paging threshold is read a page at a time, and
+/// picking into one edits the right page.
+#[test]
+fn a_pick_into_a_paged_directory_lands_each_edit() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let ws = Workspace::init(tmp.path(), false)?;
+    let many = tmp.path().join("many");
+    fs::create_dir(&many)?;
+    let count = converge_client::model::paging::PAGE_THRESHOLD + 100;
+    for i in 0..count {
+        fs::write(many.join(format!("f{i:05}.txt")), format!("{i}\n"))?;
+    }
+    let fork = ws.create_snap(None)?.id;
+    fs::write(many.join("f00017.txt"), "17, theirs\n")?;
+    let theirs = ws.create_snap(Some("theirs".to_string()))?.id;
+    ws.restore_snap(&fork, true)?;
+    fs::write(many.join("f04000.txt"), "4000, ours\n")?;
+    ws.create_snap(Some("ours".to_string()))?;
+
+    let picked = ws.pick(&theirs, &whole_file())?;
+    assert!(picked.conflicts.is_empty());
+    assert_eq!(fs::read_to_string(many.join("f00017.txt"))?, "17, theirs\n");
+    assert_eq!(fs::read_to_string(many.join("f04000.txt"))?, "4000, ours\n");
+    let index = ws.store.get_manifest(&dir_id(&ws, &picked.head, "many")?)?;
+    assert!(
+        index
+            .entries
+            .iter()
+            .all(|e| matches!(e.kind, ManifestEntryKind::Page { .. })),
+        "many is stored paged"
+    );
+    Ok(())
+}
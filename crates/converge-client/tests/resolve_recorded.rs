@@ -0,0 +1,118 @@
+//! Doc 17 §2c: a decision applied once is offered again wherever the same
+//! variants meet.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+
+use converge_client::model::{
+    Manifest, ManifestEntry, ManifestEntryKind, ObjectId, ResolutionDecision, ResolvedContent,
+    ResolvedContentKind, SuperpositionVariant, SuperpositionVariantKind,
+};
+use converge_client::resolve::{apply_resolution, record_decisions, reuse_recorded_decisions};
+use converge_client::store::LocalStore;
+
+fn variant(store: &LocalStore, source: &str, bytes: &[u8]) -> Result<SuperpositionVariant> {
+    Ok(SuperpositionVariant {
+        source: source.to_string(),
+        kind: SuperpositionVariantKind::File {
+            blob: store.put_blob(bytes)?,
+            mode: 0o100644,
+            size: bytes.len() as u64,
+        },
+    })
+}
+
+fn superposed(
+    store: &LocalStore,
+    name: &str,
+    variants: Vec<SuperpositionVariant>,
+) -> Result<ObjectId> {
+    store.put_manifest(&Manifest {
+        version: 1,
+        entries: vec![ManifestEntry {
+            name: name.to_string(),
+            kind: ManifestEntryKind::Superposition { variants },
+        }],
+    })
+}
+
+fn file_at(store: &LocalStore, root: &ObjectId) -> Result<Vec<u8>> {
+    match &store.get_manifest(root)?.entries[0].kind {
+        ManifestEntryKind::File { blob, .. } => store.get_blob(blob),
+        other => anyhow::bail!("expected a file, got {other:?}"),
+    }
+}
+
+#[test]
+fn a_pick_is_reused_whatever_the_order_or_path() -> Result<()> {
+    let ws = tempfile::tempdir()?;
+    LocalStore::init(ws.path(), false)?;
+    let store = LocalStore::open(ws.path())?;
+    let a = variant(&store, "lane-a", b"a\n")?;
+    let b = variant(&store, "lane-b", b"b\n")?;
+
+    // Decided by index, which means nothing once the order changes —
+    // so it is recorded as the key.
+    let first = superposed(&store, "x.txt", vec![a.clone(), b.clone()])?;
+    let decisions = BTreeMap::from([("x.txt".to_string(), ResolutionDecision::Index(1))]);
+    apply_resolution(&store, &first, &decisions)?;
+    assert_eq!(record_decisions(&store, &first, &decisions)?, 1);
+
+    let next = superposed(&store, "moved.txt", vec![b.clone(), a.clone()])?;
+    let mut decisions = BTreeMap::new();
+    let reused = reuse_recorded_decisions(&store, &next, &mut decisions)?;
+    assert_eq!(reused, vec!["moved.txt".to_string()]);
+    assert_eq!(decisions["moved.txt"], ResolutionDecision::Key(b.key()));
+    assert_eq!(
+        file_at(&store, &apply_resolution(&store, &next, &decisions)?)?,
+        b"b\n"
+    );
+
+    // A decision given explicitly stands.
+    let mut decisions =
+        BTreeMap::from([("moved.txt".to_string(), ResolutionDecision::Key(a.key()))]);
+    assert!(reuse_recorded_decisions(&store, &next, &mut decisions)?.is_empty());
+    assert_eq!(decisions["moved.txt"], ResolutionDecision::Key(a.key()));
+
+    // A third variant is a different conflict.
+    let c = variant(&store, "lane-c", b"c\n")?;
+    let wider = superposed(&store, "x.txt", vec![a, b, c])?;
+    let mut decisions = BTreeMap::new();
+    assert!(reuse_recorded_decisions(&store, &wider, &mut decisions)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn hand_edited_content_is_reused() -> Result<()> {
+    let ws = tempfile::tempdir()?;
+    LocalStore::init(ws.path(), false)?;
+    let store = LocalStore::open(ws.path())?;
+    let a = variant(&store, "lane-a", b"a\n")?;
+    let b = variant(&store, "lane-b", b"b\n")?;
+    let root = superposed(&store, "x.txt", vec![a.clone(), b.clone()])?;
+
+    let decisions = BTreeMap::from([(
+        "x.txt".to_string(),
+        ResolutionDecision::Content(ResolvedContent {
+            content: ResolvedContentKind::File {
+                blob: store.put_blob(b"a and b\n")?,
+                mode: 0o100644,
+                size: 8,
+            },
+            supersedes: vec![a.key(), b.key()],
+        }),
+    )]);
+    apply_resolution(&store, &root, &decisions)?;
+    record_decisions(&store, &root, &decisions)?;
+
+    let again = superposed(&store, "x.txt", vec![b, a])?;
+    let mut reused = BTreeMap::new();
+    reuse_recorded_decisions(&store, &again, &mut reused)?;
+    assert_eq!(reused, decisions);
+    assert_eq!(
+        file_at(&store, &apply_resolution(&store, &again, &reused)?)?,
+        b"a and b\n"
+    );
+    Ok(())
+}
@@ -3,8 +3,9 @@ use std::collections::BTreeMap;
 use anyhow::Result;
 
 use converge_client::model::{
-    Manifest, ManifestEntry, ManifestEntryKind, ResolutionDecision, SuperpositionVariant,
-    SuperpositionVariantKind, VariantKey, VariantKeyKind,
+    Manifest, ManifestEntry, ManifestEntryKind, ResolutionDecision, ResolvedContent,
+    ResolvedContentKind, SuperpositionVariant, SuperpositionVariantKind, VariantKey,
+    VariantKeyKind,
 };
 use converge_client::store::LocalStore;
 
@@ -73,3 +74,97 @@ fn validate_resolution_reports_missing_and_invalid() -> Result<()> {
 
     Ok(())
 }
+
+/// Doc 17 §2b: content neither variant holds, naming the variants it
+/// replaces.
+#[test]
+fn hand_edited_content_validates_and_applies() -> Result<()> {
+    let ws = tempfile::tempdir()?;
+    LocalStore::init(ws.path(), false)?;
+    let store = LocalStore::open(ws.path())?;
+
+    let variant = |source: &str, bytes: &[u8]| -> Result<SuperpositionVariant> {
+        Ok(SuperpositionVariant {
+            source: source.to_string(),
+            kind: SuperpositionVariantKind::File {
+                blob: store.put_blob(bytes)?,
+                mode: 0o100644,
+                size: bytes.len() as u64,
+            },
+        })
+    };
+    let v1 = variant("pub-1", b"one\n")?;
+    let v2 = variant("pub-2", b"two\n")?;
+    let root = store.put_manifest(&Manifest {
+        version: 1,
+        entries: vec![ManifestEntry {
+            name: "a.txt".to_string(),
+            kind: ManifestEntryKind::Superposition {
+                variants: vec![v1.clone(), v2.clone()],
+            },
+        }],
+    })?;
+
+    let edited = store.put_blob(b"one and two\n")?;
+    let decide = |blob: &converge_client::model::ObjectId, supersedes: Vec<VariantKey>| {
+        BTreeMap::from([(
+            "a.txt".to_string(),
+            ResolutionDecision::Content(ResolvedContent {
+                content: ResolvedContentKind::File {
+                    blob: blob.clone(),
+                    mode: 0o100644,
+                    size: 12,
+                },
+                supersedes,
+            }),
+        )])
+    };
+
+    let decisions = decide(&edited, vec![v1.key(), v2.key()]);
+    let r = converge_client::resolve::validate_resolution(&store, &root, &decisions)?;
+    assert!(r.ok, "{r:?}");
+    let resolved = converge_client::resolve::apply_resolution(&store, &root, &decisions)?;
+    match &store.get_manifest(&resolved)?.entries[0].kind {
+        ManifestEntryKind::File { blob, .. } => {
+            assert_eq!(store.get_blob(blob)?, b"one and two\n");
+        }
+        other => panic!("expected the hand-edited file, got {other:?}"),
+    }
+
+    // The decisions file round-trips: untagged, so the shape must not be
+    // mistaken for a key.
+    let json = serde_json::to_string(&decisions)?;
+    assert_eq!(
+        serde_json::from_str::<BTreeMap<String, ResolutionDecision>>(&json)?,
+        decisions
+    );
+
+    // A variant the content was not written against is stale.
+    let r = converge_client::resolve::validate_resolution(
+        &store,
+        &root,
+        &decide(&edited, vec![v1.key()]),
+    )?;
+    assert!(!r.ok);
+    assert_eq!(r.invalid_content.len(), 1);
+    assert!(
+        converge_client::resolve::apply_resolution(&store, &root, &decide(&edited, vec![v1.key()]))
+            .is_err()
+    );
+
+    // Content that never reached the store cannot be applied.
+    let absent = converge_client::model::ObjectId("0".repeat(64));
+    let r = converge_client::resolve::validate_resolution(
+        &store,
+        &root,
+        &decide(&absent, vec![v1.key(), v2.key()]),
+    )?;
+    assert_eq!(r.invalid_content.len(), 1);
+    assert!(
+        r.invalid_content[0]
+            .reason
+            .contains("not in the local store")
+    );
+
+    Ok(())
+}
@@ -13,7 +13,7 @@ use converge_model::{ObjectId, compute_snap_id};
 
 /// Store a hand-built manifest as a snap and return its id.
 fn snap_for_root(ws: &Workspace, root: ObjectId) -> Result<String> {
-    let id = compute_snap_id(&root, &[], None);
+    let id = compute_snap_id(&root, &[], None, None, None);
     ws.store.put_snap(&SnapRecord {
         version: 2,
         id: id.clone(),
@@ -21,6 +21,8 @@ fn snap_for_root(ws: &Workspace, root: ObjectId) -> Result<String> {
         root_manifest: root,
         parents: vec![],
         derived_from_candidate: None,
+        author: None,
+        replayed_from: None,
         message: Some("hostile".into()),
         trigger: "explicit".into(),
         stats: Default::default(),
@@ -0,0 +1,156 @@
+//! Doc 15 §5e: shelves set uncaptured edits aside off the snap lineage,
+//! and put them back over whatever head has become.
+
+use std::fs;
+use std::time::Duration;
+
+use anyhow::Result;
+
+use converge_client::workspace::Workspace;
+
+/// A workspace with `a.txt`, `src/b.txt` and `src/c.txt` captured as head.
+fn captured() -> Result<(tempfile::TempDir, Workspace)> {
+    let tmp = tempfile::tempdir()?;
+    let ws = Workspace::init(tmp.path(), false)?;
+    fs::create_dir(tmp.path().join("src"))?;
+    fs::write(tmp.path().join("a.txt"), "one\ntwo\nthree\n")?;
+    fs::write(tmp.path().join("src/b.txt"), "b\n")?;
+    fs::write(tmp.path().join("src/c.txt"), "c\n")?;
+    ws.create_snap(None)?;
+    Ok((tmp, ws))
+}
+
+fn head_root(ws: &Workspace) -> Result<converge_client::model::ObjectId> {
+    let head = ws.store.get_head()?.expect("a head");
+    Ok(ws.store.get_snap(&head)?.root_manifest)
+}
+
+#[test]
+fn shelving_restores_head_and_unshelving_puts_the_edits_back() -> Result<()> {
+    let (tmp, ws) = captured()?;
+    let root = tmp.path();
+    let snaps = ws.list_snaps()?.len();
+    fs::write(root.join("a.txt"), "one\nTWO\nthree\n")?;
+    fs::remove_file(root.join("src/c.txt"))?;
+    fs::write(root.join("src/new.txt"), "new\n")?;
+
+    let shelf = ws.shelve(None, &[])?;
+    assert_eq!(shelf.name, "shelf-1");
+    assert_eq!(shelf.paths, ["a.txt", "src/c.txt", "src/new.txt"]);
+    assert_eq!(fs::read_to_string(root.join("a.txt"))?, "one\ntwo\nthree\n");
+    assert_eq!(fs::read_to_string(root.join("src/c.txt"))?, "c\n");
+    assert!(!root.join("src/new.txt").exists());
+    assert_eq!(ws.current_manifest_tree()?.0, head_root(&ws)?);
+    // Off the lineage: no snap was made, and nothing more to shelve.
+    assert_eq!(ws.list_snaps()?.len(), snaps);
+    assert!(ws.shelve(None, &[]).is_err());
+
+    let back = ws.unshelve(None)?;
+    assert_eq!(back.name, "shelf-1");
+    assert!(back.conflicts.is_empty());
+    assert_eq!(fs::read_to_string(root.join("a.txt"))?, "one\nTWO\nthree\n");
+    assert!(!root.join("src/c.txt").exists());
+    assert_eq!(fs::read_to_string(root.join("src/new.txt"))?, "new\n");
+    assert!(ws.list_shelves()?.is_empty());
+    assert!(ws.unshelve(None).is_err(), "nothing is shelved");
+    Ok(())
+}
+
+#[test]
+fn a_path_shelves_only_the_edits_under_it() -> Result<()> {
+    let (tmp, ws) = captured()?;
+    let root = tmp.path();
+    fs::write(root.join("a.txt"), "kept edit\n")?;
+    fs::write(root.join("src/b.txt"), "shelved edit\n")?;
+
+    let shelf = ws.shelve(Some("wip"), &["src".to_string()])?;
+    assert_eq!(shelf.paths, ["src/b.txt"]);
+    assert_eq!(fs::read_to_string(root.join("a.txt"))?, "kept edit\n");
+    assert_eq!(fs::read_to_string(root.join("src/b.txt"))?, "b\n");
+    assert!(ws.shelve(Some("wip"), &[]).is_err(), "a name is taken once");
+    assert!(ws.shelve(Some("../escape"), &[]).is_err());
+
+    ws.unshelve(Some("wip"))?;
+    assert_eq!(fs::read_to_string(root.join("a.txt"))?, "kept edit\n");
+    assert_eq!(
+        fs::read_to_string(root.join("src/b.txt"))?,
+        "shelved edit\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn shelve_paths_take_the_snap_path_grammar() -> Result<()> {
+    let (tmp, ws) = captured()?;
+    let root = tmp.path();
+    fs::write(
+        root.join("a.txt"),
+        "kept edit
+",
+    )?;
+    fs::write(
+        root.join("src/b.txt"),
+        "shelved b
+",
+    )?;
+    fs::write(
+        root.join("src/c.txt"),
+        "shelved c
+",
+    )?;
+
+    let glob = ws.shelve(Some("glob"), &["src/*.txt".to_string()])?;
+    assert_eq!(glob.paths, ["src/b.txt", "src/c.txt"]);
+    ws.unshelve(Some("glob"))?;
+
+    let dotted = ws.shelve(Some("dotted"), &["./src/".to_string()])?;
+    assert_eq!(dotted.paths, ["src/b.txt", "src/c.txt"]);
+    assert_eq!(fs::read_to_string(root.join("a.txt"))?, "kept edit\n");
+    ws.unshelve(Some("dotted"))?;
+
+    assert!(ws.shelve(None, &["src/../a.txt".to_string()]).is_err());
+    Ok(())
+}
+
+#[test]
+fn an_edit_made_again_since_becomes_conflict_markers() -> Result<()> {
+    let (tmp, ws) = captured()?;
+    let root = tmp.path();
+    fs::write(root.join("a.txt"), "one\nSHELVED\nthree\n")?;
+    fs::write(root.join("src/b.txt"), "b, shelved\n")?;
+    ws.shelve(None, &[])?;
+
+    // Head moves on under the shelf: a.txt both ways, b.txt not at all.
+    fs::write(root.join("a.txt"), "one\nCAPTURED\nthree\n")?;
+    ws.create_snap(None)?;
+
+    let back = ws.unshelve(None)?;
+    assert_eq!(back.conflicts, ["a.txt"]);
+    let marked = fs::read_to_string(root.join("a.txt"))?;
+    assert!(marked.contains("<<<<<<<"), "{marked}");
+    assert!(marked.contains("CAPTURED") && marked.contains("SHELVED"));
+    assert!(marked.contains("shelf shelf-1"), "{marked}");
+    assert_eq!(fs::read_to_string(root.join("src/b.txt"))?, "b, shelved\n");
+
+    // Mid-resolution, the tree is not set aside again.
+    assert!(ws.shelve(None, &[]).is_err());
+    Ok(())
+}
+
+#[test]
+fn gc_keeps_what_only_a_shelf_holds() -> Result<()> {
+    let (tmp, ws) = captured()?;
+    let root = tmp.path();
+    fs::write(root.join("src/b.txt"), "only on the shelf\n")?;
+    ws.shelve(None, &[])?;
+    let blob = ws.store.put_blob(b"only on the shelf\n")?;
+
+    ws.collect_garbage(false, Duration::ZERO)?;
+    assert!(ws.store.has_blob(&blob));
+    ws.unshelve(None)?;
+    assert_eq!(
+        fs::read_to_string(root.join("src/b.txt"))?,
+        "only on the shelf\n"
+    );
+    Ok(())
+}
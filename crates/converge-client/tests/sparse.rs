@@ -0,0 +1,187 @@
+//! Doc 15 §5b: a sparse workspace holds part of the tree on disk and the
+//! whole tree in every snap.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+
+use converge_client::diff::{EntrySig, tree_from_store};
+use converge_client::workspace::Workspace;
+
+fn on_disk(root: &Path) -> Result<Vec<String>> {
+    let mut out = Vec::new();
+    let mut stack = vec![root.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.file_name().is_some_and(|n| n == ".converge") {
+                continue;
+            }
+            if path.is_dir() {
+                stack.push(path);
+            } else {
+                let relative = path.strip_prefix(root)?;
+                out.push(relative.to_string_lossy().replace('\\', "/"));
+            }
+        }
+    }
+    out.sort();
+    Ok(out)
+}
+
+fn tree(ws: &Workspace, snap_id: &str) -> Result<BTreeMap<String, EntrySig>> {
+    tree_from_store(&ws.store, &ws.show_snap(snap_id)?.root_manifest)
+}
+
+#[test]
+fn sparse_set_narrows_the_disk_not_the_tree() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let root = tmp.path();
+    let ws = Workspace::init(root, false)?;
+    for (path, text) in [
+        ("README", "readme"),
+        ("assets/characters/hero.png", "hero"),
+        ("assets/props/box.png", "box"),
+        ("src/main.rs", "fn main() {}"),
+    ] {
+        fs::create_dir_all(root.join(path).parent().unwrap())?;
+        fs::write(root.join(path), text)?;
+    }
+    let full = ws.create_snap(None)?;
+
+    assert_eq!(
+        ws.sparse_add(&["/assets/characters/".to_string()], false)?,
+        vec!["assets/characters".to_string()]
+    );
+    assert_eq!(on_disk(root)?, vec!["assets/characters/hero.png"]);
+    // Nothing off disk reads as deleted.
+    assert_eq!(ws.current_manifest_tree()?.0, full.root_manifest);
+    assert_eq!(ws.create_snap(None)?.id, full.id);
+
+    // An edit inside the set is captured beside everything outside it;
+    // a file created outside the set is not read.
+    fs::write(root.join("assets/characters/hero.png"), "hero v2")?;
+    fs::write(root.join("stray.txt"), "stray")?;
+    let edited = ws.create_snap(None)?;
+    let before = tree(&ws, &full.id)?;
+    let after = tree(&ws, &edited.id)?;
+    assert_eq!(
+        before.keys().collect::<Vec<_>>(),
+        after.keys().collect::<Vec<_>>()
+    );
+    for path in ["README", "assets/props/box.png", "src/main.rs"] {
+        assert_eq!(before[path], after[path], "{path}");
+    }
+    assert_ne!(
+        before["assets/characters/hero.png"],
+        after["assets/characters/hero.png"]
+    );
+    assert_eq!(edited.stats.files, 4);
+
+    // Restore honours the set too.
+    ws.restore_snap(&full.id, true)?;
+    assert_eq!(on_disk(root)?, vec!["assets/characters/hero.png"]);
+    assert_eq!(
+        fs::read_to_string(root.join("assets/characters/hero.png"))?,
+        "hero"
+    );
+
+    // Globs select at any depth, and removing the last pattern brings
+    // the whole tree back.
+    ws.sparse_add(&["**/*.rs".to_string()], false)?;
+    assert_eq!(
+        on_disk(root)?,
+        vec!["assets/characters/hero.png", "src/main.rs"]
+    );
+    fs::write(root.join("src/main.rs"), "edited")?;
+    let err = ws
+        .sparse_remove(&["**/*.rs".to_string()], false)
+        .unwrap_err();
+    assert!(format!("{err:#}").contains("changes since"), "{err:#}");
+    fs::write(root.join("src/main.rs"), "fn main() {}")?;
+    assert!(
+        ws.sparse_remove(
+            &["**/*.rs".to_string(), "assets/characters".to_string()],
+            false
+        )?
+        .is_empty()
+    );
+    assert_eq!(
+        on_disk(root)?,
+        vec![
+            "README",
+            "assets/characters/hero.png",
+            "assets/props/box.png",
+            "src/main.rs"
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn a_glob_only_reaches_as_deep_as_its_segments() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let root = tmp.path();
+    let ws = Workspace::init(root, false)?;
+    for path in [
+        "assets/top.png",
+        "assets/a/b/c/deep.png",
+        "assets/a/mid.png",
+    ] {
+        fs::create_dir_all(root.join(path).parent().unwrap())?;
+        fs::write(root.join(path), path)?;
+    }
+    ws.create_snap(None)?;
+
+    ws.sparse_add(&["assets/*.png".to_string()], false)?;
+    assert_eq!(on_disk(root)?, vec!["assets/top.png"]);
+    // No empty directories where nothing could match.
+    assert!(!root.join("assets/a").exists());
+
+    ws.sparse_add(&["assets/*/*.png".to_string()], false)?;
+    assert_eq!(on_disk(root)?, vec!["assets/a/mid.png", "assets/top.png"]);
+    assert!(!root.join("assets/a/b").exists());
+
+    // `**` still reaches any depth.
+    ws.sparse_add(&["assets/**/deep.png".to_string()], false)?;
+    assert_eq!(
+        on_disk(root)?,
+        vec![
+            "assets/a/b/c/deep.png",
+            "assets/a/mid.png",
+            "assets/top.png"
+        ]
+    );
+    Ok(())
+}
+
+/// The watch's narrow rescan (§5a) carries the same paths the full scan
+/// does.
+#[cfg(target_os = "linux")]
+#[test]
+fn watch_rescan_carries_what_the_sparse_set_leaves_out() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let root = tmp.path();
+    let ws = Workspace::init(root, false)?;
+    for path in ["a/in/x.txt", "a/out/y.txt", "b/z.txt"] {
+        fs::create_dir_all(root.join(path).parent().unwrap())?;
+        fs::write(root.join(path), path)?;
+    }
+    ws.create_snap(None)?;
+    ws.sparse_add(&["a/in".to_string()], false)?;
+
+    let mut watcher = ws.watch_tree()?;
+    let quiet = std::time::Duration::from_millis(50);
+    ws.create_snap_changed(&watcher.next_quiet(quiet)?, "automatic")?;
+    fs::write(root.join("a/in/x.txt"), "edited")?;
+    fs::write(root.join("a/in/new.txt"), "new")?;
+    let snap = ws.create_snap_changed(&watcher.next_quiet(quiet)?, "automatic")?;
+    assert_eq!(snap.root_manifest, ws.current_manifest_tree()?.0);
+    let paths: Vec<String> = tree(&ws, &snap.id)?.into_keys().collect();
+    for path in ["a/in/new.txt", "a/out/y.txt", "b/z.txt"] {
+        assert!(paths.contains(&path.to_string()), "{path}: {paths:?}");
+    }
+    Ok(())
+}
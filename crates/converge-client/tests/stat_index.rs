@@ -0,0 +1,109 @@
+//! Doc 15 §5c: the stat index lets a scan in a new process skip files
+//! whose stat has not moved, and `doctor` can tell when it lies.
+
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use anyhow::Result;
+
+use converge_client::model::ManifestEntryKind;
+use converge_client::workspace::Workspace;
+
+fn blob_at(ws: &Workspace, name: &str) -> Result<ManifestEntryKind> {
+    let (root, manifests, _) = ws.current_manifest_tree()?;
+    let entry = manifests[&root]
+        .entries
+        .iter()
+        .find(|e| e.name == name)
+        .expect("entry")
+        .clone();
+    Ok(entry.kind)
+}
+
+/// An hour back, so the index written after it is never racy with it.
+fn age(path: &Path) -> Result<()> {
+    let file = fs::File::options().write(true).open(path)?;
+    file.set_modified(SystemTime::now() - Duration::from_secs(3600))?;
+    Ok(())
+}
+
+/// Swap one raw id for another in the index and seal it again, the way
+/// a bug writing the index would: the checksum passes, the entry lies.
+fn forge(index: &Path, from: &[u8], to: &[u8]) -> Result<()> {
+    let mut bytes = fs::read(index)?;
+    let (from, to) = (blake3::hash(from), blake3::hash(to));
+    let at = bytes
+        .windows(32)
+        .position(|w| w == from.as_bytes())
+        .expect("id in the index");
+    bytes[at..at + 32].copy_from_slice(to.as_bytes());
+    let body = bytes.len() - 32;
+    let sum = blake3::hash(&bytes[..body]);
+    bytes[body..].copy_from_slice(sum.as_bytes());
+    fs::write(index, bytes)?;
+    Ok(())
+}
+
+#[test]
+fn scans_trust_the_index_until_the_stat_moves() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let root = tmp.path();
+    let ws = Workspace::init(root, false)?;
+    let file = root.join("a.txt");
+    fs::write(&file, "one")?;
+    age(&file)?;
+    let index = root.join(".converge/index");
+
+    let first = blob_at(&ws, "a.txt")?;
+    assert!(index.exists());
+    assert_eq!(ws.check_stat_index(true)?.verified, 1);
+
+    // The next scan takes the index's word for it, lie and all...
+    forge(&index, b"one", b"two")?;
+    let ManifestEntryKind::File { blob, .. } = blob_at(&ws, "a.txt")? else {
+        panic!("a file");
+    };
+    assert_eq!(blob.as_str(), blake3::hash(b"two").to_hex().as_str());
+    // ...which only a deep check can see.
+    assert!(ws.check_stat_index(false)?.problem.is_none());
+    let deep = ws.check_stat_index(true)?;
+    assert!(deep.problem.is_some_and(|p| p.contains("a.txt")));
+
+    assert_eq!(ws.rebuild_stat_index()?, 1);
+    assert_eq!(blob_at(&ws, "a.txt")?, first);
+    assert!(ws.check_stat_index(true)?.problem.is_none());
+
+    // A write the stat sees is read again, and a snap stores it.
+    fs::write(&file, "six")?;
+    let snap = ws.create_snap(None)?;
+    let tree = ws.store.get_manifest(&snap.root_manifest)?;
+    let ManifestEntryKind::File { blob, .. } = &tree.entries[0].kind else {
+        panic!("a file");
+    };
+    assert_eq!(ws.store.get_blob(blob)?, b"six");
+    Ok(())
+}
+
+#[test]
+fn a_scribbled_index_is_reported_and_read_past() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let root = tmp.path();
+    let ws = Workspace::init(root, false)?;
+    fs::write(root.join("a.txt"), "one")?;
+    let index = root.join(".converge/index");
+    let first = blob_at(&ws, "a.txt")?;
+
+    let mut bytes = fs::read(&index)?;
+    let middle = bytes.len() / 2;
+    bytes[middle] ^= 0xff;
+    fs::write(&index, bytes)?;
+    let report = ws.check_stat_index(false)?;
+    assert!(report.problem.is_some_and(|p| p.contains("checksum")));
+
+    // A scan does not fail over it: it reads the file and writes a
+    // good index in its place.
+    assert_eq!(blob_at(&ws, "a.txt")?, first);
+    assert!(ws.check_stat_index(false)?.problem.is_none());
+    Ok(())
+}
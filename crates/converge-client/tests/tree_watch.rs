@@ -0,0 +1,87 @@
+//! Doc 15 §5a: the event-driven watch — which directories it reports,
+//! and that a capture rescanning only those equals a full scan.
+#![cfg(target_os = "linux")]
+
+use std::fs;
+use std::time::Duration;
+
+use anyhow::Result;
+
+use converge_client::workspace::{TreeChanges, TreeWatcher, Workspace};
+
+const QUIET: Duration = Duration::from_millis(50);
+
+/// Wait out one quiet period and capture what it reported; the snap
+/// must hold exactly what a full scan of the tree would.
+fn capture(ws: &Workspace, watcher: &mut TreeWatcher) -> Result<TreeChanges> {
+    let changes = watcher.next_quiet(QUIET)?;
+    let snap = ws.create_snap_changed(&changes, "automatic")?;
+    let (root, _, stats) = ws.current_manifest_tree()?;
+    assert_eq!(snap.root_manifest, root);
+    assert_eq!(
+        (snap.stats.files, snap.stats.dirs, snap.stats.bytes),
+        (stats.files, stats.dirs, stats.bytes)
+    );
+    Ok(changes)
+}
+
+#[test]
+fn watch_reports_changed_dirs_and_rescans_only_those() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let root = tmp.path();
+    let ws = Workspace::init(root, false)?;
+    fs::create_dir_all(root.join("a/deep"))?;
+    fs::create_dir_all(root.join("b"))?;
+    fs::create_dir_all(root.join("target/debug"))?;
+    fs::write(root.join("a/deep/x.txt"), "x")?;
+    fs::write(root.join("b/y.txt"), "y")?;
+    fs::write(root.join(".convergeignore"), "target/\n")?;
+
+    let mut watcher = ws.watch_tree()?;
+    // Ignored directories cost no watches.
+    assert_eq!(watcher.watched_dirs(), 4);
+    // The first report is everything: the watch cannot know what moved
+    // before it started.
+    assert!(capture(&ws, &mut watcher)?.is_full());
+
+    fs::write(root.join("a/deep/x.txt"), "x2")?;
+    fs::write(root.join("target/debug/out"), "noise")?;
+    let changes = capture(&ws, &mut watcher)?;
+    assert!(!changes.is_full());
+    assert_eq!(changes.dirs().collect::<Vec<_>>(), vec!["a/deep"]);
+
+    // A new directory is watched as it appears, files and all.
+    fs::create_dir_all(root.join("b/new/nested"))?;
+    fs::write(root.join("b/new/nested/z.txt"), "z")?;
+    capture(&ws, &mut watcher)?;
+    fs::write(root.join("b/new/nested/z.txt"), "z2")?;
+    fs::remove_file(root.join("a/deep/x.txt"))?;
+    let changes = capture(&ws, &mut watcher)?;
+    assert_eq!(
+        changes.dirs().collect::<Vec<_>>(),
+        vec!["a/deep", "b/new/nested"]
+    );
+
+    // An edited ignore file changes what every directory holds.
+    fs::write(root.join(".convergeignore"), "target/\n*.txt\n")?;
+    assert!(capture(&ws, &mut watcher)?.is_full());
+
+    // A directory the ignore file lets go of is watched from then on.
+    let before = watcher.watched_dirs();
+    fs::write(root.join(".convergeignore"), "*.txt\n")?;
+    assert!(capture(&ws, &mut watcher)?.is_full());
+    assert_eq!(watcher.watched_dirs(), before + 2);
+    fs::write(root.join("target/debug/out"), "signal")?;
+    let changes = capture(&ws, &mut watcher)?;
+    assert_eq!(changes.dirs().collect::<Vec<_>>(), vec!["target/debug"]);
+
+    // And one it takes back stops raising events.
+    fs::write(root.join(".convergeignore"), "target/\n")?;
+    assert!(capture(&ws, &mut watcher)?.is_full());
+    assert_eq!(watcher.watched_dirs(), before);
+    fs::write(root.join("target/debug/out"), "noise again")?;
+    fs::write(root.join("b/y.txt"), "y2")?;
+    let changes = capture(&ws, &mut watcher)?;
+    assert_eq!(changes.dirs().collect::<Vec<_>>(), vec!["b"]);
+    Ok(())
+}
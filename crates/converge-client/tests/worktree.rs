@@ -0,0 +1,134 @@
+//! Doc 15 §5f: linked worktrees share their primary's objects and keep
+//! their own head, snaps, state and config.
+
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::Result;
+
+use converge_client::workspace::Workspace;
+
+/// A primary at `<tmp>/main` with `a.txt` captured twice.
+fn primary(tmp: &Path) -> Result<(Workspace, String, String)> {
+    let root = tmp.join("main");
+    fs::create_dir(&root)?;
+    let ws = Workspace::init(&root, false)?;
+    fs::write(root.join("a.txt"), "first")?;
+    let first = ws.create_snap(None)?.id;
+    fs::write(root.join("a.txt"), "second")?;
+    let second = ws.create_snap(None)?.id;
+    Ok((ws, first, second))
+}
+
+fn open(root: &Path) -> Result<Workspace> {
+    Workspace::discover(root)
+}
+
+#[test]
+fn a_worktree_checks_out_head_and_shares_objects() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let (main, first, second) = primary(tmp.path())?;
+    let dir = tmp.path().join("backport");
+
+    let record = main.add_worktree(&dir, Some(&first))?;
+    assert_eq!(record.name, "backport");
+    assert_eq!(fs::read_to_string(dir.join("a.txt"))?, "first");
+    assert!(
+        !dir.join(".converge/objects").exists(),
+        "objects are shared"
+    );
+
+    let wt = open(&dir)?;
+    assert_eq!(wt.store.get_head()?.as_deref(), Some(first.as_str()));
+    // Its own head: capturing here moves neither the primary's head
+    // nor its history.
+    fs::write(dir.join("a.txt"), "backported")?;
+    let fix = wt.create_snap(None)?;
+    assert_eq!(fix.parents, [first]);
+    assert_eq!(main.store.get_head()?.as_deref(), Some(second.as_str()));
+    assert!(!main.store.has_snap(&fix.id));
+    // One store: what the worktree wrote, the primary holds.
+    assert!(main.store.has_manifest(&fix.root_manifest));
+
+    // Without `--snap`, the new worktree starts at the adder's head.
+    wt.add_worktree(&tmp.path().join("experiment"), None)?;
+    let experiment = open(&tmp.path().join("experiment"))?;
+    assert_eq!(experiment.store.get_head()?, Some(fix.id));
+    assert_eq!(
+        experiment.store.primary_dir().map(Path::to_path_buf),
+        Some(main.root.canonicalize()?.join(".converge"))
+    );
+    assert_eq!(main.worktrees()?.len(), 2);
+    Ok(())
+}
+
+#[test]
+fn a_worktree_goes_in_an_empty_directory_outside_any_workspace() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let (main, _, _) = primary(tmp.path())?;
+    let taken = tmp.path().join("taken");
+    fs::create_dir(&taken)?;
+    fs::write(taken.join("file"), "x")?;
+    assert!(main.add_worktree(&taken, None).is_err());
+    assert!(main.add_worktree(&main.root.join("nested"), None).is_err());
+    assert!(!main.root.join("nested").exists());
+    assert!(main.worktrees()?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn gc_marks_from_every_linked_worktree() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let (main, _, _) = primary(tmp.path())?;
+    let dir = tmp.path().join("wt");
+    main.add_worktree(&dir, None)?;
+    let wt = open(&dir)?;
+    fs::write(dir.join("only-here.txt"), "only in the worktree")?;
+    wt.create_snap(None)?;
+    let blob = main.store.put_blob(b"only in the worktree")?;
+
+    main.collect_garbage(false, Duration::ZERO)?;
+    assert!(main.store.has_blob(&blob), "the primary's gc swept it");
+    wt.collect_garbage(false, Duration::ZERO)?;
+    // And the worktree's gc kept the primary's head.
+    fs::remove_file(main.root.join("a.txt"))?;
+    main.restore_snap(&main.store.get_head()?.expect("head"), true)?;
+    assert_eq!(fs::read_to_string(main.root.join("a.txt"))?, "second");
+    assert!(wt.store.has_blob(&blob));
+    Ok(())
+}
+
+#[test]
+fn a_broken_link_stops_gc_until_it_is_pruned_or_repaired() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let (main, _, _) = primary(tmp.path())?;
+    let gone = tmp.path().join("gone");
+    main.add_worktree(&gone, None)?;
+    fs::remove_dir_all(&gone)?;
+
+    let listed = main.worktrees()?;
+    assert_eq!(listed[0].problem.as_deref(), Some("is gone"));
+    let err = main
+        .collect_garbage(true, Duration::ZERO)
+        .expect_err("gc cannot count a missing worktree's roots");
+    assert!(format!("{err:#}").contains("worktree prune"), "{err:#}");
+    assert_eq!(main.prune_worktrees()?.len(), 1);
+    main.collect_garbage(true, Duration::ZERO)?;
+
+    // The other way round: a worktree its primary no longer lists.
+    let moved = tmp.path().join("moved");
+    main.add_worktree(&moved, None)?;
+    main.store.delete_worktree("moved")?;
+    let wt = open(&moved)?;
+    assert!(wt.worktree_link_problem()?.is_some());
+    wt.repair_worktree()?;
+    assert!(wt.worktree_link_problem()?.is_none());
+    assert!(main.worktrees()?[0].problem.is_none());
+
+    // And a worktree whose primary is gone will not open at all.
+    fs::remove_dir_all(&main.root)?;
+    let err = open(&moved).err().expect("an orphaned worktree opened");
+    assert!(format!("{err:#}").contains("which is gone"), "{err:#}");
+    Ok(())
+}
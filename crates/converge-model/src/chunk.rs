@@ -1,3 +1,6 @@
+use std::io::Read;
+
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
 use crate::ids::ObjectId;
@@ -50,3 +53,36 @@ pub fn chunk_data(data: &[u8], params: ChunkParams) -> (FileRecipe, Vec<(ObjectI
     };
     (recipe, blobs)
 }
+
+/// `chunk_data` over a reader, for files too big to hold: cut points are
+/// FastCDC's over the same bytes, so the recipe is the same one. At most
+/// `max_size` bytes of `source` are buffered; `on_chunk` sees each chunk
+/// as it is cut and may store it, and nothing keeps it afterwards.
+pub fn chunk_stream<R: Read>(
+    source: R,
+    params: ChunkParams,
+    mut on_chunk: impl FnMut(&ObjectId, &[u8]) -> Result<()>,
+) -> Result<FileRecipe> {
+    let chunker =
+        fastcdc::v2020::StreamCDC::new(source, params.min_size, params.avg_size, params.max_size);
+
+    let mut chunks = Vec::new();
+    let mut size = 0u64;
+    for chunk in chunker {
+        let chunk = chunk.context("read for chunking")?;
+        let id = ObjectId(blake3::hash(&chunk.data).to_hex().to_string());
+        on_chunk(&id, &chunk.data)?;
+        chunks.push(FileRecipeChunk {
+            blob: id,
+            size: chunk.length as u32,
+        });
+        size += chunk.length as u64;
+    }
+
+    Ok(FileRecipe {
+        version: RECIPE_VERSION_CDC,
+        size,
+        params: Some(params),
+        chunks,
+    })
+}
@@ -0,0 +1,141 @@
+//! Object encoding (arch doc 16 §1f): zstd over an object's bytes, at
+//! rest and on the wire.
+//!
+//! An encoding is how bytes are kept or carried, never what they are. An
+//! object's id stays the hash of its plain bytes, so the same content
+//! dedupes whether one copy was stored compressed and another was not,
+//! and every reader verifies after decoding, never before.
+
+use std::io::Read;
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+
+/// An encoding a store or a frame may hold an object in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ObjectEncoding {
+    Zstd,
+}
+
+impl ObjectEncoding {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ObjectEncoding::Zstd => "zstd",
+        }
+    }
+
+    /// The name a peer advertises this encoding under. Unknown names are
+    /// skipped rather than refused, so a newer peer can offer more.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "zstd" => Some(ObjectEncoding::Zstd),
+            _ => None,
+        }
+    }
+
+    /// Suffix on the file or key of an object stored in this encoding.
+    /// The plain name stays the plain object, so a store can hold both
+    /// forms and a reader never has to sniff content to tell them apart.
+    pub fn extension(self) -> &'static str {
+        match self {
+            ObjectEncoding::Zstd => "zst",
+        }
+    }
+}
+
+/// Fast enough that encoding on the way out of a batch is not the
+/// bottleneck; the higher levels buy little on text.
+const ZSTD_LEVEL: i32 = 3;
+
+/// Below this a zstd frame's own header eats most of what it could save.
+const MIN_ENCODED_INPUT: usize = 512;
+
+/// `bytes` zstd-encoded, or `None` when that would not save at least an
+/// eighth. Already-compressed content (textures, audio, archives) stays
+/// plain: decoding it on every read would cost time and save nothing.
+pub fn encode(bytes: &[u8]) -> Option<Vec<u8>> {
+    if bytes.len() < MIN_ENCODED_INPUT {
+        return None;
+    }
+    let encoded = zstd::bulk::compress(bytes, ZSTD_LEVEL).ok()?;
+    (encoded.len() <= bytes.len() - bytes.len() / 8).then_some(encoded)
+}
+
+/// Decode `bytes`, refusing to produce more than `limit` bytes.
+///
+/// The limit is what keeps a small frame from expanding into all of a
+/// server's memory: decoding streams, so a frame that lies about its
+/// size is stopped at the limit rather than trusted.
+pub fn decode(encoding: ObjectEncoding, bytes: &[u8], limit: u64) -> Result<Vec<u8>> {
+    match encoding {
+        ObjectEncoding::Zstd => {
+            let decoder = zstd::stream::read::Decoder::new(bytes).context("open zstd frame")?;
+            let mut out = Vec::new();
+            decoder
+                .take(limit.saturating_add(1))
+                .read_to_end(&mut out)
+                .context("decode zstd frame")?;
+            if out.len() as u64 > limit {
+                bail!("zstd frame decodes past the {limit}-byte limit");
+            }
+            Ok(out)
+        }
+    }
+}
+
+/// The plain size an encoded object declares, read from its header
+/// without decoding. `None` if the header does not say.
+pub fn logical_len(encoding: ObjectEncoding, bytes: &[u8]) -> Option<u64> {
+    match encoding {
+        ObjectEncoding::Zstd => zstd::zstd_safe::get_frame_content_size(bytes).ok()?,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_round_trips_and_declares_its_size() {
+        let text = "fn main() { println!(\"hello\"); }\n".repeat(200);
+        let encoded = encode(text.as_bytes()).expect("text compresses");
+        assert!(encoded.len() < text.len() / 4);
+        assert_eq!(
+            logical_len(ObjectEncoding::Zstd, &encoded),
+            Some(text.len() as u64)
+        );
+        let decoded = decode(ObjectEncoding::Zstd, &encoded, u64::MAX).expect("decode");
+        assert_eq!(decoded, text.as_bytes());
+    }
+
+    #[test]
+    fn incompressible_and_tiny_inputs_stay_plain() {
+        let mut seed = 0x9e37_79b9_7f4a_7c15u64;
+        let noise: Vec<u8> = (0..64 * 1024)
+            .map(|_| {
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+                seed as u8
+            })
+            .collect();
+        assert!(encode(&noise).is_none());
+        assert!(encode(b"short").is_none());
+    }
+
+    /// A frame that expands past the limit is refused, not allocated.
+    #[test]
+    fn decoding_stops_at_the_limit() {
+        let zeros = vec![0u8; 1024 * 1024];
+        let encoded = encode(&zeros).expect("zeros compress");
+        let err = decode(ObjectEncoding::Zstd, &encoded, 4096).expect_err("over the limit");
+        assert!(format!("{err:#}").contains("4096-byte limit"), "{err:#}");
+    }
+
+    #[test]
+    fn unknown_encodings_are_skipped_by_name() {
+        assert_eq!(ObjectEncoding::parse("zstd"), Some(ObjectEncoding::Zstd));
+        assert_eq!(ObjectEncoding::parse("brotli"), None);
+    }
+}
@@ -15,6 +15,36 @@ pub struct WorkspaceConfig {
 
     #[serde(default)]
     pub workflow_profile: WorkflowProfile,
+
+    /// Who snaps in this workspace are captured as. Set by `converge
+    /// login`, which asks the server whose token it was handed; absent
+    /// until then, and snaps captured meanwhile carry no author.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<AuthorConfig>,
+
+    /// Store blobs and manifests zstd-encoded where that saves space
+    /// (doc 16 §1f). Ids do not change, and objects already stored stay
+    /// as they are; takes effect the next time the workspace is opened.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub compress_objects: bool,
+
+    /// Sparse patterns (doc 15 §5b): when non-empty, only paths they
+    /// select are written to disk; the rest of the tree rides along from
+    /// head untouched. Empty means the whole tree.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sparse: Vec<String>,
+
+    /// Worker threads a scan reads, hashes and chunks files on (doc 15
+    /// §5d). Absent means one per core; `1` scans on the calling thread.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scan_threads: Option<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuthorConfig {
+    /// The subject the server resolves this workspace's token to.
+    pub subject: String,
+    pub display_name: String,
 }
 
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
@@ -47,6 +77,57 @@ impl WorkflowProfile {
         }
     }
 
+    /// Chunking rules this profile brings (doc 16 §2), consulted after a
+    /// workspace's own. They only ever name file types whose shape the
+    /// profile knows; everything else falls through to the defaults.
+    pub fn chunking_rules(self) -> Vec<ChunkingRule> {
+        const KIB: u32 = 1024;
+        const MIB: u32 = 1024 * KIB;
+        // Takes of the same part share long runs of identical audio;
+        // small chunks are what let one take dedupe against the next.
+        let stems = crate::ChunkParams {
+            min_size: 64 * KIB,
+            avg_size: 256 * KIB,
+            max_size: MIB,
+        };
+        // Packed textures and archives run to gigabytes; at the default
+        // size that is thousands of recipe entries for little extra
+        // dedupe. This is as large as FastCDC goes.
+        let packed = crate::ChunkParams {
+            min_size: MIB,
+            avg_size: 4 * MIB,
+            max_size: 16 * MIB,
+        };
+        let audio = ["*.wav", "*.aif", "*.aiff", "*.flac"];
+        match self {
+            WorkflowProfile::Software => Vec::new(),
+            WorkflowProfile::Daw => audio
+                .iter()
+                .map(|p| ChunkingRule::chunk(p, stems, Some(u64::from(MIB))))
+                .collect(),
+            WorkflowProfile::GameAssets => {
+                // Small text the engine rewrites wholesale: a chunk
+                // boundary inside one buys nothing.
+                let mut rules: Vec<ChunkingRule> = ["*.json", "*.meta", "*.yaml", "*.yml"]
+                    .iter()
+                    .map(|p| ChunkingRule::never(p))
+                    .collect();
+                rules.extend(
+                    ["*.pak", "*.dds", "*.ktx2", "*.basis", "*.uasset", "*.umap"]
+                        .iter()
+                        .map(|p| ChunkingRule::chunk(p, packed, None)),
+                );
+                rules.extend(
+                    audio
+                        .iter()
+                        .chain(&["*.ogg"])
+                        .map(|p| ChunkingRule::chunk(p, stems, Some(u64::from(MIB)))),
+                );
+                rules
+            }
+        }
+    }
+
     pub fn flow_hint(self) -> &'static str {
         match self {
             WorkflowProfile::Software => "flow: publish -> candidate -> promote -> release",
@@ -63,9 +144,64 @@ impl WorkflowProfile {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ChunkingConfig {
     /// Chunk size in bytes.
+    #[serde(default = "default_chunk_size")]
     pub chunk_size: u64,
     /// Chunking threshold in bytes. Files with size >= threshold are chunked.
+    #[serde(default = "default_chunk_threshold")]
     pub threshold: u64,
+    /// Per-path overrides, first match wins (doc 16 §2). Consulted
+    /// before the workflow profile's own rules, which come before the
+    /// two fields above.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<ChunkingRule>,
+}
+
+fn default_chunk_size() -> u64 {
+    1024 * 1024
+}
+
+fn default_chunk_threshold() -> u64 {
+    8 * 1024 * 1024
+}
+
+/// How files whose path matches `pattern` are stored.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkingRule {
+    /// A glob over the workspace-relative path, `/`-separated. A pattern
+    /// with no `/` matches the file name at any depth, as ignore rules do.
+    pub pattern: String,
+    #[serde(flatten)]
+    pub action: ChunkingAction,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "kebab-case")]
+pub enum ChunkingAction {
+    /// Always one blob, whatever the size.
+    Never,
+    /// Content-defined chunks with these parameters, for files of at
+    /// least `threshold` bytes (the workspace threshold when absent).
+    Chunk {
+        params: crate::ChunkParams,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        threshold: Option<u64>,
+    },
+}
+
+impl ChunkingRule {
+    pub fn never(pattern: &str) -> Self {
+        Self {
+            pattern: pattern.to_string(),
+            action: ChunkingAction::Never,
+        }
+    }
+
+    pub fn chunk(pattern: &str, params: crate::ChunkParams, threshold: Option<u64>) -> Self {
+        Self {
+            pattern: pattern.to_string(),
+            action: ChunkingAction::Chunk { params, threshold },
+        }
+    }
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
@@ -127,3 +263,14 @@ pub struct LaneSyncRecord {
     pub snap_id: String,
     pub synced_at: String,
 }
+
+/// A linked worktree, as its primary workspace lists it (arch doc 15
+/// §5f). The worktree shares the primary's objects and keeps its own
+/// head, snaps, state and config.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WorktreeRecord {
+    pub name: String,
+    /// The worktree's root directory, absolute.
+    pub path: String,
+    pub created_at: String,
+}
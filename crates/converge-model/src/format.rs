@@ -58,19 +58,37 @@ impl StoreKind {
     /// Bump when a change would make an older binary *misread* a newer
     /// store, or the reverse. Adding a file nobody older looks for is
     /// not a bump; changing what an existing file means is.
+    ///
+    /// 2: authored snaps are identified under `converge-snap-v5`, which
+    /// covers the author (doc 17 §1). A version-1 binary would recompute
+    /// every authored record's id wrongly; anonymous ones keep `v4`.
+    ///
+    /// 3: objects may be stored zstd-encoded under `<id>.zst` (doc 16
+    /// §1f). A version-2 binary would take every such object for missing.
+    ///
+    /// Workspace 4: objects may live in packs (doc 16 §1h). A version-3
+    /// binary would take every packed object for missing.
     pub fn current(&self) -> u32 {
         match self {
-            StoreKind::Workspace => 1,
-            StoreKind::Server => 1,
+            StoreKind::Workspace => 4,
+            StoreKind::Server => 3,
         }
     }
 
-    fn what(&self) -> &'static str {
+    pub(crate) fn what(&self) -> &'static str {
         match self {
             StoreKind::Workspace => "workspace",
             StoreKind::Server => "server data directory",
         }
     }
+
+    /// The command that migrates this kind of store (doc 16 §3).
+    pub fn migrate_command(&self) -> &'static str {
+        match self {
+            StoreKind::Workspace => "converge migrate",
+            StoreKind::Server => "converge-server --data-dir <DIR> --migrate",
+        }
+    }
 }
 
 /// Read a store's format version. Absent means 1.
@@ -100,9 +118,18 @@ pub fn read_version(store_root: &Path, kind: StoreKind) -> Result<u32> {
 
 /// Write the current stamp. Called at init, never on open.
 pub fn write_version(store_root: &Path, kind: StoreKind) -> Result<()> {
+    write_version_at(store_root, kind, kind.current())
+}
+
+/// Write a specific stamp — what a migration step leaves behind it.
+/// Written to a temporary name and renamed, so an interrupted write
+/// cannot leave a stamp that reads as no version at all.
+pub(crate) fn write_version_at(store_root: &Path, kind: StoreKind, version: u32) -> Result<()> {
     let path = store_root.join(FORMAT_FILE);
-    std::fs::write(&path, format!("{}-{}\n", kind.tag(), kind.current()))
-        .with_context(|| format!("write {}", path.display()))
+    let tmp = store_root.join(format!("{FORMAT_FILE}.tmp"));
+    std::fs::write(&tmp, format!("{}-{version}\n", kind.tag()))
+        .with_context(|| format!("write {}", tmp.display()))?;
+    std::fs::rename(&tmp, &path).with_context(|| format!("write {}", path.display()))
 }
 
 /// Refuse a store this binary cannot correctly read or write.
@@ -127,15 +154,27 @@ pub fn check_compatible(store_root: &Path, kind: StoreKind) -> Result<()> {
             kind.what()
         );
     }
+    // Said before the generic advice: rerunning the migration is the
+    // only thing to do with a store stopped halfway, and "older" alone
+    // would send someone looking for the build that wrote it.
+    if crate::migrate::read_journal(store_root)?.is_some() {
+        bail!(
+            "this {} is format {found}, partway through a migration to {current} \n\
+             that was interrupted.\n\
+             Run `{}` to finish it.\n\
+             Nothing has been read or written.",
+            kind.what(),
+            kind.migrate_command()
+        );
+    }
     bail!(
         "this {} is format {found}, and this build of Convergence reads {current}.\n\
          It was written by an older version and cannot be read safely — the risk is \n\
          a silent misread, not a crash.\n\
-         Use a Convergence that reads format {found}, or start a new {} and \n\
-         re-publish into it.\n\
+         Run `{}` to bring it forward (add --dry-run to see the steps first).\n\
          Nothing has been read or written.",
         kind.what(),
-        kind.what()
+        kind.migrate_command()
     );
 }
 
@@ -144,7 +183,8 @@ mod tests {
     use super::*;
 
     /// A store written before the stamp existed reads as version 1 and
-    /// is not touched — opening a store must stay a pure read.
+    /// is not touched — opening a store must stay a pure read, and that
+    /// holds for the refusal too.
     #[test]
     fn an_unstamped_store_is_version_one_and_stays_unstamped() {
         let dir = tempfile::tempdir().expect("tempdir");
@@ -152,7 +192,8 @@ mod tests {
             read_version(dir.path(), StoreKind::Workspace).expect("read"),
             1
         );
-        check_compatible(dir.path(), StoreKind::Workspace).expect("compatible");
+        let err = check_compatible(dir.path(), StoreKind::Workspace).expect_err("refused");
+        assert!(format!("{err}").contains("format 1"), "{err}");
         assert!(
             !dir.path().join(FORMAT_FILE).exists(),
             "checking compatibility wrote to the store"
@@ -206,8 +247,9 @@ mod tests {
         write_version(dir.path(), StoreKind::Workspace).expect("write");
         let err = check_compatible(dir.path(), StoreKind::Server).expect_err("refused");
         let message = format!("{err}");
+        let stamped = format!("converge-workspace-{}", StoreKind::Workspace.current());
         assert!(
-            message.contains("converge-workspace-1") && message.contains("converge-server"),
+            message.contains(&stamped) && message.contains("converge-server"),
             "name both what it is and what was expected: {message}"
         );
     }
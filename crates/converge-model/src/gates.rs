@@ -19,17 +19,25 @@
 
 use std::collections::{BTreeMap, BTreeSet};
 
+use anyhow::{Context, Result, bail};
+use globset::{GlobBuilder, GlobMatcher};
 use serde::{Deserialize, Serialize};
 
-use crate::{GateGraph, GateNode};
+use crate::{GateGraph, GateNode, StrategyRule};
 
-/// Coalesce strategies the merge engine implements (doc 17 §4).
+/// Coalesce strategies the merge engine implements (doc 17 §4, §4b-d).
 ///
 /// Listed here rather than in the server so a graph can be checked
 /// before it is sent. A gate naming a strategy nothing implements would
 /// otherwise be accepted and then fail at merge time, on data already
 /// committed.
-pub const STRATEGIES: &[&str] = &["whole-file", "text-line-merge"];
+pub const STRATEGIES: &[&str] = &[
+    "whole-file",
+    "text-line-merge",
+    "structured-merge",
+    "union-lines",
+    "lane-priority",
+];
 
 /// Why a graph was refused.
 ///
@@ -60,6 +68,22 @@ pub enum GraphFault {
         gate_id: String,
         strategy: String,
     },
+    /// A per-path strategy rule whose pattern does not compile.
+    BadStrategyPattern {
+        gate_id: String,
+        pattern: String,
+        reason: String,
+    },
+    /// A gate that uses `lane-priority`, as its fallback or in a rule,
+    /// but ranks no lanes: every conflict would tie.
+    MissingLanePriority {
+        gate_id: String,
+    },
+    /// A lane ranked twice; the second place would mean nothing.
+    DuplicatePriorityLane {
+        gate_id: String,
+        lane_id: String,
+    },
     /// A gate that may release but that no publication can ever reach.
     UnreachableRelease {
         gate_id: String,
@@ -97,6 +121,22 @@ impl std::fmt::Display for GraphFault {
                 "gate {gate_id} uses strategy {strategy}, which is not one of: {}",
                 STRATEGIES.join(", ")
             ),
+            Self::BadStrategyPattern {
+                gate_id,
+                pattern,
+                reason,
+            } => write!(
+                f,
+                "gate {gate_id} has a strategy rule for {pattern:?}, which is not a valid pattern: {reason}"
+            ),
+            Self::MissingLanePriority { gate_id } => write!(
+                f,
+                "gate {gate_id} uses lane-priority but ranks no lanes, \
+                 so every conflict would tie"
+            ),
+            Self::DuplicatePriorityLane { gate_id, lane_id } => {
+                write!(f, "gate {gate_id} ranks lane {lane_id} more than once")
+            }
             Self::UnreachableRelease { gate_id } => write!(
                 f,
                 "gate {gate_id} may release but nothing can reach it from an entry gate"
@@ -133,6 +173,40 @@ pub fn validate(graph: &GateGraph) -> Vec<GraphFault> {
                 strategy: gate.strategy.clone(),
             });
         }
+        for rule in &gate.strategy_rules {
+            if !STRATEGIES.contains(&rule.strategy.as_str()) {
+                faults.push(GraphFault::UnknownStrategy {
+                    gate_id: gate.gate_id.clone(),
+                    strategy: rule.strategy.clone(),
+                });
+            }
+            if let Err(err) = compile_rule(rule) {
+                faults.push(GraphFault::BadStrategyPattern {
+                    gate_id: gate.gate_id.clone(),
+                    pattern: rule.pattern.clone(),
+                    reason: format!("{err:#}"),
+                });
+            }
+        }
+        let uses_priority = gate.strategy == "lane-priority"
+            || gate
+                .strategy_rules
+                .iter()
+                .any(|r| r.strategy == "lane-priority");
+        if uses_priority && gate.lane_priority.is_empty() {
+            faults.push(GraphFault::MissingLanePriority {
+                gate_id: gate.gate_id.clone(),
+            });
+        }
+        let mut ranked = BTreeSet::new();
+        for lane in &gate.lane_priority {
+            if !ranked.insert(lane.as_str()) {
+                faults.push(GraphFault::DuplicatePriorityLane {
+                    gate_id: gate.gate_id.clone(),
+                    lane_id: lane.clone(),
+                });
+            }
+        }
     }
 
     for gate in &graph.gates {
@@ -288,6 +362,101 @@ fn reachable_from_entries(graph: &GateGraph) -> BTreeSet<&str> {
     }
 }
 
+/// A gate's strategy choice per path (doc 17 §4a): its rules in order,
+/// then its fallback.
+///
+/// One gate usually wants more than one answer. Line-merging source is
+/// the point of `text-line-merge`; line-merging `Cargo.lock` or a
+/// minified bundle produces a file that parses as nothing, silently,
+/// where `whole-file` would have superposed it for a person to pick.
+#[derive(Debug, Clone)]
+pub struct StrategyPolicy {
+    fallback: String,
+    rules: Vec<(CompiledPattern, String)>,
+    lane_priority: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+struct CompiledPattern {
+    matcher: GlobMatcher,
+    /// No `/` in the pattern: match the file name at any depth, the
+    /// way `.gitignore` and the chunking rules read a bare pattern.
+    by_name: bool,
+}
+
+impl StrategyPolicy {
+    /// Every path gets `strategy`.
+    pub fn uniform(strategy: &str) -> Self {
+        Self {
+            fallback: strategy.to_string(),
+            rules: Vec::new(),
+            lane_priority: Vec::new(),
+        }
+    }
+
+    /// Compile `rules` in order. `validate` refuses a graph whose rules
+    /// would fail here, so for a stored graph this only fails on data
+    /// written by something that skipped validation.
+    pub fn new(fallback: &str, rules: &[StrategyRule]) -> Result<Self> {
+        let rules = rules
+            .iter()
+            .map(|rule| Ok((compile_rule(rule)?, rule.strategy.clone())))
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            fallback: fallback.to_string(),
+            rules,
+            lane_priority: Vec::new(),
+        })
+    }
+
+    /// Rank lanes for `lane-priority`, highest first.
+    pub fn with_lane_priority(mut self, lanes: &[String]) -> Self {
+        self.lane_priority = lanes.to_vec();
+        self
+    }
+
+    /// The policy a gate declares.
+    pub fn for_gate(gate: &GateNode) -> Result<Self> {
+        Ok(Self::new(&gate.strategy, &gate.strategy_rules)
+            .with_context(|| format!("gate {}", gate.gate_id))?
+            .with_lane_priority(&gate.lane_priority))
+    }
+
+    /// The strategy for `path`, a `/`-separated path from the repo root.
+    pub fn strategy_for(&self, path: &str) -> &str {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        self.rules
+            .iter()
+            .find(|(pattern, _)| {
+                pattern
+                    .matcher
+                    .is_match(if pattern.by_name { name } else { path })
+            })
+            .map(|(_, strategy)| strategy.as_str())
+            .unwrap_or(&self.fallback)
+    }
+
+    /// Where `lane` stands in the ranking, 0 highest; `None` if unranked.
+    pub fn lane_rank(&self, lane: &str) -> Option<usize> {
+        self.lane_priority.iter().position(|l| l == lane)
+    }
+}
+
+fn compile_rule(rule: &StrategyRule) -> Result<CompiledPattern> {
+    let pattern = rule.pattern.trim_start_matches('/');
+    if pattern.is_empty() {
+        bail!("empty pattern");
+    }
+    let matcher = GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .build()?
+        .compile_matcher();
+    Ok(CompiledPattern {
+        matcher,
+        by_name: !rule.pattern.contains('/'),
+    })
+}
+
 /// What lives in a gate, and would therefore be stranded by removing it.
 #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct GateOccupancy {
@@ -385,6 +554,8 @@ pub fn impact_of(
             ));
         } else if old_gate.required_approvals != new_gate.required_approvals
             || old_gate.strategy != new_gate.strategy
+            || old_gate.strategy_rules != new_gate.strategy_rules
+            || old_gate.lane_priority != new_gate.lane_priority
             || old_gate.may_release != new_gate.may_release
             || old_gate.name != new_gate.name
         {
@@ -422,6 +593,8 @@ mod tests {
             required_approvals: 0,
             strategy: "whole-file".into(),
             may_release: false,
+            strategy_rules: vec![],
+            lane_priority: vec![],
         }
     }
 
@@ -626,4 +799,96 @@ mod tests {
         // even when the gate is full.
         assert!(!impact.strands_work());
     }
+
+    fn rule(pattern: &str, strategy: &str) -> StrategyRule {
+        StrategyRule {
+            pattern: pattern.into(),
+            strategy: strategy.into(),
+        }
+    }
+
+    #[test]
+    fn strategy_rules_are_checked_like_the_fallback() {
+        let mut intake = gate("intake", &[]);
+        intake.strategy_rules = vec![
+            rule("*.rs", "text-line-merge"),
+            rule("*.lock", "line-soup"),
+            rule("assets/[", "whole-file"),
+        ];
+        let faults = validate(&graph(vec![intake]));
+        assert_eq!(faults.len(), 2, "{faults:?}");
+        assert_eq!(
+            faults[0],
+            GraphFault::UnknownStrategy {
+                gate_id: "intake".into(),
+                strategy: "line-soup".into(),
+            }
+        );
+        assert!(matches!(
+            &faults[1],
+            GraphFault::BadStrategyPattern { pattern, .. } if pattern == "assets/["
+        ));
+    }
+
+    #[test]
+    fn the_first_matching_rule_picks_the_strategy() {
+        let policy = StrategyPolicy::new(
+            "text-line-merge",
+            &[
+                rule("Cargo.lock", "whole-file"),
+                rule("web/dist/**", "whole-file"),
+                rule("/notes/*.md", "whole-file"),
+            ],
+        )
+        .expect("compiles");
+        // A bare name matches at any depth.
+        assert_eq!(policy.strategy_for("Cargo.lock"), "whole-file");
+        assert_eq!(policy.strategy_for("tools/x/Cargo.lock"), "whole-file");
+        // A pattern with a `/` is anchored at the root.
+        assert_eq!(policy.strategy_for("web/dist/app/main.js"), "whole-file");
+        assert_eq!(
+            policy.strategy_for("src/web/dist/main.js"),
+            "text-line-merge"
+        );
+        assert_eq!(policy.strategy_for("notes/today.md"), "whole-file");
+        assert_eq!(policy.strategy_for("notes/old/today.md"), "text-line-merge");
+        assert_eq!(policy.strategy_for("src/main.rs"), "text-line-merge");
+    }
+
+    #[test]
+    fn changing_only_the_rules_retunes_the_gate() {
+        let before = graph(vec![gate("intake", &[])]);
+        let mut tuned = gate("intake", &[]);
+        tuned.strategy_rules = vec![rule("*.lock", "whole-file")];
+        let impact = impact_of(&before, &graph(vec![tuned]), &[]);
+        assert_eq!(impact.retuned, vec!["intake".to_string()]);
+    }
+
+    #[test]
+    fn lane_priority_needs_a_ranking_without_repeats() {
+        let mut by_rule = gate("intake", &[]);
+        by_rule.strategy_rules = vec![rule("art/**", "lane-priority")];
+        let mut repeated = gate("review", &["intake"]);
+        repeated.strategy = "lane-priority".into();
+        repeated.lane_priority = vec!["director".into(), "contractor".into(), "director".into()];
+        let faults = validate(&graph(vec![by_rule, repeated]));
+        assert_eq!(
+            faults,
+            vec![
+                GraphFault::MissingLanePriority {
+                    gate_id: "intake".into(),
+                },
+                GraphFault::DuplicatePriorityLane {
+                    gate_id: "review".into(),
+                    lane_id: "director".into(),
+                },
+            ]
+        );
+
+        let policy = StrategyPolicy::uniform("lane-priority")
+            .with_lane_priority(&["director".into(), "contractor".into()]);
+        assert_eq!(policy.lane_rank("director"), Some(0));
+        assert_eq!(policy.lane_rank("contractor"), Some(1));
+        assert_eq!(policy.lane_rank("intern"), None);
+    }
 }
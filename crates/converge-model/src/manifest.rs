@@ -39,6 +39,14 @@ pub enum ManifestEntryKind {
     Superposition {
         variants: Vec<SuperpositionVariant>,
     },
+    /// One page of a paged directory (arch doc 16 §1b). Appears only in a
+    /// page index, named after the first entry of the page; `entries`
+    /// counts the page so a directory's size is known without reading it.
+    /// See [`crate::paging`].
+    Page {
+        manifest: ObjectId,
+        entries: u64,
+    },
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -71,6 +79,9 @@ impl SuperpositionVariant {
                 target: target.clone(),
             },
             SuperpositionVariantKind::Tombstone => VariantKeyKind::Tombstone,
+            SuperpositionVariantKind::MovedTo { path } => {
+                VariantKeyKind::MovedTo { path: path.clone() }
+            }
         };
 
         VariantKey {
@@ -100,4 +111,10 @@ pub enum SuperpositionVariantKind {
         target: String,
     },
     Tombstone,
+    /// This lane moved the file to `path` instead (doc 17 §2a): two lanes
+    /// moved one file to different places. Resolves like a tombstone —
+    /// the path is dropped here.
+    MovedTo {
+        path: String,
+    },
 }
@@ -0,0 +1,1015 @@
+//! The base-aware fold (arch doc 17 §2-4): several trees, each with the
+//! base it was built on, folded onto one tree W.
+//!
+//! The server folds a gate's window of publications onto its last
+//! promoted candidate with it; the client replays a snap onto another
+//! lineage with it (doc 15 §5h). Both read and write objects through
+//! [`MergeObjects`], so one fold — its strategies, its rename detection —
+//! is what both sides get.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::Result;
+
+use crate::gates::StrategyPolicy;
+use crate::paging::{self, DirEdit};
+use crate::{
+    FileRecipe, Manifest, ManifestEntryKind, ObjectId, RenameRecord, SuperpositionVariant,
+    SuperpositionVariantKind,
+};
+
+use crate::structured;
+
+/// The objects a fold reads and the ones it writes: the manifests it
+/// rewrites and the blobs a content strategy merges into.
+pub trait MergeObjects {
+    fn get_manifest(&self, id: &ObjectId) -> Result<Manifest>;
+    fn put_manifest(&self, manifest: &Manifest) -> Result<ObjectId>;
+    fn get_blob(&self, id: &ObjectId) -> Result<Vec<u8>>;
+    fn put_blob(&self, bytes: &[u8]) -> Result<ObjectId>;
+    fn get_recipe(&self, id: &ObjectId) -> Result<FileRecipe>;
+}
+
+/// One publication's contribution to a candidate build (doc 17 §2).
+pub struct MergeInput {
+    /// Provenance source shown on superposition variants.
+    pub lane: String,
+    /// Root of the tree the publisher declared as base (`None` = empty).
+    pub base: Option<ObjectId>,
+    pub tree: ObjectId,
+}
+
+/// A publisher's opinion about one path.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Op {
+    /// New value plus the value this publisher's own base held there
+    /// (diff3 ancestor material).
+    Set(ManifestEntryKind, Option<ManifestEntryKind>),
+    Delete,
+}
+
+/// Result of a fold: the merged root plus what the fold learned along
+/// the way, so callers need no second walk (audit 2.2).
+pub struct MergeOutcome {
+    pub root: ObjectId,
+    /// A superposition was written (or folded through from an input).
+    /// A window's W is superposition-free by construction — promote
+    /// refuses a non-promotable candidate — so for a build this is the
+    /// complete answer.
+    pub has_superpositions: bool,
+    /// Files the inputs moved (doc 17 §2a), in input order.
+    pub renames: Vec<RenameRecord>,
+}
+
+/// One input's move of a file, as detected from its own delta.
+struct Move {
+    index: usize,
+    lane: String,
+    from: String,
+    to: String,
+}
+
+/// The fold a candidate built now records (doc 17 §2a). Version 1 is
+/// the fold before rename detection; a candidate it built replays
+/// without detection, so `verify` still reproduces it. Version 2 wrote
+/// structured-merged JSON with its keys sorted (§4b); 3 keeps them in
+/// document order.
+pub const FOLD_VERSION: u32 = 3;
+
+/// Pairs of removed and added files compared for similarity, per input.
+/// Past this, only exact moves are found: a publication that deletes and
+/// adds hundreds of files is a reorganisation, and comparing every pair
+/// would make its fold quadratic in it.
+const RENAME_PAIR_LIMIT: usize = 256;
+
+/// Files larger than this are only matched exactly.
+const RENAME_MAX_BYTES: u64 = 1 << 20;
+
+/// Share of lines two files must have in common, in percent of the
+/// longer, to count as one file moved and edited.
+const RENAME_MIN_SIMILARITY: usize = 50;
+
+/// Base-aware fold (doc 17 §2-3): compute each input's delta against its
+/// declared base, fold the opinions onto W. Unchanged paths express no
+/// opinion; clean deletions remove paths; delete-vs-modify superposes with
+/// a `Tombstone` variant. Deterministic: all maps are ordered.
+///
+/// Cost is bounded by *changed* paths (doc 17 §2): input deltas come from
+/// a diff that prunes on equal subtree ids, the values the fold needs from
+/// W or another input's base are fetched by path walk, and the merged tree
+/// rewrites only the manifests on changed paths — untouched subtrees keep
+/// their existing ids.
+pub fn merge_window(
+    objects: &dyn MergeObjects,
+    w_root: Option<&ObjectId>,
+    inputs: &[MergeInput],
+    strategy: &str,
+) -> Result<ObjectId> {
+    let policy = StrategyPolicy::uniform(strategy);
+    Ok(merge_window_outcome(objects, w_root, inputs, &policy, FOLD_VERSION)?.root)
+}
+
+/// `merge_window` with the strategy chosen per contested path by the
+/// gate's rules (doc 17 §4a), as fold `version` ran it: `FOLD_VERSION`
+/// for a new build, the candidate's own for a replay.
+pub fn merge_window_outcome(
+    objects: &dyn MergeObjects,
+    w_root: Option<&ObjectId>,
+    inputs: &[MergeInput],
+    policy: &StrategyPolicy,
+    version: u32,
+) -> Result<MergeOutcome> {
+    // Path walks are memoized by (root, path) across the whole fold
+    // (batch 15.4). The supersession pass below asks every input's base
+    // for every contested path, and a window's inputs overwhelmingly
+    // declare the *same* base — without this the fold costs
+    // paths × inputs walks, which the 100-publish benchmark measured as
+    // 20k manifest reads. Objects are immutable, so the memo cannot go
+    // stale mid-merge.
+    let mut walked: BTreeMap<(ObjectId, String), Option<ManifestEntryKind>> = BTreeMap::new();
+
+    // path -> ordered opinions (input index, lane, op). Sparse: only
+    // paths some input actually changed appear here.
+    let mut opinions: BTreeMap<String, Vec<(usize, String, Op)>> = BTreeMap::new();
+    let mut moves: Vec<Move> = Vec::new();
+    for (index, input) in inputs.iter().enumerate() {
+        let mut delta = BTreeMap::new();
+        diff_trees(
+            objects,
+            input.base.as_ref(),
+            Some(&input.tree),
+            "",
+            &mut delta,
+        )?;
+        // A move reads as a delete plus an add. Pairing them gives the
+        // add its diff3 ancestor: the content it was moved from.
+        if let Some(base) = input.base.as_ref().filter(|_| version >= 2) {
+            for (from, to, origin) in detect_renames(objects, &mut walked, base, &delta)? {
+                if let Some(Op::Set(kind, _)) = delta.remove(&to) {
+                    delta.insert(to.clone(), Op::Set(kind, Some(origin)));
+                }
+                moves.push(Move {
+                    index,
+                    lane: input.lane.clone(),
+                    from,
+                    to,
+                });
+            }
+        }
+        for (path, op) in delta {
+            opinions
+                .entry(path)
+                .or_default()
+                .push((index, input.lane.clone(), op));
+        }
+    }
+    let moved_elsewhere = follow_moves(&mut opinions, &moves);
+
+    // Values from W are needed only at contested paths, so they are read
+    // by path walk rather than by flattening the whole tree.
+    let mut w_at: BTreeMap<String, Option<ManifestEntryKind>> = BTreeMap::new();
+    for path in opinions.keys() {
+        let value = match w_root {
+            Some(root) => lookup_path_memo(objects, &mut walked, root, path)?,
+            None => None,
+        };
+        w_at.insert(path.clone(), value);
+    }
+
+    // Supersession by base containment (doc 17 §2): drop a Set(k) when a
+    // causally-newer input built on k AND the drop cannot lose content —
+    // that input has its own explicit opinion at the path, or W carries k.
+    let paths: Vec<String> = opinions.keys().cloned().collect();
+    for path in paths {
+        let ops = opinions.get(&path).expect("path present").clone();
+        let current = w_at.get(&path).and_then(|v| v.as_ref());
+        let mut retained: Vec<(usize, String, Op)> = Vec::new();
+        for (index, lane, op) in &ops {
+            let keep = match op {
+                Op::Delete => true,
+                Op::Set(kind, _) => {
+                    let mut superseded = false;
+                    for (other, other_input) in inputs.iter().enumerate() {
+                        if other == *index {
+                            continue;
+                        }
+                        let other_base = match &other_input.base {
+                            Some(root) => lookup_path_memo(objects, &mut walked, root, &path)?,
+                            None => None,
+                        };
+                        if !base_contains(other_base.as_ref(), kind) {
+                            continue;
+                        }
+                        let other_has_own_opinion = ops.iter().any(|(op_index, _, other_op)| {
+                            *op_index == other && !matches!(other_op, Op::Set(k, _) if k == kind)
+                        });
+                        if current == Some(kind) || other_has_own_opinion {
+                            superseded = true;
+                            break;
+                        }
+                    }
+                    !superseded
+                }
+            };
+            if keep {
+                retained.push((*index, lane.clone(), op.clone()));
+            }
+        }
+        opinions.insert(path, retained);
+    }
+    opinions.retain(|_, ops| !ops.is_empty());
+
+    // path -> new value (None = remove). Only changed paths appear.
+    let mut changes: BTreeMap<String, Option<ManifestEntryKind>> = BTreeMap::new();
+    let mut has_superpositions = false;
+
+    for (path, ops) in opinions {
+        // Distinct sets (dedup identical content, keep first source).
+        let mut sets: Vec<(String, ManifestEntryKind)> = Vec::new();
+        let mut set_bases: Vec<Option<ManifestEntryKind>> = Vec::new();
+        let mut deleters: Vec<String> = Vec::new();
+        for (_, lane, op) in ops {
+            match op {
+                Op::Set(kind, base_kind) => {
+                    if !sets.iter().any(|(_, k)| *k == kind) {
+                        sets.push((lane, kind));
+                        set_bases.push(base_kind);
+                    }
+                }
+                Op::Delete => deleters.push(lane),
+            }
+        }
+
+        // Drop sets that merely restate what W already holds — but only
+        // when no deletion contests the path (doc 17 §2, audit H4):
+        // against a Delete, restating W is an explicit keep opinion and
+        // must survive into the superposition.
+        let current = w_at.get(&path).cloned().flatten();
+        let (sets, set_bases) = if deleters.is_empty() {
+            let kept: Vec<(usize, (String, ManifestEntryKind))> = sets
+                .into_iter()
+                .enumerate()
+                .filter(|(_, (_, k))| current.as_ref() != Some(k))
+                .collect();
+            let bases: Vec<Option<ManifestEntryKind>> =
+                kept.iter().map(|(i, _)| set_bases[*i].clone()).collect();
+            (kept.into_iter().map(|(_, s)| s).collect::<Vec<_>>(), bases)
+        } else {
+            (sets, set_bases)
+        };
+
+        match (sets.len(), deleters.is_empty()) {
+            (0, true) => {} // all opinions collapsed into W's value
+            (0, false) => {
+                changes.insert(path, None); // clean deletion
+            }
+            (1, true) => {
+                let (_, kind) = sets.into_iter().next().expect("one set");
+                has_superpositions |= matches!(kind, ManifestEntryKind::Superposition { .. });
+                changes.insert(path, Some(kind));
+            }
+            _ => {
+                // True divergence: dispatch to the strategy the gate's
+                // rules pick for this path first (doc 17 §4-4d);
+                // unresolved divergence superposes.
+                // Diff3 ancestor (doc 17 §4): shared declared-base value if
+                // the divergent opinions agree on one, else W's value.
+                let ancestor = if !set_bases.is_empty()
+                    && set_bases.iter().all(|b| b.is_some() && *b == set_bases[0])
+                {
+                    set_bases[0].clone()
+                } else {
+                    current.clone()
+                };
+                // `Some(value)` settles the path (`Some(None)` removes it);
+                // `None` leaves it to superpose.
+                let resolved = match policy.strategy_for(&path) {
+                    "lane-priority" => pick_by_lane_priority(policy, &sets, &deleters),
+                    _ if !deleters.is_empty() => None,
+                    "text-line-merge" => {
+                        try_text_merge(objects, ancestor.as_ref(), &sets, line_merge)?.map(Some)
+                    }
+                    "structured-merge" => match structured::Format::for_path(&path) {
+                        Some(format) => {
+                            try_text_merge(objects, ancestor.as_ref(), &sets, |base, variants| {
+                                structured::merge(format, base, variants, version)
+                            })?
+                            .map(Some)
+                        }
+                        None => None,
+                    },
+                    "union-lines" => {
+                        try_text_merge(objects, ancestor.as_ref(), &sets, union_lines)?.map(Some)
+                    }
+                    _ => None,
+                };
+                if let Some(value) = resolved {
+                    has_superpositions |=
+                        matches!(value, Some(ManifestEntryKind::Superposition { .. }));
+                    changes.insert(path, value);
+                    continue;
+                }
+                let mut variants: Vec<SuperpositionVariant> = sets
+                    .into_iter()
+                    .flat_map(|(lane, kind)| to_variants(lane, kind))
+                    .collect();
+                if let Some(lane) = deleters.into_iter().next() {
+                    variants.push(SuperpositionVariant {
+                        source: lane,
+                        kind: SuperpositionVariantKind::Tombstone,
+                    });
+                }
+                has_superpositions = true;
+                changes.insert(path, Some(ManifestEntryKind::Superposition { variants }));
+            }
+        }
+    }
+
+    // Two lanes moved one file to different places (doc 17 §2a): each
+    // destination superposes what landed there with where the other
+    // lanes put it instead.
+    for (path, (lane, elsewhere)) in moved_elsewhere {
+        let variants = match changes.get(&path) {
+            Some(Some(ManifestEntryKind::Superposition { variants })) => variants.clone(),
+            Some(Some(kind)) => to_variants(lane, kind.clone()),
+            _ => continue,
+        };
+        has_superpositions = true;
+        changes.insert(
+            path,
+            Some(ManifestEntryKind::Superposition {
+                variants: variants.into_iter().chain(elsewhere).collect(),
+            }),
+        );
+    }
+
+    // Rewrite only the manifests on changed paths; untouched subtrees
+    // keep their existing ids, so nothing is re-hashed or re-stored for a
+    // directory nobody edited.
+    let root = apply_changes(objects, w_root, &changes)?;
+    Ok(MergeOutcome {
+        root,
+        has_superpositions,
+        renames: moves
+            .into_iter()
+            .map(|m| RenameRecord {
+                lane: m.lane,
+                from: m.from,
+                to: m.to,
+            })
+            .collect(),
+    })
+}
+
+/// Moves within one input's delta (doc 17 §2a): each removed file paired
+/// with at most one added file, exact content first, then the most
+/// similar text. Returns `(from, to, content at from)`, deterministic for
+/// a given delta.
+fn detect_renames(
+    objects: &dyn MergeObjects,
+    walked: &mut BTreeMap<(ObjectId, String), Option<ManifestEntryKind>>,
+    base: &ObjectId,
+    delta: &BTreeMap<String, Op>,
+) -> Result<Vec<(String, String, ManifestEntryKind)>> {
+    let mut added: Vec<(&String, &ManifestEntryKind)> = delta
+        .iter()
+        .filter_map(|(path, op)| match op {
+            Op::Set(kind, None) if content_id(kind).is_some() => Some((path, kind)),
+            _ => None,
+        })
+        .collect();
+    if added.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut removed: Vec<(&String, ManifestEntryKind)> = Vec::new();
+    for (path, op) in delta {
+        if *op == Op::Delete
+            && let Some(kind) = lookup_path_memo(objects, walked, base, path)?
+            && content_id(&kind).is_some()
+        {
+            removed.push((path, kind));
+        }
+    }
+
+    let mut found = Vec::new();
+    // Same content: a plain move. Mode changes ride along.
+    removed.retain(|(from, origin)| {
+        let Some(at) = added
+            .iter()
+            .position(|(_, kind)| content_id(kind) == content_id(origin))
+        else {
+            return true;
+        };
+        let (to, _) = added.remove(at);
+        found.push((from.to_string(), to.clone(), origin.clone()));
+        false
+    });
+    if removed.is_empty() || added.is_empty() || removed.len() * added.len() > RENAME_PAIR_LIMIT {
+        return Ok(found);
+    }
+
+    // Moved and edited: score every pair of text files, then take the
+    // best pairs first so one close match is not lost to an earlier,
+    // looser one.
+    let lines_of = |kind: &ManifestEntryKind| -> Result<Option<BTreeMap<String, usize>>> {
+        if content_size(kind) > RENAME_MAX_BYTES {
+            return Ok(None);
+        }
+        Ok(file_text(objects, kind)?.map(|text| {
+            let mut counts = BTreeMap::new();
+            for line in text.lines() {
+                *counts.entry(line.to_string()).or_insert(0) += 1;
+            }
+            counts
+        }))
+    };
+    let removed_lines = removed
+        .iter()
+        .map(|(_, kind)| lines_of(kind))
+        .collect::<Result<Vec<_>>>()?;
+    let added_lines = added
+        .iter()
+        .map(|(_, kind)| lines_of(kind))
+        .collect::<Result<Vec<_>>>()?;
+    let mut scored = Vec::new();
+    for (r, before) in removed_lines.iter().enumerate() {
+        let Some(before) = before else { continue };
+        for (a, after) in added_lines.iter().enumerate() {
+            let Some(after) = after else { continue };
+            let longer = before.values().sum::<usize>().max(after.values().sum());
+            if longer == 0 {
+                continue;
+            }
+            let common: usize = before
+                .iter()
+                .map(|(line, n)| (*n).min(after.get(line).copied().unwrap_or(0)))
+                .sum();
+            let score = common * 100 / longer;
+            if score >= RENAME_MIN_SIMILARITY {
+                scored.push((std::cmp::Reverse(score), r, a));
+            }
+        }
+    }
+    // Paths are sorted, so index order is path order: ties break the
+    // same way every time.
+    scored.sort();
+    let mut taken_removed = BTreeSet::new();
+    let mut taken_added = BTreeSet::new();
+    for (_, r, a) in scored {
+        if taken_removed.contains(&r) || taken_added.contains(&a) {
+            continue;
+        }
+        taken_removed.insert(r);
+        taken_added.insert(a);
+        found.push((
+            removed[r].0.clone(),
+            added[a].0.clone(),
+            removed[r].1.clone(),
+        ));
+    }
+    found.sort_by(|x, y| x.0.cmp(&y.0));
+    Ok(found)
+}
+
+/// Make every other input's opinion about a moved file follow it to
+/// where it went (doc 17 §2a). Returns, per destination of a file two
+/// lanes moved to different places, the mover's lane and the `MovedTo`
+/// variants naming the other destinations.
+fn follow_moves(
+    opinions: &mut BTreeMap<String, Vec<(usize, String, Op)>>,
+    moves: &[Move],
+) -> BTreeMap<String, (String, Vec<SuperpositionVariant>)> {
+    let mut by_origin: BTreeMap<&str, Vec<&Move>> = BTreeMap::new();
+    for m in moves {
+        by_origin.entry(m.from.as_str()).or_default().push(m);
+    }
+    let mut moved_elsewhere: BTreeMap<String, (String, Vec<SuperpositionVariant>)> =
+        BTreeMap::new();
+    for (from, group) in by_origin {
+        let destinations: BTreeSet<&str> = group.iter().map(|m| m.to.as_str()).collect();
+        if destinations.len() > 1 {
+            // Nobody's edit can follow a file that went two ways; it
+            // stays with the origin, and the moves contest each other.
+            for m in &group {
+                let entry = moved_elsewhere
+                    .entry(m.to.clone())
+                    .or_insert_with(|| (m.lane.clone(), Vec::new()));
+                for other in group.iter().filter(|o| o.to != m.to) {
+                    let variant = SuperpositionVariant {
+                        source: other.lane.clone(),
+                        kind: SuperpositionVariantKind::MovedTo {
+                            path: other.to.clone(),
+                        },
+                    };
+                    if !entry.1.contains(&variant) {
+                        entry.1.push(variant);
+                    }
+                }
+            }
+            continue;
+        }
+        let to = group[0].to.clone();
+        let movers: BTreeSet<usize> = group.iter().map(|m| m.index).collect();
+        let Some(ops) = opinions.get_mut(from) else {
+            continue;
+        };
+        // Edits and deletions of the file follow it. An add at the old
+        // path is a new file that happens to reuse the name, and stays.
+        let (follow, stay): (Vec<_>, Vec<_>) =
+            std::mem::take(ops).into_iter().partition(|(index, _, op)| {
+                !movers.contains(index) && matches!(op, Op::Set(_, Some(_)) | Op::Delete)
+            });
+        *ops = stay;
+        if follow.is_empty() {
+            continue;
+        }
+        let dest = opinions.entry(to).or_default();
+        // A move that kept the content says nothing about the content;
+        // the opinions that followed it do.
+        dest.retain(|(index, _, op)| {
+            !(movers.contains(index) && matches!(op, Op::Set(kind, Some(origin)) if kind == origin))
+        });
+        dest.extend(follow);
+        dest.sort_by_key(|(index, _, _)| *index);
+    }
+    opinions.retain(|_, ops| !ops.is_empty());
+    moved_elsewhere
+}
+
+/// The object a file's content is addressed by; `None` for anything
+/// that is not a file.
+fn content_id(kind: &ManifestEntryKind) -> Option<&ObjectId> {
+    match kind {
+        ManifestEntryKind::File { blob, .. } => Some(blob),
+        ManifestEntryKind::FileChunks { recipe, .. } => Some(recipe),
+        _ => None,
+    }
+}
+
+fn content_size(kind: &ManifestEntryKind) -> u64 {
+    match kind {
+        ManifestEntryKind::File { size, .. } | ManifestEntryKind::FileChunks { size, .. } => *size,
+        _ => 0,
+    }
+}
+
+/// Per-input delta with Merkle short-circuit (doc 17 §2): equal subtree
+/// ids mean that whole subtree expresses no opinion and is never read.
+fn diff_trees(
+    objects: &dyn MergeObjects,
+    base: Option<&ObjectId>,
+    tree: Option<&ObjectId>,
+    prefix: &str,
+    out: &mut BTreeMap<String, Op>,
+) -> Result<()> {
+    if base == tree {
+        return Ok(());
+    }
+    // Paged directories (doc 16 §1b) prune one level further: pages
+    // common to both sides are never read.
+    let (base_entries, tree_entries) = paging::unshared_entries(
+        load_or_empty(objects, base)?,
+        load_or_empty(objects, tree)?,
+        |id| objects.get_manifest(id),
+    )?;
+    let base_entries: BTreeMap<String, ManifestEntryKind> =
+        base_entries.into_iter().map(|e| (e.name, e.kind)).collect();
+    let tree_entries: BTreeMap<String, ManifestEntryKind> =
+        tree_entries.into_iter().map(|e| (e.name, e.kind)).collect();
+
+    let names: std::collections::BTreeSet<&String> =
+        base_entries.keys().chain(tree_entries.keys()).collect();
+    for name in names {
+        let path = if prefix.is_empty() {
+            name.clone()
+        } else {
+            format!("{prefix}/{name}")
+        };
+        let before = base_entries.get(name);
+        let after = tree_entries.get(name);
+        match (before, after) {
+            (
+                Some(ManifestEntryKind::Dir { manifest: b }),
+                Some(ManifestEntryKind::Dir { manifest: t }),
+            ) => {
+                diff_trees(objects, Some(b), Some(t), &path, out)?;
+            }
+            // A directory replaced by a leaf (or vice versa): the leaves
+            // under the directory read as deleted, the leaf as set.
+            (Some(ManifestEntryKind::Dir { manifest: b }), after) => {
+                diff_trees(objects, Some(b), None, &path, out)?;
+                if let Some(kind) = after {
+                    out.insert(path, Op::Set(kind.clone(), None));
+                }
+            }
+            (before, Some(ManifestEntryKind::Dir { manifest: t })) => {
+                if before.is_some() {
+                    out.insert(path.clone(), Op::Delete);
+                }
+                diff_trees(objects, None, Some(t), &path, out)?;
+            }
+            (before, Some(kind)) => {
+                if before != Some(kind) {
+                    out.insert(path, Op::Set(kind.clone(), before.cloned()));
+                }
+            }
+            (Some(_), None) => {
+                out.insert(path, Op::Delete);
+            }
+            (None, None) => unreachable!("name came from one of the maps"),
+        }
+    }
+    Ok(())
+}
+
+/// The value at `path`, walking only the manifests along it.
+/// `lookup_path` with a fold-lifetime memo keyed by (root, path).
+fn lookup_path_memo(
+    objects: &dyn MergeObjects,
+    walked: &mut BTreeMap<(ObjectId, String), Option<ManifestEntryKind>>,
+    root: &ObjectId,
+    path: &str,
+) -> Result<Option<ManifestEntryKind>> {
+    let key = (root.clone(), path.to_string());
+    if let Some(hit) = walked.get(&key) {
+        return Ok(hit.clone());
+    }
+    let value = lookup_path(objects, root, path)?;
+    walked.insert(key, value.clone());
+    Ok(value)
+}
+
+fn lookup_path(
+    objects: &dyn MergeObjects,
+    root: &ObjectId,
+    path: &str,
+) -> Result<Option<ManifestEntryKind>> {
+    let mut current = root.clone();
+    let mut segments = path.split('/').peekable();
+    while let Some(segment) = segments.next() {
+        let manifest = objects.get_manifest(&current)?;
+        let Some(kind) = paging::lookup(&manifest, segment, |id| objects.get_manifest(id))? else {
+            return Ok(None);
+        };
+        if segments.peek().is_none() {
+            return Ok(Some(kind));
+        }
+        match kind {
+            ManifestEntryKind::Dir { manifest } => current = manifest,
+            _ => return Ok(None),
+        }
+    }
+    Ok(None)
+}
+
+/// Apply path changes to `base`, rewriting only affected manifests.
+fn apply_changes(
+    objects: &dyn MergeObjects,
+    base: Option<&ObjectId>,
+    changes: &BTreeMap<String, Option<ManifestEntryKind>>,
+) -> Result<ObjectId> {
+    // Split each change into (first segment, rest) so a directory is
+    // visited once with all of its pending edits.
+    let mut here: BTreeMap<String, Option<ManifestEntryKind>> = BTreeMap::new();
+    let mut nested: BTreeMap<String, BTreeMap<String, Option<ManifestEntryKind>>> = BTreeMap::new();
+    for (path, value) in changes {
+        match path.split_once('/') {
+            None => {
+                here.insert(path.clone(), value.clone());
+            }
+            Some((dir, rest)) => {
+                nested
+                    .entry(dir.to_string())
+                    .or_default()
+                    .insert(rest.to_string(), value.clone());
+            }
+        }
+    }
+
+    // Only the pages of a paged directory that an edit lands in are read
+    // or rewritten (doc 16 §1b).
+    let load = |id: &ObjectId| objects.get_manifest(id);
+    let mut entries = match base {
+        Some(id) => DirEdit::open(objects.get_manifest(id)?)?,
+        None => DirEdit::empty(),
+    };
+
+    for (name, value) in here {
+        match value {
+            Some(kind) => entries.set(name, kind, load)?,
+            None => entries.remove(&name, load)?,
+        }
+    }
+
+    for (dir, child_changes) in nested {
+        let child_base = match entries.get(&dir, load)? {
+            Some(ManifestEntryKind::Dir { manifest }) => Some(manifest),
+            // A leaf being replaced by a subtree starts from nothing.
+            _ => None,
+        };
+        let rewritten = apply_changes(objects, child_base.as_ref(), &child_changes)?;
+        if manifest_is_empty(objects, &rewritten)? {
+            // A directory emptied by deletions disappears rather than
+            // lingering as an empty entry.
+            entries.remove(&dir, load)?;
+        } else {
+            entries.set(
+                dir,
+                ManifestEntryKind::Dir {
+                    manifest: rewritten,
+                },
+                load,
+            )?;
+        }
+    }
+
+    let manifest = entries.finish(load, |page| objects.put_manifest(page))?;
+    objects.put_manifest(&manifest)
+}
+
+fn manifest_is_empty(objects: &dyn MergeObjects, id: &ObjectId) -> Result<bool> {
+    Ok(objects.get_manifest(id)?.entries.is_empty())
+}
+
+fn load_or_empty(objects: &dyn MergeObjects, id: Option<&ObjectId>) -> Result<Manifest> {
+    match id {
+        Some(id) => objects.get_manifest(id),
+        None => Ok(Manifest {
+            version: 1,
+            entries: Vec::new(),
+        }),
+    }
+}
+
+/// Shared by the strategies that merge file content (doc 17 §4, §4b):
+/// load the ancestor and the divergent variants as text, hand them to
+/// `merge`, and store what comes back as a new `File` entry. `None` from
+/// `merge` means it could not merge cleanly, and the caller superposes
+/// the original variants — conflict markers are never written. Non-text
+/// content -> `None` (per-path fallback to whole-file behavior).
+fn try_text_merge(
+    objects: &dyn MergeObjects,
+    base: Option<&ManifestEntryKind>,
+    sets: &[(String, ManifestEntryKind)],
+    merge: impl FnOnce(&str, &[String]) -> Option<String>,
+) -> Result<Option<ManifestEntryKind>> {
+    let base_text = match base {
+        Some(kind) => match file_text(objects, kind)? {
+            Some(text) => text,
+            None => return Ok(None),
+        },
+        None => String::new(),
+    };
+
+    let mut variant_texts = Vec::new();
+    let mut modes = Vec::new();
+    for (_, kind) in sets {
+        match file_text(objects, kind)? {
+            Some(text) => variant_texts.push(text),
+            None => return Ok(None),
+        }
+        modes.push(match kind {
+            ManifestEntryKind::File { mode, .. } | ManifestEntryKind::FileChunks { mode, .. } => {
+                *mode
+            }
+            _ => return Ok(None),
+        });
+    }
+
+    let Some(merged) = merge(&base_text, &variant_texts) else {
+        return Ok(None);
+    };
+
+    let mode = if modes.iter().all(|m| *m == modes[0]) {
+        modes[0]
+    } else {
+        match base {
+            Some(ManifestEntryKind::File { mode, .. })
+            | Some(ManifestEntryKind::FileChunks { mode, .. }) => *mode,
+            _ => 0o644,
+        }
+    };
+    let bytes = merged.into_bytes();
+    let blob = objects.put_blob(&bytes)?;
+    Ok(Some(ManifestEntryKind::File {
+        blob,
+        mode,
+        size: bytes.len() as u64,
+    }))
+}
+
+/// `text-line-merge` (doc 17 §4): diff3 the variants against the
+/// ancestor, pairwise in input order; any overlapping hunk -> `None`.
+fn line_merge(base: &str, variants: &[String]) -> Option<String> {
+    let mut merged = variants[0].clone();
+    for variant in &variants[1..] {
+        merged = diffy::merge(base, &merged, variant).ok()?;
+    }
+    Some(merged)
+}
+
+/// `union-lines` (doc 17 §4c): keep every line any variant added, drop
+/// every line any variant removed, folded pairwise in input order. Never
+/// conflicts: where two variants add at the same place, the earlier
+/// input's lines come first, and a line both added there is kept once.
+fn union_lines(base: &str, variants: &[String]) -> Option<String> {
+    let mut merged = variants[0].clone();
+    for variant in &variants[1..] {
+        merged = union2(base, &merged, variant);
+    }
+    Some(merged)
+}
+
+fn union2(base: &str, ours: &str, theirs: &str) -> String {
+    let base_lines: Vec<&str> = base.split_inclusive('\n').collect();
+    let ours = LineEdits::between(base, ours);
+    let theirs = LineEdits::between(base, theirs);
+    let mut pieces: Vec<&str> = Vec::new();
+    for at in 0..=base_lines.len() {
+        let ours_added = ours.added.get(&at).map(Vec::as_slice).unwrap_or_default();
+        pieces.extend(ours_added.iter().map(String::as_str));
+        if let Some(theirs_added) = theirs.added.get(&at) {
+            // As a multiset: each line we added here covers one of theirs,
+            // so a line they added twice, or that we both added once more
+            // than the other, is not lost.
+            let mut covered = ours_added.to_vec();
+            for line in theirs_added {
+                match covered.iter().position(|ours| ours == line) {
+                    Some(index) => {
+                        covered.swap_remove(index);
+                    }
+                    None => pieces.push(line),
+                }
+            }
+        }
+        if at < base_lines.len() && !ours.removed.contains(&at) && !theirs.removed.contains(&at) {
+            pieces.push(base_lines[at]);
+        }
+    }
+    let mut out = String::new();
+    for piece in pieces {
+        // A last line without a newline may no longer be last.
+        if !out.is_empty() && !out.ends_with('\n') {
+            out.push('\n');
+        }
+        out.push_str(piece);
+    }
+    out
+}
+
+/// One side's line edits against a base, by base line index: lines
+/// removed, and lines added before the base line at that index (the
+/// index one past the end is the end of the file).
+struct LineEdits {
+    removed: BTreeSet<usize>,
+    added: BTreeMap<usize, Vec<String>>,
+}
+
+impl LineEdits {
+    fn between(base: &str, side: &str) -> Self {
+        let mut edits = LineEdits {
+            removed: BTreeSet::new(),
+            added: BTreeMap::new(),
+        };
+        for hunk in diffy::create_patch(base, side).hunks() {
+            let range = hunk.old_range();
+            // Unified-diff numbering: 1-based, except that an empty range
+            // names the line it follows.
+            let mut at = if range.is_empty() {
+                range.start()
+            } else {
+                range.start() - 1
+            };
+            for line in hunk.lines() {
+                match line {
+                    diffy::Line::Context(_) => at += 1,
+                    diffy::Line::Delete(_) => {
+                        edits.removed.insert(at);
+                        at += 1;
+                    }
+                    diffy::Line::Insert(text) => {
+                        edits.added.entry(at).or_default().push(text.to_string())
+                    }
+                }
+            }
+        }
+        edits
+    }
+}
+
+/// `lane-priority` (doc 17 §4d): the opinion from the highest-ranked lane
+/// wins the path outright, a deletion included. `None` — superpose — when
+/// no contesting lane is ranked, or the best-ranked lane holds more than
+/// one opinion here.
+fn pick_by_lane_priority(
+    policy: &StrategyPolicy,
+    sets: &[(String, ManifestEntryKind)],
+    deleters: &[String],
+) -> Option<Option<ManifestEntryKind>> {
+    let opinions = sets
+        .iter()
+        .map(|(lane, kind)| (lane, Some(kind)))
+        .chain(deleters.iter().map(|lane| (lane, None)));
+    let ranked: Vec<(usize, Option<&ManifestEntryKind>)> = opinions
+        .filter_map(|(lane, value)| Some((policy.lane_rank(lane)?, value)))
+        .collect();
+    let best = ranked.iter().map(|(rank, _)| *rank).min()?;
+    let mut winners = ranked.into_iter().filter(|(rank, _)| *rank == best);
+    let (_, value) = winners.next()?;
+    if winners.next().is_some() {
+        return None;
+    }
+    Some(value.cloned())
+}
+
+/// Load file-like content and admit it as text: File or FileChunks, no NUL
+/// byte in the first 8 KiB, valid UTF-8.
+fn file_text(objects: &dyn MergeObjects, kind: &ManifestEntryKind) -> Result<Option<String>> {
+    let bytes = match kind {
+        ManifestEntryKind::File { blob, .. } => objects.get_blob(blob)?,
+        ManifestEntryKind::FileChunks { recipe, .. } => {
+            let recipe = objects.get_recipe(recipe)?;
+            let mut out = Vec::with_capacity(recipe.size as usize);
+            for chunk in &recipe.chunks {
+                out.extend_from_slice(&objects.get_blob(&chunk.blob)?);
+            }
+            out
+        }
+        _ => return Ok(None),
+    };
+    if bytes.iter().take(8192).any(|b| *b == 0) {
+        return Ok(None);
+    }
+    Ok(String::from_utf8(bytes).ok())
+}
+
+/// Does a declared base hold `kind` at a path — either as the value, or
+/// as one of the variants of a superposition there (doc 17 §2)?
+///
+/// The variant case is what lets a resolution close the loop (batch
+/// 16.1). A publisher who based on a superposed candidate and set a value
+/// saw every variant and decided among them; re-superposing the losing
+/// variants against that decision would make resolution impossible until
+/// the window is promoted. Content is not at risk: the safety condition
+/// below still requires the superseder to carry its own explicit opinion
+/// at the path, or W to hold the value already.
+fn base_contains(base: Option<&ManifestEntryKind>, kind: &ManifestEntryKind) -> bool {
+    match base {
+        Some(value) if value == kind => true,
+        Some(ManifestEntryKind::Superposition { variants }) => variants
+            .iter()
+            .any(|variant| variant_matches(&variant.kind, kind)),
+        _ => false,
+    }
+}
+
+fn variant_matches(variant: &SuperpositionVariantKind, kind: &ManifestEntryKind) -> bool {
+    match (variant, kind) {
+        (
+            SuperpositionVariantKind::File { blob, mode, size },
+            ManifestEntryKind::File {
+                blob: b,
+                mode: m,
+                size: s,
+            },
+        ) => blob == b && mode == m && size == s,
+        (
+            SuperpositionVariantKind::FileChunks { recipe, mode, size },
+            ManifestEntryKind::FileChunks {
+                recipe: r,
+                mode: m,
+                size: s,
+            },
+        ) => recipe == r && mode == m && size == s,
+        (
+            SuperpositionVariantKind::Dir { manifest },
+            ManifestEntryKind::Dir { manifest: other },
+        ) => manifest == other,
+        (
+            SuperpositionVariantKind::Symlink { target },
+            ManifestEntryKind::Symlink { target: other },
+        ) => target == other,
+        // A tombstone is the absence of content, never a value someone set.
+        _ => false,
+    }
+}
+
+fn to_variants(source: String, kind: ManifestEntryKind) -> Vec<SuperpositionVariant> {
+    let kind = match kind {
+        ManifestEntryKind::File { blob, mode, size } => {
+            SuperpositionVariantKind::File { blob, mode, size }
+        }
+        ManifestEntryKind::FileChunks { recipe, mode, size } => {
+            SuperpositionVariantKind::FileChunks { recipe, mode, size }
+        }
+        ManifestEntryKind::Dir { manifest } => SuperpositionVariantKind::Dir { manifest },
+        ManifestEntryKind::Symlink { target } => SuperpositionVariantKind::Symlink { target },
+        // Nested superpositions flatten: inner variants keep their own
+        // provenance.
+        ManifestEntryKind::Superposition { variants } => return variants,
+        // Pages only appear inside an index, which the fold always reads
+        // through `paging`; they never reach a path's value.
+        ManifestEntryKind::Page { .. } => unreachable!("page entry outside a page index"),
+    };
+    vec![SuperpositionVariant { source, kind }]
+}
@@ -0,0 +1,458 @@
+//! Moving a store forward one format version at a time (doc 16 §3).
+//!
+//! [`crate::format::check_compatible`] refuses an older store rather than
+//! guess at it. This is the way forward from that refusal that is not
+//! "re-clone": an ordered registry of `vN -> vN+1` steps per store kind,
+//! run explicitly — `converge migrate`, `converge-server --migrate` —
+//! and never on open. Opening a store stays a pure read (batch 22.2).
+//!
+//! ## Resumability
+//!
+//! The stamp is the progress record. Each step ends by writing the
+//! stamp it produced, so a run that dies between steps resumes at the
+//! next one, and a run that dies *inside* a step runs that step again
+//! from the top. Steps are therefore required to be idempotent: safe to
+//! apply to a store they have already partly or wholly migrated.
+//!
+//! A journal file sits in the store for the whole run. It is what tells
+//! a resumed run that the backup it would otherwise take is already
+//! there — a second backup taken halfway would capture the half-migrated
+//! store and be worse than none — and what lets the refusal on open say
+//! "interrupted" instead of just "older".
+//!
+//! ## Backups
+//!
+//! Taken into the store's own `backups/` directory, so nothing lands in
+//! a working tree and a workspace's backup travels with it. The copy is
+//! made under a `.partial` name and renamed when complete: a backup that
+//! exists under its real name is a whole one.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use serde::Serialize;
+
+use crate::format::{StoreKind, read_version, write_version_at};
+
+/// Present in a store for the duration of a migration.
+pub const JOURNAL_FILE: &str = "migrating";
+
+/// Where backups go, inside the store. Never copied into a backup.
+pub const BACKUP_DIR: &str = "backups";
+
+/// One `from -> from + 1` step.
+///
+/// `apply` gets the store root — a workspace's `.converge`, a server's
+/// data directory — and must be idempotent (see the module docs).
+pub struct Step {
+    pub from: u32,
+    pub summary: &'static str,
+    pub apply: fn(&Path) -> Result<()>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Options {
+    /// Report what would run and touch nothing.
+    pub dry_run: bool,
+    /// Copy the store aside before the first step.
+    pub backup: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            dry_run: false,
+            backup: true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PlannedStep {
+    pub from: u32,
+    pub to: u32,
+    pub summary: &'static str,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Report {
+    /// The format found when the run started.
+    pub from: u32,
+    pub to: u32,
+    /// Steps run, or for a dry run, steps that would run.
+    pub steps: Vec<PlannedStep>,
+    /// The backup this migration restores from, if there is one.
+    pub backup: Option<PathBuf>,
+    /// An interrupted migration was picked up rather than started.
+    pub resumed: bool,
+    pub dry_run: bool,
+}
+
+/// What the journal records: the run's endpoints and its backup.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Journal {
+    pub from: u32,
+    pub to: u32,
+    pub backup: Option<PathBuf>,
+}
+
+/// Read the journal of an interrupted migration, if one is in the store.
+pub fn read_journal(store_root: &Path) -> Result<Option<Journal>> {
+    let path = store_root.join(JOURNAL_FILE);
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err).with_context(|| format!("read {}", path.display())),
+    };
+    let (mut from, mut to, mut backup) = (None, None, None);
+    for line in text.lines() {
+        match line.split_once(' ') {
+            Some(("from", value)) => from = value.parse().ok(),
+            Some(("to", value)) => to = value.parse().ok(),
+            Some(("backup", value)) => backup = Some(PathBuf::from(value)),
+            _ => {}
+        }
+    }
+    let (Some(from), Some(to)) = (from, to) else {
+        bail!("unreadable migration journal at {}", path.display());
+    };
+    Ok(Some(Journal { from, to, backup }))
+}
+
+fn write_journal(store_root: &Path, journal: &Journal) -> Result<()> {
+    let mut text = format!("from {}\nto {}\n", journal.from, journal.to);
+    if let Some(backup) = &journal.backup {
+        text.push_str(&format!("backup {}\n", backup.display()));
+    }
+    let path = store_root.join(JOURNAL_FILE);
+    std::fs::write(&path, text).with_context(|| format!("write {}", path.display()))
+}
+
+/// The steps that take `found` to `kind.current()`, in order.
+///
+/// A gap in the registry is refused before anything runs: a migration
+/// that stops halfway because the next step does not exist leaves a
+/// store that neither the old build nor the new one reads.
+pub fn plan(kind: StoreKind, found: u32, steps: &[Step]) -> Result<Vec<PlannedStep>> {
+    let current = kind.current();
+    if found > current {
+        bail!(
+            "this {} is format {found}, newer than the {current} this build reads; \
+             there is no migrating backwards",
+            kind.what()
+        );
+    }
+    (found..current)
+        .map(|from| {
+            let step = steps
+                .iter()
+                .find(|step| step.from == from)
+                .with_context(|| {
+                    format!(
+                        "this build has no migration for a {} from format {from} to {}",
+                        kind.what(),
+                        from + 1
+                    )
+                })?;
+            Ok(PlannedStep {
+                from,
+                to: from + 1,
+                summary: step.summary,
+            })
+        })
+        .collect()
+}
+
+/// Bring the store at `store_root` to `kind.current()`.
+pub fn run(store_root: &Path, kind: StoreKind, steps: &[Step], options: Options) -> Result<Report> {
+    let found = read_version(store_root, kind)?;
+    let current = kind.current();
+    let journal = read_journal(store_root)?;
+    if let Some(journal) = &journal
+        && journal.to != current
+    {
+        bail!(
+            "a migration of this {} to format {} was interrupted, and this build \
+             migrates to {current}. Finish it with the build that started it{}.",
+            kind.what(),
+            journal.to,
+            journal
+                .backup
+                .as_ref()
+                .map(|b| format!(", or restore from {}", b.display()))
+                .unwrap_or_default()
+        );
+    }
+    let planned = plan(kind, found, steps)?;
+    let resumed = journal.is_some();
+    let mut report = Report {
+        from: journal.as_ref().map_or(found, |j| j.from),
+        to: current,
+        steps: planned,
+        backup: journal.as_ref().and_then(|j| j.backup.clone()),
+        resumed,
+        dry_run: options.dry_run,
+    };
+    if options.dry_run {
+        if !resumed && options.backup && !report.steps.is_empty() {
+            report.backup = Some(backup_path(store_root, found));
+        }
+        return Ok(report);
+    }
+    if report.steps.is_empty() && !resumed {
+        return Ok(report);
+    }
+
+    if !resumed {
+        if options.backup {
+            report.backup = Some(take_backup(store_root, found)?);
+        }
+        write_journal(
+            store_root,
+            &Journal {
+                from: found,
+                to: current,
+                backup: report.backup.clone(),
+            },
+        )?;
+    }
+    for planned in &report.steps {
+        let step = steps
+            .iter()
+            .find(|step| step.from == planned.from)
+            .expect("planned steps come from the registry");
+        (step.apply)(store_root).with_context(|| {
+            format!(
+                "migrate {} from format {} to {}",
+                kind.what(),
+                planned.from,
+                planned.to
+            )
+        })?;
+        write_version_at(store_root, kind, planned.to)?;
+    }
+    let path = store_root.join(JOURNAL_FILE);
+    std::fs::remove_file(&path).with_context(|| format!("remove {}", path.display()))?;
+    Ok(report)
+}
+
+/// `backups/format-N`, or `format-N.2` and so on when a store has been
+/// restored and migrated again: an earlier backup is never overwritten.
+fn backup_path(store_root: &Path, found: u32) -> PathBuf {
+    let dir = store_root.join(BACKUP_DIR);
+    let mut path = dir.join(format!("format-{found}"));
+    let mut n = 2;
+    while path.exists() {
+        path = dir.join(format!("format-{found}.{n}"));
+        n += 1;
+    }
+    path
+}
+
+fn take_backup(store_root: &Path, found: u32) -> Result<PathBuf> {
+    let path = backup_path(store_root, found);
+    let partial = path.with_extension("partial");
+    if partial.exists() {
+        // Left by a run that died while copying, before its journal
+        // existed: nothing has been migrated, so start the copy over.
+        std::fs::remove_dir_all(&partial)
+            .with_context(|| format!("remove {}", partial.display()))?;
+    }
+    copy_dir(store_root, &partial, true)
+        .with_context(|| format!("back up {} to {}", store_root.display(), path.display()))?;
+    std::fs::rename(&partial, &path)
+        .with_context(|| format!("rename {} to {}", partial.display(), path.display()))?;
+    Ok(path)
+}
+
+fn copy_dir(from: &Path, to: &Path, top: bool) -> Result<()> {
+    std::fs::create_dir_all(to).with_context(|| format!("create {}", to.display()))?;
+    for entry in std::fs::read_dir(from).with_context(|| format!("read {}", from.display()))? {
+        let entry = entry?;
+        let name = entry.file_name();
+        if top && (name == BACKUP_DIR || name == JOURNAL_FILE) {
+            continue;
+        }
+        let source = entry.path();
+        let target = to.join(&name);
+        if entry.file_type()?.is_dir() {
+            copy_dir(&source, &target, false)?;
+        } else {
+            std::fs::copy(&source, &target)
+                .with_context(|| format!("copy {}", source.display()))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::FORMAT_FILE;
+
+    /// A stand-in step: appends the version it found to `log`, so a
+    /// re-run shows up as a repeat and the order is visible.
+    fn record(root: &Path) -> Result<()> {
+        let from = read_version(root, StoreKind::Workspace)?;
+        let path = root.join("log");
+        let mut log = std::fs::read_to_string(&path).unwrap_or_default();
+        log.push_str(&format!("{from}\n"));
+        std::fs::write(path, log)?;
+        Ok(())
+    }
+
+    fn unstamped_store() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().expect("tempdir");
+        std::fs::write(dir.path().join("data"), "v1 bytes").expect("write");
+        dir
+    }
+
+    /// One stand-in step per version below the current one, so the
+    /// registry is whole whatever the current format is.
+    fn steps() -> Vec<Step> {
+        (1..StoreKind::Workspace.current())
+            .map(|from| Step {
+                from,
+                summary: "test step",
+                apply: record,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn a_dry_run_reports_the_plan_and_writes_nothing() {
+        let dir = unstamped_store();
+        let report = run(
+            dir.path(),
+            StoreKind::Workspace,
+            &steps(),
+            Options {
+                dry_run: true,
+                backup: true,
+            },
+        )
+        .expect("dry run");
+        assert_eq!(
+            report.steps.len() as u32,
+            StoreKind::Workspace.current() - 1
+        );
+        assert!(
+            report.backup.is_some(),
+            "a dry run names the backup it would take"
+        );
+        let names: Vec<_> = std::fs::read_dir(dir.path())
+            .expect("read")
+            .map(|e| e.expect("entry").file_name())
+            .collect();
+        assert_eq!(names, vec![std::ffi::OsString::from("data")]);
+    }
+
+    #[test]
+    fn a_migration_backs_up_stamps_and_clears_its_journal() {
+        let dir = unstamped_store();
+        let report = run(
+            dir.path(),
+            StoreKind::Workspace,
+            &steps(),
+            Options::default(),
+        )
+        .expect("migrate");
+        assert_eq!(
+            (report.from, report.to),
+            (1, StoreKind::Workspace.current())
+        );
+        let backup = report.backup.expect("backed up");
+        assert_eq!(
+            std::fs::read_to_string(backup.join("data")).expect("read"),
+            "v1 bytes"
+        );
+        assert!(
+            !backup.join("log").exists(),
+            "the backup is taken before any step"
+        );
+        assert_eq!(
+            read_version(dir.path(), StoreKind::Workspace).expect("read"),
+            StoreKind::Workspace.current()
+        );
+        assert!(!dir.path().join(JOURNAL_FILE).exists());
+
+        // And a second run has nothing to do.
+        let again = run(
+            dir.path(),
+            StoreKind::Workspace,
+            &steps(),
+            Options::default(),
+        )
+        .expect("re-run");
+        assert!(again.steps.is_empty() && again.backup.is_none());
+    }
+
+    /// Killed inside a step: the stamp never moved, so the step runs
+    /// again, and the backup from the first attempt is the one kept.
+    #[test]
+    fn an_interrupted_migration_resumes_without_a_second_backup() {
+        let dir = unstamped_store();
+        let mut failing = steps();
+        failing[0] = Step {
+            from: 1,
+            summary: "dies halfway",
+            apply: |root| {
+                record(root)?;
+                bail!("power cut")
+            },
+        };
+        let err = run(
+            dir.path(),
+            StoreKind::Workspace,
+            &failing,
+            Options::default(),
+        )
+        .expect_err("interrupted");
+        assert!(format!("{err:#}").contains("power cut"), "{err:#}");
+        let journal = read_journal(dir.path())
+            .expect("read")
+            .expect("journal kept");
+        assert_eq!(journal.from, 1);
+        let first_backup = journal.backup.expect("backed up");
+
+        let report = run(
+            dir.path(),
+            StoreKind::Workspace,
+            &steps(),
+            Options::default(),
+        )
+        .expect("resume");
+        assert!(report.resumed);
+        assert_eq!(report.backup.as_ref(), Some(&first_backup));
+        assert_eq!(
+            std::fs::read_dir(dir.path().join(BACKUP_DIR))
+                .expect("read")
+                .count(),
+            1,
+            "resuming took another backup of a half-migrated store"
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("log")).expect("log"),
+            (1..StoreKind::Workspace.current())
+                .fold("1\n".to_string(), |log, from| format!("{log}{from}\n")),
+            "the interrupted step, then every step from it"
+        );
+        assert!(!dir.path().join(JOURNAL_FILE).exists());
+    }
+
+    #[test]
+    fn a_gap_in_the_registry_is_refused_before_anything_runs() {
+        let dir = unstamped_store();
+        std::fs::write(dir.path().join(FORMAT_FILE), "converge-workspace-0\n").expect("write");
+        let err = run(
+            dir.path(),
+            StoreKind::Workspace,
+            &steps(),
+            Options::default(),
+        )
+        .expect_err("no step from 0");
+        assert!(format!("{err:#}").contains("from format 0 to 1"), "{err:#}");
+        assert!(!dir.path().join(BACKUP_DIR).exists());
+        assert!(!dir.path().join("log").exists());
+    }
+}
@@ -0,0 +1,602 @@
+//! Paged directory manifests (arch doc 16 §1b).
+//!
+//! A directory manifest is one object, so a directory with a hundred
+//! thousand entries is one multi-megabyte object that every edit
+//! rewrites, every diff decodes whole, and every negotiation re-sends.
+//! Past [`PAGE_THRESHOLD`] entries the directory is instead stored as an
+//! **index**: a manifest whose entries are all [`ManifestEntryKind::Page`],
+//! each pointing at an ordinary manifest holding one ordered run of the
+//! directory's entries. The index entry is named after the first entry
+//! of its page, so a name is found by binary search without reading any
+//! page but the one that holds it.
+//!
+//! ## Boundaries are content-defined
+//!
+//! A page ends after any entry whose name hashes to a boundary — a
+//! property of the name alone, not of its position. That is what makes
+//! the split *canonical*: the client building a tree from disk and the
+//! server folding a window arrive at the same pages, hence the same ids,
+//! without agreeing on anything but this file. It is also what keeps an
+//! edit local: inserting or removing a non-boundary name changes one
+//! page, and the pages either side keep their ids. There is no forced
+//! maximum (a forced cut would depend on position and lose both
+//! properties); with a target of [`PAGE_TARGET`] a page longer than
+//! eight times that is a ~0.03% event, and a long page is only slower,
+//! never wrong.
+//!
+//! ## Only large directories page
+//!
+//! A directory at or below the threshold is written exactly as before,
+//! so every existing id stays valid and nothing small pays for this.
+//! Pages never nest: a page holds ordinary entries only, and an index
+//! holds pages only. Readers refuse anything else rather than guess.
+//!
+//! Walkers that want a directory's *contents* call [`flatten`]; walkers
+//! that want its *objects* (GC marking, negotiation, unpinning) treat a
+//! page like a `Dir` child, which it is as far as reachability goes.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Result, bail};
+
+use crate::ids::ObjectId;
+use crate::manifest::{Manifest, ManifestEntry, ManifestEntryKind};
+
+/// A directory with more entries than this is stored as pages.
+pub const PAGE_THRESHOLD: usize = 4096;
+
+/// Expected entries per page: one name in this many is a boundary.
+pub const PAGE_TARGET: u64 = 1024;
+
+/// Domain tag for the boundary hash, so the split cannot collide with
+/// any other use of blake3 over a name.
+const BOUNDARY_DOMAIN: &[u8] = b"converge-page-v1\0";
+
+/// Does a page end after the entry named `name`?
+pub fn is_boundary(name: &str) -> bool {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(BOUNDARY_DOMAIN);
+    hasher.update(name.as_bytes());
+    let hash = hasher.finalize();
+    let mut head = [0u8; 8];
+    head.copy_from_slice(&hash.as_bytes()[..8]);
+    u64::from_le_bytes(head) % PAGE_TARGET == 0
+}
+
+/// Is this manifest a page index rather than a directory's entries?
+pub fn is_index(manifest: &Manifest) -> bool {
+    manifest
+        .entries
+        .first()
+        .is_some_and(|e| matches!(e.kind, ManifestEntryKind::Page { .. }))
+}
+
+/// Split sorted entries into pages at content-defined boundaries.
+pub fn split(entries: Vec<ManifestEntry>) -> Vec<Vec<ManifestEntry>> {
+    let mut pages = Vec::new();
+    let mut current = Vec::new();
+    for entry in entries {
+        let boundary = is_boundary(&entry.name);
+        current.push(entry);
+        if boundary {
+            pages.push(std::mem::take(&mut current));
+        }
+    }
+    if !current.is_empty() {
+        pages.push(current);
+    }
+    pages
+}
+
+/// The manifest to store for a directory holding `entries` (any order):
+/// the entries themselves at or below the threshold, otherwise an index
+/// whose pages have been written through `put`.
+pub fn build(
+    mut entries: Vec<ManifestEntry>,
+    mut put: impl FnMut(&Manifest) -> Result<ObjectId>,
+) -> Result<Manifest> {
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    if entries.len() <= PAGE_THRESHOLD {
+        return Ok(Manifest {
+            version: 1,
+            entries,
+        });
+    }
+    let mut index = Vec::new();
+    for page in split(entries) {
+        index.push(write_page(page, &mut put)?);
+    }
+    Ok(Manifest {
+        version: 1,
+        entries: index,
+    })
+}
+
+/// A directory's entries in name order, reading its pages if it has any.
+pub fn flatten(
+    manifest: Manifest,
+    mut load: impl FnMut(&ObjectId) -> Result<Manifest>,
+) -> Result<Vec<ManifestEntry>> {
+    if !is_index(&manifest) {
+        refuse_pages(&manifest)?;
+        return Ok(manifest.entries);
+    }
+    let mut out = Vec::new();
+    for page in page_refs(&manifest)? {
+        out.extend(load_page(&page.manifest, &mut load)?.entries);
+    }
+    Ok(out)
+}
+
+/// The entry named `name`, reading at most one page.
+pub fn lookup(
+    manifest: &Manifest,
+    name: &str,
+    mut load: impl FnMut(&ObjectId) -> Result<Manifest>,
+) -> Result<Option<ManifestEntryKind>> {
+    if !is_index(manifest) {
+        return Ok(manifest
+            .entries
+            .iter()
+            .find(|e| e.name == name)
+            .map(|e| e.kind.clone()));
+    }
+    let pages = page_refs(manifest)?;
+    let page = &pages[page_of(&pages, name)];
+    Ok(load_page(&page.manifest, &mut load)?
+        .entries
+        .into_iter()
+        .find(|e| e.name == name)
+        .map(|e| e.kind))
+}
+
+/// The entries of two directories that can differ, in name order.
+///
+/// When both are paged, a page id present on both sides holds the same
+/// entries on both sides — and, names being unique, no entry outside it
+/// can share a name with one inside it — so shared pages are skipped
+/// unread. This is the Merkle short-circuit one level down: the cost of
+/// diffing a huge directory is the pages that changed, not its size.
+pub fn unshared_entries(
+    a: Manifest,
+    b: Manifest,
+    mut load: impl FnMut(&ObjectId) -> Result<Manifest>,
+) -> Result<(Vec<ManifestEntry>, Vec<ManifestEntry>)> {
+    if !(is_index(&a) && is_index(&b)) {
+        return Ok((flatten(a, &mut load)?, flatten(b, &mut load)?));
+    }
+    let a_pages = page_refs(&a)?;
+    let b_pages = page_refs(&b)?;
+    let a_ids: std::collections::BTreeSet<&ObjectId> =
+        a_pages.iter().map(|p| &p.manifest).collect();
+    let b_ids: std::collections::BTreeSet<&ObjectId> =
+        b_pages.iter().map(|p| &p.manifest).collect();
+
+    let mut a_out = Vec::new();
+    for page in a_pages.iter().filter(|p| !b_ids.contains(&p.manifest)) {
+        a_out.extend(load_page(&page.manifest, &mut load)?.entries);
+    }
+    let mut b_out = Vec::new();
+    for page in b_pages.iter().filter(|p| !a_ids.contains(&p.manifest)) {
+        b_out.extend(load_page(&page.manifest, &mut load)?.entries);
+    }
+    Ok((a_out, b_out))
+}
+
+/// Targeted edits to one directory, reading and rewriting only the pages
+/// the edits land in.
+///
+/// Pages are loaded on first touch; [`DirEdit::finish`] re-splits each
+/// touched page (pulling in the following page whenever the touched one
+/// no longer ends on a boundary) and keeps every untouched page id. The
+/// result is identical to [`build`] over the edited entry list — that
+/// equivalence is what lets the server's fold and the client's scan
+/// agree on ids.
+pub struct DirEdit {
+    shape: Shape,
+}
+
+enum Shape {
+    Flat(BTreeMap<String, ManifestEntryKind>),
+    Paged {
+        pages: Vec<PageRef>,
+        loaded: BTreeMap<usize, BTreeMap<String, ManifestEntryKind>>,
+        total: u64,
+    },
+}
+
+#[derive(Clone)]
+struct PageRef {
+    first: String,
+    manifest: ObjectId,
+    entries: u64,
+}
+
+impl DirEdit {
+    /// An empty directory.
+    pub fn empty() -> Self {
+        Self {
+            shape: Shape::Flat(BTreeMap::new()),
+        }
+    }
+
+    pub fn open(manifest: Manifest) -> Result<Self> {
+        if !is_index(&manifest) {
+            refuse_pages(&manifest)?;
+            return Ok(Self {
+                shape: Shape::Flat(
+                    manifest
+                        .entries
+                        .into_iter()
+                        .map(|e| (e.name, e.kind))
+                        .collect(),
+                ),
+            });
+        }
+        let pages = page_refs(&manifest)?;
+        let total = pages.iter().map(|p| p.entries).sum();
+        Ok(Self {
+            shape: Shape::Paged {
+                pages,
+                loaded: BTreeMap::new(),
+                total,
+            },
+        })
+    }
+
+    /// Number of entries the directory holds, edits included.
+    pub fn len(&self) -> u64 {
+        match &self.shape {
+            Shape::Flat(entries) => entries.len() as u64,
+            Shape::Paged { total, .. } => *total,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(
+        &mut self,
+        name: &str,
+        load: impl FnMut(&ObjectId) -> Result<Manifest>,
+    ) -> Result<Option<ManifestEntryKind>> {
+        Ok(self.entries_for(name, load)?.get(name).cloned())
+    }
+
+    pub fn set(
+        &mut self,
+        name: String,
+        kind: ManifestEntryKind,
+        load: impl FnMut(&ObjectId) -> Result<Manifest>,
+    ) -> Result<()> {
+        let added = self.entries_for(&name, load)?.insert(name, kind).is_none();
+        if added && let Shape::Paged { total, .. } = &mut self.shape {
+            *total += 1;
+        }
+        Ok(())
+    }
+
+    pub fn remove(
+        &mut self,
+        name: &str,
+        load: impl FnMut(&ObjectId) -> Result<Manifest>,
+    ) -> Result<()> {
+        let removed = self.entries_for(name, load)?.remove(name).is_some();
+        if removed && let Shape::Paged { total, .. } = &mut self.shape {
+            *total -= 1;
+        }
+        Ok(())
+    }
+
+    /// The manifest to store for the edited directory, with any new
+    /// pages written through `put`.
+    pub fn finish(
+        self,
+        mut load: impl FnMut(&ObjectId) -> Result<Manifest>,
+        mut put: impl FnMut(&Manifest) -> Result<ObjectId>,
+    ) -> Result<Manifest> {
+        let (pages, mut loaded, total) = match self.shape {
+            Shape::Flat(entries) => return build(into_entries(entries), put),
+            Shape::Paged {
+                pages,
+                loaded,
+                total,
+            } => (pages, loaded, total),
+        };
+
+        // Shrunk to a size that is not paged: write it plain, exactly as
+        // `build` would.
+        if total as usize <= PAGE_THRESHOLD {
+            let mut entries = Vec::new();
+            for (i, page) in pages.iter().enumerate() {
+                match loaded.remove(&i) {
+                    Some(edited) => entries.extend(into_entries(edited)),
+                    None => entries.extend(load_page(&page.manifest, &mut load)?.entries),
+                }
+            }
+            return build(entries, put);
+        }
+
+        let mut index = Vec::new();
+        let mut i = 0;
+        while i < pages.len() {
+            let Some(edited) = loaded.remove(&i) else {
+                index.push(ManifestEntry {
+                    name: pages[i].first.clone(),
+                    kind: ManifestEntryKind::Page {
+                        manifest: pages[i].manifest.clone(),
+                        entries: pages[i].entries,
+                    },
+                });
+                i += 1;
+                continue;
+            };
+            let mut run = into_entries(edited);
+            i += 1;
+            // A run that no longer ends on a boundary continues into the
+            // next page, touched or not.
+            while i < pages.len() && run.last().is_some_and(|e| !is_boundary(&e.name)) {
+                match loaded.remove(&i) {
+                    Some(edited) => run.extend(into_entries(edited)),
+                    None => run.extend(load_page(&pages[i].manifest, &mut load)?.entries),
+                }
+                i += 1;
+            }
+            for page in split(run) {
+                index.push(write_page(page, &mut put)?);
+            }
+        }
+        Ok(Manifest {
+            version: 1,
+            entries: index,
+        })
+    }
+
+    fn entries_for(
+        &mut self,
+        name: &str,
+        mut load: impl FnMut(&ObjectId) -> Result<Manifest>,
+    ) -> Result<&mut BTreeMap<String, ManifestEntryKind>> {
+        match &mut self.shape {
+            Shape::Flat(entries) => Ok(entries),
+            Shape::Paged { pages, loaded, .. } => {
+                let i = page_of(pages, name);
+                match loaded.entry(i) {
+                    std::collections::btree_map::Entry::Occupied(page) => Ok(page.into_mut()),
+                    std::collections::btree_map::Entry::Vacant(slot) => {
+                        let page = load_page(&pages[i].manifest, &mut load)?;
+                        Ok(slot
+                            .insert(page.entries.into_iter().map(|e| (e.name, e.kind)).collect()))
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn into_entries(entries: BTreeMap<String, ManifestEntryKind>) -> Vec<ManifestEntry> {
+    entries
+        .into_iter()
+        .map(|(name, kind)| ManifestEntry { name, kind })
+        .collect()
+}
+
+/// Index of the page that holds (or would hold) `name`: the last page
+/// whose first name is not after it, or the first page.
+fn page_of(pages: &[PageRef], name: &str) -> usize {
+    pages
+        .partition_point(|p| p.first.as_str() <= name)
+        .saturating_sub(1)
+}
+
+fn page_refs(index: &Manifest) -> Result<Vec<PageRef>> {
+    index
+        .entries
+        .iter()
+        .map(|e| match &e.kind {
+            ManifestEntryKind::Page { manifest, entries } => Ok(PageRef {
+                first: e.name.clone(),
+                manifest: manifest.clone(),
+                entries: *entries,
+            }),
+            _ => bail!("page index mixes pages with entries (at {:?})", e.name),
+        })
+        .collect()
+}
+
+fn load_page(
+    id: &ObjectId,
+    load: &mut impl FnMut(&ObjectId) -> Result<Manifest>,
+) -> Result<Manifest> {
+    let page = load(id)?;
+    refuse_pages(&page)?;
+    Ok(page)
+}
+
+fn refuse_pages(manifest: &Manifest) -> Result<()> {
+    if let Some(e) = manifest
+        .entries
+        .iter()
+        .find(|e| matches!(e.kind, ManifestEntryKind::Page { .. }))
+    {
+        bail!("page entry {:?} outside a page index", e.name);
+    }
+    Ok(())
+}
+
+fn write_page(
+    entries: Vec<ManifestEntry>,
+    put: &mut impl FnMut(&Manifest) -> Result<ObjectId>,
+) -> Result<ManifestEntry> {
+    let first = entries[0].name.clone();
+    let count = entries.len() as u64;
+    let page = Manifest {
+        version: 1,
+        entries,
+    };
+    Ok(ManifestEntry {
+        name: first,
+        kind: ManifestEntryKind::Page {
+            manifest: put(&page)?,
+            entries: count,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn file(name: &str) -> ManifestEntry {
+        ManifestEntry {
+            name: name.to_string(),
+            kind: ManifestEntryKind::File {
+                blob: ObjectId(blake3::hash(name.as_bytes()).to_hex().to_string()),
+                mode: 0o644,
+                size: 1,
+            },
+        }
+    }
+
+    fn names(n: usize) -> Vec<ManifestEntry> {
+        (0..n).map(|i| file(&format!("f{i:06}"))).collect()
+    }
+
+    #[derive(Default)]
+    struct Mem(HashMap<ObjectId, Manifest>);
+
+    impl Mem {
+        fn put(&mut self, m: &Manifest) -> Result<ObjectId> {
+            let id = ObjectId(
+                blake3::hash(&crate::encoding::encode_manifest(m))
+                    .to_hex()
+                    .to_string(),
+            );
+            self.0.insert(id.clone(), m.clone());
+            Ok(id)
+        }
+    }
+
+    #[test]
+    fn small_directories_are_written_exactly_as_before() {
+        let mut mem = Mem::default();
+        let entries = names(PAGE_THRESHOLD);
+        let m = build(entries.clone(), |m| mem.put(m)).unwrap();
+        assert_eq!(m.entries, entries);
+        assert!(mem.0.is_empty(), "no pages written");
+    }
+
+    #[test]
+    fn large_directories_page_and_flatten_back() {
+        let mut mem = Mem::default();
+        let entries = names(PAGE_THRESHOLD * 3);
+        let index = build(entries.clone(), |m| mem.put(m)).unwrap();
+        assert!(is_index(&index));
+        assert!(index.entries.len() > 1);
+        let flat = flatten(index.clone(), |id| Ok(mem.0[id].clone())).unwrap();
+        assert_eq!(flat, entries);
+
+        let hit = lookup(&index, "f001234", |id| Ok(mem.0[id].clone())).unwrap();
+        assert_eq!(hit, Some(file("f001234").kind));
+        assert_eq!(
+            lookup(&index, "nope", |id| Ok(mem.0[id].clone())).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn targeted_edits_match_a_full_rebuild_and_keep_untouched_pages() {
+        let mut mem = Mem::default();
+        let mut entries = names(PAGE_THRESHOLD * 3);
+        let index = build(entries.clone(), |m| mem.put(m)).unwrap();
+
+        // Delete every boundary entry in the first half (forcing page
+        // merges), insert before the first name, and modify one entry.
+        let boundaries: Vec<String> = entries[..entries.len() / 2]
+            .iter()
+            .filter(|e| is_boundary(&e.name))
+            .map(|e| e.name.clone())
+            .collect();
+        assert!(!boundaries.is_empty());
+        let changed = entries
+            .iter()
+            .map(|e| e.name.clone())
+            .find(|n| !boundaries.contains(n))
+            .unwrap();
+        let snapshot = mem.0.clone();
+        let load = |id: &ObjectId| -> Result<Manifest> { Ok(snapshot[id].clone()) };
+
+        let mut edit = DirEdit::open(index.clone()).unwrap();
+        for name in &boundaries {
+            edit.remove(name, load).unwrap();
+        }
+        edit.set("a-first".into(), file("a-first").kind, load)
+            .unwrap();
+        edit.set(changed.clone(), file("changed").kind, load)
+            .unwrap();
+        let edited = edit.finish(load, |m| mem.put(m)).unwrap();
+
+        entries.retain(|e| !boundaries.contains(&e.name));
+        entries.push(file("a-first"));
+        for e in &mut entries {
+            if e.name == changed {
+                e.kind = file("changed").kind;
+            }
+        }
+        let rebuilt = build(entries, |m| mem.put(m)).unwrap();
+        assert_eq!(edited, rebuilt, "edit is indistinguishable from a rebuild");
+
+        // The tail pages were not touched.
+        let last = index.entries.last().unwrap();
+        assert_eq!(edited.entries.last(), Some(last));
+    }
+
+    #[test]
+    fn shrinking_below_the_threshold_unpages() {
+        let mut mem = Mem::default();
+        let entries = names(PAGE_THRESHOLD + 1);
+        let index = build(entries.clone(), |m| mem.put(m)).unwrap();
+        let snapshot = mem.0.clone();
+        let load = |id: &ObjectId| -> Result<Manifest> { Ok(snapshot[id].clone()) };
+        let mut edit = DirEdit::open(index).unwrap();
+        edit.remove("f000000", load).unwrap();
+        let out = edit.finish(load, |m| mem.put(m)).unwrap();
+        assert!(!is_index(&out));
+        assert_eq!(out.entries, entries[1..].to_vec());
+    }
+
+    #[test]
+    fn shared_pages_are_skipped_when_diffing() {
+        let mut mem = Mem::default();
+        let entries = names(PAGE_THRESHOLD * 2);
+        let a = build(entries.clone(), |m| mem.put(m)).unwrap();
+        let mut changed = entries.clone();
+        changed[10].kind = file("other").kind;
+        let b = build(changed, |m| mem.put(m)).unwrap();
+        let (left, right) = unshared_entries(a, b, |id| Ok(mem.0[id].clone())).unwrap();
+        assert!(left.len() < entries.len() / 2, "only the changed page read");
+        assert_eq!(left.len(), right.len());
+        assert!(left.iter().any(|e| e.name == "f000010"));
+    }
+
+    #[test]
+    fn pages_outside_an_index_are_refused() {
+        let bogus = Manifest {
+            version: 1,
+            entries: vec![
+                file("a"),
+                ManifestEntry {
+                    name: "b".into(),
+                    kind: ManifestEntryKind::Page {
+                        manifest: ObjectId("00".into()),
+                        entries: 1,
+                    },
+                },
+            ],
+        };
+        assert!(flatten(bogus, |_| bail!("unreachable")).is_err());
+    }
+}
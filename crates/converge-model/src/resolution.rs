@@ -30,6 +30,9 @@ pub enum VariantKeyKind {
         target: String,
     },
     Tombstone,
+    MovedTo {
+        path: String,
+    },
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -39,6 +42,34 @@ pub enum ResolutionDecision {
     Index(u32),
     /// Stable decision: a key derived from variant content.
     Key(VariantKey),
+    /// None of the variants: content the resolver wrote by hand.
+    Content(ResolvedContent),
+}
+
+/// A hand-edited resolution (doc 17 §2b). The content is captured into
+/// the store like any file, and the decision names every variant it
+/// replaces, so the record keeps the link between the conflict and what
+/// settled it — and a superposition that gained a variant since is
+/// caught as stale rather than silently overwritten.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResolvedContent {
+    pub content: ResolvedContentKind,
+    pub supersedes: Vec<VariantKey>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ResolvedContentKind {
+    File {
+        blob: ObjectId,
+        mode: u32,
+        size: u64,
+    },
+    ChunkedFile {
+        recipe: ObjectId,
+        mode: u32,
+        size: u64,
+    },
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -48,6 +79,7 @@ pub struct Resolution {
     pub root_manifest: ObjectId,
     pub created_at: String,
 
-    /// Path -> selected decision (v1 index or v2 key)
+    /// Path -> selected decision (v1 index, v2 key, v3 hand-edited
+    /// content)
     pub decisions: std::collections::BTreeMap<String, ResolutionDecision>,
 }
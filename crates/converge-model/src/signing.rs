@@ -0,0 +1,97 @@
+//! What a signed publish signs (doc 19 §12).
+//!
+//! The client signs and the server verifies, with different crates on
+//! each side, so the bytes under the signature are defined once here and
+//! nowhere else. Both payloads open with a domain tag: a signature made
+//! for one purpose must never verify as the other, and a snap id is
+//! exactly the kind of short string that could turn up in both.
+
+use crate::wire::PublishRequest;
+
+/// Domain tag for a signature over a snap id.
+pub const SNAP_DOMAIN: &[u8] = b"converge-snap-sig-v1\n";
+
+/// Domain tag for a signature over a publish request.
+pub const PUBLISH_DOMAIN: &[u8] = b"converge-publish-sig-v1\n";
+
+/// The bytes signed to vouch for a snap. The id already commits to the
+/// tree, the parents, the provenance edge, and the author (doc 17 §1),
+/// so signing the id signs all of them.
+pub fn snap_payload(snap_id: &str) -> Vec<u8> {
+    let mut out = SNAP_DOMAIN.to_vec();
+    field(&mut out, Some(snap_id));
+    out
+}
+
+/// The parts of a publish a signature covers: where it goes and what it
+/// claims to build on.
+///
+/// Two fields are left out on purpose. The wire version describes the
+/// envelope, not the intent. The lane is a route the server resolves —
+/// no lane means the publisher's personal one — so the record holds a
+/// different value from the request, and a claim that covered it could
+/// not be re-checked from the record later. The lane is bounded by the
+/// publisher's own write access either way.
+///
+/// The server never sees a [`PublishRequest`] past its handler, so the
+/// claim is assembled from whichever side holds the fields.
+#[derive(Clone, Copy, Debug)]
+pub struct PublishClaim<'a> {
+    pub repo_id: &'a str,
+    pub scope_id: &'a str,
+    pub gate_id: &'a str,
+    pub snap_id: &'a str,
+    pub base_candidate_id: Option<&'a str>,
+    pub notes: Option<&'a str>,
+}
+
+impl<'a> From<&'a PublishRequest> for PublishClaim<'a> {
+    fn from(request: &'a PublishRequest) -> Self {
+        Self {
+            repo_id: &request.repo_id,
+            scope_id: &request.scope_id,
+            gate_id: &request.gate_id,
+            snap_id: &request.snap.id,
+            base_candidate_id: request.base_candidate_id.as_deref(),
+            notes: request.notes.as_deref(),
+        }
+    }
+}
+
+/// The bytes signed to vouch for a publish.
+pub fn publish_payload(claim: &PublishClaim<'_>) -> Vec<u8> {
+    let mut out = PUBLISH_DOMAIN.to_vec();
+    field(&mut out, Some(claim.repo_id));
+    field(&mut out, Some(claim.scope_id));
+    field(&mut out, Some(claim.gate_id));
+    field(&mut out, Some(claim.snap_id));
+    field(&mut out, claim.base_candidate_id);
+    field(&mut out, claim.notes);
+    out
+}
+
+/// Presence byte, then length-prefixed bytes — the same discipline as
+/// snap identity, for the same reason: `None` and `Some("")` are
+/// different claims and must sign differently.
+fn field(out: &mut Vec<u8>, value: Option<&str>) {
+    out.push(u8::from(value.is_some()));
+    let value = value.unwrap_or("");
+    out.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    out.extend_from_slice(value.as_bytes());
+}
+
+/// Lowercase hex, as signatures and verifying keys travel on the wire.
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Inverse of [`to_hex`]. `None` for odd lengths or non-hex digits.
+pub fn from_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
@@ -10,6 +10,21 @@ pub struct SnapStats {
     pub bytes: u64,
 }
 
+/// Who captured a snap (arch doc 17 §1). Part of identity: the same
+/// tree over the same head captured by two people is two snaps.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapAuthor {
+    /// Identity subject, as the server knows it — the subject a token
+    /// for this person resolves to, which is what upload checks it
+    /// against.
+    pub subject: String,
+    /// How the person wants to be shown. Free text.
+    pub display_name: String,
+    /// The personal key this machine held at capture, when it held one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<String>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SnapRecord {
     pub version: u32,
@@ -26,6 +41,16 @@ pub struct SnapRecord {
     /// already on disk carry the old field name.
     #[serde(alias = "derived_from_bundle")]
     pub derived_from_candidate: Option<String>,
+    /// Absent on records captured without a login, and on every record
+    /// written before authors existed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<SnapAuthor>,
+    /// The snap whose change this one replays (`pick`, `rebase`; arch
+    /// doc 15 §5h). That snap's author made the change; this record's
+    /// author replayed it. A reference, not a parent: the replayed
+    /// snap's lineage is not this one's.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replayed_from: Option<String>,
     pub message: Option<String>,
     /// Why captured: "explicit" (user verb) or "automatic" (watcher).
     /// Metadata only — never part of identity.
@@ -34,6 +59,24 @@ pub struct SnapRecord {
     pub stats: SnapStats,
 }
 
+/// Uncaptured edits set aside by `converge shelve` (arch doc 15 §5e).
+/// Off lineage: no snap names it, nothing publishes it, and thinning
+/// never sees it. Named, so there can be more than one.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ShelfRecord {
+    pub name: String,
+    pub created_at: String,
+    /// The head the edits were made over.
+    pub base: String,
+    /// That head's tree: the common ancestor when unshelving over a
+    /// head that has since moved.
+    pub base_root: ObjectId,
+    /// The base with the shelved edits applied.
+    pub root_manifest: ObjectId,
+    /// The paths the edits touch, sorted.
+    pub paths: Vec<String>,
+}
+
 fn default_trigger() -> String {
     "explicit".to_string()
 }
@@ -54,16 +97,28 @@ pub struct FileRecipe {
     pub chunks: Vec<FileRecipeChunk>,
 }
 
-/// Identity = content + lineage (arch doc 17 §1). Timestamp and message
-/// are metadata: recapturing an unchanged tree over the same head yields
-/// the same id, and messages stay editable after capture.
+/// Identity = content + lineage + author (arch doc 17 §1). Timestamp and
+/// message are metadata: recapturing an unchanged tree over the same head
+/// yields the same id, and messages stay editable after capture.
+///
+/// An anonymous record is derived under `converge-snap-v4`, exactly as
+/// before authors existed, so history captured without a login keeps its
+/// ids. Only an authored record takes `converge-snap-v5`; the tags differ,
+/// so the two derivations cannot collide. A replay reference is hashed
+/// last, and only when there is one, which leaves every other id as it
+/// was.
 pub fn compute_snap_id(
     root_manifest: &ObjectId,
     parents: &[String],
     derived_from_candidate: Option<&str>,
+    author: Option<&SnapAuthor>,
+    replayed_from: Option<&str>,
 ) -> String {
     let mut hasher = blake3::Hasher::new();
-    hasher.update(b"converge-snap-v4\n");
+    hasher.update(match author {
+        Some(_) => b"converge-snap-v5\n",
+        None => b"converge-snap-v4\n",
+    });
     hasher.update(root_manifest.as_str().as_bytes());
     hasher.update(b"\n");
     // Length-prefixed parents: a separator-joined list lets differing
@@ -79,9 +134,24 @@ pub fn compute_snap_id(
     // provenance edge shared an id with an honest record that claimed
     // none. Records are write-once, so the malformed one would have
     // squatted the id and locked the real snap out.
-    hasher.update(&[u8::from(derived_from_candidate.is_some())]);
-    let derived = derived_from_candidate.unwrap_or("");
-    hasher.update(&(derived.len() as u64).to_le_bytes());
-    hasher.update(derived.as_bytes());
+    optional_field(&mut hasher, derived_from_candidate);
+    // The author gets the same discipline, field by field, so no two
+    // authors' fields can run together into the same bytes.
+    if let Some(author) = author {
+        optional_field(&mut hasher, Some(&author.subject));
+        optional_field(&mut hasher, Some(&author.display_name));
+        optional_field(&mut hasher, author.key_id.as_deref());
+    }
+    if replayed_from.is_some() {
+        optional_field(&mut hasher, replayed_from);
+    }
     hasher.finalize().to_hex().to_string()
 }
+
+/// Presence byte, then length-prefixed bytes.
+fn optional_field(hasher: &mut blake3::Hasher, value: Option<&str>) {
+    hasher.update(&[u8::from(value.is_some())]);
+    let value = value.unwrap_or("");
+    hasher.update(&(value.len() as u64).to_le_bytes());
+    hasher.update(value.as_bytes());
+}
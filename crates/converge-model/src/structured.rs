@@ -0,0 +1,526 @@
+//! `structured-merge` (doc 17 §4b): key-level three-way merge of JSON,
+//! TOML and YAML documents.
+//!
+//! Config files are where two lanes most often touch the same file
+//! without touching the same thing: one adds a dependency, the other
+//! bumps a timeout. A line merge sees two edits near each other and gives
+//! up; a whole-file strategy superposes on principle. Parsed, they are
+//! two different keys, and merging them is not a judgement call.
+//!
+//! Everything here is pure text in, text out. The fold hands over the
+//! diff3 ancestor (empty when there is none) and the variants; `None`
+//! back means "not mine to decide", and the caller superposes the
+//! original variants.
+
+/// A format this strategy can parse, chosen by file extension. Content
+/// sniffing would make the same bytes merge differently depending on
+/// what they happened to look like.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Format {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl Format {
+    pub(crate) fn for_path(path: &str) -> Option<Self> {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        let (_, extension) = name.rsplit_once('.')?;
+        match extension.to_ascii_lowercase().as_str() {
+            "json" => Some(Format::Json),
+            "toml" => Some(Format::Toml),
+            "yaml" | "yml" => Some(Format::Yaml),
+            _ => None,
+        }
+    }
+}
+
+/// A document as the merge sees it: tables it can descend into, and
+/// everything else as a leaf compared whole. Arrays are leaves on
+/// purpose — two lanes appending to the same list is a conflict a person
+/// should see, not an order this code should invent.
+#[derive(Clone, Debug, PartialEq)]
+enum Tree<L> {
+    Table(Vec<(String, Tree<L>)>),
+    Leaf(L),
+}
+
+/// Merge `variants` key by key against `base`, folded pairwise in input
+/// order like the line merge. `None` when any document fails to parse or
+/// two variants change the same key differently.
+///
+/// The result is re-serialised rather than patched into one variant's
+/// text, so it is a function of the parsed documents alone: the same
+/// inputs give the same bytes, which is what keeps the candidate id
+/// reproducible. The price is formatting and comments, which do not
+/// survive the round trip.
+///
+/// JSON objects keep document order from fold version 3 (doc 17 §2a);
+/// a replay of an older candidate sorts them, as it was built.
+pub(crate) fn merge(
+    format: Format,
+    base: &str,
+    variants: &[String],
+    fold_version: u32,
+) -> Option<String> {
+    match format {
+        Format::Json if fold_version < 3 => merge_with::<SortedJson>(base, variants),
+        Format::Json => merge_with::<Json>(base, variants),
+        Format::Toml => merge_with::<Toml>(base, variants),
+        Format::Yaml => merge_with::<Yaml>(base, variants),
+    }
+}
+
+trait Codec {
+    type Leaf: Clone + PartialEq;
+    fn parse(text: &str) -> Option<Tree<Self::Leaf>>;
+    fn render(tree: &Tree<Self::Leaf>) -> Option<String>;
+}
+
+fn merge_with<C: Codec>(base: &str, variants: &[String]) -> Option<String> {
+    // A path both sides created has no ancestor; an empty table is the
+    // ancestor that makes every key an addition.
+    let base = if base.trim().is_empty() {
+        Tree::Table(Vec::new())
+    } else {
+        C::parse(base)?
+    };
+    let mut parsed = variants.iter().map(|text| C::parse(text));
+    let mut merged = parsed.next()??;
+    for variant in parsed {
+        merged = merge3(Some(&base), Some(&merged), Some(&variant?))??;
+    }
+    C::render(&merged)
+}
+
+/// Three-way merge of one value. The outer `None` is a conflict; the
+/// inner one is a key that ends up absent.
+fn merge3<L: Clone + PartialEq>(
+    base: Option<&Tree<L>>,
+    ours: Option<&Tree<L>>,
+    theirs: Option<&Tree<L>>,
+) -> Option<Option<Tree<L>>> {
+    if ours == theirs || base == theirs {
+        return Some(ours.cloned());
+    }
+    if base == ours {
+        return Some(theirs.cloned());
+    }
+    let (Some(Tree::Table(ours)), Some(Tree::Table(theirs))) = (ours, theirs) else {
+        return None;
+    };
+    let base: &[(String, Tree<L>)] = match base {
+        Some(Tree::Table(entries)) => entries,
+        // Both sides turned a value (or nothing) into a table: every key
+        // in them is new.
+        _ => &[],
+    };
+    // Our key order, then keys only they added, in theirs. Every codec
+    // keeps document order, so this is close to what either side would
+    // have written, and deterministic.
+    let mut keys: Vec<&str> = ours.iter().map(|(k, _)| k.as_str()).collect();
+    keys.extend(
+        theirs
+            .iter()
+            .map(|(k, _)| k.as_str())
+            .filter(|k| lookup(ours, k).is_none()),
+    );
+    let mut out = Vec::new();
+    for key in keys {
+        if let Some(value) = merge3(lookup(base, key), lookup(ours, key), lookup(theirs, key))? {
+            out.push((key.to_string(), value));
+        }
+    }
+    Some(Some(Tree::Table(out)))
+}
+
+fn lookup<'a, L>(entries: &'a [(String, Tree<L>)], key: &str) -> Option<&'a Tree<L>> {
+    entries.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+}
+
+/// JSON through `JsonValue`, whose objects keep document order. Without
+/// serde_json's `preserve_order` its maps sort their keys, and a merge
+/// touching one key of a `package.json` would reorder the whole file.
+///
+/// An object naming a key twice is refused: which one a reader keeps is
+/// up to the reader.
+struct Json;
+
+/// A JSON value with its objects in document order. Scalars are held as
+/// `serde_json` parsed them.
+#[derive(Clone, Debug, PartialEq)]
+enum JsonValue {
+    Scalar(serde_json::Value),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl<'de> serde::Deserialize<'de> for JsonValue {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::{Error, MapAccess, SeqAccess, Visitor};
+
+        struct Ordered;
+
+        impl<'de> Visitor<'de> for Ordered {
+            type Value = JsonValue;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a JSON value")
+            }
+
+            fn visit_bool<E>(self, v: bool) -> Result<JsonValue, E> {
+                Ok(JsonValue::Scalar(v.into()))
+            }
+
+            fn visit_i64<E>(self, v: i64) -> Result<JsonValue, E> {
+                Ok(JsonValue::Scalar(v.into()))
+            }
+
+            fn visit_u64<E>(self, v: u64) -> Result<JsonValue, E> {
+                Ok(JsonValue::Scalar(v.into()))
+            }
+
+            fn visit_f64<E>(self, v: f64) -> Result<JsonValue, E> {
+                Ok(JsonValue::Scalar(v.into()))
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<JsonValue, E> {
+                Ok(JsonValue::Scalar(v.into()))
+            }
+
+            fn visit_string<E>(self, v: String) -> Result<JsonValue, E> {
+                Ok(JsonValue::Scalar(v.into()))
+            }
+
+            fn visit_unit<E>(self) -> Result<JsonValue, E> {
+                Ok(JsonValue::Scalar(serde_json::Value::Null))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<JsonValue, A::Error> {
+                let mut items = Vec::new();
+                while let Some(item) = seq.next_element()? {
+                    items.push(item);
+                }
+                Ok(JsonValue::Array(items))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<JsonValue, A::Error> {
+                let mut entries: Vec<(String, JsonValue)> = Vec::new();
+                while let Some((key, value)) = map.next_entry::<String, JsonValue>()? {
+                    if entries.iter().any(|(k, _)| *k == key) {
+                        return Err(A::Error::custom(format!("duplicate key {key:?}")));
+                    }
+                    entries.push((key, value));
+                }
+                Ok(JsonValue::Object(entries))
+            }
+        }
+
+        deserializer.deserialize_any(Ordered)
+    }
+}
+
+impl serde::Serialize for JsonValue {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+        match self {
+            JsonValue::Scalar(value) => value.serialize(serializer),
+            JsonValue::Array(items) => serializer.collect_seq(items),
+            JsonValue::Object(entries) => {
+                let mut map = serializer.serialize_map(Some(entries.len()))?;
+                for (key, value) in entries {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+impl Codec for Json {
+    type Leaf = JsonValue;
+
+    fn parse(text: &str) -> Option<Tree<Self::Leaf>> {
+        fn tree(value: JsonValue) -> Tree<JsonValue> {
+            match value {
+                JsonValue::Object(entries) => {
+                    Tree::Table(entries.into_iter().map(|(k, v)| (k, tree(v))).collect())
+                }
+                other => Tree::Leaf(other),
+            }
+        }
+        serde_json::from_str(text).ok().map(tree)
+    }
+
+    fn render(tree: &Tree<Self::Leaf>) -> Option<String> {
+        fn value(tree: &Tree<JsonValue>) -> JsonValue {
+            match tree {
+                Tree::Table(entries) => {
+                    JsonValue::Object(entries.iter().map(|(k, v)| (k.clone(), value(v))).collect())
+                }
+                Tree::Leaf(leaf) => leaf.clone(),
+            }
+        }
+        let mut text = serde_json::to_string_pretty(&value(tree)).ok()?;
+        text.push('\n');
+        Some(text)
+    }
+}
+
+/// JSON as fold versions before 3 merged it: read into `serde_json`'s
+/// sorted map, so keys come out sorted and a repeated key keeps its last
+/// value. Kept so `verify` reproduces those candidates.
+struct SortedJson;
+
+impl Codec for SortedJson {
+    type Leaf = serde_json::Value;
+
+    fn parse(text: &str) -> Option<Tree<Self::Leaf>> {
+        fn tree(value: serde_json::Value) -> Tree<serde_json::Value> {
+            match value {
+                serde_json::Value::Object(map) => {
+                    Tree::Table(map.into_iter().map(|(k, v)| (k, tree(v))).collect())
+                }
+                other => Tree::Leaf(other),
+            }
+        }
+        serde_json::from_str(text).ok().map(tree)
+    }
+
+    fn render(tree: &Tree<Self::Leaf>) -> Option<String> {
+        fn value(tree: &Tree<serde_json::Value>) -> serde_json::Value {
+            match tree {
+                Tree::Table(entries) => serde_json::Value::Object(
+                    entries.iter().map(|(k, v)| (k.clone(), value(v))).collect(),
+                ),
+                Tree::Leaf(leaf) => leaf.clone(),
+            }
+        }
+        let mut text = serde_json::to_string_pretty(&value(tree)).ok()?;
+        text.push('\n');
+        Some(text)
+    }
+}
+
+struct Toml;
+
+impl Codec for Toml {
+    type Leaf = toml::Value;
+
+    fn parse(text: &str) -> Option<Tree<Self::Leaf>> {
+        fn tree(value: toml::Value) -> Tree<toml::Value> {
+            match value {
+                toml::Value::Table(table) => {
+                    Tree::Table(table.into_iter().map(|(k, v)| (k, tree(v))).collect())
+                }
+                other => Tree::Leaf(other),
+            }
+        }
+        toml::from_str::<toml::Value>(text).ok().map(tree)
+    }
+
+    fn render(tree: &Tree<Self::Leaf>) -> Option<String> {
+        fn value(tree: &Tree<toml::Value>) -> toml::Value {
+            match tree {
+                Tree::Table(entries) => {
+                    toml::Value::Table(entries.iter().map(|(k, v)| (k.clone(), value(v))).collect())
+                }
+                Tree::Leaf(leaf) => leaf.clone(),
+            }
+        }
+        toml::to_string(&value(tree)).ok()
+    }
+}
+
+/// YAML through `yaml-rust2`, whose mappings keep document order.
+///
+/// A mapping is a table only when every key is a plain string; numeric,
+/// boolean or complex keys would not survive being re-keyed by name, so
+/// such a document is refused and falls back like any other parse failure.
+/// So are merge keys (`<<`), which the loader does not implement, and a
+/// stream of more than one document. Aliases are expanded by the
+/// loader: the merged file carries the values, not the anchors that tied
+/// them together.
+struct Yaml;
+
+impl Codec for Yaml {
+    type Leaf = yaml_rust2::Yaml;
+
+    fn parse(text: &str) -> Option<Tree<Self::Leaf>> {
+        fn tree(value: yaml_rust2::Yaml) -> Option<Tree<yaml_rust2::Yaml>> {
+            match value {
+                yaml_rust2::Yaml::Hash(hash) => hash
+                    .into_iter()
+                    .map(|(key, value)| match key {
+                        // `<<` is a merge key to other readers; the loader
+                        // keeps it as a plain key, and writing it back
+                        // quoted would change what the file means.
+                        yaml_rust2::Yaml::String(key) if key != "<<" => Some((key, tree(value)?)),
+                        _ => None,
+                    })
+                    .collect::<Option<_>>()
+                    .map(Tree::Table),
+                // An alias to nothing, or a scalar that contradicts its tag.
+                yaml_rust2::Yaml::BadValue | yaml_rust2::Yaml::Alias(_) => None,
+                other => Some(Tree::Leaf(other)),
+            }
+        }
+        let mut documents = yaml_rust2::YamlLoader::load_from_str(text).ok()?;
+        match documents.len() {
+            0 => Some(Tree::Table(Vec::new())),
+            1 => tree(documents.pop()?),
+            _ => None,
+        }
+    }
+
+    fn render(tree: &Tree<Self::Leaf>) -> Option<String> {
+        fn value(tree: &Tree<yaml_rust2::Yaml>) -> yaml_rust2::Yaml {
+            match tree {
+                Tree::Table(entries) => yaml_rust2::Yaml::Hash(
+                    entries
+                        .iter()
+                        .map(|(k, v)| (yaml_rust2::Yaml::String(k.clone()), value(v)))
+                        .collect(),
+                ),
+                Tree::Leaf(leaf) => leaf.clone(),
+            }
+        }
+        let mut text = String::new();
+        let mut emitter = yaml_rust2::YamlEmitter::new(&mut text);
+        emitter.multiline_strings(true);
+        emitter.dump(&value(tree)).ok()?;
+        let mut text = text.strip_prefix("---\n")?.to_string();
+        text.push('\n');
+        Some(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merge::FOLD_VERSION;
+
+    fn merge_yaml(base: &str, ours: &str, theirs: &str) -> Option<String> {
+        merge(
+            Format::Yaml,
+            base,
+            &[ours.to_string(), theirs.to_string()],
+            FOLD_VERSION,
+        )
+    }
+
+    #[test]
+    fn formats_come_from_the_extension() {
+        assert_eq!(Format::for_path("config/app.JSON"), Some(Format::Json));
+        assert_eq!(Format::for_path("Cargo.toml"), Some(Format::Toml));
+        assert_eq!(Format::for_path("ci/deploy.yml"), Some(Format::Yaml));
+        assert_eq!(Format::for_path("README"), None);
+        assert_eq!(Format::for_path("yaml/notes.txt"), None);
+    }
+
+    #[test]
+    fn keys_added_on_both_sides_without_an_ancestor_merge() {
+        let merged = merge(
+            Format::Json,
+            "",
+            &[r#"{"a": 1}"#.to_string(), r#"{"b": 2}"#.to_string()],
+            FOLD_VERSION,
+        );
+        assert_eq!(merged.as_deref(), Some("{\n  \"a\": 1,\n  \"b\": 2\n}\n"));
+    }
+
+    #[test]
+    fn a_key_removed_on_one_side_and_changed_on_the_other_conflicts() {
+        let base = r#"{"a": 1, "b": 2}"#;
+        let merged = merge(
+            Format::Json,
+            base,
+            &[r#"{"b": 2}"#.to_string(), r#"{"a": 3, "b": 2}"#.to_string()],
+            FOLD_VERSION,
+        );
+        assert_eq!(merged, None);
+    }
+
+    #[test]
+    fn json_keys_keep_their_document_order() {
+        let base = r#"{"zeta": 1, "alpha": {"y": 2, "x": [{"q": 1, "p": 2}]}}"#;
+        let variants = [
+            base.replace("\"zeta\": 1", "\"zeta\": 3"),
+            base.replace("}}", "}, \"mid\": 4}"),
+        ];
+        let merged = merge(Format::Json, base, &variants, FOLD_VERSION);
+        assert_eq!(
+            merged.as_deref(),
+            Some(concat!(
+                "{\n  \"zeta\": 3,\n  \"alpha\": {\n    \"y\": 2,\n    \"x\": [\n",
+                "      {\n        \"q\": 1,\n        \"p\": 2\n      }\n    ]\n  },\n",
+                "  \"mid\": 4\n}\n"
+            ))
+        );
+        // A key named twice is refused rather than guessed at.
+        let twice = r#"{"a": 1, "a": 2}"#.to_string();
+        assert_eq!(
+            merge(Format::Json, "", &[twice.clone(), twice], FOLD_VERSION),
+            None
+        );
+
+        // A candidate from an older fold replays with its keys sorted.
+        assert_eq!(
+            merge(Format::Json, base, &variants, 2).as_deref(),
+            Some(concat!(
+                "{\n  \"alpha\": {\n    \"x\": [\n      {\n        \"p\": 2,\n",
+                "        \"q\": 1\n      }\n    ],\n    \"y\": 2\n  },\n",
+                "  \"mid\": 4,\n  \"zeta\": 3\n}\n"
+            ))
+        );
+    }
+
+    #[test]
+    fn toml_keys_keep_their_document_order() {
+        let base = "zeta = 1\nalpha = 2\n";
+        let merged = merge(
+            Format::Toml,
+            base,
+            &[
+                "zeta = 3\nalpha = 2\n".to_string(),
+                "zeta = 1\nalpha = 2\nmid = 4\n".to_string(),
+            ],
+            FOLD_VERSION,
+        );
+        assert_eq!(merged.as_deref(), Some("zeta = 3\nalpha = 2\nmid = 4\n"));
+    }
+
+    #[test]
+    fn yaml_round_trips_block_values_and_drops_comments() {
+        let base = "# top\nname: build\nrun: |\n  make\n  make test\nenv:\n  CI: true\n";
+        let ours = base.replace("CI: true", "CI: false");
+        let theirs = base.replace("name: build", "name: check");
+        assert_eq!(
+            merge_yaml(base, &ours, &theirs).as_deref(),
+            Some("name: check\nrun: |\n  make\n  make test\nenv:\n  CI: false\n")
+        );
+    }
+
+    #[test]
+    fn yaml_that_cannot_be_keyed_by_name_is_refused() {
+        for text in [
+            "a: 1\n---\nb: 2\n",
+            "a:\n\tb: 1\n",
+            "1: one\n",
+            "a: 1\na: 2\n",
+            "a: *nowhere\n",
+            "base: &b\n  x: 1\njob:\n  <<: *b\n",
+        ] {
+            assert_eq!(Yaml::parse(text), None, "{text:?}");
+        }
+    }
+
+    #[test]
+    fn yaml_aliases_are_merged_as_their_values() {
+        let base = "retries: &r 1\njob:\n  retries: *r\n  name: build\n";
+        let ours = base.replace("name: build", "name: check");
+        let theirs = base.replace("&r 1", "&r 2");
+        let merged = merge_yaml(base, &ours, &theirs).expect("merges");
+        assert_eq!(merged, "retries: 2\njob:\n  retries: 2\n  name: check\n");
+    }
+}
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 
+use crate::compression::ObjectEncoding;
 use crate::ids::ObjectId;
 
 /// Protocol major version. Servers refuse unknown majors; no pre-1.0
@@ -35,6 +36,47 @@ pub struct ObjectFrame {
     pub id: ObjectId,
     #[serde(with = "serde_bytes")]
     pub bytes: Vec<u8>,
+    /// How `bytes` is encoded (doc 16 §1f); `id` is always the hash of
+    /// the decoded object. Absent is the plain object, which is all an
+    /// older peer sends and all it is ever sent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<ObjectEncoding>,
+}
+
+/// The most an encoded frame may decode to: the server's request body
+/// limit, so encoding never carries an object that could not have been
+/// sent plain (doc 16 §1c).
+pub const MAX_FRAME_OBJECT_BYTES: u64 = 64 * 1024 * 1024;
+
+impl ObjectFrame {
+    /// A frame carrying the object as it is.
+    pub fn plain(kind: &str, id: ObjectId, bytes: Vec<u8>) -> Self {
+        Self {
+            kind: kind.to_string(),
+            id,
+            bytes,
+            encoding: None,
+        }
+    }
+
+    /// A frame carrying the object in `encoding`.
+    pub fn encoded(kind: &str, id: ObjectId, bytes: Vec<u8>, encoding: ObjectEncoding) -> Self {
+        Self {
+            encoding: Some(encoding),
+            ..Self::plain(kind, id, bytes)
+        }
+    }
+
+    /// The object this frame carries, decoded. Not yet verified: the
+    /// store it is written to checks it against `id`.
+    pub fn into_object(self) -> anyhow::Result<Vec<u8>> {
+        match self.encoding {
+            None => Ok(self.bytes),
+            Some(encoding) => {
+                crate::compression::decode(encoding, &self.bytes, MAX_FRAME_OBJECT_BYTES)
+            }
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -46,6 +88,10 @@ pub struct NegotiateRequest {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct NegotiateResponse {
     pub missing: ObjectSet,
+    /// Frame encodings the server accepts on upload (doc 16 §1f), by
+    /// name. An older server sends none, and is sent plain frames.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub encodings: Vec<String>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -65,6 +111,61 @@ pub struct PublishRequest {
     #[serde(default)]
     pub lane_id: Option<String>,
     pub notes: Option<String>,
+    /// Signatures over the snap id and this request (doc 19 §12).
+    /// Absent for an unsigned publish, which a repo may refuse.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<PublishSignature>,
+}
+
+/// The two signatures a signed publish carries, both made with the
+/// same registered key (doc 19 §12). Hex-encoded ed25519.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublishSignature {
+    pub key_id: String,
+    /// Over [`crate::signing::snap_payload`]: the signer vouches for
+    /// this exact lineage node, tree and parents included.
+    pub snap: String,
+    /// Over [`crate::signing::publish_payload`]: the signer sent this
+    /// snap into this gate, on this base.
+    pub publish: String,
+}
+
+/// What the server concluded about a publication's signature. Checked
+/// again on every read rather than remembered, so a key that is later
+/// removed stops vouching for what it signed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "status")]
+pub enum SignatureStatus {
+    Unsigned,
+    Verified {
+        key_id: String,
+    },
+    /// Signed by a key that is no longer registered. Publish refuses an
+    /// unregistered key, so it was registered when the publication was
+    /// accepted; it no longer vouches, but nothing says the signature lied.
+    KeyRetired {
+        key_id: String,
+    },
+    Invalid {
+        reason: String,
+    },
+}
+
+/// One input publication's signature status, for provenance and verify.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignatureCheck {
+    pub publication_id: String,
+    pub publisher: String,
+    #[serde(flatten)]
+    pub status: SignatureStatus,
+}
+
+/// Per-repo signing policy (doc 19 §12), admin-set like retention.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SigningPolicy {
+    /// Refuse any publish that does not carry a valid signature.
+    #[serde(default)]
+    pub require_signed: bool,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -99,6 +200,10 @@ pub struct PublicationRecord {
     pub publisher: String,
     pub created_at: String,
     pub notes: Option<String>,
+    /// As sent. Kept so provenance can re-check it later against the
+    /// keys registered then, not just the ones registered at publish.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<PublishSignature>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -127,10 +232,29 @@ pub struct CandidateRecord {
     /// Coalesce strategy recorded in provenance (doc 17 §4).
     #[serde(default)]
     pub strategy: String,
+    /// The gate's per-path rules as they stood at build time (doc 17
+    /// §4a); `strategy` is their fallback.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub strategy_rules: Vec<StrategyRule>,
+    /// The gate's lane ranking as it stood at build time (doc 17 §4d).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub lane_priority: Vec<String>,
+    /// Moves the fold detected in its inputs (doc 17 §2a), in input
+    /// order. Derived from the inputs, so not part of the id.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub renames: Vec<RenameRecord>,
+    /// The fold that built it (doc 17 §2a): 1 before rename detection.
+    /// Absent on candidates built then.
+    #[serde(default = "first_fold_version")]
+    pub fold_version: u32,
     pub status: CandidateStatus,
     pub created_at: String,
 }
 
+fn first_fold_version() -> u32 {
+    1
+}
+
 /// A registered lane (g02.007): ownership and visibility for the
 /// breadth/visibility partition. Publications may only name registered
 /// lanes; `personal/<subject>` lanes auto-provision on first use.
@@ -223,6 +347,14 @@ pub struct ExchangeIdentityRequest {
     pub id_token: String,
 }
 
+/// Who a token belongs to. How `converge login` learns the subject a
+/// workspace's snaps are authored as — the one place the client can ask
+/// rather than be told, so the two cannot disagree.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WhoAmI {
+    pub subject: String,
+}
+
 /// A freshly issued token. `token` is present exactly once.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct TokenIssued {
@@ -241,6 +373,10 @@ pub struct RegisterKeyRequest {
     pub public_key: String,
     /// Free-text hint, usually the machine it was made on.
     pub label: String,
+    /// Hex ed25519 verifying key generated alongside the age key (doc 19
+    /// §12). Absent for a key made before signing existed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signing_key: Option<String>,
 }
 
 /// A registered public key. Public by definition — this record carries
@@ -252,6 +388,8 @@ pub struct PublicKeyRecord {
     pub public_key: String,
     pub label: String,
     pub created_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signing_key: Option<String>,
 }
 
 /// An encrypted secret as the server holds it (g02.019, doc 19 §3).
@@ -528,6 +666,10 @@ pub struct VerifyReport {
     pub recomputed_root: Option<ObjectId>,
     pub recomputed_id: String,
     pub detail: String,
+    /// Per-input signature status. An invalid signature fails verify;
+    /// an unsigned input does not, unless the repo requires signing.
+    #[serde(default)]
+    pub signatures: Vec<SignatureCheck>,
 }
 
 /// A candidate plus its input publications — readable provenance.
@@ -535,6 +677,9 @@ pub struct VerifyReport {
 pub struct CandidateProvenance {
     pub candidate: CandidateRecord,
     pub inputs: Vec<PublicationRecord>,
+    /// One per input, in input order (doc 19 §12).
+    #[serde(default)]
+    pub signatures: Vec<SignatureCheck>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -554,19 +699,45 @@ pub struct GateNode {
     pub name: String,
     pub upstreams: Vec<String>,
     pub required_approvals: u32,
-    /// Coalesce strategy (doc 17 §4): "whole-file" (default) or
-    /// "text-line-merge".
+    /// Coalesce strategy (doc 17 §4): "whole-file" (default),
+    /// "text-line-merge", "structured-merge", "union-lines" or
+    /// "lane-priority".
     #[serde(default = "default_strategy")]
     pub strategy: String,
     /// Whether candidates produced by this gate may be released to channels.
     #[serde(default)]
     pub may_release: bool,
+    /// Per-path exceptions to `strategy` (doc 17 §4a), first match wins.
+    /// A path no rule matches falls back to `strategy`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub strategy_rules: Vec<StrategyRule>,
+    /// Lanes in descending priority, for `lane-priority` (doc 17 §4d):
+    /// where lanes disagree, the first one listed wins.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub lane_priority: Vec<String>,
 }
 
 fn default_strategy() -> String {
     "whole-file".to_string()
 }
 
+/// One path-pattern -> strategy rule (doc 17 §4a). A pattern without a
+/// `/` matches the file name at any depth; one with a `/` matches the
+/// path from the repo root. `*` stops at `/`, `**` does not.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StrategyRule {
+    pub pattern: String,
+    pub strategy: String,
+}
+
+/// A file one publication moved from `from` to `to` (doc 17 §2a).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RenameRecord {
+    pub lane: String,
+    pub from: String,
+    pub to: String,
+}
+
 /// Ask the server to replace a repo's gate graph (batch 26.2).
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SetGatesRequest {
@@ -1,6 +1,6 @@
 use std::collections::HashSet;
 
-use converge_model::{ChunkParams, chunk_data};
+use converge_model::{ChunkParams, chunk_data, chunk_stream};
 
 // Deterministic pseudo-random bytes (no external RNG dep; Date/random-free).
 fn pseudo_random_bytes(len: usize, mut seed: u64) -> Vec<u8> {
@@ -81,6 +81,59 @@ fn params_recorded_in_recipe_header() {
     assert_eq!(recipe.version, converge_model::chunk_recipe_version());
 }
 
+/// A reader that hands out at most `step` bytes per call, the way a
+/// pipe or a network filesystem does.
+struct Trickle<'a> {
+    data: &'a [u8],
+    step: usize,
+}
+
+impl std::io::Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = buf.len().min(self.step).min(self.data.len());
+        buf[..n].copy_from_slice(&self.data[..n]);
+        self.data = &self.data[n..];
+        Ok(n)
+    }
+}
+
+#[test]
+fn streaming_recipe_is_bit_identical() {
+    let small = ChunkParams {
+        min_size: 64 * 1024,
+        avg_size: 256 * 1024,
+        max_size: 1024 * 1024,
+    };
+    for (len, params) in [
+        (0, ChunkParams::default()),
+        (100, ChunkParams::default()),
+        (10 * 1024 * 1024 + 137, ChunkParams::default()),
+        (5 * 1024 * 1024 + 1, small),
+    ] {
+        let data = pseudo_random_bytes(len, len as u64 + 3);
+        let (whole, blobs) = chunk_data(&data, params);
+        for step in [usize::MAX, 4096 + 7] {
+            let mut seen = Vec::new();
+            let streamed = chunk_stream(Trickle { data: &data, step }, params, |id, chunk| {
+                assert!(chunk.len() <= params.max_size as usize);
+                seen.push((id.clone(), chunk.to_vec()));
+                Ok(())
+            })
+            .expect("chunk a slice");
+            assert_eq!(
+                converge_model::encoding::encode_recipe(&streamed),
+                converge_model::encoding::encode_recipe(&whole),
+                "{len} bytes read {step} at a time"
+            );
+            let blobs: Vec<_> = blobs
+                .iter()
+                .map(|(id, s)| (id.clone(), s.to_vec()))
+                .collect();
+            assert_eq!(seen, blobs);
+        }
+    }
+}
+
 /// Dev-only comparison (run with `cargo test -- --ignored`): canonical
 /// CBOR vs JSON on a synthetic 10k-entry manifest.
 #[test]
@@ -119,7 +172,7 @@ fn encoding_benchmark_10k_entries() {
 
 // ---- Batch 18.3: lineage identity properties ----
 
-use converge_model::{ObjectId, compute_snap_id};
+use converge_model::{ObjectId, SnapAuthor, compute_snap_id};
 
 /// Snap identity is a function of (root, parents, derived) and nothing
 /// else, and distinct triples never collide.
@@ -148,7 +201,7 @@ fn snap_identity_separates_every_distinct_lineage() {
 
     for parents in &lineages {
         for derived in [None, Some("candidate-1"), Some("candidate-2"), Some("")] {
-            let id = compute_snap_id(&root, parents, derived);
+            let id = compute_snap_id(&root, parents, derived, None, None);
             let key = (parents.clone(), derived.map(str::to_string));
             if let Some(previous) = seen.insert(id.clone(), key.clone()) {
                 assert_eq!(
@@ -157,22 +210,98 @@ fn snap_identity_separates_every_distinct_lineage() {
                 );
             }
             // Stable: the same triple always hashes the same way.
-            assert_eq!(id, compute_snap_id(&root, parents, derived));
+            assert_eq!(id, compute_snap_id(&root, parents, derived, None, None));
             // Order is part of identity, not incidental to it.
             if parents.len() == 2 && parents[0] != parents[1] {
                 let swapped = vec![parents[1].clone(), parents[0].clone()];
                 assert_ne!(
                     id,
-                    compute_snap_id(&root, &swapped, derived),
+                    compute_snap_id(&root, &swapped, derived, None, None),
                     "parent order must change identity"
                 );
             }
             // The tree is part of identity too.
             assert_ne!(
                 id,
-                compute_snap_id(&other_root, parents, derived),
+                compute_snap_id(&other_root, parents, derived, None, None),
                 "a different tree must be a different snap"
             );
         }
     }
 }
+
+/// The author is part of identity (doc 17 §1), with the same length
+/// discipline as `derived`: no author, an empty one, and authors whose
+/// fields split differently all hash apart.
+#[test]
+fn snap_identity_separates_every_distinct_author() {
+    let root = ObjectId("r".repeat(64));
+    let author = |subject: &str, name: &str, key: Option<&str>| SnapAuthor {
+        subject: subject.into(),
+        display_name: name.into(),
+        key_id: key.map(str::to_string),
+    };
+    let authors = [
+        None,
+        Some(author("", "", None)),
+        Some(author("", "", Some(""))),
+        Some(author("ab", "c", None)),
+        Some(author("a", "bc", None)),
+        Some(author("ab", "", Some("c"))),
+        Some(author("ab", "c", Some("k1"))),
+        Some(author("ab", "c", Some("k2"))),
+    ];
+    let mut seen = std::collections::HashSet::new();
+    for who in &authors {
+        let id = compute_snap_id(&root, &[], None, who.as_ref(), None);
+        assert!(
+            seen.insert(id),
+            "two distinct authors shared an id: {who:?}"
+        );
+    }
+}
+
+/// An anonymous record keeps the id it had before authors existed, so
+/// history captured without a login needs no re-deriving (doc 17 §1).
+#[test]
+fn an_anonymous_snap_keeps_its_v4_id() {
+    let root = ObjectId("r".repeat(64));
+    let parent = "p".repeat(64);
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(b"converge-snap-v4\n");
+    hasher.update(root.as_str().as_bytes());
+    hasher.update(b"\n");
+    hasher.update(&1u64.to_le_bytes());
+    hasher.update(&64u64.to_le_bytes());
+    hasher.update(parent.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(&[0]);
+    hasher.update(&0u64.to_le_bytes());
+    assert_eq!(
+        compute_snap_id(&root, &[parent], None, None, None),
+        hasher.finalize().to_hex().to_string()
+    );
+}
+
+/// Where a replayed change came from is part of identity (doc 17 §1):
+/// no reference, an empty one, and two different ones all hash apart,
+/// with an author or without.
+#[test]
+fn snap_identity_separates_every_replay_reference() {
+    let root = ObjectId("r".repeat(64));
+    let author = SnapAuthor {
+        subject: "ab".into(),
+        display_name: "c".into(),
+        key_id: None,
+    };
+    let mut seen = std::collections::HashSet::new();
+    for who in [None, Some(&author)] {
+        for replayed in [None, Some(""), Some("s1"), Some("s2")] {
+            let id = compute_snap_id(&root, &[], None, who, replayed);
+            assert!(
+                seen.insert(id),
+                "two replay references shared an id: {replayed:?}"
+            );
+        }
+    }
+}
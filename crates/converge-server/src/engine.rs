@@ -2,7 +2,7 @@ use anyhow::{Result, bail};
 use time::OffsetDateTime;
 use time::format_description::well_known::Rfc3339;
 
-use converge_model::{ObjectId, SnapRecord};
+use converge_model::{ObjectId, PublishSignature, SnapRecord, StrategyRule};
 
 use crate::authz::{AuthzContext, Capability};
 
@@ -35,6 +35,8 @@ pub struct PublishInput {
     /// `None` -> the publisher's auto-provisioned personal lane.
     pub lane_id: Option<String>,
     pub notes: Option<String>,
+    /// Checked against the publisher's registered keys (doc 19 §12).
+    pub signature: Option<PublishSignature>,
 }
 
 fn now() -> String {
@@ -51,12 +53,21 @@ mod inbox;
 mod publish;
 
 /// Deterministic candidate identity (doc 17 §3): hash(gate, W root, ordered
-/// input publication ids, strategy, merged root).
+/// input publication ids, strategy, per-path strategy rules, lane
+/// priority, fold version, merged root).
+///
+/// A gate without rules or a lane ranking, folded by version 1, hashes
+/// exactly as it did before rules and rename detection existed, so
+/// candidates built then still verify.
+#[allow(clippy::too_many_arguments)] // one per hashed field
 pub fn candidate_hash(
     gate_id: &str,
     w_root: Option<&ObjectId>,
     input_ids: &[String],
     strategy: &str,
+    strategy_rules: &[StrategyRule],
+    lane_priority: &[String],
+    fold_version: u32,
     root: Option<&ObjectId>,
 ) -> String {
     let mut hasher = blake3::Hasher::new();
@@ -68,6 +79,22 @@ pub fn candidate_hash(
         hasher.update(id.as_bytes());
     }
     hasher.update(strategy.as_bytes());
+    // Delimited, unlike the fields above: two rule lists must not hash
+    // alike by moving characters between a pattern and its strategy.
+    for rule in strategy_rules {
+        hasher.update(b"\0rule\0");
+        hasher.update(rule.pattern.as_bytes());
+        hasher.update(b"\0");
+        hasher.update(rule.strategy.as_bytes());
+    }
+    for lane in lane_priority {
+        hasher.update(b"\0lane\0");
+        hasher.update(lane.as_bytes());
+    }
+    if fold_version != 1 {
+        hasher.update(b"\0fold\0");
+        hasher.update(&fold_version.to_le_bytes());
+    }
     if let Some(root) = root {
         hasher.update(root.as_str().as_bytes());
     }
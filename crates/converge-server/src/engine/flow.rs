@@ -104,10 +104,36 @@ impl Engine<'_> {
             &snap.root_manifest,
             &snap.parents,
             snap.derived_from_candidate.as_deref(),
+            snap.author.as_ref(),
+            snap.replayed_from.as_deref(),
         );
         if expected != snap.id {
             bail!("snap record identity mismatch (expected {expected})");
         }
+        // An author is a claim, and only its subject can make it. The id
+        // covers the author, so a record already held under this id was
+        // uploaded by that subject, and sending it again — publishing a
+        // colleague's snap you fetched — changes nothing. A new record
+        // naming someone else is refused. A record with no author claims
+        // nobody and is accepted as it always was.
+        if let Some(author) = &snap.author
+            && author.subject != authz.subject()
+        {
+            if self
+                .meta
+                .get_snap_record(authz.repo_id(), &snap.id)?
+                .is_some()
+            {
+                return Ok(());
+            }
+            bail!(
+                "snap {} is authored by {}, and only {} may upload it (this token is {})",
+                snap.id,
+                author.subject,
+                author.subject,
+                authz.subject()
+            );
+        }
         // The snap's tree must be present (batch 12.2, audit M4): otherwise
         // a lane head fast-forwarded to it would dangle and never
         // materialize. Thinned *ancestors* may be absent, but the head's own
@@ -2,13 +2,15 @@
 
 use anyhow::{Result, bail};
 
-use converge_model::{CandidateStatus, ReleaseRecord, VerifyReport};
+use converge_model::{CandidateStatus, ReleaseRecord, SignatureStatus, VerifyReport};
 
 use crate::authz::{AuthzContext, Capability};
 
 use super::{candidate_hash, ensure_partition, now, require};
 
-use crate::merge::{MergeInput, merge_window};
+use converge_model::gates::StrategyPolicy;
+
+use crate::merge::{MergeInput, merge_window_outcome};
 
 use crate::storage::{BatchConflict, MetaOp, PartitionState};
 
@@ -40,6 +42,7 @@ impl Engine<'_> {
             None => None,
         };
         let mut inputs = Vec::new();
+        let mut publications = Vec::new();
         for publication_id in &candidate.inputs {
             let publication = self.meta.get_publication(publication_id)?.ok_or_else(|| {
                 anyhow::anyhow!("provenance incomplete: publication {publication_id} missing")
@@ -49,35 +52,74 @@ impl Engine<'_> {
                 None => None,
             };
             inputs.push(MergeInput {
-                lane: publication.lane_id,
+                lane: publication.lane_id.clone(),
                 base,
-                tree: publication.root_manifest,
+                tree: publication.root_manifest.clone(),
             });
+            publications.push(publication);
         }
-        let recomputed_root =
-            merge_window(self.objects, w_root.as_ref(), &inputs, &candidate.strategy)?;
+        // Against the keys registered now, not at publish (doc 19 §12).
+        let keys = self.meta.list_public_keys(&candidate.repo_id)?;
+        let signatures = crate::signatures::check_all(&keys, &publications);
+        let forged = signatures
+            .iter()
+            .find(|check| matches!(check.status, SignatureStatus::Invalid { .. }));
+        // Registered when it was published, or publish would have refused
+        // it: reported, but not a failure.
+        let retired = signatures.iter().find_map(|check| match &check.status {
+            SignatureStatus::KeyRetired { key_id } => Some((&check.publication_id, key_id)),
+            _ => None,
+        });
+        // The rules, ranking and fold recorded at build time, not the
+        // gate's current ones or this build's fold: a retuned gate or a
+        // newer fold must not make older candidates unverifiable.
+        let policy = StrategyPolicy::new(&candidate.strategy, &candidate.strategy_rules)?
+            .with_lane_priority(&candidate.lane_priority);
+        let recomputed_root = merge_window_outcome(
+            self.objects,
+            w_root.as_ref(),
+            &inputs,
+            &policy,
+            candidate.fold_version,
+        )?
+        .root;
         let recomputed_id = candidate_hash(
             &candidate.gate_id,
             w_root.as_ref(),
             &candidate.inputs,
             &candidate.strategy,
+            &candidate.strategy_rules,
+            &candidate.lane_priority,
+            candidate.fold_version,
             Some(&recomputed_root),
         );
         let root_matches = candidate.root_manifest.as_ref() == Some(&recomputed_root);
         let id_matches = recomputed_id == candidate.candidate_id;
         Ok(VerifyReport {
-            verified: root_matches && id_matches,
+            verified: root_matches && id_matches && forged.is_none(),
             candidate_id: candidate.candidate_id.clone(),
             recorded_root: candidate.root_manifest.clone(),
             recomputed_root: Some(recomputed_root),
             recomputed_id,
-            detail: if root_matches && id_matches {
-                "replayed merge reproduces the recorded candidate".to_string()
-            } else if !root_matches {
+            detail: if !root_matches {
                 "recomputed root manifest differs from the recorded one".to_string()
-            } else {
+            } else if !id_matches {
                 "recomputed candidate id differs from the recorded one".to_string()
+            } else if let Some(check) = forged {
+                format!(
+                    "replayed merge reproduces the recorded candidate, but input {} \
+                     carries a signature that does not verify",
+                    check.publication_id
+                )
+            } else if let Some((publication_id, key_id)) = retired {
+                format!(
+                    "replayed merge reproduces the recorded candidate; input \
+                     {publication_id} was signed by key {key_id}, which is no longer registered"
+                )
+            } else {
+                "replayed merge reproduces the recorded candidate".to_string()
             },
+            signatures,
         })
     }
 
@@ -2,7 +2,9 @@
 
 use anyhow::{Result, bail};
 
-use converge_model::{CandidateStatus, LaneRecord, ObjectId, PublicationRecord};
+use converge_model::gates::StrategyPolicy;
+use converge_model::signing::PublishClaim;
+use converge_model::{CandidateStatus, LaneRecord, ObjectId, PublicationRecord, SignatureStatus};
 
 use crate::storage::StoredCandidate;
 
@@ -10,7 +12,7 @@ use crate::authz::{AuthzContext, Capability};
 
 use super::{candidate_hash, now, require};
 
-use crate::merge::MergeInput;
+use crate::merge::{FOLD_VERSION, MergeInput};
 
 use crate::storage::{BatchConflict, MetaOp, PartitionState};
 
@@ -29,6 +31,39 @@ impl Engine<'_> {
         if !graph.gates.iter().any(|g| g.gate_id == input.gate_id) {
             bail!("unknown gate {} in repo {}", input.gate_id, authz.repo_id());
         }
+        // Signature before anything is stored (doc 19 §12). An invalid
+        // one is refused whatever the policy says: it is worse than none,
+        // because it claims something false about who sent this.
+        let keys = self.meta.list_public_keys(authz.repo_id())?;
+        let claim = PublishClaim {
+            repo_id: authz.repo_id(),
+            scope_id: authz.scope_id(),
+            gate_id: &input.gate_id,
+            snap_id: &input.snap.id,
+            base_candidate_id: input.base_candidate_id.as_deref(),
+            notes: input.notes.as_deref(),
+        };
+        match crate::signatures::check(&keys, authz.subject(), &claim, input.signature.as_ref()) {
+            SignatureStatus::Invalid { reason } => bail!("publish signature refused: {reason}"),
+            // Retired only as seen from a stored publication; a key
+            // unknown at publish was never registered.
+            SignatureStatus::KeyRetired { key_id } => {
+                bail!("publish signature refused: key {key_id} is not registered in this repo")
+            }
+            SignatureStatus::Unsigned
+                if self
+                    .meta
+                    .get_signing_policy(authz.repo_id())?
+                    .require_signed =>
+            {
+                bail!(
+                    "repo {} requires signed publishes; publish with `--sign` \
+                     (after `converge key init`)",
+                    authz.repo_id()
+                )
+            }
+            SignatureStatus::Unsigned | SignatureStatus::Verified { .. } => {}
+        }
         if !self.objects.has(
             crate::storage::ObjectKind::Manifest,
             &input.snap.root_manifest,
@@ -108,6 +143,7 @@ impl Engine<'_> {
                 publisher: authz.subject().to_string(),
                 created_at,
                 notes: input.notes.clone(),
+                signature: input.signature.clone(),
             };
 
             let mut window = existing.clone();
@@ -186,7 +222,7 @@ impl Engine<'_> {
         match kind {
             K::File { blob: b, .. } => self.meta.unpin_object(repo_id, blob, &b)?,
             K::FileChunks { recipe: r, .. } => self.unpin_recipe(repo_id, &r)?,
-            K::Dir { manifest } => manifests.push(manifest),
+            K::Dir { manifest } | K::Page { manifest, .. } => manifests.push(manifest),
             K::Symlink { .. } => {}
             K::Superposition { variants } => {
                 for variant in variants {
@@ -194,7 +230,7 @@ impl Engine<'_> {
                         V::File { blob: b, .. } => self.meta.unpin_object(repo_id, blob, &b)?,
                         V::FileChunks { recipe: r, .. } => self.unpin_recipe(repo_id, &r)?,
                         V::Dir { manifest } => manifests.push(manifest),
-                        V::Symlink { .. } | V::Tombstone => {}
+                        V::Symlink { .. } | V::Tombstone | V::MovedTo { .. } => {}
                     }
                 }
             }
@@ -229,12 +265,18 @@ impl Engine<'_> {
         assert!(!window.is_empty(), "publish composes at least its own");
 
         let graph = self.meta.get_gate_graph(authz.repo_id())?;
-        let strategy = graph
+        let (strategy, strategy_rules, lane_priority) = graph
             .gates
             .iter()
             .find(|g| g.gate_id == gate_id)
-            .map(|g| g.strategy.clone())
-            .unwrap_or_else(|| "whole-file".to_string());
+            .map(|g| {
+                (
+                    g.strategy.clone(),
+                    g.strategy_rules.clone(),
+                    g.lane_priority.clone(),
+                )
+            })
+            .unwrap_or_else(|| ("whole-file".to_string(), Vec::new(), Vec::new()));
 
         let w_root = match &partition.base_candidate_id {
             Some(id) => self.meta.get_candidate(id)?.root_manifest,
@@ -265,17 +307,38 @@ impl Engine<'_> {
         );
 
         let hash_id = |root: Option<&ObjectId>| {
-            candidate_hash(gate_id, w_root.as_ref(), &input_ids, &strategy, root)
+            candidate_hash(
+                gate_id,
+                w_root.as_ref(),
+                &input_ids,
+                &strategy,
+                &strategy_rules,
+                &lane_priority,
+                FOLD_VERSION,
+                root,
+            )
         };
 
+        // The rules, lane ranking and fold are recorded on the candidate
+        // as they stand now, so `verify` replays these decisions even
+        // after the gate is retuned or the fold changes.
         let candidate = match inputs.and_then(|inputs| {
-            crate::merge::merge_window_outcome(self.objects, w_root.as_ref(), &inputs, &strategy)
+            let policy =
+                StrategyPolicy::new(&strategy, &strategy_rules)?.with_lane_priority(&lane_priority);
+            crate::merge::merge_window_outcome(
+                self.objects,
+                w_root.as_ref(),
+                &inputs,
+                &policy,
+                FOLD_VERSION,
+            )
         }) {
             // The fold reports its own superpositions (batch 15.1, audit
             // 2.2) — no second walk over the merged tree.
             Ok(outcome) => {
                 let root = outcome.root;
                 let has_superpositions = outcome.has_superpositions;
+                let renames = outcome.renames;
                 StoredCandidate {
                     candidate_id: hash_id(Some(&root)),
                     repo_id: authz.repo_id().to_string(),
@@ -286,6 +349,10 @@ impl Engine<'_> {
                     base_candidate_id: partition.base_candidate_id.clone(),
                     window: window_range,
                     strategy,
+                    strategy_rules,
+                    lane_priority,
+                    renames,
+                    fold_version: FOLD_VERSION,
                     status: CandidateStatus::Ready {
                         promotable: !has_superpositions,
                     },
@@ -302,6 +369,10 @@ impl Engine<'_> {
                 base_candidate_id: partition.base_candidate_id.clone(),
                 window: window_range,
                 strategy,
+                strategy_rules,
+                lane_priority,
+                renames: Vec::new(),
+                fold_version: FOLD_VERSION,
                 status: CandidateStatus::Failed {
                     reason: format!("{err:#}"),
                 },
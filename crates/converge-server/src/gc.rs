@@ -27,7 +27,11 @@ pub struct GcReport {
     pub pruned_events: u64,
     pub reachable_objects: u64,
     pub swept_objects: u64,
+    /// Bytes freed as stored, which is less than `swept_logical_bytes`
+    /// for objects kept encoded (doc 16 §1f).
     pub swept_bytes: u64,
+    /// The plain size of what was swept.
+    pub swept_logical_bytes: u64,
     /// Abandoned upload pins cleared. Reported so a deployment that has
     /// been leaking them can see it stop.
     pub expired_pins: u64,
@@ -245,6 +249,7 @@ impl Engine<'_> {
                 }
                 report.swept_objects += 1;
                 report.swept_bytes += bytes;
+                report.swept_logical_bytes += self.objects.logical_len(kind, &id).unwrap_or(bytes);
                 if !dry_run {
                     self.objects.delete(kind, &id)?;
                     self.meta.remove_object_associations(kind, &id)?;
@@ -285,7 +290,11 @@ impl Engine<'_> {
                 ManifestEntryKind::FileChunks { recipe, .. } => {
                     self.mark_recipe(&recipe, marked)?;
                 }
-                ManifestEntryKind::Dir { manifest } => self.mark_manifest(&manifest, marked)?,
+                // A page is a child manifest as far as reachability goes
+                // (doc 16 §1b).
+                ManifestEntryKind::Dir { manifest } | ManifestEntryKind::Page { manifest, .. } => {
+                    self.mark_manifest(&manifest, marked)?
+                }
                 ManifestEntryKind::Symlink { .. } => {}
                 ManifestEntryKind::Superposition { variants } => {
                     for variant in variants {
@@ -300,7 +309,8 @@ impl Engine<'_> {
                                 self.mark_manifest(&manifest, marked)?;
                             }
                             SuperpositionVariantKind::Symlink { .. }
-                            | SuperpositionVariantKind::Tombstone => {}
+                            | SuperpositionVariantKind::Tombstone
+                            | SuperpositionVariantKind::MovedTo { .. } => {}
                         }
                     }
                 }
@@ -23,7 +23,8 @@ mod releases;
 mod secrets;
 
 use auth::{
-    auth_config, exchange_identity, issue_token, list_keys, list_tokens, register_key, revoke_token,
+    auth_config, exchange_identity, get_signing_policy, issue_token, list_keys, list_tokens,
+    register_key, revoke_token, set_signing_policy, whoami,
 };
 use candidates::{
     approve, get_candidate, get_provenance, inbox, list_events, promote, publish, verify_candidate,
@@ -65,6 +66,7 @@ pub fn router(state: AppState) -> Router {
         .route("/api/healthz", get(healthz))
         .route("/api/auth/config", get(auth_config))
         .route("/api/auth/exchange", post(exchange_identity))
+        .route("/api/auth/whoami", get(whoami))
         .route("/api/repos/:repo/negotiate", post(negotiate))
         .route(
             "/api/repos/:repo/objects/:kind/:id",
@@ -85,6 +87,10 @@ pub fn router(state: AppState) -> Router {
         )
         .route("/api/repos/:repo/members/:subject", delete(remove_member))
         .route("/api/repos/:repo/keys", post(register_key).get(list_keys))
+        .route(
+            "/api/repos/:repo/signing",
+            get(get_signing_policy).put(set_signing_policy),
+        )
         .route(
             "/api/repos/:repo/tokens",
             post(issue_token).get(list_tokens),
@@ -86,6 +86,20 @@ pub(crate) async fn exchange_identity(
     Ok(Json(converge_model::TokenIssued { token, record }))
 }
 
+/// The subject the presented token resolves to. Any valid token may ask:
+/// it is the caller's own name, and it is what a client records as the
+/// author of its snaps — which upload then checks against this same
+/// resolution.
+pub(crate) async fn whoami(
+    State(state): State<SharedState>,
+    headers: HeaderMap,
+) -> Result<Json<converge_model::WhoAmI>, ApiError> {
+    let caller = caller(&state, &headers)?;
+    Ok(Json(converge_model::WhoAmI {
+        subject: caller.subject,
+    }))
+}
+
 /// Issue a token for the calling subject, optionally narrower than they
 /// are (batch 21.2).
 ///
@@ -247,6 +261,17 @@ pub(crate) async fn register_key(
         .parse()
         .map_err(|err| bad_request(format!("not an age recipient: {err}")))?;
     let public_key = recipient.to_string();
+    // Same rule for the verifying half: one that does not parse would
+    // only surface later, as every signed publish being refused.
+    let signing_key = request
+        .signing_key
+        .map(|key| {
+            let key = key.trim().to_ascii_lowercase();
+            crate::signatures::parse_verifying_key(&key)
+                .map(|_| key)
+                .map_err(|err| bad_request(format!("not an ed25519 verifying key: {err:#}")))
+        })
+        .transpose()?;
 
     let record = converge_model::PublicKeyRecord {
         key_id: blake3::hash(public_key.as_bytes())
@@ -258,6 +283,7 @@ pub(crate) async fn register_key(
         public_key,
         label: request.label,
         created_at: now_rfc3339()?,
+        signing_key,
     };
     state
         .meta
@@ -279,3 +305,32 @@ pub(crate) async fn list_keys(
         state.meta.list_public_keys(&repo).map_err(internal_error)?,
     ))
 }
+
+pub(crate) async fn get_signing_policy(
+    State(state): State<SharedState>,
+    Path(repo): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<converge_model::SigningPolicy>, ApiError> {
+    authorize_repo(&state, &headers, &repo, Capability::Read)?;
+    let policy = state
+        .meta
+        .get_signing_policy(&repo)
+        .map_err(internal_error)?;
+    Ok(Json(policy))
+}
+
+/// Whether the repo refuses unsigned publishes (doc 19 §12). Control-
+/// plane config like retention: admin only.
+pub(crate) async fn set_signing_policy(
+    State(state): State<SharedState>,
+    Path(repo): Path<String>,
+    headers: HeaderMap,
+    Json(policy): Json<converge_model::SigningPolicy>,
+) -> Result<Json<converge_model::SigningPolicy>, ApiError> {
+    authorize_repo(&state, &headers, &repo, Capability::Admin)?;
+    state
+        .meta
+        .set_signing_policy(&repo, &policy)
+        .map_err(|err| bad_request(format!("{err:#}")))?;
+    Ok(Json(policy))
+}
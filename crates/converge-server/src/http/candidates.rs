@@ -34,6 +34,10 @@ fn candidate_record(candidate: &StoredCandidate) -> CandidateRecord {
         base_candidate_id: candidate.base_candidate_id.clone(),
         window: candidate.window,
         strategy: candidate.strategy.clone(),
+        strategy_rules: candidate.strategy_rules.clone(),
+        lane_priority: candidate.lane_priority.clone(),
+        renames: candidate.renames.clone(),
+        fold_version: candidate.fold_version,
         status: candidate.status.clone(),
         created_at: candidate.created_at.clone(),
     }
@@ -66,6 +70,7 @@ pub(crate) async fn publish(
                 base_candidate_id: request.base_candidate_id,
                 lane_id: request.lane_id,
                 notes: request.notes,
+                signature: request.signature,
             },
         )
         .map_err(|err| bad_request(format!("{err:#}")))?;
@@ -179,9 +184,15 @@ pub(crate) async fn get_provenance(
             inputs.push(publication);
         }
     }
+    let keys = state
+        .meta
+        .list_public_keys(&candidate.repo_id)
+        .map_err(internal_error)?;
+    let signatures = crate::signatures::check_all(&keys, &inputs);
     Ok(Json(CandidateProvenance {
         candidate: candidate_record(&candidate),
         inputs,
+        signatures,
     }))
 }
 
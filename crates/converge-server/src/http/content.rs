@@ -3,12 +3,16 @@
 use axum::Json;
 use axum::body::Bytes;
 use axum::extract::Path;
+use axum::extract::Query;
 use axum::extract::State;
 use axum::http::HeaderMap;
 use axum::http::StatusCode;
 use serde_json::json;
 
-use converge_model::{NegotiateRequest, NegotiateResponse, ObjectFrame, ObjectId, ObjectSet};
+use converge_model::compression;
+use converge_model::{
+    NegotiateRequest, NegotiateResponse, ObjectEncoding, ObjectFrame, ObjectId, ObjectSet,
+};
 
 use crate::authz::Capability;
 
@@ -56,6 +60,9 @@ pub(crate) async fn negotiate(
             manifests: missing_of(ObjectKind::Manifest, &request.objects.manifests),
             recipes: missing_of(ObjectKind::Recipe, &request.objects.recipes),
         },
+        // Every server that speaks this decodes it, whether or not it
+        // stores encoded (doc 16 §1f).
+        encodings: vec![ObjectEncoding::Zstd.as_str().to_string()],
     }))
 }
 
@@ -147,18 +154,28 @@ pub(crate) async fn put_batch(
     let mut stored = 0u64;
     for frame in frames {
         let kind = parse_kind(&frame.kind)?;
-        scoped
-            .put_bytes(kind, &frame.id, &frame.bytes)
-            .map_err(|err| bad_request(format!("{err:#}")))?;
+        match frame.encoding {
+            None => scoped.put_bytes(kind, &frame.id, &frame.bytes),
+            Some(encoding) => scoped.put_encoded(kind, &frame.id, &frame.bytes, encoding),
+        }
+        .map_err(|err| bad_request(format!("{err:#}")))?;
         stored += 1;
     }
     Ok(Json(json!({"ok": true, "stored": stored})))
 }
 
+#[derive(serde::Deserialize)]
+pub(crate) struct BatchGetQuery {
+    /// Frame encodings the client decodes, comma-separated (doc 16 §1f).
+    /// Absent from every older client, which is sent plain frames.
+    accept: Option<String>,
+}
+
 /// Doc 16 §1c: batch download as CBOR frames.
 pub(crate) async fn get_batch(
     State(state): State<SharedState>,
     Path(repo): Path<String>,
+    Query(query): Query<BatchGetQuery>,
     headers: HeaderMap,
     Json(request): Json<ObjectSet>,
 ) -> Result<Bytes, ApiError> {
@@ -169,6 +186,12 @@ pub(crate) async fn get_batch(
             "batch-get of {requested} ids exceeds the {MAX_BATCH_IDS}-id cap; split the request"
         )));
     }
+    let accept = query
+        .accept
+        .as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .find_map(|name| ObjectEncoding::parse(name.trim()));
     let mut frames: Vec<ObjectFrame> = Vec::new();
     let mut collect = |kind: ObjectKind, name: &str, ids: &[ObjectId]| -> Result<(), ApiError> {
         for id in ids {
@@ -179,15 +202,35 @@ pub(crate) async fn get_batch(
             {
                 return Err(not_found_object(kind, id));
             }
-            let bytes = state
+            let Some(accept) = accept.filter(|_| kind.encodable()) else {
+                let bytes = state
+                    .objects
+                    .get(kind, id)
+                    .map_err(|err| read_failure(&state, kind, id, err))?;
+                frames.push(ObjectFrame::plain(name, id.clone(), bytes));
+                continue;
+            };
+            // Served as stored when the client takes that encoding, and
+            // encoded now when it is stored plain and encoding pays.
+            let (bytes, stored) = state
                 .objects
-                .get(kind, id)
+                .get_stored(kind, id)
                 .map_err(|err| read_failure(&state, kind, id, err))?;
-            frames.push(ObjectFrame {
-                kind: name.to_string(),
-                id: id.clone(),
-                bytes,
-            });
+            let frame = match stored {
+                Some(stored) if stored == accept => {
+                    ObjectFrame::encoded(name, id.clone(), bytes, stored)
+                }
+                Some(stored) => {
+                    let plain = compression::decode(stored, &bytes, u64::MAX)
+                        .map_err(|err| read_failure(&state, kind, id, err))?;
+                    ObjectFrame::plain(name, id.clone(), plain)
+                }
+                None => match compression::encode(&bytes) {
+                    Some(encoded) => ObjectFrame::encoded(name, id.clone(), encoded, accept),
+                    None => ObjectFrame::plain(name, id.clone(), bytes),
+                },
+            };
+            frames.push(frame);
         }
         Ok(())
     };
@@ -63,6 +63,8 @@ pub(crate) async fn create_repo(
                     required_approvals: 0,
                     strategy: "whole-file".into(),
                     may_release: true,
+                    strategy_rules: vec![],
+                    lane_priority: vec![],
                 }],
             },
         )
@@ -6,11 +6,13 @@ pub mod merge;
 #[cfg(feature = "backend-postgres")]
 pub mod meta_postgres;
 pub mod meta_sqlite;
+pub mod migrate;
 pub mod object_fs;
 #[cfg(feature = "backend-s3")]
 pub mod object_s3;
 pub mod oidc;
 pub mod retention;
+pub mod signatures;
 pub mod storage;
 
 pub use authz::{AuthzContext, Capability, authorize, satisfying_capabilities};
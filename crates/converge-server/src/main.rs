@@ -27,6 +27,8 @@ OPTIONS:
     --data-dir <DIR>              Metadata and objects (default ./converge-data)
     --metadata <URL>              External metadata backend instead of SQLite
     --objects <URL>               External object store instead of the filesystem
+    --compress-objects            Store blobs and manifests zstd-encoded where
+                                  that saves space
     --token <TOKEN=SUBJECT>       A static credential; repeatable
     --bootstrap-admin <HANDLE>    Create the first admin and print one token
     --seed-dev                    Seed a development repo
@@ -34,6 +36,11 @@ OPTIONS:
     --oidc-audience <CLIENT_ID>   Audience the provider must assert
     --oidc-subject-claim <CLAIM>  Claim to read as the subject
                                   (default preferred_username)
+    --migrate                     Bring the data dir to this build's format,
+                                  then exit instead of serving
+    --dry-run                     With --migrate: list the steps, change nothing
+    --no-backup                   With --migrate: skip the copy into
+                                  <DIR>/backups taken before the first step
     -h, --help                    Print this help
     -V, --version                 Print the version
 
@@ -48,12 +55,16 @@ fn main() -> Result<()> {
     let mut data_dir = PathBuf::from("./converge-data");
     let mut metadata: Option<String> = None;
     let mut objects_url: Option<String> = None;
+    let mut compress_objects = false;
     let mut tokens = HashMap::new();
     let mut seed_dev = false;
     let mut bootstrap_admin: Option<String> = None;
     let mut oidc_issuer: Option<String> = None;
     let mut oidc_audience: Option<String> = None;
     let mut oidc_subject_claim = "preferred_username".to_string();
+    let mut migrate = false;
+    let mut dry_run = false;
+    let mut no_backup = false;
 
     let mut args = std::env::args().skip(1);
     while let Some(arg) = args.next() {
@@ -71,6 +82,7 @@ fn main() -> Result<()> {
             }
             "--metadata" => metadata = Some(args.next().context("--metadata needs a value")?),
             "--objects" => objects_url = Some(args.next().context("--objects needs a value")?),
+            "--compress-objects" => compress_objects = true,
             "--seed-dev" => seed_dev = true,
             "--bootstrap-admin" => {
                 bootstrap_admin = Some(args.next().context("--bootstrap-admin needs a handle")?)
@@ -84,6 +96,9 @@ fn main() -> Result<()> {
             "--oidc-subject-claim" => {
                 oidc_subject_claim = args.next().context("--oidc-subject-claim needs a claim")?
             }
+            "--migrate" => migrate = true,
+            "--dry-run" => dry_run = true,
+            "--no-backup" => no_backup = true,
             // A shipped binary that answers `--help` with "unknown
             // argument" is one people give up on (batch 22.5, found
             // while smoke-testing the release artifact).
@@ -99,6 +114,13 @@ fn main() -> Result<()> {
         }
     }
 
+    if (dry_run || no_backup) && !migrate {
+        anyhow::bail!("--dry-run and --no-backup only mean something with --migrate");
+    }
+    if migrate {
+        return run_migrate(&data_dir, metadata.is_some(), dry_run, no_backup);
+    }
+
     // The stamp check comes before anything opens a database or a
     // store (batch 22.2), so a fresh deployment is stamped from its
     // first run and an existing unstamped one is left alone.
@@ -113,14 +135,17 @@ fn main() -> Result<()> {
     let fresh = !data_dir.join(converge_model::format::FORMAT_FILE).exists()
         && !data_dir.join("meta.sqlite").exists()
         && !data_dir.join("objects").exists();
+    //
+    // The stamp goes down before the check: an unstamped directory reads
+    // as version 1, which this build no longer is.
     std::fs::create_dir_all(&data_dir).context("create data dir")?;
-    converge_model::format::check_compatible(&data_dir, converge_model::format::StoreKind::Server)?;
     if fresh {
         converge_model::format::write_version(
             &data_dir,
             converge_model::format::StoreKind::Server,
         )?;
     }
+    converge_model::format::check_compatible(&data_dir, converge_model::format::StoreKind::Server)?;
 
     // Backend selection (arch 14 §2): embedded defaults; external behind
     // feature gates.
@@ -138,8 +163,18 @@ fn main() -> Result<()> {
         }
         Some(spec) => Arc::new(SqliteMetadataStore::open(std::path::Path::new(spec))?),
     };
+    // Encoding is per object and reads take either form (doc 16 §1f),
+    // so this can be switched on or off between runs.
+    let fs_objects = |root: &std::path::Path| {
+        let store = FsObjectStore::new(root);
+        if compress_objects {
+            store.with_compression()
+        } else {
+            store
+        }
+    };
     let objects: Arc<dyn converge_server::ObjectStore> = match objects_url.as_deref() {
-        None => Arc::new(FsObjectStore::new(&data_dir)),
+        None => Arc::new(fs_objects(&data_dir)),
         Some(spec) if spec.starts_with("s3://") => {
             #[cfg(feature = "backend-s3")]
             {
@@ -157,16 +192,19 @@ fn main() -> Result<()> {
                         }
                     }
                 }
-                Arc::new(converge_server::S3ObjectStore::connect(
-                    &endpoint, bucket, &region,
-                )?)
+                let store = converge_server::S3ObjectStore::connect(&endpoint, bucket, &region)?;
+                Arc::new(if compress_objects {
+                    store.with_compression()
+                } else {
+                    store
+                })
             }
             #[cfg(not(feature = "backend-s3"))]
             {
                 anyhow::bail!("compiled without backend-s3 (spec: {spec})")
             }
         }
-        Some(spec) => Arc::new(FsObjectStore::new(std::path::Path::new(spec))),
+        Some(spec) => Arc::new(fs_objects(std::path::Path::new(spec))),
     };
 
     if seed_dev {
@@ -209,6 +247,59 @@ fn main() -> Result<()> {
     })
 }
 
+/// `--migrate` (doc 16 §3): run before anything opens a database or a
+/// store, because opening is what an old data directory refuses — and
+/// never on an ordinary start, so an upgrade is a step someone takes.
+fn run_migrate(
+    data_dir: &std::path::Path,
+    external_metadata: bool,
+    dry_run: bool,
+    no_backup: bool,
+) -> Result<()> {
+    if !data_dir.join(converge_model::format::FORMAT_FILE).exists()
+        && !data_dir.join("meta.sqlite").exists()
+        && !data_dir.join("objects").exists()
+    {
+        anyhow::bail!(
+            "{} is not a Convergence data directory; there is nothing to migrate",
+            data_dir.display()
+        );
+    }
+    let report = converge_server::migrate::migrate(
+        data_dir,
+        converge_model::migrate::Options {
+            dry_run,
+            backup: !no_backup,
+        },
+    )?;
+    if report.steps.is_empty() && !report.resumed {
+        println!("data directory is already format {}", report.to);
+        return Ok(());
+    }
+    let verb = if dry_run { "would migrate" } else { "migrated" };
+    let resumed = if report.resumed { " (resumed)" } else { "" };
+    println!(
+        "{verb} {} from format {} to {}{resumed}",
+        data_dir.display(),
+        report.from,
+        report.to
+    );
+    for step in &report.steps {
+        println!("  {} -> {}: {}", step.from, step.to, step.summary);
+    }
+    match &report.backup {
+        Some(backup) if dry_run => println!("backup would go to {}", backup.display()),
+        Some(backup) => println!("backup: {}", backup.display()),
+        None => println!("no backup"),
+    }
+    // The backup is a copy of the directory. A metadata backend that
+    // lives elsewhere is the operator's to dump (guide 004).
+    if external_metadata && report.backup.is_some() {
+        println!("note: --metadata is external; it is not in that backup");
+    }
+    Ok(())
+}
+
 /// Create the first server admin and print a token for them, once.
 ///
 /// Idempotent by design: a restart with the same flag must not spray new
@@ -248,6 +339,8 @@ fn seed(meta: &dyn converge_server::MetadataStore, tokens: &HashMap<String, Stri
                     required_approvals: 0,
                     strategy: "whole-file".into(),
                     may_release: false,
+                    strategy_rules: vec![],
+                    lane_priority: vec![],
                 },
                 GateNode {
                     gate_id: "main".into(),
@@ -256,6 +349,8 @@ fn seed(meta: &dyn converge_server::MetadataStore, tokens: &HashMap<String, Stri
                     required_approvals: 0,
                     strategy: "whole-file".into(),
                     may_release: false,
+                    strategy_rules: vec![],
+                    lane_priority: vec![],
                 },
             ],
         },
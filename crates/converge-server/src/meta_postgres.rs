@@ -10,7 +10,7 @@ use postgres::{Client, NoTls};
 
 use converge_model::{
     CandidateStatus, EventRecord, GateGraph, LaneHead, LaneRecord, ObjectId, PublicationRecord,
-    ReleaseRecord, RetentionPolicy, SnapRecord,
+    ReleaseRecord, RetentionPolicy, SigningPolicy, SnapRecord,
 };
 
 use crate::storage::{BatchConflict, MetaOp, MetadataStore, PartitionState, StoredCandidate};
@@ -68,6 +68,10 @@ impl PostgresMetadataStore {
                 created_at TEXT NOT NULL,
                 PRIMARY KEY (repo_id, key_id)
             );
+            -- Keys registered before signing have no verifying half;
+            -- empty reads back as none (doc 19 §12).
+            ALTER TABLE public_keys
+                ADD COLUMN IF NOT EXISTS signing_key TEXT NOT NULL DEFAULT '';
             CREATE TABLE IF NOT EXISTS gate_graphs (
                 repo_id TEXT PRIMARY KEY, graph_json TEXT NOT NULL);
             CREATE TABLE IF NOT EXISTS scopes (
@@ -109,6 +113,8 @@ impl PostgresMetadataStore {
                 created_at TEXT NOT NULL);
             CREATE TABLE IF NOT EXISTS retention (
                 repo_id TEXT PRIMARY KEY, policy_json TEXT NOT NULL);
+            CREATE TABLE IF NOT EXISTS signing_policy (
+                repo_id TEXT PRIMARY KEY, policy_json TEXT NOT NULL);
             CREATE TABLE IF NOT EXISTS event_floors (
                 repo_id TEXT PRIMARY KEY, floor BIGINT NOT NULL);
             CREATE TABLE IF NOT EXISTS releases (
@@ -182,6 +188,35 @@ impl PostgresMetadataStore {
                 }
             }
         }
+        // Candidates from before per-path strategy rules had none (doc 17
+        // §4a). After the rename, so a table that was `bundles` gets it too.
+        client
+            .batch_execute(
+                "ALTER TABLE candidates
+                     ADD COLUMN IF NOT EXISTS strategy_rules_json TEXT NOT NULL DEFAULT '[]';",
+            )
+            .context("add candidates.strategy_rules_json")?;
+        // Likewise the lane ranking (doc 17 §4d).
+        client
+            .batch_execute(
+                "ALTER TABLE candidates
+                     ADD COLUMN IF NOT EXISTS lane_priority_json TEXT NOT NULL DEFAULT '[]';",
+            )
+            .context("add candidates.lane_priority_json")?;
+        // And the moves a build detected (doc 17 §2a).
+        client
+            .batch_execute(
+                "ALTER TABLE candidates
+                     ADD COLUMN IF NOT EXISTS renames_json TEXT NOT NULL DEFAULT '[]';",
+            )
+            .context("add candidates.renames_json")?;
+        // And the fold that built it (doc 17 §2a).
+        client
+            .batch_execute(
+                "ALTER TABLE candidates
+                     ADD COLUMN IF NOT EXISTS fold_version INTEGER NOT NULL DEFAULT 1;",
+            )
+            .context("add candidates.fold_version")?;
         {
             // Number unversioned (pre-semver) releases 0.<n>.0 by order
             // (g02.028): real numbers rather than a legacy caste.
@@ -474,13 +509,15 @@ impl MetadataStore for PostgresMetadataStore {
     fn add_public_key(&self, repo_id: &str, key: &converge_model::PublicKeyRecord) -> Result<()> {
         let mut c = self.client.lock().expect("pg lock");
         c.execute(
-            "INSERT INTO public_keys (repo_id, key_id, subject, public_key, label, created_at)
-             VALUES ($1, $2, $3, $4, $5, $6)
+            "INSERT INTO public_keys
+               (repo_id, key_id, subject, public_key, label, created_at, signing_key)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)
              ON CONFLICT (repo_id, key_id) DO UPDATE SET
                subject = EXCLUDED.subject,
                public_key = EXCLUDED.public_key,
                label = EXCLUDED.label,
-               created_at = EXCLUDED.created_at",
+               created_at = EXCLUDED.created_at,
+               signing_key = EXCLUDED.signing_key",
             &[
                 &repo_id,
                 &key.key_id,
@@ -488,6 +525,7 @@ impl MetadataStore for PostgresMetadataStore {
                 &key.public_key,
                 &key.label,
                 &key.created_at,
+                &key.signing_key.clone().unwrap_or_default(),
             ],
         )?;
         Ok(())
@@ -496,7 +534,8 @@ impl MetadataStore for PostgresMetadataStore {
     fn list_public_keys(&self, repo_id: &str) -> Result<Vec<converge_model::PublicKeyRecord>> {
         let mut c = self.client.lock().expect("pg lock");
         let rows = c.query(
-            "SELECT key_id, subject, public_key, label, created_at FROM public_keys
+            "SELECT key_id, subject, public_key, label, created_at, signing_key
+             FROM public_keys
              WHERE repo_id = $1 ORDER BY subject, created_at, key_id",
             &[&repo_id],
         )?;
@@ -508,6 +547,7 @@ impl MetadataStore for PostgresMetadataStore {
                 public_key: r.get(2),
                 label: r.get(3),
                 created_at: r.get(4),
+                signing_key: Some(r.get::<_, String>(5)).filter(|k| !k.is_empty()),
             })
             .collect())
     }
@@ -909,6 +949,29 @@ impl MetadataStore for PostgresMetadataStore {
             .unwrap_or_default())
     }
 
+    fn set_signing_policy(&self, repo_id: &str, policy: &SigningPolicy) -> Result<()> {
+        let json = serde_json::to_string(policy)?;
+        let mut c = self.client.lock().expect("pg lock");
+        c.execute(
+            "INSERT INTO signing_policy (repo_id, policy_json) VALUES ($1, $2)
+             ON CONFLICT (repo_id) DO UPDATE SET policy_json = EXCLUDED.policy_json",
+            &[&repo_id, &json],
+        )?;
+        Ok(())
+    }
+
+    fn get_signing_policy(&self, repo_id: &str) -> Result<SigningPolicy> {
+        let mut c = self.client.lock().expect("pg lock");
+        let row = c.query_opt(
+            "SELECT policy_json FROM signing_policy WHERE repo_id = $1",
+            &[&repo_id],
+        )?;
+        Ok(row
+            .map(|r| serde_json::from_str(r.get(0)))
+            .transpose()?
+            .unwrap_or_default())
+    }
+
     fn add_release(&self, release: &ReleaseRecord) -> Result<()> {
         let json = serde_json::to_string(release)?;
         let mut c = self.client.lock().expect("pg lock");
@@ -1146,7 +1209,8 @@ impl MetadataStore for PostgresMetadataStore {
             .query_opt(
                 "SELECT candidate_id, repo_id, scope_id, gate_id, inputs_json, root_manifest,
                         base_candidate_id, window_first, window_last, strategy,
-                        status_json, created_at
+                        status_json, created_at, strategy_rules_json, lane_priority_json,
+                        renames_json, fold_version
                  FROM candidates WHERE candidate_id = $1",
                 &[&candidate_id],
             )?
@@ -1161,6 +1225,10 @@ impl MetadataStore for PostgresMetadataStore {
             base_candidate_id: row.get(6),
             window: (row.get::<_, i64>(7) as u64, row.get::<_, i64>(8) as u64),
             strategy: row.get(9),
+            strategy_rules: serde_json::from_str(row.get(12))?,
+            lane_priority: serde_json::from_str(row.get(13))?,
+            renames: serde_json::from_str(row.get(14))?,
+            fold_version: row.get::<_, i32>(15) as u32,
             status: serde_json::from_str::<CandidateStatus>(row.get(10))?,
             created_at: row.get(11),
         })
@@ -230,6 +230,9 @@ pub(super) fn put_candidate_pg(
 ) -> Result<()> {
     let inputs = serde_json::to_string(&candidate.inputs)?;
     let status = serde_json::to_string(&candidate.status)?;
+    let strategy_rules = serde_json::to_string(&candidate.strategy_rules)?;
+    let lane_priority = serde_json::to_string(&candidate.lane_priority)?;
+    let renames = serde_json::to_string(&candidate.renames)?;
     let root = candidate
         .root_manifest
         .as_ref()
@@ -238,8 +241,9 @@ pub(super) fn put_candidate_pg(
         "INSERT INTO candidates
            (candidate_id, repo_id, scope_id, gate_id, inputs_json, root_manifest,
             base_candidate_id, window_first, window_last, strategy,
-            status_json, created_at)
-         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+            status_json, created_at, strategy_rules_json, lane_priority_json,
+            renames_json, fold_version)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
          ON CONFLICT (candidate_id) DO UPDATE SET
            root_manifest = EXCLUDED.root_manifest,
            status_json = EXCLUDED.status_json",
@@ -256,6 +260,10 @@ pub(super) fn put_candidate_pg(
             &candidate.strategy,
             &status,
             &candidate.created_at,
+            &strategy_rules,
+            &lane_priority,
+            &renames,
+            &(candidate.fold_version as i32),
         ],
     )?;
     Ok(())
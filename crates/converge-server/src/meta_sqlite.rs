@@ -6,7 +6,7 @@ use rusqlite::{Connection, params};
 
 use converge_model::{
     CandidateStatus, EventRecord, GateGraph, LaneHead, LaneRecord, ObjectId, PublicationRecord,
-    ReleaseRecord, RetentionPolicy, SnapRecord,
+    ReleaseRecord, RetentionPolicy, SigningPolicy, SnapRecord,
 };
 
 use crate::storage::{MetaOp, MetadataStore, PartitionState, StoredCandidate};
@@ -359,15 +359,16 @@ impl MetadataStore for SqliteMetadataStore {
         let conn = self.conn.lock().expect("meta lock");
         conn.execute(
             "INSERT OR REPLACE INTO public_keys
-             (repo_id, key_id, subject, public_key, label, created_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+             (repo_id, key_id, subject, public_key, label, created_at, signing_key)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
             params![
                 repo_id,
                 key.key_id,
                 key.subject,
                 key.public_key,
                 key.label,
-                key.created_at
+                key.created_at,
+                key.signing_key.clone().unwrap_or_default()
             ],
         )?;
         Ok(())
@@ -376,16 +377,19 @@ impl MetadataStore for SqliteMetadataStore {
     fn list_public_keys(&self, repo_id: &str) -> Result<Vec<converge_model::PublicKeyRecord>> {
         let conn = self.conn.lock().expect("meta lock");
         let mut stmt = conn.prepare(
-            "SELECT key_id, subject, public_key, label, created_at FROM public_keys
+            "SELECT key_id, subject, public_key, label, created_at, signing_key
+             FROM public_keys
              WHERE repo_id = ?1 ORDER BY subject, created_at, key_id",
         )?;
         let rows = stmt.query_map(params![repo_id], |row| {
+            let signing_key: String = row.get(5)?;
             Ok(converge_model::PublicKeyRecord {
                 key_id: row.get(0)?,
                 subject: row.get(1)?,
                 public_key: row.get(2)?,
                 label: row.get(3)?,
                 created_at: row.get(4)?,
+                signing_key: Some(signing_key).filter(|k| !k.is_empty()),
             })
         })?;
         rows.collect::<std::result::Result<_, _>>()
@@ -778,7 +782,8 @@ impl MetadataStore for SqliteMetadataStore {
         conn.query_row(
             "SELECT candidate_id, repo_id, scope_id, gate_id, inputs_json, root_manifest,
                     base_candidate_id, window_first, window_last, strategy,
-                    status_json, created_at
+                    status_json, created_at, strategy_rules_json, lane_priority_json,
+                    renames_json, fold_version
              FROM candidates WHERE candidate_id = ?1",
             params![candidate_id],
             |row| {
@@ -795,12 +800,33 @@ impl MetadataStore for SqliteMetadataStore {
                     row.get::<_, String>(9)?,
                     row.get::<_, String>(10)?,
                     row.get::<_, String>(11)?,
+                    row.get::<_, String>(12)?,
+                    row.get::<_, String>(13)?,
+                    row.get::<_, String>(14)?,
+                    row.get::<_, u32>(15)?,
                 ))
             },
         )
         .map_err(|_| anyhow!("no candidate {candidate_id}"))
         .and_then(
-            |(id, repo, scope, gate, inputs, root, base, wf, wl, strategy, status, created)| {
+            |(
+                id,
+                repo,
+                scope,
+                gate,
+                inputs,
+                root,
+                base,
+                wf,
+                wl,
+                strategy,
+                status,
+                created,
+                rules,
+                lanes,
+                renames,
+                fold_version,
+            )| {
                 Ok(StoredCandidate {
                     candidate_id: id,
                     repo_id: repo,
@@ -811,6 +837,10 @@ impl MetadataStore for SqliteMetadataStore {
                     base_candidate_id: base,
                     window: (wf as u64, wl as u64),
                     strategy,
+                    strategy_rules: serde_json::from_str(&rules)?,
+                    lane_priority: serde_json::from_str(&lanes)?,
+                    renames: serde_json::from_str(&renames)?,
+                    fold_version,
                     status: serde_json::from_str::<CandidateStatus>(&status)?,
                     created_at: created,
                 })
@@ -987,6 +1017,32 @@ impl MetadataStore for SqliteMetadataStore {
             .unwrap_or_default())
     }
 
+    fn set_signing_policy(&self, repo_id: &str, policy: &SigningPolicy) -> Result<()> {
+        let json = serde_json::to_string(policy)?;
+        let conn = self.conn.lock().expect("meta lock");
+        conn.execute(
+            "INSERT INTO signing_policy (repo_id, policy_json) VALUES (?1, ?2)
+             ON CONFLICT(repo_id) DO UPDATE SET policy_json = excluded.policy_json",
+            params![repo_id, json],
+        )?;
+        Ok(())
+    }
+
+    fn get_signing_policy(&self, repo_id: &str) -> Result<SigningPolicy> {
+        let conn = self.conn.lock().expect("meta lock");
+        let json: Option<String> = conn
+            .query_row(
+                "SELECT policy_json FROM signing_policy WHERE repo_id = ?1",
+                params![repo_id],
+                |row| row.get(0),
+            )
+            .ok();
+        Ok(json
+            .map(|j| serde_json::from_str(&j))
+            .transpose()?
+            .unwrap_or_default())
+    }
+
     fn add_release(&self, release: &ReleaseRecord) -> Result<()> {
         let json = serde_json::to_string(release)?;
         let conn = self.conn.lock().expect("meta lock");
@@ -179,12 +179,16 @@ pub(super) fn add_publication_conn(
 pub(super) fn put_candidate_conn(conn: &Connection, candidate: &StoredCandidate) -> Result<()> {
     let inputs = serde_json::to_string(&candidate.inputs)?;
     let status = serde_json::to_string(&candidate.status)?;
+    let strategy_rules = serde_json::to_string(&candidate.strategy_rules)?;
+    let lane_priority = serde_json::to_string(&candidate.lane_priority)?;
+    let renames = serde_json::to_string(&candidate.renames)?;
     conn.execute(
         "INSERT INTO candidates
            (candidate_id, repo_id, scope_id, gate_id, inputs_json, root_manifest,
             base_candidate_id, window_first, window_last, strategy,
-            status_json, created_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+            status_json, created_at, strategy_rules_json, lane_priority_json,
+            renames_json, fold_version)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)
          ON CONFLICT(candidate_id) DO UPDATE SET
            root_manifest = excluded.root_manifest,
            status_json = excluded.status_json",
@@ -203,7 +207,11 @@ pub(super) fn put_candidate_conn(conn: &Connection, candidate: &StoredCandidate)
             candidate.window.1 as i64,
             candidate.strategy,
             status,
-            candidate.created_at
+            candidate.created_at,
+            strategy_rules,
+            lane_priority,
+            renames,
+            candidate.fold_version
         ],
     )?;
     Ok(())
@@ -50,6 +50,7 @@ pub(super) fn init(conn: &Connection) -> Result<()> {
                 public_key TEXT NOT NULL,
                 label TEXT NOT NULL,
                 created_at TEXT NOT NULL,
+                signing_key TEXT NOT NULL DEFAULT '',
                 PRIMARY KEY (repo_id, key_id)
             );
             CREATE TABLE IF NOT EXISTS gate_graphs (
@@ -81,6 +82,10 @@ pub(super) fn init(conn: &Connection) -> Result<()> {
                 window_first INTEGER NOT NULL DEFAULT 0,
                 window_last INTEGER NOT NULL DEFAULT 0,
                 strategy TEXT NOT NULL DEFAULT 'whole-file',
+                strategy_rules_json TEXT NOT NULL DEFAULT '[]',
+                lane_priority_json TEXT NOT NULL DEFAULT '[]',
+                renames_json TEXT NOT NULL DEFAULT '[]',
+                fold_version INTEGER NOT NULL DEFAULT 1,
                 status_json TEXT NOT NULL,
                 created_at TEXT NOT NULL
             );
@@ -127,6 +132,10 @@ pub(super) fn init(conn: &Connection) -> Result<()> {
                 repo_id TEXT PRIMARY KEY,
                 policy_json TEXT NOT NULL
             );
+            CREATE TABLE IF NOT EXISTS signing_policy (
+                repo_id TEXT PRIMARY KEY,
+                policy_json TEXT NOT NULL
+            );
             CREATE TABLE IF NOT EXISTS event_floors (
                 repo_id TEXT PRIMARY KEY,
                 floor INTEGER NOT NULL
@@ -175,6 +184,20 @@ pub(super) fn init(conn: &Connection) -> Result<()> {
         .context("add object_pins.pinned_at")?;
     }
 
+    // Keys registered before signing existed have no verifying half.
+    // Empty reads back as `None`: such a key still seals secrets and
+    // simply cannot vouch for a publish (doc 19 §12).
+    let has_signing_key = conn
+        .prepare("SELECT signing_key FROM public_keys LIMIT 1")
+        .is_ok();
+    if !has_signing_key {
+        conn.execute(
+            "ALTER TABLE public_keys ADD COLUMN signing_key TEXT NOT NULL DEFAULT ''",
+            [],
+        )
+        .context("add public_keys.signing_key")?;
+    }
+
     // Releases predating g02.028 are channel-keyed and unversioned.
     // They get real numbers — 0.<seq>.0, deterministic — rather than
     // a "legacy" label, because a permanent unversioned caste would
@@ -240,6 +263,55 @@ pub(super) fn init(conn: &Connection) -> Result<()> {
             .with_context(|| format!("rename {table}.{from}"))?;
         }
     }
+    // Candidates built before per-path strategy rules had none, and an
+    // empty list is exactly what they were built with (doc 17 §4a).
+    // After the rename above, so a table that was `bundles` gets it too.
+    let has_strategy_rules = conn
+        .prepare("SELECT strategy_rules_json FROM candidates LIMIT 1")
+        .is_ok();
+    if !has_strategy_rules {
+        conn.execute(
+            "ALTER TABLE candidates ADD COLUMN strategy_rules_json TEXT NOT NULL DEFAULT '[]'",
+            [],
+        )
+        .context("add candidates.strategy_rules_json")?;
+    }
+    // Likewise the lane ranking (doc 17 §4d).
+    let has_lane_priority = conn
+        .prepare("SELECT lane_priority_json FROM candidates LIMIT 1")
+        .is_ok();
+    if !has_lane_priority {
+        conn.execute(
+            "ALTER TABLE candidates ADD COLUMN lane_priority_json TEXT NOT NULL DEFAULT '[]'",
+            [],
+        )
+        .context("add candidates.lane_priority_json")?;
+    }
+    // And the moves a build detected (doc 17 §2a); older builds recorded
+    // none.
+    let has_renames = conn
+        .prepare("SELECT renames_json FROM candidates LIMIT 1")
+        .is_ok();
+    if !has_renames {
+        conn.execute(
+            "ALTER TABLE candidates ADD COLUMN renames_json TEXT NOT NULL DEFAULT '[]'",
+            [],
+        )
+        .context("add candidates.renames_json")?;
+    }
+    // And the fold that built it (doc 17 §2a): every older build ran the
+    // fold before rename detection.
+    let has_fold_version = conn
+        .prepare("SELECT fold_version FROM candidates LIMIT 1")
+        .is_ok();
+    if !has_fold_version {
+        conn.execute(
+            "ALTER TABLE candidates ADD COLUMN fold_version INTEGER NOT NULL DEFAULT 1",
+            [],
+        )
+        .context("add candidates.fold_version")?;
+    }
+
     let mut stmt =
         conn.prepare("SELECT seq, record_json FROM releases WHERE version = '' ORDER BY seq ASC")?;
     let unversioned: Vec<(i64, String)> = stmt
@@ -0,0 +1,47 @@
+//! Server data-directory migrations (doc 16 §3), run by
+//! `converge-server --migrate`. The framework is
+//! [`converge_model::migrate`]; this is the server's registry.
+
+use std::path::Path;
+
+use anyhow::Result;
+
+use converge_model::format::StoreKind;
+use converge_model::migrate::{Options, Report, Step};
+
+/// Every server migration this build knows, oldest first.
+pub const SERVER_STEPS: &[Step] = &[
+    Step {
+        from: 1,
+        summary: "adopt converge-snap-v5 for authored records; stored records keep their ids",
+        apply: adopt_snap_v5,
+    },
+    Step {
+        from: 2,
+        summary: "allow zstd-encoded objects (doc 16 §1f); stored objects stay as they are",
+        apply: allow_encoded_objects,
+    },
+];
+
+/// Bring the data directory at `data_dir` to the current format.
+pub fn migrate(data_dir: &Path, options: Options) -> Result<Report> {
+    converge_model::migrate::run(data_dir, StoreKind::Server, SERVER_STEPS, options)
+}
+
+/// 1 -> 2: nothing to rewrite.
+///
+/// Every version-1 record is anonymous, and anonymous records are still
+/// identified under `converge-snap-v4` (doc 17 §1). Publications,
+/// candidates and lane heads name records by the id they were accepted
+/// under, and those references stay true, as they do in every workspace
+/// that holds the same records.
+fn adopt_snap_v5(_data_dir: &Path) -> Result<()> {
+    Ok(())
+}
+
+/// 2 -> 3: nothing to rewrite, as for a workspace. Existing objects are
+/// plain and stay readable; `--compress-objects` only changes what is
+/// written from here on.
+fn allow_encoded_objects(_data_dir: &Path) -> Result<()> {
+    Ok(())
+}
@@ -3,7 +3,8 @@ use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result, anyhow};
 
-use converge_model::ObjectId;
+use converge_model::compression;
+use converge_model::{ObjectEncoding, ObjectId};
 
 use crate::storage::{ObjectKind, ObjectStore};
 
@@ -11,15 +12,25 @@ use crate::storage::{ObjectKind, ObjectStore};
 /// idempotent puts — the same discipline as the client store.
 pub struct FsObjectStore {
     root: PathBuf,
+    /// Write blobs and manifests zstd-encoded, as `<hash>.zst`, where
+    /// that saves space (doc 16 §1f). Reads take either form regardless.
+    compress: bool,
 }
 
 impl FsObjectStore {
     pub fn new(root: &Path) -> Self {
         Self {
             root: root.to_path_buf(),
+            compress: false,
         }
     }
 
+    /// Encode what is written from here on (`--compress-objects`).
+    pub fn with_compression(mut self) -> Self {
+        self.compress = true;
+        self
+    }
+
     fn path(&self, kind: ObjectKind, id: &ObjectId) -> PathBuf {
         let h = id.as_str();
         let (a, b) = if h.len() >= 4 {
@@ -34,6 +45,68 @@ impl FsObjectStore {
             .join(b)
             .join(h)
     }
+
+    fn encoded_path(&self, kind: ObjectKind, id: &ObjectId) -> PathBuf {
+        self.path(kind, id)
+            .with_extension(ObjectEncoding::Zstd.extension())
+    }
+
+    /// The object as stored, and its encoding.
+    fn read_stored(
+        &self,
+        kind: ObjectKind,
+        id: &ObjectId,
+    ) -> Result<(Vec<u8>, Option<ObjectEncoding>)> {
+        let path = self.path(kind, id);
+        match fs::read(&path) {
+            Ok(bytes) => Ok((bytes, None)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound && kind.encodable() => {
+                match fs::read(self.encoded_path(kind, id)) {
+                    Ok(bytes) => Ok((bytes, Some(ObjectEncoding::Zstd))),
+                    Err(_) => Err(err),
+                }
+            }
+            Err(err) => Err(err),
+        }
+        .with_context(|| format!("read {} {}", kind.dir(), id.as_str()))
+    }
+
+    /// Decode and check against the id: verify-on-read covers the
+    /// decoded object, never the stored bytes.
+    fn verify(
+        &self,
+        kind: ObjectKind,
+        id: &ObjectId,
+        bytes: &[u8],
+        encoding: Option<ObjectEncoding>,
+    ) -> Result<Option<Vec<u8>>> {
+        let integrity = || {
+            anyhow!(
+                "{} integrity check failed for {}",
+                kind.dir(),
+                self.path(kind, id).display()
+            )
+        };
+        let decoded = match encoding {
+            None => None,
+            Some(encoding) => {
+                Some(compression::decode(encoding, bytes, u64::MAX).map_err(|_| integrity())?)
+            }
+        };
+        if hash(decoded.as_deref().unwrap_or(bytes)) != *id {
+            return Err(integrity());
+        }
+        Ok(decoded)
+    }
+
+    fn write(path: &Path, bytes: &[u8]) -> Result<()> {
+        let parent = path.parent().expect("object path has parent");
+        fs::create_dir_all(parent).context("create object dirs")?;
+        let tmp = path.with_extension(format!("tmp.{}", std::process::id()));
+        fs::write(&tmp, bytes).with_context(|| format!("write {}", tmp.display()))?;
+        fs::rename(&tmp, path).with_context(|| format!("rename to {}", path.display()))?;
+        Ok(())
+    }
 }
 
 fn hash(bytes: &[u8]) -> ObjectId {
@@ -57,35 +130,25 @@ impl ObjectStore for FsObjectStore {
                 actual.as_str()
             ));
         }
-        let path = self.path(kind, id);
-        if path.exists() {
+        if self.has(kind, id) {
             return Ok(());
         }
-        let parent = path.parent().expect("object path has parent");
-        fs::create_dir_all(parent).context("create object dirs")?;
-        let tmp = path.with_extension(format!("tmp.{}", std::process::id()));
-        fs::write(&tmp, bytes).with_context(|| format!("write {}", tmp.display()))?;
-        fs::rename(&tmp, &path).with_context(|| format!("rename to {}", path.display()))?;
-        Ok(())
+        if self.compress
+            && kind.encodable()
+            && let Some(encoded) = compression::encode(bytes)
+        {
+            return Self::write(&self.encoded_path(kind, id), &encoded);
+        }
+        Self::write(&self.path(kind, id), bytes)
     }
 
     fn get(&self, kind: ObjectKind, id: &ObjectId) -> Result<Vec<u8>> {
-        let path = self.path(kind, id);
-        let bytes =
-            fs::read(&path).with_context(|| format!("read {} {}", kind.dir(), id.as_str()))?;
-        let actual = hash(&bytes);
-        if actual != *id {
-            return Err(anyhow!(
-                "{} integrity check failed for {}",
-                kind.dir(),
-                path.display()
-            ));
-        }
-        Ok(bytes)
+        let (bytes, encoding) = self.read_stored(kind, id)?;
+        Ok(self.verify(kind, id, &bytes, encoding)?.unwrap_or(bytes))
     }
 
     fn has(&self, kind: ObjectKind, id: &ObjectId) -> bool {
-        self.path(kind, id).exists()
+        self.path(kind, id).exists() || (kind.encodable() && self.encoded_path(kind, id).exists())
     }
 
     fn list(&self, kind: ObjectKind) -> Result<Vec<(ObjectId, u64, std::time::SystemTime)>> {
@@ -112,9 +175,14 @@ impl ObjectStore for FsObjectStore {
                     if name.contains(".tmp.") {
                         continue;
                     }
+                    // Listed by id whichever form it is stored in.
+                    let id = name
+                        .strip_suffix(&format!(".{}", ObjectEncoding::Zstd.extension()))
+                        .map(str::to_string)
+                        .unwrap_or(name);
                     let meta = entry.metadata()?;
                     out.push((
-                        ObjectId(name),
+                        ObjectId(id),
                         meta.len(),
                         meta.modified().unwrap_or(std::time::SystemTime::UNIX_EPOCH),
                     ));
@@ -125,10 +193,66 @@ impl ObjectStore for FsObjectStore {
     }
 
     fn delete(&self, kind: ObjectKind, id: &ObjectId) -> Result<()> {
-        let path = self.path(kind, id);
-        if path.exists() {
-            fs::remove_file(&path).with_context(|| format!("delete {}", path.display()))?;
+        for path in [self.path(kind, id), self.encoded_path(kind, id)] {
+            if path.exists() {
+                fs::remove_file(&path).with_context(|| format!("delete {}", path.display()))?;
+            }
         }
         Ok(())
     }
+
+    fn put_encoded(
+        &self,
+        kind: ObjectKind,
+        id: &ObjectId,
+        bytes: &[u8],
+        encoding: ObjectEncoding,
+    ) -> Result<()> {
+        let plain = compression::decode(encoding, bytes, converge_model::MAX_FRAME_OBJECT_BYTES)
+            .with_context(|| format!("decode {} {}", kind.dir(), id.as_str()))?;
+        if !self.compress || !kind.encodable() {
+            return self.put_bytes(kind, id, &plain);
+        }
+        let actual = hash(&plain);
+        if actual != *id {
+            return Err(anyhow!(
+                "{} hash mismatch (expected {}, got {})",
+                kind.dir(),
+                id.as_str(),
+                actual.as_str()
+            ));
+        }
+        if self.has(kind, id) {
+            return Ok(());
+        }
+        // Kept as it came: the sender only encodes when that pays.
+        Self::write(&self.encoded_path(kind, id), bytes)
+    }
+
+    fn get_stored(
+        &self,
+        kind: ObjectKind,
+        id: &ObjectId,
+    ) -> Result<(Vec<u8>, Option<ObjectEncoding>)> {
+        let (bytes, encoding) = self.read_stored(kind, id)?;
+        self.verify(kind, id, &bytes, encoding)?;
+        Ok((bytes, encoding))
+    }
+
+    fn logical_len(&self, kind: ObjectKind, id: &ObjectId) -> Result<u64> {
+        let path = self.path(kind, id);
+        if let Ok(meta) = fs::metadata(&path) {
+            return Ok(meta.len());
+        }
+        let (bytes, encoding) = self.read_stored(kind, id)?;
+        let Some(encoding) = encoding else {
+            return Ok(bytes.len() as u64);
+        };
+        match compression::logical_len(encoding, &bytes) {
+            Some(len) => Ok(len),
+            None => Ok(self
+                .verify(kind, id, &bytes, Some(encoding))?
+                .map_or(0, |b| b.len() as u64)),
+        }
+    }
 }
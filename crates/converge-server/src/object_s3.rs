@@ -1,16 +1,20 @@
 //! S3-compatible `ObjectStore` (arch doc 14 §2, feature `backend-s3`).
-//! Keys: `objects/<kind>/<hash>` (S3 needs no fanout). Verify-on-read and
-//! idempotent puts carry over unchanged.
+//! Keys: `objects/<kind>/<hash>` (S3 needs no fanout), and
+//! `objects/<kind>/<hash>.zst` for an object stored encoded (doc 16 §1f).
+//! Verify-on-read and idempotent puts carry over unchanged.
 
 use anyhow::{Context, Result, anyhow};
 use s3::Bucket;
 
-use converge_model::ObjectId;
+use converge_model::compression;
+use converge_model::{ObjectEncoding, ObjectId};
 
 use crate::storage::{ObjectKind, ObjectStore};
 
 pub struct S3ObjectStore {
     bucket: Box<Bucket>,
+    /// Write blobs and manifests zstd-encoded where that saves space.
+    compress: bool,
 }
 
 impl S3ObjectStore {
@@ -26,16 +30,96 @@ impl S3ObjectStore {
         let bucket = Bucket::new(bucket, region, credentials)
             .context("open bucket")?
             .with_path_style();
-        Ok(Self { bucket })
+        Ok(Self {
+            bucket,
+            compress: false,
+        })
+    }
+
+    /// Encode what is written from here on (`--compress-objects`).
+    pub fn with_compression(mut self) -> Self {
+        self.compress = true;
+        self
     }
 
     fn key(kind: ObjectKind, id: &ObjectId) -> String {
         format!("objects/{}/{}", kind.dir(), id.as_str())
     }
 
+    fn encoded_key(kind: ObjectKind, id: &ObjectId) -> String {
+        format!(
+            "{}.{}",
+            Self::key(kind, id),
+            ObjectEncoding::Zstd.extension()
+        )
+    }
+
     fn hash(bytes: &[u8]) -> ObjectId {
         ObjectId(blake3::hash(bytes).to_hex().to_string())
     }
+
+    fn exists(&self, key: String) -> bool {
+        self.bucket
+            .head_object(key)
+            .map(|(_, code)| code == 200)
+            .unwrap_or(false)
+    }
+
+    fn put_key(&self, key: String, bytes: &[u8]) -> Result<()> {
+        let response = self.bucket.put_object(key, bytes).context("s3 put")?;
+        if response.status_code() >= 300 {
+            return Err(anyhow!("s3 put failed: {}", response.status_code()));
+        }
+        Ok(())
+    }
+
+    /// The object as stored, and its encoding. The form this store
+    /// writes is tried first, so the common read is one request.
+    fn read_stored(
+        &self,
+        kind: ObjectKind,
+        id: &ObjectId,
+    ) -> Result<(Vec<u8>, Option<ObjectEncoding>)> {
+        let plain = (Self::key(kind, id), None);
+        let encoded = (Self::encoded_key(kind, id), Some(ObjectEncoding::Zstd));
+        let order = if !kind.encodable() {
+            vec![plain]
+        } else if self.compress {
+            vec![encoded, plain]
+        } else {
+            vec![plain, encoded]
+        };
+        let mut last = 404;
+        for (key, encoding) in order {
+            let response = self.bucket.get_object(key).context("s3 get")?;
+            if response.status_code() < 300 {
+                return Ok((response.to_vec(), encoding));
+            }
+            last = response.status_code();
+        }
+        Err(anyhow!("s3 get {} {}: {last}", kind.dir(), id.as_str()))
+    }
+
+    /// Decode and check against the id.
+    fn verify(
+        &self,
+        kind: ObjectKind,
+        id: &ObjectId,
+        bytes: &[u8],
+        encoding: Option<ObjectEncoding>,
+    ) -> Result<Option<Vec<u8>>> {
+        let integrity = || anyhow!("{} integrity check failed for {}", kind.dir(), id.as_str());
+        let decoded = match encoding {
+            None => None,
+            Some(encoding) => {
+                Some(compression::decode(encoding, bytes, u64::MAX).map_err(|_| integrity())?)
+            }
+        };
+        if Self::hash(decoded.as_deref().unwrap_or(bytes)) != *id {
+            return Err(integrity());
+        }
+        Ok(decoded)
+    }
 }
 
 impl ObjectStore for S3ObjectStore {
@@ -60,50 +144,28 @@ impl ObjectStore for S3ObjectStore {
         if self.has(kind, id) {
             return Ok(());
         }
-        let response = self
-            .bucket
-            .put_object(Self::key(kind, id), bytes)
-            .context("s3 put")?;
-        if response.status_code() >= 300 {
-            return Err(anyhow!("s3 put failed: {}", response.status_code()));
+        if self.compress
+            && kind.encodable()
+            && let Some(encoded) = compression::encode(bytes)
+        {
+            return self.put_key(Self::encoded_key(kind, id), &encoded);
         }
-        Ok(())
+        self.put_key(Self::key(kind, id), bytes)
     }
 
     fn get(&self, kind: ObjectKind, id: &ObjectId) -> Result<Vec<u8>> {
-        let response = self
-            .bucket
-            .get_object(Self::key(kind, id))
-            .context("s3 get")?;
-        if response.status_code() >= 300 {
-            return Err(anyhow!(
-                "s3 get {} {}: {}",
-                kind.dir(),
-                id.as_str(),
-                response.status_code()
-            ));
-        }
-        let bytes = response.to_vec();
-        let actual = Self::hash(&bytes);
-        if actual != *id {
-            return Err(anyhow!(
-                "{} integrity check failed for {}",
-                kind.dir(),
-                id.as_str()
-            ));
-        }
-        Ok(bytes)
+        let (bytes, encoding) = self.read_stored(kind, id)?;
+        Ok(self.verify(kind, id, &bytes, encoding)?.unwrap_or(bytes))
     }
 
     fn has(&self, kind: ObjectKind, id: &ObjectId) -> bool {
-        self.bucket
-            .head_object(Self::key(kind, id))
-            .map(|(_, code)| code == 200)
-            .unwrap_or(false)
+        self.exists(Self::key(kind, id))
+            || (kind.encodable() && self.exists(Self::encoded_key(kind, id)))
     }
 
     fn list(&self, kind: ObjectKind) -> Result<Vec<(ObjectId, u64, std::time::SystemTime)>> {
         let prefix = format!("objects/{}/", kind.dir());
+        let suffix = format!(".{}", ObjectEncoding::Zstd.extension());
         let pages = self.bucket.list(prefix.clone(), None).context("s3 list")?;
         let mut out = Vec::new();
         for page in pages {
@@ -111,6 +173,7 @@ impl ObjectStore for S3ObjectStore {
                 let Some(name) = object.key.strip_prefix(&prefix) else {
                     continue;
                 };
+                let name = name.strip_suffix(&suffix).unwrap_or(name);
                 let mtime = time::OffsetDateTime::parse(
                     &object.last_modified,
                     &time::format_description::well_known::Rfc3339,
@@ -127,6 +190,58 @@ impl ObjectStore for S3ObjectStore {
         self.bucket
             .delete_object(Self::key(kind, id))
             .context("s3 delete")?;
+        if kind.encodable() {
+            self.bucket
+                .delete_object(Self::encoded_key(kind, id))
+                .context("s3 delete")?;
+        }
         Ok(())
     }
+
+    fn put_encoded(
+        &self,
+        kind: ObjectKind,
+        id: &ObjectId,
+        bytes: &[u8],
+        encoding: ObjectEncoding,
+    ) -> Result<()> {
+        let plain = compression::decode(encoding, bytes, converge_model::MAX_FRAME_OBJECT_BYTES)
+            .with_context(|| format!("decode {} {}", kind.dir(), id.as_str()))?;
+        if !self.compress || !kind.encodable() {
+            return self.put_bytes(kind, id, &plain);
+        }
+        let actual = Self::hash(&plain);
+        if actual != *id {
+            return Err(anyhow!(
+                "{} hash mismatch (expected {}, got {})",
+                kind.dir(),
+                id.as_str(),
+                actual.as_str()
+            ));
+        }
+        if self.has(kind, id) {
+            return Ok(());
+        }
+        self.put_key(Self::encoded_key(kind, id), bytes)
+    }
+
+    fn get_stored(
+        &self,
+        kind: ObjectKind,
+        id: &ObjectId,
+    ) -> Result<(Vec<u8>, Option<ObjectEncoding>)> {
+        let (bytes, encoding) = self.read_stored(kind, id)?;
+        self.verify(kind, id, &bytes, encoding)?;
+        Ok((bytes, encoding))
+    }
+
+    fn logical_len(&self, kind: ObjectKind, id: &ObjectId) -> Result<u64> {
+        let (bytes, encoding) = self.read_stored(kind, id)?;
+        match encoding.and_then(|encoding| compression::logical_len(encoding, &bytes)) {
+            Some(len) => Ok(len),
+            None => Ok(self
+                .verify(kind, id, &bytes, encoding)?
+                .map_or(bytes.len(), |plain| plain.len()) as u64),
+        }
+    }
 }
@@ -129,6 +129,10 @@ mod tests {
             base_candidate_id: None,
             window: (0, 0),
             strategy: "whole-file".into(),
+            strategy_rules: vec![],
+            lane_priority: vec![],
+            renames: vec![],
+            fold_version: crate::merge::FOLD_VERSION,
             status: CandidateStatus::Ready { promotable: true },
             created_at: String::new(),
         }
@@ -0,0 +1,126 @@
+//! Pure signature evaluation (doc 19 §12): given the repo's registered
+//! keys, decide what a publication's signature proves. Nothing here
+//! refuses anything — publish and the provenance reads decide what a
+//! status means for them.
+
+use anyhow::{Context, Result, bail};
+use ed25519_dalek::{Signature, VerifyingKey};
+
+use converge_model::signing::{PublishClaim, from_hex, publish_payload, snap_payload};
+use converge_model::{
+    PublicKeyRecord, PublicationRecord, PublishSignature, SignatureCheck, SignatureStatus,
+};
+
+/// Parse a hex ed25519 verifying key as registered.
+pub fn parse_verifying_key(hex: &str) -> Result<VerifyingKey> {
+    let bytes = from_hex(hex).context("not hex")?;
+    let bytes: [u8; 32] = bytes
+        .try_into()
+        .map_err(|bytes: Vec<u8>| anyhow::anyhow!("{} bytes, expected 32", bytes.len()))?;
+    VerifyingKey::from_bytes(&bytes).context("not a curve point")
+}
+
+/// Check `signature` as sent by `publisher` for `claim`.
+///
+/// The key must be registered in the repo *to the publisher*. A valid
+/// signature by somebody else's key proves that somebody else signed,
+/// which is exactly the thing a stolen token plus a borrowed signature
+/// would want the server to accept.
+pub fn check(
+    keys: &[PublicKeyRecord],
+    publisher: &str,
+    claim: &PublishClaim<'_>,
+    signature: Option<&PublishSignature>,
+) -> SignatureStatus {
+    let Some(signature) = signature else {
+        return SignatureStatus::Unsigned;
+    };
+    // Publish refuses this; on a stored publication it means the key was
+    // removed since, not that the signature was ever false.
+    let Some(key) = keys.iter().find(|k| k.key_id == signature.key_id) else {
+        return SignatureStatus::KeyRetired {
+            key_id: signature.key_id.clone(),
+        };
+    };
+    match verify(key, publisher, claim, signature) {
+        Ok(()) => SignatureStatus::Verified {
+            key_id: signature.key_id.clone(),
+        },
+        Err(err) => SignatureStatus::Invalid {
+            reason: format!("{err:#}"),
+        },
+    }
+}
+
+/// Re-check a stored publication against the keys registered now.
+///
+/// The lane is not part of what was signed (doc 19 §12), so the record's
+/// resolved lane never has to be mapped back to the one requested.
+pub fn check_publication(
+    keys: &[PublicKeyRecord],
+    publication: &PublicationRecord,
+) -> SignatureStatus {
+    check(
+        keys,
+        &publication.publisher,
+        &PublishClaim {
+            repo_id: &publication.repo_id,
+            scope_id: &publication.scope_id,
+            gate_id: &publication.target_gate_id,
+            snap_id: &publication.snap_id,
+            base_candidate_id: publication.base_candidate_id.as_deref(),
+            notes: publication.notes.as_deref(),
+        },
+        publication.signature.as_ref(),
+    )
+}
+
+/// One check per publication, in order — what provenance and verify
+/// report.
+pub fn check_all(
+    keys: &[PublicKeyRecord],
+    publications: &[PublicationRecord],
+) -> Vec<SignatureCheck> {
+    publications
+        .iter()
+        .map(|publication| SignatureCheck {
+            publication_id: publication.publication_id.clone(),
+            publisher: publication.publisher.clone(),
+            status: check_publication(keys, publication),
+        })
+        .collect()
+}
+
+fn verify(
+    key: &PublicKeyRecord,
+    publisher: &str,
+    claim: &PublishClaim<'_>,
+    signature: &PublishSignature,
+) -> Result<()> {
+    if key.subject != publisher {
+        bail!(
+            "key {} belongs to {}, not the publisher {publisher}",
+            key.key_id,
+            key.subject
+        );
+    }
+    let Some(signing_key) = &key.signing_key else {
+        bail!(
+            "key {} predates signing and has no verifying half",
+            key.key_id
+        );
+    };
+    let verifying = parse_verifying_key(signing_key)
+        .with_context(|| format!("registered verifying key for {}", key.key_id))?;
+    let parse = |hex: &str| -> Result<Signature> {
+        let bytes = from_hex(hex).context("not hex")?;
+        Signature::from_slice(&bytes).context("not an ed25519 signature")
+    };
+    verifying
+        .verify_strict(&snap_payload(claim.snap_id), &parse(&signature.snap)?)
+        .with_context(|| format!("snap signature does not verify under {}", key.key_id))?;
+    verifying
+        .verify_strict(&publish_payload(claim), &parse(&signature.publish)?)
+        .with_context(|| format!("publish signature does not verify under {}", key.key_id))?;
+    Ok(())
+}
@@ -3,8 +3,9 @@ use anyhow::Result;
 use crate::authz::Capability;
 
 use converge_model::{
-    CandidateStatus, EventRecord, GateGraph, LaneHead, LaneRecord, ObjectId, PublicationRecord,
-    ReleaseRecord, RetentionPolicy, SnapRecord,
+    CandidateStatus, EventRecord, GateGraph, LaneHead, LaneRecord, ObjectEncoding, ObjectId,
+    PublicationRecord, ReleaseRecord, RenameRecord, RetentionPolicy, SigningPolicy, SnapRecord,
+    StrategyRule,
 };
 
 /// Content-addressed object storage (blobs, manifests, recipes). Embedded
@@ -18,6 +19,40 @@ pub trait ObjectStore: Send + Sync {
     fn list(&self, kind: ObjectKind) -> Result<Vec<(ObjectId, u64, std::time::SystemTime)>>;
     /// Remove one object (GC sweep only).
     fn delete(&self, kind: ObjectKind, id: &ObjectId) -> Result<()>;
+
+    /// Store an object that arrived encoded (doc 16 §1f). It is decoded
+    /// and verified like any other put; a store that keeps encoded
+    /// objects may then write the bytes as they came.
+    fn put_encoded(
+        &self,
+        kind: ObjectKind,
+        id: &ObjectId,
+        bytes: &[u8],
+        encoding: ObjectEncoding,
+    ) -> Result<()> {
+        let plain = converge_model::compression::decode(
+            encoding,
+            bytes,
+            converge_model::MAX_FRAME_OBJECT_BYTES,
+        )?;
+        self.put_bytes(kind, id, &plain)
+    }
+
+    /// A verified object in the form it is stored, so it can be served
+    /// without encoding it again. Plain unless the store encodes.
+    fn get_stored(
+        &self,
+        kind: ObjectKind,
+        id: &ObjectId,
+    ) -> Result<(Vec<u8>, Option<ObjectEncoding>)> {
+        Ok((self.get(kind, id)?, None))
+    }
+
+    /// An object's plain size however it is stored; `list` reports the
+    /// stored size. GC reports both (doc 16 §1f).
+    fn logical_len(&self, kind: ObjectKind, id: &ObjectId) -> Result<u64> {
+        Ok(self.get(kind, id)?.len() as u64)
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -35,6 +70,12 @@ impl ObjectKind {
             ObjectKind::Recipe => "recipes",
         }
     }
+
+    /// Kinds a store may keep encoded (doc 16 §1f). A recipe is a list
+    /// of hashes, which no compressor does anything with.
+    pub fn encodable(&self) -> bool {
+        !matches!(self, ObjectKind::Recipe)
+    }
 }
 
 /// A candidate as the server stores it: the wire record plus policy state.
@@ -51,6 +92,14 @@ pub struct StoredCandidate {
     /// (first_seq, last_seq) of the consumed publication window.
     pub window: (u64, u64),
     pub strategy: String,
+    /// The producing gate's per-path rules at build time (doc 17 §4a).
+    pub strategy_rules: Vec<StrategyRule>,
+    /// The producing gate's lane ranking at build time (doc 17 §4d).
+    pub lane_priority: Vec<String>,
+    /// Moves detected in the inputs (doc 17 §2a).
+    pub renames: Vec<RenameRecord>,
+    /// The fold that built it (doc 17 §2a), so `verify` replays that one.
+    pub fold_version: u32,
     pub status: CandidateStatus,
     pub created_at: String,
 }
@@ -352,6 +401,10 @@ pub trait MetadataStore: Send + Sync {
     fn set_retention(&self, repo_id: &str, policy: &RetentionPolicy) -> Result<()>;
     fn get_retention(&self, repo_id: &str) -> Result<RetentionPolicy>;
 
+    // signing policy (doc 19 §12)
+    fn set_signing_policy(&self, repo_id: &str, policy: &SigningPolicy) -> Result<()>;
+    fn get_signing_policy(&self, repo_id: &str) -> Result<SigningPolicy>;
+
     // releases (g02.008)
     fn add_release(&self, release: &ReleaseRecord) -> Result<()>;
     fn list_releases(&self, repo_id: &str) -> Result<Vec<ReleaseRecord>>;
@@ -476,6 +529,30 @@ impl ObjectStore for AssociatingObjects<'_> {
     fn delete(&self, kind: ObjectKind, id: &ObjectId) -> Result<()> {
         self.inner.delete(kind, id)
     }
+
+    fn put_encoded(
+        &self,
+        kind: ObjectKind,
+        id: &ObjectId,
+        bytes: &[u8],
+        encoding: ObjectEncoding,
+    ) -> Result<()> {
+        self.inner.put_encoded(kind, id, bytes, encoding)?;
+        self.meta.associate_object(&self.repo_id, kind, id)?;
+        self.meta.pin_object(&self.repo_id, kind, id)
+    }
+
+    fn get_stored(
+        &self,
+        kind: ObjectKind,
+        id: &ObjectId,
+    ) -> Result<(Vec<u8>, Option<ObjectEncoding>)> {
+        self.inner.get_stored(kind, id)
+    }
+
+    fn logical_len(&self, kind: ObjectKind, id: &ObjectId) -> Result<u64> {
+        self.inner.logical_len(kind, id)
+    }
 }
 
 /// Copy-on-write scratch view (g02.011 batch 11.3): reads fall through to
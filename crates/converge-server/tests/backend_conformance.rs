@@ -5,7 +5,10 @@
 
 use anyhow::Result;
 
-use converge_model::{GateGraph, GateNode, LaneHead, ObjectId, PublicationRecord, RetentionPolicy};
+use converge_model::{
+    GateGraph, GateNode, LaneHead, ObjectId, PublicKeyRecord, PublicationRecord, RetentionPolicy,
+    SigningPolicy,
+};
 use converge_server::{
     BatchConflict, FsObjectStore, MetaOp, MetadataStore, ObjectKind, ObjectStore, PartitionState,
     SqliteMetadataStore, StoredCandidate,
@@ -50,6 +53,8 @@ fn conform_metadata(meta: &dyn MetadataStore) -> Result<()> {
                 required_approvals: 1,
                 strategy: "whole-file".into(),
                 may_release: true,
+                strategy_rules: vec![],
+                lane_priority: vec![],
             }],
         },
     )?;
@@ -107,6 +112,31 @@ fn conform_metadata(meta: &dyn MetadataStore) -> Result<()> {
     meta.set_retention("conf", &policy)?;
     assert_eq!(meta.get_retention("conf")?, policy);
 
+    // signing policy and verifying keys (doc 19 §12): a key with no
+    // verifying half reads back as none, not as an empty string.
+    assert_eq!(meta.get_signing_policy("conf")?, SigningPolicy::default());
+    let signing = SigningPolicy {
+        require_signed: true,
+    };
+    meta.set_signing_policy("conf", &signing)?;
+    assert_eq!(meta.get_signing_policy("conf")?, signing);
+    let key = |key_id: &str, signing_key: Option<&str>| PublicKeyRecord {
+        key_id: key_id.into(),
+        subject: "alice".into(),
+        public_key: format!("age1{key_id}"),
+        label: "laptop".into(),
+        created_at: "2026-07-25T00:00:00Z".into(),
+        signing_key: signing_key.map(str::to_string),
+    };
+    meta.add_public_key("conf", &key("k-old", None))?;
+    meta.add_public_key("conf", &key("k-new", Some(&"ab".repeat(32))))?;
+    let mut keys = meta.list_public_keys("conf")?;
+    keys.sort_by(|a, b| a.key_id.cmp(&b.key_id));
+    assert_eq!(
+        keys,
+        vec![key("k-new", Some(&"ab".repeat(32))), key("k-old", None)]
+    );
+
     meta.add_approval("b1", "alice")?;
     meta.add_approval("b1", "alice")?;
     assert_eq!(meta.count_approvals("b1")?, 1, "approvals dedupe");
@@ -192,6 +222,7 @@ fn conform_metadata(meta: &dyn MetadataStore) -> Result<()> {
         publisher: "alice".into(),
         created_at: "2026-07-25T00:00:00Z".into(),
         notes: None,
+        signature: None,
     };
     let candidate = StoredCandidate {
         candidate_id: "batch-b1".into(),
@@ -203,6 +234,10 @@ fn conform_metadata(meta: &dyn MetadataStore) -> Result<()> {
         base_candidate_id: None,
         window: (1, 1),
         strategy: "whole-file".into(),
+        strategy_rules: vec![],
+        lane_priority: vec![],
+        renames: vec![],
+        fold_version: 2,
         status: converge_model::CandidateStatus::Ready { promotable: true },
         created_at: "2026-07-25T00:00:00Z".into(),
     };
@@ -236,6 +271,7 @@ fn conform_metadata(meta: &dyn MetadataStore) -> Result<()> {
     assert_eq!(listed.len(), 1, "batched publication committed");
     assert_eq!(listed[0].0, 1, "seq assigned inside the transaction");
     assert_eq!(meta.get_candidate("batch-b1")?.window, (1, 1));
+    assert_eq!(meta.get_candidate("batch-b1")?.fold_version, 2);
     assert_eq!(
         meta.get_partition_state("conf", scope, "g")?.window_floor,
         1
@@ -271,6 +307,10 @@ fn conform_metadata(meta: &dyn MetadataStore) -> Result<()> {
         base_candidate_id: None,
         window: (1, 1),
         strategy: "whole-file".into(),
+        strategy_rules: vec![],
+        lane_priority: vec![],
+        renames: vec![],
+        fold_version: 2,
         status: converge_model::CandidateStatus::Ready { promotable: true },
         created_at: "2026-07-25T00:00:00Z".into(),
     };
@@ -41,6 +41,8 @@ impl Cluster {
                         required_approvals: 0,
                         strategy: "whole-file".into(),
                         may_release: false,
+                        strategy_rules: vec![],
+                        lane_priority: vec![],
                     },
                     GateNode {
                         gate_id: "main".into(),
@@ -49,6 +51,8 @@ impl Cluster {
                         required_approvals: 0,
                         strategy: "whole-file".into(),
                         may_release: true,
+                        strategy_rules: vec![],
+                        lane_priority: vec![],
                     },
                 ],
             },
@@ -27,6 +27,8 @@ fn start_server(data_dir: &std::path::Path) -> Result<String> {
                     required_approvals: 1,
                     strategy: "whole-file".into(),
                     may_release: false,
+                    strategy_rules: vec![],
+                    lane_priority: vec![],
                 },
                 GateNode {
                     gate_id: "main".into(),
@@ -35,6 +37,8 @@ fn start_server(data_dir: &std::path::Path) -> Result<String> {
                     required_approvals: 0,
                     strategy: "whole-file".into(),
                     may_release: false,
+                    strategy_rules: vec![],
+                    lane_priority: vec![],
                 },
             ],
         },
@@ -172,11 +176,15 @@ fn full_vertical_slice_over_http() -> Result<()> {
             &resolved_root,
             std::slice::from_ref(&snap_a.id),
             Some(&candidate_b.candidate_id),
+            None,
+            None,
         ),
         created_at: "2026-07-24T00:00:00Z".into(),
         root_manifest: resolved_root,
         parents: vec![snap_a.id.clone()],
         derived_from_candidate: Some(candidate_b.candidate_id.clone()),
+        author: None,
+        replayed_from: None,
         message: None,
         trigger: "explicit".into(),
         stats: converge_model::SnapStats::default(),
@@ -310,6 +318,75 @@ fn batch_cap_splitting_round_trips_a_larger_tree() -> Result<()> {
     Ok(())
 }
 
+/// Doc 16 §1g: a lazy fetch brings the tree's shape and promises its
+/// blobs, which arrive when something first reads them.
+#[test]
+fn lazy_fetch_promises_blobs_until_first_read() -> Result<()> {
+    let server_dir = tempfile::tempdir()?;
+    let base_url = start_server(server_dir.path())?;
+    let client = RemoteClient::new(&base_url, "token-a");
+
+    let ws_dir = tempfile::tempdir()?;
+    let ws = Workspace::init(ws_dir.path(), false)?;
+    std::fs::write(ws_dir.path().join("top.txt"), "top content")?;
+    std::fs::create_dir(ws_dir.path().join("sub"))?;
+    std::fs::write(ws_dir.path().join("sub/inner.txt"), "inner content")?;
+    let snap = ws.create_snap(None)?;
+    let (candidate, _) = client.publish(
+        &ws.store,
+        "repo",
+        "scope",
+        "intake",
+        &snap,
+        None,
+        Some("lane-a".into()),
+        None,
+    )?;
+
+    let ws_b_dir = tempfile::tempdir()?;
+    let mut ws_b = Workspace::init(ws_b_dir.path(), false)?;
+    let root = client.fetch_candidate_lazy(&ws_b.store, "repo", &candidate.candidate_id)?;
+    assert!(ws_b.store.is_partial());
+    assert_eq!(ws_b.store.promised_blobs()?.len(), 2);
+    // The tree reads without a blob in hand: show and diff need no more.
+    let tree = converge_client::diff::tree_from_store(&ws_b.store, &root)?;
+    assert_eq!(
+        tree.keys().collect::<Vec<_>>(),
+        vec!["sub/inner.txt", "top.txt"]
+    );
+
+    // With nowhere to read them from, a promised blob is a clear error.
+    let out = tempfile::tempdir()?;
+    let err = ws_b
+        .materialize_manifest_to(&root, &out.path().join("tree"), true)
+        .unwrap_err();
+    assert!(format!("{err:#}").contains("fetched lazily"), "{err:#}");
+
+    let source =
+        converge_client::remote::RemoteBlobs::connected(ws_b.store.clone(), client.clone(), "repo");
+    ws_b.store = ws_b.store.clone().with_blob_source(Arc::new(source));
+    let top_blob = ws_b
+        .store
+        .get_manifest(&root)?
+        .entries
+        .into_iter()
+        .find_map(|e| match e.kind {
+            ManifestEntryKind::File { blob, .. } if e.name == "top.txt" => Some(blob),
+            _ => None,
+        })
+        .expect("top.txt blob");
+    assert_eq!(ws_b.store.get_blob(&top_blob)?, b"top content");
+    assert_eq!(ws_b.store.promised_blobs()?.len(), 1);
+
+    ws_b.materialize_manifest_to(&root, &out.path().join("tree"), true)?;
+    assert_eq!(
+        std::fs::read_to_string(out.path().join("tree/sub/inner.txt"))?,
+        "inner content"
+    );
+    assert!(ws_b.store.promised_blobs()?.is_empty());
+    Ok(())
+}
+
 /// Server FS path for an object (mirrors FsObjectStore sharding).
 fn object_path(data_dir: &std::path::Path, kind_dir: &str, id: &str) -> std::path::PathBuf {
     data_dir
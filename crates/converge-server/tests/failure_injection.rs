@@ -33,6 +33,8 @@ fn seed_meta(dir: &std::path::Path) -> Result<SqliteMetadataStore> {
                 required_approvals: 0,
                 strategy: "whole-file".into(),
                 may_release: true,
+                strategy_rules: vec![],
+                lane_priority: vec![],
             }],
         },
     )?;
@@ -126,7 +128,18 @@ fn flaky_proxy(upstream: String, cut_after_bytes: usize, cuts: usize) -> Result<
 fn workspace_with_payload(bytes: usize) -> Result<(tempfile::TempDir, Workspace)> {
     let dir = tempfile::tempdir()?;
     let ws = Workspace::init(dir.path(), false)?;
-    let body: Vec<u8> = (0..bytes).map(|i| (i % 251) as u8).collect();
+    // Noise rather than a repeating pattern: the wire encodes what
+    // compresses (doc 16 §1f), and a payload that shrank to a few KiB
+    // would finish before the proxy's cut.
+    let mut seed = 0x9e37_79b9_7f4a_7c15u64;
+    let body: Vec<u8> = (0..bytes)
+        .map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed as u8
+        })
+        .collect();
     std::fs::write(dir.path().join("payload.bin"), &body)?;
     std::fs::write(dir.path().join("small.txt"), "hello")?;
     Ok((dir, ws))
@@ -31,6 +31,8 @@ fn start_server(data_dir: &std::path::Path) -> Result<String> {
                 required_approvals: 0,
                 strategy: "whole-file".into(),
                 may_release: true,
+                strategy_rules: vec![],
+                lane_priority: vec![],
             }],
         },
     )?;
@@ -72,6 +74,8 @@ fn gate(id: &str, upstreams: &[&str]) -> GateNode {
         required_approvals: 0,
         strategy: "whole-file".into(),
         may_release: false,
+        strategy_rules: vec![],
+        lane_priority: vec![],
     }
 }
 
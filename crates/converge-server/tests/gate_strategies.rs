@@ -37,6 +37,8 @@ fn fixture() -> Result<Fixture> {
                 required_approvals: 0,
                 strategy: "text-line-merge".into(),
                 may_release: false,
+                strategy_rules: vec![],
+                lane_priority: vec![],
             }],
         },
     )?;
@@ -71,11 +73,13 @@ fn test_snap_record(tag: &str, root: converge_model::ObjectId) -> converge_model
     let _ = tag;
     converge_model::SnapRecord {
         version: 2,
-        id: converge_model::compute_snap_id(&root, &[], None),
+        id: converge_model::compute_snap_id(&root, &[], None, None, None),
         created_at: "2026-07-24T00:00:00Z".into(),
         root_manifest: root,
         parents: Vec::new(),
         derived_from_candidate: None,
+        author: None,
+        replayed_from: None,
         message: None,
         trigger: "explicit".into(),
         stats: converge_model::SnapStats::default(),
@@ -123,6 +127,7 @@ fn publish(
             base_candidate_id: base,
             lane_id: Some(lane.into()),
             notes: None,
+            signature: None,
         },
     )
 }
@@ -59,6 +59,8 @@ fn uploaded_objects_survive_gc_before_publish_then_publish_succeeds() -> Result<
                 required_approvals: 0,
                 strategy: "whole-file".into(),
                 may_release: false,
+                strategy_rules: vec![],
+                lane_priority: vec![],
             }],
         },
     )?;
@@ -118,6 +120,7 @@ fn uploaded_objects_survive_gc_before_publish_then_publish_succeeds() -> Result<
             base_candidate_id: None,
             lane_id: None,
             notes: None,
+            signature: None,
         },
     )?;
 
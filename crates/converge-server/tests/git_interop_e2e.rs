@@ -57,6 +57,8 @@ fn start_server(data_dir: &std::path::Path) -> Result<String> {
                 required_approvals: 0,
                 strategy: "whole-file".into(),
                 may_release: true,
+                strategy_rules: vec![],
+                lane_priority: vec![],
             }],
         },
     )?;
@@ -235,6 +235,8 @@ fn start() -> Result<Fixture> {
                 required_approvals: 0,
                 strategy: "whole-file".into(),
                 may_release: false,
+                strategy_rules: vec![],
+                lane_priority: vec![],
             }],
         },
     )?;
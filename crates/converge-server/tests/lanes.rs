@@ -28,6 +28,8 @@ fn start_server(data_dir: &std::path::Path) -> Result<String> {
                 required_approvals: 0,
                 strategy: "whole-file".into(),
                 may_release: false,
+                strategy_rules: vec![],
+                lane_priority: vec![],
             }],
         },
     )?;
@@ -332,6 +334,8 @@ fn inbox_recommends_approval_when_short() -> Result<()> {
                     required_approvals: 2,
                     strategy: "whole-file".into(),
                     may_release: false,
+                    strategy_rules: vec![],
+                    lane_priority: vec![],
                 }],
             },
         )?;
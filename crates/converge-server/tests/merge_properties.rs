@@ -238,7 +238,9 @@ fn copy_tree(from: &dyn ObjectStore, to: &LocalStore, root: &ObjectId) -> Result
     let manifest = converge_model::encoding::decode_manifest(&bytes)?;
     for entry in manifest.entries {
         match entry.kind {
-            ManifestEntryKind::Dir { manifest } => copy_tree(from, to, &manifest)?,
+            ManifestEntryKind::Dir { manifest } | ManifestEntryKind::Page { manifest, .. } => {
+                copy_tree(from, to, &manifest)?
+            }
             ManifestEntryKind::File { blob, .. } => {
                 to.put_blob(&from.get(ObjectKind::Blob, &blob)?)?;
             }
@@ -285,3 +285,75 @@ fn window_cost_stays_flat_per_publish() -> Result<()> {
     );
     Ok(())
 }
+
+/// Doc 16 §1b: a directory past the paging threshold folds page by page.
+/// The merged directory is exactly what a client scan of the same entries
+/// would produce, and the fold reads the pages an edit lands in, not the
+/// whole directory.
+#[test]
+fn a_paged_directory_merges_page_by_page() -> Result<()> {
+    use converge_model::paging;
+
+    let tmp = tempfile::tempdir()?;
+    let fs = FsObjectStore::new(tmp.path());
+    let put = |m: &Manifest| {
+        fs.put(
+            ObjectKind::Manifest,
+            &converge_model::encoding::encode_manifest(m),
+        )
+    };
+    let wide = |entries: Vec<ManifestEntry>| -> Result<ObjectId> {
+        let dir = put(&paging::build(entries, put)?)?;
+        put_manifest(
+            &fs,
+            vec![ManifestEntry {
+                name: "wide".into(),
+                kind: ManifestEntryKind::Dir { manifest: dir },
+            }],
+        )
+    };
+
+    let files = paging::PAGE_THRESHOLD * 3;
+    let entries = (0..files)
+        .map(|i| file_entry(&fs, &format!("f{i:05}"), format!("{i}").as_bytes()))
+        .collect::<Result<Vec<_>>>()?;
+    let w = wide(entries.clone())?;
+    assert!(paging::is_index(
+        &converge_model::encoding::decode_manifest(
+            &fs.get(ObjectKind::Manifest, &dir_id(&fs, &w, "wide")?)?
+        )?
+    ));
+
+    // Two lanes: one edits near the start, one adds a file near the end.
+    let mut edited = entries.clone();
+    edited[3] = file_entry(&fs, "f00003", b"edited")?;
+    let mut added = entries.clone();
+    added.push(file_entry(&fs, "f99999", b"new")?);
+    let inputs = [
+        MergeInput {
+            lane: "a".into(),
+            base: Some(w.clone()),
+            tree: wide(edited.clone())?,
+        },
+        MergeInput {
+            lane: "b".into(),
+            base: Some(w.clone()),
+            tree: wide(added)?,
+        },
+    ];
+
+    let counting = CountingStore::new(&fs);
+    let merged = merge_window(&counting, Some(&w), &inputs, "whole-file")?;
+
+    let mut expected = edited;
+    expected.push(file_entry(&fs, "f99999", b"new")?);
+    assert_eq!(merged, wide(expected)?, "same ids a scan would produce");
+
+    let pages = files / paging::PAGE_TARGET as usize;
+    assert!(
+        counting.manifest_reads() < pages * 2,
+        "shared pages are skipped, got {} reads for ~{pages} pages per tree",
+        counting.manifest_reads()
+    );
+    Ok(())
+}
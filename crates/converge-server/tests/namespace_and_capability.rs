@@ -30,6 +30,8 @@ fn start_server(data_dir: &std::path::Path) -> Result<String> {
                 required_approvals: 0,
                 strategy: "whole-file".into(),
                 may_release: false,
+                strategy_rules: vec![],
+                lane_priority: vec![],
             }],
         },
     )?;
@@ -0,0 +1,247 @@
+//! Doc 16 §1f: zstd object encoding at rest and on the wire. Ids are the
+//! hash of the plain content whatever the encoding, and a peer that never
+//! asks for an encoding never sees one.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+
+use converge_client::remote::RemoteClient;
+use converge_client::workspace::Workspace;
+use converge_model::{GateGraph, GateNode, ObjectId};
+use converge_server::{
+    AppState, Capability, Engine, FsObjectStore, MetadataStore, ObjectKind, ObjectStore,
+    SqliteMetadataStore, authorize, router,
+};
+
+fn seed_meta(data_dir: &std::path::Path) -> Result<SqliteMetadataStore> {
+    let meta = SqliteMetadataStore::open(&data_dir.join("meta.sqlite"))?;
+    meta.create_repo("repo")?;
+    meta.create_scope("repo", "scope", "2026-07-25T00:00:00Z")?;
+    meta.set_gate_graph(
+        "repo",
+        &GateGraph {
+            gates: vec![GateNode {
+                gate_id: "intake".into(),
+                name: "Intake".into(),
+                upstreams: vec![],
+                required_approvals: 0,
+                strategy: "text-line-merge".into(),
+                may_release: false,
+                strategy_rules: vec![],
+                lane_priority: vec![],
+            }],
+        },
+    )?;
+    meta.upsert_user("alice")?;
+    for capability in ["read", "publish", "admin"] {
+        meta.add_grant("alice", "repo", "*", capability)?;
+    }
+    Ok(meta)
+}
+
+fn start_server(data_dir: &std::path::Path) -> Result<String> {
+    let state = AppState {
+        meta: Arc::new(seed_meta(data_dir)?),
+        objects: Arc::new(FsObjectStore::new(data_dir).with_compression()),
+        tokens: HashMap::from([("token-a".to_string(), "alice".to_string())]),
+        gc_running: Default::default(),
+        oidc: None,
+    };
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let addr = listener.local_addr()?;
+    listener.set_nonblocking(true)?;
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Runtime::new().expect("test runtime");
+        runtime.block_on(async {
+            let listener = tokio::net::TcpListener::from_std(listener).expect("adopt listener");
+            axum::serve(listener, router(state)).await.expect("serve");
+        });
+    });
+    Ok(format!("http://{addr}"))
+}
+
+fn source_text() -> String {
+    (0..4000)
+        .map(|i| format!("let value_{i} = compute({i});\n"))
+        .collect()
+}
+
+fn blob_path(data_dir: &std::path::Path, id: &ObjectId, suffix: &str) -> std::path::PathBuf {
+    let h = id.as_str();
+    data_dir
+        .join("objects/blobs")
+        .join(&h[..2])
+        .join(&h[2..4])
+        .join(format!("{h}{suffix}"))
+}
+
+/// The frame shape a client from before encoding sends and decodes.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct PlainFrame {
+    kind: String,
+    id: String,
+    #[serde(with = "serde_bytes")]
+    bytes: Vec<u8>,
+}
+
+#[test]
+fn text_travels_and_rests_encoded_under_its_plain_id() -> Result<()> {
+    let server_dir = tempfile::tempdir()?;
+    let base_url = start_server(server_dir.path())?;
+    let alice = RemoteClient::new(&base_url, "token-a");
+
+    let ws_dir = tempfile::tempdir()?;
+    let ws = Workspace::init(ws_dir.path(), false)?;
+    let text = source_text();
+    std::fs::write(ws_dir.path().join("main.rs"), &text)?;
+    let snap = ws.create_snap(Some("s".into()))?;
+    let (candidate, stats) = alice.publish(
+        &ws.store, "repo", "scope", "intake", &snap, None, None, None,
+    )?;
+    assert!(
+        stats.sent_bytes * 3 < stats.logical_bytes,
+        "text should shrink on the wire: {stats:?}"
+    );
+
+    let id = ObjectId(blake3::hash(text.as_bytes()).to_hex().to_string());
+    assert!(blob_path(server_dir.path(), &id, ".zst").exists());
+    assert!(!blob_path(server_dir.path(), &id, "").exists());
+
+    // Back down into a second workspace: the same id, the same bytes.
+    let other_dir = tempfile::tempdir()?;
+    let other = Workspace::init(other_dir.path(), false)?;
+    let root = alice.fetch_candidate(&other.store, "repo", &candidate.candidate_id)?;
+    assert_eq!(root, snap.root_manifest);
+    assert_eq!(other.store.get_blob(&id)?, text.as_bytes());
+    Ok(())
+}
+
+/// A client that predates encoding neither sends nor is sent it.
+#[test]
+fn an_older_client_moves_plain_frames() -> Result<()> {
+    let server_dir = tempfile::tempdir()?;
+    let base_url = start_server(server_dir.path())?;
+    let http = reqwest::blocking::Client::new();
+
+    let text = source_text().into_bytes();
+    let id = blake3::hash(&text).to_hex().to_string();
+    let mut body = Vec::new();
+    ciborium::into_writer(
+        &vec![PlainFrame {
+            kind: "blobs".into(),
+            id: id.clone(),
+            bytes: text.clone(),
+        }],
+        &mut body,
+    )?;
+    let response = http
+        .post(format!("{base_url}/api/repos/repo/objects/batch"))
+        .bearer_auth("token-a")
+        .body(body)
+        .send()?;
+    assert_eq!(response.status(), 200, "{}", response.text()?);
+    // Stored encoded all the same: at rest is the server's business.
+    assert!(blob_path(server_dir.path(), &ObjectId(id.clone()), ".zst").exists());
+
+    let response = http
+        .post(format!("{base_url}/api/repos/repo/objects/batch-get"))
+        .bearer_auth("token-a")
+        .json(&serde_json::json!({"blobs": [id]}))
+        .send()?;
+    assert_eq!(response.status(), 200);
+    let raw: Vec<ciborium::Value> = ciborium::from_reader(response.bytes()?.as_ref())?;
+    let frame = raw[0].as_map().expect("a frame is a map");
+    assert!(
+        !frame
+            .iter()
+            .any(|(key, _)| key.as_text() == Some("encoding")),
+        "an unasked-for encoding field"
+    );
+    let frame: PlainFrame = raw[0].deserialized()?;
+    assert_eq!(frame.bytes, text);
+    Ok(())
+}
+
+/// An encoded frame is checked against its id after decoding, and one
+/// that expands past the body limit is refused without being held.
+#[test]
+fn encoded_frames_are_verified_and_bounded() -> Result<()> {
+    let server_dir = tempfile::tempdir()?;
+    let base_url = start_server(server_dir.path())?;
+    let alice = RemoteClient::new(&base_url, "token-a");
+    let http = reqwest::blocking::Client::new();
+    let post = |frames: Vec<converge_model::ObjectFrame>| -> Result<reqwest::blocking::Response> {
+        let mut body = Vec::new();
+        ciborium::into_writer(&frames, &mut body)?;
+        Ok(http
+            .post(format!("{base_url}/api/repos/repo/objects/batch"))
+            .bearer_auth("token-a")
+            .body(body)
+            .send()?)
+    };
+    let encoded = |bytes: &[u8]| converge_model::compression::encode(bytes).expect("compresses");
+
+    let text = source_text().into_bytes();
+    let wrong = ObjectId(blake3::hash(b"something else").to_hex().to_string());
+    let response = post(vec![converge_model::ObjectFrame::encoded(
+        "blobs",
+        wrong,
+        encoded(&text),
+        converge_model::ObjectEncoding::Zstd,
+    )])?;
+    assert_eq!(response.status(), 400);
+    assert!(response.text()?.contains("hash mismatch"));
+
+    let huge = vec![0u8; converge_model::MAX_FRAME_OBJECT_BYTES as usize + 1];
+    let response = post(vec![converge_model::ObjectFrame::encoded(
+        "blobs",
+        ObjectId(blake3::hash(&huge).to_hex().to_string()),
+        encoded(&huge),
+        converge_model::ObjectEncoding::Zstd,
+    )])?;
+    assert_eq!(response.status(), 400);
+    assert!(response.text()?.contains("limit"));
+
+    // Neither left anything behind.
+    let missing = alice.negotiate(
+        "repo",
+        converge_model::ObjectSet {
+            blobs: vec![ObjectId(blake3::hash(&text).to_hex().to_string())],
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(missing.blobs.len(), 1);
+    Ok(())
+}
+
+#[test]
+fn gc_reports_stored_and_logical_bytes() -> Result<()> {
+    let data = tempfile::tempdir()?;
+    let meta = seed_meta(data.path())?;
+    let objects = FsObjectStore::new(data.path()).with_compression();
+    let text = source_text().into_bytes();
+    objects.put(ObjectKind::Blob, &text)?;
+    objects.put(ObjectKind::Blob, b"too short to encode")?;
+
+    let report = Engine {
+        meta: &meta,
+        objects: &objects,
+    }
+    .gc(
+        &authorize(&meta, "alice", "repo", "scope", Capability::Admin)?,
+        true,
+        "2026-07-24T00:00:00Z",
+        Duration::ZERO,
+    )?;
+    assert_eq!(report.swept_objects, 2);
+    let logical = text.len() as u64 + b"too short to encode".len() as u64;
+    assert_eq!(report.swept_logical_bytes, logical);
+    assert!(
+        report.swept_bytes * 3 < report.swept_logical_bytes,
+        "{report:?}"
+    );
+    Ok(())
+}
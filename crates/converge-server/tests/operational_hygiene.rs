@@ -138,11 +138,13 @@ fn gc_in_one_repo_keeps_another_repos_objects() -> Result<()> {
     )?;
     let snap = converge_model::SnapRecord {
         version: 2,
-        id: converge_model::compute_snap_id(&root, &[], None),
+        id: converge_model::compute_snap_id(&root, &[], None, None, None),
         created_at: "2026-07-25T00:00:00Z".into(),
         root_manifest: root.clone(),
         parents: vec![],
         derived_from_candidate: None,
+        author: None,
+        replayed_from: None,
         message: None,
         trigger: "explicit".into(),
         stats: Default::default(),
@@ -24,6 +24,8 @@ fn start_server(data_dir: &std::path::Path, lanes: usize) -> Result<String> {
                 required_approvals: 0,
                 strategy: "whole-file".into(),
                 may_release: false,
+                strategy_rules: vec![],
+                lane_priority: vec![],
             }],
         },
     )?;
@@ -190,6 +192,10 @@ fn inbox_reads_one_candidate_per_gate_not_the_whole_scope() -> Result<()> {
                 base_candidate_id: None,
                 window: (0, 0),
                 strategy: "whole-file".into(),
+                strategy_rules: vec![],
+                lane_priority: vec![],
+                renames: vec![],
+                fold_version: 2,
                 status: CandidateStatus::Ready { promotable: true },
                 created_at: format!("2026-07-25T00:00:{i:02}Z"),
             })?;
@@ -21,6 +21,8 @@ fn gate_graph() -> GateGraph {
             required_approvals: 0,
             strategy: "whole-file".into(),
             may_release: false,
+            strategy_rules: vec![],
+            lane_priority: vec![],
         }],
     }
 }
@@ -32,6 +32,8 @@ fn start_server(data_dir: &std::path::Path) -> Result<String> {
                     required_approvals: 0,
                     strategy: "whole-file".into(),
                     may_release: false,
+                    strategy_rules: vec![],
+                    lane_priority: vec![],
                 },
                 GateNode {
                     gate_id: "main".into(),
@@ -40,6 +42,8 @@ fn start_server(data_dir: &std::path::Path) -> Result<String> {
                     required_approvals: 0,
                     strategy: "whole-file".into(),
                     may_release: true,
+                    strategy_rules: vec![],
+                    lane_priority: vec![],
                 },
             ],
         },
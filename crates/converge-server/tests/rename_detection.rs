@@ -0,0 +1,399 @@
+//! Doc 17 §2a: moves in the base-aware fold — another lane's edit follows
+//! a file to where it was moved, the move is recorded on the candidate,
+//! and two lanes moving one file to different places superpose with
+//! `MovedTo` variants.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+
+use converge_model::{
+    CandidateStatus, GateGraph, GateNode, Manifest, ManifestEntry, ManifestEntryKind, ObjectId,
+    RenameRecord, SuperpositionVariantKind,
+};
+use converge_server::{
+    Capability, Engine, FsObjectStore, MetadataStore, ObjectKind, ObjectStore, PublishInput,
+    SqliteMetadataStore, StoredCandidate, authorize,
+};
+
+struct Fixture {
+    meta: SqliteMetadataStore,
+    objects: FsObjectStore,
+    _tmp: tempfile::TempDir,
+}
+
+fn fixture(strategy: &str) -> Result<Fixture> {
+    let tmp = tempfile::tempdir()?;
+    let meta = SqliteMetadataStore::open_in_memory()?;
+    let objects = FsObjectStore::new(tmp.path());
+    meta.upsert_user("alice")?;
+    meta.create_repo("repo")?;
+    meta.create_scope("repo", "scope", "2026-07-25T00:00:00Z")?;
+    meta.set_gate_graph(
+        "repo",
+        &GateGraph {
+            gates: vec![GateNode {
+                gate_id: "intake".into(),
+                name: "Intake".into(),
+                upstreams: vec![],
+                required_approvals: 0,
+                strategy: strategy.into(),
+                may_release: false,
+                strategy_rules: vec![],
+                lane_priority: vec![],
+            }],
+        },
+    )?;
+    meta.add_grant("alice", "repo", "*", "publish")?;
+    Ok(Fixture {
+        meta,
+        objects,
+        _tmp: tmp,
+    })
+}
+
+/// A tree from `/`-separated paths.
+fn put_tree(fx: &Fixture, files: &[(&str, &str)]) -> Result<ObjectId> {
+    let mut here: Vec<ManifestEntry> = Vec::new();
+    let mut nested: BTreeMap<&str, Vec<(&str, &str)>> = BTreeMap::new();
+    for (path, content) in files {
+        match path.split_once('/') {
+            Some((dir, rest)) => nested.entry(dir).or_default().push((rest, content)),
+            None => here.push(ManifestEntry {
+                name: path.to_string(),
+                kind: ManifestEntryKind::File {
+                    blob: fx.objects.put(ObjectKind::Blob, content.as_bytes())?,
+                    mode: 0o644,
+                    size: content.len() as u64,
+                },
+            }),
+        }
+    }
+    for (dir, children) in nested {
+        here.push(ManifestEntry {
+            name: dir.to_string(),
+            kind: ManifestEntryKind::Dir {
+                manifest: put_tree(fx, &children)?,
+            },
+        });
+    }
+    here.sort_by(|a, b| a.name.cmp(&b.name));
+    fx.objects.put(
+        ObjectKind::Manifest,
+        &converge_model::encoding::encode_manifest(&Manifest {
+            version: 1,
+            entries: here,
+        }),
+    )
+}
+
+fn ensure_lane(fx: &Fixture, lane: &str) -> Result<()> {
+    if fx.meta.get_lane("repo", lane)?.is_none() {
+        fx.meta.create_lane(&converge_model::LaneRecord {
+            lane_id: lane.into(),
+            repo_id: "repo".into(),
+            owner: "alice".into(),
+            members: vec![],
+            visibility: "repo".into(),
+            created_at: "2026-07-24T00:00:00Z".into(),
+        })?;
+    }
+    Ok(())
+}
+
+fn publish(
+    fx: &Fixture,
+    lane: &str,
+    root: ObjectId,
+    base: Option<String>,
+) -> Result<StoredCandidate> {
+    ensure_lane(fx, lane)?;
+    let engine = Engine {
+        meta: &fx.meta,
+        objects: &fx.objects,
+    };
+    let authz = authorize(&fx.meta, "alice", "repo", "scope", Capability::Publish)?;
+    engine.publish(
+        authz,
+        PublishInput {
+            gate_id: "intake".into(),
+            snap: converge_model::SnapRecord {
+                version: 2,
+                id: converge_model::compute_snap_id(&root, &[], None, None, None),
+                created_at: "2026-07-24T00:00:00Z".into(),
+                root_manifest: root,
+                parents: Vec::new(),
+                derived_from_candidate: None,
+                author: None,
+                replayed_from: None,
+                message: None,
+                trigger: "explicit".into(),
+                stats: converge_model::SnapStats::default(),
+            },
+            base_candidate_id: base,
+            lane_id: Some(lane.into()),
+            notes: None,
+            signature: None,
+        },
+    )
+}
+
+/// A base, then each `(lane, tree)` built on it; the last candidate.
+fn contest(
+    fx: &Fixture,
+    base: &[(&str, &str)],
+    edits: &[(&str, &[(&str, &str)])],
+) -> Result<StoredCandidate> {
+    let first = publish(fx, "lane-0", put_tree(fx, base)?, None)?;
+    let from = Some(first.candidate_id.clone());
+    let mut last = first;
+    for (lane, files) in edits {
+        last = publish(fx, lane, put_tree(fx, files)?, from.clone())?;
+    }
+    Ok(last)
+}
+
+fn at(fx: &Fixture, candidate: &StoredCandidate, path: &str) -> Result<Option<ManifestEntryKind>> {
+    let mut manifest_id = candidate.root_manifest.clone().expect("root");
+    let mut segments = path.split('/').peekable();
+    while let Some(segment) = segments.next() {
+        let manifest = converge_model::encoding::decode_manifest(
+            &fx.objects.get(ObjectKind::Manifest, &manifest_id)?,
+        )?;
+        let Some(kind) = manifest
+            .entries
+            .into_iter()
+            .find(|e| e.name == segment)
+            .map(|e| e.kind)
+        else {
+            return Ok(None);
+        };
+        if segments.peek().is_none() {
+            return Ok(Some(kind));
+        }
+        match kind {
+            ManifestEntryKind::Dir { manifest } => manifest_id = manifest,
+            _ => return Ok(None),
+        }
+    }
+    Ok(None)
+}
+
+fn text_at(fx: &Fixture, candidate: &StoredCandidate, path: &str) -> Result<String> {
+    match at(fx, candidate, path)? {
+        Some(ManifestEntryKind::File { blob, .. }) => {
+            Ok(String::from_utf8(fx.objects.get(ObjectKind::Blob, &blob)?)?)
+        }
+        other => anyhow::bail!("expected a file at {path}, got {other:?}"),
+    }
+}
+
+const OLD: &str = "fn one() {}\nfn two() {}\nfn three() {}\nfn four() {}\nfn five() {}\n";
+const EDITED: &str = "fn one() {}\nfn two() {}\nfn three() {}\nfn four() {}\nfn five() { 5 }\n";
+
+/// The case from the field: one lane moves the file, another edits it
+/// where it used to be. Without detection this is a delete-vs-modify
+/// tombstone at the old path plus an orphan copy at the new one.
+#[test]
+fn an_edit_follows_the_file_to_where_it_was_moved() -> Result<()> {
+    let fx = fixture("whole-file")?;
+    let candidate = contest(
+        &fx,
+        &[("src/old.rs", OLD), ("README", "readme\n")],
+        &[
+            ("mover", &[("src/new.rs", OLD), ("README", "readme\n")]),
+            ("editor", &[("src/old.rs", EDITED), ("README", "readme\n")]),
+        ],
+    )?;
+    assert_eq!(
+        candidate.status,
+        CandidateStatus::Ready { promotable: true }
+    );
+    assert_eq!(at(&fx, &candidate, "src/old.rs")?, None);
+    assert_eq!(text_at(&fx, &candidate, "src/new.rs")?, EDITED);
+    assert_eq!(
+        candidate.renames,
+        vec![RenameRecord {
+            lane: "mover".into(),
+            from: "src/old.rs".into(),
+            to: "src/new.rs".into(),
+        }]
+    );
+
+    let engine = Engine {
+        meta: &fx.meta,
+        objects: &fx.objects,
+    };
+    let report = engine.verify(&candidate.candidate_id)?;
+    assert!(report.verified, "{}", report.detail);
+    Ok(())
+}
+
+/// A candidate built before rename detection records no fold version,
+/// and read back it is fold 1: `verify` replays it without detection,
+/// which is what built it, rather than reporting a root mismatch.
+#[test]
+fn a_candidate_built_before_rename_detection_still_verifies() -> Result<()> {
+    let fx = fixture("whole-file")?;
+    let built = contest(
+        &fx,
+        &[("src/old.rs", OLD), ("README", "readme\n")],
+        &[
+            ("mover", &[("src/new.rs", OLD), ("README", "readme\n")]),
+            ("editor", &[("src/old.rs", EDITED), ("README", "readme\n")]),
+        ],
+    )?;
+    assert_eq!(built.fold_version, converge_server::merge::FOLD_VERSION);
+
+    // The same window as the old fold built it: the edit stays behind
+    // as a tombstone against the move.
+    let mut inputs = Vec::new();
+    for publication_id in &built.inputs {
+        let publication = fx.meta.get_publication(publication_id)?.expect("input");
+        inputs.push(converge_server::MergeInput {
+            lane: publication.lane_id.clone(),
+            base: match &publication.base_candidate_id {
+                Some(id) => fx.meta.get_candidate(id)?.root_manifest,
+                None => None,
+            },
+            tree: publication.root_manifest,
+        });
+    }
+    let w_root = match &built.base_candidate_id {
+        Some(id) => fx.meta.get_candidate(id)?.root_manifest,
+        None => None,
+    };
+    let policy = converge_model::gates::StrategyPolicy::uniform("whole-file");
+    let old = converge_server::merge::merge_window_outcome(
+        &fx.objects,
+        w_root.as_ref(),
+        &inputs,
+        &policy,
+        1,
+    )?;
+    assert!(old.has_superpositions && old.renames.is_empty());
+    let candidate = StoredCandidate {
+        candidate_id: converge_server::engine::candidate_hash(
+            &built.gate_id,
+            w_root.as_ref(),
+            &built.inputs,
+            &built.strategy,
+            &[],
+            &[],
+            1,
+            Some(&old.root),
+        ),
+        root_manifest: Some(old.root),
+        renames: Vec::new(),
+        fold_version: 1,
+        status: CandidateStatus::Ready { promotable: false },
+        ..built
+    };
+    fx.meta.put_candidate(&candidate)?;
+
+    let engine = Engine {
+        meta: &fx.meta,
+        objects: &fx.objects,
+    };
+    let report = engine.verify(&candidate.candidate_id)?;
+    assert!(report.verified, "{}", report.detail);
+    Ok(())
+}
+
+/// Moved and edited is still found, by similarity, and the move's own
+/// edit merges with the other lane's against the content it was moved
+/// from.
+#[test]
+fn a_moved_and_edited_file_merges_with_an_edit_at_the_old_path() -> Result<()> {
+    let fx = fixture("text-line-merge")?;
+    let moved = "fn one() { 1 }\nfn two() {}\nfn three() {}\nfn four() {}\nfn five() {}\n";
+    let candidate = contest(
+        &fx,
+        &[("src/old.rs", OLD)],
+        &[
+            ("mover", &[("lib/new.rs", moved)]),
+            ("editor", &[("src/old.rs", EDITED)]),
+        ],
+    )?;
+    assert_eq!(
+        text_at(&fx, &candidate, "lib/new.rs")?,
+        "fn one() { 1 }\nfn two() {}\nfn three() {}\nfn four() {}\nfn five() { 5 }\n"
+    );
+    assert_eq!(at(&fx, &candidate, "src/old.rs")?, None);
+    Ok(())
+}
+
+/// Too little in common is a delete and an unrelated add.
+#[test]
+fn a_dissimilar_add_is_not_a_move() -> Result<()> {
+    let fx = fixture("whole-file")?;
+    let candidate = contest(
+        &fx,
+        &[("src/old.rs", OLD)],
+        &[("mover", &[("src/new.rs", "something else entirely\n")])],
+    )?;
+    assert!(candidate.renames.is_empty());
+    Ok(())
+}
+
+#[test]
+fn two_lanes_moving_one_file_apart_superpose_both_destinations() -> Result<()> {
+    let fx = fixture("whole-file")?;
+    let candidate = contest(
+        &fx,
+        &[("src/old.rs", OLD)],
+        &[
+            ("left", &[("src/left.rs", OLD)]),
+            ("right", &[("src/right.rs", OLD)]),
+        ],
+    )?;
+    assert_eq!(
+        candidate.status,
+        CandidateStatus::Ready { promotable: false }
+    );
+    assert_eq!(at(&fx, &candidate, "src/old.rs")?, None);
+    for (path, lane, other_lane, elsewhere) in [
+        ("src/left.rs", "left", "right", "src/right.rs"),
+        ("src/right.rs", "right", "left", "src/left.rs"),
+    ] {
+        let Some(ManifestEntryKind::Superposition { variants }) = at(&fx, &candidate, path)? else {
+            panic!("{path} should superpose");
+        };
+        assert_eq!(variants.len(), 2, "{variants:?}");
+        assert_eq!(variants[0].source, lane);
+        assert!(matches!(
+            variants[0].kind,
+            SuperpositionVariantKind::File { .. }
+        ));
+        assert_eq!(variants[1].source, other_lane);
+        assert_eq!(
+            variants[1].kind,
+            SuperpositionVariantKind::MovedTo {
+                path: elsewhere.into()
+            }
+        );
+    }
+    Ok(())
+}
+
+/// A deletion follows the move like an edit does: the file is gone,
+/// not resurrected under its new name.
+#[test]
+fn a_deletion_follows_the_move() -> Result<()> {
+    let fx = fixture("whole-file")?;
+    let candidate = contest(
+        &fx,
+        &[("src/old.rs", OLD), ("README", "readme\n")],
+        &[
+            ("mover", &[("src/new.rs", OLD), ("README", "readme\n")]),
+            ("deleter", &[("README", "readme\n")]),
+        ],
+    )?;
+    assert_eq!(
+        candidate.status,
+        CandidateStatus::Ready { promotable: true }
+    );
+    assert_eq!(at(&fx, &candidate, "src/old.rs")?, None);
+    assert_eq!(at(&fx, &candidate, "src/new.rs")?, None);
+    Ok(())
+}
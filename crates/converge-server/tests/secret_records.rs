@@ -55,7 +55,7 @@ fn start_server(data_dir: &std::path::Path) -> Result<String> {
 /// Register a key for `subject` and return its key id.
 fn register_key(client: &RemoteClient) -> Result<String> {
     let identity = age::x25519::Identity::generate();
-    let record = client.register_key("repo", &identity.to_public().to_string(), "test")?;
+    let record = client.register_key("repo", &identity.to_public().to_string(), None, "test")?;
     Ok(record.key_id)
 }
 
@@ -37,6 +37,8 @@ fn fixture() -> Result<Fixture> {
                     required_approvals: 1,
                     strategy: "whole-file".into(),
                     may_release: false,
+                    strategy_rules: vec![],
+                    lane_priority: vec![],
                 },
                 GateNode {
                     gate_id: "main".into(),
@@ -45,6 +47,8 @@ fn fixture() -> Result<Fixture> {
                     required_approvals: 0,
                     strategy: "whole-file".into(),
                     may_release: false,
+                    strategy_rules: vec![],
+                    lane_priority: vec![],
                 },
             ],
         },
@@ -82,11 +86,13 @@ fn test_snap_record(tag: &str, root: converge_model::ObjectId) -> converge_model
     let _ = tag;
     converge_model::SnapRecord {
         version: 2,
-        id: converge_model::compute_snap_id(&root, &[], None),
+        id: converge_model::compute_snap_id(&root, &[], None, None, None),
         created_at: "2026-07-24T00:00:00Z".into(),
         root_manifest: root,
         parents: Vec::new(),
         derived_from_candidate: None,
+        author: None,
+        replayed_from: None,
         message: None,
         trigger: "explicit".into(),
         stats: converge_model::SnapStats::default(),
@@ -134,6 +140,7 @@ fn publish(
             base_candidate_id: None,
             lane_id: Some(lane.into()),
             notes: None,
+            signature: None,
         },
     )
 }
@@ -268,3 +275,44 @@ fn capability_mismatch_rejected_even_with_other_grants() -> Result<()> {
     assert!(err.to_string().contains("operation needs promote"));
     Ok(())
 }
+
+/// Doc 17 §1: a snap's author is checked against the uploader. Naming
+/// someone else is refused; re-sending a record the server already holds
+/// under its real author changes nothing and is allowed.
+#[test]
+fn a_snap_may_only_be_authored_by_its_uploader() -> Result<()> {
+    let fx = fixture()?;
+    fx.meta.add_grant("bob", "repo", "*", "publish")?;
+    let engine = Engine {
+        meta: &fx.meta,
+        objects: &fx.objects,
+    };
+    let root = put_file_manifest(&fx.objects, "a.txt", b"authored")?;
+    let authored_by = |subject: &str| {
+        let mut snap = test_snap_record("authored", root.clone());
+        snap.author = Some(converge_model::SnapAuthor {
+            subject: subject.into(),
+            display_name: subject.to_uppercase(),
+            key_id: None,
+        });
+        snap.id = converge_model::compute_snap_id(&root, &[], None, snap.author.as_ref(), None);
+        snap
+    };
+    let alice = authorize(&fx.meta, "alice", "repo", "scope", Capability::Publish)?;
+    let bob = authorize(&fx.meta, "bob", "repo", "scope", Capability::Publish)?;
+
+    let err = engine
+        .upload_snap_record(&bob, &authored_by("alice"))
+        .expect_err("bob cannot author as alice");
+    assert!(err.to_string().contains("only alice may upload"), "{err}");
+
+    let snap = authored_by("alice");
+    engine.upload_snap_record(&alice, &snap)?;
+    engine.upload_snap_record(&bob, &snap)?;
+    let stored = fx.meta.get_snap_record("repo", &snap.id)?.expect("stored");
+    assert_eq!(stored.author.map(|a| a.subject), Some("alice".to_string()));
+
+    // An unauthored record claims nobody.
+    engine.upload_snap_record(&bob, &test_snap_record("anonymous", root.clone()))?;
+    Ok(())
+}
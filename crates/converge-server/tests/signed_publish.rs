@@ -0,0 +1,275 @@
+//! Doc 19 §12: signed snaps and publications.
+//!
+//! The claim an auditor wants is "this publication came from the person
+//! whose token sent it". A token alone cannot say that — tokens are
+//! bearer credentials, and a leaked one publishes as its owner. The
+//! signature can, so these pin the ways it must fail: a signature over
+//! something else, a key that belongs to someone else, and an unsigned
+//! publish into a repo that requires one.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Result;
+use converge_client::identity::{KeyPair, Signer};
+use converge_client::remote::RemoteClient;
+use converge_client::workspace::Workspace;
+use converge_model::{
+    GateGraph, GateNode, PublishRequest, SignatureStatus, SigningPolicy, SnapRecord, WIRE_VERSION,
+};
+use converge_server::{AppState, FsObjectStore, MetadataStore, SqliteMetadataStore, router};
+
+/// alice and bob publish; dana is the repo admin.
+fn start_server(data_dir: &Path) -> Result<String> {
+    let meta = SqliteMetadataStore::open(&data_dir.join("meta.sqlite"))?;
+    meta.create_repo("repo")?;
+    meta.create_scope("repo", "scope", "2026-07-25T00:00:00Z")?;
+    meta.set_gate_graph(
+        "repo",
+        &GateGraph {
+            gates: vec![GateNode {
+                gate_id: "intake".into(),
+                name: "Intake".into(),
+                upstreams: vec![],
+                required_approvals: 0,
+                strategy: "whole-file".into(),
+                may_release: false,
+                strategy_rules: vec![],
+                lane_priority: vec![],
+            }],
+        },
+    )?;
+    for subject in ["alice", "bob", "dana"] {
+        meta.upsert_user(subject)?;
+        meta.add_grant(subject, "repo", "*", "read")?;
+        meta.add_grant(subject, "repo", "*", "publish")?;
+    }
+    meta.add_grant("dana", "repo", "*", "admin")?;
+    let state = AppState {
+        meta: Arc::new(meta),
+        objects: Arc::new(FsObjectStore::new(data_dir)),
+        tokens: HashMap::from([
+            ("token-a".to_string(), "alice".to_string()),
+            ("token-b".to_string(), "bob".to_string()),
+            ("token-d".to_string(), "dana".to_string()),
+        ]),
+        gc_running: Default::default(),
+        oidc: None,
+    };
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let addr = listener.local_addr()?;
+    listener.set_nonblocking(true)?;
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Runtime::new().expect("test runtime");
+        runtime.block_on(async {
+            let listener = tokio::net::TcpListener::from_std(listener).expect("adopt listener");
+            axum::serve(listener, router(state)).await.expect("serve");
+        });
+    });
+    Ok(format!("http://{addr}"))
+}
+
+/// Make a personal key in `home`, register both halves as the client's
+/// subject, and open its signing half.
+fn signer_for(client: &RemoteClient, home: &Path) -> Result<Signer> {
+    let passphrase = age::secrecy::SecretString::from("correct horse battery staple".to_string());
+    let key = KeyPair::create_in(home, &passphrase, "test", "2026-07-25T00:00:00Z")?;
+    client.register_key(
+        "repo",
+        &key.public.public_key,
+        key.public.signing_key.as_deref(),
+        "test",
+    )?;
+    Signer::load_in(home, None, &passphrase)
+}
+
+fn snap_in(dir: &Path, content: &str) -> Result<(Workspace, SnapRecord)> {
+    let ws = Workspace::init(dir, false)?;
+    std::fs::write(dir.join("file.txt"), content)?;
+    let snap = ws.create_snap(None)?;
+    Ok((ws, snap))
+}
+
+fn publish(client: &RemoteClient, ws: &Workspace, snap: &SnapRecord) -> Result<String> {
+    let (candidate, _) =
+        client.publish(&ws.store, "repo", "scope", "intake", snap, None, None, None)?;
+    Ok(candidate.candidate_id)
+}
+
+/// POST a publish request as built, bypassing the client's own signing,
+/// so a test can send a signature that does not belong to the request.
+fn publish_raw(base_url: &str, token: &str, request: &PublishRequest) -> Result<String> {
+    let response = reqwest::blocking::Client::new()
+        .post(format!("{base_url}/api/publish"))
+        .bearer_auth(token)
+        .json(request)
+        .send()?;
+    let status = response.status();
+    let body = response.text()?;
+    anyhow::ensure!(status.is_success(), "{status}: {body}");
+    Ok(body)
+}
+
+fn request_for(snap: &SnapRecord, notes: &str) -> PublishRequest {
+    PublishRequest {
+        wire_version: WIRE_VERSION,
+        repo_id: "repo".into(),
+        scope_id: "scope".into(),
+        gate_id: "intake".into(),
+        snap: snap.clone(),
+        base_candidate_id: None,
+        lane_id: None,
+        notes: Some(notes.into()),
+        signature: None,
+    }
+}
+
+#[test]
+fn a_signed_publish_is_verified_in_provenance_and_verify() -> Result<()> {
+    let server_dir = tempfile::tempdir()?;
+    let base_url = start_server(server_dir.path())?;
+    let home = tempfile::tempdir()?;
+    let alice = RemoteClient::new(&base_url, "token-a");
+    let signer = signer_for(&alice, home.path())?;
+    let key_id = signer.key_id().to_string();
+    let alice = alice.with_signer(signer);
+    let bob = RemoteClient::new(&base_url, "token-b");
+
+    let dir_a = tempfile::tempdir()?;
+    let (ws_a, snap_a) = snap_in(dir_a.path(), "alice")?;
+    publish(&alice, &ws_a, &snap_a)?;
+    // bob publishes unsigned into the same window: allowed, and reported
+    // as exactly that rather than lumped in with alice's.
+    let dir_b = tempfile::tempdir()?;
+    let (ws_b, snap_b) = snap_in(dir_b.path(), "bob")?;
+    let candidate = publish(&bob, &ws_b, &snap_b)?;
+
+    let provenance = alice.get_provenance(&candidate)?;
+    assert_eq!(provenance.inputs.len(), 2);
+    let statuses: Vec<(&str, &SignatureStatus)> = provenance
+        .signatures
+        .iter()
+        .map(|check| (check.publisher.as_str(), &check.status))
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![
+            ("alice", &SignatureStatus::Verified { key_id }),
+            ("bob", &SignatureStatus::Unsigned),
+        ]
+    );
+
+    let report = alice.verify(&candidate)?;
+    assert!(report.verified, "{}", report.detail);
+    assert_eq!(report.signatures, provenance.signatures);
+    Ok(())
+}
+
+/// A key that disappears after the fact stops vouching, but a signature
+/// it made at publish was checked then: verify reports the key as
+/// retired and still passes.
+#[test]
+fn a_retired_key_is_reported_without_failing_verify() -> Result<()> {
+    let server_dir = tempfile::tempdir()?;
+    let base_url = start_server(server_dir.path())?;
+    let home = tempfile::tempdir()?;
+    let alice = RemoteClient::new(&base_url, "token-a");
+    let signer = signer_for(&alice, home.path())?;
+    let key_id = signer.key_id().to_string();
+    let alice = alice.with_signer(signer);
+
+    let dir = tempfile::tempdir()?;
+    let (ws, snap) = snap_in(dir.path(), "alice")?;
+    let candidate = publish(&alice, &ws, &snap)?;
+
+    // No endpoint unregisters a key; an operator's cleanup can.
+    rusqlite::Connection::open(server_dir.path().join("meta.sqlite"))?
+        .execute("DELETE FROM public_keys WHERE key_id = ?1", [&key_id])?;
+
+    let report = alice.verify(&candidate)?;
+    assert!(report.verified, "{}", report.detail);
+    assert!(
+        report.detail.contains("no longer registered"),
+        "{}",
+        report.detail
+    );
+    assert_eq!(
+        report
+            .signatures
+            .iter()
+            .map(|check| &check.status)
+            .collect::<Vec<_>>(),
+        vec![&SignatureStatus::KeyRetired { key_id }]
+    );
+    Ok(())
+}
+
+#[test]
+fn a_signature_over_anything_else_is_refused() -> Result<()> {
+    let server_dir = tempfile::tempdir()?;
+    let base_url = start_server(server_dir.path())?;
+    let home = tempfile::tempdir()?;
+    let alice = RemoteClient::new(&base_url, "token-a");
+    let signer = signer_for(&alice, home.path())?;
+
+    let dir = tempfile::tempdir()?;
+    let (ws, snap) = snap_in(dir.path(), "content")?;
+    // Upload the tree once, so what follows is refused for its signature
+    // and not for a missing manifest.
+    alice.upload_tree(&ws.store, "repo", &snap.root_manifest)?;
+
+    // Signed for one set of notes, sent with another.
+    let mut request = request_for(&snap, "what was signed");
+    request.signature = Some(signer.sign_publish(&request));
+    request.notes = Some("what was sent".into());
+    let err = publish_raw(&base_url, "token-a", &request).unwrap_err();
+    assert!(
+        format!("{err:#}").contains("publish signature does not verify"),
+        "{err:#}"
+    );
+
+    // A genuine signature by alice's key, replayed with bob's token. The
+    // signature verifies; it just is not bob's.
+    let mut request = request_for(&snap, "by alice");
+    request.signature = Some(signer.sign_publish(&request));
+    let err = publish_raw(&base_url, "token-b", &request).unwrap_err();
+    assert!(
+        format!("{err:#}").contains("belongs to alice, not the publisher bob"),
+        "{err:#}"
+    );
+
+    // And the untampered original goes through.
+    publish_raw(&base_url, "token-a", &request)?;
+    Ok(())
+}
+
+#[test]
+fn a_repo_that_requires_signing_refuses_unsigned_publishes() -> Result<()> {
+    let server_dir = tempfile::tempdir()?;
+    let base_url = start_server(server_dir.path())?;
+    let alice = RemoteClient::new(&base_url, "token-a");
+    let dana = RemoteClient::new(&base_url, "token-d");
+    let require = SigningPolicy {
+        require_signed: true,
+    };
+
+    // Policy is control-plane config: a publisher cannot set it.
+    assert!(alice.set_signing_policy("repo", &require).is_err());
+    dana.set_signing_policy("repo", &require)?;
+    assert_eq!(alice.get_signing_policy("repo")?, require);
+
+    let dir = tempfile::tempdir()?;
+    let (ws, snap) = snap_in(dir.path(), "content")?;
+    let err = publish(&alice, &ws, &snap).unwrap_err();
+    assert!(
+        format!("{err:#}").contains("requires signed publishes"),
+        "{err:#}"
+    );
+
+    let home = tempfile::tempdir()?;
+    let signer = signer_for(&alice, home.path())?;
+    let alice = alice.with_signer(signer);
+    publish(&alice, &ws, &snap)?;
+    Ok(())
+}
@@ -0,0 +1,273 @@
+//! Doc 17 §4a: per-path strategy rules within a gate — the first matching
+//! rule picks the strategy, the gate's own is the fallback, and the rules
+//! a candidate was built under are what `verify` replays.
+
+use anyhow::Result;
+
+use converge_model::{
+    CandidateStatus, GateGraph, GateNode, Manifest, ManifestEntry, ManifestEntryKind, ObjectId,
+    StrategyRule,
+};
+use converge_server::{
+    Capability, Engine, FsObjectStore, MetadataStore, ObjectKind, ObjectStore, PublishInput,
+    SqliteMetadataStore, StoredCandidate, authorize,
+};
+
+struct Fixture {
+    meta: SqliteMetadataStore,
+    objects: FsObjectStore,
+    _tmp: tempfile::TempDir,
+}
+
+fn intake(strategy_rules: Vec<StrategyRule>) -> GateGraph {
+    GateGraph {
+        gates: vec![GateNode {
+            gate_id: "intake".into(),
+            name: "Intake".into(),
+            upstreams: vec![],
+            required_approvals: 0,
+            strategy: "text-line-merge".into(),
+            may_release: false,
+            strategy_rules,
+            lane_priority: vec![],
+        }],
+    }
+}
+
+fn rule(pattern: &str, strategy: &str) -> StrategyRule {
+    StrategyRule {
+        pattern: pattern.into(),
+        strategy: strategy.into(),
+    }
+}
+
+fn fixture() -> Result<Fixture> {
+    let tmp = tempfile::tempdir()?;
+    let meta = SqliteMetadataStore::open_in_memory()?;
+    let objects = FsObjectStore::new(tmp.path());
+    meta.upsert_user("alice")?;
+    meta.create_repo("repo")?;
+    meta.create_scope("repo", "scope", "2026-07-25T00:00:00Z")?;
+    meta.set_gate_graph(
+        "repo",
+        &intake(vec![
+            rule("Cargo.lock", "whole-file"),
+            rule("web/dist/**", "whole-file"),
+        ]),
+    )?;
+    meta.add_grant("alice", "repo", "*", "publish")?;
+    Ok(Fixture {
+        meta,
+        objects,
+        _tmp: tmp,
+    })
+}
+
+fn put_manifest(fx: &Fixture, entries: Vec<ManifestEntry>) -> Result<ObjectId> {
+    fx.objects.put(
+        ObjectKind::Manifest,
+        &converge_model::encoding::encode_manifest(&Manifest {
+            version: 1,
+            entries,
+        }),
+    )
+}
+
+fn file(fx: &Fixture, name: &str, content: &[u8]) -> Result<ManifestEntry> {
+    Ok(ManifestEntry {
+        name: name.into(),
+        kind: ManifestEntryKind::File {
+            blob: fx.objects.put(ObjectKind::Blob, content)?,
+            mode: 0o644,
+            size: content.len() as u64,
+        },
+    })
+}
+
+/// The same three files every publication touches: one at the root the
+/// fallback handles, one a bare-name rule catches, and one under an
+/// anchored rule.
+fn put_tree(fx: &Fixture, code: &[u8], lock: &[u8], bundle: &[u8]) -> Result<ObjectId> {
+    let dist = put_manifest(fx, vec![file(fx, "app.js", bundle)?])?;
+    let web = put_manifest(
+        fx,
+        vec![ManifestEntry {
+            name: "dist".into(),
+            kind: ManifestEntryKind::Dir { manifest: dist },
+        }],
+    )?;
+    put_manifest(
+        fx,
+        vec![
+            file(fx, "Cargo.lock", lock)?,
+            file(fx, "code.txt", code)?,
+            ManifestEntry {
+                name: "web".into(),
+                kind: ManifestEntryKind::Dir { manifest: web },
+            },
+        ],
+    )
+}
+
+fn ensure_lane(fx: &Fixture, lane: &str) -> Result<()> {
+    if fx.meta.get_lane("repo", lane)?.is_none() {
+        fx.meta.create_lane(&converge_model::LaneRecord {
+            lane_id: lane.into(),
+            repo_id: "repo".into(),
+            owner: "alice".into(),
+            members: vec![],
+            visibility: "repo".into(),
+            created_at: "2026-07-24T00:00:00Z".into(),
+        })?;
+    }
+    Ok(())
+}
+
+fn publish(
+    fx: &Fixture,
+    lane: &str,
+    root: ObjectId,
+    base: Option<String>,
+) -> Result<StoredCandidate> {
+    ensure_lane(fx, lane)?;
+    let engine = Engine {
+        meta: &fx.meta,
+        objects: &fx.objects,
+    };
+    let authz = authorize(&fx.meta, "alice", "repo", "scope", Capability::Publish)?;
+    engine.publish(
+        authz,
+        PublishInput {
+            gate_id: "intake".into(),
+            snap: converge_model::SnapRecord {
+                version: 2,
+                id: converge_model::compute_snap_id(&root, &[], None, None, None),
+                created_at: "2026-07-24T00:00:00Z".into(),
+                root_manifest: root,
+                parents: Vec::new(),
+                derived_from_candidate: None,
+                author: None,
+                replayed_from: None,
+                message: None,
+                trigger: "explicit".into(),
+                stats: converge_model::SnapStats::default(),
+            },
+            base_candidate_id: base,
+            lane_id: Some(lane.into()),
+            notes: None,
+            signature: None,
+        },
+    )
+}
+
+/// The value at a `/`-separated path in a candidate's tree.
+fn at(fx: &Fixture, candidate: &StoredCandidate, path: &str) -> Result<ManifestEntryKind> {
+    let mut manifest_id = candidate.root_manifest.clone().expect("root");
+    let mut segments = path.split('/').peekable();
+    while let Some(segment) = segments.next() {
+        let manifest = converge_model::encoding::decode_manifest(
+            &fx.objects.get(ObjectKind::Manifest, &manifest_id)?,
+        )?;
+        let kind = manifest
+            .entries
+            .into_iter()
+            .find(|e| e.name == segment)
+            .unwrap_or_else(|| panic!("{path} is not in the tree"))
+            .kind;
+        if segments.peek().is_none() {
+            return Ok(kind);
+        }
+        match kind {
+            ManifestEntryKind::Dir { manifest } => manifest_id = manifest,
+            other => panic!("{segment} is not a directory: {other:?}"),
+        }
+    }
+    unreachable!("a path has at least one segment")
+}
+
+const BASE: &[u8] = b"line one\nline two\nline three\nline four\nline five\n";
+const FIRST: &[u8] = b"line one EDITED\nline two\nline three\nline four\nline five\n";
+const LAST: &[u8] = b"line one\nline two\nline three\nline four\nline five EDITED\n";
+
+/// Two publications make the same disjoint edits to all three files. Under
+/// one gate-wide `text-line-merge` every file would line-merge; the rules
+/// keep the lockfile and the bundle whole.
+fn contested(fx: &Fixture) -> Result<StoredCandidate> {
+    let first = publish(fx, "lane-0", put_tree(fx, BASE, BASE, BASE)?, None)?;
+    let base = Some(first.candidate_id.clone());
+    publish(
+        fx,
+        "lane-b",
+        put_tree(fx, FIRST, FIRST, FIRST)?,
+        base.clone(),
+    )?;
+    publish(fx, "lane-c", put_tree(fx, LAST, LAST, LAST)?, base)
+}
+
+#[test]
+fn the_first_matching_rule_picks_each_paths_strategy() -> Result<()> {
+    let fx = fixture()?;
+    let candidate = contested(&fx)?;
+
+    assert_eq!(
+        candidate.status,
+        CandidateStatus::Ready { promotable: false }
+    );
+    // Unmatched: the gate's fallback line-merges it.
+    match at(&fx, &candidate, "code.txt")? {
+        ManifestEntryKind::File { blob, .. } => assert_eq!(
+            fx.objects.get(ObjectKind::Blob, &blob)?,
+            b"line one EDITED\nline two\nline three\nline four\nline five EDITED\n"
+        ),
+        other => panic!("code.txt should line-merge, got {other:?}"),
+    }
+    // A bare name at the root, and an anchored pattern two levels down:
+    // both kept whole, so both superpose.
+    for path in ["Cargo.lock", "web/dist/app.js"] {
+        assert!(
+            matches!(
+                at(&fx, &candidate, path)?,
+                ManifestEntryKind::Superposition { .. }
+            ),
+            "{path} should stay whole"
+        );
+    }
+    assert_eq!(
+        candidate.strategy_rules,
+        vec![
+            rule("Cargo.lock", "whole-file"),
+            rule("web/dist/**", "whole-file")
+        ]
+    );
+    Ok(())
+}
+
+/// `verify` replays the rules recorded on the candidate. Retuning the gate
+/// afterwards changes what the next build does, not whether this one
+/// still reproduces.
+#[test]
+fn verify_replays_the_recorded_rules_after_a_retune() -> Result<()> {
+    let fx = fixture()?;
+    let candidate = contested(&fx)?;
+    let engine = Engine {
+        meta: &fx.meta,
+        objects: &fx.objects,
+    };
+    assert!(engine.verify(&candidate.candidate_id)?.verified);
+
+    fx.meta.set_gate_graph("repo", &intake(vec![]))?;
+    let report = engine.verify(&candidate.candidate_id)?;
+    assert!(report.verified, "{}", report.detail);
+
+    // The next build under the retuned gate line-merges everything, and
+    // is a different candidate for it.
+    let rebuilt = publish(
+        &fx,
+        "lane-d",
+        put_tree(&fx, BASE, BASE, BASE)?,
+        Some(candidate.candidate_id.clone()),
+    )?;
+    assert!(rebuilt.strategy_rules.is_empty());
+    assert_ne!(rebuilt.candidate_id, candidate.candidate_id);
+    Ok(())
+}
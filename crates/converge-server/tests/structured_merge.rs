@@ -0,0 +1,301 @@
+//! Doc 17 §4b: `structured-merge` — config files merge key by key, and
+//! superpose only when two lanes changed the same key differently.
+
+use anyhow::Result;
+
+use converge_model::{
+    CandidateStatus, GateGraph, GateNode, Manifest, ManifestEntry, ManifestEntryKind, ObjectId,
+};
+use converge_server::{
+    Capability, Engine, FsObjectStore, MetadataStore, ObjectKind, ObjectStore, PublishInput,
+    SqliteMetadataStore, StoredCandidate, authorize,
+};
+
+struct Fixture {
+    meta: SqliteMetadataStore,
+    objects: FsObjectStore,
+    _tmp: tempfile::TempDir,
+}
+
+fn fixture() -> Result<Fixture> {
+    let tmp = tempfile::tempdir()?;
+    let meta = SqliteMetadataStore::open_in_memory()?;
+    let objects = FsObjectStore::new(tmp.path());
+    meta.upsert_user("alice")?;
+    meta.create_repo("repo")?;
+    meta.create_scope("repo", "scope", "2026-07-25T00:00:00Z")?;
+    meta.set_gate_graph(
+        "repo",
+        &GateGraph {
+            gates: vec![GateNode {
+                gate_id: "intake".into(),
+                name: "Intake".into(),
+                upstreams: vec![],
+                required_approvals: 0,
+                strategy: "structured-merge".into(),
+                may_release: false,
+                strategy_rules: vec![],
+                lane_priority: vec![],
+            }],
+        },
+    )?;
+    meta.add_grant("alice", "repo", "*", "publish")?;
+    Ok(Fixture {
+        meta,
+        objects,
+        _tmp: tmp,
+    })
+}
+
+fn put_file(fx: &Fixture, name: &str, content: &[u8]) -> Result<ObjectId> {
+    let blob = fx.objects.put(ObjectKind::Blob, content)?;
+    fx.objects.put(
+        ObjectKind::Manifest,
+        &converge_model::encoding::encode_manifest(&Manifest {
+            version: 1,
+            entries: vec![ManifestEntry {
+                name: name.into(),
+                kind: ManifestEntryKind::File {
+                    blob,
+                    mode: 0o644,
+                    size: content.len() as u64,
+                },
+            }],
+        }),
+    )
+}
+
+fn ensure_lane(fx: &Fixture, lane: &str) -> Result<()> {
+    if fx.meta.get_lane("repo", lane)?.is_none() {
+        fx.meta.create_lane(&converge_model::LaneRecord {
+            lane_id: lane.into(),
+            repo_id: "repo".into(),
+            owner: "alice".into(),
+            members: vec![],
+            visibility: "repo".into(),
+            created_at: "2026-07-24T00:00:00Z".into(),
+        })?;
+    }
+    Ok(())
+}
+
+fn publish(
+    fx: &Fixture,
+    lane: &str,
+    root: ObjectId,
+    base: Option<String>,
+) -> Result<StoredCandidate> {
+    ensure_lane(fx, lane)?;
+    let engine = Engine {
+        meta: &fx.meta,
+        objects: &fx.objects,
+    };
+    let authz = authorize(&fx.meta, "alice", "repo", "scope", Capability::Publish)?;
+    engine.publish(
+        authz,
+        PublishInput {
+            gate_id: "intake".into(),
+            snap: converge_model::SnapRecord {
+                version: 2,
+                id: converge_model::compute_snap_id(&root, &[], None, None, None),
+                created_at: "2026-07-24T00:00:00Z".into(),
+                root_manifest: root,
+                parents: Vec::new(),
+                derived_from_candidate: None,
+                author: None,
+                replayed_from: None,
+                message: None,
+                trigger: "explicit".into(),
+                stats: converge_model::SnapStats::default(),
+            },
+            base_candidate_id: base,
+            lane_id: Some(lane.into()),
+            notes: None,
+            signature: None,
+        },
+    )
+}
+
+/// Base, then two lanes editing it from that base; the candidate of the
+/// second.
+fn three_way(fx: &Fixture, name: &str, base: &str, b: &str, c: &str) -> Result<StoredCandidate> {
+    let first = publish(fx, "lane-0", put_file(fx, name, base.as_bytes())?, None)?;
+    let from = Some(first.candidate_id.clone());
+    publish(
+        fx,
+        "lane-b",
+        put_file(fx, name, b.as_bytes())?,
+        from.clone(),
+    )?;
+    publish(fx, "lane-c", put_file(fx, name, c.as_bytes())?, from)
+}
+
+fn merged_text(fx: &Fixture, candidate: &StoredCandidate) -> Result<String> {
+    let root = candidate.root_manifest.clone().expect("root");
+    let manifest =
+        converge_model::encoding::decode_manifest(&fx.objects.get(ObjectKind::Manifest, &root)?)?;
+    match &manifest.entries[0].kind {
+        ManifestEntryKind::File { blob, .. } => {
+            Ok(String::from_utf8(fx.objects.get(ObjectKind::Blob, blob)?)?)
+        }
+        other => anyhow::bail!("expected a merged file, got {other:?}"),
+    }
+}
+
+fn superposed(fx: &Fixture, candidate: &StoredCandidate) -> Result<bool> {
+    let root = candidate.root_manifest.clone().expect("root");
+    let manifest =
+        converge_model::encoding::decode_manifest(&fx.objects.get(ObjectKind::Manifest, &root)?)?;
+    Ok(matches!(
+        manifest.entries[0].kind,
+        ManifestEntryKind::Superposition { .. }
+    ))
+}
+
+/// The case a line merge refuses: adjacent lines, different keys.
+#[test]
+fn json_edits_to_different_keys_merge() -> Result<()> {
+    let fx = fixture()?;
+    let candidate = three_way(
+        &fx,
+        "settings.json",
+        r#"{"timeout": 30, "retries": 3, "server": {"host": "a", "port": 80}}"#,
+        r#"{"timeout": 60, "retries": 3, "server": {"host": "a", "port": 80}}"#,
+        r#"{"timeout": 30, "retries": 5, "server": {"host": "b", "port": 80}, "debug": true}"#,
+    )?;
+    assert_eq!(
+        candidate.status,
+        CandidateStatus::Ready { promotable: true }
+    );
+    let merged: serde_json::Value = serde_json::from_str(&merged_text(&fx, &candidate)?)?;
+    assert_eq!(
+        merged,
+        serde_json::json!({
+            "timeout": 60, "retries": 5, "debug": true,
+            "server": {"host": "b", "port": 80}
+        })
+    );
+    Ok(())
+}
+
+#[test]
+fn toml_removals_and_nested_tables_merge() -> Result<()> {
+    let fx = fixture()?;
+    let base = "[package]\nname = \"game\"\nversion = \"0.1.0\"\n\n[dependencies]\nserde = \"1\"\nrand = \"0.8\"\n";
+    let candidate = three_way(
+        &fx,
+        "Cargo.toml",
+        base,
+        "[package]\nname = \"game\"\nversion = \"0.2.0\"\n\n[dependencies]\nserde = \"1\"\nrand = \"0.8\"\n",
+        "[package]\nname = \"game\"\nversion = \"0.1.0\"\n\n[dependencies]\nserde = \"1\"\ntoml = \"0.5\"\n",
+    )?;
+    let merged: toml::Value = toml::from_str(&merged_text(&fx, &candidate)?)?;
+    assert_eq!(merged["package"]["version"].as_str(), Some("0.2.0"));
+    assert_eq!(merged["dependencies"]["toml"].as_str(), Some("0.5"));
+    assert!(
+        merged["dependencies"].get("rand").is_none(),
+        "a key one side removed and the other left alone stays removed"
+    );
+    Ok(())
+}
+
+#[test]
+fn yaml_mappings_merge_and_lists_stay_whole() -> Result<()> {
+    let fx = fixture()?;
+    let base = "\
+# asset pipeline
+name: forest
+textures:
+  size: 1024
+  format: bc7
+layers:
+  - ground
+  - trees
+";
+    let b = base.replace("size: 1024", "size: 2048");
+    let c = base.replace("format: bc7", "format: astc");
+    let candidate = three_way(&fx, "pipeline.yaml", base, &b, &c)?;
+    assert_eq!(
+        merged_text(&fx, &candidate)?,
+        "name: forest\ntextures:\n  size: 2048\n  format: astc\nlayers:\n  - ground\n  - trees\n"
+    );
+
+    // Both lanes append to the same list: a leaf changed two ways.
+    let fx = fixture()?;
+    let b = base.replace("  - trees\n", "  - trees\n  - rocks\n");
+    let c = base.replace("  - trees\n", "  - trees\n  - river\n");
+    assert!(superposed(
+        &fx,
+        &three_way(&fx, "pipeline.yml", base, &b, &c)?
+    )?);
+    Ok(())
+}
+
+#[test]
+fn the_same_key_changed_two_ways_superposes() -> Result<()> {
+    let fx = fixture()?;
+    let candidate = three_way(
+        &fx,
+        "settings.json",
+        r#"{"timeout": 30, "retries": 3}"#,
+        r#"{"timeout": 60, "retries": 3}"#,
+        r#"{"timeout": 90, "retries": 5}"#,
+    )?;
+    assert_eq!(
+        candidate.status,
+        CandidateStatus::Ready { promotable: false }
+    );
+    assert!(superposed(&fx, &candidate)?);
+    Ok(())
+}
+
+/// A variant that does not parse is not this strategy's to guess at, and
+/// neither is a file whose extension names no format it knows.
+#[test]
+fn unparseable_or_unknown_files_fall_back_to_superposing() -> Result<()> {
+    let fx = fixture()?;
+    let candidate = three_way(
+        &fx,
+        "settings.json",
+        r#"{"timeout": 30, "retries": 3}"#,
+        r#"{"timeout": 60, "retries": 3}"#,
+        r#"{"timeout": 30, "retries": 5,"#,
+    )?;
+    assert!(superposed(&fx, &candidate)?);
+
+    let fx = fixture()?;
+    let candidate = three_way(
+        &fx,
+        "notes.txt",
+        "one\ntwo\nthree\nfour\n",
+        "ONE\ntwo\nthree\nfour\n",
+        "one\ntwo\nthree\nFOUR\n",
+    )?;
+    assert!(superposed(&fx, &candidate)?);
+    Ok(())
+}
+
+/// The merged bytes depend on the documents and their key order, not on
+/// how they were laid out, so a replay reproduces the candidate.
+#[test]
+fn structured_merges_verify() -> Result<()> {
+    let fx = fixture()?;
+    let candidate = three_way(
+        &fx,
+        "settings.json",
+        "{\n  \"timeout\": 30,\n  \"retries\": 3\n}\n",
+        "{\"timeout\": 60, \"retries\": 3}",
+        "{\n    \"retries\": 5,\n    \"timeout\": 30\n}",
+    )?;
+    assert_eq!(
+        merged_text(&fx, &candidate)?,
+        "{\n  \"timeout\": 60,\n  \"retries\": 5\n}\n"
+    );
+    let engine = Engine {
+        meta: &fx.meta,
+        objects: &fx.objects,
+    };
+    let report = engine.verify(&candidate.candidate_id)?;
+    assert!(report.verified, "{}", report.detail);
+    Ok(())
+}
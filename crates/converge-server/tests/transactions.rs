@@ -29,6 +29,8 @@ fn start_server(data_dir: &std::path::Path, users: usize) -> Result<(String, Vec
                 required_approvals: 0,
                 strategy: "whole-file".into(),
                 may_release: false,
+                strategy_rules: vec![],
+                lane_priority: vec![],
             }],
         },
     )?;
@@ -144,6 +146,8 @@ fn guard_setup(data: &std::path::Path) -> Result<SqliteMetadataStore> {
                     required_approvals: 0,
                     strategy: "whole-file".into(),
                     may_release: false,
+                    strategy_rules: vec![],
+                    lane_priority: vec![],
                 },
                 GateNode {
                     gate_id: "main".into(),
@@ -152,6 +156,8 @@ fn guard_setup(data: &std::path::Path) -> Result<SqliteMetadataStore> {
                     required_approvals: 0,
                     strategy: "whole-file".into(),
                     may_release: false,
+                    strategy_rules: vec![],
+                    lane_priority: vec![],
                 },
                 GateNode {
                     gate_id: "aux".into(),
@@ -160,6 +166,8 @@ fn guard_setup(data: &std::path::Path) -> Result<SqliteMetadataStore> {
                     required_approvals: 0,
                     strategy: "whole-file".into(),
                     may_release: false,
+                    strategy_rules: vec![],
+                    lane_priority: vec![],
                 },
             ],
         },
@@ -211,6 +219,7 @@ fn publish_file(
             base_candidate_id: None,
             lane_id: None,
             notes: None,
+            signature: None,
         },
     )
 }
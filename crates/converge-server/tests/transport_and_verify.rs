@@ -28,6 +28,8 @@ fn start_server(data_dir: &std::path::Path, seed_events: u64) -> Result<String>
                 required_approvals: 0,
                 strategy: "text-line-merge".into(),
                 may_release: false,
+                strategy_rules: vec![],
+                lane_priority: vec![],
             }],
         },
     )?;
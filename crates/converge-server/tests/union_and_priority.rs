@@ -0,0 +1,340 @@
+//! Doc 17 §4c-d: `union-lines` keeps every side's added lines instead of
+//! superposing; `lane-priority` lets the highest-ranked lane win a
+//! contested path outright. Both replay under `verify` from what the
+//! candidate recorded.
+
+use anyhow::Result;
+
+use converge_model::{
+    CandidateStatus, GateGraph, GateNode, Manifest, ManifestEntry, ManifestEntryKind, ObjectId,
+};
+use converge_server::{
+    Capability, Engine, FsObjectStore, MetadataStore, ObjectKind, ObjectStore, PublishInput,
+    SqliteMetadataStore, StoredCandidate, authorize,
+};
+
+struct Fixture {
+    meta: SqliteMetadataStore,
+    objects: FsObjectStore,
+    _tmp: tempfile::TempDir,
+}
+
+fn intake(strategy: &str, lane_priority: &[&str]) -> GateGraph {
+    GateGraph {
+        gates: vec![GateNode {
+            gate_id: "intake".into(),
+            name: "Intake".into(),
+            upstreams: vec![],
+            required_approvals: 0,
+            strategy: strategy.into(),
+            may_release: false,
+            strategy_rules: vec![],
+            lane_priority: lane_priority.iter().map(|l| l.to_string()).collect(),
+        }],
+    }
+}
+
+fn fixture(graph: GateGraph) -> Result<Fixture> {
+    let tmp = tempfile::tempdir()?;
+    let meta = SqliteMetadataStore::open_in_memory()?;
+    let objects = FsObjectStore::new(tmp.path());
+    meta.upsert_user("alice")?;
+    meta.create_repo("repo")?;
+    meta.create_scope("repo", "scope", "2026-07-25T00:00:00Z")?;
+    meta.set_gate_graph("repo", &graph)?;
+    meta.add_grant("alice", "repo", "*", "publish")?;
+    Ok(Fixture {
+        meta,
+        objects,
+        _tmp: tmp,
+    })
+}
+
+/// A tree of `(name, content)` files; `None` content leaves the file out.
+fn put_tree(fx: &Fixture, files: &[(&str, Option<&[u8]>)]) -> Result<ObjectId> {
+    let mut entries = Vec::new();
+    for (name, content) in files {
+        let Some(content) = content else { continue };
+        entries.push(ManifestEntry {
+            name: name.to_string(),
+            kind: ManifestEntryKind::File {
+                blob: fx.objects.put(ObjectKind::Blob, content)?,
+                mode: 0o644,
+                size: content.len() as u64,
+            },
+        });
+    }
+    fx.objects.put(
+        ObjectKind::Manifest,
+        &converge_model::encoding::encode_manifest(&Manifest {
+            version: 1,
+            entries,
+        }),
+    )
+}
+
+fn ensure_lane(fx: &Fixture, lane: &str) -> Result<()> {
+    if fx.meta.get_lane("repo", lane)?.is_none() {
+        fx.meta.create_lane(&converge_model::LaneRecord {
+            lane_id: lane.into(),
+            repo_id: "repo".into(),
+            owner: "alice".into(),
+            members: vec![],
+            visibility: "repo".into(),
+            created_at: "2026-07-24T00:00:00Z".into(),
+        })?;
+    }
+    Ok(())
+}
+
+fn publish(
+    fx: &Fixture,
+    lane: &str,
+    root: ObjectId,
+    base: Option<String>,
+) -> Result<StoredCandidate> {
+    ensure_lane(fx, lane)?;
+    let engine = Engine {
+        meta: &fx.meta,
+        objects: &fx.objects,
+    };
+    let authz = authorize(&fx.meta, "alice", "repo", "scope", Capability::Publish)?;
+    engine.publish(
+        authz,
+        PublishInput {
+            gate_id: "intake".into(),
+            snap: converge_model::SnapRecord {
+                version: 2,
+                id: converge_model::compute_snap_id(&root, &[], None, None, None),
+                created_at: "2026-07-24T00:00:00Z".into(),
+                root_manifest: root,
+                parents: Vec::new(),
+                derived_from_candidate: None,
+                author: None,
+                replayed_from: None,
+                message: None,
+                trigger: "explicit".into(),
+                stats: converge_model::SnapStats::default(),
+            },
+            base_candidate_id: base,
+            lane_id: Some(lane.into()),
+            notes: None,
+            signature: None,
+        },
+    )
+}
+
+/// A base from an unranked lane, then `edits` in order, each built on
+/// that base; the candidate of the last.
+fn contest(fx: &Fixture, base: ObjectId, edits: Vec<(&str, ObjectId)>) -> Result<StoredCandidate> {
+    let first = publish(fx, "lane-0", base, None)?;
+    let from = Some(first.candidate_id.clone());
+    let mut last = first;
+    for (lane, root) in edits {
+        last = publish(fx, lane, root, from.clone())?;
+    }
+    Ok(last)
+}
+
+fn entry(
+    fx: &Fixture,
+    candidate: &StoredCandidate,
+    name: &str,
+) -> Result<Option<ManifestEntryKind>> {
+    let root = candidate.root_manifest.clone().expect("root");
+    let manifest =
+        converge_model::encoding::decode_manifest(&fx.objects.get(ObjectKind::Manifest, &root)?)?;
+    Ok(manifest
+        .entries
+        .into_iter()
+        .find(|e| e.name == name)
+        .map(|e| e.kind))
+}
+
+fn text(fx: &Fixture, candidate: &StoredCandidate, name: &str) -> Result<String> {
+    match entry(fx, candidate, name)? {
+        Some(ManifestEntryKind::File { blob, .. }) => {
+            Ok(String::from_utf8(fx.objects.get(ObjectKind::Blob, &blob)?)?)
+        }
+        other => anyhow::bail!("expected {name} to be a file, got {other:?}"),
+    }
+}
+
+fn verified(fx: &Fixture, candidate: &StoredCandidate) -> Result<()> {
+    let engine = Engine {
+        meta: &fx.meta,
+        objects: &fx.objects,
+    };
+    let report = engine.verify(&candidate.candidate_id)?;
+    assert!(report.verified, "{}", report.detail);
+    Ok(())
+}
+
+const CHANGELOG: &str = "# Changelog\n\n## Unreleased\n- faster sync\n- fix login\n";
+
+/// Two lanes append an entry at the same spot, which a line merge
+/// refuses; one of them also drops an entry.
+#[test]
+fn union_lines_keeps_every_added_line() -> Result<()> {
+    let fx = fixture(intake("union-lines", &[]))?;
+    let tree = |text: &str| put_tree(&fx, &[("CHANGELOG.md", Some(text.as_bytes()))]);
+    let candidate = contest(
+        &fx,
+        tree(CHANGELOG)?,
+        vec![
+            (
+                "lane-b",
+                tree("# Changelog\n\n## Unreleased\n- faster sync\n- new icons\n")?,
+            ),
+            (
+                "lane-c",
+                tree(&format!("{CHANGELOG}- dark mode\n- new icons\n"))?,
+            ),
+        ],
+    )?;
+    assert_eq!(
+        candidate.status,
+        CandidateStatus::Ready { promotable: true }
+    );
+    // lane-b's removal holds; both additions land, lane-b's first, and
+    // the line both added appears once.
+    assert_eq!(
+        text(&fx, &candidate, "CHANGELOG.md")?,
+        "# Changelog\n\n## Unreleased\n- faster sync\n- new icons\n- dark mode\n"
+    );
+    verified(&fx, &candidate)
+}
+
+/// A line repeated within one side's addition is counted, not
+/// collapsed: the earlier lane's copy covers one of them only.
+#[test]
+fn union_lines_keeps_a_line_added_more_times_than_the_other_side_added_it() -> Result<()> {
+    let fx = fixture(intake("union-lines", &[]))?;
+    let tree = |text: &str| put_tree(&fx, &[("CHANGELOG.md", Some(text.as_bytes()))]);
+    let candidate = contest(
+        &fx,
+        tree(CHANGELOG)?,
+        vec![
+            ("lane-b", tree(&format!("{CHANGELOG}- bump deps\n"))?),
+            (
+                "lane-c",
+                tree(&format!(
+                    "{CHANGELOG}- bump deps\n- dark mode\n- bump deps\n"
+                ))?,
+            ),
+        ],
+    )?;
+    assert_eq!(
+        text(&fx, &candidate, "CHANGELOG.md")?,
+        format!("{CHANGELOG}- bump deps\n- dark mode\n- bump deps\n")
+    );
+    verified(&fx, &candidate)
+}
+
+#[test]
+fn the_higher_ranked_lane_wins_a_contested_file() -> Result<()> {
+    let fx = fixture(intake("lane-priority", &["director", "contractor"]))?;
+    let tree = |art: Option<&[u8]>| {
+        put_tree(
+            &fx,
+            &[
+                ("hero.png", art),
+                ("notes.txt", Some(b"shared\n".as_slice())),
+            ],
+        )
+    };
+    // The director publishes first and the contractor after: rank
+    // decides, not order.
+    let candidate = contest(
+        &fx,
+        tree(Some(b"\x89PNG base"))?,
+        vec![
+            ("director", tree(Some(b"\x89PNG director"))?),
+            ("contractor", tree(Some(b"\x89PNG contractor"))?),
+        ],
+    )?;
+    assert_eq!(
+        candidate.status,
+        CandidateStatus::Ready { promotable: true }
+    );
+    match entry(&fx, &candidate, "hero.png")? {
+        Some(ManifestEntryKind::File { blob, .. }) => {
+            assert_eq!(
+                fx.objects.get(ObjectKind::Blob, &blob)?,
+                b"\x89PNG director"
+            )
+        }
+        other => panic!("hero.png should be the director's, got {other:?}"),
+    }
+    assert_eq!(candidate.lane_priority, vec!["director", "contractor"]);
+    verified(&fx, &candidate)?;
+
+    // A ranked deletion wins like any other opinion.
+    let fx = fixture(intake("lane-priority", &["director", "contractor"]))?;
+    let tree = |art: Option<&[u8]>| {
+        put_tree(
+            &fx,
+            &[
+                ("hero.png", art),
+                ("notes.txt", Some(b"shared\n".as_slice())),
+            ],
+        )
+    };
+    let candidate = contest(
+        &fx,
+        tree(Some(b"\x89PNG base"))?,
+        vec![
+            ("contractor", tree(Some(b"\x89PNG contractor"))?),
+            ("director", tree(None)?),
+        ],
+    )?;
+    assert_eq!(entry(&fx, &candidate, "hero.png")?, None);
+    assert!(entry(&fx, &candidate, "notes.txt")?.is_some());
+    Ok(())
+}
+
+/// Lanes the gate does not rank get no say: their conflicts superpose
+/// as under `whole-file`.
+#[test]
+fn unranked_lanes_still_superpose() -> Result<()> {
+    let fx = fixture(intake("lane-priority", &["director"]))?;
+    let tree = |art: &[u8]| put_tree(&fx, &[("hero.png", Some(art))]);
+    let candidate = contest(
+        &fx,
+        tree(b"\x89PNG base")?,
+        vec![
+            ("contractor-a", tree(b"\x89PNG a")?),
+            ("contractor-b", tree(b"\x89PNG b")?),
+        ],
+    )?;
+    assert_eq!(
+        candidate.status,
+        CandidateStatus::Ready { promotable: false }
+    );
+    assert!(matches!(
+        entry(&fx, &candidate, "hero.png")?,
+        Some(ManifestEntryKind::Superposition { .. })
+    ));
+    Ok(())
+}
+
+/// The ranking enters the candidate id and is what `verify` replays, so
+/// reordering the lanes afterwards leaves the candidate verifiable.
+#[test]
+fn verify_replays_the_recorded_ranking_after_a_reorder() -> Result<()> {
+    let fx = fixture(intake("lane-priority", &["director", "contractor"]))?;
+    let tree = |art: &[u8]| put_tree(&fx, &[("hero.png", Some(art))]);
+    let candidate = contest(
+        &fx,
+        tree(b"\x89PNG base")?,
+        vec![
+            ("director", tree(b"\x89PNG director")?),
+            ("contractor", tree(b"\x89PNG contractor")?),
+        ],
+    )?;
+    fx.meta.set_gate_graph(
+        "repo",
+        &intake("lane-priority", &["contractor", "director"]),
+    )?;
+    verified(&fx, &candidate)
+}
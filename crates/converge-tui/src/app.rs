@@ -182,9 +182,11 @@ pub const COMMANDS: &[(&str, &str)] = &[
     ("lane", "share unpublished work with teammates"),
     ("login", "connect this workspace to a server"),
     ("member", "who can do what in this repo"),
+    ("pick", "replay one snap's change onto head"),
     ("profile", "workflow profile (shapes guidance)"),
     ("promote", "move a candidate to the next gate"),
     ("publish", "send your snaps to the server"),
+    ("rebase", "replay your snaps onto another lineage"),
     ("releases", "list releases by version"),
     ("release", "cut a release: <candidate> --as 1.2.0"),
     ("remote", "show the configured server"),
@@ -194,13 +196,16 @@ pub const COMMANDS: &[(&str, &str)] = &[
     ("retention", "what the server keeps, and how long"),
     ("scope", "scope registry operations"),
     ("secret", "encrypted values only you can read"),
+    ("shelve", "set uncaptured edits aside for later"),
     ("show", "browse a snap or candidate read-only"),
     ("snap", "capture the workspace as it is now"),
     ("status", "workspace state at a glance"),
     ("sync", "push or pull lane work"),
+    ("unshelve", "put shelved edits back"),
     ("unsnap", "undo the last capture, keep the files"),
     ("verify", "replay a candidate and prove its identity"),
     ("watch", "auto-snap on quiet periods"),
+    ("worktree", "more checkouts sharing one object store"),
 ];
 
 /// Commands that hit the network run on the async worker so the event loop
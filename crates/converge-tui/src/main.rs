@@ -538,8 +538,14 @@ fn enter_resolution(app: &mut App, target: String, result: anyhow::Result<serde_
     let mut paths: Vec<(String, Vec<serde_json::Value>)> = Vec::new();
     let mut previews: std::collections::BTreeMap<String, Vec<app::VariantPreview>> =
         Default::default();
+    // A variant picked the last time these variants met starts out
+    // chosen; the resolver sees it and can change it (doc 17 §2c).
+    let mut decisions = std::collections::BTreeMap::new();
     for (path, variants) in value.as_object().into_iter().flatten() {
         let variants = variants.as_array().cloned().unwrap_or_default();
+        if let Some(index) = variants.iter().position(|v| v["reused"] == true) {
+            decisions.insert(path.clone(), index as u32);
+        }
         let keys: Vec<serde_json::Value> = variants
             .iter()
             .map(|v| {
@@ -567,15 +573,21 @@ fn enter_resolution(app: &mut App, target: String, result: anyhow::Result<serde_
         paths.push((path.clone(), keys));
     }
     paths.sort_by(|a, b| a.0.cmp(&b.0));
-    app.record_result(Ok(serde_json::json!(format!(
-        "{} superposed path(s)",
-        paths.len()
-    ))));
+    let summary = if decisions.is_empty() {
+        format!("{} superposed path(s)", paths.len())
+    } else {
+        format!(
+            "{} superposed path(s), {} reused from recorded decisions",
+            paths.len(),
+            decisions.len()
+        )
+    };
+    app.record_result(Ok(serde_json::json!(summary)));
     app.resolution = Some(ResolutionState {
         snap_id: target,
         paths,
         previews,
-        decisions: Default::default(),
+        decisions,
         selected: 0,
     });
     if app.current_view() != View::Resolution {
@@ -866,8 +866,16 @@ pub(crate) fn row_label(row: &serde_json::Value) -> String {
                     .join(", ")
             })
             .unwrap_or_default();
+        let mut rules = match row["strategy_rules"].as_array().map(Vec::len) {
+            Some(n) if n > 0 => format!(" (+{n} path rule(s))"),
+            _ => String::new(),
+        };
+        if let Some(lanes) = row["lane_priority"].as_array().filter(|l| !l.is_empty()) {
+            let lanes: Vec<&str> = lanes.iter().filter_map(|v| v.as_str()).collect();
+            rules.push_str(&format!(" [{}]", lanes.join(" > ")));
+        }
         return format!(
-            "{}  {}  {} approval(s)  {}{}",
+            "{}  {}  {} approval(s)  {}{}{}",
             s("gate_id"),
             if upstreams.is_empty() {
                 "entry".to_string()
@@ -876,6 +884,7 @@ pub(crate) fn row_label(row: &serde_json::Value) -> String {
             },
             row["required_approvals"],
             s("strategy"),
+            rules,
             if row["may_release"].as_bool().unwrap_or(false) {
                 "  releasable"
             } else {